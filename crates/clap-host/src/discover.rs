@@ -82,6 +82,7 @@ pub struct ClapPluginDescriptor {
     pub id: String,
     pub name: String,
     pub vendor: String,
+    pub version: String,
 }
 
 pub struct PluginDiscovery<'a> {
@@ -119,6 +120,7 @@ impl<'a> PluginDiscovery<'a> {
                     id: to_string(descriptor.id),
                     name: to_string(descriptor.name),
                     vendor: to_string(descriptor.vendor),
+                    version: to_string(descriptor.version),
                 });
             }
         }
@@ -1,6 +1,11 @@
 use core::cell::UnsafeCell;
+use core::ffi::c_void;
 use core::marker::PhantomData;
 
+use clap_sys::{
+    clap_event_header, clap_event_param_value, clap_input_events, clap_output_events,
+    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_VALUE,
+};
 use heapless::spsc::{Consumer, Producer, Queue};
 
 /// A lock-free single-producer/single-consumer queue for passing events between threads.
@@ -56,3 +61,104 @@ impl<'a, T: Copy, const N: usize> EventSlice<'a, T, N> {
         self.consumer.dequeue()
     }
 }
+
+/// Parameter changes for one `process` call, handed to the plug-in as its
+/// `in_events` list. Storage is reserved up front so queueing never
+/// allocates; events past the capacity are rejected.
+pub struct ParamEventList {
+    events: Vec<clap_event_param_value>,
+}
+
+// Every event is queued with a null cookie, so no pointer is shared.
+unsafe impl Send for ParamEventList {}
+unsafe impl Sync for ParamEventList {}
+
+impl ParamEventList {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            events: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Queues `value` for parameter `id` at sample `time`, after any event
+    /// already queued for the same sample. Returns `false` when full.
+    pub fn push(&mut self, time: u32, id: u32, value: f64) -> bool {
+        if self.events.len() == self.events.capacity() {
+            return false;
+        }
+        let event = clap_event_param_value {
+            header: clap_event_header {
+                size: core::mem::size_of::<clap_event_param_value>() as u32,
+                time,
+                space_id: CLAP_CORE_EVENT_SPACE_ID,
+                type_: CLAP_EVENT_PARAM_VALUE.0 as u16,
+                flags: 0,
+            },
+            param_id: id,
+            cookie: core::ptr::null_mut(),
+            note_id: -1,
+            port_index: -1,
+            channel: -1,
+            key: -1,
+            value,
+        };
+        let position = self
+            .events
+            .partition_point(|queued| queued.header.time <= time);
+        self.events.insert(position, event);
+        true
+    }
+
+    /// The list in the shape CLAP expects. The result points at `self`, so
+    /// it is only valid while the list is neither moved nor modified.
+    pub fn as_input_events(&self) -> clap_input_events {
+        clap_input_events {
+            ctx: self as *const Self as *mut c_void,
+            size: Some(Self::size),
+            get: Some(Self::get),
+        }
+    }
+
+    unsafe extern "C" fn size(list: *const clap_input_events) -> u32 {
+        let this = &*((*list).ctx as *const Self);
+        this.events.len() as u32
+    }
+
+    unsafe extern "C" fn get(
+        list: *const clap_input_events,
+        index: u32,
+    ) -> *const clap_event_header {
+        let this = &*((*list).ctx as *const Self);
+        this.events
+            .get(index as usize)
+            .map(|event| &event.header as *const clap_event_header)
+            .unwrap_or(core::ptr::null())
+    }
+}
+
+/// An output event list that accepts every event and drops it.
+pub fn discard_output_events() -> clap_output_events {
+    unsafe extern "C" fn try_push(
+        _list: *const clap_output_events,
+        _event: *const clap_event_header,
+    ) -> bool {
+        true
+    }
+
+    clap_output_events {
+        ctx: core::ptr::null_mut(),
+        try_push: Some(try_push),
+    }
+}
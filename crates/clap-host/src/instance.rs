@@ -1,12 +1,16 @@
+use core::ffi::c_char;
 use std::ffi::CString;
 
 use clap_sys::{
-    clap_host, clap_plugin, clap_plugin_factory_t, clap_process, clap_process_status,
-    CLAP_PROCESS_ERROR,
+    clap_host, clap_input_events, clap_output_events, clap_param_info, clap_plugin,
+    clap_plugin_factory_t, clap_plugin_params, clap_plugin_state, clap_process,
+    clap_process_status, CLAP_PROCESS_ERROR, CLAP_VERSION_LATEST,
 };
 use thiserror::Error;
 
 use crate::discover::ClapPluginDescriptor;
+use crate::params::{ParamInfo, ParameterQuery};
+use crate::state::{InputStream, OutputStream, StateError};
 
 #[derive(Debug, Clone, Copy)]
 pub struct AudioConfig {
@@ -27,6 +31,38 @@ pub enum ActivationError {
     MissingCreatePlugin,
 }
 
+/// A `clap_host` that can live in a `static`. Only build one from pointers
+/// to immutable static data, with no `host_data`.
+pub struct HostInfo(pub clap_host);
+
+unsafe impl Send for HostInfo {}
+unsafe impl Sync for HostInfo {}
+
+impl HostInfo {
+    pub fn as_ptr(&self) -> *const clap_host {
+        &self.0
+    }
+}
+
+const HOST_NAME: &[u8] = b"Harmoniq Studio\0";
+const HOST_VENDOR: &[u8] = b"Harmoniq Labs\0";
+const HOST_URL: &[u8] = b"https://harmoniq.audio\0";
+const HOST_VERSION: &[u8] = b"0.1.0\0";
+
+/// How Harmoniq Studio introduces itself to the plug-ins it hosts.
+pub static HARMONIQ_HOST: HostInfo = HostInfo(clap_host {
+    clap_version: CLAP_VERSION_LATEST,
+    host_data: core::ptr::null_mut(),
+    name: HOST_NAME.as_ptr() as *const c_char,
+    vendor: HOST_VENDOR.as_ptr() as *const c_char,
+    url: HOST_URL.as_ptr() as *const c_char,
+    version: HOST_VERSION.as_ptr() as *const c_char,
+    get_extension: None,
+    request_restart: None,
+    request_process: None,
+    request_callback: None,
+});
+
 /// Represents a running CLAP plug-in instance.
 pub struct ClapInstance {
     plugin: *const clap_plugin,
@@ -135,6 +171,87 @@ impl ClapInstance {
     pub fn host(&self) -> *const clap_host {
         self.host
     }
+
+    /// Parameters published through the params extension, in the plug-in's
+    /// order. Empty when the extension is missing.
+    pub unsafe fn params(&self) -> Vec<ParamInfo> {
+        let Some(params) = self.params_extension() else {
+            return Vec::new();
+        };
+        let (Some(count), Some(get_info)) = (params.count, params.get_info) else {
+            return Vec::new();
+        };
+        (0..count(self.plugin))
+            .filter_map(|index| {
+                let mut raw = clap_param_info::default();
+                get_info(self.plugin, index, &mut raw).then(|| ParameterQuery::new(&raw).info())
+            })
+            .collect()
+    }
+
+    pub unsafe fn param_value(&self, id: u32) -> Option<f64> {
+        let get_value = self.params_extension()?.get_value?;
+        let mut value = 0.0;
+        get_value(self.plugin, id, &mut value).then_some(value)
+    }
+
+    /// Hands parameter events to the plug-in outside of `process`.
+    pub unsafe fn flush_params(
+        &mut self,
+        input: *const clap_input_events,
+        output: *const clap_output_events,
+    ) {
+        if let Some(flush) = self.params_extension().and_then(|params| params.flush) {
+            flush(self.plugin, input, output);
+        }
+    }
+
+    /// The plug-in's state, saved through `clap.state`. A plug-in without
+    /// the extension has no state to save, so the blob is empty.
+    pub unsafe fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        let Some(save) = self.state_extension().and_then(|state| state.save) else {
+            return Ok(Vec::new());
+        };
+        let mut stream = OutputStream::new();
+        let raw = stream.raw();
+        if !save(self.plugin, &raw) {
+            return Err(StateError::SaveFailed);
+        }
+        Ok(stream.into_inner())
+    }
+
+    /// Restores a blob produced by [`save_state`](Self::save_state). Empty
+    /// blobs, and plug-ins without `clap.state`, are left alone.
+    pub unsafe fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let Some(load) = self.state_extension().and_then(|state| state.load) else {
+            return Ok(());
+        };
+        let mut stream = InputStream::new(data);
+        let raw = stream.raw();
+        if load(self.plugin, &raw) {
+            Ok(())
+        } else {
+            Err(StateError::LoadFailed)
+        }
+    }
+
+    unsafe fn params_extension(&self) -> Option<&clap_plugin_params> {
+        self.extension(b"clap.params\0")
+    }
+
+    unsafe fn state_extension(&self) -> Option<&clap_plugin_state> {
+        self.extension(b"clap.state\0")
+    }
+
+    /// Looks up extension `id`, which must be nul-terminated, as a `T`.
+    unsafe fn extension<T>(&self, id: &[u8]) -> Option<&T> {
+        let get_extension = (*self.plugin).get_extension?;
+        let extension = get_extension(self.plugin, id.as_ptr() as *const c_char);
+        (extension as *const T).as_ref()
+    }
 }
 
 impl Drop for ClapInstance {
@@ -5,12 +5,14 @@ mod events;
 mod gui;
 mod instance;
 mod params;
+mod state;
 
 pub use discover::{ClapLibrary, ClapPluginDescriptor, PluginDiscovery};
-pub use events::{ClapEventQueue, EventSlice, EventWriter};
+pub use events::{discard_output_events, ClapEventQueue, EventSlice, EventWriter, ParamEventList};
 pub use gui::{GuiAttachRequest, GuiHandle};
-pub use instance::{ActivationError, AudioConfig, ClapInstance};
-pub use params::{ParamValue, ParameterQuery};
+pub use instance::{ActivationError, AudioConfig, ClapInstance, HostInfo, HARMONIQ_HOST};
+pub use params::{ParamInfo, ParamValue, ParameterQuery};
+pub use state::StateError;
 
 /// Re-export the raw bindings for users that need to drop down to the ABI.
 pub use clap_sys as ffi;
@@ -9,6 +9,16 @@ pub struct ParamValue {
     pub value: f64,
 }
 
+/// Owned description of a parameter reported by `clap_plugin_params.get_info`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamInfo {
+    pub id: u32,
+    pub name: String,
+    pub min: f64,
+    pub max: f64,
+    pub default: f64,
+}
+
 /// Provides query utilities for CLAP parameters.
 pub struct ParameterQuery<'a> {
     raw: &'a clap_param_info_t,
@@ -28,4 +38,14 @@ impl<'a> ParameterQuery<'a> {
     pub fn id(&self) -> u32 {
         self.raw.id
     }
+
+    pub fn info(&self) -> ParamInfo {
+        ParamInfo {
+            id: self.id(),
+            name: self.name().to_string(),
+            min: self.raw.min_value,
+            max: self.raw.max_value,
+            default: self.raw.default_value,
+        }
+    }
 }
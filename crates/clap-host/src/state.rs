@@ -0,0 +1,88 @@
+use core::ffi::c_void;
+
+use clap_sys::{clap_istream, clap_ostream};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("plug-in failed to save its state")]
+    SaveFailed,
+    #[error("plug-in rejected the saved state")]
+    LoadFailed,
+}
+
+/// Collects the bytes a plug-in writes while saving.
+pub(crate) struct OutputStream {
+    data: Vec<u8>,
+}
+
+impl OutputStream {
+    pub(crate) fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    /// The stream as CLAP expects it. Points at `self`, which must not
+    /// move while the plug-in writes.
+    pub(crate) fn raw(&mut self) -> clap_ostream {
+        clap_ostream {
+            ctx: self as *mut Self as *mut c_void,
+            write: Some(Self::write),
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+
+    unsafe extern "C" fn write(
+        stream: *const clap_ostream,
+        buffer: *const c_void,
+        size: u64,
+    ) -> i64 {
+        let this = &mut *((*stream).ctx as *mut Self);
+        if size == 0 {
+            return 0;
+        }
+        if buffer.is_null() {
+            return -1;
+        }
+        let bytes = core::slice::from_raw_parts(buffer as *const u8, size as usize);
+        this.data.extend_from_slice(bytes);
+        size as i64
+    }
+}
+
+/// Hands a saved blob back to a plug-in while loading.
+pub(crate) struct InputStream<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> InputStream<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// See [`OutputStream::raw`].
+    pub(crate) fn raw(&mut self) -> clap_istream {
+        clap_istream {
+            ctx: self as *mut Self as *mut c_void,
+            read: Some(Self::read),
+        }
+    }
+
+    unsafe extern "C" fn read(stream: *const clap_istream, buffer: *mut c_void, size: u64) -> i64 {
+        let this = &mut *((*stream).ctx as *mut Self);
+        let remaining = &this.data[this.position..];
+        let count = remaining.len().min(size as usize);
+        if count == 0 {
+            return 0;
+        }
+        if buffer.is_null() {
+            return -1;
+        }
+        core::ptr::copy_nonoverlapping(remaining.as_ptr(), buffer as *mut u8, count);
+        this.position += count;
+        count as i64
+    }
+}
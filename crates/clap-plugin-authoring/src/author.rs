@@ -58,10 +58,29 @@ pub trait AudioProcessor {
     fn process(&mut self, process: &mut clap_process) -> clap_process_status;
 }
 
-#[allow(dead_code)]
-pub trait Params {}
+/// An automatable parameter exposed through the CLAP params extension.
+#[derive(Clone, Copy, Debug)]
+pub struct ParamDescriptor {
+    /// Stable identifier; hosts persist automation against it.
+    pub id: u32,
+    pub name: &'static str,
+    pub min: f64,
+    pub max: f64,
+    pub default: f64,
+}
 
-#[allow(dead_code)]
+/// Parameters a plug-in publishes to the host. Changes made while
+/// processing arrive in `process.in_events` and can be read with
+/// [`ParamValueEvents`](crate::ParamValueEvents); changes made while idle
+/// are applied through [`set_param_value`](Self::set_param_value).
+pub trait Params {
+    fn param_descriptors(&self) -> &'static [ParamDescriptor];
+    fn param_value(&self, id: u32) -> Option<f64>;
+    fn set_param_value(&mut self, id: u32, value: f64);
+}
+
+/// Opaque plug-in state the host stores with the project, exposed through
+/// the CLAP state extension.
 pub trait State {
     fn save(&mut self, _stream: &mut dyn std::io::Write) -> Result<()> {
         Ok(())
@@ -107,6 +126,14 @@ pub trait Plugin: AudioProcessor + Send + 'static {
     fn deactivate(&mut self) {}
     fn reset(&mut self) {}
     fn on_main_thread(&mut self) {}
+    /// Enables the params extension when returning `Some`.
+    fn params(&mut self) -> Option<&mut dyn Params> {
+        None
+    }
+    /// Enables the state extension when returning `Some`.
+    fn state(&mut self) -> Option<&mut dyn State> {
+        None
+    }
 }
 
 pub trait PluginFactory {
@@ -0,0 +1,68 @@
+use clap_sys::{
+    clap_event_header, clap_event_param_value, clap_input_events, CLAP_CORE_EVENT_SPACE_ID,
+    CLAP_EVENT_PARAM_VALUE,
+};
+
+/// A `CLAP_EVENT_PARAM_VALUE` event read from the host's input list.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamValueEvent {
+    /// Sample offset within the block.
+    pub time: u32,
+    pub id: u32,
+    pub value: f64,
+}
+
+/// Iterates the parameter value events of an input event list in the order
+/// the host queued them, skipping every other event type.
+pub struct ParamValueEvents<'a> {
+    events: Option<&'a clap_input_events>,
+    index: u32,
+    count: u32,
+}
+
+impl<'a> ParamValueEvents<'a> {
+    /// # Safety
+    /// `events` must be null or point to an input event list that stays valid
+    /// for `'a`.
+    pub unsafe fn new(events: *const clap_input_events) -> Self {
+        let events = events.as_ref();
+        let count = events
+            .and_then(|list| list.size.map(|size| size(list)))
+            .unwrap_or(0);
+        Self {
+            events,
+            index: 0,
+            count,
+        }
+    }
+}
+
+impl Iterator for ParamValueEvents<'_> {
+    type Item = ParamValueEvent;
+
+    fn next(&mut self) -> Option<ParamValueEvent> {
+        let events = self.events?;
+        let get = events.get?;
+        while self.index < self.count {
+            let header = unsafe { get(events, self.index) };
+            self.index += 1;
+            if let Some(event) = unsafe { param_value(header) } {
+                return Some(event);
+            }
+        }
+        None
+    }
+}
+
+unsafe fn param_value(header: *const clap_event_header) -> Option<ParamValueEvent> {
+    let raw = header.as_ref()?;
+    if raw.space_id != CLAP_CORE_EVENT_SPACE_ID || raw.type_ != CLAP_EVENT_PARAM_VALUE.0 as u16 {
+        return None;
+    }
+    let event = &*(header as *const clap_event_param_value);
+    Some(ParamValueEvent {
+        time: raw.time,
+        id: event.param_id,
+        value: event.value,
+    })
+}
@@ -1,12 +1,18 @@
+use core::ffi::{c_char, c_void};
 use std::ffi::CStr;
+use std::io;
 use std::marker::PhantomData;
 
 use clap_sys::{
-    clap_host, clap_plugin, clap_plugin_factory_t, clap_process, clap_process_status,
-    CLAP_PROCESS_ERROR,
+    clap_host, clap_input_events, clap_istream, clap_ostream, clap_output_events, clap_param_info,
+    clap_plugin, clap_plugin_factory_t, clap_plugin_params, clap_plugin_state, clap_process,
+    clap_process_status, CLAP_PARAM_IS_AUTOMATABLE, CLAP_PROCESS_ERROR,
 };
 
-use crate::author::{ActivationContext, AudioProcessor, Plugin, PluginDescriptor, PluginFactory};
+use crate::author::{
+    ActivationContext, AudioProcessor, ParamDescriptor, Plugin, PluginDescriptor, PluginFactory,
+};
+use crate::events::ParamValueEvents;
 
 #[allow(dead_code)]
 #[doc(hidden)]
@@ -88,10 +94,171 @@ impl<F: PluginFactory> Instance<F> {
     }
 
     unsafe extern "C" fn get_extension(
-        _plugin: *const clap_plugin,
-        _id: *const i8,
+        plugin: *const clap_plugin,
+        id: *const i8,
     ) -> *const ::core::ffi::c_void {
-        ::core::ptr::null()
+        if id.is_null() {
+            return ::core::ptr::null();
+        }
+        let this = Self::from_plugin(plugin);
+        match CStr::from_ptr(id).to_bytes() {
+            b"clap.params" if this.plugin.params().is_some() => {
+                &Self::PARAMS as *const clap_plugin_params as *const _
+            }
+            b"clap.state" if this.plugin.state().is_some() => {
+                &Self::STATE as *const clap_plugin_state as *const _
+            }
+            _ => ::core::ptr::null(),
+        }
+    }
+
+    const STATE: clap_plugin_state = clap_plugin_state {
+        save: Some(Self::state_save),
+        load: Some(Self::state_load),
+    };
+
+    unsafe extern "C" fn state_save(
+        plugin: *const clap_plugin,
+        stream: *const clap_ostream,
+    ) -> bool {
+        let this = Self::from_plugin(plugin);
+        match (this.plugin.state(), stream.as_ref()) {
+            (Some(state), Some(stream)) => state.save(&mut OutputStream(stream)).is_ok(),
+            _ => false,
+        }
+    }
+
+    unsafe extern "C" fn state_load(
+        plugin: *const clap_plugin,
+        stream: *const clap_istream,
+    ) -> bool {
+        let this = Self::from_plugin(plugin);
+        match (this.plugin.state(), stream.as_ref()) {
+            (Some(state), Some(stream)) => state.load(&mut InputStream(stream)).is_ok(),
+            _ => false,
+        }
+    }
+
+    const PARAMS: clap_plugin_params = clap_plugin_params {
+        count: Some(Self::params_count),
+        get_info: Some(Self::params_get_info),
+        get_value: Some(Self::params_get_value),
+        value_to_text: Some(Self::params_value_to_text),
+        text_to_value: Some(Self::params_text_to_value),
+        flush: Some(Self::params_flush),
+    };
+
+    unsafe fn param_descriptor(plugin: *const clap_plugin, id: u32) -> Option<ParamDescriptor> {
+        let params = Self::from_plugin(plugin).plugin.params()?;
+        params
+            .param_descriptors()
+            .iter()
+            .find(|descriptor| descriptor.id == id)
+            .copied()
+    }
+
+    unsafe extern "C" fn params_count(plugin: *const clap_plugin) -> u32 {
+        let this = Self::from_plugin(plugin);
+        this.plugin
+            .params()
+            .map(|params| params.param_descriptors().len() as u32)
+            .unwrap_or(0)
+    }
+
+    unsafe extern "C" fn params_get_info(
+        plugin: *const clap_plugin,
+        index: u32,
+        info: *mut clap_param_info,
+    ) -> bool {
+        let this = Self::from_plugin(plugin);
+        let Some(params) = this.plugin.params() else {
+            return false;
+        };
+        let (Some(descriptor), Some(info)) = (
+            params.param_descriptors().get(index as usize),
+            info.as_mut(),
+        ) else {
+            return false;
+        };
+        *info = clap_param_info::default();
+        info.id = descriptor.id;
+        info.flags = CLAP_PARAM_IS_AUTOMATABLE.0 as _;
+        copy_c_string(&mut info.name, descriptor.name);
+        info.min_value = descriptor.min;
+        info.max_value = descriptor.max;
+        info.default_value = descriptor.default;
+        true
+    }
+
+    unsafe extern "C" fn params_get_value(
+        plugin: *const clap_plugin,
+        id: u32,
+        value: *mut f64,
+    ) -> bool {
+        let this = Self::from_plugin(plugin);
+        let current = this
+            .plugin
+            .params()
+            .and_then(|params| params.param_value(id));
+        match (current, value.as_mut()) {
+            (Some(current), Some(value)) => {
+                *value = current;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    unsafe extern "C" fn params_value_to_text(
+        plugin: *const clap_plugin,
+        id: u32,
+        value: f64,
+        buffer: *mut c_char,
+        capacity: u32,
+    ) -> bool {
+        if Self::param_descriptor(plugin, id).is_none() || buffer.is_null() || capacity == 0 {
+            return false;
+        }
+        let buffer = ::core::slice::from_raw_parts_mut(buffer, capacity as usize);
+        copy_c_string(buffer, &format!("{value:.3}"));
+        true
+    }
+
+    unsafe extern "C" fn params_text_to_value(
+        plugin: *const clap_plugin,
+        id: u32,
+        text: *const c_char,
+        value: *mut f64,
+    ) -> bool {
+        let Some(descriptor) = Self::param_descriptor(plugin, id) else {
+            return false;
+        };
+        if text.is_null() || value.is_null() {
+            return false;
+        }
+        match CStr::from_ptr(text)
+            .to_str()
+            .map(|text| text.trim().parse::<f64>())
+        {
+            Ok(Ok(parsed)) => {
+                *value = parsed.clamp(descriptor.min, descriptor.max);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    unsafe extern "C" fn params_flush(
+        plugin: *const clap_plugin,
+        input: *const clap_input_events,
+        _output: *const clap_output_events,
+    ) {
+        let this = Self::from_plugin(plugin);
+        if let Some(params) = this.plugin.params() {
+            for event in ParamValueEvents::new(input) {
+                params.set_param_value(event.id, event.value);
+            }
+        }
     }
 
     unsafe extern "C" fn on_main_thread(plugin: *const clap_plugin) {
@@ -100,6 +267,48 @@ impl<F: PluginFactory> Instance<F> {
     }
 }
 
+/// The host's save stream as a [`Write`](io::Write).
+struct OutputStream<'a>(&'a clap_ostream);
+
+impl io::Write for OutputStream<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(write) = self.0.write else {
+            return Err(io::ErrorKind::Unsupported.into());
+        };
+        let written = unsafe { write(self.0, buf.as_ptr() as *const c_void, buf.len() as u64) };
+        usize::try_from(written).map_err(|_| io::Error::other("host rejected plug-in state"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The host's load stream as a [`Read`](io::Read).
+struct InputStream<'a>(&'a clap_istream);
+
+impl io::Read for InputStream<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(read) = self.0.read else {
+            return Err(io::ErrorKind::Unsupported.into());
+        };
+        let count = unsafe { read(self.0, buf.as_mut_ptr() as *mut c_void, buf.len() as u64) };
+        usize::try_from(count).map_err(|_| io::Error::other("host failed to read plug-in state"))
+    }
+}
+
+/// Copies `text` into a fixed-size C string, truncating it to fit.
+fn copy_c_string(target: &mut [c_char], text: &str) {
+    let Some(capacity) = target.len().checked_sub(1) else {
+        return;
+    };
+    let bytes = &text.as_bytes()[..text.len().min(capacity)];
+    for (slot, byte) in target.iter_mut().zip(bytes) {
+        *slot = *byte as c_char;
+    }
+    target[bytes.len()] = 0;
+}
+
 #[allow(dead_code)]
 #[doc(hidden)]
 pub struct FactoryShim<F: PluginFactory> {
@@ -1,9 +1,11 @@
 //! Helpers for exporting CLAP plug-ins with safe Rust wrappers.
 
 mod author;
+mod events;
 pub mod export;
 
 pub use author::{
-    ActivationContext, AudioProcessor, Gui, Latency, NotePorts, Plugin, PluginDescriptor,
-    PluginFactory, State, Tail,
+    ActivationContext, AudioProcessor, Gui, Latency, NotePorts, ParamDescriptor, Params, Plugin,
+    PluginDescriptor, PluginFactory, State, Tail,
 };
+pub use events::{ParamValueEvent, ParamValueEvents};
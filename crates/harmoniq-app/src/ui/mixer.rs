@@ -362,6 +362,42 @@ impl MixerView {
             }
         });
 
+        #[cfg(feature = "mixer_api")]
+        let tx_vca = engine_sender.clone();
+        callbacks.set_vca_gain = Box::new(move |_vca, _gain_db| {
+            #[cfg(feature = "mixer_api")]
+            if let Some(tx) = &tx_vca {
+                let _ = tx.send(MixerCommand::SetVcaGain {
+                    vca: _vca,
+                    gain_db: _gain_db,
+                });
+            }
+        });
+
+        #[cfg(feature = "mixer_api")]
+        let tx_assign_vca = engine_sender.clone();
+        callbacks.assign_vca = Box::new(move |_channel_id, _vca| {
+            #[cfg(feature = "mixer_api")]
+            if let Some(tx) = &tx_assign_vca {
+                let _ = tx.send(MixerCommand::AssignVca {
+                    ch: _channel_id,
+                    vca: _vca,
+                });
+            }
+        });
+
+        #[cfg(feature = "mixer_api")]
+        let tx_vca_parent = engine_sender.clone();
+        callbacks.set_vca_parent = Box::new(move |_vca, _parent| {
+            #[cfg(feature = "mixer_api")]
+            if let Some(tx) = &tx_vca_parent {
+                let _ = tx.send(MixerCommand::SetVcaParent {
+                    vca: _vca,
+                    parent: _parent,
+                });
+            }
+        });
+
         let api_mute = Arc::clone(&self.api);
         let map_mute = snapshots.clone();
         #[cfg(feature = "mixer_api")]
@@ -516,6 +552,36 @@ impl MixerView {
             }
         });
 
+        #[cfg(feature = "mixer_api")]
+        let tx_solo_mode = engine_sender.clone();
+        callbacks.set_solo_mode = Box::new(move |_mode| {
+            #[cfg(feature = "mixer_api")]
+            if let Some(tx) = &tx_solo_mode {
+                let _ = tx.send(MixerCommand::SetSoloMode { mode: _mode });
+            }
+        });
+
+        #[cfg(feature = "mixer_api")]
+        let tx_monitor_dim = engine_sender.clone();
+        callbacks.set_monitor_dim = Box::new(move |_enabled, _dim_db| {
+            #[cfg(feature = "mixer_api")]
+            if let Some(tx) = &tx_monitor_dim {
+                let _ = tx.send(MixerCommand::SetMonitorDim {
+                    enabled: _enabled,
+                    dim_db: _dim_db,
+                });
+            }
+        });
+
+        #[cfg(feature = "mixer_api")]
+        let tx_monitor_mono = engine_sender.clone();
+        callbacks.set_monitor_mono = Box::new(move |_enabled| {
+            #[cfg(feature = "mixer_api")]
+            if let Some(tx) = &tx_monitor_mono {
+                let _ = tx.send(MixerCommand::SetMonitorMono { enabled: _enabled });
+            }
+        });
+
         #[cfg(feature = "mixer_api")]
         let tx_routing = engine_sender;
         callbacks.apply_routing = Box::new(move |delta: RoutingDelta| {
@@ -5,8 +5,9 @@ use std::sync::Arc;
 use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use harmoniq_engine::render::{
-    DitherKind, FreezeSettings, RenderDuration, RenderFile, RenderFormat, RenderProject,
-    RenderQueue, RenderRequest, RenderSpeed, StemSettings,
+    DitherKind, FreezeSettings, Normalization, RenderDuration, RenderFile, RenderFormat,
+    RenderProject, RenderQueue, RenderRequest, RenderSource, RenderSpeed, ShapingProfile,
+    StemSettings, StemSplit, DEFAULT_STEM_TEMPLATE,
 };
 use harmoniq_engine::{
     nodes::{NodeNoise, NodeOsc},
@@ -45,6 +46,18 @@ struct RenderArgs {
     /// Optional directory for exporting stems.
     #[arg(long)]
     stems_dir: Option<PathBuf>,
+    /// How stems are split.
+    #[arg(long, value_enum, default_value_t = StemMode::Track)]
+    stem_split: StemMode,
+    /// Stem file name template using `{project}`, `{track}` and `{index}`.
+    #[arg(long, default_value = DEFAULT_STEM_TEMPLATE)]
+    stem_template: String,
+    /// Seconds rendered past the end of each stem so tails ring out.
+    #[arg(long, default_value_t = 0.0)]
+    stem_tail: f32,
+    /// Keep shared sends audible in stems.
+    #[arg(long)]
+    wet_stems: bool,
     /// Optional directory for project freeze assets.
     #[arg(long)]
     freeze_dir: Option<PathBuf>,
@@ -57,6 +70,26 @@ struct RenderArgs {
     /// Enable TPDF dithering when exporting integer formats.
     #[arg(long)]
     dither: bool,
+    /// Shape the dither noise out of the most audible band. Implies
+    /// `--dither`.
+    #[arg(long, value_enum)]
+    noise_shaping: Option<Shaping>,
+    /// FLAC compression level, 0 (fastest) to 8 (smallest).
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u8).range(0..=8))]
+    flac_compression: u8,
+    /// Ogg Vorbis quality, -0.1 to 1.0.
+    #[arg(long, default_value_t = 0.5)]
+    ogg_quality: f32,
+    /// Pace the render at this multiple of real time, e.g. 1 for hardware
+    /// synths. Renders as fast as possible when omitted.
+    #[arg(long)]
+    speed: Option<f32>,
+    /// Normalise the mixdown to this integrated loudness in LUFS.
+    #[arg(long)]
+    target_lufs: Option<f32>,
+    /// True-peak ceiling in dBTP used with `--target-lufs`.
+    #[arg(long, default_value_t = -1.0)]
+    true_peak_ceiling: f32,
 }
 
 fn execute_render(args: RenderArgs) -> Result<()> {
@@ -70,11 +103,24 @@ fn execute_render(args: RenderArgs) -> Result<()> {
         .map(RenderDuration::Seconds)
         .unwrap_or_else(|| RenderDuration::Seconds(spec.duration_seconds));
 
-    let format = RenderFormat::from(args.format);
-    let dither = if args.dither {
-        Some(DitherKind::Tpdf)
-    } else {
-        None
+    let format = match args.format {
+        OutputFormat::Wav => RenderFormat::Wav,
+        OutputFormat::Flac => RenderFormat::Flac {
+            compression: args.flac_compression,
+        },
+        OutputFormat::Ogg => RenderFormat::OggVorbis {
+            quality: args.ogg_quality,
+        },
+    };
+    let dither = match args.noise_shaping {
+        Some(Shaping::Third) => Some(DitherKind::NoiseShaped {
+            profile: ShapingProfile::ThirdOrder,
+        }),
+        Some(Shaping::Ninth) => Some(DitherKind::NoiseShaped {
+            profile: ShapingProfile::NinthOrder,
+        }),
+        None if args.dither => Some(DitherKind::Tpdf),
+        None => None,
     };
 
     let mixdown = RenderFile {
@@ -84,10 +130,15 @@ fn execute_render(args: RenderArgs) -> Result<()> {
     };
 
     let stems = args.stems_dir.as_ref().map(|dir| StemSettings {
-        directory: dir.clone(),
-        format,
         dither,
-        plugins: None,
+        split: match args.stem_split {
+            StemMode::Track => StemSplit::PerTrack,
+            StemMode::Bus => StemSplit::PerBus,
+        },
+        name_template: args.stem_template.clone(),
+        tail_seconds: args.stem_tail,
+        wet: args.wet_stems,
+        ..StemSettings::new(dir.clone(), format)
     });
 
     let freeze = args.freeze_dir.as_ref().map(|dir| FreezeSettings {
@@ -95,6 +146,8 @@ fn execute_render(args: RenderArgs) -> Result<()> {
         format,
         dither,
         plugins: None,
+        duration,
+        tail_seconds: 0.0,
     });
 
     let request = RenderRequest {
@@ -102,7 +155,18 @@ fn execute_render(args: RenderArgs) -> Result<()> {
         mixdown: Some(mixdown),
         stems,
         freeze,
-        speed: RenderSpeed::Offline,
+        speed: match args.speed {
+            None => RenderSpeed::Offline,
+            Some(factor) if factor == 1.0 => RenderSpeed::Realtime,
+            Some(factor) => RenderSpeed::Multiplier(factor),
+        },
+        master_oversample: 1,
+        normalization: args
+            .target_lufs
+            .map_or(Normalization::None, |target| Normalization::Lufs {
+                target,
+                true_peak_ceiling: args.true_peak_ceiling,
+            }),
     };
 
     let project = Arc::new(spec);
@@ -120,8 +184,16 @@ fn execute_render(args: RenderArgs) -> Result<()> {
         }
         if !report.stems.is_empty() {
             println!("  Stems:");
-            for stem in report.stems {
-                println!("    {}", stem.display());
+            for file in report
+                .files
+                .iter()
+                .filter(|file| matches!(file.source, RenderSource::Stem(_)))
+            {
+                println!(
+                    "    {} (peak {:.1} dBFS)",
+                    file.path.display(),
+                    file.peak_dbfs
+                );
             }
         }
         if !report.freezes.is_empty() {
@@ -134,19 +206,23 @@ fn execute_render(args: RenderArgs) -> Result<()> {
     Ok(())
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Shaping {
+    Third,
+    Ninth,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum StemMode {
+    Track,
+    Bus,
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum OutputFormat {
     Wav,
     Flac,
-}
-
-impl From<OutputFormat> for RenderFormat {
-    fn from(format: OutputFormat) -> Self {
-        match format {
-            OutputFormat::Wav => RenderFormat::Wav,
-            OutputFormat::Flac => RenderFormat::Flac,
-        }
-    }
+    Ogg,
 }
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,45 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use harmoniq_dsp::resample::{InterpolationKind, Resampler};
+use harmoniq_dsp::simd::{mul_add_to, F32x8, SimdFloat};
+
+const LEN: usize = 1024;
+
+fn ramp() -> Vec<f32> {
+    (0..LEN).map(|i| i as f32 / LEN as f32 - 0.5).collect()
+}
+
+fn bench_mul_add(c: &mut Criterion) {
+    let lhs = ramp();
+    let rhs = vec![0.75f32; LEN];
+    let addend = vec![0.25f32; LEN];
+    let mut output = vec![0.0f32; LEN];
+    c.bench_function("simd mul_add 1024", |b| {
+        b.iter(|| mul_add_to(&mut output, black_box(&lhs), &rhs, &addend))
+    });
+}
+
+fn bench_clamp_sum(c: &mut Criterion) {
+    let input = ramp();
+    let (lo, hi) = (F32x8::splat(-0.25), F32x8::splat(0.25));
+    c.bench_function("simd clamp+reduce_sum 1024", |b| {
+        b.iter(|| {
+            black_box(&input)
+                .chunks_exact(8)
+                .map(|chunk| F32x8::from_slice(chunk).clamp(lo, hi).abs().reduce_sum())
+                .sum::<f32>()
+        })
+    });
+}
+
+fn bench_sinc_resample(c: &mut Criterion) {
+    let input = ramp();
+    let mut output = vec![0.0f32; 2 * LEN];
+    let mut resampler =
+        Resampler::with_kind(44_100.0, 48_000.0, InterpolationKind::Sinc { taps: 32 });
+    c.bench_function("sinc32 resample 44.1k->48k 1024", |b| {
+        b.iter(|| resampler.process(black_box(&input), &mut output))
+    });
+}
+
+criterion_group!(benches, bench_mul_add, bench_clamp_sum, bench_sinc_resample);
+criterion_main!(benches);
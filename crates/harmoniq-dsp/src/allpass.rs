@@ -0,0 +1,175 @@
+//! Allpass stages and DC removal.
+
+use core::f32::consts::PI;
+
+/// Corner frequency of [`DcBlocker`].
+pub const DC_BLOCKER_HZ: f32 = 5.0;
+
+/// One-pole, one-zero highpass at [`DC_BLOCKER_HZ`] that strips constant
+/// offsets, e.g. after asymmetric saturation.
+#[derive(Clone, Copy, Debug)]
+pub struct DcBlocker {
+    pole: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl DcBlocker {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut blocker = Self {
+            pole: 0.0,
+            x1: 0.0,
+            y1: 0.0,
+        };
+        blocker.set_sample_rate(sample_rate);
+        blocker
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.pole = (-2.0 * PI * DC_BLOCKER_HZ / sample_rate.max(1.0)).exp();
+    }
+
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.y1 = 0.0;
+    }
+
+    #[inline]
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        let y = x - self.x1 + self.pole * self.y1;
+        self.x1 = x;
+        self.y1 = y;
+        y
+    }
+}
+
+/// First-order allpass `(a + z^-1) / (1 + a z^-1)`.
+///
+/// The magnitude is one at every frequency; the phase falls from 0 at DC to
+/// -180 degrees at Nyquist, passing -90 degrees at the break frequency set
+/// by [`set_frequency`](Self::set_frequency).
+#[derive(Clone, Copy, Debug)]
+pub struct Allpass1 {
+    pub coefficient: f32,
+    sample_rate: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl Allpass1 {
+    pub fn new(coefficient: f32) -> Self {
+        Self {
+            coefficient,
+            sample_rate: 48_000.0,
+            x1: 0.0,
+            y1: 0.0,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate.max(1.0);
+    }
+
+    /// Places the -90 degree point at `hz`.
+    pub fn set_frequency(&mut self, hz: f32) {
+        let t = (PI * hz.clamp(1.0, 0.49 * self.sample_rate) / self.sample_rate).tan();
+        self.coefficient = (t - 1.0) / (t + 1.0);
+    }
+
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.y1 = 0.0;
+    }
+
+    #[inline]
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        let y = self.coefficient * (x - self.y1) + self.x1;
+        self.x1 = x;
+        self.y1 = y;
+        y
+    }
+}
+
+/// Second-order allpass with the -180 degree point at `frequency` and a
+/// phase transition whose steepness is set by `q`.
+#[derive(Clone, Copy, Debug)]
+pub struct Allpass2 {
+    frequency: f32,
+    q: f32,
+    sample_rate: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Allpass2 {
+    pub fn new(frequency: f32, q: f32) -> Self {
+        let mut allpass = Self {
+            frequency,
+            q,
+            sample_rate: 48_000.0,
+            a1: 0.0,
+            a2: 0.0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        };
+        allpass.update();
+        allpass
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate.max(1.0);
+        self.update();
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    pub fn set_frequency(&mut self, hz: f32) {
+        self.frequency = hz;
+        self.update();
+    }
+
+    pub fn q(&self) -> f32 {
+        self.q
+    }
+
+    pub fn set_q(&mut self, q: f32) {
+        self.q = q;
+        self.update();
+    }
+
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.x2 = 0.0;
+        self.y1 = 0.0;
+        self.y2 = 0.0;
+    }
+
+    #[inline]
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        // An allpass numerator mirrors its denominator: b0 = a2, b1 = a1,
+        // b2 = 1.
+        let y = self.a2 * x + self.a1 * self.x1 + self.x2 - self.a1 * self.y1 - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+
+    fn update(&mut self) {
+        let frequency = self.frequency.clamp(1.0, 0.49 * self.sample_rate);
+        let w = 2.0 * PI * frequency / self.sample_rate;
+        let alpha = w.sin() / (2.0 * self.q.max(0.05));
+        let a0 = 1.0 + alpha;
+        self.a1 = -2.0 * w.cos() / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+}
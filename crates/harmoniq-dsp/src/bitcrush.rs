@@ -0,0 +1,123 @@
+//! Bit-depth and sample-rate reduction.
+
+use crate::buffer::{AudioBlock, AudioBlockMut};
+
+/// Lo-fi bitcrusher.
+///
+/// Every sample is quantised to `bit_depth` bits with a signed mid-tread
+/// quantiser: zero is a level, so silence stays silent, and the codes span a
+/// signed integer of `bit_depth` bits. At [`MAX_BITS`](Self::MAX_BITS) the
+/// quantiser is bypassed. Sample-rate reduction holds one input sample per
+/// channel for `downsample` frames; a fractional phase accumulator spreads
+/// non-integer factors evenly, e.g. `2.5` alternates holds of two and three
+/// frames. There is no anti-alias filter; the aliasing is the effect.
+///
+/// [`prepare`](Self::prepare) sizes the per-channel state; channels beyond
+/// the prepared count are left untouched.
+#[derive(Debug, Clone)]
+pub struct BitCrusher {
+    pub bit_depth: f32,
+    pub downsample: f32,
+    phase: f32,
+    held: Vec<f32>,
+}
+
+impl BitCrusher {
+    pub const MIN_BITS: f32 = 1.0;
+    /// The mantissa width of `f32`; quantising any finer changes nothing.
+    pub const MAX_BITS: f32 = 24.0;
+
+    pub fn new(bit_depth: f32, downsample: f32) -> Self {
+        Self {
+            bit_depth,
+            downsample,
+            phase: 1.0,
+            held: Vec::new(),
+        }
+    }
+
+    /// Allocates the held sample of each of `channels` channels.
+    pub fn prepare(&mut self, channels: usize) {
+        self.held = vec![0.0; channels];
+        self.phase = 1.0;
+    }
+
+    /// Drops the held samples so the next block starts with a fresh one.
+    pub fn reset(&mut self) {
+        self.held.fill(0.0);
+        self.phase = 1.0;
+    }
+
+    #[inline]
+    pub fn quantize(&self, sample: f32) -> f32 {
+        if self.bit_depth >= Self::MAX_BITS {
+            return sample;
+        }
+        let bits = self.bit_depth.max(Self::MIN_BITS).round();
+        let levels = (bits - 1.0).exp2();
+        (sample * levels).round().clamp(-levels, levels - 1.0) / levels
+    }
+
+    /// Advances the hold phase by one frame and reports whether a new
+    /// sample is taken on it.
+    #[inline]
+    fn advance(&mut self) -> bool {
+        let sample_now = self.phase >= 1.0;
+        if sample_now {
+            self.phase -= 1.0;
+        }
+        self.phase += 1.0 / self.downsample.max(1.0);
+        sample_now
+    }
+
+    pub fn process_block(&mut self, block: &mut AudioBlockMut<'_>) {
+        let channels = (block.channels() as usize).min(self.held.len());
+        let frames = block.frames() as usize;
+
+        for frame in 0..frames {
+            let sample_now = self.advance();
+            for ch in 0..channels {
+                let mut chan = unsafe { block.chan_mut(ch) };
+                if sample_now {
+                    let input = unsafe { chan.read(frame) };
+                    self.held[ch] = self.quantize(input);
+                }
+                unsafe { chan.write(frame, self.held[ch]) };
+            }
+        }
+    }
+
+    /// Crushes the first `frames` frames of `input` into `output`. Output
+    /// channels without a matching input are silenced.
+    pub fn process_into(
+        &mut self,
+        input: &AudioBlock<'_>,
+        output: &mut AudioBlockMut<'_>,
+        frames: usize,
+    ) {
+        let out_channels = output.channels() as usize;
+        let channels = (input.channels() as usize)
+            .min(out_channels)
+            .min(self.held.len());
+
+        for frame in 0..frames {
+            let sample_now = self.advance();
+            for ch in 0..channels {
+                if sample_now {
+                    let input = unsafe { input.read_sample(ch, frame) };
+                    self.held[ch] = self.quantize(input);
+                }
+                unsafe { output.write_sample(ch, frame, self.held[ch]) };
+            }
+            for ch in channels..out_channels {
+                unsafe { output.write_sample(ch, frame, 0.0) };
+            }
+        }
+    }
+}
+
+impl Default for BitCrusher {
+    fn default() -> Self {
+        Self::new(16.0, 1.0)
+    }
+}
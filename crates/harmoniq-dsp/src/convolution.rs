@@ -0,0 +1,305 @@
+//! FFT convolution with a linear-phase FIR.
+//!
+//! [`LinearPhaseConvolver`] turns a magnitude response into a symmetric,
+//! Blackman-windowed FIR and runs it as a uniformly partitioned convolution.
+//! The first [`PARTITION`] taps are applied directly, sample by sample; the
+//! rest are split into partitions of the same length whose spectra are
+//! multiplied with a delay line of input spectra once per partition of
+//! input. Only the direct head needs the current input, so the convolver
+//! adds no buffering delay on top of the FIR's own.
+//!
+//! A new kernel is crossfaded in over one partition. All buffers are sized
+//! up front, so processing does not allocate.
+
+use crate::fft::{Complex, Fft};
+use crate::resample::blackman;
+
+/// Length of each kernel partition and of the input blocks they convolve.
+pub const PARTITION: usize = 128;
+
+/// A designed FIR: the directly applied head and the spectra of the
+/// remaining partitions, each zero-padded to twice the partition length.
+#[derive(Clone, Debug)]
+struct Kernel {
+    head: Vec<f32>,
+    partitions: Vec<Complex>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Fade {
+    Idle,
+    /// A new kernel is waiting for the next partition boundary. Until then
+    /// the channel keeps playing the previous one.
+    Pending,
+    /// The partition in progress blends from the previous kernel to the
+    /// current one.
+    Fading,
+}
+
+#[derive(Clone, Debug)]
+struct ChannelState {
+    /// The last complete input partition followed by the one being filled.
+    input: Vec<f32>,
+    fill: usize,
+    /// Spectra of the most recent input windows, one per tail partition.
+    spectra: Vec<Complex>,
+    newest: usize,
+    /// Tail partitions' output for the partition being filled.
+    tail: Vec<f32>,
+    /// The same, through the previous kernel, while fading.
+    previous_tail: Vec<f32>,
+    fade: Fade,
+    /// Whether the channel has had input since the last reset. Until then
+    /// there is nothing to fade from.
+    primed: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct LinearPhaseConvolver {
+    taps: usize,
+    design_fft: Fft,
+    design: Vec<Complex>,
+    fft: Fft,
+    kernel: Kernel,
+    previous: Kernel,
+    scratch: Vec<Complex>,
+    channels: Vec<ChannelState>,
+}
+
+impl LinearPhaseConvolver {
+    /// A convolver for `channels` channels with a `taps`-point FIR, rounded
+    /// up to an odd count so the kernel has a centre sample. The kernel
+    /// starts as a pure delay.
+    pub fn new(taps: usize, channels: usize) -> Self {
+        let taps = odd_taps(taps);
+        let design_size = (2 * (taps - 1)).next_power_of_two();
+        let tail_partitions = taps.div_ceil(PARTITION) - 1;
+        let kernel = Kernel {
+            head: vec![0.0; PARTITION],
+            partitions: vec![Complex::ZERO; tail_partitions * 2 * PARTITION],
+        };
+        let channel = ChannelState {
+            input: vec![0.0; 2 * PARTITION],
+            fill: 0,
+            spectra: vec![Complex::ZERO; tail_partitions * 2 * PARTITION],
+            newest: 0,
+            tail: vec![0.0; PARTITION],
+            previous_tail: vec![0.0; PARTITION],
+            fade: Fade::Idle,
+            primed: false,
+        };
+        let mut convolver = Self {
+            taps,
+            design_fft: Fft::new(design_size),
+            design: vec![Complex::ZERO; design_size],
+            fft: Fft::new(2 * PARTITION),
+            previous: kernel.clone(),
+            kernel,
+            scratch: vec![Complex::ZERO; 2 * PARTITION],
+            channels: vec![channel; channels],
+        };
+        convolver.set_magnitude_response(|_| 1.0);
+        convolver.reset();
+        convolver
+    }
+
+    pub fn taps(&self) -> usize {
+        self.taps
+    }
+
+    /// Delay in samples: the FIR's half length.
+    pub fn latency(&self) -> usize {
+        Self::latency_for(self.taps)
+    }
+
+    /// [`latency`](Self::latency) of a convolver built with `taps` taps.
+    pub fn latency_for(taps: usize) -> usize {
+        odd_taps(taps) / 2
+    }
+
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.input.fill(0.0);
+            channel.fill = 0;
+            channel.spectra.fill(Complex::ZERO);
+            channel.newest = 0;
+            channel.tail.fill(0.0);
+            channel.previous_tail.fill(0.0);
+            channel.fade = Fade::Idle;
+            channel.primed = false;
+        }
+    }
+
+    /// Designs the kernel from `magnitude`, called with each frequency as a
+    /// fraction of the sample rate from 0 to 0.5. Each channel crossfades to
+    /// it over the partition after its next partition boundary.
+    pub fn set_magnitude_response(&mut self, mut magnitude: impl FnMut(f32) -> f32) {
+        // A channel still on its way to the last kernel keeps fading from
+        // the one it is playing rather than from that kernel.
+        if self
+            .channels
+            .iter()
+            .all(|channel| channel.fade == Fade::Idle)
+        {
+            std::mem::swap(&mut self.kernel, &mut self.previous);
+        }
+        for channel in &mut self.channels {
+            if channel.primed && channel.fade == Fade::Idle {
+                channel.fade = Fade::Pending;
+            }
+        }
+
+        let size = self.design_fft.len();
+        // A real, even spectrum transforms to a zero-phase impulse centred
+        // on sample 0 and wrapping around the end of the buffer.
+        for bin in 0..=size / 2 {
+            let value = Complex::new(magnitude(bin as f32 / size as f32), 0.0);
+            self.design[bin] = value;
+            if bin > 0 && bin < size / 2 {
+                self.design[size - bin] = value;
+            }
+        }
+        self.design_fft.inverse(&mut self.design);
+
+        let centre = self.taps / 2;
+        let taps = self.taps;
+        let design = &self.design;
+        let tap = |index: usize| {
+            if index >= taps {
+                return 0.0;
+            }
+            let source = (index + size - centre) % size;
+            let window = blackman(index as f64 / (taps - 1) as f64) as f32;
+            design[source].re * window
+        };
+
+        for (index, value) in self.kernel.head.iter_mut().enumerate() {
+            *value = tap(index);
+        }
+        for (partition, spectrum) in self
+            .kernel
+            .partitions
+            .chunks_exact_mut(2 * PARTITION)
+            .enumerate()
+        {
+            let start = (partition + 1) * PARTITION;
+            for (index, value) in spectrum.iter_mut().enumerate() {
+                *value = if index < PARTITION {
+                    Complex::new(tap(start + index), 0.0)
+                } else {
+                    Complex::ZERO
+                };
+            }
+            self.fft.forward(spectrum);
+        }
+    }
+
+    /// Filters `samples` of `channel` in place, delayed by
+    /// [`latency`](Self::latency).
+    pub fn process(&mut self, channel: usize, samples: &mut [f32]) {
+        let Some(state) = self.channels.get_mut(channel) else {
+            return;
+        };
+        state.primed |= !samples.is_empty();
+        for sample in samples {
+            state.input[PARTITION + state.fill] = *sample;
+            let newest = &state.input[state.fill + 1..=PARTITION + state.fill];
+            *sample = match state.fade {
+                Fade::Idle => direct(&self.kernel.head, newest) + state.tail[state.fill],
+                Fade::Pending => direct(&self.previous.head, newest) + state.tail[state.fill],
+                Fade::Fading => {
+                    let from =
+                        direct(&self.previous.head, newest) + state.previous_tail[state.fill];
+                    let to = direct(&self.kernel.head, newest) + state.tail[state.fill];
+                    let mix = state.fill as f32 / PARTITION as f32;
+                    from + (to - from) * mix
+                }
+            };
+            state.fill += 1;
+            if state.fill == PARTITION {
+                finish_partition(
+                    state,
+                    &self.fft,
+                    &self.kernel,
+                    &self.previous,
+                    &mut self.scratch,
+                );
+            }
+        }
+    }
+}
+
+fn odd_taps(taps: usize) -> usize {
+    taps.max(3) | 1
+}
+
+/// The head taps applied to the newest input, which ends with the current
+/// sample.
+#[inline]
+fn direct(head: &[f32], newest: &[f32]) -> f32 {
+    head.iter()
+        .zip(newest.iter().rev())
+        .map(|(tap, input)| tap * input)
+        .sum()
+}
+
+/// Pushes the completed partition's spectrum into the delay line and
+/// computes the tail output for the next partition, through the previous
+/// kernel too when a fade starts there.
+fn finish_partition(
+    state: &mut ChannelState,
+    fft: &Fft,
+    kernel: &Kernel,
+    previous: &Kernel,
+    scratch: &mut [Complex],
+) {
+    state.fade = match state.fade {
+        Fade::Idle | Fade::Fading => Fade::Idle,
+        Fade::Pending => Fade::Fading,
+    };
+    let window = 2 * PARTITION;
+    let count = state.spectra.len() / window;
+    if count > 0 {
+        state.newest = (state.newest + 1) % count;
+        let slot = &mut state.spectra[state.newest * window..(state.newest + 1) * window];
+        for (value, input) in slot.iter_mut().zip(&state.input) {
+            *value = Complex::new(*input, 0.0);
+        }
+        fft.forward(slot);
+
+        convolve_tail(state, fft, kernel, scratch, false);
+        if state.fade == Fade::Fading {
+            convolve_tail(state, fft, previous, scratch, true);
+        }
+    }
+    state.input.copy_within(PARTITION.., 0);
+    state.fill = 0;
+}
+
+fn convolve_tail(
+    state: &mut ChannelState,
+    fft: &Fft,
+    kernel: &Kernel,
+    scratch: &mut [Complex],
+    into_previous: bool,
+) {
+    let window = 2 * PARTITION;
+    let count = state.spectra.len() / window;
+    scratch.fill(Complex::ZERO);
+    for (age, partition) in kernel.partitions.chunks_exact(window).enumerate() {
+        let slot = (state.newest + count - age) % count;
+        let input = &state.spectra[slot * window..(slot + 1) * window];
+        for ((value, input), partition) in scratch.iter_mut().zip(input).zip(partition) {
+            *value = *value + *input * *partition;
+        }
+    }
+    fft.inverse(scratch);
+    let output = if into_previous {
+        &mut state.previous_tail
+    } else {
+        &mut state.tail
+    };
+    for (output, value) in output.iter_mut().zip(&scratch[PARTITION..]) {
+        *output = value.re;
+    }
+}
@@ -0,0 +1,155 @@
+//! Linkwitz-Riley band splitting.
+//!
+//! A fourth-order Linkwitz-Riley crossover is a pair of cascaded
+//! Butterworth lowpass and highpass sections. The two bands are in phase at
+//! every frequency and add up to a second-order allpass, so their sum has a
+//! flat magnitude response. [`LinkwitzRiley4::matching_allpass`] is that
+//! allpass on its own, used to keep bands that skip a crossover in phase
+//! with the ones that pass through it.
+
+use core::f32::consts::{FRAC_1_SQRT_2, PI, SQRT_2};
+
+use crate::allpass::Allpass2;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Section {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Section {
+    fn set(&mut self, b: [f32; 3], a: [f32; 3]) {
+        let inv_a0 = 1.0 / a[0];
+        self.b0 = b[0] * inv_a0;
+        self.b1 = b[1] * inv_a0;
+        self.b2 = b[2] * inv_a0;
+        self.a1 = a[1] * inv_a0;
+        self.a2 = a[2] * inv_a0;
+    }
+
+    fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+
+    #[inline]
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// Butterworth-Q lowpass and highpass coefficients at `hz`, as `(b, a)`
+/// pairs.
+fn butterworth(sample_rate: f32, hz: f32) -> [([f32; 3], [f32; 3]); 2] {
+    let sr = sample_rate.max(1.0);
+    let omega = 2.0 * PI * clamp_crossover(sr, hz) / sr;
+    let cos = omega.cos();
+    let alpha = omega.sin() / (2.0 * FRAC_1_SQRT_2);
+    let a = [1.0 + alpha, -2.0 * cos, 1.0 - alpha];
+    let low = (1.0 - cos) / 2.0;
+    let high = (1.0 + cos) / 2.0;
+    [([low, 2.0 * low, low], a), ([high, -2.0 * high, high], a)]
+}
+
+fn clamp_crossover(sample_rate: f32, hz: f32) -> f32 {
+    hz.clamp(10.0, 0.45 * sample_rate.max(1.0))
+}
+
+/// Fourth-order Linkwitz-Riley crossover splitting a signal into low and
+/// high bands at `crossover_hz`.
+#[derive(Clone, Copy, Debug)]
+pub struct LinkwitzRiley4 {
+    crossover_hz: f32,
+    sample_rate: f32,
+    lowpass: [Section; 2],
+    highpass: [Section; 2],
+}
+
+impl LinkwitzRiley4 {
+    pub fn new(sample_rate: f32, crossover_hz: f32) -> Self {
+        let mut crossover = Self {
+            crossover_hz,
+            sample_rate: sample_rate.max(1.0),
+            lowpass: [Section::default(); 2],
+            highpass: [Section::default(); 2],
+        };
+        crossover.update();
+        crossover
+    }
+
+    pub fn crossover_hz(&self) -> f32 {
+        self.crossover_hz
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate.max(1.0);
+        self.update();
+    }
+
+    /// Moves the crossover point. Filter state is kept, so the split stays
+    /// continuous while the frequency is swept.
+    pub fn set_crossover_hz(&mut self, crossover_hz: f32) {
+        self.crossover_hz = crossover_hz;
+        self.update();
+    }
+
+    pub fn reset(&mut self) {
+        for section in self.lowpass.iter_mut().chain(self.highpass.iter_mut()) {
+            section.reset();
+        }
+    }
+
+    /// Splits one sample into its `(low, high)` bands.
+    #[inline]
+    pub fn split(&mut self, x: f32) -> (f32, f32) {
+        let [low_1, low_2] = &mut self.lowpass;
+        let [high_1, high_2] = &mut self.highpass;
+        (
+            low_2.process(low_1.process(x)),
+            high_2.process(high_1.process(x)),
+        )
+    }
+
+    /// Splits `input` into `low` and `high`, which must be at least as long.
+    pub fn split_block(&mut self, input: &[f32], low: &mut [f32], high: &mut [f32]) {
+        for ((x, low), high) in input.iter().zip(low.iter_mut()).zip(high.iter_mut()) {
+            (*low, *high) = self.split(*x);
+        }
+    }
+
+    /// Delay of the summed bands at low frequencies, in samples. This is the
+    /// group delay of the equivalent allpass at DC.
+    pub fn group_delay_samples(&self) -> f32 {
+        // A Butterworth-Q allpass delays DC by sqrt(2) / tan(w / 2).
+        let hz = clamp_crossover(self.sample_rate, self.crossover_hz);
+        SQRT_2 / (PI * hz / self.sample_rate).tan()
+    }
+
+    /// Allpass with the same phase response as `low + high`.
+    pub fn matching_allpass(&self) -> Allpass2 {
+        let mut allpass = Allpass2::new(
+            clamp_crossover(self.sample_rate, self.crossover_hz),
+            FRAC_1_SQRT_2,
+        );
+        allpass.set_sample_rate(self.sample_rate);
+        allpass
+    }
+
+    fn update(&mut self) {
+        let [low, high] = butterworth(self.sample_rate, self.crossover_hz);
+        for section in &mut self.lowpass {
+            section.set(low.0, low.1);
+        }
+        for section in &mut self.highpass {
+            section.set(high.0, high.1);
+        }
+    }
+}
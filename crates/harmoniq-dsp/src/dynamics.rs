@@ -0,0 +1,37 @@
+//! Static gain computers shared by the compressor, limiter and multiband
+//! dynamics processors. All levels are in dB; the returned value is the gain
+//! change to apply (zero or negative).
+
+/// Gain change for a hard-knee compressor: no reduction at or below the
+/// threshold, `ratio`:1 above it.
+#[inline]
+pub fn hard_knee(input_db: f32, threshold_db: f32, ratio: f32) -> f32 {
+    if input_db <= threshold_db {
+        return 0.0;
+    }
+    let ratio = ratio.max(1.0);
+    (threshold_db + (input_db - threshold_db) / ratio) - input_db
+}
+
+/// Gain change for a soft-knee compressor.
+///
+/// Within `knee_db / 2` of the threshold the curve blends quadratically from
+/// unity into the `ratio`:1 slope, so the transfer function and its first
+/// derivative stay continuous. A knee of zero matches [`hard_knee`].
+#[inline]
+pub fn soft_knee_gain(input_db: f32, threshold_db: f32, ratio: f32, knee_db: f32) -> f32 {
+    if knee_db <= 0.0 {
+        return hard_knee(input_db, threshold_db, ratio);
+    }
+    let overshoot = input_db - threshold_db;
+    let half_knee = knee_db * 0.5;
+    if overshoot <= -half_knee {
+        0.0
+    } else if overshoot < half_knee {
+        let slope = 1.0 / ratio.max(1.0) - 1.0;
+        let x = overshoot + half_knee;
+        slope * x * x / (2.0 * knee_db)
+    } else {
+        hard_knee(input_db, threshold_db, ratio)
+    }
+}
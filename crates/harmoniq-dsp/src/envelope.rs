@@ -0,0 +1,139 @@
+//! Level detection for dynamics and envelope-driven effects.
+
+/// What [`EnvelopeFollower`] tracks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DetectorMode {
+    /// Rectified sample magnitude.
+    #[default]
+    Peak,
+    /// Root mean square: squared samples are smoothed, then square-rooted.
+    Rms,
+}
+
+/// Attack/release envelope follower.
+///
+/// The envelope rises towards the detector input with the attack time
+/// constant and falls with the release time constant, so a step reaches
+/// `1 - 1/e` (about 63%) of its height after one time constant. The
+/// coefficients are recomputed only when a time or the sample rate changes.
+#[derive(Debug, Clone)]
+pub struct EnvelopeFollower {
+    attack_ms: f32,
+    release_ms: f32,
+    sample_rate: f32,
+    mode: DetectorMode,
+    attack_coeff: f32,
+    release_coeff: f32,
+    /// Smoothed magnitude, or smoothed square in RMS mode.
+    state: f32,
+}
+
+impl EnvelopeFollower {
+    pub fn new(attack_ms: f32, release_ms: f32) -> Self {
+        let mut follower = Self {
+            attack_ms: attack_ms.max(0.0),
+            release_ms: release_ms.max(0.0),
+            sample_rate: 48_000.0,
+            mode: DetectorMode::Peak,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            state: 0.0,
+        };
+        follower.update_coefficients();
+        follower
+    }
+
+    pub fn with_mode(mut self, mode: DetectorMode) -> Self {
+        self.set_mode(mode);
+        self
+    }
+
+    pub fn mode(&self) -> DetectorMode {
+        self.mode
+    }
+
+    /// Switches the detector. The envelope restarts from silence because
+    /// the two modes keep state in different domains.
+    pub fn set_mode(&mut self, mode: DetectorMode) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.reset();
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        let sample_rate = sample_rate.max(1.0);
+        if self.sample_rate != sample_rate {
+            self.sample_rate = sample_rate;
+            self.update_coefficients();
+        }
+    }
+
+    pub fn attack_ms(&self) -> f32 {
+        self.attack_ms
+    }
+
+    pub fn set_attack_ms(&mut self, attack_ms: f32) {
+        let attack_ms = attack_ms.max(0.0);
+        if self.attack_ms != attack_ms {
+            self.attack_ms = attack_ms;
+            self.attack_coeff = coefficient(attack_ms, self.sample_rate);
+        }
+    }
+
+    pub fn release_ms(&self) -> f32 {
+        self.release_ms
+    }
+
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        let release_ms = release_ms.max(0.0);
+        if self.release_ms != release_ms {
+            self.release_ms = release_ms;
+            self.release_coeff = coefficient(release_ms, self.sample_rate);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.state = 0.0;
+    }
+
+    /// Current envelope level.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        match self.mode {
+            DetectorMode::Peak => self.state,
+            DetectorMode::Rms => self.state.sqrt(),
+        }
+    }
+
+    #[inline]
+    pub fn process_sample(&mut self, x: f32) -> f32 {
+        let input = match self.mode {
+            DetectorMode::Peak => x.abs(),
+            DetectorMode::Rms => x * x,
+        };
+        let coeff = if input > self.state {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.state = input + coeff * (self.state - input);
+        self.value()
+    }
+
+    fn update_coefficients(&mut self) {
+        self.attack_coeff = coefficient(self.attack_ms, self.sample_rate);
+        self.release_coeff = coefficient(self.release_ms, self.sample_rate);
+    }
+}
+
+/// One-pole feedback coefficient for a time constant of `time_ms`. Zero
+/// means the envelope follows its input instantly.
+fn coefficient(time_ms: f32, sample_rate: f32) -> f32 {
+    let samples = time_ms * 0.001 * sample_rate;
+    if samples <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / samples).exp()
+    }
+}
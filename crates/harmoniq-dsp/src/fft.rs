@@ -0,0 +1,137 @@
+//! Radix-2 FFT.
+
+use core::f64::consts::PI;
+use core::ops::{Add, Mul, Sub};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const ZERO: Self = Self { re: 0.0, im: 0.0 };
+
+    #[inline]
+    pub const fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    #[inline]
+    pub fn norm(self) -> f32 {
+        self.re.hypot(self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+/// In-place iterative FFT of a fixed power-of-two size. Twiddles and the
+/// bit-reversal table are computed up front, so transforms do not allocate.
+#[derive(Clone, Debug)]
+pub struct Fft {
+    twiddles: Vec<Complex>,
+    reversed: Vec<u32>,
+}
+
+impl Fft {
+    /// Panics unless `size` is a power of two.
+    pub fn new(size: usize) -> Self {
+        assert!(
+            size.is_power_of_two(),
+            "FFT size {size} is not a power of two"
+        );
+        let bits = size.trailing_zeros();
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -2.0 * PI * k as f64 / size as f64;
+                Complex::new(angle.cos() as f32, angle.sin() as f32)
+            })
+            .collect();
+        let reversed = (0..size as u32)
+            .map(|index| index.reverse_bits().checked_shr(32 - bits).unwrap_or(0))
+            .collect();
+        Self { twiddles, reversed }
+    }
+
+    pub fn len(&self) -> usize {
+        self.reversed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reversed.is_empty()
+    }
+
+    /// Forward transform of `data`, which must hold exactly
+    /// [`len`](Self::len) values.
+    pub fn forward(&self, data: &mut [Complex]) {
+        self.transform(data, false);
+    }
+
+    /// Inverse transform, scaled by `1 / len` so it undoes
+    /// [`forward`](Self::forward).
+    pub fn inverse(&self, data: &mut [Complex]) {
+        self.transform(data, true);
+        let scale = 1.0 / self.len() as f32;
+        for value in data.iter_mut() {
+            value.re *= scale;
+            value.im *= scale;
+        }
+    }
+
+    fn transform(&self, data: &mut [Complex], inverse: bool) {
+        let size = self.len();
+        assert_eq!(data.len(), size, "FFT input length");
+        for (index, &reversed) in self.reversed.iter().enumerate() {
+            let reversed = reversed as usize;
+            if index < reversed {
+                data.swap(index, reversed);
+            }
+        }
+
+        let mut span = 1;
+        while span < size {
+            let stride = size / (2 * span);
+            for start in (0..size).step_by(2 * span) {
+                for offset in 0..span {
+                    let mut twiddle = self.twiddles[offset * stride];
+                    if inverse {
+                        twiddle.im = -twiddle.im;
+                    }
+                    let even = data[start + offset];
+                    let odd = data[start + offset + span] * twiddle;
+                    data[start + offset] = even + odd;
+                    data[start + offset + span] = even - odd;
+                }
+            }
+            span *= 2;
+        }
+    }
+}
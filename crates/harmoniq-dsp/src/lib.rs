@@ -1,13 +1,25 @@
 #![deny(unsafe_op_in_unsafe_fn)]
 #![cfg_attr(feature = "fast-math", allow(clippy::excessive_precision))]
 
+pub mod allpass;
 pub mod biquad;
+pub mod bitcrush;
 pub mod buffer;
+pub mod convolution;
+pub mod crossover;
 pub mod delay;
+pub mod dynamics;
+pub mod envelope;
+pub mod fft;
 pub mod gain;
+pub mod loudness;
+pub mod osc;
+pub mod oversample;
 pub mod pan;
+pub mod resample;
 pub mod saturator;
 pub mod smoothing;
+pub mod true_peak;
 pub mod utils;
 
 pub use buffer::{AudioBlock, AudioBlockMut, ChanMut, ChanRef};
@@ -0,0 +1,94 @@
+//! Loudness weighting after ITU-R BS.1770, shared by the offline analysis
+//! and the realtime meters.
+
+/// BS.1770 K-weighting: a high-shelf pre-filter followed by the RLB
+/// high-pass, with coefficients derived for the sample rate. State is kept
+/// in `f64` so long integrations stay accurate.
+#[derive(Clone, Debug)]
+pub struct KWeighting {
+    shelf: Stage,
+    highpass: Stage,
+}
+
+impl KWeighting {
+    pub fn new(sample_rate: f32) -> Self {
+        let sample_rate = (sample_rate as f64).max(1.0);
+
+        let k = (core::f64::consts::PI * 1_681.974_450_955_533 / sample_rate).tan();
+        let q = 0.707_175_236_955_419_6;
+        let vh = 10f64.powf(3.999_843_853_973_347 / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Stage {
+            b0: (vh + vb * k / q + k * k) / a0,
+            b1: 2.0 * (k * k - vh) / a0,
+            b2: (vh - vb * k / q + k * k) / a0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            z1: 0.0,
+            z2: 0.0,
+        };
+
+        let k = (core::f64::consts::PI * 38.135_470_876_024_44 / sample_rate).tan();
+        let q = 0.500_327_037_323_877_3;
+        let a0 = 1.0 + k / q + k * k;
+        let highpass = Stage {
+            b0: 1.0,
+            b1: -2.0,
+            b2: 1.0,
+            a1: 2.0 * (k * k - 1.0) / a0,
+            a2: (1.0 - k / q + k * k) / a0,
+            z1: 0.0,
+            z2: 0.0,
+        };
+
+        Self { shelf, highpass }
+    }
+
+    pub fn reset(&mut self) {
+        self.shelf.reset();
+        self.highpass.reset();
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f64) -> f64 {
+        self.highpass.process(self.shelf.process(input))
+    }
+}
+
+/// Loudness in LUFS of a K-weighted mean square, or negative infinity for
+/// silence.
+#[inline]
+pub fn lufs(mean_square: f64) -> f64 {
+    if mean_square > 0.0 {
+        -0.691 + 10.0 * mean_square.log10()
+    } else {
+        f64::NEG_INFINITY
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Stage {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Stage {
+    fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+
+    #[inline]
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+}
@@ -0,0 +1,242 @@
+use once_cell::sync::Lazy;
+
+/// Zero crossings of the windowed sinc on either side of the step.
+const ZERO_CROSSINGS: usize = 8;
+/// Table entries per output sample.
+const OVERSAMPLING: usize = 64;
+/// Length of the band-limited step correction in output samples.
+pub const MINBLEP_LENGTH: usize = ZERO_CROSSINGS * 2;
+
+static MINBLEP: Lazy<Vec<f32>> = Lazy::new(build_minblep);
+
+/// Minimum-phase band-limited step, sampled at `OVERSAMPLING` points per sample.
+///
+/// Built once following Brandt's method: a Blackman windowed sinc is turned
+/// minimum phase through its real cepstrum and then integrated into a step.
+fn build_minblep() -> Vec<f32> {
+    let n = MINBLEP_LENGTH * OVERSAMPLING;
+    let centre = n as f64 / 2.0;
+    let mut re: Vec<f64> = (0..n)
+        .map(|i| {
+            let x = (i as f64 - centre) / OVERSAMPLING as f64;
+            let sinc = if x == 0.0 {
+                1.0
+            } else {
+                let px = std::f64::consts::PI * x;
+                px.sin() / px
+            };
+            let w = 2.0 * std::f64::consts::PI * i as f64 / n as f64;
+            let blackman = 0.42 - 0.5 * w.cos() + 0.08 * (2.0 * w).cos();
+            sinc * blackman
+        })
+        .collect();
+    let mut im = vec![0.0f64; n];
+
+    // Real cepstrum.
+    dft(&mut re, &mut im, false);
+    for k in 0..n {
+        let magnitude = (re[k] * re[k] + im[k] * im[k]).sqrt().max(1e-12);
+        re[k] = magnitude.ln();
+        im[k] = 0.0;
+    }
+    dft(&mut re, &mut im, true);
+
+    // Fold the cepstrum onto positive quefrencies to obtain the minimum phase spectrum.
+    re[1..n / 2].iter_mut().for_each(|value| *value *= 2.0);
+    re[n / 2 + 1..].iter_mut().for_each(|value| *value = 0.0);
+    im.iter_mut().for_each(|value| *value = 0.0);
+    dft(&mut re, &mut im, false);
+    for k in 0..n {
+        let scale = re[k].exp();
+        let (sin, cos) = im[k].sin_cos();
+        re[k] = scale * cos;
+        im[k] = scale * sin;
+    }
+    dft(&mut re, &mut im, true);
+
+    let mut step = Vec::with_capacity(n + 1);
+    let mut acc = 0.0f64;
+    for value in &re {
+        acc += value;
+        step.push(acc);
+    }
+    let total = acc.max(f64::EPSILON);
+    let mut table: Vec<f32> = step.into_iter().map(|v| (v / total) as f32).collect();
+    table.push(1.0);
+    table
+}
+
+fn dft(re: &mut [f64], im: &mut [f64], inverse: bool) {
+    let n = re.len();
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let twiddles: Vec<(f64, f64)> = (0..n)
+        .map(|k| (sign * 2.0 * std::f64::consts::PI * k as f64 / n as f64).sin_cos())
+        .collect();
+    let mut out_re = vec![0.0f64; n];
+    let mut out_im = vec![0.0f64; n];
+    for k in 0..n {
+        let mut sum_re = 0.0;
+        let mut sum_im = 0.0;
+        for t in 0..n {
+            let (sin, cos) = twiddles[(k * t) % n];
+            sum_re += re[t] * cos - im[t] * sin;
+            sum_im += re[t] * sin + im[t] * cos;
+        }
+        out_re[k] = sum_re;
+        out_im[k] = sum_im;
+    }
+    let scale = if inverse { 1.0 / n as f64 } else { 1.0 };
+    for k in 0..n {
+        re[k] = out_re[k] * scale;
+        im[k] = out_im[k] * scale;
+    }
+}
+
+/// Band-limited step evaluated `elapsed` samples after the discontinuity.
+#[inline]
+fn minblep_at(elapsed: f32) -> f32 {
+    let table = &*MINBLEP;
+    let position = elapsed.max(0.0) * OVERSAMPLING as f32;
+    let index = position as usize;
+    if index + 1 >= table.len() {
+        return 1.0;
+    }
+    let frac = position - index as f32;
+    table[index] + (table[index + 1] - table[index]) * frac
+}
+
+/// Accumulates minBLEP residuals for discontinuities placed at sub-sample positions.
+#[derive(Clone, Debug)]
+pub struct MinBlepBuffer {
+    residual: [f32; MINBLEP_LENGTH],
+    position: usize,
+}
+
+impl Default for MinBlepBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MinBlepBuffer {
+    pub fn new() -> Self {
+        Lazy::force(&MINBLEP);
+        Self {
+            residual: [0.0; MINBLEP_LENGTH],
+            position: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.residual = [0.0; MINBLEP_LENGTH];
+        self.position = 0;
+    }
+
+    /// Registers a step of `height` that happened `elapsed` samples (0..1)
+    /// before the sample about to be read.
+    #[inline]
+    pub fn add_step(&mut self, elapsed: f32, height: f32) {
+        for k in 0..MINBLEP_LENGTH {
+            let slot = (self.position + k) % MINBLEP_LENGTH;
+            self.residual[slot] += height * (minblep_at(k as f32 + elapsed) - 1.0);
+        }
+    }
+
+    /// Returns the correction for the current sample and advances.
+    #[inline]
+    pub fn next_correction(&mut self) -> f32 {
+        let value = self.residual[self.position];
+        self.residual[self.position] = 0.0;
+        self.position = (self.position + 1) % MINBLEP_LENGTH;
+        value
+    }
+}
+
+/// Sawtooth oscillator with optional anti-aliased hard sync.
+///
+/// A hidden master oscillator runs at `frequency / sync_ratio`; every time it
+/// wraps the audible oscillator is reset, and both the natural wrap and the
+/// sync reset are corrected with a minBLEP so the discontinuities stay
+/// band-limited.
+#[derive(Clone, Debug)]
+pub struct SyncSaw {
+    phase: f32,
+    master_phase: f32,
+    sync_ratio: f32,
+    blep: MinBlepBuffer,
+}
+
+impl Default for SyncSaw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyncSaw {
+    pub fn new() -> Self {
+        Self {
+            phase: 0.0,
+            master_phase: 0.0,
+            sync_ratio: 1.0,
+            blep: MinBlepBuffer::new(),
+        }
+    }
+
+    /// Ratio between the synced oscillator and its master. `1.0` disables sync.
+    pub fn set_sync_ratio(&mut self, ratio: f32) {
+        self.sync_ratio = ratio.max(1.0);
+    }
+
+    pub fn sync_ratio(&self) -> f32 {
+        self.sync_ratio
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+        self.master_phase = 0.0;
+        self.blep.reset();
+    }
+
+    #[inline]
+    pub fn next(&mut self, freq: f32, sample_rate: f32) -> f32 {
+        let inc = (freq / sample_rate.max(1.0)).clamp(0.0, 0.5);
+        if inc <= 0.0 {
+            return 2.0 * self.phase - 1.0 + self.blep.next_correction();
+        }
+
+        let sync_elapsed = if self.sync_ratio > 1.0 {
+            let master_inc = inc / self.sync_ratio;
+            self.master_phase += master_inc;
+            if self.master_phase >= 1.0 {
+                self.master_phase -= 1.0;
+                Some((self.master_phase / master_inc).min(1.0))
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        match sync_elapsed {
+            Some(elapsed) => {
+                let mut before = self.phase + (1.0 - elapsed) * inc;
+                if before >= 1.0 {
+                    before -= 1.0;
+                    let wrapped_for = before / inc + elapsed;
+                    self.blep.add_step(wrapped_for.min(1.0), -2.0);
+                }
+                self.blep.add_step(elapsed, -2.0 * before);
+                self.phase = elapsed * inc;
+            }
+            None => {
+                self.phase += inc;
+                if self.phase >= 1.0 {
+                    self.phase -= 1.0;
+                    self.blep.add_step(self.phase / inc, -2.0);
+                }
+            }
+        }
+
+        2.0 * self.phase - 1.0 + self.blep.next_correction()
+    }
+}
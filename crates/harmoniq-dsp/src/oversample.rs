@@ -0,0 +1,232 @@
+//! Polyphase oversampling for nonlinear processing.
+//!
+//! [`Oversampler`] interpolates each channel by `FACTOR`, runs a per-sample
+//! closure at the higher rate and decimates back. Processors that work on
+//! whole blocks use [`Oversampler::upsample`] and
+//! [`Oversampler::downsample`] around their own processing instead. Both
+//! filters are
+//! symmetric windowed-sinc FIRs, so they are linear-phase and delay every
+//! frequency by the same [`Oversampler::LATENCY`] frames.
+//!
+//! Filter state is allocated per channel by [`Oversampler::prepare`], never
+//! while processing; channels beyond the prepared count are left untouched.
+
+use core::f64::consts::PI;
+
+use crate::buffer::AudioBlockMut;
+use crate::resample::blackman;
+
+/// Filter length in base-rate samples. The kernels have
+/// `TAPS_PER_FACTOR * FACTOR + 1` taps, so each filter delays by exactly
+/// half this many base-rate frames.
+const TAPS_PER_FACTOR: usize = 32;
+
+/// Passband edge as a fraction of the base-rate Nyquist frequency.
+const PASSBAND: f64 = 0.9;
+
+pub struct Oversampler<const FACTOR: usize> {
+    taps: Vec<f32>,
+    channels: Vec<ChannelState>,
+}
+
+#[derive(Clone)]
+struct ChannelState {
+    /// Base-rate input history for the interpolator.
+    input: Vec<f32>,
+    input_pos: usize,
+    /// Oversampled history for the decimator.
+    output: Vec<f32>,
+    output_pos: usize,
+}
+
+impl<const FACTOR: usize> Oversampler<FACTOR> {
+    /// Delay in base-rate frames added by the interpolator and decimator.
+    pub const LATENCY: usize = if FACTOR > 1 { TAPS_PER_FACTOR } else { 0 };
+
+    /// An oversampler prepared for stereo.
+    pub fn new() -> Self {
+        Self::with_channels(2)
+    }
+
+    pub fn with_channels(channels: usize) -> Self {
+        let mut oversampler = Self {
+            taps: lowpass(FACTOR.max(1)),
+            channels: Vec::new(),
+        };
+        oversampler.prepare(channels);
+        oversampler
+    }
+
+    /// Allocates cleared filter histories for `channels` channels.
+    pub fn prepare(&mut self, channels: usize) {
+        let state = ChannelState {
+            input: vec![0.0; TAPS_PER_FACTOR + 1],
+            input_pos: 0,
+            output: vec![0.0; self.taps.len()],
+            output_pos: 0,
+        };
+        self.channels = vec![state; channels];
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn factor(&self) -> usize {
+        FACTOR
+    }
+
+    pub fn latency(&self) -> usize {
+        Self::LATENCY
+    }
+
+    /// Clears the filter histories.
+    pub fn reset(&mut self) {
+        for state in &mut self.channels {
+            state.input.fill(0.0);
+            state.output.fill(0.0);
+        }
+    }
+
+    /// Replaces every sample of `block` with `shape` applied at `FACTOR`
+    /// times the block rate. The result is delayed by
+    /// [`LATENCY`](Self::LATENCY) frames.
+    pub fn process<F>(&mut self, block: &mut AudioBlockMut<'_>, mut shape: F)
+    where
+        F: FnMut(f32) -> f32,
+    {
+        let channels = block.channels() as usize;
+        let frames = block.frames() as usize;
+        if FACTOR <= 1 {
+            for ch in 0..channels {
+                let mut chan = unsafe { block.chan_mut(ch) };
+                for frame in 0..frames {
+                    let sample = unsafe { chan.read(frame) };
+                    unsafe { chan.write(frame, shape(sample)) };
+                }
+            }
+            return;
+        }
+
+        for (ch, state) in self.channels.iter_mut().enumerate().take(channels) {
+            let mut chan = unsafe { block.chan_mut(ch) };
+            for frame in 0..frames {
+                state.push_input(unsafe { chan.read(frame) });
+                let mut decimated = 0.0;
+                for phase in 0..FACTOR {
+                    let shaped = shape(state.interpolate::<FACTOR>(&self.taps, phase));
+                    state.push_output(shaped);
+                    // Decimate: only the first phase of each frame is kept.
+                    if phase == 0 {
+                        decimated = state.decimate(&self.taps);
+                    }
+                }
+                unsafe { chan.write(frame, decimated) };
+            }
+        }
+    }
+
+    /// Interpolates channel `ch` of a base-rate block into `output`, which
+    /// receives `FACTOR` samples per frame of `input`. Channels that were
+    /// not prepared, or a factor of 1, copy the input through.
+    pub fn upsample(&mut self, ch: usize, input: &[f32], output: &mut [f32]) {
+        let output = &mut output[..input.len() * FACTOR];
+        let Some(state) = self.channels.get_mut(ch).filter(|_| FACTOR > 1) else {
+            output.copy_from_slice(input);
+            return;
+        };
+        for (sample, frame) in input.iter().zip(output.chunks_exact_mut(FACTOR)) {
+            state.push_input(*sample);
+            for (phase, value) in frame.iter_mut().enumerate() {
+                *value = state.interpolate::<FACTOR>(&self.taps, phase);
+            }
+        }
+    }
+
+    /// Decimates channel `ch` of an oversampled block back into `output`,
+    /// one sample per `FACTOR` samples of `input`. Together with
+    /// [`upsample`](Self::upsample) the signal is delayed by
+    /// [`LATENCY`](Self::LATENCY) frames.
+    pub fn downsample(&mut self, ch: usize, input: &[f32], output: &mut [f32]) {
+        let frames = output.len().min(input.len() / FACTOR.max(1));
+        let Some(state) = self.channels.get_mut(ch).filter(|_| FACTOR > 1) else {
+            output[..frames].copy_from_slice(&input[..frames]);
+            return;
+        };
+        for (sample, frame) in output.iter_mut().zip(input.chunks_exact(FACTOR)) {
+            for (phase, value) in frame.iter().enumerate() {
+                state.push_output(*value);
+                if phase == 0 {
+                    *sample = state.decimate(&self.taps);
+                }
+            }
+        }
+    }
+}
+
+impl ChannelState {
+    #[inline]
+    fn push_input(&mut self, sample: f32) {
+        self.input_pos = (self.input_pos + 1) % self.input.len();
+        self.input[self.input_pos] = sample;
+    }
+
+    /// Oversampled value at `phase` of the newest input frame. Only every
+    /// `FACTOR`-th tap meets a non-zero sample of the zero-stuffed input.
+    #[inline]
+    fn interpolate<const FACTOR: usize>(&self, taps: &[f32], phase: usize) -> f32 {
+        let len = self.input.len();
+        let mut value = 0.0;
+        let mut history = self.input_pos;
+        for tap in taps.iter().skip(phase).step_by(FACTOR) {
+            value += tap * self.input[history];
+            history = (history + len - 1) % len;
+        }
+        value * FACTOR as f32
+    }
+
+    #[inline]
+    fn push_output(&mut self, sample: f32) {
+        self.output_pos = (self.output_pos + 1) % self.output.len();
+        self.output[self.output_pos] = sample;
+    }
+
+    #[inline]
+    fn decimate(&self, taps: &[f32]) -> f32 {
+        let len = self.output.len();
+        let mut value = 0.0;
+        let mut history = self.output_pos;
+        for tap in taps {
+            value += tap * self.output[history];
+            history = (history + len - 1) % len;
+        }
+        value
+    }
+}
+
+impl<const FACTOR: usize> Default for Oversampler<FACTOR> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Blackman-windowed sinc lowpass at the oversampled rate, normalised to
+/// unity DC gain.
+fn lowpass(factor: usize) -> Vec<f32> {
+    let len = TAPS_PER_FACTOR * factor + 1;
+    let centre = (len - 1) as f64 / 2.0;
+    let cutoff = PASSBAND * 0.5 / factor as f64;
+    let taps: Vec<f64> = (0..len)
+        .map(|index| {
+            let x = index as f64 - centre;
+            let sinc = if x == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * PI * cutoff * x).sin() / (PI * x)
+            };
+            sinc * blackman(index as f64 / (len - 1) as f64)
+        })
+        .collect();
+    let sum: f64 = taps.iter().sum();
+    taps.into_iter().map(|tap| (tap / sum) as f32).collect()
+}
@@ -0,0 +1,434 @@
+//! Streaming sample-rate conversion.
+//!
+//! [`Resampler`] converts a mono stream between two rates with a selectable
+//! interpolator. Input that the interpolator cannot use yet, because it
+//! needs samples past the end of the block, stays buffered until the next
+//! call, so splitting a stream into blocks produces exactly the same output
+//! as processing it in one go. The buffer is sized on construction and
+//! never grows: input it cannot take while the output is full is left to
+//! the caller, who passes it again on the next call.
+
+use core::f64::consts::PI;
+
+/// Fractional positions tabulated per sinc kernel.
+const SINC_PHASES: usize = 256;
+/// Input staged beyond the interpolation history on each refill.
+const STAGING: usize = 1024;
+
+/// Interpolator used by [`Resampler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InterpolationKind {
+    /// Two-point linear interpolation. Cheap, but aliases audibly.
+    #[default]
+    Linear,
+    /// Four-point Catmull-Rom spline.
+    Cubic,
+    /// Blackman-windowed sinc with `taps` points, rounded up to an even
+    /// count of at least 4. The cutoff follows the lower of the two rates.
+    Sinc { taps: usize },
+}
+
+impl InterpolationKind {
+    /// Samples needed on each side of the interpolated position.
+    fn half_width(self) -> usize {
+        match self {
+            Self::Linear => 1,
+            Self::Cubic => 2,
+            Self::Sinc { taps } => taps.max(4).div_ceil(2),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    kind: InterpolationKind,
+    /// Input samples advanced per output sample, split into whole and
+    /// fractional parts so the phase accumulates identically no matter how
+    /// the stream is divided into blocks.
+    step_whole: usize,
+    step_frac: f64,
+    half: usize,
+    /// Read position in `buffer`: `index + frac`.
+    index: usize,
+    frac: f64,
+    /// History before the read position followed by staged input, valid up
+    /// to `len`.
+    buffer: Box<[f32]>,
+    len: usize,
+    /// `SINC_PHASES + 1` rows of `2 * half` weights.
+    kernel: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn with_kind(input_rate: f32, output_rate: f32, kind: InterpolationKind) -> Self {
+        let step = input_rate.max(1.0) as f64 / output_rate.max(1.0) as f64;
+        let half = kind.half_width();
+        let kernel = match kind {
+            InterpolationKind::Sinc { .. } => sinc_kernel(half, step),
+            _ => Vec::new(),
+        };
+        let step_whole = step.trunc() as usize;
+        // Room for a full window past the furthest the read position can
+        // land after compaction, plus the staged input.
+        let capacity = 2 * half + step_whole + 1 + STAGING;
+        let mut resampler = Self {
+            kind,
+            step_whole,
+            step_frac: step.fract(),
+            half,
+            index: 0,
+            frac: 0.0,
+            buffer: vec![0.0; capacity].into_boxed_slice(),
+            len: 0,
+            kernel,
+        };
+        resampler.reset();
+        resampler
+    }
+
+    pub fn kind(&self) -> InterpolationKind {
+        self.kind
+    }
+
+    /// Output rate divided by input rate.
+    pub fn ratio(&self) -> f64 {
+        1.0 / (self.step_whole as f64 + self.step_frac)
+    }
+
+    /// Clears the phase and the buffered history.
+    pub fn reset(&mut self) {
+        // Silence before the stream lets the first output land exactly on
+        // the first input sample.
+        self.len = self.half - 1;
+        self.buffer[..self.len].fill(0.0);
+        self.index = self.half - 1;
+        self.frac = 0.0;
+    }
+
+    /// Input samples held back waiting for more lookahead or output space.
+    pub fn pending(&self) -> usize {
+        self.len.saturating_sub(self.index)
+    }
+
+    /// Appends `input` to the stream and writes as many output samples as
+    /// are ready, up to `output.len()`. Returns how many input samples were
+    /// taken and how many output samples were written; once `output` is
+    /// full, the rest of `input` is left for the next call.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> (usize, usize) {
+        let mut consumed = 0;
+        let mut written = 0;
+        let capacity = output.len();
+        loop {
+            let take = (self.buffer.len() - self.len).min(input.len() - consumed);
+            self.buffer[self.len..self.len + take]
+                .copy_from_slice(&input[consumed..consumed + take]);
+            self.len += take;
+            consumed += take;
+
+            let output = &mut output[written..];
+            written += match self.kind {
+                InterpolationKind::Linear => self.run_linear(output),
+                InterpolationKind::Cubic => self.run(output, cubic),
+                InterpolationKind::Sinc { .. } => self.run_sinc(output),
+            };
+            self.compact();
+            if written == capacity || consumed == input.len() {
+                break;
+            }
+        }
+        (consumed, written)
+    }
+
+    /// Drops everything before the history the next interpolation window
+    /// reaches back to.
+    fn compact(&mut self) {
+        let start = self.index.saturating_sub(self.half - 1).min(self.len);
+        self.buffer.copy_within(start..self.len, 0);
+        self.len -= start;
+        self.index -= start;
+    }
+
+    #[inline]
+    fn advance(&mut self) {
+        (self.index, self.frac) = self.step_from(self.index, self.frac);
+    }
+
+    /// The read position one output sample after `index + frac`.
+    #[inline]
+    fn step_from(&self, index: usize, frac: f64) -> (usize, f64) {
+        let mut index = index + self.step_whole;
+        let mut frac = frac + self.step_frac;
+        if frac >= 1.0 {
+            frac -= 1.0;
+            index += 1;
+        }
+        (index, frac)
+    }
+
+    fn run(&mut self, output: &mut [f32], interpolate: fn(&[f32], f32) -> f32) -> usize {
+        let half = self.half;
+        let mut written = 0;
+        for sample in output.iter_mut() {
+            let index = self.index;
+            if index + half >= self.len {
+                break;
+            }
+            let window = &self.buffer[index + 1 - half..=index + half];
+            *sample = interpolate(window, self.frac as f32);
+            self.advance();
+            written += 1;
+        }
+        written
+    }
+
+    /// The linear interpolator, eight outputs at a time with the `simd`
+    /// feature. Lanes use the same arithmetic as [`linear`], so the result
+    /// does not depend on how the block splits between the two paths.
+    fn run_linear(&mut self, output: &mut [f32]) -> usize {
+        #[allow(unused_mut)]
+        let mut written = 0;
+        #[cfg(feature = "simd")]
+        {
+            use crate::simd::{F32x8, Simd};
+
+            while written + 8 <= output.len() {
+                let (mut index, mut frac) = (self.index, self.frac);
+                let mut lower = [0u32; 8];
+                let mut fracs = [0.0f32; 8];
+                for lane in 0..8 {
+                    lower[lane] = index as u32;
+                    fracs[lane] = frac as f32;
+                    (index, frac) = self.step_from(index, frac);
+                }
+                if lower[7] as usize + 1 >= self.len {
+                    break;
+                }
+                let table = &self.buffer[..self.len];
+                let y0 = F32x8::gather(table, Simd(lower));
+                let y1 = F32x8::gather(table, Simd(lower.map(|index| index + 1)));
+                let value = y0 + (y1 - y0) * F32x8::from_array(fracs);
+                value.write_to_slice(&mut output[written..written + 8]);
+                self.index = index;
+                self.frac = frac;
+                written += 8;
+            }
+        }
+        written + self.run(&mut output[written..], linear)
+    }
+
+    /// The sinc interpolator, eight outputs at a time with the `simd`
+    /// feature: each lane gathers its own window and kernel rows tap by
+    /// tap. Lanes accumulate in the same order as [`sinc_dot`], so the
+    /// result does not depend on how the block splits between the paths.
+    fn run_sinc(&mut self, output: &mut [f32]) -> usize {
+        let half = self.half;
+        let width = 2 * half;
+        #[allow(unused_mut)]
+        let mut written = 0;
+        #[cfg(feature = "simd")]
+        {
+            use crate::simd::{F32x8, Simd};
+
+            while written + 8 <= output.len() {
+                let (mut index, mut frac) = (self.index, self.frac);
+                let mut last = index;
+                let mut starts = [0u32; 8];
+                let mut rows = [0u32; 8];
+                let mut blends = [0.0f32; 8];
+                for lane in 0..8 {
+                    let (row, blend) = sinc_phase(frac);
+                    starts[lane] = (index + 1 - half) as u32;
+                    rows[lane] = (row * width) as u32;
+                    blends[lane] = blend;
+                    last = index;
+                    (index, frac) = self.step_from(index, frac);
+                }
+                if last + half >= self.len {
+                    break;
+                }
+                let buffer = &self.buffer[..self.len];
+                let blend = F32x8::from_array(blends);
+                let mut sum = F32x8::splat(0.0);
+                for tap in 0..width as u32 {
+                    let x = F32x8::gather(buffer, Simd(starts.map(|start| start + tap)));
+                    let a = F32x8::gather(&self.kernel, Simd(rows.map(|row| row + tap)));
+                    let b =
+                        F32x8::gather(&self.kernel, Simd(rows.map(|row| row + width as u32 + tap)));
+                    sum += x * (a + (b - a) * blend);
+                }
+                sum.write_to_slice(&mut output[written..written + 8]);
+                self.index = index;
+                self.frac = frac;
+                written += 8;
+            }
+        }
+        for sample in output[written..].iter_mut() {
+            let index = self.index;
+            if index + half >= self.len {
+                break;
+            }
+            let (row, blend) = sinc_phase(self.frac);
+            let lower = &self.kernel[row * width..(row + 1) * width];
+            let upper = &self.kernel[(row + 1) * width..(row + 2) * width];
+            let window = &self.buffer[index + 1 - half..=index + half];
+            *sample = sinc_dot(window, lower, upper, blend);
+            self.advance();
+            written += 1;
+        }
+        written
+    }
+}
+
+/// Linear interpolator kept for callers that only need the cheap path.
+#[derive(Debug, Clone)]
+pub struct LinearResampler {
+    inner: Resampler,
+}
+
+impl LinearResampler {
+    pub fn new(input_rate: f32, output_rate: f32) -> Self {
+        Self {
+            inner: Resampler::with_kind(input_rate, output_rate, InterpolationKind::Linear),
+        }
+    }
+
+    pub fn ratio(&self) -> f64 {
+        self.inner.ratio()
+    }
+
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> (usize, usize) {
+        self.inner.process(input, output)
+    }
+}
+
+/// Resamples planar multi-channel audio with one independent phase and
+/// history per channel.
+#[derive(Debug, Clone)]
+pub struct MultiChannelResampler {
+    channels: Vec<Resampler>,
+}
+
+impl MultiChannelResampler {
+    pub fn new(channels: usize, input_rate: f32, output_rate: f32) -> Self {
+        Self::with_kind(
+            channels,
+            input_rate,
+            output_rate,
+            InterpolationKind::default(),
+        )
+    }
+
+    pub fn with_kind(
+        channels: usize,
+        input_rate: f32,
+        output_rate: f32,
+        kind: InterpolationKind,
+    ) -> Self {
+        Self {
+            channels: vec![Resampler::with_kind(input_rate, output_rate, kind); channels],
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Clears the phase and history of every channel, e.g. when a loop
+    /// wraps, so no state carries into the next pass.
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.reset();
+        }
+    }
+
+    /// Resamples one block per channel and returns the frames taken from
+    /// each input and written to each output, as [`Resampler::process`]
+    /// does. Every channel sees the same input length and output capacity,
+    /// so they stay in phase: uneven inputs or outputs are processed up to
+    /// the shortest one, and channels without both an input and an output
+    /// are left untouched.
+    pub fn process_planar(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) -> (usize, usize) {
+        let input_len = inputs.iter().map(|input| input.len()).min().unwrap_or(0);
+        let output_len = outputs.iter().map(|output| output.len()).min().unwrap_or(0);
+
+        let mut frames = (0, 0);
+        for ((resampler, input), output) in self.channels.iter_mut().zip(inputs).zip(outputs) {
+            frames = resampler.process(&input[..input_len], &mut output[..output_len]);
+        }
+        frames
+    }
+}
+
+#[inline]
+fn linear(window: &[f32], t: f32) -> f32 {
+    window[0] + (window[1] - window[0]) * t
+}
+
+#[inline]
+fn cubic(window: &[f32], t: f32) -> f32 {
+    let [y0, y1, y2, y3] = [window[0], window[1], window[2], window[3]];
+    let a = -0.5 * y0 + 1.5 * y1 - 1.5 * y2 + 0.5 * y3;
+    let b = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+    let c = -0.5 * y0 + 0.5 * y2;
+    ((a * t + b) * t + c) * t + y1
+}
+
+/// Kernel row below the fractional position `frac` and the blend towards
+/// the row above it.
+#[inline]
+fn sinc_phase(frac: f64) -> (usize, f32) {
+    let phase = frac * SINC_PHASES as f64;
+    let row = (phase as usize).min(SINC_PHASES - 1);
+    (row, (phase - row as f64) as f32)
+}
+
+/// Dot product of `window` with the kernel rows `lower` and `upper`
+/// blended by `blend`.
+#[inline]
+fn sinc_dot(window: &[f32], lower: &[f32], upper: &[f32], blend: f32) -> f32 {
+    let mut value = 0.0;
+    for ((x, a), b) in window.iter().zip(lower).zip(upper) {
+        value += x * (a + (b - a) * blend);
+    }
+    value
+}
+
+/// Tabulates the windowed-sinc weights for every fractional phase. Row `p`
+/// holds the weights for the window starting `half - 1` samples before the
+/// read position at fraction `p / SINC_PHASES`, normalised to unity gain.
+fn sinc_kernel(half: usize, step: f64) -> Vec<f32> {
+    let width = 2 * half;
+    let cutoff = (1.0 / step).min(1.0);
+    let mut kernel = Vec::with_capacity((SINC_PHASES + 1) * width);
+    for row in 0..=SINC_PHASES {
+        let frac = row as f64 / SINC_PHASES as f64;
+        let weights: Vec<f64> = (0..width)
+            .map(|tap| {
+                let x = tap as f64 - (half - 1) as f64 - frac;
+                let sinc = if x.abs() < 1e-12 {
+                    1.0
+                } else {
+                    (PI * cutoff * x).sin() / (PI * cutoff * x)
+                };
+                sinc * blackman((x + half as f64) / width as f64)
+            })
+            .collect();
+        let sum: f64 = weights.iter().sum();
+        kernel.extend(weights.iter().map(|weight| (weight / sum) as f32));
+    }
+    kernel
+}
+
+/// Blackman window at `position` in `0..=1` across the window.
+#[inline]
+pub(crate) fn blackman(position: f64) -> f64 {
+    0.42 - 0.5 * (2.0 * PI * position).cos() + 0.08 * (4.0 * PI * position).cos()
+}
@@ -1,6 +1,95 @@
+use crate::buffer::AudioBlockMut;
+use crate::oversample::Oversampler;
+
 #[inline]
 pub fn soft_clip(sample: f32) -> f32 {
     let x = sample;
     let a = x.abs();
     (x * (27.0 + a * a)) / (27.0 + 9.0 * a * a)
 }
+
+enum Oversampling {
+    Off,
+    X2(Oversampler<2>),
+    X4(Oversampler<4>),
+}
+
+/// Drive into [`soft_clip`], optionally oversampled to keep the added
+/// harmonics from folding back below Nyquist.
+pub struct Saturator {
+    pub drive: f32,
+    oversampling: Oversampling,
+}
+
+impl Saturator {
+    pub fn new(drive: f32) -> Self {
+        Self {
+            drive,
+            oversampling: Oversampling::Off,
+        }
+    }
+
+    /// Runs the saturation at `factor` times the block rate. Factors of 1 or
+    /// less disable oversampling, 2 selects 2x and anything higher 4x.
+    pub fn with_oversampling(mut self, factor: usize) -> Self {
+        self.oversampling = match factor {
+            0 | 1 => Oversampling::Off,
+            2 => Oversampling::X2(Oversampler::new()),
+            _ => Oversampling::X4(Oversampler::new()),
+        };
+        self
+    }
+
+    pub fn oversampling_factor(&self) -> usize {
+        match &self.oversampling {
+            Oversampling::Off => 1,
+            Oversampling::X2(oversampler) => oversampler.factor(),
+            Oversampling::X4(oversampler) => oversampler.factor(),
+        }
+    }
+
+    /// Delay in frames added by the oversampling filters.
+    pub fn latency(&self) -> usize {
+        match &self.oversampling {
+            Oversampling::Off => 0,
+            Oversampling::X2(oversampler) => oversampler.latency(),
+            Oversampling::X4(oversampler) => oversampler.latency(),
+        }
+    }
+
+    /// Sizes the oversampling filters for `channels` channels; see
+    /// [`Oversampler::prepare`].
+    pub fn prepare(&mut self, channels: usize) {
+        match &mut self.oversampling {
+            Oversampling::Off => {}
+            Oversampling::X2(oversampler) => oversampler.prepare(channels),
+            Oversampling::X4(oversampler) => oversampler.prepare(channels),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match &mut self.oversampling {
+            Oversampling::Off => {}
+            Oversampling::X2(oversampler) => oversampler.reset(),
+            Oversampling::X4(oversampler) => oversampler.reset(),
+        }
+    }
+
+    pub fn process_block(&mut self, block: &mut AudioBlockMut<'_>) {
+        let drive = self.drive;
+        let shape = |sample: f32| soft_clip(sample * drive);
+        match &mut self.oversampling {
+            Oversampling::Off => {
+                for ch in 0..block.channels() as usize {
+                    let mut chan = unsafe { block.chan_mut(ch) };
+                    for frame in 0..chan.frames() {
+                        let sample = unsafe { chan.read(frame) };
+                        unsafe { chan.write(frame, shape(sample)) };
+                    }
+                }
+            }
+            Oversampling::X2(oversampler) => oversampler.process(block, shape),
+            Oversampling::X4(oversampler) => oversampler.process(block, shape),
+        }
+    }
+}
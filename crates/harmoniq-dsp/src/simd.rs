@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use core::ops::{Add, AddAssign, Div, Mul, Sub};
 use core::slice;
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
@@ -311,3 +312,495 @@ unsafe fn mul_buffers_in_place_neon(buffer: &mut [f32], rhs: &[f32]) {
         index += 1;
     }
 }
+
+/// Fixed-width vector of `LANES` values.
+///
+/// Every operation is a per-lane loop over the backing array, which the
+/// compiler vectorises for the common widths. The eight-lane `f32` case is
+/// additionally lowered onto AVX: with the `std` feature when the CPU reports
+/// it at runtime, otherwise when the build enables the target features.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(transparent)]
+pub struct Simd<T, const LANES: usize>(pub [T; LANES]);
+
+pub type F32x4 = Simd<f32, 4>;
+pub type F32x8 = Simd<f32, 8>;
+
+impl<T: Copy, const LANES: usize> Simd<T, LANES> {
+    #[inline]
+    pub fn splat(value: T) -> Self {
+        Self([value; LANES])
+    }
+
+    #[inline]
+    pub fn from_array(lanes: [T; LANES]) -> Self {
+        Self(lanes)
+    }
+
+    #[inline]
+    pub fn to_array(self) -> [T; LANES] {
+        self.0
+    }
+
+    /// Loads the first `LANES` values of `slice`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slice` is shorter than `LANES`.
+    #[inline]
+    pub fn from_slice(slice: &[T]) -> Self {
+        assert!(slice.len() >= LANES, "slice shorter than {LANES} lanes");
+        Self(core::array::from_fn(|lane| slice[lane]))
+    }
+
+    /// Stores the lanes into the first `LANES` values of `slice`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slice` is shorter than `LANES`.
+    #[inline]
+    pub fn write_to_slice(self, slice: &mut [T]) {
+        slice[..LANES].copy_from_slice(&self.0);
+    }
+
+    #[inline]
+    fn map(self, f: impl Fn(T) -> T) -> Self {
+        Self(self.0.map(f))
+    }
+
+    #[inline]
+    fn zip(self, other: Self, f: impl Fn(T, T) -> T) -> Self {
+        Self(core::array::from_fn(|lane| f(self.0[lane], other.0[lane])))
+    }
+}
+
+impl<T: Copy, const LANES: usize> Simd<T, LANES> {
+    /// Loads the lanes whose `mask` entry is set from the start of `slice`
+    /// and takes the rest from `or`. Lanes past the end of `slice` count as
+    /// masked off, so a short tail can be loaded without padding.
+    #[inline]
+    pub fn masked_load(slice: &[T], mask: [bool; LANES], or: Self) -> Self {
+        Self(core::array::from_fn(|lane| match slice.get(lane) {
+            Some(value) if mask[lane] => *value,
+            _ => or.0[lane],
+        }))
+    }
+
+    /// Stores the lanes whose `mask` entry is set into the start of
+    /// `slice`, skipping lanes past its end.
+    #[inline]
+    pub fn masked_store(self, slice: &mut [T], mask: [bool; LANES]) {
+        for (lane, dst) in slice.iter_mut().take(LANES).enumerate() {
+            if mask[lane] {
+                *dst = self.0[lane];
+            }
+        }
+    }
+
+    /// Writes lane `i` to `table[indices[i]]`. Later lanes win when two
+    /// indices collide.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any index is out of bounds.
+    #[inline]
+    pub fn scatter(self, table: &mut [T], indices: Simd<u32, LANES>) {
+        for (value, index) in self.0.iter().zip(indices.0) {
+            table[index as usize] = *value;
+        }
+    }
+}
+
+impl<T: Copy + Default, const LANES: usize> Default for Simd<T, LANES> {
+    fn default() -> Self {
+        Self::splat(T::default())
+    }
+}
+
+impl<T: Copy + Add<Output = T>, const LANES: usize> Add for Simd<T, LANES> {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        self.zip(rhs, |a, b| a + b)
+    }
+}
+
+impl<T: Copy + Add<Output = T>, const LANES: usize> AddAssign for Simd<T, LANES> {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Copy + Sub<Output = T>, const LANES: usize> Sub for Simd<T, LANES> {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        self.zip(rhs, |a, b| a - b)
+    }
+}
+
+impl<T: Copy + Mul<Output = T>, const LANES: usize> Mul for Simd<T, LANES> {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        self.zip(rhs, |a, b| a * b)
+    }
+}
+
+impl<const LANES: usize> Div for Simd<f32, LANES> {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: Self) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let (Some(a), Some(b)) = (self.as_x8(), rhs.as_x8()) {
+                if let Some(div) = avx::KERNELS.div {
+                    return Self::from_x8(unsafe { div(a, b) });
+                }
+            }
+        }
+        self.zip(rhs, |a, b| a / b)
+    }
+}
+
+impl<const LANES: usize> Simd<f32, LANES> {
+    /// Loads `table[indices[i]]` into lane `i`, e.g. for wavetable lookup.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any index is out of bounds.
+    #[inline]
+    pub fn gather(table: &[f32], indices: Simd<u32, LANES>) -> Self {
+        let max = indices.0.iter().copied().max().unwrap_or(0);
+        assert!(
+            LANES == 0 || (max as usize) < table.len(),
+            "gather index {max} out of bounds for table of {}",
+            table.len()
+        );
+        unsafe { Self::gather_unchecked(table, indices) }
+    }
+
+    /// [`gather`](Self::gather) without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// Every index must be less than `table.len()`.
+    #[inline]
+    pub unsafe fn gather_unchecked(table: &[f32], indices: Simd<u32, LANES>) -> Self {
+        debug_assert!(indices
+            .0
+            .iter()
+            .all(|index| (*index as usize) < table.len()));
+        #[cfg(target_arch = "x86_64")]
+        {
+            // The AVX2 gather takes signed 32-bit offsets.
+            if let Ok(index) = <&[u32; 8]>::try_from(indices.0.as_slice()) {
+                if table.len() <= i32::MAX as usize {
+                    if let Some(gather) = avx::KERNELS.gather {
+                        return Self::from_x8(unsafe { gather(table, index) });
+                    }
+                }
+            }
+        }
+        Self(
+            indices
+                .0
+                .map(|index| unsafe { *table.get_unchecked(index as usize) }),
+        )
+    }
+}
+
+impl<const LANES: usize> Simd<f32, LANES> {
+    /// The lanes as an eight-wide array, if this is an eight-lane vector.
+    #[cfg(target_arch = "x86_64")]
+    #[inline]
+    fn as_x8(&self) -> Option<&[f32; 8]> {
+        self.0.as_slice().try_into().ok()
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    /// Only called once [`as_x8`](Self::as_x8) has matched, so `LANES` is 8.
+    #[inline]
+    fn from_x8(lanes: [f32; 8]) -> Self {
+        debug_assert_eq!(LANES, 8);
+        unsafe { core::mem::transmute_copy(&lanes) }
+    }
+}
+
+/// Floating-point lane operations beyond the arithmetic operators.
+pub trait SimdFloat: Copy {
+    type Scalar;
+
+    /// `self * a + b` per lane, rounded once.
+    fn mul_add(self, a: Self, b: Self) -> Self;
+
+    /// Lane-wise minimum. Lanes holding NaN give an unspecified result.
+    fn min(self, other: Self) -> Self;
+
+    /// Lane-wise maximum. Lanes holding NaN give an unspecified result.
+    fn max(self, other: Self) -> Self;
+
+    fn abs(self) -> Self;
+
+    fn sqrt(self) -> Self;
+
+    /// Sum of all lanes. The summation order is unspecified, so the result
+    /// may differ from a sequential sum in the last bits.
+    fn reduce_sum(self) -> Self::Scalar;
+
+    #[inline]
+    fn clamp(self, min: Self, max: Self) -> Self {
+        self.max(min).min(max)
+    }
+}
+
+impl<const LANES: usize> SimdFloat for Simd<f32, LANES> {
+    type Scalar = f32;
+
+    #[inline]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let (Some(x), Some(y), Some(z)) = (self.as_x8(), a.as_x8(), b.as_x8()) {
+                // Without FMA the product would be rounded twice, so only
+                // the fused instruction replaces `f32::mul_add`.
+                if let Some(mul_add) = avx::KERNELS.mul_add {
+                    return Self::from_x8(unsafe { mul_add(x, y, z) });
+                }
+            }
+        }
+        Self(core::array::from_fn(|lane| {
+            self.0[lane].mul_add(a.0[lane], b.0[lane])
+        }))
+    }
+
+    #[inline]
+    fn min(self, other: Self) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let (Some(a), Some(b)) = (self.as_x8(), other.as_x8()) {
+                if let Some(min) = avx::KERNELS.min {
+                    return Self::from_x8(unsafe { min(a, b) });
+                }
+            }
+        }
+        self.zip(other, f32::min)
+    }
+
+    #[inline]
+    fn max(self, other: Self) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let (Some(a), Some(b)) = (self.as_x8(), other.as_x8()) {
+                if let Some(max) = avx::KERNELS.max {
+                    return Self::from_x8(unsafe { max(a, b) });
+                }
+            }
+        }
+        self.zip(other, f32::max)
+    }
+
+    #[inline]
+    fn abs(self) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let Some(a) = self.as_x8() {
+                if let Some(abs) = avx::KERNELS.abs {
+                    return Self::from_x8(unsafe { abs(a) });
+                }
+            }
+        }
+        self.map(f32::abs)
+    }
+
+    #[inline]
+    fn sqrt(self) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let Some(a) = self.as_x8() {
+                if let Some(sqrt) = avx::KERNELS.sqrt {
+                    return Self::from_x8(unsafe { sqrt(a) });
+                }
+            }
+        }
+        self.map(f32::sqrt)
+    }
+
+    #[inline]
+    fn reduce_sum(self) -> f32 {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let Some(a) = self.as_x8() {
+                if let Some(reduce_sum) = avx::KERNELS.reduce_sum {
+                    return unsafe { reduce_sum(a) };
+                }
+            }
+        }
+        self.0.iter().sum()
+    }
+}
+
+/// Writes `lhs * rhs + addend` into `output`, eight lanes at a time.
+#[inline]
+pub fn mul_add_to(output: &mut [f32], lhs: &[f32], rhs: &[f32], addend: &[f32]) {
+    assert_eq!(output.len(), lhs.len());
+    assert_eq!(lhs.len(), rhs.len());
+    assert_eq!(rhs.len(), addend.len());
+
+    let mut index = 0usize;
+    let len = output.len();
+    while index + 8 <= len {
+        let value = F32x8::from_slice(&lhs[index..]).mul_add(
+            F32x8::from_slice(&rhs[index..]),
+            F32x8::from_slice(&addend[index..]),
+        );
+        value.write_to_slice(&mut output[index..]);
+        index += 8;
+    }
+    while index < len {
+        output[index] = lhs[index].mul_add(rhs[index], addend[index]);
+        index += 1;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx {
+    use core::arch::x86_64::*;
+
+    type Unary = unsafe fn(&[f32; 8]) -> [f32; 8];
+    type Binary = unsafe fn(&[f32; 8], &[f32; 8]) -> [f32; 8];
+    type Ternary = unsafe fn(&[f32; 8], &[f32; 8], &[f32; 8]) -> [f32; 8];
+    type Gather = unsafe fn(&[f32], &[u32; 8]) -> [f32; 8];
+    type Reduce = unsafe fn(&[f32; 8]) -> f32;
+
+    /// The lowerings the CPU supports, picked once so an operation costs a
+    /// load and an indirect call rather than a feature query.
+    pub(super) struct Kernels {
+        pub(super) mul_add: Option<Ternary>,
+        pub(super) gather: Option<Gather>,
+        pub(super) div: Option<Binary>,
+        pub(super) min: Option<Binary>,
+        pub(super) max: Option<Binary>,
+        pub(super) abs: Option<Unary>,
+        pub(super) sqrt: Option<Unary>,
+        pub(super) reduce_sum: Option<Reduce>,
+    }
+
+    impl Kernels {
+        const fn new(avx: bool, avx2: bool, fma: bool) -> Self {
+            if !avx {
+                return Self {
+                    mul_add: None,
+                    gather: None,
+                    div: None,
+                    min: None,
+                    max: None,
+                    abs: None,
+                    sqrt: None,
+                    reduce_sum: None,
+                };
+            }
+            Self {
+                mul_add: if fma { Some(mul_add as Ternary) } else { None },
+                gather: if avx2 { Some(gather as Gather) } else { None },
+                div: Some(div as Binary),
+                min: Some(min as Binary),
+                max: Some(max as Binary),
+                abs: Some(abs as Unary),
+                sqrt: Some(sqrt as Unary),
+                reduce_sum: Some(reduce_sum as Reduce),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(super) static KERNELS: once_cell::sync::Lazy<Kernels> = once_cell::sync::Lazy::new(|| {
+        Kernels::new(
+            std::arch::is_x86_feature_detected!("avx"),
+            std::arch::is_x86_feature_detected!("avx2"),
+            std::arch::is_x86_feature_detected!("fma"),
+        )
+    });
+
+    #[cfg(not(feature = "std"))]
+    pub(super) static KERNELS: Kernels = Kernels::new(
+        cfg!(target_feature = "avx"),
+        cfg!(target_feature = "avx2"),
+        cfg!(target_feature = "fma"),
+    );
+
+    #[inline]
+    #[target_feature(enable = "avx")]
+    unsafe fn load(lanes: &[f32; 8]) -> __m256 {
+        unsafe { _mm256_loadu_ps(lanes.as_ptr()) }
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx")]
+    unsafe fn store(value: __m256) -> [f32; 8] {
+        let mut out = [0.0; 8];
+        unsafe { _mm256_storeu_ps(out.as_mut_ptr(), value) };
+        out
+    }
+
+    #[target_feature(enable = "avx,fma")]
+    unsafe fn mul_add(a: &[f32; 8], b: &[f32; 8], c: &[f32; 8]) -> [f32; 8] {
+        unsafe { store(_mm256_fmadd_ps(load(a), load(b), load(c))) }
+    }
+
+    /// # Safety
+    ///
+    /// Every index must be less than `table.len()`, which must fit in an
+    /// `i32`.
+    #[target_feature(enable = "avx,avx2")]
+    unsafe fn gather(table: &[f32], indices: &[u32; 8]) -> [f32; 8] {
+        unsafe {
+            let offsets = _mm256_loadu_si256(indices.as_ptr().cast());
+            store(_mm256_i32gather_ps::<4>(table.as_ptr(), offsets))
+        }
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn div(a: &[f32; 8], b: &[f32; 8]) -> [f32; 8] {
+        unsafe { store(_mm256_div_ps(load(a), load(b))) }
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn min(a: &[f32; 8], b: &[f32; 8]) -> [f32; 8] {
+        unsafe { store(_mm256_min_ps(load(a), load(b))) }
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn max(a: &[f32; 8], b: &[f32; 8]) -> [f32; 8] {
+        unsafe { store(_mm256_max_ps(load(a), load(b))) }
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn abs(a: &[f32; 8]) -> [f32; 8] {
+        unsafe { store(_mm256_andnot_ps(_mm256_set1_ps(-0.0), load(a))) }
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn sqrt(a: &[f32; 8]) -> [f32; 8] {
+        unsafe { store(_mm256_sqrt_ps(load(a))) }
+    }
+
+    #[target_feature(enable = "avx")]
+    unsafe fn reduce_sum(a: &[f32; 8]) -> f32 {
+        unsafe {
+            let value = load(a);
+            let quad = _mm_add_ps(
+                _mm256_castps256_ps128(value),
+                _mm256_extractf128_ps::<1>(value),
+            );
+            let pair = _mm_add_ps(quad, _mm_movehl_ps(quad, quad));
+            let single = _mm_add_ss(pair, _mm_movehdup_ps(pair));
+            _mm_cvtss_f32(single)
+        }
+    }
+}
@@ -45,3 +45,166 @@ impl OnePole {
         self.state
     }
 }
+
+/// How a [`SmoothedParam`] approaches a new target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmoothingMode {
+    /// Constant-rate ramp that lands exactly on the target after the
+    /// smoothing time.
+    Linear,
+    /// One-pole approach that covers 63% of the remaining distance per
+    /// smoothing time.
+    Exponential,
+}
+
+/// Parameter value that glides towards its target instead of jumping, so
+/// gain and similar changes do not zipper.
+#[derive(Clone, Copy, Debug)]
+pub struct SmoothedParam {
+    mode: SmoothingMode,
+    sample_rate: f32,
+    time_ms: f32,
+    current: f32,
+    target: f32,
+    step: f32,
+    remaining: u32,
+    coeff: f32,
+}
+
+impl SmoothedParam {
+    pub fn new(mode: SmoothingMode, sample_rate: f32, time_ms: f32, value: f32) -> Self {
+        let mut param = Self {
+            mode,
+            sample_rate: sample_rate.max(1.0),
+            time_ms: time_ms.max(0.0),
+            current: value,
+            target: value,
+            step: 0.0,
+            remaining: 0,
+            coeff: 1.0,
+        };
+        param.update_coeff();
+        param
+    }
+
+    pub fn linear(sample_rate: f32, time_ms: f32, value: f32) -> Self {
+        Self::new(SmoothingMode::Linear, sample_rate, time_ms, value)
+    }
+
+    pub fn exponential(sample_rate: f32, time_ms: f32, value: f32) -> Self {
+        Self::new(SmoothingMode::Exponential, sample_rate, time_ms, value)
+    }
+
+    pub fn mode(&self) -> SmoothingMode {
+        self.mode
+    }
+
+    /// Changes the smoothing time. A ramp in progress restarts from the
+    /// current value at the new rate.
+    pub fn set_time_ms(&mut self, sample_rate: f32, time_ms: f32) {
+        self.sample_rate = sample_rate.max(1.0);
+        self.time_ms = time_ms.max(0.0);
+        self.update_coeff();
+        if self.is_smoothing() {
+            let target = self.target;
+            self.set_target(target);
+        }
+    }
+
+    pub fn set_target(&mut self, target: f32) {
+        self.target = target;
+        match self.mode {
+            SmoothingMode::Linear => {
+                let samples = (self.time_ms * 0.001 * self.sample_rate).round() as u32;
+                if samples == 0 {
+                    self.reset(target);
+                } else {
+                    self.remaining = samples;
+                    self.step = (target - self.current) / samples as f32;
+                }
+            }
+            SmoothingMode::Exponential => {
+                if self.coeff >= 1.0 {
+                    self.reset(target);
+                }
+            }
+        }
+    }
+
+    /// Jumps to `value` without smoothing.
+    pub fn reset(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.step = 0.0;
+        self.remaining = 0;
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.current != self.target
+    }
+
+    /// Advances one sample and returns the new value.
+    #[inline]
+    pub fn next_value(&mut self) -> f32 {
+        if !self.is_smoothing() {
+            return self.current;
+        }
+        match self.mode {
+            SmoothingMode::Linear => {
+                self.remaining = self.remaining.saturating_sub(1);
+                let next = self.current + self.step;
+                // Clamping keeps rounding in the step from overshooting.
+                self.current = if self.remaining == 0 {
+                    self.target
+                } else if self.step > 0.0 {
+                    next.min(self.target)
+                } else {
+                    next.max(self.target)
+                };
+            }
+            SmoothingMode::Exponential => {
+                let distance = self.target - self.current;
+                let next = self.current + self.coeff * distance;
+                let left = self.target - next;
+                // Snap once the remaining distance is negligible or too small
+                // to move the value at f32 precision, and never step past the
+                // target through rounding.
+                self.current =
+                    if left.abs() < 1e-6 || next == self.current || left * distance <= 0.0 {
+                        self.target
+                    } else {
+                        next
+                    };
+            }
+        }
+        self.current
+    }
+
+    /// Fills `output` with the next `output.len()` values.
+    pub fn process_into(&mut self, output: &mut [f32]) {
+        if !self.is_smoothing() {
+            output.fill(self.current);
+            return;
+        }
+        for value in output {
+            *value = self.next_value();
+        }
+    }
+
+    fn update_coeff(&mut self) {
+        let tau = self.time_ms * 0.001 * self.sample_rate;
+        self.coeff = if tau <= 1.0 {
+            1.0
+        } else {
+            1.0 - (-1.0 / tau).exp()
+        };
+    }
+}
@@ -0,0 +1,90 @@
+//! Inter-sample peak detection after ITU-R BS.1770 Annex 2. Every sample is
+//! followed by points reconstructed at [`OVERSAMPLING`]x with a
+//! Blackman-windowed sinc, and the largest magnitude of the group is its
+//! true peak.
+
+use crate::resample::blackman;
+
+/// Oversampling factor of the reconstruction.
+pub const OVERSAMPLING: usize = 4;
+const HALF_TAPS: usize = 8;
+const TAPS: usize = 2 * HALF_TAPS;
+
+/// Streaming true-peak detector for one channel.
+///
+/// The interpolated points after a sample depend on the samples that follow
+/// it, so [`process`](Self::process) reports the sample
+/// [`LATENCY`](Self::LATENCY) frames behind the one pushed. Silence is
+/// assumed before the first sample; push `LATENCY` zeros to flush the end of
+/// a signal.
+#[derive(Clone, Debug)]
+pub struct TruePeakDetector {
+    phases: [[f32; TAPS]; OVERSAMPLING - 1],
+    /// The last `TAPS` samples, written twice so the window is contiguous.
+    history: [f32; 2 * TAPS],
+    position: usize,
+}
+
+impl TruePeakDetector {
+    pub const LATENCY: usize = HALF_TAPS;
+
+    pub fn new() -> Self {
+        Self {
+            phases: core::array::from_fn(|phase| {
+                interpolation_taps((phase + 1) as f64 / OVERSAMPLING as f64)
+            }),
+            history: [0.0; 2 * TAPS],
+            position: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.history = [0.0; 2 * TAPS];
+        self.position = 0;
+    }
+
+    /// Pushes `sample` and returns the linear true peak of the sample
+    /// [`LATENCY`](Self::LATENCY) frames earlier.
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.history[self.position] = sample;
+        self.history[self.position + TAPS] = sample;
+        self.position = (self.position + 1) % TAPS;
+
+        let window = &self.history[self.position..self.position + TAPS];
+        let mut peak = window[HALF_TAPS - 1].abs();
+        for weights in &self.phases {
+            let value: f32 = window.iter().zip(weights).map(|(x, w)| x * w).sum();
+            peak = peak.max(value.abs());
+        }
+        peak
+    }
+
+    /// Largest true peak reported while pushing `samples`.
+    pub fn process_block(&mut self, samples: &[f32]) -> f32 {
+        samples
+            .iter()
+            .fold(0.0f32, |peak, &sample| peak.max(self.process(sample)))
+    }
+}
+
+impl Default for TruePeakDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Windowed-sinc taps interpolating a point `fraction` of a sample after
+/// the centre tap, which sits at index `HALF_TAPS - 1`.
+fn interpolation_taps(fraction: f64) -> [f32; TAPS] {
+    core::array::from_fn(|tap| {
+        let offset = tap as f64 - HALF_TAPS as f64 + 1.0;
+        let x = fraction - offset;
+        let sinc = if x.abs() < 1e-12 {
+            1.0
+        } else {
+            (core::f64::consts::PI * x).sin() / (core::f64::consts::PI * x)
+        };
+        (sinc * blackman((x + HALF_TAPS as f64) / TAPS as f64)) as f32
+    })
+}
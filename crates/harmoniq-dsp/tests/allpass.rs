@@ -0,0 +1,72 @@
+use harmoniq_dsp::allpass::{Allpass1, Allpass2, DcBlocker};
+
+const SR: f32 = 48_000.0;
+
+/// Output/input RMS ratio for a sine at `freq` once the filter has settled.
+fn gain(freq: f32, mut process: impl FnMut(f32) -> f32) -> f32 {
+    let settle = 4_800;
+    let frames = settle + 9_600;
+    let (mut input_energy, mut output_energy) = (0.0f64, 0.0f64);
+    for i in 0..frames {
+        let x = (std::f32::consts::TAU * freq * i as f32 / SR).sin();
+        let y = process(x);
+        if i >= settle {
+            input_energy += (x * x) as f64;
+            output_energy += (y * y) as f64;
+        }
+    }
+    (output_energy / input_energy).sqrt() as f32
+}
+
+#[test]
+fn dc_blocker_removes_offset() {
+    let mut blocker = DcBlocker::new(SR);
+    let mut output = 0.0;
+    for _ in 0..SR as usize {
+        output = blocker.process_sample(0.5);
+    }
+    assert!(output.abs() < 1e-4, "{output}");
+
+    // Audio-band content passes untouched.
+    blocker.reset();
+    let passband = gain(1_000.0, |x| blocker.process_sample(x + 0.25));
+    assert!((passband - 1.0).abs() < 1e-3, "{passband}");
+}
+
+#[test]
+fn first_order_allpass_has_unity_magnitude() {
+    let mut allpass = Allpass1::new(0.0);
+    allpass.set_sample_rate(SR);
+    allpass.set_frequency(1_000.0);
+    for freq in [50.0, 300.0, 1_000.0, 4_000.0, 12_000.0, 20_000.0] {
+        allpass.reset();
+        let gain = gain(freq, |x| allpass.process_sample(x));
+        assert!((gain - 1.0).abs() < 1e-3, "{freq} Hz: {gain}");
+    }
+}
+
+#[test]
+fn first_order_allpass_is_a_quarter_cycle_late_at_its_break() {
+    let mut allpass = Allpass1::new(0.0);
+    allpass.set_sample_rate(SR);
+    allpass.set_frequency(SR / 8.0);
+    // Eight samples per cycle: a -90 degree shift is a two-sample delay.
+    let input: Vec<f32> = (0..64)
+        .map(|i| (std::f32::consts::TAU * i as f32 / 8.0).sin())
+        .collect();
+    let output: Vec<f32> = input.iter().map(|x| allpass.process_sample(*x)).collect();
+    for i in 48..64 {
+        assert!((output[i] - input[i - 2]).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn second_order_allpass_has_unity_magnitude() {
+    let mut allpass = Allpass2::new(2_000.0, 0.7);
+    allpass.set_sample_rate(SR);
+    for freq in [50.0, 500.0, 2_000.0, 8_000.0, 20_000.0] {
+        allpass.reset();
+        let gain = gain(freq, |x| allpass.process_sample(x));
+        assert!((gain - 1.0).abs() < 1e-3, "{freq} Hz: {gain}");
+    }
+}
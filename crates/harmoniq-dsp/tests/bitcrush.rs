@@ -0,0 +1,85 @@
+use harmoniq_dsp::bitcrush::BitCrusher;
+use harmoniq_dsp::AudioBlockMut;
+
+fn sine(frames: usize) -> Vec<f32> {
+    (0..frames)
+        .map(|i| 0.8 * (i as f32 * 0.031).sin())
+        .collect()
+}
+
+/// Runs a mono signal through the crusher in blocks of `block` frames.
+fn crush(crusher: &mut BitCrusher, input: &[f32], block: usize) -> Vec<f32> {
+    crusher.prepare(1);
+    let mut output = input.to_vec();
+    for chunk in output.chunks_mut(block) {
+        let mut block =
+            unsafe { AudioBlockMut::from_interleaved(chunk.as_mut_ptr(), 1, chunk.len() as u32) };
+        crusher.process_block(&mut block);
+    }
+    output
+}
+
+#[test]
+fn sixteen_bits_without_downsampling_is_transparent() {
+    let input = sine(4096);
+    let output = crush(&mut BitCrusher::new(16.0, 1.0), &input, 256);
+    let max_error = input
+        .iter()
+        .zip(&output)
+        .fold(0.0f32, |max, (a, b)| max.max((a - b).abs()));
+    assert!(max_error <= 0.5 / 32_768.0 + f32::EPSILON, "{max_error}");
+}
+
+#[test]
+fn low_bit_depth_snaps_to_levels() {
+    let crusher = BitCrusher::new(3.0, 1.0);
+    for sample in [-1.0, -0.6, -0.1, 0.0, 0.2, 0.49, 0.85] {
+        let value = crusher.quantize(sample);
+        assert_eq!((value * 4.0).fract(), 0.0, "{sample} -> {value}");
+        assert!((value - sample).abs() <= 0.125 + 1e-6);
+    }
+    assert_eq!(crusher.quantize(0.0), 0.0);
+    // Three bits are the signed codes -4..=3.
+    assert_eq!(crusher.quantize(0.99), 0.75);
+    assert_eq!(crusher.quantize(-1.5), -1.0);
+}
+
+#[test]
+fn integer_downsample_holds_each_sample() {
+    let input: Vec<f32> = (0..12).map(|i| i as f32 / 16.0).collect();
+    let output = crush(&mut BitCrusher::new(16.0, 3.0), &input, 5);
+    let expected: Vec<f32> = (0..12).map(|i| (i / 3 * 3) as f32 / 16.0).collect();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn fractional_downsample_alternates_hold_lengths() {
+    let input: Vec<f32> = (0..20).map(|i| i as f32 / 32.0).collect();
+    let output = crush(&mut BitCrusher::new(16.0, 2.5), &input, 7);
+    let mut holds = Vec::new();
+    let mut run = 1;
+    for pair in output.windows(2) {
+        if pair[0] == pair[1] {
+            run += 1;
+        } else {
+            holds.push(run);
+            run = 1;
+        }
+    }
+    assert_eq!(holds, [3, 2, 3, 2, 3, 2, 3]);
+}
+
+#[test]
+fn channels_hold_independently() {
+    let mut frames: Vec<f32> = (0..8)
+        .flat_map(|i| [i as f32 / 8.0, -(i as f32) / 8.0])
+        .collect();
+    let mut crusher = BitCrusher::new(16.0, 2.0);
+    crusher.prepare(2);
+    let mut block = unsafe { AudioBlockMut::from_interleaved(frames.as_mut_ptr(), 2, 8) };
+    crusher.process_block(&mut block);
+    for (frame, pair) in frames.chunks(2).enumerate() {
+        let held = (frame / 2 * 2) as f32 / 8.0;
+        assert_eq!(pair, [held, -held]);
+    }
+}
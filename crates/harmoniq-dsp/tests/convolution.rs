@@ -0,0 +1,69 @@
+use harmoniq_dsp::convolution::{LinearPhaseConvolver, PARTITION};
+
+/// Runs `input` through `convolver` in uneven blocks.
+fn run(convolver: &mut LinearPhaseConvolver, input: &[f32]) -> Vec<f32> {
+    let mut output = input.to_vec();
+    let mut start = 0;
+    for (index, block) in [1usize, 100, 333, 512, 64].iter().cycle().enumerate() {
+        if start >= output.len() {
+            break;
+        }
+        let end = (start + block + index % 3).min(output.len());
+        convolver.process(0, &mut output[start..end]);
+        start = end;
+    }
+    output
+}
+
+#[test]
+fn flat_response_is_a_pure_delay() {
+    let mut convolver = LinearPhaseConvolver::new(255, 1);
+    assert_eq!(convolver.taps(), 255);
+    let latency = convolver.latency();
+    assert_eq!(latency, 127);
+    let input: Vec<f32> = (0..4_000)
+        .map(|n| ((n * 7919) % 113) as f32 / 56.0 - 1.0)
+        .collect();
+    let output = run(&mut convolver, &input);
+    assert!(output[..latency].iter().all(|sample| sample.abs() < 1e-6));
+    for (frame, sample) in output[latency..].iter().enumerate() {
+        assert!((sample - input[frame]).abs() < 1e-3, "frame {frame}");
+    }
+}
+
+#[test]
+fn impulse_response_is_symmetric() {
+    let mut convolver = LinearPhaseConvolver::new(511, 1);
+    // A gentle lowpass.
+    convolver.set_magnitude_response(|frequency| 1.0 / (1.0 + (frequency / 0.05).powi(2)));
+    let mut input = vec![0.0; 4_096];
+    input[0] = 1.0;
+    let output = run(&mut convolver, &input);
+    let centre = convolver.latency();
+    assert!(output[centre] > 0.0);
+    for offset in 1..256 {
+        let (early, late) = (output[centre - offset], output[centre + offset]);
+        assert!(
+            (early - late).abs() < 1e-6,
+            "offset {offset}: {early} vs {late}"
+        );
+    }
+}
+
+#[test]
+fn new_kernels_are_crossfaded_in() {
+    let mut convolver = LinearPhaseConvolver::new(1_023, 1);
+    let mut output = vec![1.0; 4_096];
+    convolver.process(0, &mut output);
+    assert!((output[4_095] - 1.0).abs() < 1e-3);
+
+    convolver.set_magnitude_response(|_| 0.5);
+    let mut faded = vec![1.0; 3 * PARTITION];
+    convolver.process(0, &mut faded);
+    let mut previous = output[4_095];
+    for sample in &faded {
+        assert!((sample - previous).abs() < 0.01, "{previous} -> {sample}");
+        previous = *sample;
+    }
+    assert!((previous - 0.5).abs() < 1e-3);
+}
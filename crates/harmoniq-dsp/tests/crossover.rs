@@ -0,0 +1,103 @@
+use harmoniq_dsp::crossover::LinkwitzRiley4;
+
+const SR: f32 = 48_000.0;
+const CROSSOVER: f32 = 1_000.0;
+
+/// Deterministic white noise in [-1, 1).
+fn white_noise(frames: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678u32;
+    (0..frames)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+        })
+        .collect()
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    let energy: f64 = samples.iter().map(|x| (*x as f64).powi(2)).sum();
+    (energy / samples.len() as f64).sqrt() as f32
+}
+
+#[test]
+fn split_bands_of_white_noise_sum_back_to_the_input() {
+    let input = white_noise(96_000);
+    let mut crossover = LinkwitzRiley4::new(SR, CROSSOVER);
+    let mut low = vec![0.0; input.len()];
+    let mut high = vec![0.0; input.len()];
+    crossover.split_block(&input, &mut low, &mut high);
+    let sum: Vec<f32> = low
+        .iter()
+        .zip(&high)
+        .map(|(low, high)| low + high)
+        .collect();
+
+    // The sum is the input with only its phase turned by the crossover's
+    // allpass: sample for sample it matches that allpass...
+    let mut allpass = crossover.matching_allpass();
+    for (frame, (x, y)) in input.iter().zip(&sum).enumerate() {
+        let expected = allpass.process_sample(*x);
+        assert!(
+            (y - expected).abs() < 1e-4,
+            "frame {frame}: {y} vs {expected}"
+        );
+    }
+
+    // ...and its level is the input's.
+    let ratio = rms(&sum) / rms(&input);
+    assert!((ratio - 1.0).abs() < 1e-3, "{ratio}");
+}
+
+#[test]
+fn bands_are_in_phase_and_6_db_down_at_the_crossover() {
+    let mut crossover = LinkwitzRiley4::new(SR, CROSSOVER);
+    let frames = 48_000;
+    let settle = 9_600;
+    let (mut low_energy, mut high_energy, mut input_energy) = (0.0f64, 0.0f64, 0.0f64);
+    let mut correlation = 0.0f64;
+    for i in 0..frames {
+        let x = (std::f32::consts::TAU * CROSSOVER * i as f32 / SR).sin();
+        let (low, high) = crossover.split(x);
+        if i >= settle {
+            input_energy += (x * x) as f64;
+            low_energy += (low * low) as f64;
+            high_energy += (high * high) as f64;
+            correlation += (low * high) as f64;
+        }
+    }
+    let low_gain = (low_energy / input_energy).sqrt();
+    let high_gain = (high_energy / input_energy).sqrt();
+    assert!((low_gain - 0.5).abs() < 1e-2, "{low_gain}");
+    assert!((high_gain - 0.5).abs() < 1e-2, "{high_gain}");
+    assert!(correlation / (low_energy * high_energy).sqrt() > 0.999);
+}
+
+#[test]
+fn low_frequency_delay_matches_the_reported_group_delay() {
+    let crossover = LinkwitzRiley4::new(SR, CROSSOVER);
+    // sqrt(2) / tan(pi * 1000 / 48000), about 21.6 samples.
+    let expected = std::f32::consts::SQRT_2 / (std::f32::consts::PI * CROSSOVER / SR).tan();
+    assert!((crossover.group_delay_samples() - expected).abs() < 1e-3);
+
+    // A slow sine comes out shifted by that many samples.
+    let mut crossover = crossover;
+    let freq = 20.0;
+    let omega = std::f32::consts::TAU * freq / SR;
+    let frames = 96_000;
+    let mut best = (0, f32::MAX);
+    let output: Vec<f32> = (0..frames)
+        .map(|i| {
+            let (low, high) = crossover.split((omega * i as f32).sin());
+            low + high
+        })
+        .collect();
+    for lag in 0..64 {
+        let error: f32 = (48_000..frames)
+            .map(|i| (output[i] - (omega * (i - lag) as f32).sin()).abs())
+            .fold(0.0, f32::max);
+        if error < best.1 {
+            best = (lag, error);
+        }
+    }
+    assert_eq!(best.0, crossover.group_delay_samples().round() as usize);
+}
@@ -0,0 +1,62 @@
+use harmoniq_dsp::dynamics::{hard_knee, soft_knee_gain};
+
+const THRESHOLD: f32 = -20.0;
+const RATIO: f32 = 4.0;
+const KNEE: f32 = 6.0;
+
+/// Analytic soft-knee transfer curve (output level in dB).
+fn soft_knee_output(input_db: f32) -> f32 {
+    let overshoot = input_db - THRESHOLD;
+    if 2.0 * overshoot < -KNEE {
+        input_db
+    } else if 2.0 * overshoot.abs() <= KNEE {
+        input_db + (1.0 / RATIO - 1.0) * (overshoot + KNEE / 2.0).powi(2) / (2.0 * KNEE)
+    } else {
+        THRESHOLD + overshoot / RATIO
+    }
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-4,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn hard_knee_leaves_signals_at_or_below_threshold() {
+    assert_eq!(hard_knee(-30.0, THRESHOLD, RATIO), 0.0);
+    assert_eq!(hard_knee(THRESHOLD, THRESHOLD, RATIO), 0.0);
+}
+
+#[test]
+fn hard_knee_applies_ratio_above_threshold() {
+    // 8 dB over a 4:1 threshold comes out 2 dB over.
+    assert_close(hard_knee(-12.0, THRESHOLD, RATIO), -6.0);
+}
+
+#[test]
+fn soft_knee_matches_analytic_curve() {
+    for input in [-40.0, -23.0, -22.0, THRESHOLD, -18.5, -17.0, -10.0, 0.0] {
+        let gain = soft_knee_gain(input, THRESHOLD, RATIO, KNEE);
+        assert_close(input + gain, soft_knee_output(input));
+    }
+}
+
+#[test]
+fn soft_knee_regions() {
+    // Below the knee: untouched.
+    assert_eq!(soft_knee_gain(-30.0, THRESHOLD, RATIO, KNEE), 0.0);
+    // At the threshold: (1/R - 1) * (W/2)^2 / 2W.
+    assert_close(soft_knee_gain(THRESHOLD, THRESHOLD, RATIO, KNEE), -0.5625);
+    // Above the knee: identical to the hard knee.
+    assert_close(
+        soft_knee_gain(-10.0, THRESHOLD, RATIO, KNEE),
+        hard_knee(-10.0, THRESHOLD, RATIO),
+    );
+    // A zero knee is a hard knee.
+    assert_close(
+        soft_knee_gain(-19.0, THRESHOLD, RATIO, 0.0),
+        hard_knee(-19.0, THRESHOLD, RATIO),
+    );
+}
@@ -0,0 +1,73 @@
+use harmoniq_dsp::envelope::{DetectorMode, EnvelopeFollower};
+
+const SR: f32 = 48_000.0;
+const ATTACK_MS: f32 = 10.0;
+const RELEASE_MS: f32 = 100.0;
+
+fn samples(ms: f32) -> usize {
+    (ms * 0.001 * SR).round() as usize
+}
+
+fn follower(mode: DetectorMode) -> EnvelopeFollower {
+    let mut follower = EnvelopeFollower::new(ATTACK_MS, RELEASE_MS).with_mode(mode);
+    follower.set_sample_rate(SR);
+    follower
+}
+
+#[test]
+fn step_reaches_63_percent_after_one_attack_time() {
+    for mode in [DetectorMode::Peak, DetectorMode::Rms] {
+        let mut env = follower(mode);
+        let mut level = 0.0;
+        for _ in 0..samples(ATTACK_MS) {
+            level = env.process_sample(1.0);
+        }
+        assert!(level >= 0.63, "{mode:?}: {level}");
+        if mode == DetectorMode::Peak {
+            assert!(level < 0.64, "{level}");
+        }
+    }
+}
+
+#[test]
+fn release_is_slower_than_attack() {
+    let mut env = follower(DetectorMode::Peak);
+    for _ in 0..samples(10.0 * ATTACK_MS) {
+        env.process_sample(-1.0);
+    }
+    assert!((env.value() - 1.0).abs() < 1e-3);
+
+    let mut level = 0.0;
+    for _ in 0..samples(RELEASE_MS) {
+        level = env.process_sample(0.0);
+    }
+    assert!((level - (-1.0f32).exp()).abs() < 0.01, "{level}");
+}
+
+#[test]
+fn rms_of_a_sine_settles_near_its_rms() {
+    let mut env = EnvelopeFollower::new(50.0, 50.0).with_mode(DetectorMode::Rms);
+    env.set_sample_rate(SR);
+    let mut level = 0.0;
+    for i in 0..samples(1_000.0) {
+        let x = (std::f32::consts::TAU * 1_000.0 * i as f32 / SR).sin();
+        level = env.process_sample(x);
+    }
+    assert!(
+        (level - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.02,
+        "{level}"
+    );
+}
+
+#[test]
+fn times_can_change_on_the_fly() {
+    let mut env = follower(DetectorMode::Peak);
+    env.set_attack_ms(0.0);
+    assert_eq!(env.attack_ms(), 0.0);
+    assert_eq!(env.process_sample(0.5), 0.5);
+    env.set_release_ms(0.0);
+    assert_eq!(env.process_sample(0.0), 0.0);
+
+    env.set_mode(DetectorMode::Rms);
+    assert_eq!(env.value(), 0.0);
+}
@@ -0,0 +1,39 @@
+use harmoniq_dsp::fft::{Complex, Fft};
+
+#[test]
+fn sine_lands_in_its_bin() {
+    let size = 256;
+    let fft = Fft::new(size);
+    let mut data: Vec<Complex> = (0..size)
+        .map(|n| {
+            let phase = std::f32::consts::TAU * 8.0 * n as f32 / size as f32;
+            Complex::new(phase.cos(), 0.0)
+        })
+        .collect();
+    fft.forward(&mut data);
+    for (bin, value) in data.iter().enumerate() {
+        let expected = if bin == 8 || bin == size - 8 {
+            size as f32 / 2.0
+        } else {
+            0.0
+        };
+        assert!(
+            (value.norm() - expected).abs() < 1e-3,
+            "bin {bin}: {value:?}"
+        );
+    }
+}
+
+#[test]
+fn inverse_undoes_forward() {
+    let fft = Fft::new(1_024);
+    let input: Vec<Complex> = (0..1_024)
+        .map(|n| Complex::new((n as f32 * 0.37).sin(), (n as f32 * 0.11).cos()))
+        .collect();
+    let mut data = input.clone();
+    fft.forward(&mut data);
+    fft.inverse(&mut data);
+    for (actual, expected) in data.iter().zip(&input) {
+        assert!((*actual - *expected).norm() < 1e-4);
+    }
+}
@@ -0,0 +1,20 @@
+use harmoniq_dsp::loudness::{lufs, KWeighting};
+
+#[test]
+fn full_scale_997_hz_sine_reads_minus_3_lufs() {
+    let sample_rate = 48_000.0;
+    let mut filter = KWeighting::new(sample_rate);
+    let frames = 48_000 * 2;
+    let mut energy = 0.0;
+    for i in 0..frames {
+        let phase = std::f64::consts::TAU * 997.0 * i as f64 / sample_rate as f64;
+        let weighted = filter.process(phase.sin());
+        // Skip the filters' settling time.
+        if i >= 4_800 {
+            energy += weighted * weighted;
+        }
+    }
+    let loudness = lufs(energy / (frames - 4_800) as f64);
+    assert!((loudness + 3.01).abs() < 0.05, "{loudness}");
+    assert_eq!(lufs(0.0), f64::NEG_INFINITY);
+}
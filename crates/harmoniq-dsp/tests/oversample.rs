@@ -0,0 +1,153 @@
+use harmoniq_dsp::oversample::Oversampler;
+use harmoniq_dsp::saturator::{soft_clip, Saturator};
+use harmoniq_dsp::AudioBlockMut;
+
+const SR: f32 = 48_000.0;
+
+fn sine(freq: f32, amplitude: f32, frames: usize) -> Vec<f32> {
+    (0..frames)
+        .map(|i| amplitude * (std::f32::consts::TAU * freq * i as f32 / SR).sin())
+        .collect()
+}
+
+fn run_blocks(signal: &mut [f32], block: usize, mut process: impl FnMut(&mut AudioBlockMut<'_>)) {
+    for chunk in signal.chunks_mut(block) {
+        let mut block =
+            unsafe { AudioBlockMut::from_interleaved(chunk.as_mut_ptr(), 1, chunk.len() as u32) };
+        process(&mut block);
+    }
+}
+
+/// Magnitude of the `freq` component, by correlation over `signal`.
+fn magnitude(signal: &[f32], freq: f32) -> f32 {
+    let (mut re, mut im) = (0.0f64, 0.0f64);
+    for (i, sample) in signal.iter().enumerate() {
+        let phase = std::f64::consts::TAU * freq as f64 * i as f64 / SR as f64;
+        re += *sample as f64 * phase.cos();
+        im += *sample as f64 * phase.sin();
+    }
+    (2.0 * (re * re + im * im).sqrt() / signal.len() as f64) as f32
+}
+
+fn impulse_response<const FACTOR: usize>() -> Vec<f32> {
+    let mut oversampler = Oversampler::<FACTOR>::new();
+    let mut signal = vec![0.0f32; 4 * Oversampler::<FACTOR>::LATENCY];
+    signal[0] = 1.0;
+    run_blocks(&mut signal, 13, |block| oversampler.process(block, |x| x));
+    signal
+}
+
+#[test]
+fn identity_round_trip_is_a_pure_delay() {
+    let latency = Oversampler::<2>::LATENCY;
+    assert_eq!(latency, Oversampler::<4>::LATENCY);
+    let input = sine(1_000.0, 0.5, 4_096);
+
+    let mut x2 = Oversampler::<2>::new();
+    let mut x4 = Oversampler::<4>::new();
+    for output in [
+        {
+            let mut signal = input.clone();
+            run_blocks(&mut signal, 64, |block| x2.process(block, |x| x));
+            signal
+        },
+        {
+            let mut signal = input.clone();
+            run_blocks(&mut signal, 100, |block| x4.process(block, |x| x));
+            signal
+        },
+    ] {
+        for (frame, sample) in output.iter().enumerate().skip(2 * latency) {
+            let expected = input[frame - latency];
+            assert!(
+                (sample - expected).abs() < 1e-3,
+                "frame {frame}: {sample} vs {expected}"
+            );
+        }
+    }
+}
+
+#[test]
+fn filters_are_linear_phase() {
+    for response in [impulse_response::<2>(), impulse_response::<4>()] {
+        let centre = Oversampler::<2>::LATENCY;
+        let peak = response
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .unwrap()
+            .0;
+        assert_eq!(peak, centre);
+        for offset in 1..centre {
+            let (before, after) = (response[centre - offset], response[centre + offset]);
+            assert!(
+                (before - after).abs() < 1e-6,
+                "{offset}: {before} vs {after}"
+            );
+        }
+        let dc: f32 = response.iter().sum();
+        assert!((dc - 1.0).abs() < 1e-3, "dc gain {dc}");
+    }
+}
+
+#[test]
+fn oversampled_saturation_aliases_less() {
+    // The third harmonic of 15 kHz lands at 45 kHz and folds to 3 kHz at
+    // the base rate.
+    let input = sine(15_000.0, 0.9, 9_600);
+    let alias = |factor: usize| {
+        let mut saturator = Saturator::new(4.0).with_oversampling(factor);
+        assert_eq!(saturator.oversampling_factor(), factor);
+        let mut signal = input.clone();
+        run_blocks(&mut signal, 256, |block| saturator.process_block(block));
+        magnitude(&signal[saturator.latency() + 256..], 3_000.0)
+    };
+
+    let plain = alias(1);
+    let x2 = alias(2);
+    let x4 = alias(4);
+    assert!(plain > 0.01, "{plain}");
+    assert!(x2 < plain * 0.1, "{x2} vs {plain}");
+    assert!(x4 < plain * 0.1, "{x4} vs {plain}");
+}
+
+#[test]
+fn saturator_without_oversampling_matches_soft_clip() {
+    let input = sine(440.0, 0.8, 512);
+    let mut saturator = Saturator::new(2.0);
+    assert_eq!(saturator.latency(), 0);
+    let mut signal = input.clone();
+    run_blocks(&mut signal, 128, |block| saturator.process_block(block));
+    for (output, input) in signal.iter().zip(&input) {
+        assert_eq!(*output, soft_clip(input * 2.0));
+    }
+}
+
+#[test]
+fn unprepared_channels_are_left_untouched() {
+    let mut oversampler = Oversampler::<2>::with_channels(1);
+    let mut frames: Vec<f32> = (0..64).flat_map(|i| [1.0, i as f32]).collect();
+    let expected: Vec<f32> = frames.iter().skip(1).step_by(2).copied().collect();
+    let mut block = unsafe { AudioBlockMut::from_interleaved(frames.as_mut_ptr(), 2, 64) };
+    oversampler.process(&mut block, |x| x);
+    let second: Vec<f32> = frames.iter().skip(1).step_by(2).copied().collect();
+    assert_eq!(second, expected);
+    assert!(frames[0].abs() < 1e-3, "the prepared channel is delayed");
+}
+
+#[test]
+fn block_round_trip_matches_per_sample_processing() {
+    let input = sine(3_000.0, 0.5, 512);
+    let mut expected = input.clone();
+    let mut reference = Oversampler::<4>::with_channels(1);
+    run_blocks(&mut expected, 64, |block| reference.process(block, |x| x));
+
+    let mut oversampler = Oversampler::<4>::with_channels(1);
+    let mut upsampled = vec![0.0f32; 64 * 4];
+    let mut output = vec![0.0f32; input.len()];
+    for (chunk, out) in input.chunks(64).zip(output.chunks_mut(64)) {
+        oversampler.upsample(0, chunk, &mut upsampled);
+        oversampler.downsample(0, &upsampled[..chunk.len() * 4], out);
+    }
+    assert_eq!(output, expected);
+}
@@ -0,0 +1,175 @@
+use harmoniq_dsp::resample::{
+    InterpolationKind, LinearResampler, MultiChannelResampler, Resampler,
+};
+use std::f64::consts::TAU;
+
+const KINDS: [InterpolationKind; 3] = [
+    InterpolationKind::Linear,
+    InterpolationKind::Cubic,
+    InterpolationKind::Sinc { taps: 32 },
+];
+
+fn sine(freq: f64, rate: f64, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| (TAU * freq * i as f64 / rate).sin() as f32)
+        .collect()
+}
+
+/// Feeds `input` in `chunk`-sized blocks through output buffers of
+/// `out_len` samples, passing back whatever input the resampler left and
+/// flushing whatever it has ready.
+fn resample(resampler: &mut Resampler, input: &[f32], chunk: usize, out_len: usize) -> Vec<f32> {
+    let mut output = Vec::new();
+    let mut block = vec![0.0; out_len];
+    for chunk in input.chunks(chunk) {
+        let mut rest = chunk;
+        loop {
+            let (consumed, written) = resampler.process(rest, &mut block);
+            output.extend_from_slice(&block[..written]);
+            rest = &rest[consumed..];
+            if rest.is_empty() && written < out_len {
+                break;
+            }
+        }
+    }
+    output
+}
+
+#[test]
+fn block_boundaries_do_not_change_output() {
+    let input: Vec<f32> = (0..5_000)
+        .map(|i| ((i * 7_919 % 1_000) as f32 / 1_000.0 - 0.5) + (i as f32 * 0.05).sin() * 0.5)
+        .collect();
+    for (from, to) in [(44_100.0, 48_000.0), (48_000.0, 44_100.0)] {
+        for kind in KINDS {
+            let whole = resample(
+                &mut Resampler::with_kind(from, to, kind),
+                &input,
+                5_000,
+                20_000,
+            );
+            for (chunk, out_len) in [(1, 3), (7, 5), (64, 100)] {
+                let split = resample(
+                    &mut Resampler::with_kind(from, to, kind),
+                    &input,
+                    chunk,
+                    out_len,
+                );
+                assert_eq!(split, whole, "{kind:?} {from}->{to} in blocks of {chunk}");
+            }
+        }
+    }
+}
+
+#[test]
+fn higher_order_kinds_track_a_sine_more_closely() {
+    let input = sine(1_000.0, 44_100.0, 44_100);
+    let error = |kind| {
+        let mut resampler = Resampler::with_kind(44_100.0, 48_000.0, kind);
+        let output = resample(&mut resampler, &input, 512, 1_024);
+        output
+            .iter()
+            .enumerate()
+            .skip(100)
+            .take(40_000)
+            .map(|(i, v)| (*v as f64 - (TAU * 1_000.0 * i as f64 / 48_000.0).sin()).abs())
+            .fold(0.0, f64::max)
+    };
+    let linear = error(InterpolationKind::Linear);
+    let cubic = error(InterpolationKind::Cubic);
+    let sinc = error(InterpolationKind::Sinc { taps: 32 });
+    assert!(linear < 5e-3, "linear {linear}");
+    assert!(cubic < linear / 10.0, "cubic {cubic}");
+    assert!(sinc < linear / 10.0, "sinc {sinc}");
+}
+
+#[test]
+fn sinc_filters_content_above_the_new_nyquist() {
+    // 30 kHz folds to 18 kHz when 96 kHz is halved.
+    let input = sine(30_000.0, 96_000.0, 96_000);
+    let rms = |kind| {
+        let mut resampler = Resampler::with_kind(96_000.0, 48_000.0, kind);
+        let output = resample(&mut resampler, &input, 512, 1_024);
+        let tail = &output[1_000..40_000];
+        (tail.iter().map(|v| v * v).sum::<f32>() / tail.len() as f32).sqrt()
+    };
+    assert!(rms(InterpolationKind::Linear) > 0.5);
+    let sinc = rms(InterpolationKind::Sinc { taps: 32 });
+    assert!(sinc < 0.02, "sinc alias rms {sinc}");
+}
+
+#[test]
+fn reset_and_linear_wrapper() {
+    let input = sine(440.0, 44_100.0, 2_000);
+    let mut resampler = Resampler::with_kind(44_100.0, 48_000.0, InterpolationKind::Cubic);
+    let first = resample(&mut resampler, &input, 256, 4_096);
+    resampler.reset();
+    assert_eq!(resampler.pending(), 0);
+    assert_eq!(resample(&mut resampler, &input, 300, 4_096), first);
+
+    let mut wrapper = LinearResampler::new(44_100.0, 48_000.0);
+    let mut linear = Resampler::with_kind(44_100.0, 48_000.0, InterpolationKind::Linear);
+    let mut a = vec![0.0; 4_096];
+    let mut b = vec![0.0; 4_096];
+    let (consumed, written) = wrapper.process(&input, &mut a);
+    assert_eq!(consumed, input.len());
+    assert_eq!(linear.process(&input, &mut b), (consumed, written));
+    assert_eq!(a[..written], b[..written]);
+    assert_eq!(a[0], input[0]);
+}
+
+#[test]
+fn input_is_left_to_the_caller_once_the_output_is_full() {
+    let input = sine(440.0, 44_100.0, 100_000);
+    let mut resampler = Resampler::with_kind(44_100.0, 48_000.0, InterpolationKind::Linear);
+    let mut block = [0.0; 64];
+    let (consumed, written) = resampler.process(&input, &mut block);
+    assert_eq!(written, block.len());
+    assert!(consumed < 2_048, "buffered {consumed} samples");
+    assert_eq!(resampler.pending(), consumed - 58);
+}
+
+#[test]
+fn channels_keep_independent_state() {
+    let left = sine(440.0, 44_100.0, 3_000);
+    let right = sine(1_250.0, 44_100.0, 3_000);
+    let kind = InterpolationKind::Sinc { taps: 16 };
+    let mut stereo = MultiChannelResampler::with_kind(2, 44_100.0, 48_000.0, kind);
+    let mut planar = [Vec::new(), Vec::new()];
+    let mut blocks = [vec![0.0; 600], vec![0.0; 600]];
+    for (l, r) in left.chunks(500).zip(right.chunks(500)) {
+        let [a, b] = &mut blocks;
+        let (_, written) = stereo.process_planar(&[l, r], &mut [&mut a[..], &mut b[..]]);
+        planar[0].extend_from_slice(&a[..written]);
+        planar[1].extend_from_slice(&b[..written]);
+    }
+
+    for (channel, input) in [&left, &right].into_iter().enumerate() {
+        let mut mono = Resampler::with_kind(44_100.0, 48_000.0, kind);
+        assert_eq!(planar[channel], resample(&mut mono, input, 500, 600));
+    }
+
+    stereo.reset();
+    let [a, b] = &mut blocks;
+    let (_, written) = stereo.process_planar(&[&left, &right], &mut [&mut a[..], &mut b[..]]);
+    assert_eq!(a[..written], planar[0][..written]);
+}
+
+#[test]
+fn uneven_channels_process_the_shortest() {
+    let mut stereo = MultiChannelResampler::new(2, 44_100.0, 48_000.0);
+    let long = sine(440.0, 44_100.0, 64);
+    let short = &long[..40];
+    let mut a = [0.0; 80];
+    let mut b = [0.0; 30];
+    let (consumed, written) = stereo.process_planar(&[&long, short], &mut [&mut a[..], &mut b[..]]);
+    assert_eq!(written, 30);
+    assert!(consumed <= 40);
+    assert_eq!(a[..30], b[..]);
+    assert!(a[30..].iter().all(|sample| *sample == 0.0));
+
+    // A third input without a channel is ignored.
+    stereo.reset();
+    let (_, written) = stereo.process_planar(&[&long, &long, &long], &mut [&mut a[..], &mut b[..]]);
+    assert_eq!(written, 30);
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "simd")]
+
+use harmoniq_dsp::simd::{mul_add_to, F32x4, F32x8, Simd, SimdFloat};
+
+const A: [f32; 8] = [1.5, -2.0, 0.25, 9.0, -0.0, 3.0, -7.5, 100.0];
+const B: [f32; 8] = [0.5, 4.0, -1.0, 3.0, 2.0, -6.0, 0.125, 0.01];
+
+fn lanes(f: impl Fn(f32, f32) -> f32) -> [f32; 8] {
+    core::array::from_fn(|lane| f(A[lane], B[lane]))
+}
+
+#[test]
+fn eight_lane_ops_match_scalar() {
+    let a = F32x8::from_array(A);
+    let b = F32x8::from_array(B);
+    let c = F32x8::splat(0.75);
+
+    assert_eq!((a / b).to_array(), lanes(|x, y| x / y));
+    assert_eq!(a.min(b).to_array(), lanes(f32::min));
+    assert_eq!(a.max(b).to_array(), lanes(f32::max));
+    assert_eq!(a.abs().to_array(), lanes(|x, _| x.abs()));
+    assert_eq!(a.abs().sqrt().to_array(), lanes(|x, _| x.abs().sqrt()));
+    assert_eq!(a.mul_add(b, c).to_array(), lanes(|x, y| x.mul_add(y, 0.75)));
+    let sum: f32 = A.iter().sum();
+    assert!((a.reduce_sum() - sum).abs() < 1e-4);
+    assert_eq!(
+        a.clamp(F32x8::splat(-1.0), F32x8::splat(1.0)).to_array(),
+        lanes(|x, _| x.clamp(-1.0, 1.0))
+    );
+}
+
+#[test]
+fn other_widths_use_the_generic_path() {
+    let a = F32x4::from_slice(&A);
+    let b = F32x4::from_slice(&B);
+    assert_eq!((a / b).to_array(), [3.0, -0.5, -0.25, 3.0]);
+    assert_eq!(a.max(b).to_array(), [1.5, 4.0, 0.25, 9.0]);
+    assert_eq!(a.reduce_sum(), 8.75);
+
+    let wide = Simd::<f32, 16>::splat(-4.0);
+    assert_eq!(wide.abs().sqrt().reduce_sum(), 32.0);
+}
+
+#[test]
+fn mul_add_helper_covers_the_tail() {
+    let lhs: Vec<f32> = (0..19).map(|i| i as f32 * 0.5).collect();
+    let rhs: Vec<f32> = (0..19).map(|i| 3.0 - i as f32).collect();
+    let addend = vec![1.0f32; 19];
+    let mut output = vec![0.0f32; 19];
+    mul_add_to(&mut output, &lhs, &rhs, &addend);
+    for i in 0..19 {
+        assert_eq!(output[i], lhs[i].mul_add(rhs[i], 1.0));
+    }
+}
+
+#[test]
+fn gather_reads_indexed_lanes() {
+    let table: Vec<f32> = (0..64).map(|i| i as f32 * 0.25).collect();
+    let indices = Simd::<u32, 8>::from_array([0, 63, 7, 7, 31, 2, 40, 1]);
+    let gathered = F32x8::gather(&table, indices);
+    let expected = indices.to_array().map(|index| table[index as usize]);
+    assert_eq!(gathered.to_array(), expected);
+    assert_eq!(
+        unsafe { F32x8::gather_unchecked(&table, indices) }.to_array(),
+        expected
+    );
+
+    let narrow = F32x4::gather(&table, Simd::from_array([3, 2, 1, 0]));
+    assert_eq!(narrow.to_array(), [0.75, 0.5, 0.25, 0.0]);
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn gather_panics_out_of_bounds() {
+    let table = [0.0f32; 8];
+    F32x8::gather(&table, Simd::from_array([0, 1, 2, 3, 4, 5, 6, 8]));
+}
+
+#[test]
+fn masked_load_and_store_handle_short_tails() {
+    let tail = [1.0f32, 2.0, 3.0];
+    let loaded = F32x8::masked_load(&tail, [true; 8], F32x8::splat(-1.0));
+    assert_eq!(
+        loaded.to_array(),
+        [1.0, 2.0, 3.0, -1.0, -1.0, -1.0, -1.0, -1.0]
+    );
+
+    let mask = [true, false, true, true, true, true, true, true];
+    let mut out = [0.0f32; 3];
+    F32x8::from_array(A).masked_store(&mut out, mask);
+    assert_eq!(out, [A[0], 0.0, A[2]]);
+
+    let mut table = [0.0f32; 4];
+    F32x4::from_array([1.0, 2.0, 3.0, 4.0]).scatter(&mut table, Simd::from_array([3, 1, 0, 2]));
+    assert_eq!(table, [3.0, 2.0, 4.0, 1.0]);
+}
@@ -0,0 +1,77 @@
+use harmoniq_dsp::smoothing::{SmoothedParam, SmoothingMode};
+
+const SR: f32 = 48_000.0;
+const TIME_MS: f32 = 10.0;
+const TIME_SAMPLES: usize = 480;
+
+fn step_response(mode: SmoothingMode, from: f32, to: f32, samples: usize) -> Vec<f32> {
+    let mut param = SmoothedParam::new(mode, SR, TIME_MS, from);
+    param.set_target(to);
+    (0..samples).map(|_| param.next_value()).collect()
+}
+
+#[test]
+fn step_targets_approach_monotonically_without_overshoot() {
+    for mode in [SmoothingMode::Linear, SmoothingMode::Exponential] {
+        for (from, to) in [(0.0, 1.0), (1.0, 0.25), (-12.0, 6.0)] {
+            let values = step_response(mode, from, to, 20 * TIME_SAMPLES);
+            let (low, high) = if from < to { (from, to) } else { (to, from) };
+            let mut previous = from;
+            for (index, value) in values.iter().enumerate() {
+                assert!(
+                    (low..=high).contains(value),
+                    "{mode:?} {from}->{to} at {index}: {value}"
+                );
+                if from < to {
+                    assert!(*value >= previous, "{mode:?} {from}->{to} at {index}");
+                } else {
+                    assert!(*value <= previous, "{mode:?} {from}->{to} at {index}");
+                }
+                previous = *value;
+            }
+            assert_eq!(*values.last().unwrap(), to, "{mode:?} {from}->{to}");
+        }
+    }
+}
+
+#[test]
+fn linear_lands_on_the_target_after_the_smoothing_time() {
+    let values = step_response(SmoothingMode::Linear, 0.0, 1.0, TIME_SAMPLES + 1);
+    assert!((values[TIME_SAMPLES / 2 - 1] - 0.5).abs() < 1e-4);
+    assert!(values[TIME_SAMPLES - 2] < 1.0);
+    assert_eq!(values[TIME_SAMPLES - 1], 1.0);
+    assert_eq!(values[TIME_SAMPLES], 1.0);
+}
+
+#[test]
+fn exponential_reaches_63_percent_in_one_time_constant() {
+    let values = step_response(SmoothingMode::Exponential, 0.0, 1.0, TIME_SAMPLES);
+    let reached = values[TIME_SAMPLES - 1];
+    assert!((reached - 0.632).abs() < 2e-3, "{reached}");
+}
+
+#[test]
+fn block_fill_matches_per_sample_values() {
+    for mode in [SmoothingMode::Linear, SmoothingMode::Exponential] {
+        let mut block = SmoothedParam::new(mode, SR, TIME_MS, 0.0);
+        let mut single = block;
+        block.set_target(2.0);
+        single.set_target(2.0);
+        let mut output = vec![0.0; 3 * TIME_SAMPLES];
+        for chunk in output.chunks_mut(64) {
+            block.process_into(chunk);
+        }
+        let expected: Vec<f32> = (0..output.len()).map(|_| single.next_value()).collect();
+        assert_eq!(output, expected, "{mode:?}");
+    }
+}
+
+#[test]
+fn zero_time_jumps_immediately() {
+    for mode in [SmoothingMode::Linear, SmoothingMode::Exponential] {
+        let mut param = SmoothedParam::new(mode, SR, 0.0, 0.0);
+        param.set_target(0.7);
+        assert!(!param.is_smoothing());
+        assert_eq!(param.next_value(), 0.7);
+    }
+}
@@ -0,0 +1,33 @@
+use harmoniq_dsp::true_peak::TruePeakDetector;
+
+#[test]
+fn detects_peaks_between_samples() {
+    // A quarter-rate sine sampled 45 degrees off its crests never reaches
+    // full scale on a sample.
+    let samples: Vec<f32> = (0..256)
+        .map(|i| (std::f32::consts::FRAC_PI_2 * i as f32 + std::f32::consts::FRAC_PI_4).sin())
+        .collect();
+    let sample_peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    assert!(sample_peak < 0.71);
+
+    let mut detector = TruePeakDetector::new();
+    let true_peak = detector.process_block(&samples);
+    assert!(true_peak > 0.95, "{true_peak}");
+}
+
+#[test]
+fn reports_each_sample_after_the_latency() {
+    let mut detector = TruePeakDetector::new();
+    // Only the far edge of the interpolator has seen the sample yet.
+    assert!(detector.process(0.5) < 1e-3);
+    for _ in 1..TruePeakDetector::LATENCY {
+        detector.process(0.0);
+    }
+    let peak = detector.process(0.0);
+    assert!((peak - 0.5).abs() < 1e-6, "{peak}");
+
+    detector.reset();
+    for _ in 0..TruePeakDetector::LATENCY {
+        assert_eq!(detector.process(0.0), 0.0);
+    }
+}
@@ -1,7 +1,9 @@
 use std::fs::create_dir_all;
 use std::path::PathBuf;
 
-use harmoniq_engine::render::{RenderDuration, RenderProject, RenderRequest, RenderSpeed};
+use harmoniq_engine::render::{
+    Normalization, RenderDuration, RenderProject, RenderRequest, RenderSpeed,
+};
 use harmoniq_engine::{
     nodes::NodeOsc, AudioClip, BufferConfig, ChannelLayout, GraphBuilder, HarmoniqEngine,
 };
@@ -41,6 +43,8 @@ fn render_clip() -> AudioClip {
         stems: None,
         freeze: None,
         speed: RenderSpeed::Offline,
+        master_oversample: 1,
+        normalization: Normalization::None,
     };
 
     renderer.render(&request).expect("render result").mixdown
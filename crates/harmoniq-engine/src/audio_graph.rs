@@ -6,9 +6,9 @@ use parking_lot::Mutex;
 
 use crate::automation::AutomationEvent;
 use crate::buffer::AudioBuffer;
-use crate::delay::DelayCompensator;
+use crate::delay::{insert_sorted, DelayCompensator, MidiDelay, MIDI_QUEUE_CAPACITY};
 use crate::mixer_rt::{Mixer, MixerConfig};
-use crate::plugin::{MidiEvent, PluginId};
+use crate::plugin::{MidiEvent, PluginId, ProcessTransport};
 use crate::AudioProcessor;
 
 /// Real-time friendly DSP node abstraction used by the audio graph runner.
@@ -25,11 +25,34 @@ pub trait DspNode: Send {
         output: &mut AudioBuffer,
         frames: usize,
     ) -> anyhow::Result<()>;
+
+    /// Like [`process`](Self::process), with the outputs of the nodes keying
+    /// its sidechain. The default ignores them.
+    fn process_with_sidechain(
+        &mut self,
+        inputs: &[&AudioBuffer],
+        sidechain: &[&AudioBuffer],
+        output: &mut AudioBuffer,
+        frames: usize,
+    ) -> anyhow::Result<()> {
+        let _ = sidechain;
+        self.process(inputs, output, frames)
+    }
+
+    /// MIDI the node emitted during its last `process`.
+    fn midi_output(&self) -> &[MidiEvent] {
+        &[]
+    }
+
+    /// Queues MIDI from an upstream node for the next `process`.
+    fn receive_midi(&mut self, _events: &[MidiEvent]) {}
 }
 
 struct NodeSpec {
     node: Box<dyn DspNode + Send>,
     inputs: Vec<usize>,
+    midi_inputs: Vec<usize>,
+    sidechain_inputs: Vec<usize>,
 }
 
 struct NodeState {
@@ -65,7 +88,7 @@ impl GraphRunner {
             node.buffer.resize(channels, max_block);
         }
 
-        let order = (0..state.len()).collect();
+        let order = topological_order(&state);
 
         Self {
             nodes: state,
@@ -98,53 +121,160 @@ impl GraphRunner {
             let (before, after) = self.nodes.split_at_mut(*index);
             let (node, after) = after.split_first_mut().expect("index must be valid");
 
-            let inputs: Vec<&AudioBuffer> = node
-                .spec
-                .inputs
-                .iter()
-                .filter_map(|idx| {
-                    if *idx < *index {
-                        before.get(*idx)
-                    } else if *idx > *index {
-                        after.get(idx - index - 1)
-                    } else {
-                        None
-                    }
-                })
-                .map(|node| &node.buffer)
-                .collect();
+            let inputs = node_buffers(&node.spec.inputs, *index, before, after);
+            let sidechain = node_buffers(&node.spec.sidechain_inputs, *index, before, after);
+
+            for source in &node.spec.midi_inputs {
+                let upstream = if *source < *index {
+                    before.get(*source)
+                } else if *source > *index {
+                    after.get(source - index - 1)
+                } else {
+                    None
+                };
+                if let Some(upstream) = upstream {
+                    node.spec
+                        .node
+                        .receive_midi(upstream.spec.node.midi_output());
+                }
+            }
 
             node.buffer.resize(self.channels, frames);
             node.buffer.clear();
-            node.spec.node.process(&inputs, &mut node.buffer, frames)?;
+            if sidechain.is_empty() {
+                node.spec.node.process(&inputs, &mut node.buffer, frames)?;
+            } else {
+                node.spec.node.process_with_sidechain(
+                    &inputs,
+                    &sidechain,
+                    &mut node.buffer,
+                    frames,
+                )?;
+            }
         }
 
         Ok(())
     }
 }
 
+/// Output buffers of `sources`, given the nodes before and after the one at
+/// `index`.
+fn node_buffers<'a>(
+    sources: &[usize],
+    index: usize,
+    before: &'a [NodeState],
+    after: &'a [NodeState],
+) -> Vec<&'a AudioBuffer> {
+    sources
+        .iter()
+        .filter_map(|idx| {
+            if *idx < index {
+                before.get(*idx)
+            } else if *idx > index {
+                after.get(idx - index - 1)
+            } else {
+                None
+            }
+        })
+        .map(|node| &node.buffer)
+        .collect()
+}
+
+/// Orders nodes so each runs after every node feeding it audio or MIDI,
+/// keeping index order wherever the connections allow it.
+fn topological_order(nodes: &[NodeState]) -> Vec<usize> {
+    fn visit(index: usize, nodes: &[NodeState], visited: &mut [bool], order: &mut Vec<usize>) {
+        if visited[index] {
+            return;
+        }
+        visited[index] = true;
+        let spec = &nodes[index].spec;
+        for source in spec
+            .inputs
+            .iter()
+            .chain(&spec.midi_inputs)
+            .chain(&spec.sidechain_inputs)
+        {
+            if *source < nodes.len() {
+                visit(*source, nodes, visited, order);
+            }
+        }
+        order.push(index);
+    }
+
+    let mut visited = vec![false; nodes.len()];
+    let mut order = Vec::with_capacity(nodes.len());
+    for index in 0..nodes.len() {
+        visit(index, nodes, &mut visited, &mut order);
+    }
+    order
+}
+
 /// Node that wraps an [`AudioProcessor`] instrument or effect instance.
 pub struct ProcessorNode {
     processor: Arc<Mutex<Box<dyn AudioProcessor>>>,
     automation: Vec<AutomationEvent>,
     midi: Vec<MidiEvent>,
+    midi_output: Vec<MidiEvent>,
+    midi_delay: Option<NonNull<MidiDelay>>,
     latency: usize,
+    input_trim: f32,
+    transport: ProcessTransport,
+    /// Sum of the sidechain inputs, handed to the processor as its key.
+    sidechain: AudioBuffer,
 }
 
+unsafe impl Send for ProcessorNode {}
+
 impl ProcessorNode {
     pub fn new(
         processor: Arc<Mutex<Box<dyn AudioProcessor>>>,
         automation: Vec<AutomationEvent>,
-        midi: Vec<MidiEvent>,
+        mut midi: Vec<MidiEvent>,
         latency: usize,
     ) -> Self {
+        // Room for upstream MIDI, queued while the graph runs.
+        midi.reserve(MIDI_QUEUE_CAPACITY);
         Self {
             processor,
             automation,
             midi,
+            midi_output: Vec::with_capacity(MIDI_QUEUE_CAPACITY),
+            midi_delay: None,
             latency,
+            input_trim: 1.0,
+            transport: ProcessTransport::default(),
+            sidechain: AudioBuffer::new(0, 0),
         }
     }
+
+    /// Reserves the key buffer for a node with routed sidechain inputs.
+    pub fn with_sidechain(mut self, channels: usize, max_block: usize) -> Self {
+        self.sidechain = AudioBuffer::new(channels, max_block);
+        self
+    }
+
+    /// Linear gain applied at the head of the track: to the summed upstream
+    /// signal before the processor sees it or, for a source without
+    /// upstream audio, to what it produces before it reaches any insert or
+    /// the fader.
+    pub fn with_input_trim(mut self, trim: f32) -> Self {
+        self.input_trim = trim;
+        self
+    }
+
+    /// Transport passed to the processor with the block.
+    pub fn with_transport(mut self, transport: ProcessTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Holds the MIDI the processor emits until it is due downstream. The
+    /// pointer must stay valid for as long as the node is processed.
+    pub(crate) fn with_midi_delay(mut self, delay: NonNull<MidiDelay>) -> Self {
+        self.midi_delay = Some(delay);
+        self
+    }
 }
 
 impl DspNode for ProcessorNode {
@@ -154,14 +284,39 @@ impl DspNode for ProcessorNode {
 
     fn process(
         &mut self,
-        _inputs: &[&AudioBuffer],
+        inputs: &[&AudioBuffer],
+        output: &mut AudioBuffer,
+        frames: usize,
+    ) -> anyhow::Result<()> {
+        self.process_with_sidechain(inputs, &[], output, frames)
+    }
+
+    fn process_with_sidechain(
+        &mut self,
+        inputs: &[&AudioBuffer],
+        sidechain: &[&AudioBuffer],
         output: &mut AudioBuffer,
-        _frames: usize,
+        frames: usize,
     ) -> anyhow::Result<()> {
+        self.midi_output.clear();
         if output.channel_count() == 0 || output.len() == 0 {
             return Ok(());
         }
 
+        for input in inputs {
+            let channels = output.channel_count().min(input.channel_count());
+            for channel in 0..channels {
+                let source = input.channel(channel);
+                for (target, sample) in output.channel_mut(channel).iter_mut().zip(source) {
+                    *target += *sample;
+                }
+            }
+        }
+        let is_source = inputs.is_empty();
+        if !is_source {
+            apply_trim(output, self.input_trim);
+        }
+
         let mut guard = self.processor.lock();
 
         for event in &self.automation {
@@ -172,11 +327,58 @@ impl DspNode for ProcessorNode {
             )?;
         }
 
-        if !self.midi.is_empty() {
-            guard.process_midi(&self.midi)?;
+        match self.midi_delay {
+            Some(mut delay) => {
+                // SAFETY: the pointer originates from a stable Box stored on the engine.
+                let delay = unsafe { delay.as_mut() };
+                guard.process_midi_with_output(&self.midi, delay.emitted_mut())?;
+                delay.release(self.latency, frames, &mut self.midi_output);
+            }
+            None => guard.process_midi_with_output(&self.midi, &mut Vec::new())?,
+        }
+
+        if sidechain.is_empty() || guard.aux_input_count() == 0 {
+            guard.process_with_context(output, &self.transport)?;
+        } else {
+            let channels = self.sidechain.channel_count().min(output.channel_count());
+            self.sidechain.resize(channels, frames);
+            self.sidechain.clear();
+            for key in sidechain {
+                for channel in 0..channels.min(key.channel_count()) {
+                    let source = key.channel(channel);
+                    for (target, sample) in
+                        self.sidechain.channel_mut(channel).iter_mut().zip(source)
+                    {
+                        *target += *sample;
+                    }
+                }
+            }
+            guard.process_with_aux(output, &[&self.sidechain], &self.transport)?;
         }
+        if is_source {
+            apply_trim(output, self.input_trim);
+        }
+        Ok(())
+    }
 
-        guard.process(output)
+    fn midi_output(&self) -> &[MidiEvent] {
+        &self.midi_output
+    }
+
+    fn receive_midi(&mut self, events: &[MidiEvent]) {
+        for event in events {
+            if !insert_sorted(&mut self.midi, event.clone()) {
+                break;
+            }
+        }
+    }
+}
+
+fn apply_trim(buffer: &mut AudioBuffer, trim: f32) {
+    if trim != 1.0 {
+        for sample in buffer.as_mut_slice() {
+            *sample *= trim;
+        }
     }
 }
 
@@ -294,16 +496,41 @@ impl DspNode for MixerNode {
     }
 }
 
+/// Mixer track, and with it the plugin node, that receives MIDI on
+/// `channel`. Plugin nodes feed the mixer track of their own index and
+/// events are routed to the track numbered by their channel; channels past
+/// the plugins or the mixer's tracks reach nobody.
+pub(crate) fn midi_track(channel: u8, plugins: usize, max_tracks: usize) -> Option<usize> {
+    let track = channel as usize;
+    (track < plugins && track < max_tracks).then_some(track)
+}
+
 /// Helper to assemble the pre-topologized graph for the current block.
+///
+/// `plugin_inputs` lists the upstream plugins of each plugin, whose summed
+/// output is handed to the processor before it runs; `plugin_sidechains`
+/// lists, the same way, the plugins keying its sidechain. A key with less
+/// latency than the plugin's main input is delayed by the difference through
+/// the `sidechain_delays` line of its (plugin, key) pair. Each plugin's
+/// `input_trims` entry (in dB) is applied at the head of its track; see
+/// [`ProcessorNode::with_input_trim`].
+#[allow(clippy::too_many_arguments)]
 pub fn build_graph(
     plugin_ids: &[PluginId],
     processors: &[Arc<Mutex<Box<dyn AudioProcessor>>>],
     latencies: &[usize],
+    plugin_inputs: &[Vec<usize>],
+    plugin_midi_inputs: &[Vec<usize>],
+    plugin_sidechains: &[Vec<usize>],
+    input_trims: &[f32],
     automation: &[Vec<AutomationEvent>],
     midi: &[MidiEvent],
+    transport: &ProcessTransport,
     mixer: NonNull<Mixer>,
     mixer_cfg: MixerConfig,
     delay_lines: &mut HashMap<PluginId, Box<DelayCompensator>>,
+    sidechain_delays: &mut HashMap<(PluginId, PluginId), Box<DelayCompensator>>,
+    midi_delays: &mut HashMap<PluginId, Box<MidiDelay>>,
     channels: usize,
     block_size: usize,
 ) -> GraphRunner {
@@ -311,43 +538,12 @@ pub fn build_graph(
 
     let mut nodes: Vec<NodeSpec> = Vec::new();
     let mut mixer_inputs = Vec::new();
-
-    let plugin_tracks: Vec<Option<u8>> = plugin_ids
-        .iter()
-        .enumerate()
-        .map(|(idx, _)| {
-            if idx < mixer_cfg.max_tracks {
-                Some(idx as u8)
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    let mut track_to_plugin: HashMap<u8, usize> = HashMap::new();
-    for (plugin_index, track) in plugin_tracks.iter().enumerate() {
-        if let Some(track) = track {
-            track_to_plugin.entry(*track).or_insert(plugin_index);
-        }
-    }
+    let mut processor_indices = Vec::with_capacity(plugin_ids.len());
 
     let mut midi_buckets: Vec<Vec<MidiEvent>> = vec![Vec::new(); plugin_ids.len()];
-    let mut midi_channel = |event: &MidiEvent| -> Option<u8> {
-        match event {
-            MidiEvent::NoteOn { channel, .. }
-            | MidiEvent::NoteOff { channel, .. }
-            | MidiEvent::ControlChange { channel, .. }
-            | MidiEvent::PitchBend { channel, .. } => Some(*channel),
-        }
-    };
-
     for event in midi {
-        if let Some(channel) = midi_channel(event) {
-            if let Some(&plugin_index) = track_to_plugin.get(&channel) {
-                if let Some(bucket) = midi_buckets.get_mut(plugin_index) {
-                    bucket.push(event.clone());
-                }
-            }
+        if let Some(track) = midi_track(event.channel(), plugin_ids.len(), mixer_cfg.max_tracks) {
+            midi_buckets[track].push(event.clone());
         }
     }
 
@@ -355,16 +551,33 @@ pub fn build_graph(
         let automation_bucket = automation.get(index).cloned().unwrap_or_default();
         let midi_bucket = midi_buckets.get(index).cloned().unwrap_or_default();
         let latency = *latencies.get(index).unwrap_or(&0);
+        let trim_db = input_trims.get(index).copied().unwrap_or(0.0);
+        let midi_delay = midi_delays
+            .entry(*plugin_id)
+            .or_insert_with(|| Box::new(MidiDelay::new()));
+        let mut node = ProcessorNode::new(
+            Arc::clone(processor),
+            automation_bucket,
+            midi_bucket,
+            latency,
+        )
+        .with_input_trim(10.0f32.powf(trim_db / 20.0))
+        .with_transport(*transport)
+        .with_midi_delay(NonNull::from(midi_delay.as_mut()));
+        if plugin_sidechains
+            .get(index)
+            .is_some_and(|keys| !keys.is_empty())
+        {
+            node = node.with_sidechain(channels, block_size);
+        }
         let proc_idx = nodes.len();
         nodes.push(NodeSpec {
-            node: Box::new(ProcessorNode::new(
-                Arc::clone(processor),
-                automation_bucket,
-                midi_bucket,
-                latency,
-            )),
+            node: Box::new(node),
             inputs: Vec::new(),
+            midi_inputs: Vec::new(),
+            sidechain_inputs: Vec::new(),
         });
+        processor_indices.push(proc_idx);
 
         let extra_delay = max_latency.saturating_sub(latency);
         let final_idx = if extra_delay > 0 {
@@ -376,6 +589,8 @@ pub fn build_graph(
             nodes.push(NodeSpec {
                 node: Box::new(DelayNode::new(ptr, extra_delay, channels, block_size)),
                 inputs: vec![proc_idx],
+                midi_inputs: Vec::new(),
+                sidechain_inputs: Vec::new(),
             });
             idx
         } else {
@@ -388,10 +603,73 @@ pub fn build_graph(
         mixer_inputs.push(final_idx);
     }
 
+    for (index, sources) in plugin_inputs.iter().enumerate() {
+        let Some(&proc_idx) = processor_indices.get(index) else {
+            continue;
+        };
+        nodes[proc_idx].inputs = sources
+            .iter()
+            .filter_map(|source| processor_indices.get(*source).copied())
+            .collect();
+    }
+    for (index, sources) in plugin_midi_inputs.iter().enumerate() {
+        let Some(&proc_idx) = processor_indices.get(index) else {
+            continue;
+        };
+        nodes[proc_idx].midi_inputs = sources
+            .iter()
+            .filter_map(|source| processor_indices.get(*source).copied())
+            .collect();
+    }
+    for (index, sources) in plugin_sidechains.iter().enumerate() {
+        let Some(&proc_idx) = processor_indices.get(index) else {
+            continue;
+        };
+        // A key that is earlier than the main input is delayed to line up
+        // with it, the way the mixer aligns tracks to the slowest plugin.
+        let input_latency = plugin_inputs
+            .get(index)
+            .into_iter()
+            .flatten()
+            .filter_map(|source| latencies.get(*source).copied())
+            .max()
+            .unwrap_or(0);
+        let mut keys = Vec::with_capacity(sources.len());
+        for &source in sources {
+            let Some(&source_idx) = processor_indices.get(source) else {
+                continue;
+            };
+            let key_latency = latencies.get(source).copied().unwrap_or(0);
+            let extra_delay = input_latency.saturating_sub(key_latency);
+            let route = (plugin_ids[index], plugin_ids[source]);
+            if extra_delay == 0 {
+                if let Some(delay) = sidechain_delays.get_mut(&route) {
+                    delay.reset();
+                }
+                keys.push(source_idx);
+                continue;
+            }
+            let entry = sidechain_delays
+                .entry(route)
+                .or_insert_with(|| Box::new(DelayCompensator::new()));
+            let ptr = NonNull::from(entry.as_mut());
+            keys.push(nodes.len());
+            nodes.push(NodeSpec {
+                node: Box::new(DelayNode::new(ptr, extra_delay, channels, block_size)),
+                inputs: vec![source_idx],
+                midi_inputs: Vec::new(),
+                sidechain_inputs: Vec::new(),
+            });
+        }
+        nodes[proc_idx].sidechain_inputs = keys;
+    }
+
     let master_index = nodes.len();
     nodes.push(NodeSpec {
         node: Box::new(MixerNode::new(mixer, mixer_cfg)),
         inputs: mixer_inputs,
+        midi_inputs: Vec::new(),
+        sidechain_inputs: Vec::new(),
     });
 
     GraphRunner::new(nodes, master_index, channels, block_size)
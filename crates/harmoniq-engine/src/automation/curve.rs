@@ -1,9 +1,54 @@
 use std::ops::Range;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CurveShape {
     Step,
     Linear,
+    /// Cubic Bezier easing between the two points. `tension` runs from -1
+    /// to 1: 0 is a straight line, positive values ease in and out and
+    /// negative values rush through the ends and flatten in the middle. The
+    /// curve never leaves the range spanned by its two points.
+    Bezier {
+        tension: f32,
+    },
+}
+
+/// Progress along a Bezier segment at `t` in `0.0..=1.0`.
+fn bezier_ease(tension: f32, t: f64) -> f64 {
+    // Control values inside 0..=1 keep the curve inside the hull of its end
+    // points; at tension 0 they sit at thirds and the curve is linear.
+    let tension = tension.clamp(-1.0, 1.0) as f64;
+    let first = (1.0 - tension) / 3.0;
+    let second = 1.0 - first;
+    let inverse = 1.0 - t;
+    3.0 * inverse * inverse * t * first + 3.0 * inverse * t * t * second + t * t * t
+}
+
+/// Value at `sample` on the segment that runs from `prev` to `next`.
+fn interpolate(prev: &CurvePoint, next: &CurvePoint, sample: u64) -> f32 {
+    match prev.shape {
+        CurveShape::Step => prev.value,
+        CurveShape::Linear => {
+            let span = next.sample.saturating_sub(prev.sample);
+            if span == 0 {
+                return next.value;
+            }
+            let position = sample.saturating_sub(prev.sample) as f32;
+            let span = span as f32;
+            let t = (position / span).clamp(0.0, 1.0);
+            prev.value + (next.value - prev.value) * t
+        }
+        CurveShape::Bezier { tension } => {
+            let span = next.sample.saturating_sub(prev.sample);
+            if span == 0 {
+                return next.value;
+            }
+            // Positions in f64 so long segments stay exact at any sample.
+            let t = (sample.saturating_sub(prev.sample) as f64 / span as f64).clamp(0.0, 1.0);
+            let eased = bezier_ease(tension, t);
+            (prev.value as f64 + (next.value as f64 - prev.value as f64) * eased) as f32
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -49,16 +94,131 @@ impl AutomationCurve {
         &self.points
     }
 
-    pub fn add_point(&mut self, point: CurvePoint) {
+    /// Makes this curve a copy of `other` in its existing storage, which
+    /// only allocates when `other` has more points than there is room for.
+    pub fn copy_from(&mut self, other: &AutomationCurve) {
+        self.points.clone_from(&other.points);
+    }
+
+    /// Makes room for at least `len` points in total.
+    pub fn reserve_total(&mut self, len: usize) {
+        self.points.reserve(len.saturating_sub(self.points.len()));
+    }
+
+    /// Scales every point position by `ratio`, e.g. after a sample-rate
+    /// change. Points that land on the same sample keep the later one.
+    pub fn rescale(&mut self, ratio: f64) {
+        if ratio <= 0.0 {
+            return;
+        }
+        for point in &mut self.points {
+            point.sample = (point.sample as f64 * ratio).round() as u64;
+        }
+        self.points.dedup_by(|later, earlier| {
+            if later.sample == earlier.sample {
+                *earlier = later.clone();
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    /// Inserts `point`, replacing any point on the same sample, and returns
+    /// its index.
+    pub fn add_point(&mut self, point: CurvePoint) -> usize {
         match self
             .points
             .binary_search_by_key(&point.sample, |existing| existing.sample)
         {
-            Ok(index) => self.points[index] = point,
-            Err(index) => self.points.insert(index, point),
+            Ok(index) => {
+                self.points[index] = point;
+                index
+            }
+            Err(index) => {
+                self.points.insert(index, point);
+                index
+            }
         }
     }
 
+    pub fn remove_point(&mut self, index: usize) -> Option<CurvePoint> {
+        (index < self.points.len()).then(|| self.points.remove(index))
+    }
+
+    /// Sets the shape of the segment that starts at point `index`.
+    pub fn set_shape(&mut self, index: usize, shape: CurveShape) -> bool {
+        match self.points.get_mut(index) {
+            Some(point) => {
+                point.shape = shape;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn index_of(&self, sample: u64) -> Option<usize> {
+        self.points
+            .binary_search_by_key(&sample, |point| point.sample)
+            .ok()
+    }
+
+    /// Removes the points in `start..end` and returns how many there were.
+    pub fn remove_range(&mut self, start: u64, end: u64) -> usize {
+        let range = self.range_indices(start, end);
+        let removed = range.len();
+        self.points.drain(range);
+        removed
+    }
+
+    /// Drops points that lie within `tolerance` of the segment joining the
+    /// points kept around them (Ramer–Douglas–Peucker), and returns how many
+    /// were removed. The first and last points always stay.
+    ///
+    /// Distances are measured in value, at each point and, after a step, just
+    /// before the next point, so a jump larger than `tolerance` survives.
+    /// Allocates; call it from editing code, not the audio thread.
+    pub fn simplify(&mut self, tolerance: f32) -> usize {
+        let count = self.points.len();
+        if count < 3 || tolerance.is_nan() {
+            return 0;
+        }
+        let tolerance = tolerance.max(0.0);
+
+        let mut keep = vec![false; count];
+        keep[0] = true;
+        keep[count - 1] = true;
+        let mut spans = vec![(0, count - 1)];
+        while let Some((first, last)) = spans.pop() {
+            let start = &self.points[first];
+            let end = &self.points[last];
+            let mut worst = None;
+            let mut worst_error = tolerance;
+            for index in first + 1..last {
+                let point = &self.points[index];
+                let mut error = (point.value - interpolate(start, end, point.sample)).abs();
+                if point.shape == CurveShape::Step {
+                    // The held value until the next point is part of the curve.
+                    let held_until = self.points[index + 1].sample - 1;
+                    error = error.max((point.value - interpolate(start, end, held_until)).abs());
+                }
+                if error > worst_error {
+                    worst_error = error;
+                    worst = Some(index);
+                }
+            }
+            if let Some(index) = worst {
+                keep[index] = true;
+                spans.push((first, index));
+                spans.push((index, last));
+            }
+        }
+
+        let mut flags = keep.into_iter();
+        self.points.retain(|_| flags.next().unwrap_or(true));
+        count - self.points.len()
+    }
+
     pub fn remove_after(&mut self, sample: u64) {
         let index = self.partition_point(|point| point.sample <= sample);
         self.points.truncate(index);
@@ -79,20 +239,7 @@ impl AutomationCurve {
             return Some(prev.value);
         }
 
-        let next = &self.points[index];
-        match prev.shape {
-            CurveShape::Step => Some(prev.value),
-            CurveShape::Linear => {
-                let span = next.sample.saturating_sub(prev.sample);
-                if span == 0 {
-                    return Some(next.value);
-                }
-                let position = sample.saturating_sub(prev.sample) as f32;
-                let span = span as f32;
-                let t = (position / span).clamp(0.0, 1.0);
-                Some(prev.value + (next.value - prev.value) * t)
-            }
-        }
+        Some(interpolate(prev, &self.points[index], sample))
     }
 
     pub fn last_value_before(&self, sample: u64) -> Option<f32> {
@@ -156,6 +303,121 @@ mod tests {
         assert_eq!(curve.value_at(10), Some(1.0));
     }
 
+    /// Deterministic noise in `-amplitude..amplitude`.
+    fn noise(state: &mut u64, amplitude: f32) -> f32 {
+        *state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1);
+        ((*state >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0) * amplitude
+    }
+
+    #[test]
+    fn simplify_reduces_a_noisy_ramp_to_its_ends() {
+        let mut state = 7;
+        let mut curve = AutomationCurve::new();
+        for index in 0..2_000u64 {
+            let value = index as f32 / 1_999.0 + noise(&mut state, 0.002);
+            curve.add_point(CurvePoint::new(index * 10, value, CurveShape::Linear));
+        }
+        let original = curve.clone();
+        let (first, last) = (original.points[0].clone(), original.points[1_999].clone());
+
+        assert_eq!(curve.simplify(0.01), 1_998);
+        assert_eq!(curve.len(), 2);
+        assert_eq!(curve.points[0].sample, first.sample);
+        assert_eq!(curve.points[0].value, first.value);
+        assert_eq!(curve.points[1].sample, last.sample);
+        assert_eq!(curve.points[1].value, last.value);
+        for point in original.points() {
+            let difference = curve.value_at(point.sample).unwrap() - point.value;
+            assert!(difference.abs() <= 0.01, "{}", point.sample);
+        }
+    }
+
+    #[test]
+    fn simplify_keeps_step_jumps() {
+        let mut state = 11;
+        let mut curve = AutomationCurve::new();
+        for index in 0..200u64 {
+            let level = if index < 100 { 0.2 } else { 0.7 };
+            let value = level + noise(&mut state, 0.002);
+            curve.add_point(CurvePoint::new(index * 10, value, CurveShape::Step));
+        }
+        let original = curve.clone();
+
+        let removed = curve.simplify(0.01);
+        assert!(curve.len() <= 4, "{} points left", curve.len());
+        assert_eq!(removed, 200 - curve.len());
+        assert!(curve.index_of(1_000).is_some());
+        for sample in 0..2_000 {
+            let difference = curve.value_at(sample).unwrap() - original.value_at(sample).unwrap();
+            assert!(difference.abs() <= 0.01, "{sample}");
+        }
+    }
+
+    #[test]
+    fn simplify_leaves_short_curves_alone() {
+        let mut curve = AutomationCurve::new();
+        curve.add_point(CurvePoint::new(0, 0.0, CurveShape::Linear));
+        curve.add_point(CurvePoint::new(10, 1.0, CurveShape::Linear));
+        assert_eq!(curve.simplify(1.0), 0);
+        assert_eq!(curve.len(), 2);
+    }
+
+    #[test]
+    fn bezier_stays_between_its_points() {
+        for tension in [-1.0, -0.5, 0.25, 0.5, 1.0, 4.0] {
+            let mut curve = AutomationCurve::new();
+            curve.add_point(CurvePoint::new(100, 0.8, CurveShape::Bezier { tension }));
+            curve.add_point(CurvePoint::new(1_100, -0.2, CurveShape::Step));
+            let mut previous = 0.8;
+            for sample in 100..=1_100 {
+                let value = curve.value_at(sample).unwrap();
+                assert!(
+                    (-0.2..=0.8).contains(&value),
+                    "{tension} at {sample}: {value}"
+                );
+                assert!(value <= previous + 1e-6, "{tension} at {sample}");
+                previous = value;
+            }
+            assert_eq!(curve.value_at(1_100), Some(-0.2));
+        }
+    }
+
+    #[test]
+    fn bezier_at_zero_tension_matches_linear() {
+        let mut bezier = AutomationCurve::new();
+        bezier.add_point(CurvePoint::new(
+            0,
+            0.25,
+            CurveShape::Bezier { tension: 0.0 },
+        ));
+        bezier.add_point(CurvePoint::new(48_000, 0.75, CurveShape::Step));
+        let mut linear = AutomationCurve::new();
+        linear.add_point(CurvePoint::new(0, 0.25, CurveShape::Linear));
+        linear.add_point(CurvePoint::new(48_000, 0.75, CurveShape::Step));
+        for sample in (0..=48_000).step_by(97) {
+            let difference = bezier.value_at(sample).unwrap() - linear.value_at(sample).unwrap();
+            assert!(difference.abs() < 1e-6, "{sample}");
+        }
+    }
+
+    #[test]
+    fn removes_points_and_changes_shapes() {
+        let mut curve = AutomationCurve::new();
+        curve.add_point(CurvePoint::new(0, 0.0, CurveShape::Step));
+        let index = curve.add_point(CurvePoint::new(10, 1.0, CurveShape::Step));
+        assert_eq!(curve.index_of(10), Some(index));
+        assert!(curve.set_shape(0, CurveShape::Linear));
+        assert!((curve.value_at(5).unwrap() - 0.5).abs() < 1e-6);
+        assert_eq!(
+            curve.remove_point(index).map(|point| point.sample),
+            Some(10)
+        );
+        assert_eq!(curve.remove_point(index).map(|point| point.sample), None);
+        assert_eq!(curve.len(), 1);
+    }
+
     #[test]
     fn step_hold() {
         let mut curve = AutomationCurve::new();
@@ -59,6 +59,15 @@ pub enum AutomationCommand {
         sample: u64,
         value: Option<f32>,
     },
+    /// Playback started at `sample`; touches now record according to each
+    /// parameter's write mode.
+    StartPass {
+        sample: u64,
+    },
+    /// Playback stopped at `sample`, closing any latched or written region.
+    StopPass {
+        sample: u64,
+    },
 }
 
 #[derive(Clone)]
@@ -109,6 +118,14 @@ impl AutomationLane {
         }
     }
 
+    /// Scales the recorded curves of every parameter by `ratio`.
+    pub fn rescale_positions(&mut self, ratio: f64) {
+        for lane in self.parameters.values_mut() {
+            lane.curve.rescale(ratio);
+            lane.baseline.rescale(ratio);
+        }
+    }
+
     pub fn apply_command(&mut self, command: AutomationCommand) {
         match command {
             AutomationCommand::RegisterParameter(spec) => {
@@ -148,13 +165,28 @@ impl AutomationLane {
                     lane.release(sample, value);
                 }
             }
+            AutomationCommand::StartPass { sample } => {
+                for lane in self.parameters.values_mut() {
+                    lane.start_pass(sample);
+                }
+            }
+            AutomationCommand::StopPass { sample } => {
+                for lane in self.parameters.values_mut() {
+                    lane.stop_pass(sample);
+                }
+            }
         }
     }
 
-    pub fn render(&mut self, block_start: u64, block_len: u32, output: &mut Vec<AutomationEvent>) {
+    /// Applies every command queued through an [`AutomationSender`].
+    pub fn drain_commands(&mut self) {
         while let Some(command) = self.consumer.pop() {
             self.apply_command(command);
         }
+    }
+
+    pub fn render(&mut self, block_start: u64, block_len: u32, output: &mut Vec<AutomationEvent>) {
+        self.drain_commands();
 
         for lane in self.parameters.values_mut() {
             lane.render_into(self.plugin_id, block_start, block_len, output);
@@ -163,6 +195,15 @@ impl AutomationLane {
         output.sort_by_key(|event| (event.sample_offset, event.parameter));
     }
 
+    /// Forgets the last emitted values so the next render re-sends every
+    /// parameter's value at its block start, e.g. when playback restarts.
+    pub fn rewind(&mut self) {
+        for lane in self.parameters.values_mut() {
+            lane.last_value = None;
+            lane.needs_initial_event = true;
+        }
+    }
+
     pub fn parameter_index_by_name(&self, name: &str) -> Option<usize> {
         self.parameters.iter().find_map(|(index, lane)| {
             if lane.spec().name.eq_ignore_ascii_case(name) {
@@ -173,6 +214,10 @@ impl AutomationLane {
         })
     }
 
+    pub fn curve(&self, parameter: usize) -> Option<&AutomationCurve> {
+        self.parameters.get(&parameter).map(|lane| &lane.curve)
+    }
+
     pub fn parameter_spec(&self, parameter: usize) -> Option<ParameterSpec> {
         self.parameters
             .get(&parameter)
@@ -186,6 +231,14 @@ struct ParameterLane {
     recorder: AutomationRecorder,
     last_value: Option<f32>,
     needs_initial_event: bool,
+    /// The curve as it was when playback started, while `in_pass` is set.
+    /// Kept with room for every point of `curve`, so starting a pass copies
+    /// into it without allocating.
+    baseline: AutomationCurve,
+    in_pass: bool,
+    /// Last point written in the open region and the value held after it.
+    /// Playback reads the held value past it until the region closes.
+    written: Option<(u64, f32)>,
 }
 
 impl ParameterLane {
@@ -196,6 +249,9 @@ impl ParameterLane {
             recorder: AutomationRecorder::new(AutomationWriteMode::Read),
             last_value: None,
             needs_initial_event: true,
+            baseline: AutomationCurve::new(),
+            in_pass: false,
+            written: None,
         }
     }
 
@@ -205,6 +261,9 @@ impl ParameterLane {
         self.needs_initial_event = true;
         self.curve = AutomationCurve::new();
         self.recorder = AutomationRecorder::new(AutomationWriteMode::Read);
+        self.baseline.clear();
+        self.in_pass = false;
+        self.written = None;
     }
 
     fn set_mode(&mut self, mode: AutomationWriteMode) {
@@ -217,24 +276,132 @@ impl ParameterLane {
 
     fn draw(&mut self, sample: u64, value: f32, shape: CurveShape) {
         let value = self.spec.clamp(value);
-        self.curve.add_point(CurvePoint::new(sample, value, shape));
+        self.add_point(CurvePoint::new(sample, value, shape));
         self.last_value = None;
     }
 
+    /// Adds `point` to the curve, growing the baseline's storage with it.
+    fn add_point(&mut self, point: CurvePoint) {
+        self.curve.add_point(point);
+        self.baseline.reserve_total(self.curve.len());
+    }
+
     fn touch(&mut self, sample: u64, value: f32, shape: CurveShape) {
-        if self.recorder.begin_touch() {
+        if !self.recorder.begin_touch() {
+            return;
+        }
+        if self.in_pass {
+            self.write(sample, value, shape);
+        } else {
             self.draw(sample, value, shape);
         }
     }
 
     fn release(&mut self, sample: u64, value: Option<f32>) {
-        if self.recorder.end_touch() {
+        if !self.recorder.end_touch() {
+            return;
+        }
+        if self.in_pass {
+            // Touch: hold up to the release, then return to what was there.
+            let held = value.or(self.written.map(|(_, held)| held));
+            if let Some(held) = held {
+                self.write(sample, held, CurveShape::Step);
+            }
+            self.close_region(sample, true);
+        } else {
             let value = value.unwrap_or(self.spec.default);
             self.draw(sample, value, CurveShape::Step);
         }
     }
 
+    fn start_pass(&mut self, sample: u64) {
+        self.baseline.copy_from(&self.curve);
+        self.in_pass = true;
+        self.written = None;
+        if self.recorder.mode() == AutomationWriteMode::Write {
+            let value = self.value_at_or_default(sample);
+            self.write(sample, value, CurveShape::Step);
+        }
+    }
+
+    fn stop_pass(&mut self, sample: u64) {
+        if !self.in_pass {
+            return;
+        }
+        if let Some((_, held)) = self.written {
+            self.write(sample, held, CurveShape::Step);
+        }
+        let revert_to_default = self.recorder.mode() == AutomationWriteMode::Touch;
+        self.close_region(sample, revert_to_default);
+        self.recorder.end_pass();
+        self.in_pass = false;
+    }
+
+    /// Records `value` at `sample`, replacing every earlier point of the
+    /// open region since the previous write.
+    fn write(&mut self, sample: u64, value: f32, shape: CurveShape) {
+        let value = self.spec.clamp(value);
+        match self.written {
+            Some((last, _)) => {
+                self.curve.remove_range(last + 1, sample + 1);
+            }
+            None => {
+                // Punching in: pin the curve just before the region so the
+                // segment leading into it keeps its recorded shape.
+                if let Some(anchor) = sample.checked_sub(1) {
+                    if let Some(before) = self.baseline_value(anchor) {
+                        self.add_point(CurvePoint::new(anchor, before, CurveShape::Step));
+                    }
+                }
+            }
+        }
+        self.add_point(CurvePoint::new(sample, value, shape));
+        self.written = Some((sample, value));
+        self.last_value = None;
+    }
+
+    /// Ends the open region at `end`. From the next sample the curve follows
+    /// the automation recorded before the pass again; an empty lane reverts
+    /// to the parameter default when `revert_to_default` is set and keeps the
+    /// written value otherwise.
+    fn close_region(&mut self, end: u64, revert_to_default: bool) {
+        if self.written.take().is_none() {
+            return;
+        }
+        let resume = end + 1;
+        let points = self.baseline.points();
+        let index = points.partition_point(|point| point.sample <= resume);
+        let shape = index
+            .checked_sub(1)
+            .filter(|_| self.in_pass)
+            .map_or(CurveShape::Step, |index| points[index].shape);
+        let value = match self.baseline_value(resume) {
+            Some(value) => value,
+            None if revert_to_default => self.spec.default,
+            None => return,
+        };
+        self.add_point(CurvePoint::new(resume, value, shape));
+        self.last_value = None;
+    }
+
+    fn baseline_value(&self, sample: u64) -> Option<f32> {
+        if !self.in_pass {
+            return None;
+        }
+        let baseline = &self.baseline;
+        let first = baseline.points().first()?;
+        if sample < first.sample {
+            return Some(self.spec.default);
+        }
+        baseline.value_at(sample)
+    }
+
     fn value_at_or_default(&self, sample: u64) -> f32 {
+        if let Some((last, held)) = self.written {
+            if sample > last {
+                return held;
+            }
+        }
         let first_point_sample = self.curve.points().first().map(|point| point.sample);
         if let Some(first_sample) = first_point_sample {
             if sample < first_sample {
@@ -3,10 +3,15 @@ use crate::plugin::PluginId;
 pub mod curve;
 pub mod lane;
 pub mod record;
+pub mod target;
 
 pub use curve::{AutomationCurve, CurvePoint, CurveShape};
 pub use lane::{AutomationCommand, AutomationLane, AutomationSender, ParameterSpec};
 pub use record::{AutomationRecorder, AutomationWriteMode};
+pub use target::{
+    AutomationSlot, AutomationTarget, LaneTarget, TargetParseError, MIXER_GAIN_PARAM,
+    MIXER_PAN_PARAM,
+};
 
 #[derive(Debug, Clone)]
 pub struct AutomationEvent {
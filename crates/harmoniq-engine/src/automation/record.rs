@@ -71,6 +71,12 @@ impl AutomationRecorder {
         }
     }
 
+    /// Ends the current playback pass: touches and latches are released.
+    pub fn end_pass(&mut self) {
+        self.touching = false;
+        self.latched = false;
+    }
+
     pub fn is_touching(&self) -> bool {
         self.touching
     }
@@ -0,0 +1,198 @@
+//! Stable addresses for automated parameters.
+//!
+//! An [`AutomationTarget`] names a parameter by track, slot and parameter id
+//! rather than by display name, so automation reconnects to the same control
+//! after a project is reloaded or a plugin renames its parameters. The
+//! canonical string form is used in project files:
+//!
+//! * `track:3/mixer/param:0`
+//! * `track:3/instrument/param:12`
+//! * `track:3/insert:1/param:4`
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::core::state::TrackId;
+
+/// Mixer parameter id of the track fader.
+pub const MIXER_GAIN_PARAM: u32 = 0;
+/// Mixer parameter id of the track pan.
+pub const MIXER_PAN_PARAM: u32 = 1;
+
+/// Where on a track the automated parameter lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AutomationSlot {
+    /// The track's mixer strip, addressed by the `MIXER_*_PARAM` ids.
+    Mixer,
+    /// The track's instrument or source processor.
+    Instrument,
+    /// An insert effect, by slot index.
+    Insert(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AutomationTarget {
+    pub track: TrackId,
+    pub slot: AutomationSlot,
+    pub parameter: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TargetParseError {
+    #[error("automation target '{0}' is missing a track")]
+    MissingTrack(String),
+    #[error("automation target '{0}' has an unknown slot")]
+    UnknownSlot(String),
+    #[error("automation target '{0}' is missing a parameter id")]
+    MissingParameter(String),
+    #[error("automation target '{0}' has trailing components")]
+    Trailing(String),
+}
+
+impl AutomationTarget {
+    pub fn new(track: TrackId, slot: AutomationSlot, parameter: u32) -> Self {
+        Self {
+            track,
+            slot,
+            parameter,
+        }
+    }
+
+    pub fn mixer(track: TrackId, parameter: u32) -> Self {
+        Self::new(track, AutomationSlot::Mixer, parameter)
+    }
+
+    pub fn instrument(track: TrackId, parameter: u32) -> Self {
+        Self::new(track, AutomationSlot::Instrument, parameter)
+    }
+
+    pub fn insert(track: TrackId, slot: u32, parameter: u32) -> Self {
+        Self::new(track, AutomationSlot::Insert(slot), parameter)
+    }
+
+    /// Converts a free-form target string from older projects.
+    ///
+    /// Canonical strings parse as usual. The mixer names `volume`, `gain`
+    /// and `pan` map to the track's mixer strip, and a bare number or
+    /// `param:<n>` addresses the instrument parameter with that id. Other
+    /// names depend on the plugin that was loaded and cannot be converted
+    /// without it; see `HarmoniqEngine::automation_target_for`.
+    pub fn from_legacy(track: TrackId, target: &str) -> Option<Self> {
+        let target = target.trim();
+        if let Ok(parsed) = target.parse() {
+            return Some(parsed);
+        }
+        match target.to_ascii_lowercase().as_str() {
+            "volume" | "gain" | "fader" => return Some(Self::mixer(track, MIXER_GAIN_PARAM)),
+            "pan" => return Some(Self::mixer(track, MIXER_PAN_PARAM)),
+            _ => {}
+        }
+        let id = target.strip_prefix("param:").unwrap_or(target);
+        id.parse().ok().map(|id| Self::instrument(track, id))
+    }
+}
+
+impl fmt::Display for AutomationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "track:{}/", self.track)?;
+        match self.slot {
+            AutomationSlot::Mixer => f.write_str("mixer")?,
+            AutomationSlot::Instrument => f.write_str("instrument")?,
+            AutomationSlot::Insert(slot) => write!(f, "insert:{slot}")?,
+        }
+        write!(f, "/param:{}", self.parameter)
+    }
+}
+
+impl FromStr for AutomationTarget {
+    type Err = TargetParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts = text.split('/');
+        let track = parts
+            .next()
+            .and_then(|part| part.strip_prefix("track:"))
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| TargetParseError::MissingTrack(text.to_string()))?;
+        let slot = match parts.next() {
+            Some("mixer") => AutomationSlot::Mixer,
+            Some("instrument") => AutomationSlot::Instrument,
+            Some(part) => part
+                .strip_prefix("insert:")
+                .and_then(|slot| slot.parse().ok())
+                .map(AutomationSlot::Insert)
+                .ok_or_else(|| TargetParseError::UnknownSlot(text.to_string()))?,
+            None => return Err(TargetParseError::UnknownSlot(text.to_string())),
+        };
+        let parameter = parts
+            .next()
+            .and_then(|part| part.strip_prefix("param:"))
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| TargetParseError::MissingParameter(text.to_string()))?;
+        if parts.next().is_some() {
+            return Err(TargetParseError::Trailing(text.to_string()));
+        }
+        Ok(Self::new(track, slot, parameter))
+    }
+}
+
+/// What an automation lane drives. Lanes from older projects whose target
+/// string names a plugin parameter by display name stay `Unresolved` until
+/// that plugin is loaded, and keep the string when saved.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum LaneTarget {
+    Resolved(AutomationTarget),
+    Unresolved(String),
+}
+
+impl LaneTarget {
+    pub fn resolved(&self) -> Option<&AutomationTarget> {
+        match self {
+            Self::Resolved(target) => Some(target),
+            Self::Unresolved(_) => None,
+        }
+    }
+}
+
+impl From<AutomationTarget> for LaneTarget {
+    fn from(target: AutomationTarget) -> Self {
+        Self::Resolved(target)
+    }
+}
+
+impl From<String> for LaneTarget {
+    fn from(text: String) -> Self {
+        match text.parse() {
+            Ok(target) => Self::Resolved(target),
+            Err(_) => Self::Unresolved(text),
+        }
+    }
+}
+
+impl From<LaneTarget> for String {
+    fn from(target: LaneTarget) -> Self {
+        match target {
+            LaneTarget::Resolved(target) => target.to_string(),
+            LaneTarget::Unresolved(text) => text,
+        }
+    }
+}
+
+impl TryFrom<String> for AutomationTarget {
+    type Error = TargetParseError;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<AutomationTarget> for String {
+    fn from(target: AutomationTarget) -> Self {
+        target.to_string()
+    }
+}
@@ -26,6 +26,11 @@ pub struct BufferConfig {
     pub sample_rate: f32,
     pub block_size: usize,
     pub layout: ChannelLayout,
+    /// Sums the master bus and applies the master fader in `f64`. The sum is
+    /// converted to `f32` before the master inserts, which run in `f32` like
+    /// every other processor.
+    #[serde(default)]
+    pub high_precision_master: bool,
 }
 
 impl BufferConfig {
@@ -34,8 +39,15 @@ impl BufferConfig {
             sample_rate,
             block_size,
             layout,
+            high_precision_master: false,
         }
     }
+
+    /// See [`high_precision_master`](Self::high_precision_master).
+    pub fn with_high_precision_master(mut self, enabled: bool) -> Self {
+        self.high_precision_master = enabled;
+        self
+    }
 }
 
 /// Owned planar audio buffer that can expose mutable slices per channel without
@@ -100,6 +112,29 @@ impl AudioBuffer {
         &mut self.data[start..start + self.frames]
     }
 
+    /// Writes one stereo frame using the buffer's channel layout.
+    ///
+    /// Mono buffers receive the average of both sides, stereo buffers get the
+    /// pair as-is and wider buffers repeat the pair across each channel pair
+    /// (left on even channels, right on odd ones). Out of range frames are
+    /// ignored.
+    #[inline]
+    pub fn write_stereo_frame(&mut self, index: usize, left: f32, right: f32) {
+        if index >= self.frames {
+            return;
+        }
+        match self.channels {
+            0 => {}
+            1 => self.data[index] = 0.5 * (left + right),
+            channels => {
+                for channel in 0..channels {
+                    let value = if channel % 2 == 0 { left } else { right };
+                    self.data[channel * self.frames + index] = value;
+                }
+            }
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &f32> {
         self.data.iter()
     }
@@ -108,6 +108,57 @@ impl AudioClip {
         super::stretch::stretch_clip(self, ratio, quality)
     }
 
+    /// Converts the clip to `target_rate`, keeping its duration in seconds.
+    pub fn resample_to(&self, target_rate: f32) -> Self {
+        let source_rate = self.sample_rate();
+        if target_rate <= 0.0 || source_rate <= 0.0 || source_rate == target_rate {
+            return self.clone();
+        }
+        if self.frames() == 0 {
+            return Self::empty(target_rate, self.channels());
+        }
+        let ratio = target_rate / source_rate;
+        Self::with_sample_rate(target_rate, super::stretch::resample_channels(self, ratio))
+    }
+
+    /// Repeats the clip until it is `frames` long. With a crossfade, the last
+    /// `length` frames of each pass fade out while the head of the next pass
+    /// fades in, so the loop period shrinks to `frames() - length`.
+    pub fn looped(&self, frames: usize, crossfade: Option<FadeSpec>) -> Self {
+        let source_frames = self.frames();
+        if source_frames == 0 {
+            return Self::with_sample_rate(
+                self.sample_rate(),
+                vec![vec![0.0; frames]; self.channels()],
+            );
+        }
+        let fade = crossfade.unwrap_or(FadeSpec::new(0, FadeCurve::Linear));
+        let overlap = fade.length().min(source_frames - 1);
+        let period = source_frames - overlap;
+
+        let channels = self
+            .inner
+            .channels
+            .iter()
+            .map(|source| {
+                (0..frames)
+                    .map(|index| {
+                        if index < period {
+                            return source[index];
+                        }
+                        let position = (index - period) % period;
+                        if position < overlap {
+                            fade.mix_at(position, source[period + position], source[position])
+                        } else {
+                            source[position]
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Self::with_sample_rate(self.sample_rate(), channels)
+    }
+
     fn map_channels<F>(&self, mut f: F) -> Self
     where
         F: FnMut(&mut Vec<f32>),
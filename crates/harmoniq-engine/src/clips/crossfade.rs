@@ -44,13 +44,11 @@ pub fn crossfade(
         }
 
         for i in 0..overlap {
-            let fade_in = fade.gain_in_at(i);
-            let fade_out = fade.gain_out_at(i);
             let a_idx = pre_length + i;
             let b_idx = i;
             let a_sample = source_a.get(a_idx).copied().unwrap_or(0.0);
             let b_sample = source_b.get(b_idx).copied().unwrap_or(0.0);
-            output_channel[pre_length + i] = a_sample * fade_out + b_sample * fade_in;
+            output_channel[pre_length + i] = fade.mix_at(i, a_sample, b_sample);
         }
 
         let tail = &mut output_channel[pre_length + overlap..];
@@ -77,6 +77,12 @@ impl FadeSpec {
             (index as f32).clamp(0.0, (self.length - 1) as f32) / (self.length - 1) as f32;
         self.curve.gain_out(progress)
     }
+
+    /// Mixes `outgoing` fading out with `incoming` fading in, `index` frames
+    /// into the fade.
+    pub(crate) fn mix_at(&self, index: usize, outgoing: f32, incoming: f32) -> f32 {
+        outgoing * self.gain_out_at(index) + incoming * self.gain_in_at(index)
+    }
 }
 
 impl FadeCurve {
@@ -32,6 +32,14 @@ pub fn stretch_clip(
     Ok(AudioClip::with_sample_rate(clip.sample_rate(), channels))
 }
 
+/// Resamples every channel by `ratio` (target rate / source rate) with the
+/// offline-quality interpolator.
+pub(crate) fn resample_channels(clip: &AudioClip, ratio: f32) -> Vec<Vec<f32>> {
+    (0..clip.channels())
+        .map(|index| cubic_resample(clip.channel(index).unwrap_or(&[]), ratio))
+        .collect()
+}
+
 fn linear_resample(source: &[f32], ratio: f32) -> Vec<f32> {
     resample_channel(source, ratio, Interpolation::Linear)
 }
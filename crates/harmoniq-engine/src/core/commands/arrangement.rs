@@ -1,6 +1,8 @@
 use super::{CommandOutcome, ProjectCommand};
+use crate::automation::{AutomationTarget, MIXER_GAIN_PARAM};
 use crate::core::state::{
-    ArrangementClip, ArrangementTrack, AutomationLaneState, AutomationOwner, ProjectState, TrackId,
+    ArrangementClip, ArrangementTrack, AutomationLaneState, AutomationOwner, ClipId, ProjectState,
+    TrackId,
 };
 use crate::core::CommandError;
 
@@ -26,7 +28,7 @@ impl ProjectCommand for CreateTrackCommand {
         let lane = AutomationLaneState {
             id: lane_id,
             owner: AutomationOwner::Track(track_id),
-            parameter: "volume".into(),
+            target: AutomationTarget::mixer(track_id, MIXER_GAIN_PARAM).into(),
             points: Vec::new(),
         };
         state.automation.insert_lane(lane);
@@ -126,6 +128,9 @@ impl ProjectCommand for AddClipCommand {
     }
 
     fn apply(&self, state: &mut ProjectState) -> Result<CommandOutcome, CommandError> {
+        if state.is_track_locked(self.track_id) {
+            return Err(CommandError::Locked("track"));
+        }
         let track = state
             .arrangement
             .track_mut(self.track_id)
@@ -231,7 +236,7 @@ impl ProjectCommand for RestoreClipCommand {
 
 #[derive(Clone)]
 pub struct MoveClipCommand {
-    pub clip_id: super::super::state::ClipId,
+    pub clip_id: ClipId,
     pub target_track: TrackId,
     pub new_start: f32,
 }
@@ -242,6 +247,12 @@ impl ProjectCommand for MoveClipCommand {
     }
 
     fn apply(&self, state: &mut ProjectState) -> Result<CommandOutcome, CommandError> {
+        if state.is_clip_locked(self.clip_id) {
+            return Err(CommandError::Locked("clip"));
+        }
+        if state.is_track_locked(self.target_track) {
+            return Err(CommandError::Locked("track"));
+        }
         let (source_track_index, clip_index) = state
             .arrangement
             .clip_position(self.clip_id)
@@ -282,3 +293,58 @@ impl ProjectCommand for MoveClipCommand {
         self
     }
 }
+
+/// Item whose lock flag a [`SetLockCommand`] toggles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockTarget {
+    Track(TrackId),
+    Clip(ClipId),
+}
+
+/// Locks or unlocks a track or clip. Always allowed, so locked items can be
+/// released again.
+#[derive(Clone)]
+pub struct SetLockCommand {
+    pub target: LockTarget,
+    pub locked: bool,
+}
+
+impl ProjectCommand for SetLockCommand {
+    fn label(&self) -> &'static str {
+        if self.locked {
+            "Lock"
+        } else {
+            "Unlock"
+        }
+    }
+
+    fn apply(&self, state: &mut ProjectState) -> Result<CommandOutcome, CommandError> {
+        let flag = match self.target {
+            LockTarget::Track(id) => {
+                &mut state
+                    .arrangement
+                    .track_mut(id)
+                    .ok_or(CommandError::NotFound("track"))?
+                    .locked
+            }
+            LockTarget::Clip(id) => {
+                let (track, clip) = state
+                    .arrangement
+                    .clip_position(id)
+                    .ok_or(CommandError::NotFound("clip"))?;
+                &mut state.arrangement.tracks[track].clips[clip].locked
+            }
+        };
+        let previous = std::mem::replace(flag, self.locked);
+        Ok(CommandOutcome {
+            inverse: Box::new(SetLockCommand {
+                target: self.target,
+                locked: previous,
+            }),
+        })
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -3,7 +3,9 @@ mod automation;
 mod bus;
 mod mixer;
 
-pub use arrangement::{AddClipCommand, CreateTrackCommand, MoveClipCommand};
+pub use arrangement::{
+    AddClipCommand, CreateTrackCommand, LockTarget, MoveClipCommand, SetLockCommand,
+};
 pub use automation::WriteAutomationPointCommand;
 pub use bus::CommandBus;
 pub use mixer::{MixerEndpoint, SetMixerTargetCommand};
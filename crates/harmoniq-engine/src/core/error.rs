@@ -5,6 +5,8 @@ use thiserror::Error;
 pub enum CommandError {
     #[error("{0} not found")]
     NotFound(&'static str),
+    #[error("{0} is locked")]
+    Locked(&'static str),
     #[error("invalid command: {0}")]
     Invalid(&'static str),
     #[error("invariant violated: {0}")]
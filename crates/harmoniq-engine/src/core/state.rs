@@ -1,7 +1,8 @@
 use std::collections::HashSet;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::automation::{AutomationTarget, LaneTarget};
 use crate::mixer::{MixerBusState, MixerState, MixerTargetState};
 
 use super::CommandError;
@@ -10,7 +11,7 @@ pub type TrackId = u32;
 pub type ClipId = u64;
 pub type LaneId = u32;
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, PartialEq)]
 pub struct ProjectState {
     pub arrangement: ArrangementState,
     pub mixer: MixerState,
@@ -35,6 +36,24 @@ impl ProjectState {
         Ok(())
     }
 
+    pub fn is_track_locked(&self, id: TrackId) -> bool {
+        self.arrangement
+            .tracks
+            .iter()
+            .any(|track| track.id == id && track.locked)
+    }
+
+    /// A clip is locked when either it or the track holding it is locked.
+    pub fn is_clip_locked(&self, id: ClipId) -> bool {
+        self.arrangement
+            .clip_position(id)
+            .map(|(track, clip)| {
+                let track = &self.arrangement.tracks[track];
+                track.locked || track.clips[clip].locked
+            })
+            .unwrap_or(false)
+    }
+
     fn validate_arrangement(&self) -> Result<(), CommandError> {
         let mut seen = HashSet::new();
         for track in &self.arrangement.tracks {
@@ -157,6 +176,21 @@ impl ArrangementState {
         self.tracks.get_mut(index)
     }
 
+    /// Clips carrying `tag`, in track order.
+    pub fn clips_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a ArrangementClip> {
+        self.tracks
+            .iter()
+            .flat_map(|track| track.clips.iter())
+            .filter(move |clip| clip.has_tag(tag))
+    }
+
+    pub fn tracks_with_tag<'a>(
+        &'a self,
+        tag: &'a str,
+    ) -> impl Iterator<Item = &'a ArrangementTrack> {
+        self.tracks.iter().filter(move |track| track.has_tag(tag))
+    }
+
     pub fn clip_position(&self, clip_id: ClipId) -> Option<(usize, usize)> {
         for (track_index, track) in self.tracks.iter().enumerate() {
             if let Some(index) = track.clips.iter().position(|clip| clip.id == clip_id) {
@@ -172,6 +206,13 @@ pub struct ArrangementTrack {
     pub id: TrackId,
     pub name: String,
     pub clips: Vec<ArrangementClip>,
+    #[serde(default)]
+    pub color: Option<[f32; 4]>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Locked tracks reject edits to themselves and their clips.
+    #[serde(default)]
+    pub locked: bool,
 }
 
 impl ArrangementTrack {
@@ -180,9 +221,16 @@ impl ArrangementTrack {
             id: 0,
             name: name.into(),
             clips: Vec::new(),
+            color: None,
+            tags: Vec::new(),
+            locked: false,
         }
     }
 
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|existing| existing == tag)
+    }
+
     pub fn insert_clip(&mut self, clip: ArrangementClip) -> usize {
         let position = self
             .clips
@@ -201,12 +249,23 @@ pub struct ArrangementClip {
     pub start: f32,
     pub length: f32,
     pub media: Option<String>,
+    #[serde(default)]
+    pub color: Option<[f32; 4]>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Locked clips cannot be moved or removed until unlocked.
+    #[serde(default)]
+    pub locked: bool,
 }
 
 impl ArrangementClip {
     pub fn end(&self) -> f32 {
         self.start + self.length
     }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|existing| existing == tag)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
@@ -263,7 +322,7 @@ impl AutomationState {
 pub struct AutomationLaneState {
     pub id: LaneId,
     pub owner: AutomationOwner,
-    pub parameter: String,
+    pub target: LaneTarget,
     pub points: Vec<AutomationPoint>,
 }
 
@@ -279,6 +338,115 @@ pub struct AutomationPoint {
     pub value: f32,
 }
 
+impl<'de> Deserialize<'de> for ProjectState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Binary encodings are positional and postdate string targets, so
+        // only text formats need the legacy-tolerant record.
+        if deserializer.is_human_readable() {
+            ProjectStateRecord::deserialize(deserializer).map(Self::from)
+        } else {
+            let ProjectStateLayout {
+                arrangement,
+                mixer,
+                automation,
+            } = ProjectStateLayout::deserialize(deserializer)?;
+            Ok(Self {
+                arrangement,
+                mixer,
+                automation,
+            })
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ProjectStateLayout {
+    arrangement: ArrangementState,
+    mixer: MixerState,
+    automation: AutomationState,
+}
+
+/// Serialized form of [`ProjectState`]. Automation lanes saved before
+/// targets were structured carry a free-form `parameter` string, which is
+/// converted with [`AutomationTarget::from_legacy`] on load; lanes whose
+/// string cannot be converted keep it as [`LaneTarget::Unresolved`].
+#[derive(Deserialize)]
+struct ProjectStateRecord {
+    arrangement: ArrangementState,
+    mixer: MixerState,
+    automation: AutomationStateRecord,
+}
+
+#[derive(Deserialize)]
+struct AutomationStateRecord {
+    lanes: Vec<AutomationLaneRecord>,
+    next_lane_id: LaneId,
+}
+
+#[derive(Deserialize)]
+struct AutomationLaneRecord {
+    id: LaneId,
+    owner: AutomationOwner,
+    #[serde(default)]
+    target: Option<LaneTarget>,
+    #[serde(default)]
+    parameter: Option<String>,
+    points: Vec<AutomationPoint>,
+}
+
+impl From<ProjectStateRecord> for ProjectState {
+    fn from(record: ProjectStateRecord) -> Self {
+        let ProjectStateRecord {
+            arrangement,
+            mixer,
+            automation,
+        } = record;
+        let lanes = automation
+            .lanes
+            .into_iter()
+            .map(|lane| {
+                let target = match (lane.target, lane.parameter) {
+                    (Some(target), _) => target,
+                    (None, legacy) => {
+                        let legacy = legacy.unwrap_or_default();
+                        let track = match lane.owner {
+                            AutomationOwner::Track(id) => Some(id),
+                            AutomationOwner::Clip(id) => arrangement
+                                .clip_position(id)
+                                .map(|(track, _)| arrangement.tracks[track].id),
+                        };
+                        match track.and_then(|track| AutomationTarget::from_legacy(track, &legacy))
+                        {
+                            Some(target) => LaneTarget::Resolved(target),
+                            None => {
+                                log::warn!(
+                                    "automation lane {} keeps unresolved target '{legacy}'",
+                                    lane.id
+                                );
+                                LaneTarget::Unresolved(legacy)
+                            }
+                        }
+                    }
+                };
+                AutomationLaneState {
+                    id: lane.id,
+                    owner: lane.owner,
+                    target,
+                    points: lane.points,
+                }
+            })
+            .collect();
+        Self {
+            arrangement,
+            mixer,
+            automation: AutomationState {
+                lanes,
+                next_lane_id: automation.next_lane_id,
+            },
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum VisitState {
     Unvisited,
@@ -1,4 +1,5 @@
 use crate::buffer::AudioBuffer;
+use crate::plugin::MidiEvent;
 
 pub(crate) struct DelayCompensator {
     buffers: Vec<Vec<f32>>,
@@ -106,3 +107,103 @@ impl DelayCompensator {
         self.delay_samples
     }
 }
+
+/// Events a node's MIDI queues hold without reallocating on the audio
+/// thread.
+pub(crate) const MIDI_QUEUE_CAPACITY: usize = 256;
+
+/// Inserts `event` into the time-ordered `queue` after any event on the same
+/// sample. Returns `false` and drops the event when the queue is full, since
+/// growing it would allocate on the audio thread.
+pub(crate) fn insert_sorted(queue: &mut Vec<MidiEvent>, event: MidiEvent) -> bool {
+    if queue.len() == queue.capacity() {
+        return false;
+    }
+    let offset = event.sample_offset();
+    let index = queue.partition_point(|queued| queued.sample_offset() <= offset);
+    queue.insert(index, event);
+    true
+}
+
+/// MIDI a processor emitted that is not due yet. Events are delayed by the
+/// processor's latency so they stay aligned with its audio, and events that
+/// fall past the end of a block wait for the block they land in.
+pub(crate) struct MidiDelay {
+    emitted: Vec<MidiEvent>,
+    pending: Vec<MidiEvent>,
+}
+
+impl MidiDelay {
+    pub fn new() -> Self {
+        Self {
+            emitted: Vec::with_capacity(MIDI_QUEUE_CAPACITY),
+            pending: Vec::with_capacity(MIDI_QUEUE_CAPACITY),
+        }
+    }
+
+    /// Empty buffer for the processor to write one block of output into.
+    pub fn emitted_mut(&mut self) -> &mut Vec<MidiEvent> {
+        self.emitted.clear();
+        &mut self.emitted
+    }
+
+    /// Schedules the emitted events `latency` samples late, then moves the
+    /// events due within the next `frames` samples into `output` in time
+    /// order. Events beyond [`MIDI_QUEUE_CAPACITY`] pending ones are dropped.
+    pub fn release(&mut self, latency: usize, frames: usize, output: &mut Vec<MidiEvent>) {
+        for mut event in self.emitted.drain(..) {
+            let offset = event.sample_offset().saturating_add(latency as u32);
+            event.set_sample_offset(offset);
+            // Inserting keeps events on the same sample in emission order.
+            insert_sorted(&mut self.pending, event);
+        }
+
+        let frames = frames.min(u32::MAX as usize) as u32;
+        let due = self
+            .pending
+            .partition_point(|event| event.sample_offset() < frames);
+        output.extend(self.pending.drain(..due));
+        for event in &mut self.pending {
+            event.set_sample_offset(event.sample_offset() - frames);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.emitted.clear();
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorted_insert_keeps_order_and_never_grows() {
+        let mut queue = Vec::with_capacity(MIDI_QUEUE_CAPACITY);
+        assert!(insert_sorted(
+            &mut queue,
+            MidiEvent::new(8, [0x90, 60, 100])
+        ));
+        assert!(insert_sorted(
+            &mut queue,
+            MidiEvent::new(2, [0x90, 62, 100])
+        ));
+        assert!(insert_sorted(&mut queue, MidiEvent::new(8, [0x80, 60, 0])));
+        let order: Vec<(u32, u8)> = queue
+            .iter()
+            .map(|event| match event {
+                MidiEvent::NoteOn { note, .. } => (event.sample_offset(), *note),
+                _ => (event.sample_offset(), 0),
+            })
+            .collect();
+        assert_eq!(order, vec![(2, 62), (8, 60), (8, 0)]);
+
+        let capacity = queue.capacity();
+        while queue.len() < capacity {
+            assert!(insert_sorted(&mut queue, MidiEvent::new(0, [0xB0, 1, 0])));
+        }
+        assert!(!insert_sorted(&mut queue, MidiEvent::new(0, [0xB0, 1, 0])));
+        assert_eq!(queue.capacity(), capacity);
+    }
+}
@@ -16,12 +16,15 @@ use crate::dsp::events::{MidiEvent, TransportClock};
 use crate::dsp::graph::DspGraph;
 #[cfg(feature = "openasio")]
 use crate::dsp::graph::GraphProcess;
+use crate::timeline::TimelinePlayer;
+use crate::transport::{MidiSyncGenerator, MidiSyncSettings};
 
 #[cfg(feature = "openasio")]
 use crate::backend::EngineRt;
 
 const MIDI_RING_CAPACITY: usize = 512;
 const MIDI_BUFFER_CAPACITY: usize = 512;
+const MIDI_OUTPUT_CAPACITY: usize = 512;
 
 #[derive(Clone)]
 pub struct MidiPort {
@@ -46,7 +49,11 @@ pub struct RealtimeDspEngine {
     midi_consumer: HeapConsumer<MidiEvent>,
     midi_producer: Arc<Mutex<HeapProducer<MidiEvent>>>,
     midi_buffer: ArrayVec<MidiEvent, MIDI_BUFFER_CAPACITY>,
+    midi_output: HeapProducer<MidiEvent>,
+    midi_output_consumer: Option<HeapConsumer<MidiEvent>>,
+    midi_sync: Option<MidiSyncGenerator>,
     transport: TransportClock,
+    timeline: Option<TimelinePlayer>,
     sample_rate: f32,
     max_block: u32,
     in_ch: u32,
@@ -61,12 +68,17 @@ impl RealtimeDspEngine {
     pub fn with_midi_capacity(graph: DspGraph, capacity: usize) -> Self {
         let ring = HeapRb::new(capacity.max(32));
         let (producer, consumer) = ring.split();
+        let (output, output_consumer) = HeapRb::new(MIDI_OUTPUT_CAPACITY).split();
         Self {
             graph,
             midi_consumer: consumer,
             midi_producer: Arc::new(Mutex::new(producer)),
             midi_buffer: ArrayVec::new(),
+            midi_output: output,
+            midi_output_consumer: Some(output_consumer),
+            midi_sync: None,
             transport: TransportClock::new(),
+            timeline: None,
             sample_rate: 44_100.0,
             max_block: 64,
             in_ch: 0,
@@ -79,6 +91,10 @@ impl RealtimeDspEngine {
         self.max_block = max_block.max(1);
         self.in_ch = in_ch;
         self.out_ch = out_ch;
+        self.transport.set_sample_rate(sr);
+        if let Some(sync) = &self.midi_sync {
+            self.midi_sync = Some(MidiSyncGenerator::new(sr, sync.settings()));
+        }
         flush_denormals();
         self.graph
             .prepare(self.sample_rate, self.max_block, self.in_ch, self.out_ch);
@@ -98,6 +114,25 @@ impl RealtimeDspEngine {
     pub fn transport_clock(&self) -> TransportClock {
         self.transport.clone()
     }
+
+    /// Consumer of the MIDI the engine sends out, such as sync messages,
+    /// with offsets into the block they were sent in. Handed out once.
+    pub fn take_midi_output(&mut self) -> Option<HeapConsumer<MidiEvent>> {
+        self.midi_output_consumer.take()
+    }
+
+    /// Sends MIDI clock and time code following the transport to the MIDI
+    /// output, or stops with `None`.
+    pub fn set_midi_sync(&mut self, settings: Option<MidiSyncSettings>) {
+        self.midi_sync =
+            settings.map(|settings| MidiSyncGenerator::new(self.sample_rate, settings));
+    }
+
+    /// Mixes `timeline` over the graph output at the transport position,
+    /// crossfading loop wraps with the clock's loop crossfade.
+    pub fn set_timeline(&mut self, timeline: Option<TimelinePlayer>) {
+        self.timeline = timeline;
+    }
 }
 
 #[cfg(feature = "openasio")]
@@ -140,6 +175,9 @@ impl EngineRt for RealtimeDspEngine {
         }
 
         let transport = self.transport.load();
+        if let Some(sync) = self.midi_sync.as_mut() {
+            sync.process(&transport, frames, &mut self.midi_output);
+        }
 
         let input_block = if let Some(interleaved) = inputs.interleaved() {
             unsafe {
@@ -151,29 +189,42 @@ impl EngineRt for RealtimeDspEngine {
             AudioBlock::empty()
         };
 
-        let output_block = if let Some(interleaved) = outputs.interleaved_mut() {
-            unsafe {
-                AudioBlockMut::from_interleaved(
-                    interleaved.as_mut_ptr(),
-                    outputs.channels() as u32,
-                    frames,
-                )
-            }
-        } else if let Some(planes) = outputs.planes_ptrs_mut() {
-            unsafe { AudioBlockMut::from_planar(planes, outputs.channels() as u32, frames) }
-        } else {
-            AudioBlockMut::empty()
-        };
-
         self.graph.process(GraphProcess {
             inputs: input_block,
-            outputs: output_block,
+            outputs: output_block(&mut outputs, frames),
             frames,
             transport,
             midi: self.midi_buffer.as_slice(),
         });
 
-        self.transport.advance_samples(frames);
+        match &self.timeline {
+            Some(timeline) => {
+                let mut block = output_block(&mut outputs, frames);
+                let channels = block.channels() as usize;
+                self.transport.advance_with(frames, |frame, playhead| {
+                    for channel in 0..channels {
+                        let value = timeline.sample(channel, playhead);
+                        unsafe {
+                            let mut samples = block.chan_mut(channel);
+                            samples.write(frame, samples.read(frame) + value);
+                        }
+                    }
+                });
+            }
+            None => self.transport.advance_samples(frames),
+        }
         true
     }
 }
+
+#[cfg(feature = "openasio")]
+fn output_block<'a>(outputs: &'a mut AudioViewMut<'_>, frames: u32) -> AudioBlockMut<'a> {
+    let channels = outputs.channels() as u32;
+    if let Some(interleaved) = outputs.interleaved_mut().map(|buf| buf.as_mut_ptr()) {
+        unsafe { AudioBlockMut::from_interleaved(interleaved, channels, frames) }
+    } else if let Some(planes) = outputs.planes_ptrs_mut() {
+        unsafe { AudioBlockMut::from_planar(planes, channels, frames) }
+    } else {
+        AudioBlockMut::empty()
+    }
+}
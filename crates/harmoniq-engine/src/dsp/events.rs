@@ -1,13 +1,24 @@
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
+use parking_lot::Mutex;
 
-use crate::time::{LoopRegion, SharedTempoMap, TempoMap, Transport};
+use crate::time::{LoopRegion, SharedTempoMap, Tempo, TempoMap, Transport};
 
 const STATE_PLAYING: u32 = 0b0001;
 const STATE_LOOP_ENABLED: u32 = 0b0010;
 const NO_EVENT: u32 = u32::MAX;
+const NO_PUNCH_IN: u64 = u64::MAX;
+/// Share of each external tick's phase error the clock PLL corrects.
+const PLL_PHASE_GAIN: f64 = 0.2;
+/// Share of each external tick's phase error folded into the period.
+const PLL_PERIOD_GAIN: f64 = 0.01;
+/// Phase error, as a fraction of the tick period, past which the PLL
+/// relocks instead of smoothing.
+const PLL_SNAP_THRESHOLD: f64 = 0.5;
+/// Locked tempo change, in BPM, worth republishing the tempo map for.
+const PLL_PUBLISH_BPM: f64 = 0.01;
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct MidiEvent {
@@ -27,6 +38,61 @@ impl MidiEvent {
     }
 }
 
+/// What the playhead plays at one frame of a block, as reported by
+/// [`TransportClock::advance_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Playhead {
+    /// Stopped or counting in.
+    Idle,
+    Playing(u64),
+    /// Just after a loop wrap: `head` plays from the loop start while `tail`
+    /// carries on past the loop end, `elapsed` frames into a crossfade of
+    /// `length` frames.
+    Crossfading {
+        head: u64,
+        tail: u64,
+        elapsed: u64,
+        length: u64,
+    },
+}
+
+/// Where the transport takes its tempo from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClockSource {
+    /// The transport's own tempo map.
+    #[default]
+    Internal,
+    /// Clock ticks fed through [`TransportClock::on_external_clock`] at
+    /// `ppqn` ticks per quarter note, such as 24 for MIDI clock.
+    External { ppqn: u32 },
+}
+
+/// Commands applied to a [`TransportClock`] from the control thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportCommand {
+    /// Starts playback, after the count-in set with
+    /// [`TransportClock::set_count_in`] when stopped.
+    Start,
+    /// Records from `punch_in`: counts in, then plays the pre-roll set with
+    /// [`TransportClock::set_pre_roll`] before reaching `punch_in`.
+    Record {
+        punch_in: u64,
+    },
+    Stop,
+    Seek(u64),
+    SetLoop(Option<LoopRegion>),
+    /// See [`TransportClock::set_loop_crossfade`].
+    SetLoopCrossfade(u64),
+    /// Loops `region` indefinitely, counting in `count_in_beats` metronome
+    /// beats before every pass. The playhead holds at the region start and
+    /// reports not-playing while counting in.
+    LoopWithCount {
+        region: LoopRegion,
+        count_in_beats: u32,
+    },
+    SetClockSource(ClockSource),
+}
+
 #[derive(Clone)]
 pub struct TransportClock {
     inner: Arc<TransportAtomic>,
@@ -40,7 +106,61 @@ struct TransportAtomic {
     pending_stop: AtomicU32,
     loop_start: AtomicU64,
     loop_end: AtomicU64,
+    loop_crossfade: AtomicU64,
+    crossfade_remaining: AtomicU64,
+    loop_iteration: AtomicU64,
+    count_in_samples: AtomicU64,
+    count_in_remaining: AtomicU64,
+    count_in_bars: AtomicU32,
+    pre_roll_bits: AtomicU64,
+    punch_in: AtomicU64,
+    sample_rate_bits: AtomicU32,
     map_version: AtomicU64,
+    /// Tempo map with the external clock's tempo, once it has locked.
+    external_map: ArcSwapOption<TempoMap>,
+    pll: Mutex<ClockPll>,
+}
+
+/// Second-order PLL tracking the tick period of an external clock.
+#[derive(Debug, Default)]
+struct ClockPll {
+    source: ClockSource,
+    /// Smoothed sample time of the latest tick.
+    phase: Option<f64>,
+    /// Smoothed samples between ticks.
+    period: Option<f64>,
+    published_bpm: Option<f64>,
+}
+
+impl ClockPll {
+    /// Folds in a tick at `sample_time` and returns the tick period once
+    /// there is an estimate.
+    fn tick(&mut self, sample_time: f64) -> Option<f64> {
+        match (self.phase, self.period) {
+            (None, _) => self.phase = Some(sample_time),
+            (Some(phase), None) => {
+                let period = sample_time - phase;
+                self.phase = Some(sample_time);
+                if period > 0.0 {
+                    self.period = Some(period);
+                }
+            }
+            (Some(phase), Some(period)) => {
+                let predicted = phase + period;
+                let error = sample_time - predicted;
+                if error.abs() > period * PLL_SNAP_THRESHOLD {
+                    // A jump this large is a relocation or a new tempo, not
+                    // jitter: restart from this tick and measure afresh.
+                    self.phase = Some(sample_time);
+                    self.period = None;
+                } else {
+                    self.phase = Some(predicted + PLL_PHASE_GAIN * error);
+                    self.period = Some(period + PLL_PERIOD_GAIN * error);
+                }
+            }
+        }
+        self.period
+    }
 }
 
 impl TransportClock {
@@ -58,7 +178,18 @@ impl TransportClock {
                 pending_stop: AtomicU32::new(NO_EVENT),
                 loop_start: AtomicU64::new(0),
                 loop_end: AtomicU64::new(0),
+                loop_crossfade: AtomicU64::new(0),
+                crossfade_remaining: AtomicU64::new(0),
+                loop_iteration: AtomicU64::new(0),
+                count_in_samples: AtomicU64::new(0),
+                count_in_remaining: AtomicU64::new(0),
+                count_in_bars: AtomicU32::new(0),
+                pre_roll_bits: AtomicU64::new(0.0f64.to_bits()),
+                punch_in: AtomicU64::new(NO_PUNCH_IN),
+                sample_rate_bits: AtomicU32::new(48_000.0f32.to_bits()),
                 map_version: AtomicU64::new(0),
+                external_map: ArcSwapOption::empty(),
+                pll: Mutex::new(ClockPll::default()),
             }),
         }
     }
@@ -67,9 +198,12 @@ impl TransportClock {
     pub fn load(&self) -> Transport {
         let sample_position = self.inner.sample_pos.load(Ordering::Relaxed);
         let state_bits = self.inner.state.load(Ordering::Relaxed);
-        let is_playing = (state_bits & STATE_PLAYING) != 0;
+        let count_in_remaining = self.inner.count_in_remaining.load(Ordering::Relaxed);
+        let is_playing = (state_bits & STATE_PLAYING) != 0 && count_in_remaining == 0;
+        let loop_iteration = self.inner.loop_iteration.load(Ordering::Relaxed);
+        let punch_in = self.inner.punch_in.load(Ordering::Relaxed);
         let map_version = self.inner.map_version.load(Ordering::Relaxed);
-        let tempo_map = self.inner.tempo_map.load_full();
+        let tempo_map = self.tempo_map();
         let tempo = tempo_map.tempo_at(sample_position);
         let time_signature = tempo_map.time_signature_at(sample_position);
         Transport {
@@ -77,21 +211,206 @@ impl TransportClock {
             time_signature,
             sample_position,
             is_playing,
+            loop_iteration,
+            count_in_remaining,
+            punch_in: (punch_in != NO_PUNCH_IN).then_some(punch_in),
             map_version,
             tempo_map,
         }
     }
 
+    /// The tempo map in effect: the internal one, or the external clock's
+    /// tempo over the internal time signatures once the clock has locked.
     pub fn tempo_map(&self) -> SharedTempoMap {
-        self.inner.tempo_map.load_full()
+        self.inner
+            .external_map
+            .load_full()
+            .unwrap_or_else(|| self.inner.tempo_map.load_full())
     }
 
     pub fn set_tempo_map(&self, map: TempoMap) {
         self.inner.tempo_map.store(Arc::new(map));
         self.inner.map_version.fetch_add(1, Ordering::AcqRel);
+        let mut pll = self.inner.pll.lock();
+        if let Some(bpm) = pll.published_bpm.take() {
+            self.publish_external_tempo(&mut pll, bpm);
+        }
+    }
+
+    pub fn clock_source(&self) -> ClockSource {
+        self.inner.pll.lock().source
+    }
+
+    /// Switches between the internal tempo map and an external clock. The
+    /// internal tempo stays in effect until the external clock has locked.
+    pub fn set_clock_source(&self, source: ClockSource) {
+        let mut pll = self.inner.pll.lock();
+        if pll.source == source {
+            return;
+        }
+        *pll = ClockPll {
+            source,
+            ..ClockPll::default()
+        };
+        if self.inner.external_map.swap(None).is_some() {
+            self.inner.map_version.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Tempo the external clock is locked to, if any.
+    pub fn locked_tempo(&self) -> Option<Tempo> {
+        self.inner.pll.lock().published_bpm.map(Tempo)
+    }
+
+    /// Feeds one external clock tick received at `sample_time`. Jitter is
+    /// smoothed; a tick far from where the PLL expected it relocks. Ignored
+    /// while the clock source is internal.
+    pub fn on_external_clock(&self, sample_time: u64) {
+        let mut pll = self.inner.pll.lock();
+        let ClockSource::External { ppqn } = pll.source else {
+            return;
+        };
+        let Some(period) = pll.tick(sample_time as f64) else {
+            return;
+        };
+        let bpm = 60.0 * self.sample_rate() as f64 / (period * ppqn.max(1) as f64);
+        let stale = pll
+            .published_bpm
+            .map_or(true, |published| (published - bpm).abs() >= PLL_PUBLISH_BPM);
+        if stale {
+            self.publish_external_tempo(&mut pll, bpm);
+        }
+    }
+
+    /// Handles an external start message at `sample_time`: the next tick
+    /// is measured from here rather than from the clock's previous phase.
+    pub fn on_external_start(&self, sample_time: u64) {
+        let mut pll = self.inner.pll.lock();
+        if matches!(pll.source, ClockSource::External { .. }) {
+            pll.phase = Some(sample_time as f64);
+            pll.period = None;
+        }
+    }
+
+    fn publish_external_tempo(&self, pll: &mut ClockPll, bpm: f64) {
+        let map = self.inner.tempo_map.load().with_tempo(Tempo(bpm));
+        self.inner.external_map.store(Some(Arc::new(map)));
+        self.inner.map_version.fetch_add(1, Ordering::AcqRel);
+        pll.published_bpm = Some(bpm);
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        f32::from_bits(self.inner.sample_rate_bits.load(Ordering::Relaxed))
+    }
+
+    /// Sample rate used to convert count-in beats into samples.
+    pub fn set_sample_rate(&self, sample_rate: f32) {
+        self.inner
+            .sample_rate_bits
+            .store(sample_rate.max(1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn count_in(&self) -> u32 {
+        self.inner.count_in_bars.load(Ordering::Relaxed)
+    }
+
+    /// Bars of metronome-only count-in played by
+    /// [`TransportCommand::Start`] and [`TransportCommand::Record`]. The
+    /// playhead holds and reports not-playing while counting in.
+    pub fn set_count_in(&self, bars: u32) {
+        self.inner.count_in_bars.store(bars, Ordering::Relaxed);
+    }
+
+    pub fn pre_roll(&self) -> f64 {
+        f64::from_bits(self.inner.pre_roll_bits.load(Ordering::Relaxed))
+    }
+
+    /// Seconds of audio played before the punch-in point of a
+    /// [`TransportCommand::Record`], after the count-in.
+    pub fn set_pre_roll(&self, seconds: f64) {
+        self.inner
+            .pre_roll_bits
+            .store(seconds.max(0.0).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn execute(&self, command: TransportCommand) {
+        match command {
+            TransportCommand::Start => self.start_with_count_in(),
+            TransportCommand::Record { punch_in } => self.record(punch_in),
+            TransportCommand::Stop => {
+                self.stop_immediately();
+                self.inner.punch_in.store(NO_PUNCH_IN, Ordering::Release);
+                self.inner.count_in_remaining.store(0, Ordering::Release);
+                self.inner.crossfade_remaining.store(0, Ordering::Release);
+            }
+            TransportCommand::Seek(sample) => self.seek(sample),
+            TransportCommand::SetLoop(region) => self.set_loop_region(region),
+            TransportCommand::SetLoopCrossfade(samples) => self.set_loop_crossfade(samples),
+            TransportCommand::LoopWithCount {
+                region,
+                count_in_beats,
+            } => self.loop_with_count(region, count_in_beats),
+            TransportCommand::SetClockSource(source) => self.set_clock_source(source),
+        }
+    }
+
+    /// Samples in the configured count-in, in the tempo and metre at
+    /// `position`, matching where the metronome places its clicks.
+    fn count_in_length(&self, position: u64) -> u64 {
+        let map = self.tempo_map();
+        let beat = map
+            .tempo_at(position)
+            .samples_per_beat(self.sample_rate())
+            .round() as u64;
+        let beats = self.count_in() as u64 * map.time_signature_at(position).beats_per_bar() as u64;
+        beat * beats
+    }
+
+    fn start_with_count_in(&self) {
+        if self.inner.state.load(Ordering::Acquire) & STATE_PLAYING == 0 {
+            let position = self.inner.sample_pos.load(Ordering::Acquire);
+            self.inner
+                .count_in_remaining
+                .store(self.count_in_length(position), Ordering::Release);
+        }
+        self.start_immediately();
+    }
+
+    fn record(&self, punch_in: u64) {
+        let pre_roll = (self.pre_roll() * self.sample_rate() as f64).round() as u64;
+        let start = punch_in.saturating_sub(pre_roll);
+        self.seek(start);
+        self.inner.punch_in.store(punch_in, Ordering::Release);
+        self.inner
+            .count_in_remaining
+            .store(self.count_in_length(start), Ordering::Release);
+        self.start_immediately();
+    }
+
+    fn loop_with_count(&self, region: LoopRegion, count_in_beats: u32) {
+        if region.end <= region.start {
+            self.set_loop_region(None);
+            return;
+        }
+        self.set_loop_region(Some(region));
+        let beat = self
+            .tempo_map()
+            .tempo_at(region.start)
+            .samples_per_beat(self.sample_rate())
+            .round() as u64;
+        let count_in = beat * count_in_beats as u64;
+        self.inner
+            .count_in_samples
+            .store(count_in, Ordering::Release);
+        self.inner
+            .count_in_remaining
+            .store(count_in, Ordering::Release);
+        self.seek(region.start);
+        self.start_immediately();
     }
 
     pub fn seek(&self, sample_position: u64) {
+        self.inner.crossfade_remaining.store(0, Ordering::Release);
         self.inner
             .sample_pos
             .store(sample_position, Ordering::Release);
@@ -113,7 +432,14 @@ impl TransportClock {
         self.inner.pending_stop.store(offset, Ordering::Release);
     }
 
+    /// Sets or clears the loop region. Any count-in from
+    /// [`TransportCommand::LoopWithCount`] is cancelled and the loop
+    /// iteration counter restarts at zero.
     pub fn set_loop_region(&self, region: Option<LoopRegion>) {
+        self.inner.count_in_samples.store(0, Ordering::Release);
+        self.inner.crossfade_remaining.store(0, Ordering::Release);
+        self.inner.count_in_remaining.store(0, Ordering::Release);
+        self.inner.loop_iteration.store(0, Ordering::Release);
         match region {
             Some(region) if region.end > region.start => {
                 self.inner.loop_start.store(region.start, Ordering::Release);
@@ -132,7 +458,29 @@ impl TransportClock {
         }
     }
 
+    pub fn loop_crossfade(&self) -> u64 {
+        self.inner.loop_crossfade.load(Ordering::Relaxed)
+    }
+
+    /// Length of the equal-power crossfade from the audio after the loop end
+    /// into the audio at the loop start each time the playhead wraps, capped
+    /// at the loop length. Zero wraps with a hard cut.
+    pub fn set_loop_crossfade(&self, samples: u64) {
+        self.inner.loop_crossfade.store(samples, Ordering::Relaxed);
+    }
+
     pub fn advance_samples(&self, frames: u32) {
+        self.advance_with(frames, |_, _| {});
+    }
+
+    /// Advances the clock by `frames`, calling `visit` with each frame's
+    /// offset in the block and what the playhead plays there. A loop wrap
+    /// straight into playback is followed by the loop crossfade; a wrap
+    /// into a count-in is not.
+    pub fn advance_with<F>(&self, frames: u32, mut visit: F)
+    where
+        F: FnMut(usize, Playhead),
+    {
         if frames == 0 {
             return;
         }
@@ -157,6 +505,15 @@ impl TransportClock {
         let loop_start = self.inner.loop_start.load(Ordering::Relaxed);
         let loop_end = self.inner.loop_end.load(Ordering::Relaxed);
         let loop_enabled = (state_bits & STATE_LOOP_ENABLED) != 0 && loop_end > loop_start;
+        let count_in_samples = self.inner.count_in_samples.load(Ordering::Relaxed);
+        let mut count_in_remaining = self.inner.count_in_remaining.load(Ordering::Relaxed);
+        let mut loop_iteration = self.inner.loop_iteration.load(Ordering::Relaxed);
+        let crossfade = self
+            .inner
+            .loop_crossfade
+            .load(Ordering::Relaxed)
+            .min(loop_end.saturating_sub(loop_start));
+        let mut crossfade_remaining = self.inner.crossfade_remaining.load(Ordering::Relaxed);
 
         let frames_u64 = frames as u64;
         for frame in 0..frames_u64 {
@@ -173,13 +530,38 @@ impl TransportClock {
                     playing = false;
                     state_bits &= !STATE_PLAYING;
                     stop_offset = None;
+                    crossfade_remaining = 0;
+                }
+            }
+
+            if !playing || count_in_remaining > 0 {
+                if playing {
+                    count_in_remaining -= 1;
                 }
+                visit(frame as usize, Playhead::Idle);
+                continue;
             }
 
-            if playing {
-                sample_pos = sample_pos.wrapping_add(1);
-                if loop_enabled && sample_pos >= loop_end {
-                    sample_pos = loop_start;
+            let playhead = if crossfade_remaining > 0 {
+                let elapsed = crossfade - crossfade_remaining.min(crossfade);
+                crossfade_remaining -= 1;
+                Playhead::Crossfading {
+                    head: sample_pos,
+                    tail: loop_end + elapsed,
+                    elapsed,
+                    length: crossfade,
+                }
+            } else {
+                Playhead::Playing(sample_pos)
+            };
+            visit(frame as usize, playhead);
+            sample_pos = sample_pos.wrapping_add(1);
+            if loop_enabled && sample_pos >= loop_end {
+                sample_pos = loop_start;
+                loop_iteration += 1;
+                count_in_remaining = count_in_samples;
+                if count_in_samples == 0 {
+                    crossfade_remaining = crossfade;
                 }
             }
         }
@@ -199,6 +581,15 @@ impl TransportClock {
         }
 
         self.inner.sample_pos.store(sample_pos, Ordering::Release);
+        self.inner
+            .count_in_remaining
+            .store(count_in_remaining, Ordering::Release);
+        self.inner
+            .loop_iteration
+            .store(loop_iteration, Ordering::Release);
+        self.inner
+            .crossfade_remaining
+            .store(crossfade_remaining, Ordering::Release);
         self.inner.state.store(state_bits, Ordering::Release);
     }
 }
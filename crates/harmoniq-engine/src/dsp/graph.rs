@@ -1,6 +1,3 @@
-use std::sync::Arc;
-
-use parking_lot::Mutex;
 use ringbuf::{HeapConsumer, HeapProducer, HeapRb};
 
 use harmoniq_dsp::{AudioBlock, AudioBlockMut};
@@ -16,20 +13,20 @@ pub struct NodeLatency {
     pub samples: u32,
 }
 
-#[derive(Clone)]
+/// Lock-free single-producer queue into one node's parameters. Every port owns its
+/// own ring, so each sender asks [`DspGraph::param_port`] for a port of its own.
 pub struct ParamPort {
-    inner: Arc<Mutex<HeapProducer<ParamUpdate>>>,
+    producer: HeapProducer<ParamUpdate>,
 }
 
 impl ParamPort {
     #[inline]
-    pub fn try_send(&self, update: ParamUpdate) -> Result<(), ParamUpdate> {
-        let mut producer = self.inner.lock();
-        producer.push(update)
+    pub fn try_send(&mut self, update: ParamUpdate) -> Result<(), ParamUpdate> {
+        self.producer.push(update)
     }
 
     #[inline]
-    pub fn send(&self, update: ParamUpdate) {
+    pub fn send(&mut self, update: ParamUpdate) {
         let _ = self.try_send(update);
     }
 }
@@ -65,12 +62,20 @@ pub trait DspNode: Send {
 
 struct NodeSlot {
     node: Box<dyn DspNode>,
-    params: Option<HeapConsumer<ParamUpdate>>,
+    params: Vec<HeapConsumer<ParamUpdate>>,
+    param_capacity: usize,
     latency: NodeLatency,
 }
 
-struct ParamPortInner {
-    sender: Arc<Mutex<HeapProducer<ParamUpdate>>>,
+impl NodeSlot {
+    fn open_port(&mut self) -> Option<ParamPort> {
+        if self.param_capacity == 0 {
+            return None;
+        }
+        let (producer, consumer) = HeapRb::new(self.param_capacity).split();
+        self.params.push(consumer);
+        Some(ParamPort { producer })
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -89,7 +94,6 @@ struct NodeExec {
 
 pub struct DspGraph {
     nodes: Vec<NodeSlot>,
-    param_ports: Vec<Option<ParamPortInner>>,
     exec_order: Vec<NodeExec>,
     scratch: Vec<Vec<f32>>,
     sr: f32,
@@ -103,7 +107,6 @@ impl DspGraph {
     pub fn new() -> Self {
         Self {
             nodes: Vec::new(),
-            param_ports: Vec::new(),
             exec_order: Vec::new(),
             scratch: Vec::new(),
             sr: 44_100.0,
@@ -120,23 +123,14 @@ impl DspGraph {
         param_capacity: usize,
     ) -> (NodeId, Option<ParamPort>) {
         let id = self.nodes.len() as NodeId;
-        let mut consumer = None;
-        let mut port = None;
-        if param_capacity > 0 {
-            let rb = HeapRb::new(param_capacity);
-            let (producer, cons) = rb.split();
-            let arc = Arc::new(Mutex::new(producer));
-            consumer = Some(cons);
-            port = Some(ParamPort { inner: arc.clone() });
-            self.param_ports.push(Some(ParamPortInner { sender: arc }));
-        } else {
-            self.param_ports.push(None);
-        }
-        self.nodes.push(NodeSlot {
+        let mut slot = NodeSlot {
             node,
-            params: consumer,
+            params: Vec::new(),
+            param_capacity,
             latency: NodeLatency { samples: 0 },
-        });
+        };
+        let port = slot.open_port();
+        self.nodes.push(slot);
         (id, port)
     }
 
@@ -192,13 +186,10 @@ impl DspGraph {
         }
     }
 
-    pub fn param_port(&self, node: NodeId) -> Option<ParamPort> {
-        let index = node as usize;
-        self.param_ports.get(index).and_then(|slot| {
-            slot.as_ref().map(|inner| ParamPort {
-                inner: inner.sender.clone(),
-            })
-        })
+    /// Opens another parameter port on `node` for a second sender. This allocates
+    /// the port's ring, so call it while building the graph.
+    pub fn param_port(&mut self, node: NodeId) -> Option<ParamPort> {
+        self.nodes.get_mut(node as usize)?.open_port()
     }
 
     pub fn process(&mut self, mut block: GraphProcess<'_>) {
@@ -216,7 +207,7 @@ impl DspGraph {
             let exec = self.exec_order[exec_index];
             {
                 let node_slot = &mut self.nodes[exec.node_index];
-                if let Some(params) = node_slot.params.as_mut() {
+                for params in &mut node_slot.params {
                     while let Some(update) = params.pop() {
                         node_slot.node.param(update);
                     }
@@ -6,6 +6,6 @@ pub mod params;
 
 pub use crate::time::Transport;
 pub use engine::{MidiPort, RealtimeDspEngine};
-pub use events::{MidiEvent, TransportClock};
+pub use events::{ClockSource, MidiEvent, Playhead, TransportClock, TransportCommand};
 pub use graph::{DspGraph, DspNode, GraphProcess, NodeId, NodeLatency, ParamPort, ProcessContext};
-pub use params::ParamUpdate;
+pub use params::{ParamRamp, ParamUpdate};
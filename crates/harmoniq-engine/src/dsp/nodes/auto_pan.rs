@@ -0,0 +1,187 @@
+use harmoniq_dsp::pan::constant_power;
+
+use crate::dsp::graph::{DspNode, ProcessContext};
+use crate::dsp::params::ParamUpdate;
+
+/// LFO shape used by [`AutoPanNode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutoPanShape {
+    #[default]
+    Sine,
+    Triangle,
+    Square,
+}
+
+impl AutoPanShape {
+    /// Maps a parameter value (`0`, `1`, `2`) to a shape.
+    pub fn from_param(value: f32) -> Self {
+        match value.round() as i32 {
+            1 => Self::Triangle,
+            2 => Self::Square,
+            _ => Self::Sine,
+        }
+    }
+
+    /// LFO value in `-1..=1` at `phase` cycles. Every shape starts at the
+    /// centre or swings right first, so the shapes line up with each other.
+    pub fn value_at(self, phase: f64) -> f32 {
+        let phase = phase.rem_euclid(1.0);
+        match self {
+            Self::Sine => (std::f64::consts::TAU * phase).sin() as f32,
+            Self::Triangle => {
+                let value = if phase < 0.25 {
+                    4.0 * phase
+                } else if phase < 0.75 {
+                    2.0 - 4.0 * phase
+                } else {
+                    4.0 * phase - 4.0
+                };
+                value as f32
+            }
+            Self::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+        }
+    }
+}
+
+/// Tempo-synced auto-panner.
+///
+/// While the transport plays, the LFO phase is derived from the transport
+/// position and tempo, so one cycle spans `cycle_beats` beats and stays
+/// locked to the bar. When stopped the LFO keeps running at the last tempo.
+/// The pan position is `width * lfo`, with full width sweeping hard left to
+/// hard right through the constant-power law used by [`super::PanNode`]. A
+/// mono input feeds both outputs.
+pub struct AutoPanNode {
+    shape: AutoPanShape,
+    cycle_beats: f32,
+    width: f32,
+    sample_rate: f32,
+    phase: f64,
+    pan: f32,
+}
+
+impl AutoPanNode {
+    pub const SHAPE_PARAM: u32 = 0;
+    pub const CYCLE_PARAM: u32 = 1;
+    pub const WIDTH_PARAM: u32 = 2;
+
+    pub fn new(shape: AutoPanShape, cycle_beats: f32, width: f32) -> Self {
+        Self {
+            shape,
+            cycle_beats: cycle_beats.max(1.0 / 64.0),
+            width: width.clamp(0.0, 1.0),
+            sample_rate: 48_000.0,
+            phase: 0.0,
+            pan: 0.0,
+        }
+    }
+
+    pub fn shape(&self) -> AutoPanShape {
+        self.shape
+    }
+
+    pub fn set_shape(&mut self, shape: AutoPanShape) {
+        self.shape = shape;
+    }
+
+    pub fn cycle_beats(&self) -> f32 {
+        self.cycle_beats
+    }
+
+    /// Sets the LFO period in beats, e.g. `0.5` for eighth notes.
+    pub fn set_cycle_beats(&mut self, beats: f32) {
+        self.cycle_beats = beats.max(1.0 / 64.0);
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width.clamp(0.0, 1.0);
+    }
+
+    /// Pan position of the last processed frame, in `-1..=1`.
+    pub fn current_pan(&self) -> f32 {
+        self.pan
+    }
+}
+
+impl Default for AutoPanNode {
+    fn default() -> Self {
+        Self::new(AutoPanShape::Sine, 1.0, 1.0)
+    }
+}
+
+impl DspNode for AutoPanNode {
+    fn prepare(&mut self, sr: f32, _max_block: u32, _in_ch: u32, _out_ch: u32) {
+        self.sample_rate = sr.max(1.0);
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+        self.pan = 0.0;
+    }
+
+    fn param(&mut self, update: ParamUpdate) {
+        match update.id {
+            Self::SHAPE_PARAM => self.set_shape(AutoPanShape::from_param(update.value)),
+            Self::CYCLE_PARAM => self.set_cycle_beats(update.value),
+            Self::WIDTH_PARAM => self.set_width(update.value),
+            _ => {}
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext<'_>) {
+        let frames = ctx.frames as usize;
+        let in_channels = ctx.inputs.channels() as usize;
+        let out_channels = ctx.outputs.channels() as usize;
+        if in_channels == 0 || out_channels == 0 {
+            return;
+        }
+
+        let cycle = ctx.transport.samples_per_beat(self.sample_rate) * self.cycle_beats as f64;
+        let increment = if cycle > 0.0 { 1.0 / cycle } else { 0.0 };
+        let synced = ctx.transport.is_playing && cycle > 0.0;
+        let start = ctx.transport.sample_position;
+
+        for frame in 0..frames {
+            if synced {
+                self.phase = ((start + frame as u64) as f64 / cycle).fract();
+            }
+            self.pan = self.width * self.shape.value_at(self.phase);
+            self.phase = (self.phase + increment).fract();
+            let (g_l, g_r) = constant_power(self.pan);
+
+            let left = unsafe { ctx.inputs.read_sample(0, frame) };
+            if out_channels == 1 {
+                unsafe { ctx.outputs.write_sample(0, frame, left * g_l) };
+                continue;
+            }
+            let right = if in_channels > 1 {
+                unsafe { ctx.inputs.read_sample(1, frame) }
+            } else {
+                left
+            };
+            unsafe {
+                ctx.outputs.write_sample(0, frame, left * g_l);
+                ctx.outputs.write_sample(1, frame, right * g_r);
+            }
+            for ch in 2..out_channels {
+                let sample = if ch < in_channels {
+                    unsafe { ctx.inputs.read_sample(ch, frame) }
+                } else {
+                    0.0
+                };
+                unsafe { ctx.outputs.write_sample(ch, frame, sample) };
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use harmoniq_dsp::bitcrush::BitCrusher;
+
+use crate::dsp::graph::{DspNode, ProcessContext};
+use crate::dsp::params::ParamUpdate;
+
+/// Bit-depth and sample-rate reducer for lo-fi textures, driving
+/// [`harmoniq_dsp::bitcrush::BitCrusher`].
+///
+/// Parameter `0` sets the bit depth (1–24) and parameter `1` the target
+/// sample rate in hertz, converted to a hold factor against the rate the
+/// node is prepared at. The reduced rate is produced by sample-and-hold
+/// without any anti-alias filtering, so aliasing is part of the sound; at
+/// 24 bits and a target rate at or above the engine rate the node is a
+/// clean pass-through.
+pub struct BitCrusherNode {
+    crusher: BitCrusher,
+    target_rate: f32,
+    sample_rate: f32,
+}
+
+impl BitCrusherNode {
+    pub const BITS_PARAM: u32 = 0;
+    pub const RATE_PARAM: u32 = 1;
+    pub const MAX_BITS: f32 = BitCrusher::MAX_BITS;
+
+    pub fn new(bits: f32, target_rate: f32) -> Self {
+        let mut node = Self {
+            crusher: BitCrusher::new(Self::MAX_BITS, 1.0),
+            target_rate: 0.0,
+            // Unknown until `prepare`; no rate reduction happens before.
+            sample_rate: 0.0,
+        };
+        node.set_bits(bits);
+        node.set_target_rate(target_rate);
+        node
+    }
+
+    pub fn bits(&self) -> f32 {
+        self.crusher.bit_depth
+    }
+
+    pub fn set_bits(&mut self, bits: f32) {
+        self.crusher.bit_depth = bits.clamp(BitCrusher::MIN_BITS, Self::MAX_BITS);
+    }
+
+    pub fn target_rate(&self) -> f32 {
+        self.target_rate
+    }
+
+    pub fn set_target_rate(&mut self, rate: f32) {
+        self.target_rate = rate.max(1.0);
+        self.crusher.downsample = (self.sample_rate / self.target_rate).max(1.0);
+    }
+}
+
+impl Default for BitCrusherNode {
+    fn default() -> Self {
+        Self::new(Self::MAX_BITS, f32::INFINITY)
+    }
+}
+
+impl DspNode for BitCrusherNode {
+    fn prepare(&mut self, sr: f32, _max_block: u32, in_ch: u32, out_ch: u32) {
+        self.sample_rate = sr.max(1.0);
+        self.crusher.prepare(in_ch.max(out_ch) as usize);
+        self.set_target_rate(self.target_rate);
+    }
+
+    fn reset(&mut self) {
+        self.crusher.reset();
+    }
+
+    fn param(&mut self, update: ParamUpdate) {
+        match update.id {
+            Self::BITS_PARAM => self.set_bits(update.value),
+            Self::RATE_PARAM => self.set_target_rate(update.value),
+            _ => {}
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext<'_>) {
+        let frames = ctx.frames as usize;
+        self.crusher
+            .process_into(&ctx.inputs, &mut ctx.outputs, frames);
+    }
+}
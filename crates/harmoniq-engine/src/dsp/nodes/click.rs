@@ -95,6 +95,27 @@ impl DspNode for MetronomeClickNode {
             self.last_map_version = transport.map_version;
         }
 
+        if transport.count_in_remaining > 0 {
+            let beat = transport.samples_per_beat(self.sample_rate).round() as u64;
+            if beat > 0 {
+                let beats_per_bar = transport.time_signature.numerator.max(1) as u64;
+                let end = (ctx.frames as u64).min(transport.count_in_remaining);
+                // Count-in beats fall where the remaining count is a whole
+                // number of beats, accenting each bar of the count-in.
+                let mut frame = transport.count_in_remaining % beat;
+                while frame < end {
+                    let beats_left = (transport.count_in_remaining - frame) / beat;
+                    let gain = if beats_left % beats_per_bar == 0 {
+                        self.accent_gain
+                    } else {
+                        self.beat_gain
+                    };
+                    Self::write_impulse(&mut ctx.outputs, frame as usize, gain);
+                    frame += beat;
+                }
+            }
+        }
+
         if !transport.is_playing {
             self.ensure_next_beat(transport, self.sample_rate);
             self.last_position = block_end;
@@ -0,0 +1,150 @@
+use harmoniq_dsp::dynamics::hard_knee;
+use harmoniq_dsp::gain::db_to_linear;
+
+use crate::dsp::graph::{DspNode, ProcessContext};
+use crate::dsp::params::ParamUpdate;
+
+/// Sidechain ducker that lowers its main input while a key signal is loud.
+///
+/// The node writes as many channels as it has outputs. Input channels beyond
+/// that count are the key; without extra inputs the main signal keys itself.
+/// A peak follower tracks the key with the attack and release times, and the
+/// level above the threshold is reduced by the ratio.
+pub struct DuckerNode {
+    threshold_db: f32,
+    ratio: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    sample_rate: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+    gain: f32,
+}
+
+impl DuckerNode {
+    pub const THRESHOLD_PARAM: u32 = 0;
+    pub const RATIO_PARAM: u32 = 1;
+    pub const ATTACK_PARAM: u32 = 2;
+    pub const RELEASE_PARAM: u32 = 3;
+
+    pub fn new(threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32) -> Self {
+        let mut node = Self {
+            threshold_db,
+            ratio: ratio.max(1.0),
+            attack_ms: attack_ms.max(0.01),
+            release_ms: release_ms.max(0.01),
+            sample_rate: 48_000.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            envelope: 0.0,
+            gain: 1.0,
+        };
+        node.update_coefficients();
+        node
+    }
+
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold_db = threshold_db;
+    }
+
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio.max(1.0);
+    }
+
+    pub fn set_attack_ms(&mut self, attack_ms: f32) {
+        self.attack_ms = attack_ms.max(0.01);
+        self.update_coefficients();
+    }
+
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.max(0.01);
+        self.update_coefficients();
+    }
+
+    /// Linear gain applied to the last processed frame.
+    pub fn current_gain(&self) -> f32 {
+        self.gain
+    }
+
+    fn update_coefficients(&mut self) {
+        self.attack_coeff = time_to_coeff(self.attack_ms, self.sample_rate);
+        self.release_coeff = time_to_coeff(self.release_ms, self.sample_rate);
+    }
+
+    #[inline]
+    fn follow(&mut self, key_level: f32) -> f32 {
+        let coeff = if key_level > self.envelope {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.envelope = key_level + coeff * (self.envelope - key_level);
+        self.gain = if self.envelope > 1e-6 {
+            let level_db = 20.0 * self.envelope.log10();
+            db_to_linear(hard_knee(level_db, self.threshold_db, self.ratio))
+        } else {
+            1.0
+        };
+        self.gain
+    }
+}
+
+impl Default for DuckerNode {
+    fn default() -> Self {
+        Self::new(-24.0, 8.0, 1.0, 150.0)
+    }
+}
+
+fn time_to_coeff(ms: f32, sample_rate: f32) -> f32 {
+    (-1.0 / (ms / 1_000.0 * sample_rate.max(1.0))).exp()
+}
+
+impl DspNode for DuckerNode {
+    fn prepare(&mut self, sr: f32, _max_block: u32, _in_ch: u32, _out_ch: u32) {
+        self.sample_rate = sr.max(1.0);
+        self.update_coefficients();
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.envelope = 0.0;
+        self.gain = 1.0;
+    }
+
+    fn param(&mut self, update: ParamUpdate) {
+        match update.id {
+            Self::THRESHOLD_PARAM => self.set_threshold_db(update.value),
+            Self::RATIO_PARAM => self.set_ratio(update.value),
+            Self::ATTACK_PARAM => self.set_attack_ms(update.value),
+            Self::RELEASE_PARAM => self.set_release_ms(update.value),
+            _ => {}
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext<'_>) {
+        let frames = ctx.frames as usize;
+        let in_channels = ctx.inputs.channels() as usize;
+        let out_channels = ctx.outputs.channels() as usize;
+        let main_channels = in_channels.min(out_channels);
+        let key = if in_channels > out_channels {
+            out_channels..in_channels
+        } else {
+            0..main_channels
+        };
+        for frame in 0..frames {
+            let key_level = key
+                .clone()
+                .map(|ch| unsafe { ctx.inputs.read_sample(ch, frame) }.abs())
+                .fold(0.0f32, f32::max);
+            let gain = self.follow(key_level);
+            for ch in 0..main_channels {
+                let input = unsafe { ctx.inputs.read_sample(ch, frame) };
+                unsafe { ctx.outputs.write_sample(ch, frame, input * gain) };
+            }
+            for ch in main_channels..out_channels {
+                unsafe { ctx.outputs.write_sample(ch, frame, 0.0) };
+            }
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use crate::dsp::graph::{DspNode, ParamPort, ProcessContext};
+use crate::dsp::params::ParamUpdate;
+
+/// A single breakpoint of a multi-segment envelope: ramp linearly to `level`
+/// over `time_seconds`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnvelopeSegment {
+    pub level: f32,
+    pub time_seconds: f32,
+}
+
+impl EnvelopeSegment {
+    pub fn new(level: f32, time_seconds: f32) -> Self {
+        Self {
+            level,
+            time_seconds: time_seconds.max(0.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnvelopeTrigger {
+    /// Note-on opens the gate, releasing every held note closes it.
+    Midi,
+    /// The gate follows the transport play state.
+    Transport,
+}
+
+/// Destination of the envelope output on another node's parameter port.
+pub struct ModulationRoute {
+    port: ParamPort,
+    param: u32,
+    min: f32,
+    max: f32,
+}
+
+impl ModulationRoute {
+    pub fn new(port: ParamPort, param: u32, min: f32, max: f32) -> Self {
+        Self {
+            port,
+            param,
+            min,
+            max,
+        }
+    }
+
+    #[inline]
+    fn send(&mut self, level: f32, frames: u32) {
+        let value = self.min + (self.max - self.min) * level;
+        self.port.send(ParamUpdate::ramp(self.param, value, frames));
+    }
+}
+
+/// Standalone multi-segment envelope that modulates parameters on other nodes.
+///
+/// Audio passes through untouched; at the end of every block the current
+/// envelope level is mapped into each route's range and pushed to the target
+/// parameter port as a ramp over the block, so targets that follow
+/// [`ParamRamp`](crate::dsp::ParamRamp) glide per sample instead of stepping.
+/// Parameter `0` acts as a gate so the envelope can also be driven from
+/// automation.
+pub struct EnvelopeNode {
+    segments: Vec<EnvelopeSegment>,
+    sustain: Option<usize>,
+    loop_range: Option<(usize, usize)>,
+    trigger: EnvelopeTrigger,
+    routes: Vec<ModulationRoute>,
+    sample_rate: f32,
+    stage: Option<usize>,
+    gate: bool,
+    level: f32,
+    start_level: f32,
+    elapsed: u32,
+    length: u32,
+    held_notes: u32,
+    was_playing: bool,
+}
+
+impl EnvelopeNode {
+    pub fn new(segments: Vec<EnvelopeSegment>) -> Self {
+        Self {
+            segments,
+            sustain: None,
+            loop_range: None,
+            trigger: EnvelopeTrigger::Midi,
+            routes: Vec::new(),
+            sample_rate: 48_000.0,
+            stage: None,
+            gate: false,
+            level: 0.0,
+            start_level: 0.0,
+            elapsed: 0,
+            length: 0,
+            held_notes: 0,
+            was_playing: false,
+        }
+    }
+
+    pub fn adsr(attack: f32, decay: f32, sustain: f32, release: f32) -> Self {
+        Self::new(vec![
+            EnvelopeSegment::new(1.0, attack),
+            EnvelopeSegment::new(sustain.clamp(0.0, 1.0), decay),
+            EnvelopeSegment::new(0.0, release),
+        ])
+        .with_sustain(1)
+    }
+
+    /// Holds at the end of segment `index` while the gate is open.
+    pub fn with_sustain(mut self, index: usize) -> Self {
+        self.sustain = Some(index);
+        self
+    }
+
+    /// Loops segments `start..=end` while the gate is open.
+    pub fn with_loop(mut self, start: usize, end: usize) -> Self {
+        if start <= end {
+            self.loop_range = Some((start, end));
+        }
+        self
+    }
+
+    pub fn with_trigger(mut self, trigger: EnvelopeTrigger) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    pub fn with_route(mut self, route: ModulationRoute) -> Self {
+        self.routes.push(route);
+        self
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.stage.is_some()
+    }
+
+    pub fn gate_on(&mut self) {
+        self.gate = true;
+        self.enter_stage(0);
+    }
+
+    pub fn gate_off(&mut self) {
+        if !self.gate {
+            return;
+        }
+        self.gate = false;
+        let Some(stage) = self.stage else {
+            return;
+        };
+        let release_from = match (self.sustain, self.loop_range) {
+            (Some(sustain), _) if stage <= sustain => Some(sustain + 1),
+            (_, Some((start, end))) if stage >= start && stage <= end => Some(end + 1),
+            _ => None,
+        };
+        if let Some(next) = release_from {
+            self.enter_stage(next);
+        }
+    }
+
+    fn enter_stage(&mut self, index: usize) {
+        match self.segments.get(index) {
+            Some(segment) => {
+                self.stage = Some(index);
+                self.start_level = self.level;
+                self.elapsed = 0;
+                self.length = (segment.time_seconds * self.sample_rate).round() as u32;
+            }
+            None => self.stage = None,
+        }
+    }
+
+    fn finish_stage(&mut self, stage: usize) {
+        if self.gate && self.sustain == Some(stage) {
+            return;
+        }
+        match self.loop_range {
+            Some((start, end)) if self.gate && stage == end => self.enter_stage(start),
+            _ => self.enter_stage(stage + 1),
+        }
+    }
+
+    #[inline]
+    fn next_sample(&mut self) -> f32 {
+        let Some(stage) = self.stage else {
+            return self.level;
+        };
+        let target = self.segments[stage].level;
+        if self.elapsed >= self.length {
+            if self.gate && self.sustain == Some(stage) {
+                self.level = target;
+                return self.level;
+            }
+        } else {
+            self.elapsed += 1;
+            let t = self.elapsed as f32 / self.length as f32;
+            self.level = self.start_level + (target - self.start_level) * t;
+        }
+        if self.elapsed >= self.length {
+            self.level = target;
+            self.finish_stage(stage);
+        }
+        self.level
+    }
+
+    fn handle_midi(&mut self, data: [u8; 3]) {
+        match data[0] & 0xF0 {
+            0x90 if data[2] > 0 => {
+                self.held_notes = self.held_notes.saturating_add(1);
+                self.gate_on();
+            }
+            0x80 | 0x90 => {
+                self.held_notes = self.held_notes.saturating_sub(1);
+                if self.held_notes == 0 {
+                    self.gate_off();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl DspNode for EnvelopeNode {
+    fn prepare(&mut self, sr: f32, _max_block: u32, _in: u32, _out: u32) {
+        self.sample_rate = sr.max(1.0);
+    }
+
+    fn reset(&mut self) {
+        self.stage = None;
+        self.gate = false;
+        self.level = 0.0;
+        self.held_notes = 0;
+        self.was_playing = false;
+    }
+
+    fn param(&mut self, update: ParamUpdate) {
+        if update.id == 0 {
+            if update.value > 0.5 {
+                self.gate_on();
+            } else {
+                self.gate_off();
+            }
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext<'_>) {
+        let frames = ctx.frames as usize;
+
+        if self.trigger == EnvelopeTrigger::Transport {
+            let playing = ctx.transport.is_playing;
+            if playing && !self.was_playing {
+                self.gate_on();
+            } else if !playing && self.was_playing {
+                self.gate_off();
+            }
+            self.was_playing = playing;
+        }
+
+        let mut frame = 0usize;
+        for event in ctx.midi {
+            let offset = (event.sample_offset as usize).min(frames);
+            while frame < offset {
+                self.next_sample();
+                frame += 1;
+            }
+            if self.trigger == EnvelopeTrigger::Midi {
+                self.handle_midi(event.data);
+            }
+        }
+        while frame < frames {
+            self.next_sample();
+            frame += 1;
+        }
+
+        for route in &mut self.routes {
+            route.send(self.level, ctx.frames);
+        }
+
+        let channels = ctx.inputs.channels().min(ctx.outputs.channels()) as usize;
+        let out_channels = ctx.outputs.channels() as usize;
+        for frame in 0..frames {
+            for ch in 0..channels {
+                let sample = unsafe { ctx.inputs.read_sample(ch, frame) };
+                unsafe { ctx.outputs.write_sample(ch, frame, sample) };
+            }
+            for ch in channels..out_channels {
+                unsafe { ctx.outputs.write_sample(ch, frame, 0.0) };
+            }
+        }
+    }
+}
@@ -3,10 +3,10 @@ use harmoniq_dsp::smoothing::OnePole;
 use harmoniq_dsp::{ChanMut, ChanRef};
 
 use crate::dsp::graph::{DspNode, ProcessContext};
-use crate::dsp::params::ParamUpdate;
+use crate::dsp::params::{ParamRamp, ParamUpdate};
 
 pub struct GainNode {
-    target_db: f32,
+    target_db: ParamRamp,
     smoother: OnePole,
     gains: Vec<f32>,
 }
@@ -14,7 +14,7 @@ pub struct GainNode {
 impl GainNode {
     pub fn new(initial_db: f32) -> Self {
         Self {
-            target_db: initial_db,
+            target_db: ParamRamp::new(initial_db),
             smoother: OnePole::new(48_000.0, 5.0),
             gains: Vec::new(),
         }
@@ -24,13 +24,13 @@ impl GainNode {
 impl DspNode for GainNode {
     fn prepare(&mut self, sr: f32, max_block: u32, _in: u32, _out: u32) {
         self.smoother.set_time_ms(sr, 5.0);
-        self.smoother.reset(db_to_linear(self.target_db));
+        self.smoother.reset(db_to_linear(self.target_db.current()));
         self.gains.resize(max_block as usize, 0.0);
     }
 
     fn param(&mut self, update: ParamUpdate) {
         if update.id == 0 {
-            self.target_db = update.value;
+            self.target_db.apply(update);
         }
     }
 
@@ -40,9 +40,16 @@ impl DspNode for GainNode {
             self.gains.resize(frames, 0.0);
         }
         let channels = ctx.outputs.channels().min(ctx.inputs.channels()) as usize;
-        let target = db_to_linear(self.target_db);
-        for frame in 0..frames {
-            self.gains[frame] = self.smoother.next(target);
+        if self.target_db.is_ramping() {
+            for frame in 0..frames {
+                let target = db_to_linear(self.target_db.next());
+                self.gains[frame] = self.smoother.next(target);
+            }
+        } else {
+            let target = db_to_linear(self.target_db.current());
+            for frame in 0..frames {
+                self.gains[frame] = self.smoother.next(target);
+            }
         }
         let gains = &self.gains[..frames];
         for ch in 0..channels {
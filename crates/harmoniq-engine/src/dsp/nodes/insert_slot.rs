@@ -0,0 +1,156 @@
+use crate::dsp::graph::{DspNode, NodeLatency, ProcessContext};
+use crate::dsp::params::ParamUpdate;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BypassMode {
+    /// Bypass skips the insert and passes the input straight through.
+    Immediate,
+    /// Bypass keeps reporting the insert latency and delays the dry signal to match.
+    LatencyPreserving,
+}
+
+/// Plug-in container slot that hosts an insert and handles its bypass.
+///
+/// Parameter [`InsertSlot::BYPASS_PARAM`] toggles bypass; every other
+/// parameter update is forwarded to the hosted node.
+pub struct InsertSlot {
+    node: Box<dyn DspNode>,
+    mode: BypassMode,
+    bypassed: bool,
+    latency: u32,
+    dry_delay: Vec<Vec<f32>>,
+    write_pos: usize,
+}
+
+impl InsertSlot {
+    pub const BYPASS_PARAM: u32 = u32::MAX;
+
+    pub fn new(node: Box<dyn DspNode>) -> Self {
+        Self {
+            node,
+            mode: BypassMode::LatencyPreserving,
+            bypassed: false,
+            latency: 0,
+            dry_delay: Vec::new(),
+            write_pos: 0,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: BypassMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_bypass(mut self, bypassed: bool) -> Self {
+        self.bypassed = bypassed;
+        self
+    }
+
+    pub fn mode(&self) -> BypassMode {
+        self.mode
+    }
+
+    pub fn is_bypassed(&self) -> bool {
+        self.bypassed
+    }
+
+    pub fn set_bypass(&mut self, bypassed: bool) {
+        self.bypassed = bypassed;
+    }
+
+    /// Feeds the dry input into the compensation delay, optionally writing the
+    /// delayed signal to the outputs.
+    fn run_dry_delay(&mut self, ctx: &mut ProcessContext<'_>, write_output: bool) {
+        let frames = ctx.frames as usize;
+        let in_channels = ctx.inputs.channels() as usize;
+        let out_channels = ctx.outputs.channels() as usize;
+        let delay = self.latency as usize;
+        let mut write_pos = self.write_pos;
+        for frame in 0..frames {
+            for ch in 0..out_channels {
+                let input = if ch < in_channels {
+                    unsafe { ctx.inputs.read_sample(ch, frame) }
+                } else {
+                    0.0
+                };
+                let output = match self.dry_delay.get_mut(ch) {
+                    Some(line) if delay > 0 => {
+                        let delayed = line[write_pos];
+                        line[write_pos] = input;
+                        delayed
+                    }
+                    _ => input,
+                };
+                if write_output {
+                    unsafe { ctx.outputs.write_sample(ch, frame, output) };
+                }
+            }
+            if delay > 0 {
+                write_pos = (write_pos + 1) % delay;
+            }
+        }
+        self.write_pos = write_pos;
+    }
+}
+
+impl DspNode for InsertSlot {
+    fn prepare(&mut self, sr: f32, max_block: u32, in_ch: u32, out_ch: u32) {
+        self.node.prepare(sr, max_block, in_ch, out_ch);
+        self.latency = self.node.latency().samples;
+        let channels = out_ch.max(in_ch) as usize;
+        self.dry_delay = vec![vec![0.0; self.latency as usize]; channels];
+        self.write_pos = 0;
+    }
+
+    fn latency(&self) -> NodeLatency {
+        if self.bypassed && self.mode == BypassMode::Immediate {
+            NodeLatency { samples: 0 }
+        } else {
+            NodeLatency {
+                samples: self.latency,
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.node.reset();
+        for line in &mut self.dry_delay {
+            line.fill(0.0);
+        }
+        self.write_pos = 0;
+    }
+
+    fn param(&mut self, update: ParamUpdate) {
+        if update.id == Self::BYPASS_PARAM {
+            self.bypassed = update.value > 0.5;
+        } else {
+            self.node.param(update);
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext<'_>) {
+        match (self.bypassed, self.mode) {
+            (false, BypassMode::LatencyPreserving) => {
+                // Keep the dry line primed so switching to bypass stays aligned.
+                self.run_dry_delay(ctx, false);
+                self.node.process(ctx);
+            }
+            (false, BypassMode::Immediate) => self.node.process(ctx),
+            (true, BypassMode::LatencyPreserving) => self.run_dry_delay(ctx, true),
+            (true, BypassMode::Immediate) => {
+                let frames = ctx.frames as usize;
+                let channels = ctx.inputs.channels().min(ctx.outputs.channels()) as usize;
+                let out_channels = ctx.outputs.channels() as usize;
+                for frame in 0..frames {
+                    for ch in 0..channels {
+                        let sample = unsafe { ctx.inputs.read_sample(ch, frame) };
+                        unsafe { ctx.outputs.write_sample(ch, frame, sample) };
+                    }
+                    for ch in channels..out_channels {
+                        unsafe { ctx.outputs.write_sample(ch, frame, 0.0) };
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use harmoniq_dsp::loudness::{lufs, KWeighting};
+use harmoniq_dsp::true_peak::TruePeakDetector;
 use parking_lot::Mutex;
 
 use crate::buffer::AudioBuffer;
@@ -42,104 +44,6 @@ impl MeterHandle {
     }
 }
 
-#[derive(Clone, Debug)]
-struct Biquad {
-    b0: f32,
-    b1: f32,
-    b2: f32,
-    a1: f32,
-    a2: f32,
-    z1: f32,
-    z2: f32,
-}
-
-impl Biquad {
-    fn highpass(sample_rate: f32, freq: f32, q: f32) -> Self {
-        let sr = sample_rate.max(1.0);
-        let omega = 2.0 * core::f32::consts::PI * (freq / sr);
-        let sin = omega.sin();
-        let cos = omega.cos();
-        let alpha = sin / (2.0 * q.max(0.1));
-        let b0 = (1.0 + cos) * 0.5;
-        let b1 = -(1.0 + cos);
-        let b2 = (1.0 + cos) * 0.5;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos;
-        let a2 = 1.0 - alpha;
-        Self::normalize(b0, b1, b2, a0, a1, a2)
-    }
-
-    fn highshelf(sample_rate: f32, freq: f32, gain_db: f32, slope: f32) -> Self {
-        let sr = sample_rate.max(1.0);
-        let omega = 2.0 * core::f32::consts::PI * (freq / sr);
-        let sin = omega.sin();
-        let cos = omega.cos();
-        let a = 10.0f32.powf(gain_db * 0.05);
-        let beta = (a.sqrt() / slope.max(1e-6)).clamp(1e-6, 1e6);
-        let b0 = a * ((a + 1.0) + (a - 1.0) * cos + beta * sin);
-        let b1 = -2.0 * a * ((a - 1.0) + (a + 1.0) * cos);
-        let b2 = a * ((a + 1.0) + (a - 1.0) * cos - beta * sin);
-        let a0 = (a + 1.0) - (a - 1.0) * cos + beta * sin;
-        let a1 = 2.0 * ((a - 1.0) - (a + 1.0) * cos);
-        let a2 = (a + 1.0) - (a - 1.0) * cos - beta * sin;
-        Self::normalize(b0, b1, b2, a0, a1, a2)
-    }
-
-    fn normalize(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
-        let inv = if a0.abs() < 1e-6 { 1.0 } else { 1.0 / a0 };
-        Self {
-            b0: b0 * inv,
-            b1: b1 * inv,
-            b2: b2 * inv,
-            a1: a1 * inv,
-            a2: a2 * inv,
-            z1: 0.0,
-            z2: 0.0,
-        }
-    }
-
-    fn reset(&mut self) {
-        self.z1 = 0.0;
-        self.z2 = 0.0;
-    }
-
-    fn process(&mut self, input: f32) -> f32 {
-        let out = self.b0 * input + self.z1;
-        self.z1 = self.b1 * input - self.a1 * out + self.z2;
-        self.z2 = self.b2 * input - self.a2 * out;
-        out
-    }
-}
-
-#[derive(Clone, Debug)]
-struct KWeighting {
-    pre_highpass: Biquad,
-    high_shelf: Biquad,
-}
-
-impl KWeighting {
-    fn new(sample_rate: f32) -> Self {
-        let mut hp = Biquad::highpass(sample_rate, 40.0, 0.5);
-        let mut shelf = Biquad::highshelf(sample_rate, 4000.0, 4.0, 1.0);
-        hp.reset();
-        shelf.reset();
-        Self {
-            pre_highpass: hp,
-            high_shelf: shelf,
-        }
-    }
-
-    fn reset(&mut self) {
-        self.pre_highpass.reset();
-        self.high_shelf.reset();
-    }
-
-    fn process(&mut self, input: f32) -> f32 {
-        let filtered = self.pre_highpass.process(input);
-        self.high_shelf.process(filtered)
-    }
-}
-
 #[derive(Clone, Debug)]
 pub struct MeterTapNode {
     handle: MeterHandle,
@@ -149,7 +53,7 @@ pub struct MeterTapNode {
     ring_index: usize,
     ring_sum: f64,
     k_filters: Vec<KWeighting>,
-    prev_samples: Vec<f32>,
+    true_peak: Vec<TruePeakDetector>,
     max_true_peak: f32,
 }
 
@@ -164,7 +68,7 @@ impl MeterTapNode {
             ring_index: 0,
             ring_sum: 0.0,
             k_filters: Vec::new(),
-            prev_samples: Vec::new(),
+            true_peak: Vec::new(),
             max_true_peak: 0.0,
         }
     }
@@ -182,7 +86,7 @@ impl MeterTapNode {
         self.k_filters = (0..channels)
             .map(|_| KWeighting::new(self.sample_rate))
             .collect();
-        self.prev_samples = vec![0.0; channels];
+        self.true_peak = vec![TruePeakDetector::new(); channels];
         self.max_true_peak = 0.0;
     }
 
@@ -211,21 +115,9 @@ impl MeterTapNode {
             let mut weighted_energy = 0.0;
             for ch in 0..channels {
                 let sample = buffer.channel(ch)[frame];
-                let prev = self.prev_samples[ch];
-                let diff = sample - prev;
-                for step in 1..4 {
-                    let interp = prev + diff * (step as f32 / 4.0);
-                    let abs = interp.abs();
-                    if abs > self.max_true_peak {
-                        self.max_true_peak = abs;
-                    }
-                }
-                let abs = sample.abs();
-                if abs > self.max_true_peak {
-                    self.max_true_peak = abs;
-                }
-                self.prev_samples[ch] = sample;
-                let weighted = self.k_filters[ch].process(sample);
+                let peak = self.true_peak[ch].process(sample);
+                self.max_true_peak = self.max_true_peak.max(peak);
+                let weighted = self.k_filters[ch].process(sample as f64) as f32;
                 weighted_energy += weighted * weighted;
             }
             let average = weighted_energy / channels as f32;
@@ -254,11 +146,7 @@ impl MeterTapNode {
         } else {
             0.0
         };
-        let lufs = if mean_square > 0.0 {
-            -0.691 + 10.0 * mean_square.log10()
-        } else {
-            f64::NEG_INFINITY
-        } as f32;
+        let short_term = lufs(mean_square) as f32;
         let true_peak = if self.max_true_peak > 0.0 {
             20.0 * self.max_true_peak.log10()
         } else {
@@ -271,7 +159,7 @@ impl MeterTapNode {
         };
         self.handle.set(MeterReadout {
             true_peak_dbfs: true_peak,
-            short_term_lufs: lufs,
+            short_term_lufs: short_term,
             phase_correlation: correlation,
         });
         self.max_true_peak = 0.0;
@@ -1,17 +1,29 @@
+mod auto_pan;
+mod bit_crusher;
 mod click;
+mod ducker;
+mod envelope;
 mod fader;
 mod gain;
+mod insert_slot;
 mod meter_tap;
 mod pan;
+mod spectrum_tap;
 mod stereo_delay;
 mod stereo_width;
 mod svf_lowpass;
 
+pub use auto_pan::{AutoPanNode, AutoPanShape};
+pub use bit_crusher::BitCrusherNode;
 pub use click::MetronomeClickNode;
+pub use ducker::DuckerNode;
+pub use envelope::{EnvelopeNode, EnvelopeSegment, EnvelopeTrigger, ModulationRoute};
 pub use fader::FaderNode;
 pub use gain::GainNode;
+pub use insert_slot::{BypassMode, InsertSlot};
 pub use meter_tap::{MeterHandle, MeterReadout, MeterTapNode};
 pub use pan::PanNode;
+pub use spectrum_tap::{SpectrumHandle, SpectrumTap, SPECTRUM_FLOOR_DB};
 pub use stereo_delay::StereoDelayNode;
 pub use stereo_width::StereoWidthNode;
 pub use svf_lowpass::SvfLowpassNode;
@@ -2,17 +2,17 @@ use harmoniq_dsp::pan::constant_power;
 use harmoniq_dsp::smoothing::OnePole;
 
 use crate::dsp::graph::{DspNode, ProcessContext};
-use crate::dsp::params::ParamUpdate;
+use crate::dsp::params::{ParamRamp, ParamUpdate};
 
 pub struct PanNode {
-    pan: f32,
+    pan: ParamRamp,
     smoother: OnePole,
 }
 
 impl PanNode {
     pub fn new(initial_pan: f32) -> Self {
         Self {
-            pan: initial_pan.clamp(-1.0, 1.0),
+            pan: ParamRamp::new(initial_pan.clamp(-1.0, 1.0)),
             smoother: OnePole::new(48_000.0, 5.0),
         }
     }
@@ -21,12 +21,13 @@ impl PanNode {
 impl DspNode for PanNode {
     fn prepare(&mut self, sr: f32, _max_block: u32, _in: u32, _out: u32) {
         self.smoother.set_time_ms(sr, 5.0);
-        self.smoother.reset(self.pan);
+        self.smoother.reset(self.pan.current());
     }
 
     fn param(&mut self, update: ParamUpdate) {
         if update.id == 0 {
-            self.pan = update.value.clamp(-1.0, 1.0);
+            self.pan
+                .set(update.value.clamp(-1.0, 1.0), update.ramp_frames);
         }
     }
 
@@ -37,7 +38,7 @@ impl DspNode for PanNode {
             return;
         }
         for frame in 0..frames {
-            let pan = self.smoother.next(self.pan);
+            let pan = self.smoother.next(self.pan.next());
             let (g_l, g_r) = constant_power(pan);
             let left = unsafe { ctx.inputs.read_sample(0, frame) } * g_l;
             if channels == 1 {
@@ -0,0 +1,188 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use harmoniq_dsp::fft::{Complex, Fft};
+
+use crate::buffer::AudioBuffer;
+
+/// Level reported for bins with no energy.
+pub const SPECTRUM_FLOOR_DB: f32 = -160.0;
+
+/// Most analyses run per second, whatever the overlap. Twice the rate a UI
+/// redraws at, so a ~30 Hz reader always finds a fresh frame.
+const MAX_ANALYSES_PER_SECOND: f32 = 60.0;
+
+/// Read side of a [`SpectrumTap`]. Bins are stored as atomics, so reading
+/// never blocks the audio thread. A read racing an update may mix bins from
+/// two neighbouring frames.
+#[derive(Clone, Debug)]
+pub struct SpectrumHandle {
+    inner: Arc<SpectrumShared>,
+}
+
+#[derive(Debug)]
+struct SpectrumShared {
+    fft_size: usize,
+    bins: Vec<AtomicU32>,
+    generation: AtomicU64,
+}
+
+impl SpectrumHandle {
+    /// A handle for a tap running `fft_size`-point transforms, rounded up
+    /// to a power of two of at least 16.
+    pub fn new(fft_size: usize) -> Self {
+        let fft_size = fft_size.max(16).next_power_of_two();
+        Self {
+            inner: Arc::new(SpectrumShared {
+                fft_size,
+                bins: (0..fft_size / 2 + 1)
+                    .map(|_| AtomicU32::new(SPECTRUM_FLOOR_DB.to_bits()))
+                    .collect(),
+                generation: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.inner.fft_size
+    }
+
+    /// Magnitudes in dBFS from DC up to Nyquist, `fft_size / 2 + 1` bins.
+    pub fn spectrum(&self) -> Vec<f32> {
+        self.inner
+            .bins
+            .iter()
+            .map(|bin| f32::from_bits(bin.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Counts completed analyses, so a reader can skip redrawing when
+    /// nothing changed.
+    pub fn generation(&self) -> u64 {
+        self.inner.generation.load(Ordering::Acquire)
+    }
+}
+
+/// Pass-through analysis node publishing the magnitude spectrum of the
+/// channel average through a Hann-windowed FFT.
+#[derive(Clone, Debug)]
+pub struct SpectrumTap {
+    handle: SpectrumHandle,
+    sample_rate: f32,
+    fft: Fft,
+    window: Vec<f32>,
+    /// Normalises a full-scale sine to 0 dBFS.
+    scale: f32,
+    overlap: usize,
+    /// Samples between analyses.
+    interval: usize,
+    history: Vec<f32>,
+    write: usize,
+    since_analysis: usize,
+    scratch: Vec<Complex>,
+}
+
+impl SpectrumTap {
+    /// A tap publishing to `handle`, sized by it, with 4x overlap.
+    pub fn new(sample_rate: f32, handle: SpectrumHandle) -> Self {
+        let size = handle.fft_size();
+        let window: Vec<f32> = (0..size)
+            .map(|n| {
+                let phase = std::f32::consts::TAU * n as f32 / size as f32;
+                0.5 - 0.5 * phase.cos()
+            })
+            .collect();
+        let scale = 2.0 / window.iter().sum::<f32>();
+        let mut tap = Self {
+            handle,
+            sample_rate,
+            fft: Fft::new(size),
+            window,
+            scale,
+            overlap: 4,
+            interval: 0,
+            history: vec![0.0; size],
+            write: 0,
+            since_analysis: 0,
+            scratch: vec![Complex::ZERO; size],
+        };
+        tap.prepare(sample_rate);
+        tap
+    }
+
+    /// Analyses every `fft_size / overlap` samples, limited to
+    /// [`MAX_ANALYSES_PER_SECOND`].
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap.max(1);
+        self.prepare(self.sample_rate);
+        self
+    }
+
+    pub fn prepare(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate.max(1.0);
+        let hop = self.fft_size() / self.overlap;
+        let decimated = (self.sample_rate / MAX_ANALYSES_PER_SECOND).ceil() as usize;
+        self.interval = hop.max(decimated).max(1);
+        self.history.fill(0.0);
+        self.write = 0;
+        self.since_analysis = 0;
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.fft.len()
+    }
+
+    pub fn handle(&self) -> SpectrumHandle {
+        self.handle.clone()
+    }
+
+    /// See [`SpectrumHandle::spectrum`].
+    pub fn spectrum(&self) -> Vec<f32> {
+        self.handle.spectrum()
+    }
+
+    /// Centre frequency of `bin` in Hz.
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate / self.fft_size() as f32
+    }
+
+    /// Feeds `buffer` to the analyser. The audio itself is not touched.
+    pub fn process_buffer(&mut self, buffer: &AudioBuffer) {
+        let channels = buffer.channel_count();
+        if channels == 0 || buffer.is_empty() {
+            return;
+        }
+        let gain = 1.0 / channels as f32;
+        let size = self.history.len();
+        for frame in 0..buffer.len() {
+            let sum: f32 = (0..channels).map(|ch| buffer.channel(ch)[frame]).sum();
+            self.history[self.write] = sum * gain;
+            self.write = (self.write + 1) % size;
+            self.since_analysis += 1;
+            if self.since_analysis >= self.interval {
+                self.since_analysis = 0;
+                self.analyse();
+            }
+        }
+    }
+
+    fn analyse(&mut self) {
+        let size = self.history.len();
+        // `write` is the oldest sample in the history.
+        for (index, value) in self.scratch.iter_mut().enumerate() {
+            let sample = self.history[(self.write + index) % size];
+            *value = Complex::new(sample * self.window[index], 0.0);
+        }
+        self.fft.forward(&mut self.scratch);
+        for (bin, slot) in self.handle.inner.bins.iter().enumerate() {
+            let magnitude = self.scratch[bin].norm() * self.scale;
+            let db = if magnitude > 0.0 {
+                (20.0 * magnitude.log10()).max(SPECTRUM_FLOOR_DB)
+            } else {
+                SPECTRUM_FLOOR_DB
+            };
+            slot.store(db.to_bits(), Ordering::Relaxed);
+        }
+        self.handle.inner.generation.fetch_add(1, Ordering::Release);
+    }
+}
@@ -2,11 +2,90 @@
 pub struct ParamUpdate {
     pub id: u32,
     pub value: f32,
+    /// Frames over which the parameter glides to `value`; `0` jumps.
+    pub ramp_frames: u32,
 }
 
 impl ParamUpdate {
     #[inline]
     pub fn new(id: u32, value: f32) -> Self {
-        Self { id, value }
+        Self {
+            id,
+            value,
+            ramp_frames: 0,
+        }
+    }
+
+    /// Glide linearly to `value` over the next `frames` frames the node processes.
+    #[inline]
+    pub fn ramp(id: u32, value: f32, frames: u32) -> Self {
+        Self {
+            id,
+            value,
+            ramp_frames: frames,
+        }
+    }
+}
+
+/// Per-sample value of a parameter fed by [`ParamUpdate`]s, so modulation
+/// lands with its ramp instead of stepping once per block.
+#[derive(Clone, Copy, Debug)]
+pub struct ParamRamp {
+    current: f32,
+    target: f32,
+    step: f32,
+    remaining: u32,
+}
+
+impl ParamRamp {
+    pub fn new(value: f32) -> Self {
+        Self {
+            current: value,
+            target: value,
+            step: 0.0,
+            remaining: 0,
+        }
+    }
+
+    /// Starts the update's ramp from the current value, or jumps when it has none.
+    pub fn set(&mut self, value: f32, ramp_frames: u32) {
+        self.target = value;
+        self.remaining = ramp_frames;
+        if ramp_frames == 0 {
+            self.current = value;
+            self.step = 0.0;
+        } else {
+            self.step = (value - self.current) / ramp_frames as f32;
+        }
+    }
+
+    pub fn apply(&mut self, update: ParamUpdate) {
+        self.set(update.value, update.ramp_frames);
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn is_ramping(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Advances one sample and returns the new value.
+    #[inline]
+    pub fn next(&mut self) -> f32 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.current = if self.remaining == 0 {
+                self.target
+            } else {
+                self.current + self.step
+            };
+        }
+        self.current
     }
 }
@@ -6,33 +6,46 @@ use std::time::{Duration, Instant};
 
 use arc_swap::ArcSwap;
 use crossbeam::queue::ArrayQueue;
+use harmoniq_midi::{NoteRepeat, TimedMessage};
 use parking_lot::{Mutex, RwLock};
 
-use crate::audio_graph::{build_graph, GraphRunner};
+use crate::audio_graph::{build_graph, midi_track, GraphRunner};
 use crate::mixer::api::{MixerUiApi, MixerUiState};
 #[cfg(feature = "mixer_api")]
 use crate::mixer::control::{
-    ChannelId, EngineMixerHandle, MeterEvent, MixerBackend, SendId, MASTER_CHANNEL_ID,
+    ChannelId, EngineMixerHandle, MeterEvent, MixerBackend, SendId, SoloMode, VcaId,
+    MASTER_CHANNEL_ID,
+};
+use crate::mixer_rt::{
+    AutoTx, AutomationEvent as MixerAutomationEvent, Command, CommandTx, Mixer, MixerConfig,
+    TrackId,
 };
-use crate::mixer_rt::{AutoTx, Command, CommandTx, Mixer, MixerConfig, TrackId};
 use crate::{
     automation::{
-        AutomationCommand, AutomationEvent, AutomationLane, AutomationSender, CurveShape,
-        ParameterSpec,
+        AutomationCommand, AutomationEvent, AutomationLane, AutomationSender, AutomationSlot,
+        AutomationTarget, CurveShape, LaneTarget, ParameterSpec, MIXER_PAN_PARAM,
     },
-    delay::DelayCompensator,
-    graph::{GraphBuilder, GraphHandle},
+    core::state::{AutomationLaneState, AutomationOwner},
+    delay::{DelayCompensator, MidiDelay},
+    graph::{GraphBuilder, GraphHandle, NodeHandle},
+    humanize::HumanizeSettings,
+    legato::{MonoLegato, MonoLegatoSettings},
     nodes::{GainNode as BuiltinGain, NodeNoise as BuiltinNoise, NodeOsc as BuiltinSine},
-    plugin::{MidiEvent, PluginDescriptor, PluginId},
+    plugin::{MidiEvent, PluginDescriptor, PluginId, ProcessTransport},
+    render::oversample::Oversampler,
     rt::{AudioMetrics, AudioMetricsCollector},
     rt_bridge::RtBridge,
     sched::events::{slice_for_block as slice_events_for_block, Ev as ScheduledEvent, EventLane},
     scratch::RtAllocGuard,
     tone::ToneShaper,
     transport::Transport as TransportMetrics,
-    AudioBuffer, AudioClip, AudioProcessor, BufferConfig,
+    AudioBuffer, AudioClip, AudioProcessor, BufferConfig, LoopRegion, Tempo, TempoMap,
+    TimeSignature,
+};
+use harmoniq_playlist::state::{
+    AudioSourceId, AutomationPoint as PlaylistPoint, ClipId as PlaylistClipId, Playlist,
+    PlaylistClipKind,
 };
-use harmoniq_playlist::state::{AudioSourceId, PatternNote, Playlist, PlaylistClipKind};
 use harmoniq_rt::RtEvent;
 #[cfg(feature = "mixer_api")]
 use log::debug;
@@ -91,9 +104,17 @@ impl EngineCommandQueue {
 #[derive(Debug, Clone)]
 pub enum EngineCommand {
     SetTempo(f32),
+    /// Replaces the tempo and meter changes reported to processors.
+    SetTempoMap(TempoMap),
     SetTransport(TransportState),
+    /// Loops playback over a region, or clears the loop with `None`.
+    SetLoop(Option<LoopRegion>),
     SetPatternMode(bool),
     SetPlaylist(Playlist),
+    /// Replaces the project's track automation, played alongside the
+    /// playlist.
+    SetAutomationLanes(Vec<AutomationLaneState>),
+    SetHumanize(Option<HumanizeSettings>),
     RegisterAudioSource(AudioSourceId, AudioClip),
     ReplaceGraph(GraphHandle),
     SubmitMidi(Vec<MidiEvent>),
@@ -101,6 +122,28 @@ pub enum EngineCommand {
     PlaySoundTest(AudioClip),
 }
 
+/// What an [`AutomationTarget`] currently drives.
+#[derive(Debug, Clone)]
+pub enum ResolvedTarget {
+    /// A parameter registered by a processor.
+    Parameter {
+        plugin: PluginId,
+        spec: ParameterSpec,
+    },
+    /// A mixer strip's fader or pan, by `MIXER_*_PARAM` id.
+    Mixer { track: TrackId, parameter: u32 },
+}
+
+/// A track automation lane with its target resolved and its points placed
+/// in samples. Rebuilt only after the lanes or anything they address
+/// change; `cursor` is the next point to send.
+#[derive(Default)]
+struct ScheduledLane {
+    target: Option<ResolvedTarget>,
+    points: Vec<(u64, f32)>,
+    cursor: usize,
+}
+
 struct RtBlockSnapshot {
     graph: Option<GraphHandle>,
     plugin_ids: Vec<PluginId>,
@@ -110,6 +153,7 @@ struct RtBlockSnapshot {
     pending_midi: Vec<MidiEvent>,
     max_latency: usize,
     graph_runner: Option<Mutex<GraphRunner>>,
+    transport: ProcessTransport,
 }
 
 impl Default for RtBlockSnapshot {
@@ -123,6 +167,7 @@ impl Default for RtBlockSnapshot {
             pending_midi: Vec::new(),
             max_latency: 0,
             graph_runner: None,
+            transport: ProcessTransport::default(),
         }
     }
 }
@@ -131,16 +176,38 @@ impl Default for RtBlockSnapshot {
 pub struct HarmoniqEngine {
     config: BufferConfig,
     processors: RwLock<HashMap<PluginId, Arc<Mutex<Box<dyn AudioProcessor>>>>>,
+    /// Processors set aside by `freeze_track`, keyed by the frozen node.
+    pub(crate) frozen_tracks: HashMap<NodeHandle, Vec<(PluginId, Box<dyn AudioProcessor>)>>,
     graph: RwLock<Option<GraphHandle>>,
     master_buffer: Mutex<AudioBuffer>,
     tone_shaper: ToneShaper,
+    master_inserts: Vec<Box<dyn AudioProcessor>>,
+    master_oversampler: Option<Oversampler>,
     next_plugin_id: AtomicU64,
     transport: RwLock<TransportState>,
     pattern_mode: bool,
     tempo: f32,
+    tempo_map: TempoMap,
+    loop_region: Option<LoopRegion>,
     playlist: RwLock<Option<Playlist>>,
     playlist_last_tick: u64,
+    humanize: Option<HumanizeSettings>,
     playlist_audio: RwLock<HashMap<AudioSourceId, AudioClip>>,
+    /// Canonical targets of the playlist's automation clips, parsed when
+    /// the playlist is set.
+    playlist_targets: HashMap<PlaylistClipId, AutomationTarget>,
+    automation_lanes: Vec<AutomationLaneState>,
+    scheduled_lanes: Vec<ScheduledLane>,
+    /// Set when the lanes, their targets or the tempo map changed since
+    /// `scheduled_lanes` was built.
+    lanes_dirty: AtomicBool,
+    /// Where the block after the last scheduled one starts; any other start
+    /// moves the lane cursors.
+    lanes_next_sample: Option<u64>,
+    /// Each project track's instrument followed by its inserts.
+    track_plugins: HashMap<crate::core::state::TrackId, Vec<PluginId>>,
+    /// Saved form of each registered CLAP slot, keyed by its processor.
+    clap_inserts: HashMap<PluginId, crate::mixer::MixerInsertState>,
     transport_metrics: Arc<TransportMetrics>,
     command_queue: Arc<ArrayQueue<EngineCommand>>,
     midi_lane: EventLane,
@@ -153,6 +220,17 @@ pub struct HarmoniqEngine {
     automations: RwLock<HashMap<PluginId, AutomationLane>>,
     latencies: RwLock<HashMap<PluginId, usize>>,
     delay_lines: HashMap<PluginId, Box<DelayCompensator>>,
+    /// Compensation lines of sidechain keys, by (keyed plugin, key source).
+    sidechain_delays: HashMap<(PluginId, PluginId), Box<DelayCompensator>>,
+    midi_delays: HashMap<PluginId, Box<MidiDelay>>,
+    track_input_trims: Vec<f32>,
+    track_mono_legato: HashMap<TrackId, MonoLegato>,
+    /// Reused output of [`apply_mono_legato`](Self::apply_mono_legato).
+    legato_block: Vec<MidiEvent>,
+    /// Retriggers held live-input notes while armed.
+    note_repeat: NoteRepeat,
+    /// Reused input and output of [`apply_note_repeat`](Self::apply_note_repeat).
+    note_repeat_block: (Vec<TimedMessage>, Vec<TimedMessage>),
     sound_tests: Vec<ClipPlayback>,
     metrics: AudioMetricsCollector,
     block_period_ns: u64,
@@ -180,6 +258,7 @@ impl HarmoniqEngine {
     pub fn new(config: BufferConfig) -> anyhow::Result<Self> {
         let command_queue = Arc::new(ArrayQueue::new(COMMAND_QUEUE_CAPACITY));
         let tone_shaper = ToneShaper::new(&config);
+        let note_repeat = NoteRepeat::new(config.sample_rate);
         let metrics = AudioMetricsCollector::new(METRICS_HISTORY_CAPACITY);
         let block_period_ns = Self::block_period_from_config(&config);
         let transport_metrics = Arc::new(TransportMetrics::default());
@@ -193,6 +272,8 @@ impl HarmoniqEngine {
             sample_rate: config.sample_rate,
             smooth_alpha: 0.2,
             max_aux_busses: 4,
+            high_precision_master: config.high_precision_master,
+            ..MixerConfig::default()
         };
         let (mixer, command_tx, auto_tx) = Mixer::new(mixer_cfg, 4096, 4096);
         let mixer_ui = MixerUiState::demo();
@@ -202,14 +283,25 @@ impl HarmoniqEngine {
         let mut engine = Self {
             master_buffer: Mutex::new(AudioBuffer::from_config(&config)),
             processors: RwLock::new(HashMap::new()),
+            frozen_tracks: HashMap::new(),
             graph: RwLock::new(None),
             next_plugin_id: AtomicU64::new(1),
             transport: RwLock::new(TransportState::Stopped),
             pattern_mode: true,
             tempo: 120.0,
+            tempo_map: TempoMap::single(Tempo(120.0), TimeSignature::four_four()),
+            loop_region: None,
             playlist: RwLock::new(None),
             playlist_last_tick: 0,
+            humanize: None,
             playlist_audio: RwLock::new(HashMap::new()),
+            playlist_targets: HashMap::new(),
+            automation_lanes: Vec::new(),
+            scheduled_lanes: Vec::new(),
+            lanes_dirty: AtomicBool::new(false),
+            lanes_next_sample: None,
+            track_plugins: HashMap::new(),
+            clap_inserts: HashMap::new(),
             transport_metrics: Arc::clone(&transport_metrics),
             command_queue,
             midi_lane: EventLane::with_capacity(midi_capacity),
@@ -221,9 +313,18 @@ impl HarmoniqEngine {
             learn_automation: Vec::new(),
             config,
             tone_shaper,
+            master_inserts: Vec::new(),
+            master_oversampler: None,
             automations: RwLock::new(HashMap::new()),
             latencies: RwLock::new(HashMap::new()),
             delay_lines: HashMap::new(),
+            sidechain_delays: HashMap::new(),
+            midi_delays: HashMap::new(),
+            track_input_trims: Vec::new(),
+            track_mono_legato: HashMap::new(),
+            legato_block: Vec::new(),
+            note_repeat,
+            note_repeat_block: (Vec::new(), Vec::new()),
             sound_tests: Vec::new(),
             metrics,
             block_period_ns,
@@ -261,6 +362,11 @@ impl HarmoniqEngine {
         &self.config
     }
 
+    /// Current project tempo in BPM.
+    pub fn tempo(&self) -> f32 {
+        self.tempo
+    }
+
     pub fn graph(&self) -> Option<GraphHandle> {
         self.graph.read().clone()
     }
@@ -296,6 +402,15 @@ impl HarmoniqEngine {
         for delay in self.delay_lines.values_mut() {
             delay.reset();
         }
+        for delay in self.sidechain_delays.values_mut() {
+            delay.reset();
+        }
+        for delay in self.midi_delays.values_mut() {
+            delay.reset();
+        }
+        for lane in self.automations.write().values_mut() {
+            lane.rewind();
+        }
 
         {
             let processors = self.processors.read();
@@ -303,6 +418,7 @@ impl HarmoniqEngine {
                 processor.lock().prepare(&self.config)?;
             }
         }
+        self.prepare_master_chain()?;
 
         Ok(())
     }
@@ -361,7 +477,10 @@ impl HarmoniqEngine {
         self.master_buffer = Mutex::new(AudioBuffer::from_config(&config));
         self.tone_shaper = ToneShaper::new(&self.config);
         self.tone_shaper.set_enabled(tone_enabled);
+        self.note_repeat.set_sample_rate(self.config.sample_rate);
+        self.note_repeat.reset();
         self.block_period_ns = Self::block_period_from_config(&self.config);
+        self.lanes_dirty.store(true, Ordering::Release);
         self.metrics.reset();
         self.transport_metrics
             .sr
@@ -369,6 +488,7 @@ impl HarmoniqEngine {
 
         self.mixer_cfg.max_block = self.config.block_size.max(1);
         self.mixer_cfg.sample_rate = self.config.sample_rate;
+        self.mixer_cfg.high_precision_master = self.config.high_precision_master;
         let (mixer, command_tx, auto_tx) = Mixer::new(self.mixer_cfg, 4096, 4096);
         self.mixer = mixer;
         self.mixer_command_tx = Mutex::new(command_tx);
@@ -392,7 +512,15 @@ impl HarmoniqEngine {
             }
         }
 
+        let factor = self
+            .master_oversampler
+            .as_ref()
+            .map_or(1, Oversampler::factor);
+        self.set_master_oversample(factor)?;
+
         self.delay_lines.clear();
+        self.sidechain_delays.clear();
+        self.midi_delays.clear();
         self.sound_tests.clear();
         self.automation_block.clear();
         self.midi_block.clear();
@@ -403,6 +531,61 @@ impl HarmoniqEngine {
         Ok(())
     }
 
+    /// Appends a processor to the master chain, which runs on the summed mix
+    /// after the tone shaper.
+    pub fn add_master_insert(
+        &mut self,
+        mut processor: Box<dyn AudioProcessor>,
+    ) -> anyhow::Result<()> {
+        processor.prepare(&self.master_chain_config())?;
+        processor.set_tempo(Tempo(self.tempo as f64));
+        self.master_inserts.push(processor);
+        Ok(())
+    }
+
+    pub fn master_insert_count(&self) -> usize {
+        self.master_inserts.len()
+    }
+
+    /// Runs the master chain at `factor` times the engine rate and prepares
+    /// its processors for that rate. Returns the latency the resampling
+    /// filters add to the master output. Only offline renders change the
+    /// factor; realtime playback always runs the chain at the base rate.
+    pub(crate) fn set_master_oversample(&mut self, factor: usize) -> anyhow::Result<usize> {
+        self.master_oversampler = (factor > 1).then(|| {
+            Oversampler::new(
+                factor,
+                self.config.layout.channels() as usize,
+                self.config.block_size,
+            )
+        });
+        self.prepare_master_chain()?;
+        Ok(self
+            .master_oversampler
+            .as_ref()
+            .map_or(0, Oversampler::latency))
+    }
+
+    fn master_chain_config(&self) -> BufferConfig {
+        let factor = self
+            .master_oversampler
+            .as_ref()
+            .map_or(1, Oversampler::factor);
+        BufferConfig {
+            sample_rate: self.config.sample_rate * factor as f32,
+            block_size: self.config.block_size * factor,
+            ..self.config.clone()
+        }
+    }
+
+    fn prepare_master_chain(&mut self) -> anyhow::Result<()> {
+        let config = self.master_chain_config();
+        for insert in &mut self.master_inserts {
+            insert.prepare(&config)?;
+        }
+        Ok(())
+    }
+
     /// Enables or disables the built-in tone shaper. By default the engine
     /// keeps the shaper bypassed so that the master bus remains sonically
     /// neutral when no additional effects are loaded.
@@ -440,6 +623,22 @@ impl HarmoniqEngine {
         self.transport_metrics
             .playing
             .store(now_playing, Ordering::Relaxed);
+        if was_playing != now_playing {
+            let sample = if now_playing {
+                0
+            } else {
+                self.transport_metrics.sample_pos.load(Ordering::Relaxed)
+            };
+            let command = if now_playing {
+                AutomationCommand::StartPass { sample }
+            } else {
+                AutomationCommand::StopPass { sample }
+            };
+            for lane in self.automations.write().values_mut() {
+                lane.drain_commands();
+                lane.apply_command(command.clone());
+            }
+        }
         if now_playing && !was_playing {
             self.transport_metrics
                 .sample_pos
@@ -455,6 +654,103 @@ impl HarmoniqEngine {
         }
     }
 
+    /// Enables seeded humanisation of scheduled playlist notes.
+    pub fn set_humanize(&mut self, settings: Option<HumanizeSettings>) {
+        self.humanize = settings;
+    }
+
+    pub fn humanize(&self) -> Option<HumanizeSettings> {
+        self.humanize
+    }
+
+    /// Assigns the processors of project track `track`: its instrument
+    /// followed by its inserts in chain order. Automation targets find the
+    /// track's processors and mixer strip through this; an empty list
+    /// removes the track.
+    pub fn set_track_plugins(
+        &mut self,
+        track: crate::core::state::TrackId,
+        plugins: Vec<PluginId>,
+    ) {
+        if plugins.is_empty() {
+            self.track_plugins.remove(&track);
+        } else {
+            self.track_plugins.insert(track, plugins);
+        }
+        self.lanes_dirty.store(true, Ordering::Release);
+    }
+
+    /// Sets the gain-staging trim applied to a track's incoming signal before
+    /// its processor runs. Unlike the fader, the trim changes what the insert
+    /// chain receives.
+    pub fn set_track_input_trim(&mut self, track: TrackId, db: f32) {
+        let index = track as usize;
+        if self.track_input_trims.len() <= index {
+            self.track_input_trims.resize(index + 1, 0.0);
+        }
+        self.track_input_trims[index] = db;
+    }
+
+    pub fn track_input_trim(&self, track: TrackId) -> f32 {
+        self.track_input_trims
+            .get(track as usize)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Enables or disables mono/legato note handling for a track. While
+    /// enabled, overlapping notes on the track reach its instrument as legato
+    /// transitions chosen by the settings' priority.
+    pub fn set_track_mono_legato(&mut self, track: TrackId, settings: Option<MonoLegatoSettings>) {
+        match settings {
+            Some(settings) => {
+                self.track_mono_legato
+                    .entry(track)
+                    .and_modify(|legato| legato.set_settings(settings))
+                    .or_insert_with(|| MonoLegato::new(settings));
+            }
+            None => {
+                self.track_mono_legato.remove(&track);
+            }
+        }
+    }
+
+    pub fn track_mono_legato(&self, track: TrackId) -> Option<MonoLegatoSettings> {
+        self.track_mono_legato
+            .get(&track)
+            .map(|legato| legato.settings())
+    }
+
+    pub fn note_repeat(&self) -> &NoteRepeat {
+        &self.note_repeat
+    }
+
+    /// Note repeat applied to live MIDI from [`EngineCommand::SubmitMidi`]
+    /// before it is routed to tracks; it follows the engine tempo.
+    pub fn note_repeat_mut(&mut self) -> &mut NoteRepeat {
+        &mut self.note_repeat
+    }
+
+    /// Loops playback over `region`, or plays straight through with `None`.
+    /// Empty regions clear the loop. Offline renders ignore it.
+    pub fn set_loop_region(&mut self, region: Option<LoopRegion>) {
+        self.loop_region = region.filter(|region| region.end > region.start);
+    }
+
+    pub fn loop_region(&self) -> Option<LoopRegion> {
+        self.loop_region
+    }
+
+    /// Puts the transport back in `state` at `sample`, as saved before an
+    /// offline render moved it.
+    pub(crate) fn restore_transport(&mut self, state: TransportState, sample: u64) {
+        self.set_transport(state);
+        self.transport_metrics
+            .sample_pos
+            .store(sample, Ordering::Relaxed);
+        self.automation_cursor = sample;
+    }
+
     pub fn transport_metrics(&self) -> Arc<TransportMetrics> {
         Arc::clone(&self.transport_metrics)
     }
@@ -464,6 +760,7 @@ impl HarmoniqEngine {
         mut processor: Box<dyn AudioProcessor>,
     ) -> anyhow::Result<PluginId> {
         processor.prepare(&self.config)?;
+        processor.set_tempo(Tempo(self.tempo as f64));
         let latency = processor.latency_samples();
         let id = PluginId(self.next_plugin_id.fetch_add(1, Ordering::SeqCst));
         let descriptor = processor.descriptor();
@@ -478,12 +775,41 @@ impl HarmoniqEngine {
         Ok(id)
     }
 
-    pub fn replace_graph(&mut self, graph: GraphHandle) -> anyhow::Result<()> {
+    /// Installs `processor` in place of the one registered as `id` and
+    /// returns the previous processor.
+    pub(crate) fn swap_processor(
+        &self,
+        id: PluginId,
+        processor: Box<dyn AudioProcessor>,
+    ) -> Option<Box<dyn AudioProcessor>> {
+        let handle = self.processors.read().get(&id)?.clone();
+        let mut slot = handle.lock();
+        Some(std::mem::replace(&mut *slot, processor))
+    }
+
+    /// Latency reported by processor `id` when it was last prepared.
+    pub(crate) fn processor_latency(&self, id: PluginId) -> usize {
+        self.latencies.read().get(&id).copied().unwrap_or(0)
+    }
+
+    pub fn replace_graph(&mut self, mut graph: GraphHandle) -> anyhow::Result<()> {
         if graph.is_empty() {
             anyhow::bail!("graph must contain at least one node");
         }
+        let latencies: Vec<usize> = {
+            let reported = self.latencies.read();
+            graph
+                .plugin_ids()
+                .iter()
+                .map(|id| reported.get(id).copied().unwrap_or(0))
+                .collect()
+        };
+        if let Err(err) = graph.recompute_pdc_checked(&latencies) {
+            warn!("delay compensation skipped part of the graph: {err}");
+        }
         self.configure_mixer_for_graph(&graph);
         *self.graph.write() = Some(graph);
+        self.lanes_dirty.store(true, Ordering::Release);
         Ok(())
     }
 
@@ -551,15 +877,9 @@ impl HarmoniqEngine {
                 break;
             }
             let track_id = idx as TrackId;
-            let gain = graph.gain_for(*node);
-            let gain_db = if gain <= 0.0 {
-                -90.0
-            } else {
-                20.0 * gain.log10()
-            };
             push(Command::SetGain {
                 track: track_id,
-                gain_db,
+                gain_db: gain_to_db(graph.gain_for(*node)),
             });
             push(Command::SetPan {
                 track: track_id,
@@ -591,16 +911,32 @@ impl HarmoniqEngine {
         match command {
             EngineCommand::SetTempo(tempo) => {
                 self.tempo = tempo.max(1.0);
+                self.tempo_map = self.tempo_map.with_tempo(Tempo(self.tempo as f64));
+                self.lanes_dirty.store(true, Ordering::Release);
+                self.broadcast_tempo();
+            }
+            EngineCommand::SetTempoMap(map) => {
+                self.tempo = (map.tempo_at(block_start_samples).0 as f32).max(1.0);
+                self.tempo_map = map;
+                self.lanes_dirty.store(true, Ordering::Release);
+                self.broadcast_tempo();
             }
             EngineCommand::SetTransport(state) => self.set_transport(state),
+            EngineCommand::SetLoop(region) => self.set_loop_region(region),
             EngineCommand::SetPatternMode(enabled) => {
                 self.pattern_mode = enabled;
                 self.playlist_last_tick = 0;
             }
             EngineCommand::SetPlaylist(playlist) => {
+                self.playlist_targets = playlist_automation_targets(&playlist);
                 *self.playlist.write() = Some(playlist);
                 self.playlist_last_tick = 0;
             }
+            EngineCommand::SetAutomationLanes(lanes) => {
+                self.automation_lanes = lanes;
+                self.lanes_dirty.store(true, Ordering::Release);
+            }
+            EngineCommand::SetHumanize(settings) => self.set_humanize(settings),
             EngineCommand::RegisterAudioSource(id, clip) => {
                 self.playlist_audio.write().insert(id, clip);
             }
@@ -616,6 +952,62 @@ impl HarmoniqEngine {
         Ok(())
     }
 
+    fn broadcast_tempo(&mut self) {
+        self.note_repeat.set_tempo(self.tempo);
+        let tempo = Tempo(self.tempo as f64);
+        for processor in self.processors.read().values() {
+            processor.lock().set_tempo(tempo);
+        }
+        for insert in &mut self.master_inserts {
+            insert.set_tempo(tempo);
+        }
+    }
+
+    /// Transport handed to processors for the block starting at `sample`.
+    fn process_transport(&self, sample: u64) -> ProcessTransport {
+        let state = self.transport();
+        let segment = self.tempo_map.segment_at(sample);
+        ProcessTransport {
+            tempo: segment.tempo,
+            time_signature: segment.time_signature,
+            sample_position: sample,
+            beat_position: self
+                .tempo_map
+                .beat_at_sample(self.config.sample_rate, sample as f64),
+            is_playing: matches!(state, TransportState::Playing | TransportState::Recording),
+            is_recording: state == TransportState::Recording,
+            loop_region: self.loop_region,
+        }
+    }
+
+    /// Re-sends the tempo to processors whenever playback enters a tempo
+    /// map segment with a different tempo.
+    fn follow_tempo_map(&mut self, sample: u64) {
+        let tempo = (self.tempo_map.tempo_at(sample).0 as f32).max(1.0);
+        if tempo != self.tempo {
+            self.tempo = tempo;
+            self.broadcast_tempo();
+        }
+    }
+
+    /// Sends a playhead that crossed the loop end during the block starting
+    /// at `block_start` back into the loop.
+    fn wrap_loop(&mut self, block_start: u64) {
+        let Some(region) = self.loop_region else {
+            return;
+        };
+        let position = self.transport_metrics.sample_pos.load(Ordering::Relaxed);
+        if block_start >= region.end || position < region.end {
+            return;
+        }
+        let wrapped = region.start + (position - region.end) % region.length();
+        self.transport_metrics
+            .sample_pos
+            .store(wrapped, Ordering::Relaxed);
+        self.automation_cursor = wrapped;
+        self.playlist_last_tick = 0;
+    }
+
     pub fn process_block(&mut self, output: &mut AudioBuffer) -> anyhow::Result<()> {
         if output.channel_count() != self.config.layout.channels() as usize
             || output.len() != self.config.block_size as usize
@@ -625,11 +1017,20 @@ impl HarmoniqEngine {
             ));
         }
 
-        self.render_block_with(|master, _| {
+        let block_start = self.transport_metrics.sample_pos.load(Ordering::Relaxed);
+        let result = self.render_block_with(|master, _| {
             for (target_channel, source_channel) in output.channels_mut().zip(master.channels()) {
                 target_channel.copy_from_slice(source_channel);
             }
-        })
+        });
+        // The device hears the monitor path; offline renders take the master
+        // through `render_block_with` and never get here.
+        let mut channels = output.channels_mut();
+        if let (Some(left), Some(right)) = (channels.next(), channels.next()) {
+            self.mixer.write_monitor(left, right);
+        }
+        self.wrap_loop(block_start);
+        result
     }
 
     pub(crate) fn render_block_with<R, F>(&mut self, mut visitor: F) -> anyhow::Result<R>
@@ -740,6 +1141,7 @@ impl HarmoniqEngine {
                 PlaylistClipKind::Pattern { pattern_id } => {
                     if let Some(pattern) = playlist.pattern(pattern_id) {
                         self.schedule_pattern_clip(
+                            clip.id.0,
                             clip.start_ticks,
                             clip.track_index as usize,
                             pattern,
@@ -757,11 +1159,20 @@ impl HarmoniqEngine {
                     }
                 }
                 PlaylistClipKind::Automation { ref lane } => {
+                    let target = match self.playlist_targets.get(&clip.id) {
+                        Some(target) => self.resolve_automation_target(target),
+                        None => {
+                            self.named_automation_target(clip.track_index as usize, &lane.parameter)
+                        }
+                    };
+                    let Some(target) = target else {
+                        continue;
+                    };
                     self.schedule_automation_clip(
-                        clip.track_index as usize,
+                        &target,
                         clip.start_ticks,
                         clip.length_ticks,
-                        lane,
+                        &lane.points,
                         block_start_tick,
                         block_end_tick,
                         block_start_samples,
@@ -774,41 +1185,36 @@ impl HarmoniqEngine {
         self.playlist_last_tick = block_end_tick;
     }
 
+    /// The target of a playlist lane that names its parameter rather than
+    /// addressing it: the parameter with that name, or else the first one,
+    /// on the processor at `track_index`.
+    fn named_automation_target(&self, track_index: usize, name: &str) -> Option<ResolvedTarget> {
+        let plugin = self
+            .graph
+            .read()
+            .as_ref()?
+            .plugin_ids()
+            .get(track_index)
+            .copied()?;
+        let spec = self
+            .automation_parameter_index(plugin, name)
+            .and_then(|index| self.automation_parameter_spec(plugin, index))
+            .or_else(|| self.automation_parameter_spec(plugin, 0))?;
+        Some(ResolvedTarget::Parameter { plugin, spec })
+    }
+
     fn schedule_automation_clip(
         &self,
-        track_index: usize,
+        target: &ResolvedTarget,
         clip_start_tick: u64,
         clip_duration_ticks: u64,
-        lane: &harmoniq_playlist::state::AutomationLane,
+        points: &[PlaylistPoint],
         block_start_tick: u64,
         block_end_tick: u64,
         block_start_samples: u64,
         samples_per_tick: f64,
     ) {
-        let graph = self.graph.read();
-        let Some(graph_handle) = graph.as_ref() else {
-            return;
-        };
-        let plugin_ids = graph_handle.plugin_ids();
-        let Some(plugin_id) = plugin_ids.get(track_index).copied() else {
-            return;
-        };
-
-        let Some(parameter) = self
-            .automation_parameter_index(plugin_id, &lane.parameter)
-            .or_else(|| {
-                self.automation_parameter_spec(plugin_id, 0)
-                    .map(|spec| spec.index)
-            })
-        else {
-            return;
-        };
-
-        let Some(sender) = self.automation_sender(plugin_id) else {
-            return;
-        };
-
-        for point in &lane.points {
+        for point in points {
             let absolute_tick = clip_start_tick.saturating_add(point.tick);
             if absolute_tick >= block_end_tick || absolute_tick < self.playlist_last_tick {
                 continue;
@@ -823,19 +1229,128 @@ impl HarmoniqEngine {
                 samples_per_tick,
                 self.config.block_size as u32,
             ) {
-                let sample = block_start_samples.saturating_add(offset as u64);
-                let _ = sender.send(AutomationCommand::DrawCurve {
-                    parameter,
-                    sample,
-                    value: point.value,
-                    shape: CurveShape::Linear,
-                });
+                self.send_automation_point(target, block_start_samples, offset, point.value);
+            }
+        }
+    }
+
+    /// Resolves the track automation lanes and places their points, given
+    /// in beats, in samples through the tempo map.
+    fn schedule_automation_lanes(&mut self) {
+        let sample_rate = self.config.sample_rate;
+        let mut scheduled = std::mem::take(&mut self.scheduled_lanes);
+        scheduled.resize_with(self.automation_lanes.len(), ScheduledLane::default);
+        for (lane, slot) in self.automation_lanes.iter().zip(&mut scheduled) {
+            slot.target = self.resolve_lane_target(&lane.target, &lane.owner);
+            slot.points.clear();
+            slot.points.extend(lane.points.iter().map(|point| {
+                let sample = self
+                    .tempo_map
+                    .sample_at_beat(sample_rate, point.beat as f64)
+                    .round() as u64;
+                (sample, point.value)
+            }));
+            slot.points.sort_by_key(|(sample, _)| *sample);
+            slot.cursor = 0;
+        }
+        self.scheduled_lanes = scheduled;
+        self.lanes_next_sample = None;
+    }
+
+    /// Looks up an unresolved lane target by name on the track that owns
+    /// the lane. Lanes owned by a clip are only played once resolved.
+    fn resolve_lane_target(
+        &self,
+        target: &LaneTarget,
+        owner: &AutomationOwner,
+    ) -> Option<ResolvedTarget> {
+        match (target, owner) {
+            (LaneTarget::Resolved(target), _) => self.resolve_automation_target(target),
+            (LaneTarget::Unresolved(name), AutomationOwner::Track(track)) => {
+                let target = self.automation_target_for(*track, name)?;
+                self.resolve_automation_target(&target)
+            }
+            (LaneTarget::Unresolved(_), AutomationOwner::Clip(_)) => None,
+        }
+    }
+
+    /// Plays the project's track automation for the block starting at
+    /// `block_start_samples`, advancing each lane's cursor past the points
+    /// it sends.
+    fn process_automation_lanes(&mut self, block_start_samples: u64, block_len_samples: u64) {
+        if self.lanes_dirty.swap(false, Ordering::AcqRel) {
+            self.schedule_automation_lanes();
+        }
+        let block_end = block_start_samples.saturating_add(block_len_samples);
+        let seek = self.lanes_next_sample != Some(block_start_samples);
+        self.lanes_next_sample = Some(block_end);
+        let mut lanes = std::mem::take(&mut self.scheduled_lanes);
+        for lane in &mut lanes {
+            if seek {
+                lane.cursor = lane
+                    .points
+                    .partition_point(|(sample, _)| *sample < block_start_samples);
+            }
+            while let Some(&(sample, value)) = lane.points.get(lane.cursor) {
+                if sample >= block_end {
+                    break;
+                }
+                if let Some(target) = &lane.target {
+                    let offset = (sample - block_start_samples) as u32;
+                    self.send_automation_point(target, block_start_samples, offset, value);
+                }
+                lane.cursor += 1;
+            }
+        }
+        self.scheduled_lanes = lanes;
+    }
+
+    /// Sends the automation point due `offset` samples into the block. A
+    /// mixer strip ramps to it over the samples before the point; mixer
+    /// gain values are linear and pan values run from 0 (left) to 1
+    /// (right).
+    fn send_automation_point(
+        &self,
+        target: &ResolvedTarget,
+        block_start_samples: u64,
+        offset: u32,
+        value: f32,
+    ) {
+        match target {
+            ResolvedTarget::Parameter { plugin, spec } => {
+                if let Some(sender) = self.automation_sender(*plugin) {
+                    let _ = sender.send(AutomationCommand::DrawCurve {
+                        parameter: spec.index,
+                        sample: block_start_samples.saturating_add(offset as u64),
+                        value,
+                        shape: CurveShape::Linear,
+                    });
+                }
+            }
+            ResolvedTarget::Mixer { track, parameter } => {
+                let event = if *parameter == MIXER_PAN_PARAM {
+                    MixerAutomationEvent::PanRamp {
+                        track: *track,
+                        to: value * 2.0 - 1.0,
+                        duration: offset,
+                    }
+                } else {
+                    MixerAutomationEvent::GainDbRamp {
+                        track: *track,
+                        to: gain_to_db(value),
+                        duration: offset,
+                    }
+                };
+                if let Err(event) = self.mixer_auto_tx.lock().push(event) {
+                    warn!("dropping mixer automation due to full queue: {:?}", event);
+                }
             }
         }
     }
 
     fn schedule_pattern_clip(
         &self,
+        clip_id: u64,
         clip_start_tick: u64,
         track_index: usize,
         pattern: &harmoniq_playlist::state::Pattern,
@@ -852,7 +1367,16 @@ impl HarmoniqEngine {
                 continue;
             }
 
-            let note_start_tick = clip_start_tick.saturating_add(note.start_ticks.max(0) as u64);
+            let mut note_start_tick =
+                clip_start_tick.saturating_add(note.start_ticks.max(0) as u64);
+            let mut velocity = note.velocity;
+            if let Some(humanize) = self.humanize {
+                let (timing, velocity_delta) = humanize.offsets(clip_id, note.id);
+                note_start_tick = note_start_tick
+                    .saturating_add_signed(timing)
+                    .max(clip_start_tick);
+                velocity = humanize.velocity(velocity, velocity_delta);
+            }
             let note_end_tick = note_start_tick.saturating_add(note.duration_ticks as u64);
 
             if note_start_tick >= block_start_tick && note_start_tick < block_end_tick {
@@ -862,7 +1386,13 @@ impl HarmoniqEngine {
                     samples_per_tick,
                     block_len_samples,
                 ) {
-                    pending_midi.push(Self::note_event(note, offset, true, track_channel));
+                    pending_midi.push(Self::note_event(
+                        note.pitch,
+                        velocity,
+                        offset,
+                        true,
+                        track_channel,
+                    ));
                 }
             }
 
@@ -873,7 +1403,13 @@ impl HarmoniqEngine {
                     samples_per_tick,
                     block_len_samples,
                 ) {
-                    pending_midi.push(Self::note_event(note, offset, false, track_channel));
+                    pending_midi.push(Self::note_event(
+                        note.pitch,
+                        velocity,
+                        offset,
+                        false,
+                        track_channel,
+                    ));
                 }
             }
         }
@@ -909,10 +1445,10 @@ impl HarmoniqEngine {
         (samples as f64 / samples_per_tick).floor() as u64
     }
 
-    fn note_event(note: &PatternNote, sample_offset: u32, on: bool, channel: u8) -> MidiEvent {
+    fn note_event(pitch: u8, velocity: u8, sample_offset: u32, on: bool, channel: u8) -> MidiEvent {
         let status = (if on { 0x90 } else { 0x80 }) | (channel & 0x0F);
-        let velocity = if on { note.velocity } else { 0 };
-        MidiEvent::new(sample_offset, [status, note.pitch, velocity])
+        let velocity = if on { velocity } else { 0 };
+        MidiEvent::new(sample_offset, [status, pitch, velocity])
     }
 
     fn trigger_audio_clip(&mut self, source: AudioSourceId) {
@@ -1101,21 +1637,100 @@ impl HarmoniqEngine {
         self.midi_block.clear();
         let slice = slice_events_for_block(&self.midi_lane, block_start_samples, block_len);
         for event in slice.ev {
-            if let ScheduledEvent::Midi(bytes, offset) = event {
-                self.midi_block.push(MidiEvent::new(*offset, *bytes));
+            match *event {
+                ScheduledEvent::Midi(bytes, offset) => {
+                    self.midi_block.push(MidiEvent::new(offset, bytes));
+                }
+                ScheduledEvent::NoteExpression {
+                    channel,
+                    note,
+                    controller,
+                    value,
+                    sample,
+                } => {
+                    self.midi_block.push(MidiEvent::note_expression(
+                        sample, channel, note, controller, value,
+                    ));
+                }
+                ScheduledEvent::Param { .. } => {}
             }
         }
     }
 
+    /// Runs the events of every mono/legato track through its processor.
+    /// Events reach tracks through [`midi_track`], the routing the graph
+    /// uses for the `plugins` plugin nodes of the block.
+    fn apply_mono_legato(&mut self, events: &mut Vec<MidiEvent>, plugins: usize) {
+        if self.track_mono_legato.is_empty() {
+            return;
+        }
+        let max_tracks = self.mixer_cfg.max_tracks;
+        let mut output = core::mem::take(&mut self.legato_block);
+        output.clear();
+        for event in events.drain(..) {
+            let legato = midi_track(event.channel(), plugins, max_tracks)
+                .and_then(|track| self.track_mono_legato.get_mut(&(track as TrackId)));
+            match legato {
+                Some(legato) => legato.process_event(&event, &mut output),
+                None => output.push(event),
+            }
+        }
+        core::mem::swap(events, &mut output);
+        self.legato_block = output;
+    }
+
+    /// Runs the live events of the block through the note repeat. Expression
+    /// events carry no MIDI bytes and bypass it.
+    fn apply_note_repeat(&mut self, events: &mut Vec<MidiEvent>, block_len: u32) {
+        if !self.note_repeat.is_armed() && !self.note_repeat.is_holding() {
+            return;
+        }
+        let (mut input, mut output) = core::mem::take(&mut self.note_repeat_block);
+        input.clear();
+        output.clear();
+        events.sort_by_key(MidiEvent::sample_offset);
+        events.retain(|event| match Self::midi_bytes(event) {
+            Some(bytes) => {
+                input.push(TimedMessage::new(event.sample_offset(), bytes));
+                false
+            }
+            None => true,
+        });
+        self.note_repeat.process(&input, block_len, &mut output);
+        events.extend(
+            output
+                .iter()
+                .map(|message| MidiEvent::new(message.offset, message.msg)),
+        );
+        events.sort_by_key(MidiEvent::sample_offset);
+        self.note_repeat_block = (input, output);
+    }
+
     fn enqueue_midi(&self, events: Vec<MidiEvent>, block_start_samples: u64) {
         for event in events {
-            let Some(bytes) = Self::midi_bytes(&event) else {
-                continue;
-            };
-
             let absolute_sample = block_start_samples.saturating_add(event.sample_offset() as u64);
             let bounded_sample = absolute_sample.min(u32::MAX as u64) as u32;
-            let scheduled = ScheduledEvent::Midi(bytes, bounded_sample);
+            let scheduled = match event {
+                MidiEvent::NoteExpression {
+                    channel,
+                    note,
+                    controller,
+                    value,
+                    ..
+                } => ScheduledEvent::NoteExpression {
+                    channel,
+                    note,
+                    controller,
+                    value,
+                    sample: bounded_sample,
+                },
+                other => {
+                    let Some(bytes) = Self::midi_bytes(&other) else {
+                        continue;
+                    };
+                    ScheduledEvent::Midi(bytes, bounded_sample)
+                }
+            };
 
             if self.midi_lane.push(scheduled).is_err()
                 && !self.midi_lane_warned_overflow.swap(true, Ordering::AcqRel)
@@ -1143,6 +1758,7 @@ impl HarmoniqEngine {
             MidiEvent::PitchBend {
                 channel, lsb, msb, ..
             } => Some([0xE0 | (channel & 0x0F), *lsb, *msb]),
+            MidiEvent::NoteExpression { .. } => None,
         }
     }
 
@@ -1158,6 +1774,7 @@ impl HarmoniqEngine {
         self.fill_midi_events_for_block(block_start_samples, block_len as u32);
 
         let mut midi_block = core::mem::take(&mut self.midi_block);
+        self.apply_note_repeat(&mut midi_block, block_len);
 
         if !self.pattern_mode
             && matches!(
@@ -1166,6 +1783,7 @@ impl HarmoniqEngine {
             )
         {
             self.process_playlist_block(block_start_samples, block_len_samples, &mut midi_block);
+            self.process_automation_lanes(block_start_samples, block_len_samples);
         }
 
         #[cfg(feature = "mixer_api")]
@@ -1191,6 +1809,7 @@ impl HarmoniqEngine {
             self.rt_snapshot.store(Arc::new(RtBlockSnapshot::default()));
             return Ok(());
         }
+        self.apply_mono_legato(&mut midi_block, plugin_ids.len());
 
         let processors_guard = self.processors.read();
         let processor_handles: Vec<_> = plugin_ids
@@ -1206,24 +1825,33 @@ impl HarmoniqEngine {
         let latencies_guard = self.latencies.read();
         let latencies: Vec<usize> = plugin_ids
             .iter()
-            .map(|plugin_id| *latencies_guard.get(plugin_id).unwrap_or(&0))
+            .map(|plugin_id| graph.clamp_latency(*latencies_guard.get(plugin_id).unwrap_or(&0)))
             .collect();
         drop(latencies_guard);
 
         self.fill_automation_events_for_block(&plugin_ids, block_start, block_len);
         self.append_learned_automation(&plugin_ids);
         let max_latency = latencies.iter().copied().max().unwrap_or(0);
+        self.follow_tempo_map(block_start_samples);
+        let transport = self.process_transport(block_start_samples);
 
         let mixer_ptr = NonNull::from(&mut self.mixer);
         let runner = build_graph(
             &plugin_ids,
             &processor_handles,
             &latencies,
+            &graph.plugin_inputs(),
+            &graph.plugin_midi_inputs(),
+            &graph.plugin_sidechain_inputs(),
+            &self.track_input_trims,
             &self.automation_block,
             &midi_block,
+            &transport,
             mixer_ptr,
             self.mixer_cfg,
             &mut self.delay_lines,
+            &mut self.sidechain_delays,
+            &mut self.midi_delays,
             self.config.layout.channels() as usize,
             self.config.block_size,
         );
@@ -1237,6 +1865,7 @@ impl HarmoniqEngine {
             pending_midi: midi_block.clone(),
             max_latency,
             graph_runner: Some(Mutex::new(runner)),
+            transport,
         };
 
         self.midi_block = midi_block;
@@ -1278,6 +1907,23 @@ impl HarmoniqEngine {
 
             let _guard = RtAllocGuard::enter();
             self.tone_shaper.process(&mut master);
+            if !self.master_inserts.is_empty() {
+                let inserts = &mut self.master_inserts;
+                let transport = &snapshot.transport;
+                let mut result = Ok(());
+                let mut run = |buffer: &mut AudioBuffer| {
+                    for insert in inserts.iter_mut() {
+                        if result.is_ok() {
+                            result = insert.process_with_context(buffer, transport);
+                        }
+                    }
+                };
+                match self.master_oversampler.as_mut() {
+                    Some(oversampler) => oversampler.process(&mut *master, run),
+                    None => run(&mut *master),
+                }
+                result?;
+            }
 
             let mut index = 0;
             while index < self.sound_tests.len() {
@@ -1402,6 +2048,32 @@ impl<'a> MixerBackend for MixerUiBridge<'a> {
         }
     }
 
+    fn set_vca_gain(&mut self, vca: VcaId, gain_db: f32) {
+        self.push_command(Command::SetVcaGain { vca, gain_db });
+    }
+
+    fn assign_vca(&mut self, ch: ChannelId, vca: Option<VcaId>) {
+        if let Some(track) = self.track_id(ch) {
+            self.push_command(Command::AssignVca { track, vca });
+        }
+    }
+
+    fn set_vca_parent(&mut self, vca: VcaId, parent: Option<VcaId>) {
+        self.push_command(Command::SetVcaParent { vca, parent });
+    }
+
+    fn set_solo_mode(&mut self, mode: SoloMode) {
+        self.push_command(Command::SetSoloMode { mode });
+    }
+
+    fn set_monitor_dim(&mut self, enabled: bool, dim_db: f32) {
+        self.push_command(Command::SetMonitorDim { enabled, dim_db });
+    }
+
+    fn set_monitor_mono(&mut self, enabled: bool) {
+        self.push_command(Command::SetMonitorMono { enabled });
+    }
+
     fn open_insert_browser(&mut self, ch: ChannelId, slot: Option<usize>) {
         debug!(channel = ch, ?slot, "open_insert_browser request");
     }
@@ -1527,6 +2199,86 @@ impl HarmoniqEngine {
             .map(|lane| lane.sender())
     }
 
+    /// Registers a hosted CLAP plug-in together with its parameters, so
+    /// automation sent through [`automation_sender`](Self::automation_sender)
+    /// reaches the plug-in as sample-accurate `clap_event_param_value`
+    /// events. Parameter indices follow
+    /// [`ClapSlot::parameter_ids`](crate::ClapSlot::parameter_ids).
+    #[cfg(feature = "clap_host")]
+    pub fn register_clap_slot(
+        &mut self,
+        slot: crate::host::clap_hosting::ClapSlot,
+    ) -> anyhow::Result<PluginId> {
+        let specs = slot.parameter_specs();
+        let insert = slot.insert_state();
+        let id = self.register_processor(Box::new(slot))?;
+        for spec in specs {
+            self.register_automation_parameter(id, spec)?;
+        }
+        self.clap_inserts.insert(id, insert);
+        Ok(id)
+    }
+
+    /// Writes the id, parameter order and plug-in state of every hosted
+    /// CLAP insert into the mixer track of the project track it was
+    /// assigned to with [`set_track_plugins`](Self::set_track_plugins),
+    /// ready for saving.
+    pub fn store_insert_states(
+        &self,
+        state: &mut crate::core::state::ProjectState,
+    ) -> anyhow::Result<()> {
+        let processors = self.processors.read();
+        let tracks = state.arrangement.tracks.iter();
+        for (track, mixer_track) in tracks.zip(state.mixer.tracks.iter_mut()) {
+            let Some(plugins) = self.track_plugins.get(&track.id) else {
+                continue;
+            };
+            for (slot, plugin) in plugins.iter().skip(1).enumerate() {
+                let (Some(insert), Some(processor)) =
+                    (self.clap_inserts.get(plugin), processors.get(plugin))
+                else {
+                    continue;
+                };
+                if mixer_track.pre_inserts.len() <= slot {
+                    mixer_track
+                        .pre_inserts
+                        .resize_with(slot + 1, Default::default);
+                }
+                let saved = &mut mixer_track.pre_inserts[slot];
+                saved.id = insert.id.clone();
+                saved.parameter_ids = insert.parameter_ids.clone();
+                saved.state = processor.lock().save_state()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Hands the plug-in state saved with each insert of a loaded project
+    /// back to the processor assigned to that insert. A hosted CLAP insert
+    /// restores its parameter order before registration instead, with
+    /// [`ClapSlot::with_insert_state`](crate::ClapSlot::with_insert_state).
+    pub fn restore_insert_states(
+        &self,
+        state: &crate::core::state::ProjectState,
+    ) -> anyhow::Result<()> {
+        let processors = self.processors.read();
+        let tracks = state.arrangement.tracks.iter();
+        for (track, mixer_track) in tracks.zip(&state.mixer.tracks) {
+            let Some(plugins) = self.track_plugins.get(&track.id) else {
+                continue;
+            };
+            for (plugin, insert) in plugins.iter().skip(1).zip(&mixer_track.pre_inserts) {
+                if insert.state.is_empty() {
+                    continue;
+                }
+                if let Some(processor) = processors.get(plugin) {
+                    processor.lock().load_state(&insert.state)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn register_automation_parameter(
         &self,
         plugin_id: PluginId,
@@ -1537,6 +2289,7 @@ impl HarmoniqEngine {
             .get_mut(&plugin_id)
             .ok_or_else(|| anyhow::anyhow!("missing automation lane for plugin"))?;
         lane.register_parameter(spec);
+        self.lanes_dirty.store(true, Ordering::Release);
         Ok(())
     }
 
@@ -1557,6 +2310,77 @@ impl HarmoniqEngine {
             .get(&plugin_id)
             .and_then(|lane| lane.parameter_spec(parameter))
     }
+
+    /// Resolves `target` through the processors assigned with
+    /// [`set_track_plugins`](Self::set_track_plugins). A mixer target
+    /// drives the strip fed by the last processor in the track's chain.
+    pub fn resolve_automation_target(&self, target: &AutomationTarget) -> Option<ResolvedTarget> {
+        let plugins = self.track_plugins.get(&target.track)?;
+        let plugin = match target.slot {
+            AutomationSlot::Instrument => *plugins.first()?,
+            AutomationSlot::Insert(slot) => *plugins.get(slot as usize + 1)?,
+            AutomationSlot::Mixer => {
+                if target.parameter > MIXER_PAN_PARAM {
+                    return None;
+                }
+                let last = plugins.last()?;
+                let index = self
+                    .graph
+                    .read()
+                    .as_ref()?
+                    .plugin_ids()
+                    .iter()
+                    .position(|id| id == last)?;
+                if index >= self.mixer_track_count {
+                    return None;
+                }
+                return Some(ResolvedTarget::Mixer {
+                    track: index as TrackId,
+                    parameter: target.parameter,
+                });
+            }
+        };
+        let spec = self.automation_parameter_spec(plugin, target.parameter as usize)?;
+        Some(ResolvedTarget::Parameter { plugin, spec })
+    }
+
+    /// Converts a legacy target string on `track`, falling back to the
+    /// parameter names registered by the track's processor.
+    pub fn automation_target_for(
+        &self,
+        track: crate::core::state::TrackId,
+        legacy: &str,
+    ) -> Option<AutomationTarget> {
+        if let Some(target) = AutomationTarget::from_legacy(track, legacy) {
+            return Some(target);
+        }
+        let plugin_id = *self.track_plugins.get(&track)?.first()?;
+        let index = self.automation_parameter_index(plugin_id, legacy.trim())?;
+        Some(AutomationTarget::instrument(track, index as u32))
+    }
+}
+
+/// Parses the addressed targets of `playlist`'s automation clips. Clips
+/// that name their parameter instead are looked up while playing.
+fn playlist_automation_targets(playlist: &Playlist) -> HashMap<PlaylistClipId, AutomationTarget> {
+    playlist
+        .playback_clips()
+        .into_iter()
+        .filter_map(|clip| match clip.kind {
+            PlaylistClipKind::Automation { lane } => {
+                lane.parameter.parse().ok().map(|target| (clip.id, target))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn gain_to_db(gain: f32) -> f32 {
+    if gain <= 0.0 {
+        -90.0
+    } else {
+        20.0 * gain.log10()
+    }
 }
 
 pub struct Engine {
@@ -0,0 +1,86 @@
+//! Per-note expression in the style of MPE and MIDI 2.0 per-note controllers.
+//!
+//! Controllers are addressed to a single sounding note (channel + note
+//! number) and travel through the graph as [`MidiEvent::NoteExpression`], so
+//! instruments can map them onto the parameters of the matching voice.
+//!
+//! [`MidiEvent::NoteExpression`]: crate::plugin::MidiEvent::NoteExpression
+
+use serde::{Deserialize, Serialize};
+
+/// Named per-note controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NoteController {
+    /// Pitch offset in semitones relative to the note.
+    Pitch,
+    /// Timbre or brightness, normalised to `0.0..=1.0` (MPE's CC74 slide).
+    Timbre,
+    /// Per-note pressure, normalised to `0.0..=1.0`.
+    Pressure,
+    /// Stereo position, `-1.0` (left) to `1.0` (right).
+    Pan,
+    /// Instrument-defined controller.
+    Custom(u16),
+}
+
+impl NoteController {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoteController::Pitch => "pitch",
+            NoteController::Timbre => "timbre",
+            NoteController::Pressure => "pressure",
+            NoteController::Pan => "pan",
+            NoteController::Custom(_) => "custom",
+        }
+    }
+
+    /// Looks up a built-in controller by its [`name`](Self::name).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pitch" => Some(NoteController::Pitch),
+            "timbre" => Some(NoteController::Timbre),
+            "pressure" => Some(NoteController::Pressure),
+            "pan" => Some(NoteController::Pan),
+            _ => None,
+        }
+    }
+}
+
+/// Expression state of one voice. Reset on note-on, updated by every
+/// controller addressed to the voice's note.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VoiceExpression {
+    pub pitch: f32,
+    pub timbre: f32,
+    pub pressure: f32,
+    pub pan: f32,
+}
+
+impl VoiceExpression {
+    /// Stores `value` for `controller`. Returns `false` for custom
+    /// controllers, which instruments handle themselves.
+    pub fn apply(&mut self, controller: NoteController, value: f32) -> bool {
+        match controller {
+            NoteController::Pitch => self.pitch = value,
+            NoteController::Timbre => self.timbre = value.clamp(0.0, 1.0),
+            NoteController::Pressure => self.pressure = value.clamp(0.0, 1.0),
+            NoteController::Pan => self.pan = value.clamp(-1.0, 1.0),
+            NoteController::Custom(_) => return false,
+        }
+        true
+    }
+
+    pub fn get(&self, controller: NoteController) -> Option<f32> {
+        match controller {
+            NoteController::Pitch => Some(self.pitch),
+            NoteController::Timbre => Some(self.timbre),
+            NoteController::Pressure => Some(self.pressure),
+            NoteController::Pan => Some(self.pan),
+            NoteController::Custom(_) => None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -1,7 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::OnceLock;
 
+use petgraph::algo::has_path_connecting;
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use thiserror::Error;
 
 use crate::{plugin::PluginId, AudioBuffer};
 
@@ -9,17 +13,146 @@ use crate::{plugin::PluginId, AudioBuffer};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct NodeHandle(pub(crate) NodeIndex);
 
+impl NodeHandle {
+    /// Addresses one of this node's ports.
+    pub fn pin(self, index: usize) -> PinId {
+        PinId { node: self, index }
+    }
+}
+
+/// A port on a graph node. Whether it names an input or an output depends on
+/// which side of a connection it is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PinId {
+    pub node: NodeHandle,
+    pub index: usize,
+}
+
+impl From<NodeHandle> for PinId {
+    fn from(node: NodeHandle) -> Self {
+        node.pin(0)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum NodeKind {
     Input,
+    MidiInput,
     Plugin { id: PluginId },
     MixerBus { name: String },
+    MidiOutput,
     Master,
 }
 
+/// Input pin of a plugin node that carries its sidechain key. The processor
+/// reads it through [`AudioProcessor::process_with_aux`]; the key's source
+/// still reaches the master on its own route.
+///
+/// [`AudioProcessor::process_with_aux`]: crate::AudioProcessor::process_with_aux
+pub const SIDECHAIN_PIN: usize = 2;
+
+/// Returns the `(inputs, outputs)` port counts for a node kind.
+pub fn port_count(kind: &NodeKind) -> (usize, usize) {
+    match kind {
+        NodeKind::Input | NodeKind::MidiInput => (0, 1),
+        // Audio on pin 0 and MIDI on pin 1, in both directions, plus the
+        // sidechain input.
+        NodeKind::Plugin { .. } => (SIDECHAIN_PIN + 1, 2),
+        NodeKind::MixerBus { .. } => (1, 1),
+        NodeKind::MidiOutput | NodeKind::Master => (1, 0),
+    }
+}
+
+/// What travels along a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignalKind {
+    Audio,
+    Midi,
+}
+
+/// Returns the signal carried by a pin, or `None` if the node has no such
+/// pin.
+pub fn pin_signal(kind: &NodeKind, direction: PinDirection, index: usize) -> Option<SignalKind> {
+    let (inputs, outputs) = port_count(kind);
+    let available = match direction {
+        PinDirection::Input => inputs,
+        PinDirection::Output => outputs,
+    };
+    if index >= available {
+        return None;
+    }
+    Some(match (kind, direction) {
+        (NodeKind::MidiInput | NodeKind::MidiOutput, _) => SignalKind::Midi,
+        (NodeKind::Plugin { .. }, _) if index == 1 => SignalKind::Midi,
+        _ => SignalKind::Audio,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinDirection {
+    Input,
+    Output,
+}
+
+#[derive(Debug, Error)]
+pub enum GraphError {
+    #[error("gain must be non-negative")]
+    NegativeGain,
+    #[error("node is not part of this graph")]
+    UnknownNode,
+    #[error("{direction:?} pin {index} out of range for node with {available} {direction:?} pins")]
+    InvalidPin {
+        direction: PinDirection,
+        index: usize,
+        available: usize,
+    },
+    #[error("connecting {from:?} to {to:?} would create a feedback loop")]
+    WouldCreateCycle { from: NodeHandle, to: NodeHandle },
+    #[error("cannot connect a {from:?} output to a {to:?} input")]
+    SignalMismatch { from: SignalKind, to: SignalKind },
+}
+
+/// Default upper bound on the latency a single plugin may contribute to
+/// delay compensation: one second at 192 kHz.
+pub const DEFAULT_MAX_LATENCY_CAP: usize = 192_000;
+
+#[derive(Debug, Error)]
+pub enum PdcError {
+    #[error("{} node(s) could not be ordered for delay compensation", nodes.len())]
+    Unresolved { nodes: Vec<NodeHandle> },
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Connection {
     pub gain: f32,
+    pub from_pin: usize,
+    pub to_pin: usize,
+    pub signal: SignalKind,
+}
+
+/// A connection as seen from outside the graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub from: PinId,
+    pub to: PinId,
+    pub gain: f32,
+    pub signal: SignalKind,
+}
+
+fn edges_directed(
+    graph: &StableDiGraph<NodeKind, Connection>,
+    node: NodeHandle,
+    direction: Direction,
+) -> impl Iterator<Item = Edge> + '_ {
+    graph.edges_directed(node.0, direction).map(|edge| {
+        let connection = edge.weight();
+        Edge {
+            from: NodeHandle(edge.source()).pin(connection.from_pin),
+            to: NodeHandle(edge.target()).pin(connection.to_pin),
+            gain: connection.gain,
+            signal: connection.signal,
+        }
+    })
 }
 
 /// A fully prepared processing graph ready to be executed by the engine.
@@ -29,6 +162,11 @@ pub struct GraphHandle {
     pub(crate) master: NodeIndex,
     pub(crate) plugin_nodes: Vec<NodeIndex>,
     pub(crate) node_lookup: HashMap<NodeIndex, usize>,
+    pub(crate) max_latency_cap: usize,
+    /// Audio latency accumulated upstream of each node by the last
+    /// [`recompute_pdc`](Self::recompute_pdc).
+    pub(crate) input_latency: HashMap<NodeIndex, usize>,
+    pub(crate) unresolved: Vec<NodeHandle>,
 }
 
 impl GraphHandle {
@@ -46,13 +184,167 @@ impl GraphHandle {
             .collect()
     }
 
+    /// Connections leaving `node`.
+    pub fn edges_from(&self, node: NodeHandle) -> impl Iterator<Item = Edge> + '_ {
+        edges_directed(&self.graph, node, Direction::Outgoing)
+    }
+
+    /// Connections arriving at `node`.
+    pub fn edges_into(&self, node: NodeHandle) -> impl Iterator<Item = Edge> + '_ {
+        edges_directed(&self.graph, node, Direction::Incoming)
+    }
+
+    pub fn max_latency_cap(&self) -> usize {
+        self.max_latency_cap
+    }
+
+    /// Clamps a reported plugin latency to the graph's cap, so a plugin
+    /// claiming an absurd latency cannot force a huge compensation delay.
+    pub fn clamp_latency(&self, latency: usize) -> usize {
+        latency.min(self.max_latency_cap)
+    }
+
+    /// Propagates plugin latencies along audio edges. `latencies` is indexed
+    /// like [`plugin_ids`](Self::plugin_ids); each entry is clamped to
+    /// [`max_latency_cap`](Self::max_latency_cap). Nodes that cannot be
+    /// ordered because they sit in or behind a feedback loop are recorded in
+    /// [`unresolved_nodes`](Self::unresolved_nodes) instead.
+    pub fn recompute_pdc(&mut self, latencies: &[usize]) {
+        let audio_edges = |node: NodeIndex, direction: Direction| {
+            self.graph
+                .edges_directed(node, direction)
+                .filter(|edge| edge.weight().signal == SignalKind::Audio)
+        };
+
+        let mut pending: HashMap<NodeIndex, usize> = self
+            .graph
+            .node_indices()
+            .map(|node| (node, audio_edges(node, Direction::Incoming).count()))
+            .collect();
+        let mut queue: VecDeque<NodeIndex> = pending
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(node, _)| *node)
+            .collect();
+        let mut input_latency: HashMap<NodeIndex, usize> = HashMap::new();
+
+        while let Some(node) = queue.pop_front() {
+            pending.remove(&node);
+            let arrival = input_latency.get(&node).copied().unwrap_or(0);
+            let own = self
+                .node_lookup
+                .get(&node)
+                .and_then(|index| latencies.get(*index))
+                .map_or(0, |latency| self.clamp_latency(*latency));
+            input_latency.entry(node).or_insert(arrival);
+            for edge in audio_edges(node, Direction::Outgoing) {
+                let target = edge.target();
+                let slot = input_latency.entry(target).or_insert(0);
+                *slot = (*slot).max(arrival.saturating_add(own));
+                if let Some(count) = pending.get_mut(&target) {
+                    *count -= 1;
+                    if *count == 0 {
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+
+        for node in pending.keys() {
+            input_latency.remove(node);
+        }
+        let mut unresolved: Vec<NodeHandle> = pending.into_keys().map(NodeHandle).collect();
+        unresolved.sort_unstable_by_key(|node| node.0);
+        self.input_latency = input_latency;
+        self.unresolved = unresolved;
+    }
+
+    /// Like [`recompute_pdc`](Self::recompute_pdc), but fails with the nodes
+    /// left unprocessed if any could not be ordered.
+    pub fn recompute_pdc_checked(&mut self, latencies: &[usize]) -> Result<(), PdcError> {
+        self.recompute_pdc(latencies);
+        if self.unresolved.is_empty() {
+            Ok(())
+        } else {
+            Err(PdcError::Unresolved {
+                nodes: self.unresolved.clone(),
+            })
+        }
+    }
+
+    /// Latency of the audio arriving at `node`, or `None` if the node was not
+    /// reached by the last PDC pass.
+    pub fn input_latency(&self, node: NodeHandle) -> Option<usize> {
+        self.input_latency.get(&node.0).copied()
+    }
+
+    /// Nodes the last PDC pass could not order.
+    pub fn unresolved_nodes(&self) -> &[NodeHandle] {
+        &self.unresolved
+    }
+
     pub(crate) fn plugin_nodes(&self) -> &[NodeIndex] {
         &self.plugin_nodes
     }
 
+    /// Upstream plugins feeding audio into each plugin node, as indices into
+    /// [`plugin_ids`](Self::plugin_ids). MIDI edges are left out: events are
+    /// timestamped rather than delayed, so they take no part in summing or
+    /// delay compensation.
+    pub(crate) fn plugin_inputs(&self) -> Vec<Vec<usize>> {
+        self.plugin_sources(|connection| {
+            connection.signal == SignalKind::Audio && connection.to_pin != SIDECHAIN_PIN
+        })
+    }
+
+    /// Upstream plugins whose MIDI output feeds each plugin node, indexed
+    /// like [`plugin_inputs`](Self::plugin_inputs).
+    pub(crate) fn plugin_midi_inputs(&self) -> Vec<Vec<usize>> {
+        self.plugin_sources(|connection| connection.signal == SignalKind::Midi)
+    }
+
+    /// Upstream plugins keying each plugin node's [`SIDECHAIN_PIN`], indexed
+    /// like [`plugin_inputs`](Self::plugin_inputs).
+    pub(crate) fn plugin_sidechain_inputs(&self) -> Vec<Vec<usize>> {
+        self.plugin_sources(|connection| connection.to_pin == SIDECHAIN_PIN)
+    }
+
+    fn plugin_sources(&self, keep: impl Fn(&Connection) -> bool) -> Vec<Vec<usize>> {
+        self.plugin_nodes
+            .iter()
+            .map(|node| {
+                let mut sources: Vec<usize> = self
+                    .graph
+                    .edges_directed(*node, Direction::Incoming)
+                    .filter(|edge| keep(edge.weight()))
+                    .filter_map(|edge| self.node_lookup.get(&edge.source()).copied())
+                    .collect();
+                sources.sort_unstable();
+                sources.dedup();
+                sources
+            })
+            .collect()
+    }
+
+    /// Gain of `node`'s route to the master. Plugins without an explicit
+    /// route are mixed at unity, unless they feed another plugin's audio
+    /// input: an insert chain reaches the master through its last plugin,
+    /// so its source must not be heard a second time. A sidechain key is
+    /// only listened to, so it does not count.
     pub(crate) fn gain_for(&self, node: NodeIndex) -> f32 {
         if let Some(edge) = self.graph.find_edge(node, self.master) {
-            self.graph[edge].gain
+            return self.graph[edge].gain;
+        }
+        let feeds_plugin = self
+            .graph
+            .edges_directed(node, Direction::Outgoing)
+            .any(|edge| {
+                edge.weight().signal == SignalKind::Audio
+                    && edge.weight().to_pin != SIDECHAIN_PIN
+                    && self.node_lookup.contains_key(&edge.target())
+            });
+        if feeds_plugin {
+            0.0
         } else {
             1.0
         }
@@ -65,6 +357,7 @@ pub struct GraphBuilder {
     graph: StableDiGraph<NodeKind, Connection>,
     master: NodeIndex,
     plugin_nodes: Vec<NodeIndex>,
+    max_latency_cap: usize,
 }
 
 impl Default for GraphBuilder {
@@ -75,6 +368,7 @@ impl Default for GraphBuilder {
             graph,
             master,
             plugin_nodes: Vec::new(),
+            max_latency_cap: DEFAULT_MAX_LATENCY_CAP,
         }
     }
 }
@@ -84,10 +378,24 @@ impl GraphBuilder {
         Self::default()
     }
 
+    /// Sets the most latency, in samples, any one plugin may contribute to
+    /// delay compensation. Defaults to [`DEFAULT_MAX_LATENCY_CAP`].
+    pub fn set_max_latency_cap(&mut self, samples: usize) {
+        self.max_latency_cap = samples;
+    }
+
     pub fn add_input(&mut self) -> NodeHandle {
         NodeHandle(self.graph.add_node(NodeKind::Input))
     }
 
+    pub fn add_midi_input(&mut self) -> NodeHandle {
+        NodeHandle(self.graph.add_node(NodeKind::MidiInput))
+    }
+
+    pub fn add_midi_output(&mut self) -> NodeHandle {
+        NodeHandle(self.graph.add_node(NodeKind::MidiOutput))
+    }
+
     pub fn add_mixer_bus(&mut self, name: impl Into<String>) -> NodeHandle {
         NodeHandle(
             self.graph
@@ -101,20 +409,109 @@ impl GraphBuilder {
         NodeHandle(node)
     }
 
-    pub fn connect(&mut self, from: NodeHandle, to: NodeHandle, gain: f32) -> anyhow::Result<()> {
+    /// Connects an output pin of `from` to an input pin of `to`. Passing a
+    /// [`NodeHandle`] addresses its first pin. Both pins must carry the same
+    /// [`SignalKind`], and edges that would close a feedback loop, including
+    /// self-connections, are rejected.
+    pub fn connect(
+        &mut self,
+        from: impl Into<PinId>,
+        to: impl Into<PinId>,
+        gain: f32,
+    ) -> Result<(), GraphError> {
+        let (from, to) = (from.into(), to.into());
         if gain < 0.0 {
-            anyhow::bail!("Gain must be non-negative");
+            return Err(GraphError::NegativeGain);
+        }
+        let source = self.check_pin(from, PinDirection::Output)?;
+        let target = self.check_pin(to, PinDirection::Input)?;
+        if source != target {
+            return Err(GraphError::SignalMismatch {
+                from: source,
+                to: target,
+            });
+        }
+        if has_path_connecting(&self.graph, to.node.0, from.node.0, None) {
+            return Err(GraphError::WouldCreateCycle {
+                from: from.node,
+                to: to.node,
+            });
         }
-        self.graph.add_edge(from.0, to.0, Connection { gain });
+        self.graph.add_edge(
+            from.node.0,
+            to.node.0,
+            Connection {
+                gain,
+                from_pin: from.index,
+                to_pin: to.index,
+                signal: source,
+            },
+        );
         Ok(())
     }
 
+    /// Removes the connection from the `from` output pin to the `to` input
+    /// pin. Returns `false` if no such connection exists. Delay compensation
+    /// is derived from the remaining edges when the graph is next scheduled.
+    pub fn disconnect(&mut self, from: impl Into<PinId>, to: impl Into<PinId>) -> bool {
+        let (from, to) = (from.into(), to.into());
+        let edge = self
+            .graph
+            .edges_directed(from.node.0, Direction::Outgoing)
+            .find(|edge| {
+                edge.target() == to.node.0
+                    && edge.weight().from_pin == from.index
+                    && edge.weight().to_pin == to.index
+            })
+            .map(|edge| edge.id());
+        match edge {
+            Some(edge) => self.graph.remove_edge(edge).is_some(),
+            None => false,
+        }
+    }
+
+    /// Connections leaving `node`.
+    pub fn edges_from(&self, node: NodeHandle) -> impl Iterator<Item = Edge> + '_ {
+        edges_directed(&self.graph, node, Direction::Outgoing)
+    }
+
+    /// Connections arriving at `node`.
+    pub fn edges_into(&self, node: NodeHandle) -> impl Iterator<Item = Edge> + '_ {
+        edges_directed(&self.graph, node, Direction::Incoming)
+    }
+
+    fn check_pin(&self, pin: PinId, direction: PinDirection) -> Result<SignalKind, GraphError> {
+        let kind = self
+            .graph
+            .node_weight(pin.node.0)
+            .ok_or(GraphError::UnknownNode)?;
+        pin_signal(kind, direction, pin.index).ok_or_else(|| {
+            let (inputs, outputs) = port_count(kind);
+            GraphError::InvalidPin {
+                direction,
+                index: pin.index,
+                available: match direction {
+                    PinDirection::Input => inputs,
+                    PinDirection::Output => outputs,
+                },
+            }
+        })
+    }
+
     pub fn connect_to_mixer(&mut self, node: NodeHandle, gain: f32) -> anyhow::Result<()> {
         if gain < 0.0 {
             anyhow::bail!("Gain must be non-negative");
         }
-        self.graph
-            .add_edge(node.0, self.master, Connection { gain });
+        self.graph.add_edge(
+            node.0,
+            self.master,
+            Connection {
+                gain,
+                from_pin: 0,
+                to_pin: 0,
+                signal: SignalKind::Audio,
+            },
+        );
         Ok(())
     }
 
@@ -128,6 +525,9 @@ impl GraphBuilder {
             master: self.master,
             plugin_nodes: self.plugin_nodes,
             node_lookup,
+            max_latency_cap: self.max_latency_cap,
+            input_latency: HashMap::new(),
+            unresolved: Vec::new(),
         }
     }
 }
@@ -276,3 +676,40 @@ unsafe fn mix_channel_avx512(target: &mut [f32], source: &[f32], gain: f32) {
         mix_channel_scalar(target_tail, source_tail, gain);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feedback_loops_are_reported_as_unresolved() {
+        let mut builder = GraphBuilder::new();
+        let input = builder.add_input();
+        let a = builder.add_node(PluginId(1));
+        let b = builder.add_node(PluginId(2));
+        builder.connect(input, a, 1.0).unwrap();
+        builder.connect(a, b, 1.0).unwrap();
+        builder.connect_to_mixer(b, 1.0).unwrap();
+        // `connect` refuses loops, so close one behind its back.
+        builder.graph.add_edge(
+            b.0,
+            a.0,
+            Connection {
+                gain: 1.0,
+                from_pin: 0,
+                to_pin: 0,
+                signal: SignalKind::Audio,
+            },
+        );
+
+        let mut graph = builder.build();
+        let Err(PdcError::Unresolved { nodes }) = graph.recompute_pdc_checked(&[64, 64]) else {
+            panic!("loop went unnoticed");
+        };
+        assert_eq!(nodes.len(), 3, "a, b and the master behind them");
+        assert!(nodes.contains(&a) && nodes.contains(&b));
+        assert_eq!(graph.unresolved_nodes(), nodes.as_slice());
+        assert_eq!(graph.input_latency(input), Some(0));
+        assert_eq!(graph.input_latency(a), None);
+    }
+}
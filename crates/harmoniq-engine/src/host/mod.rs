@@ -2,43 +2,55 @@
 #![cfg(feature = "clap_host")]
 
 pub mod clap_hosting {
+    use std::collections::HashMap;
     use std::path::Path;
 
     use anyhow::{anyhow, Result};
     use clap_host::{
-        discover::{ClapLibrary, PluginDiscovery},
-        ffi::{clap_host, clap_plugin_factory_t},
-        instance::{ActivationError, AudioConfig, ClapInstance},
-        ClapPluginDescriptor,
-    };
-    use clap_sys::CLAP_VERSION_LATEST;
-    use core::ffi::c_char;
-
-    const HOST_NAME: &[u8] = b"Harmoniq Studio\0";
-    const HOST_VENDOR: &[u8] = b"Harmoniq Labs\0";
-    const HOST_URL: &[u8] = b"https://harmoniq.audio\0";
-    const HOST_VERSION: &[u8] = b"0.1.0\0";
-
-    static HOST_INFO: clap_host = clap_host {
-        clap_version: CLAP_VERSION_LATEST,
-        host_data: core::ptr::null_mut(),
-        name: HOST_NAME.as_ptr() as *const c_char,
-        vendor: HOST_VENDOR.as_ptr() as *const c_char,
-        url: HOST_URL.as_ptr() as *const c_char,
-        version: HOST_VERSION.as_ptr() as *const c_char,
-        get_extension: None,
-        request_restart: None,
-        request_process: None,
-        request_callback: None,
+        discard_output_events,
+        ffi::{
+            clap_audio_buffer, clap_plugin_factory_t, clap_process, clap_process_status,
+            CLAP_PROCESS_ERROR,
+        },
+        ActivationError, AudioConfig, ClapInstance, ClapLibrary, ClapPluginDescriptor,
+        ParamEventList, ParamInfo, PluginDiscovery, HARMONIQ_HOST,
     };
 
+    use crate::automation::ParameterSpec;
+    use crate::mixer::MixerInsertState;
+    use crate::{AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, PluginDescriptor};
+
+    /// Parameter changes a single block can carry.
+    const PARAM_EVENT_CAPACITY: usize = 1_024;
+
+    /// Channel pointers handed to the plug-in, rebuilt for every block.
+    struct ChannelPointers(Vec<*mut f32>);
+
+    // The pointers only ever refer to the buffer of the block in flight.
+    unsafe impl Send for ChannelPointers {}
+    unsafe impl Sync for ChannelPointers {}
+
     /// Represents a CLAP plug-in slot managed by the host façade.
+    ///
+    /// Parameters are addressed by engine index, the position of their CLAP
+    /// id in [`parameter_ids`](Self::parameter_ids). Indices are only ever
+    /// appended, so saving the ids with the project and restoring them with
+    /// [`with_parameter_ids`](Self::with_parameter_ids) keeps automation
+    /// pointed at the same parameters across sessions and plug-in updates.
     pub struct ClapSlot {
         pub name: String,
         pub descriptor: ClapPluginDescriptor,
-        pub library: ClapLibrary,
+        // Declared before `library` so the plug-in is destroyed before its
+        // code is unloaded.
         pub instance: ClapInstance,
+        library: Option<ClapLibrary>,
         pub bypass: bool,
+        parameters: HashMap<u32, ParamInfo>,
+        parameter_ids: Vec<u32>,
+        events: ParamEventList,
+        channels: ChannelPointers,
+        processing: bool,
+        steady_time: i64,
     }
 
     impl ClapSlot {
@@ -52,30 +64,55 @@ pub mod clap_hosting {
             unsafe {
                 let library = ClapLibrary::load(path)?;
                 let factory = library.factory()?;
-                let descriptor = PluginDiscovery::new(factory)
-                    .list()
-                    .into_iter()
-                    .find(|desc| desc.id == clap_id)
-                    .ok_or_else(|| anyhow!("CLAP plugin id not found: {clap_id}"))?;
-
-                let mut instance = ClapSlot::create_instance(factory, &descriptor)?;
-                ClapSlot::activate_instance(&mut instance, sample_rate, block_size)?;
-
-                Ok(Self {
-                    name: descriptor.name.clone(),
-                    descriptor,
-                    library,
-                    instance,
-                    bypass: false,
-                })
+                let mut slot = ClapSlot::from_factory(factory, clap_id, sample_rate, block_size)?;
+                slot.library = Some(library);
+                Ok(slot)
             }
         }
 
+        /// Instantiates `clap_id` from a factory that is already in memory,
+        /// such as one linked into the host binary.
+        ///
+        /// # Safety
+        /// `factory` must be a valid CLAP plug-in factory that outlives the
+        /// slot.
+        pub unsafe fn from_factory(
+            factory: &clap_plugin_factory_t,
+            clap_id: &str,
+            sample_rate: f64,
+            block_size: u32,
+        ) -> Result<Self> {
+            let descriptor = PluginDiscovery::new(factory)
+                .list()
+                .into_iter()
+                .find(|desc| desc.id == clap_id)
+                .ok_or_else(|| anyhow!("CLAP plugin id not found: {clap_id}"))?;
+
+            let mut instance = ClapSlot::create_instance(factory, &descriptor)?;
+            ClapSlot::activate_instance(&mut instance, sample_rate, block_size)?;
+
+            let mut slot = Self {
+                name: descriptor.name.clone(),
+                descriptor,
+                instance,
+                library: None,
+                bypass: false,
+                parameters: HashMap::new(),
+                parameter_ids: Vec::new(),
+                events: ParamEventList::with_capacity(PARAM_EVENT_CAPACITY),
+                channels: ChannelPointers(Vec::new()),
+                processing: false,
+                steady_time: 0,
+            };
+            slot.scan_parameters();
+            Ok(slot)
+        }
+
         fn create_instance(
             factory: &clap_plugin_factory_t,
             descriptor: &ClapPluginDescriptor,
         ) -> Result<ClapInstance, ActivationError> {
-            unsafe { ClapInstance::create(factory, descriptor, &HOST_INFO as *const clap_host) }
+            unsafe { ClapInstance::create(factory, descriptor, HARMONIQ_HOST.as_ptr()) }
         }
 
         fn activate_instance(
@@ -92,6 +129,12 @@ pub mod clap_hosting {
             }
         }
 
+        /// The library the plug-in was loaded from, `None` for a plug-in
+        /// created from an in-memory factory.
+        pub fn library(&self) -> Option<&ClapLibrary> {
+            self.library.as_ref()
+        }
+
         /// Sets the bypass state of the plug-in.
         pub fn set_bypass(&mut self, bypass: bool) {
             self.bypass = bypass;
@@ -101,5 +144,214 @@ pub mod clap_hosting {
         pub fn open_editor(&mut self) {
             log::debug!("CLAP plug-in '{}' requested editor open", self.name);
         }
+
+        /// Restores a parameter order saved with the project. Ids the
+        /// plug-in no longer reports keep their index reserved; parameters
+        /// it has gained since are appended.
+        pub fn with_parameter_ids(mut self, ids: Vec<u32>) -> Self {
+            self.parameter_ids.clear();
+            for id in ids {
+                if !self.parameter_ids.contains(&id) {
+                    self.parameter_ids.push(id);
+                }
+            }
+            self.scan_parameters();
+            self
+        }
+
+        /// CLAP id behind each engine parameter index, for saving with the
+        /// project.
+        pub fn parameter_ids(&self) -> &[u32] {
+            &self.parameter_ids
+        }
+
+        /// The slot's id, bypass state and parameter order as saved in the
+        /// project's insert list. The plug-in state is saved separately,
+        /// through [`AudioProcessor::save_state`] and restored by
+        /// [`HarmoniqEngine::restore_insert_states`](crate::HarmoniqEngine::restore_insert_states).
+        pub fn insert_state(&self) -> MixerInsertState {
+            MixerInsertState {
+                id: Some(self.descriptor.id.clone()),
+                bypassed: self.bypass,
+                parameter_ids: self.parameter_ids.clone(),
+                ..MixerInsertState::default()
+            }
+        }
+
+        /// Restores the bypass state and parameter order of an insert loaded
+        /// with the project, before the slot is registered with the engine.
+        pub fn with_insert_state(self, insert: &MixerInsertState) -> Self {
+            let mut slot = self.with_parameter_ids(insert.parameter_ids.clone());
+            slot.set_bypass(insert.bypassed);
+            slot
+        }
+
+        /// Parameters the plug-in currently reports, with their engine index.
+        pub fn parameters(&self) -> impl Iterator<Item = (usize, &ParamInfo)> + '_ {
+            self.parameter_ids
+                .iter()
+                .enumerate()
+                .filter_map(|(index, id)| Some((index, self.parameters.get(id)?)))
+        }
+
+        pub fn parameter_index(&self, id: u32) -> Option<usize> {
+            self.parameter_ids.iter().position(|known| *known == id)
+        }
+
+        /// Automation specs for every parameter, ready to register with the
+        /// engine.
+        pub fn parameter_specs(&self) -> Vec<ParameterSpec> {
+            self.parameters()
+                .map(|(index, info)| {
+                    ParameterSpec::new(
+                        index,
+                        info.name.clone(),
+                        info.min as f32,
+                        info.max as f32,
+                        info.default as f32,
+                    )
+                })
+                .collect()
+        }
+
+        /// Queues a change of parameter `index` to `value`, which the plug-in
+        /// receives as a `clap_event_param_value` at `sample_offset` in the
+        /// next block. Returns `false` for an unknown index or a full queue.
+        pub fn set_parameter(&mut self, index: usize, value: f64, sample_offset: u32) -> bool {
+            let Some(info) = self
+                .parameter_ids
+                .get(index)
+                .and_then(|id| self.parameters.get(id))
+            else {
+                return false;
+            };
+            let value = value.clamp(info.min, info.max);
+            self.events.push(sample_offset, info.id, value)
+        }
+
+        fn scan_parameters(&mut self) {
+            let parameters = unsafe { self.instance.params() };
+            for info in &parameters {
+                if !self.parameter_ids.contains(&info.id) {
+                    self.parameter_ids.push(info.id);
+                }
+            }
+            self.parameters = parameters.into_iter().map(|info| (info.id, info)).collect();
+        }
+
+        /// Hands queued parameter changes to a plug-in that is not processing.
+        fn flush_events(&mut self) {
+            if self.events.is_empty() {
+                return;
+            }
+            let input = self.events.as_input_events();
+            let output = discard_output_events();
+            unsafe { self.instance.flush_params(&input, &output) };
+            self.events.clear();
+        }
+
+        fn stop_processing(&mut self) {
+            if self.processing {
+                unsafe { self.instance.stop_processing() };
+                self.processing = false;
+            }
+        }
+    }
+
+    impl AudioProcessor for ClapSlot {
+        fn descriptor(&self) -> PluginDescriptor {
+            let vendor = if self.descriptor.vendor.is_empty() {
+                "Unknown"
+            } else {
+                self.descriptor.vendor.as_str()
+            };
+            PluginDescriptor::new(&self.descriptor.id, &self.name, vendor)
+                .with_description("CLAP plug-in hosted by Harmoniq Studio")
+        }
+
+        fn prepare(&mut self, config: &BufferConfig) -> anyhow::Result<()> {
+            self.stop_processing();
+            self.flush_events();
+            let block_size = config.block_size.max(1) as u32;
+            unsafe { self.instance.deactivate() };
+            ClapSlot::activate_instance(&mut self.instance, config.sample_rate as f64, block_size)?;
+            let channels = config.layout.channels() as usize;
+            self.channels.0.reserve(channels);
+            self.steady_time = 0;
+            Ok(())
+        }
+
+        fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+            if self.bypass || buffer.is_empty() {
+                self.flush_events();
+                return Ok(());
+            }
+            if !self.processing {
+                self.processing = unsafe { self.instance.start_processing() };
+            }
+
+            let frames = buffer.len() as u32;
+            self.channels.0.clear();
+            self.channels
+                .0
+                .extend(buffer.channels_mut().map(|channel| channel.as_mut_ptr()));
+            // The plug-in processes in place: inputs and outputs share buffers.
+            let mut audio = clap_audio_buffer {
+                data32: self.channels.0.as_mut_ptr(),
+                data64: core::ptr::null_mut(),
+                channel_count: self.channels.0.len() as u32,
+                latency: 0,
+                constant_mask: 0,
+            };
+            let audio: *mut clap_audio_buffer = &mut audio;
+            let input = self.events.as_input_events();
+            let output = discard_output_events();
+            let process = clap_process {
+                steady_time: self.steady_time,
+                frames_count: frames,
+                transport: core::ptr::null(),
+                audio_inputs: audio,
+                audio_outputs: audio,
+                audio_inputs_count: 1,
+                audio_outputs_count: 1,
+                in_events: &input,
+                out_events: &output,
+            };
+            let status = unsafe { self.instance.process(&process) };
+            self.events.clear();
+            self.channels.0.clear();
+            self.steady_time += frames as i64;
+            if status == CLAP_PROCESS_ERROR.0 as clap_process_status {
+                anyhow::bail!("CLAP plug-in '{}' failed to process", self.name);
+            }
+            Ok(())
+        }
+
+        fn supports_layout(&self, _layout: ChannelLayout) -> bool {
+            true
+        }
+
+        fn handle_automation_event(
+            &mut self,
+            parameter: usize,
+            value: f32,
+            sample_offset: usize,
+        ) -> anyhow::Result<()> {
+            if !self.set_parameter(parameter, value as f64, sample_offset as u32) {
+                log::debug!(
+                    "CLAP plug-in '{}' dropped automation for parameter {parameter}",
+                    self.name
+                );
+            }
+            Ok(())
+        }
+
+        fn save_state(&self) -> anyhow::Result<Vec<u8>> {
+            Ok(unsafe { self.instance.save_state() }?)
+        }
+
+        fn load_state(&mut self, data: &[u8]) -> anyhow::Result<()> {
+            Ok(unsafe { self.instance.load_state(data) }?)
+        }
     }
 }
@@ -0,0 +1,60 @@
+//! Deterministic, non-destructive humanisation applied while scheduling notes.
+//!
+//! Offsets are derived from a hash of the seed, clip and note identifiers so a
+//! given seed always produces the same feel regardless of block size or how
+//! often the arrangement is rendered.
+
+use serde::{Deserialize, Serialize};
+
+/// Seeded timing and velocity variation applied by the playlist scheduler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HumanizeSettings {
+    pub seed: u64,
+    /// Maximum timing deviation in either direction, in playlist ticks.
+    pub timing_ticks: u32,
+    /// Maximum velocity deviation in either direction.
+    pub velocity: u8,
+}
+
+impl HumanizeSettings {
+    pub fn new(seed: u64, timing_ticks: u32, velocity: u8) -> Self {
+        Self {
+            seed,
+            timing_ticks,
+            velocity,
+        }
+    }
+
+    /// Timing offset in ticks and velocity delta for a note.
+    pub fn offsets(&self, clip_id: u64, note_id: u64) -> (i64, i16) {
+        let base =
+            self.seed ^ clip_id.rotate_left(32) ^ note_id.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let timing = spread(splitmix64(base), self.timing_ticks as i64);
+        let velocity = spread(
+            splitmix64(base ^ 0xA5A5_A5A5_A5A5_A5A5),
+            self.velocity as i64,
+        );
+        (timing, velocity as i16)
+    }
+
+    /// Applies the velocity delta, keeping note-ons audible.
+    pub fn velocity(&self, velocity: u8, delta: i16) -> u8 {
+        (velocity as i16 + delta).clamp(1, 127) as u8
+    }
+}
+
+fn splitmix64(mut value: u64) -> u64 {
+    value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
+
+/// Maps a hash onto `-range..=range`.
+fn spread(hash: u64, range: i64) -> i64 {
+    if range <= 0 {
+        return 0;
+    }
+    let span = (range * 2 + 1) as u64;
+    (hash % span) as i64 - range
+}
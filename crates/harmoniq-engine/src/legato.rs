@@ -0,0 +1,208 @@
+//! Track-level mono/legato note handling.
+//!
+//! [`MonoLegato`] sits between the scheduler and a track's instrument and
+//! reduces overlapping notes to a single sounding note. When the sounding
+//! note changes while a key is still held, the new note-on is sent before the
+//! previous note-off so mono instruments treat it as a legato transition
+//! rather than a retrigger. With glide enabled the transition is preceded by
+//! the standard portamento controllers, giving every mono-capable instrument
+//! the same glide behaviour.
+
+use serde::{Deserialize, Serialize};
+
+use crate::plugin::MidiEvent;
+
+/// Portamento time controller (CC 5).
+pub const PORTAMENTO_TIME_CC: u8 = 5;
+/// Portamento control controller (CC 84); its value is the note the glide
+/// starts from.
+pub const PORTAMENTO_CONTROL_CC: u8 = 84;
+/// Milliseconds represented by one step of [`PORTAMENTO_TIME_CC`].
+pub const PORTAMENTO_TIME_STEP_MS: f32 = 10.0;
+
+/// Which held note sounds when several keys are down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NotePriority {
+    /// The most recently pressed key.
+    #[default]
+    Last,
+    /// The lowest held key.
+    Low,
+    /// The highest held key.
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MonoLegatoSettings {
+    pub priority: NotePriority,
+    /// Glide time for legato transitions. Zero disables glide.
+    pub glide_ms: f32,
+}
+
+impl Default for MonoLegatoSettings {
+    fn default() -> Self {
+        Self {
+            priority: NotePriority::Last,
+            glide_ms: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HeldNote {
+    channel: u8,
+    note: u8,
+    velocity: u8,
+}
+
+/// MIDI processor that turns overlapping notes into mono legato transitions.
+#[derive(Debug, Clone)]
+pub struct MonoLegato {
+    settings: MonoLegatoSettings,
+    /// Held keys in the order they were pressed.
+    held: Vec<HeldNote>,
+    sounding: Option<HeldNote>,
+}
+
+impl MonoLegato {
+    pub fn new(settings: MonoLegatoSettings) -> Self {
+        Self {
+            settings,
+            held: Vec::with_capacity(128),
+            sounding: None,
+        }
+    }
+
+    pub fn settings(&self) -> MonoLegatoSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: MonoLegatoSettings) {
+        self.settings = settings;
+    }
+
+    /// Note currently sent to the instrument, if any.
+    pub fn sounding_note(&self) -> Option<u8> {
+        self.sounding.map(|held| held.note)
+    }
+
+    /// Processes a block of events, appending the mono stream to `output`.
+    pub fn process(&mut self, events: &[MidiEvent], output: &mut Vec<MidiEvent>) {
+        for event in events {
+            self.process_event(event, output);
+        }
+    }
+
+    pub fn process_event(&mut self, event: &MidiEvent, output: &mut Vec<MidiEvent>) {
+        match *event {
+            MidiEvent::NoteOn {
+                channel,
+                note,
+                velocity,
+                sample_offset,
+                ..
+            } if velocity > 0 => {
+                self.held.retain(|held| held.note != note);
+                self.held.push(HeldNote {
+                    channel,
+                    note,
+                    velocity,
+                });
+                self.update(sample_offset, output);
+            }
+            MidiEvent::NoteOn {
+                note,
+                sample_offset,
+                ..
+            }
+            | MidiEvent::NoteOff {
+                note,
+                sample_offset,
+                ..
+            } => {
+                self.held.retain(|held| held.note != note);
+                self.update(sample_offset, output);
+            }
+            _ => output.push(event.clone()),
+        }
+    }
+
+    /// Releases the sounding note and forgets every held key.
+    pub fn release_all(&mut self, sample_offset: u32, output: &mut Vec<MidiEvent>) {
+        self.held.clear();
+        self.update(sample_offset, output);
+    }
+
+    fn select(&self) -> Option<HeldNote> {
+        match self.settings.priority {
+            NotePriority::Last => self.held.last().copied(),
+            NotePriority::Low => self.held.iter().copied().min_by_key(|held| held.note),
+            NotePriority::High => self.held.iter().copied().max_by_key(|held| held.note),
+        }
+    }
+
+    fn update(&mut self, sample_offset: u32, output: &mut Vec<MidiEvent>) {
+        let next = self.select();
+        match (self.sounding, next) {
+            (None, None) => {}
+            (Some(current), Some(next)) if current.note == next.note => {}
+            (None, Some(next)) => output.push(note_on(next, sample_offset)),
+            (Some(current), None) => output.push(note_off(current, sample_offset)),
+            (Some(current), Some(next)) => {
+                if self.settings.glide_ms > 0.0 {
+                    let steps = (self.settings.glide_ms / PORTAMENTO_TIME_STEP_MS).round();
+                    output.push(control(
+                        next.channel,
+                        PORTAMENTO_TIME_CC,
+                        steps.clamp(0.0, 127.0) as u8,
+                        sample_offset,
+                    ));
+                    output.push(control(
+                        next.channel,
+                        PORTAMENTO_CONTROL_CC,
+                        current.note,
+                        sample_offset,
+                    ));
+                }
+                output.push(note_on(next, sample_offset));
+                output.push(note_off(current, sample_offset));
+            }
+        }
+        self.sounding = next;
+    }
+}
+
+impl Default for MonoLegato {
+    fn default() -> Self {
+        Self::new(MonoLegatoSettings::default())
+    }
+}
+
+fn note_on(held: HeldNote, sample_offset: u32) -> MidiEvent {
+    MidiEvent::NoteOn {
+        channel: held.channel,
+        note: held.note,
+        velocity: held.velocity,
+        sample_offset,
+        timestamp: None,
+    }
+}
+
+fn note_off(held: HeldNote, sample_offset: u32) -> MidiEvent {
+    MidiEvent::NoteOff {
+        channel: held.channel,
+        note: held.note,
+        sample_offset,
+        timestamp: None,
+    }
+}
+
+fn control(channel: u8, control: u8, value: u8, sample_offset: u32) -> MidiEvent {
+    MidiEvent::ControlChange {
+        channel,
+        control,
+        value,
+        sample_offset,
+        timestamp: None,
+    }
+}
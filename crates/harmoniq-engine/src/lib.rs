@@ -16,13 +16,16 @@ pub mod cpu_pinning;
 pub(crate) mod delay;
 pub mod dsp;
 pub mod engine;
+pub mod expression;
 pub mod graph;
 #[cfg(feature = "clap_host")]
 pub mod host;
+pub mod humanize;
 pub mod ipc;
+pub mod legato;
 pub mod media;
 pub mod mixer;
-mod mixer_rt;
+pub mod mixer_rt;
 pub mod nodes;
 #[cfg(feature = "mixer_api")]
 pub mod panlaw;
@@ -33,7 +36,9 @@ pub mod rt;
 pub mod rt_bridge;
 pub mod sched;
 mod scratch;
+pub mod session;
 pub mod sound_server;
+pub mod step_pattern;
 pub mod time;
 pub mod timeline;
 mod tone;
@@ -44,14 +49,14 @@ pub mod realtime;
 
 pub use api::Engine as RtEngine;
 pub use automation::{
-    AutomationCommand, AutomationCurve, AutomationEvent, AutomationWriteMode, CurveShape,
-    ParameterSpec,
+    AutomationCommand, AutomationCurve, AutomationEvent, AutomationSlot, AutomationTarget,
+    AutomationWriteMode, CurveShape, LaneTarget, ParameterSpec, TargetParseError,
 };
 pub use buffer::{AudioBuffer, BufferConfig, ChannelLayout};
 pub use clips::{AudioClip, ClipError, CrossfadeSpec, FadeCurve, FadeSpec, StretchQuality};
 pub use core::commands::{
-    AddClipCommand, CommandBus, CreateTrackCommand, MixerEndpoint, MoveClipCommand,
-    SetMixerTargetCommand, WriteAutomationPointCommand,
+    AddClipCommand, CommandBus, CreateTrackCommand, LockTarget, MixerEndpoint, MoveClipCommand,
+    SetLockCommand, SetMixerTargetCommand, WriteAutomationPointCommand,
 };
 pub use core::state::{
     ArrangementClip, ArrangementState, ArrangementTrack, AutomationLaneState, AutomationOwner,
@@ -59,10 +64,18 @@ pub use core::state::{
 };
 pub use core::CommandError;
 pub use dsp::RealtimeDspEngine;
-pub use engine::{EngineCommand, EngineCommandQueue, HarmoniqEngine, TransportState};
-pub use graph::{GraphBuilder, GraphHandle, NodeHandle};
+pub use engine::{
+    EngineCommand, EngineCommandQueue, HarmoniqEngine, ResolvedTarget, TransportState,
+};
+pub use expression::{NoteController, VoiceExpression};
+pub use graph::{
+    pin_signal, port_count, Edge, GraphBuilder, GraphError, GraphHandle, NodeHandle, NodeKind,
+    PdcError, PinDirection, PinId, SignalKind, DEFAULT_MAX_LATENCY_CAP, SIDECHAIN_PIN,
+};
 #[cfg(feature = "clap_host")]
 pub use host::clap_hosting::ClapSlot;
+pub use humanize::HumanizeSettings;
+pub use legato::{MonoLegato, MonoLegatoSettings, NotePriority};
 #[cfg(feature = "mixer_api")]
 pub use mixer::control::{
     ChannelId, EngineMixerHandle, GuiMeterReceiver, MeterEvent, MixerBackend, MixerCommand, SendId,
@@ -71,28 +84,39 @@ pub use mixer::{
     MixerAuxSendState, MixerAuxState, MixerBusState, MixerEngine, MixerInsertProcessor,
     MixerInsertState, MixerMasterState, MixerModel, MixerState, MixerTargetState, MixerTrackState,
 };
-pub use nodes::{GainNode, NodeNoise, NodeOsc, NoiseNode, SineNode};
+pub use nodes::{GainNode, NodeNoise, NodeOsc, NodeSaw, NoiseNode, SineNode};
 pub use plugin::{
     AudioProcessor, MidiEvent, MidiProcessor, MidiTimestamp, PluginDescriptor, PluginId,
+    ProcessTransport,
 };
 pub use project::{
-    autosave_path, load_project, save_autosave, save_project, LoadError as ProjectLoadError,
+    autosave_path, latest_recovery_snapshot, load_project, save_autosave, save_project,
+    save_project_with, save_recovery_snapshot, AutosaveConfig, LoadError as ProjectLoadError,
     LoadOptions as ProjectLoadOptions, LoadReport as ProjectLoadReport, MediaAsset, MediaChecksum,
-    MediaChunkDescriptor, MigrationError as ProjectMigrationError, ProjectDocument,
-    ProjectMetadata, SaveError as ProjectSaveError, SaveOptions as ProjectSaveOptions,
+    MediaChunkDescriptor, MigrationError as ProjectMigrationError, ProjectDocument, ProjectFormat,
+    ProjectMetadata, SampleRef, SaveError as ProjectSaveError, SaveOptions as ProjectSaveOptions,
     SaveReport as ProjectSaveReport, CURRENT_VERSION as PROJECT_VERSION,
 };
 pub use render::{
-    DitherKind, FreezeSettings, OfflineRenderer, RenderDuration, RenderFile, RenderFormat,
-    RenderProject, RenderQueue, RenderReport, RenderRequest, RenderResult, RenderSpeed,
-    StemSettings,
+    AbRenderResult, DitherKind, FreezeSettings, LoudnessReport, Normalization, NormalizationReport,
+    OfflineRenderer, ProcessorKind, RenderControl, RenderDuration, RenderFile, RenderFormat,
+    RenderMetadata, RenderProgress, RenderProject, RenderQueue, RenderReport, RenderRequest,
+    RenderResult, RenderSource, RenderSpeed, RenderedFile, ShapingProfile, StemGroup, StemSettings,
+    StemSource, StemSplit, TrackPeak,
+};
+pub use rt::governor::{
+    GovernorState, LoadGovernor, LoadGovernorConfig, VoiceLevel, VoiceShedding,
 };
 pub use rt::{AudioMetrics, AudioMetricsCollector};
+pub use session::{
+    LaunchQuantize, ScheduledLaunch, SessionClip, SessionError, SessionEvent, SessionView,
+};
+pub use step_pattern::{Step, StepPattern};
 pub use time::{
     BeatInfo, LoopRegion, Tempo, TempoMap, TempoSegment, TimeSignature,
     Transport as TimelineTransport,
 };
-pub use timeline::{ClipEvent, Timeline, TimelineError};
+pub use timeline::{ClipEvent, Timeline, TimelineError, TimelinePlayer};
 pub use transport::Transport as RealtimeTransport;
 
 pub use scratch::{
@@ -103,7 +127,10 @@ pub use scratch::{
 pub mod backend;
 
 #[cfg(feature = "native")]
-pub use realtime::{start_realtime, EngineHandle};
+pub use realtime::{
+    list_devices, start_realtime, start_realtime_duplex, watch_devices, DeviceEvent, DeviceInfo,
+    DeviceWatcher, DuplexStreams, EngineHandle, StreamMetrics,
+};
 
 #[cfg(test)]
 mod tests {
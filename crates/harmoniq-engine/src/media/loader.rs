@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Seek, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -146,8 +147,18 @@ impl MediaLoader {
         audio: &DecodedAudio,
         path: P,
     ) -> Result<(), MediaLoaderError> {
-        let mut writer = hound::WavWriter::create(
-            path,
+        self.encode_wav(audio, BufWriter::new(File::create(path)?))
+    }
+
+    /// Writes `audio` as 32-bit float WAV to any seekable writer, such as an
+    /// in-memory cursor.
+    pub fn encode_wav<W: Write + Seek>(
+        &self,
+        audio: &DecodedAudio,
+        writer: W,
+    ) -> Result<(), MediaLoaderError> {
+        let mut writer = hound::WavWriter::new(
+            writer,
             hound::WavSpec {
                 channels: audio.channels.len() as u16,
                 sample_rate: audio.sample_rate,
@@ -4,6 +4,8 @@ use crossbeam_channel::{unbounded, Receiver, Sender};
 use parking_lot::Mutex;
 use ringbuf::{Consumer, HeapRb, Producer};
 
+pub use crate::mixer_rt::{SoloMode, VcaId};
+
 pub type ChannelId = u32;
 pub type SendId = u8;
 
@@ -24,6 +26,28 @@ pub enum MixerCommand {
         ch: ChannelId,
         solo: bool,
     },
+    SetVcaGain {
+        vca: VcaId,
+        gain_db: f32,
+    },
+    AssignVca {
+        ch: ChannelId,
+        vca: Option<VcaId>,
+    },
+    SetVcaParent {
+        vca: VcaId,
+        parent: Option<VcaId>,
+    },
+    SetSoloMode {
+        mode: SoloMode,
+    },
+    SetMonitorDim {
+        enabled: bool,
+        dim_db: f32,
+    },
+    SetMonitorMono {
+        enabled: bool,
+    },
     OpenInsertBrowser {
         ch: ChannelId,
         slot: Option<usize>,
@@ -77,6 +101,12 @@ pub trait MixerBackend {
     fn set_gain_pan(&mut self, ch: ChannelId, gain_db: f32, pan: f32);
     fn set_mute(&mut self, ch: ChannelId, mute: bool);
     fn set_solo(&mut self, ch: ChannelId, solo: bool);
+    fn set_vca_gain(&mut self, _vca: VcaId, _gain_db: f32) {}
+    fn assign_vca(&mut self, _ch: ChannelId, _vca: Option<VcaId>) {}
+    fn set_vca_parent(&mut self, _vca: VcaId, _parent: Option<VcaId>) {}
+    fn set_solo_mode(&mut self, _mode: SoloMode) {}
+    fn set_monitor_dim(&mut self, _enabled: bool, _dim_db: f32) {}
+    fn set_monitor_mono(&mut self, _enabled: bool) {}
     fn open_insert_browser(&mut self, ch: ChannelId, slot: Option<usize>);
     fn open_insert_ui(&mut self, ch: ChannelId, slot: usize);
     fn set_insert_bypass(&mut self, ch: ChannelId, slot: usize, bypass: bool);
@@ -143,6 +173,24 @@ impl EngineMixerHandle {
                 MixerCommand::SetSolo { ch, solo } => {
                     backend.set_solo(ch, solo);
                 }
+                MixerCommand::SetVcaGain { vca, gain_db } => {
+                    backend.set_vca_gain(vca, gain_db);
+                }
+                MixerCommand::AssignVca { ch, vca } => {
+                    backend.assign_vca(ch, vca);
+                }
+                MixerCommand::SetVcaParent { vca, parent } => {
+                    backend.set_vca_parent(vca, parent);
+                }
+                MixerCommand::SetSoloMode { mode } => {
+                    backend.set_solo_mode(mode);
+                }
+                MixerCommand::SetMonitorDim { enabled, dim_db } => {
+                    backend.set_monitor_dim(enabled, dim_db);
+                }
+                MixerCommand::SetMonitorMono { enabled } => {
+                    backend.set_monitor_mono(enabled);
+                }
                 MixerCommand::OpenInsertBrowser { ch, slot } => {
                     backend.open_insert_browser(ch, slot);
                 }
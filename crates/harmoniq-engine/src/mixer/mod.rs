@@ -26,7 +26,12 @@ use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 
 use crate::buffer::AudioBuffer;
-use crate::dsp::nodes::{FaderNode, MeterHandle, MeterTapNode, StereoWidthNode};
+use crate::dsp::nodes::{
+    FaderNode, MeterHandle, MeterTapNode, SpectrumHandle, SpectrumTap, StereoWidthNode,
+};
+
+/// Transform size of the master spectrum: about 12 Hz per bin at 48 kHz.
+pub const MASTER_SPECTRUM_FFT_SIZE: usize = 4_096;
 
 /// Runtime audio processor that can be inserted into a mixer channel.
 pub trait MixerInsertProcessor: Send {
@@ -46,6 +51,14 @@ where
 pub struct MixerInsertState {
     pub id: Option<String>,
     pub bypassed: bool,
+    /// CLAP parameter id behind each automation index of a hosted plug-in,
+    /// so saved automation keeps addressing the same parameters.
+    #[serde(default)]
+    pub parameter_ids: Vec<u32>,
+    /// Opaque state blob saved by the hosted plug-in, empty when it keeps
+    /// none.
+    #[serde(default)]
+    pub state: Vec<u8>,
 }
 
 impl Default for MixerInsertState {
@@ -53,6 +66,8 @@ impl Default for MixerInsertState {
         Self {
             id: None,
             bypassed: false,
+            parameter_ids: Vec::new(),
+            state: Vec::new(),
         }
     }
 }
@@ -254,6 +269,7 @@ pub struct MixerModel {
     bus_meters: Vec<MeterHandle>,
     aux_meters: Vec<MeterHandle>,
     master_meter: MeterHandle,
+    master_spectrum: SpectrumHandle,
     pre_inserts: Vec<Vec<Option<Arc<Mutex<Box<dyn MixerInsertProcessor>>>>>>,
     post_inserts: Vec<Vec<Option<Arc<Mutex<Box<dyn MixerInsertProcessor>>>>>>,
     bus_post_inserts: Vec<Vec<Option<Arc<Mutex<Box<dyn MixerInsertProcessor>>>>>>,
@@ -266,6 +282,7 @@ impl MixerModel {
             bus_meters: Vec::new(),
             aux_meters: Vec::new(),
             master_meter: MeterHandle::new(),
+            master_spectrum: SpectrumHandle::new(MASTER_SPECTRUM_FFT_SIZE),
             pre_inserts: Vec::new(),
             post_inserts: Vec::new(),
             bus_post_inserts: Vec::new(),
@@ -299,6 +316,11 @@ impl MixerModel {
         self.master_meter.clone()
     }
 
+    /// Spectrum of the master output, e.g. for an EQ overlay.
+    pub fn master_spectrum(&self) -> SpectrumHandle {
+        self.master_spectrum.clone()
+    }
+
     pub fn set_state(&mut self, state: MixerState) {
         self.state = state;
         self.ensure_handles();
@@ -654,6 +676,7 @@ pub struct MixerEngine {
     master_fader: FaderNode,
     master_width: StereoWidthNode,
     master_meter: MeterTapNode,
+    master_spectrum: SpectrumTap,
     master_pan: f32,
 }
 
@@ -702,6 +725,7 @@ impl MixerEngine {
         master_fader.prepare(sample_rate);
         let mut master_meter = MeterTapNode::new(sample_rate, model.master_meter.clone());
         master_meter.prepare(sample_rate, 2);
+        let master_spectrum = SpectrumTap::new(sample_rate, model.master_spectrum.clone());
         let mut master_width = StereoWidthNode::new(model.state.master.width);
         master_width.set_width(model.state.master.width);
         Self {
@@ -713,6 +737,7 @@ impl MixerEngine {
             master_fader,
             master_width,
             master_meter,
+            master_spectrum,
             master_pan: model.state.master.pan,
         }
     }
@@ -804,6 +829,7 @@ impl MixerEngine {
         self.master_width.process_buffer(output);
         self.master_fader.process_buffer(output);
         self.master_meter.process_buffer(output);
+        self.master_spectrum.process_buffer(output);
     }
 }
 
@@ -1,6 +1,7 @@
 use arc_swap::ArcSwap;
 use atomic_float::AtomicF32;
 use core::sync::atomic::Ordering;
+use harmoniq_dsp::true_peak::TruePeakDetector;
 use ringbuf::{HeapConsumer, HeapProducer, HeapRb};
 use std::collections::BTreeMap;
 use std::sync::Arc;
@@ -33,6 +34,44 @@ pub enum Command {
         track: TrackId,
         enable: bool,
     },
+    /// Set a VCA fader. Member tracks are scaled by it without having their
+    /// audio summed through a bus.
+    SetVcaGain {
+        vca: VcaId,
+        gain_db: f32,
+    },
+    /// Place a track under a VCA, or release it with `None`.
+    AssignVca {
+        track: TrackId,
+        vca: Option<VcaId>,
+    },
+    /// Nest a VCA under another so their gains multiply, or detach it.
+    SetVcaParent {
+        vca: VcaId,
+        parent: Option<VcaId>,
+    },
+    /// Choose how soloed tracks are heard.
+    SetSoloMode {
+        mode: SoloMode,
+    },
+    /// Dim the monitor bus by `dim_db` while `enabled`.
+    SetMonitorDim {
+        enabled: bool,
+        dim_db: f32,
+    },
+    /// Fold the monitor bus to mono.
+    SetMonitorMono {
+        enabled: bool,
+    },
+    /// Choose the detector feeding [`Mixer::meter_levels`].
+    SetMeterMode {
+        mode: MeterMode,
+    },
+    /// Change the peak-hold time and the decay rate of held peaks.
+    SetMeterBallistics {
+        hold_ms: f32,
+        decay_db_per_s: f32,
+    },
     /// Routing swap: atomically switch to a new routing table at next block.
     SwapRouting {
         epoch_hint: u64,
@@ -74,6 +113,16 @@ pub struct MixerConfig {
     pub smooth_alpha: f32,
     /// Maximum number of aux busses to pre-allocate buffers for.
     pub max_aux_busses: usize,
+    /// Number of VCA faders to pre-allocate.
+    pub max_vcas: usize,
+    pub meter_mode: MeterMode,
+    /// How long a peak is held before it starts to decay.
+    pub peak_hold_ms: f32,
+    /// Fall rate of a held peak once the hold time has passed.
+    pub peak_decay_db_per_s: f32,
+    /// Accumulate the master sum and apply the master gain in `f64`; the
+    /// master output handed to the master chain is still `f32`.
+    pub high_precision_master: bool,
 }
 
 impl Default for MixerConfig {
@@ -84,6 +133,73 @@ impl Default for MixerConfig {
             sample_rate: 48_000.0,
             smooth_alpha: 0.2,
             max_aux_busses: 4,
+            max_vcas: MAX_VCAS,
+            high_precision_master: false,
+            meter_mode: MeterMode::Peak,
+            peak_hold_ms: 1_500.0,
+            peak_decay_db_per_s: 20.0,
+        }
+    }
+}
+
+/// How soloing a track changes what is heard.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SoloMode {
+    /// Solo in place: every track that is not soloed is muted in the main
+    /// mix.
+    #[default]
+    Sip,
+    /// After-fader listen: the main mix is untouched and soloed tracks are
+    /// summed post-fader and post-pan into [`MONITOR_BUS`].
+    Afl,
+    /// Pre-fader listen: as [`SoloMode::Afl`], but tapped before the fader
+    /// and pan, so the level ignores fader moves.
+    Pfl,
+}
+
+/// Detector used for the level reported by [`Mixer::meter_levels`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MeterMode {
+    /// Smoothed RMS of the mono sum.
+    Rms,
+    /// Highest absolute sample of either side.
+    #[default]
+    Peak,
+    /// Highest absolute value of either side after 4x oversampling, which
+    /// catches inter-sample peaks a sample meter misses.
+    TruePeak,
+}
+
+/// VCA faders allocated by [`MixerConfig::default`].
+pub const MAX_VCAS: usize = 16;
+
+/// Meter reading with peak hold.
+///
+/// `level` is the most recent block's value and `hold` the highest level
+/// seen within the hold window. Once the window passes without a new peak,
+/// `hold` falls at the configured rate until it meets `level` again.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MeterLevels {
+    pub level: f32,
+    pub hold: f32,
+    held_secs: f32,
+}
+
+impl MeterLevels {
+    /// Folds in the level of a block lasting `elapsed_secs`.
+    pub fn update(&mut self, level: f32, elapsed_secs: f32, hold_secs: f32, decay_db_per_s: f32) {
+        self.level = level;
+        if level >= self.hold {
+            self.hold = level;
+            self.held_secs = 0.0;
+            return;
+        }
+        let held = self.held_secs + elapsed_secs;
+        let decaying = (held - hold_secs.max(0.0)).clamp(0.0, elapsed_secs);
+        self.held_secs = held;
+        if decaying > 0.0 {
+            self.hold *= db_to_gain(-decay_db_per_s.max(0.0) * decaying);
+            self.hold = self.hold.max(level);
         }
     }
 }
@@ -145,8 +261,14 @@ struct Track {
     pan_target_current: f32,
     gain_ramp: RampState,
     pan_ramp: RampState,
+    vca: Option<VcaId>,
     peak_atomic: AtomicF32,
     rms_atomic: AtomicF32,
+    level_atomic: AtomicF32,
+    hold_atomic: AtomicF32,
+    meter: MeterLevels,
+    true_peak: [TruePeakDetector; 2],
+    true_peak_block: f32,
     peak_block: f32,
     rms_accum: f64,
     rms_count: usize,
@@ -166,8 +288,14 @@ impl Track {
             pan_target_current: 0.0,
             gain_ramp: RampState::default(),
             pan_ramp: RampState::default(),
+            vca: None,
             peak_atomic: AtomicF32::new(0.0),
             rms_atomic: AtomicF32::new(0.0),
+            level_atomic: AtomicF32::new(0.0),
+            hold_atomic: AtomicF32::new(0.0),
+            meter: MeterLevels::default(),
+            true_peak: Default::default(),
+            true_peak_block: 0.0,
             peak_block: 0.0,
             rms_accum: 0.0,
             rms_count: 0,
@@ -175,10 +303,45 @@ impl Track {
     }
 }
 
+/// Applies the monitor dim `gain` and the mono fold to a stereo pair.
+fn apply_monitor_controls(gain: f32, mono: bool, left: &mut [f32], right: &mut [f32]) {
+    let monitor = left.iter_mut().zip(right.iter_mut());
+    if mono {
+        // -3 dB keeps centred material at the level it had in stereo.
+        let fold = gain * core::f32::consts::FRAC_1_SQRT_2;
+        for (l, r) in monitor {
+            let mono = (*l + *r) * fold;
+            *l = mono;
+            *r = mono;
+        }
+    } else if gain != 1.0 {
+        for (l, r) in monitor {
+            *l *= gain;
+            *r *= gain;
+        }
+    }
+}
+
 /// Aux bus identifiers.
 pub type AuxBusId = u16;
 /// Group identifiers.
 pub type GroupId = u16;
+/// VCA fader identifiers.
+pub type VcaId = u16;
+
+/// Bus carrying the monitor signal, readable through [`Mixer::bus_output`]
+/// for routing to headphones or a control room, and put on the device
+/// output by [`Mixer::write_monitor`]. It mirrors the main output unless
+/// tracks are soloed in [`SoloMode::Afl`] or [`SoloMode::Pfl`], in which
+/// case it carries only their sum. Dim and mono are applied here and
+/// never to the main output, so bounces are unaffected by them.
+pub const MONITOR_BUS: AuxBusId = AuxBusId::MAX;
+
+#[derive(Clone, Copy, Debug)]
+struct VcaSlot {
+    gain_lin: f32,
+    parent: Option<VcaId>,
+}
 
 /// Fixed routing table that can be swapped atomically at block boundaries.
 #[derive(Debug)]
@@ -275,12 +438,34 @@ pub struct Mixer {
     auto_rx: AutoRx,
     left_accum: Vec<f32>,
     right_accum: Vec<f32>,
+    left_accum_wide: Vec<f64>,
+    right_accum_wide: Vec<f64>,
     aux_l: Vec<f32>,
     aux_r: Vec<f32>,
     group_l: Vec<f32>,
     group_r: Vec<f32>,
     routing_epoch: ArcSwap<RoutingTable>,
     routing_shadow: Arc<RoutingTable>,
+    vcas: Vec<VcaSlot>,
+    /// Per-VCA gain with every parent multiplied in, refreshed each block.
+    vca_gain: Vec<f32>,
+    solo_mode: SoloMode,
+    /// Monitor dim gain, `1.0` when dim is off.
+    monitor_gain: f32,
+    monitor_mono: bool,
+    /// Set by `process` when the monitor bus carries a solo listen sum
+    /// rather than the main output.
+    monitor_listening: bool,
+    /// Set by `process`, cleared by `write_monitor`, so a block the mixer
+    /// did not run never replays stale monitor audio.
+    monitor_fresh: bool,
+    monitor_l: Vec<f32>,
+    monitor_r: Vec<f32>,
+    meter_mode: MeterMode,
+    /// Post-pan signal of the track being mixed, for the true-peak meter.
+    meter_l: Vec<f32>,
+    meter_r: Vec<f32>,
+    block_frames: usize,
 }
 
 impl Mixer {
@@ -301,6 +486,13 @@ impl Mixer {
 
         let left_accum = vec![0.0f32; cfg.max_block];
         let right_accum = vec![0.0f32; cfg.max_block];
+        let wide_len = if cfg.high_precision_master {
+            cfg.max_block
+        } else {
+            0
+        };
+        let left_accum_wide = vec![0.0f64; wide_len];
+        let right_accum_wide = vec![0.0f64; wide_len];
         let aux_capacity = cfg.max_aux_busses.max(1);
         let aux_l = vec![0.0f32; aux_capacity * cfg.max_block];
         let aux_r = vec![0.0f32; aux_capacity * cfg.max_block];
@@ -321,12 +513,33 @@ impl Mixer {
                 auto_rx,
                 left_accum: left_accum,
                 right_accum: right_accum,
+                left_accum_wide,
+                right_accum_wide,
                 aux_l,
                 aux_r,
                 group_l,
                 group_r,
                 routing_epoch: ArcSwap::from(routing.clone()),
                 routing_shadow: routing,
+                vcas: vec![
+                    VcaSlot {
+                        gain_lin: 1.0,
+                        parent: None,
+                    };
+                    cfg.max_vcas
+                ],
+                vca_gain: vec![1.0; cfg.max_vcas],
+                solo_mode: SoloMode::default(),
+                monitor_gain: 1.0,
+                monitor_mono: false,
+                monitor_listening: false,
+                monitor_fresh: false,
+                monitor_l: vec![0.0f32; cfg.max_block],
+                monitor_r: vec![0.0f32; cfg.max_block],
+                meter_mode: cfg.meter_mode,
+                meter_l: vec![0.0f32; cfg.max_block],
+                meter_r: vec![0.0f32; cfg.max_block],
+                block_frames: 0,
             },
             cmd_tx,
             auto_tx,
@@ -374,6 +587,51 @@ impl Mixer {
                     t.enabled = enable;
                 }
             }
+            Command::SetVcaGain { vca, gain_db } => {
+                if let Some(slot) = self.vcas.get_mut(vca as usize) {
+                    slot.gain_lin = db_to_lin(gain_db);
+                }
+            }
+            Command::AssignVca { track, vca } => {
+                if let Some(t) = self.tracks.get_mut(track as usize) {
+                    t.vca = vca.filter(|vca| (*vca as usize) < self.cfg.max_vcas);
+                }
+            }
+            Command::SetVcaParent { vca, parent } => {
+                if let Some(slot) = self.vcas.get_mut(vca as usize) {
+                    slot.parent = parent;
+                }
+            }
+            Command::SetSoloMode { mode } => {
+                self.solo_mode = mode;
+            }
+            Command::SetMonitorDim { enabled, dim_db } => {
+                self.monitor_gain = if enabled {
+                    db_to_lin(-dim_db.abs())
+                } else {
+                    1.0
+                };
+            }
+            Command::SetMonitorMono { enabled } => {
+                self.monitor_mono = enabled;
+            }
+            Command::SetMeterMode { mode } => {
+                if mode == MeterMode::TruePeak && self.meter_mode != mode {
+                    for track in &mut self.tracks {
+                        for detector in &mut track.true_peak {
+                            detector.reset();
+                        }
+                    }
+                }
+                self.meter_mode = mode;
+            }
+            Command::SetMeterBallistics {
+                hold_ms,
+                decay_db_per_s,
+            } => {
+                self.cfg.peak_hold_ms = hold_ms.max(0.0);
+                self.cfg.peak_decay_db_per_s = decay_db_per_s.max(0.0);
+            }
             Command::SwapRouting { table, .. } => {
                 debug_assert!(table.aux_to_master_gain.len() <= self.cfg.max_aux_busses);
                 debug_assert!(table.groups.len() <= self.cfg.max_tracks);
@@ -456,11 +714,21 @@ impl Mixer {
         debug_assert_eq!(out_l.len(), nframes);
         debug_assert_eq!(out_r.len(), nframes);
 
-        self.left_accum[..nframes].fill(0.0);
-        self.right_accum[..nframes].fill(0.0);
+        let wide = self.cfg.high_precision_master;
+        if wide {
+            self.left_accum_wide[..nframes].fill(0.0);
+            self.right_accum_wide[..nframes].fill(0.0);
+        } else {
+            self.left_accum[..nframes].fill(0.0);
+            self.right_accum[..nframes].fill(0.0);
+        }
+        self.monitor_l[..nframes].fill(0.0);
+        self.monitor_r[..nframes].fill(0.0);
 
+        self.block_frames = nframes;
         for track in &mut self.tracks {
             track.peak_block = 0.0;
+            track.true_peak_block = 0.0;
             track.rms_accum = 0.0;
             track.rms_count = 0;
         }
@@ -483,6 +751,21 @@ impl Mixer {
             self.group_r[base..base + nframes].fill(0.0);
         }
 
+        // Walk each VCA up its parent chain. The walk is bounded so a loop
+        // left by conflicting commands cannot hang the audio thread.
+        for vca in 0..self.vcas.len() {
+            let mut gain = 1.0;
+            let mut next = Some(vca as VcaId);
+            for _ in 0..self.vcas.len() {
+                let Some(slot) = next.and_then(|id| self.vcas.get(id as usize)) else {
+                    break;
+                };
+                gain *= slot.gain_lin;
+                next = slot.parent;
+            }
+            self.vca_gain[vca] = gain;
+        }
+
         let any_solo = self
             .tracks
             .iter()
@@ -498,7 +781,17 @@ impl Mixer {
 
             let mute = track.mute.load(Ordering::Relaxed) >= 0.5;
             let solo_this = track.solo.load(Ordering::Relaxed) >= 0.5;
-            if mute || (any_solo && !solo_this) {
+            let audible = match self.solo_mode {
+                SoloMode::Sip => !mute && (!any_solo || solo_this),
+                SoloMode::Afl | SoloMode::Pfl => !mute,
+            };
+            // Pre-fader listen also reaches tracks muted at the fader.
+            let tap = match self.solo_mode {
+                SoloMode::Sip => None,
+                SoloMode::Afl => Some(SoloMode::Afl).filter(|_| solo_this && !mute),
+                SoloMode::Pfl => Some(SoloMode::Pfl).filter(|_| solo_this),
+            };
+            if !audible && tap.is_none() {
                 continue;
             }
 
@@ -509,6 +802,11 @@ impl Mixer {
                 .get(ti)
                 .and_then(|g| *g)
                 .filter(|idx| *idx < group_count);
+            let vca_gain = track
+                .vca
+                .and_then(|vca| self.vca_gain.get(vca as usize))
+                .copied()
+                .unwrap_or(1.0);
             for i in 0..n {
                 if track.gain_ramp.is_active() {
                     track.gain_ramp.advance(&mut track.gain_target_current);
@@ -517,17 +815,38 @@ impl Mixer {
                     track.pan_ramp.advance(&mut track.pan_target_current);
                 }
 
-                let target_gain = track.gain_target_current;
+                let target_gain = track.gain_target_current * vca_gain;
                 let target_pan = track.pan_target_current;
                 track.gain_work_lin += (target_gain - track.gain_work_lin) * self.cfg.smooth_alpha;
                 track.pan_work += (target_pan - track.pan_work) * self.cfg.smooth_alpha;
 
                 let sample = input[i] * track.gain_work_lin;
                 let (l, r) = pan_mono(sample, track.pan_work);
+                match tap {
+                    Some(SoloMode::Afl) => {
+                        self.monitor_l[i] += l;
+                        self.monitor_r[i] += r;
+                    }
+                    Some(SoloMode::Pfl) => {
+                        let (pre_l, pre_r) = pan_mono(input[i], 0.0);
+                        self.monitor_l[i] += pre_l;
+                        self.monitor_r[i] += pre_r;
+                    }
+                    _ => {}
+                }
+                if !audible {
+                    continue;
+                }
+
+                self.meter_l[i] = l;
+                self.meter_r[i] = r;
                 if let Some(group_idx) = group_idx {
                     let base = group_idx * self.cfg.max_block;
                     self.group_l[base + i] += l;
                     self.group_r[base + i] += r;
+                } else if wide {
+                    self.left_accum_wide[i] += l as f64;
+                    self.right_accum_wide[i] += r as f64;
                 } else {
                     self.left_accum[i] += l;
                     self.right_accum[i] += r;
@@ -545,6 +864,21 @@ impl Mixer {
                 track.rms_count += 1;
             }
 
+            if !audible {
+                continue;
+            }
+
+            if self.meter_mode == MeterMode::TruePeak {
+                for (detector, side) in track
+                    .true_peak
+                    .iter_mut()
+                    .zip([&self.meter_l[..n], &self.meter_r[..n]])
+                {
+                    let peak = detector.process_block(side);
+                    track.true_peak_block = track.true_peak_block.max(peak);
+                }
+            }
+
             if let Some(sends) = self.routing_shadow.sends.get(ti) {
                 for &(aux_id, send_gain) in sends.iter() {
                     let aux_idx = aux_id as usize;
@@ -566,16 +900,26 @@ impl Mixer {
             let gain = self.routing_shadow.aux_to_master_gain[aux_idx];
             let base = aux_idx * self.cfg.max_block;
             for i in 0..nframes {
-                self.left_accum[i] += self.aux_l[base + i] * gain;
-                self.right_accum[i] += self.aux_r[base + i] * gain;
+                if wide {
+                    self.left_accum_wide[i] += self.aux_l[base + i] as f64 * gain as f64;
+                    self.right_accum_wide[i] += self.aux_r[base + i] as f64 * gain as f64;
+                } else {
+                    self.left_accum[i] += self.aux_l[base + i] * gain;
+                    self.right_accum[i] += self.aux_r[base + i] * gain;
+                }
             }
         }
 
         for group_idx in 0..group_count {
             let base = group_idx * self.cfg.max_block;
             for i in 0..nframes {
-                self.left_accum[i] += self.group_l[base + i];
-                self.right_accum[i] += self.group_r[base + i];
+                if wide {
+                    self.left_accum_wide[i] += self.group_l[base + i] as f64;
+                    self.right_accum_wide[i] += self.group_r[base + i] as f64;
+                } else {
+                    self.left_accum[i] += self.group_l[base + i];
+                    self.right_accum[i] += self.group_r[base + i];
+                }
             }
         }
 
@@ -586,14 +930,78 @@ impl Mixer {
             self.master_gain_work +=
                 (self.master_target_current - self.master_gain_work) * self.cfg.smooth_alpha;
             let master = self.master_gain_work;
-            out_l[i] = self.left_accum[i] * master;
-            out_r[i] = self.right_accum[i] * master;
+            if wide {
+                out_l[i] = (self.left_accum_wide[i] * master as f64) as f32;
+                out_r[i] = (self.right_accum_wide[i] * master as f64) as f32;
+            } else {
+                out_l[i] = self.left_accum[i] * master;
+                out_r[i] = self.right_accum[i] * master;
+            }
+        }
+
+        self.monitor_listening = self.solo_mode != SoloMode::Sip && any_solo;
+        self.monitor_fresh = true;
+        if !self.monitor_listening {
+            self.monitor_l[..nframes].copy_from_slice(&out_l[..nframes]);
+            self.monitor_r[..nframes].copy_from_slice(&out_r[..nframes]);
+        }
+
+        apply_monitor_controls(
+            self.monitor_gain,
+            self.monitor_mono,
+            &mut self.monitor_l[..nframes],
+            &mut self.monitor_r[..nframes],
+        );
+    }
+
+    pub fn solo_mode(&self) -> SoloMode {
+        self.solo_mode
+    }
+
+    /// Turns `left` and `right`, holding the block's final main output,
+    /// into what the device output plays: the [`MONITOR_BUS`] solo listen
+    /// sum while tracks are soloed in AFL or PFL, otherwise the main output
+    /// with monitor dim and mono applied. Call once per block after
+    /// `process`; offline rendering never does, so listening aids stay out
+    /// of bounces.
+    pub fn write_monitor(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let fresh = core::mem::take(&mut self.monitor_fresh);
+        if fresh && self.monitor_listening {
+            let frames = left.len().min(right.len()).min(self.block_frames);
+            left[..frames].copy_from_slice(&self.monitor_l[..frames]);
+            right[..frames].copy_from_slice(&self.monitor_r[..frames]);
+        } else {
+            apply_monitor_controls(self.monitor_gain, self.monitor_mono, left, right);
+        }
+    }
+
+    /// Left and right audio of an aux bus or [`MONITOR_BUS`] from the last
+    /// `process` call. Aux busses are read before their master gain.
+    pub fn bus_output(&self, bus: AuxBusId) -> Option<(&[f32], &[f32])> {
+        let frames = self.block_frames;
+        if bus == MONITOR_BUS {
+            return Some((&self.monitor_l[..frames], &self.monitor_r[..frames]));
+        }
+        let aux_count = self
+            .routing_shadow
+            .aux_to_master_gain
+            .len()
+            .min(self.cfg.max_aux_busses);
+        if bus as usize >= aux_count {
+            return None;
         }
+        let base = bus as usize * self.cfg.max_block;
+        Some((
+            &self.aux_l[base..base + frames],
+            &self.aux_r[base..base + frames],
+        ))
     }
 
     /// Finalize block processing (publish meters).
     pub fn end_block(&mut self) {
         let rms_decay = 0.9f32;
+        let elapsed = self.block_frames as f32 / self.cfg.sample_rate.max(1.0);
+        let hold_secs = self.cfg.peak_hold_ms * 0.001;
         for track in &mut self.tracks {
             track.peak_atomic.store(track.peak_block, Ordering::Relaxed);
             if track.rms_count > 0 {
@@ -609,9 +1017,42 @@ impl Mixer {
                     .rms_atomic
                     .store(previous * rms_decay, Ordering::Relaxed);
             }
+
+            let level = match self.meter_mode {
+                MeterMode::Rms => track.rms_atomic.load(Ordering::Relaxed),
+                MeterMode::Peak => track.peak_block,
+                // The interpolator lags by a few samples, so a sample peak
+                // it has not caught up with yet still counts.
+                MeterMode::TruePeak => track.true_peak_block.max(track.peak_block),
+            };
+            track
+                .meter
+                .update(level, elapsed, hold_secs, self.cfg.peak_decay_db_per_s);
+            track
+                .level_atomic
+                .store(track.meter.level, Ordering::Relaxed);
+            track.hold_atomic.store(track.meter.hold, Ordering::Relaxed);
         }
     }
 
+    pub fn meter_mode(&self) -> MeterMode {
+        self.meter_mode
+    }
+
+    /// Level and held peak of a track in the current [`MeterMode`], as
+    /// published by the last `end_block`. Only reads atomics, so the UI
+    /// thread can poll it while audio runs. Unknown tracks read as silent.
+    pub fn meter_levels(&self, track: TrackId) -> MeterLevels {
+        self.tracks
+            .get(track as usize)
+            .map(|t| MeterLevels {
+                level: t.level_atomic.load(Ordering::Relaxed),
+                hold: t.hold_atomic.load(Ordering::Relaxed),
+                held_secs: 0.0,
+            })
+            .unwrap_or_default()
+    }
+
     /// Read the most recent peak meter for a track.
     pub fn track_peak(&self, track: TrackId) -> Option<f32> {
         self.tracks
@@ -627,6 +1068,20 @@ impl Mixer {
     }
 }
 
+#[inline]
+fn pan_mono(sample: f32, pan: f32) -> (f32, f32) {
+    let p = (pan.clamp(-1.0, 1.0) + 1.0) * 0.5;
+    let angle = core::f32::consts::FRAC_PI_2 * p;
+    let l = angle.cos();
+    let r = angle.sin();
+    (sample * l, sample * r)
+}
+
+#[inline]
+fn db_to_gain(db: f32) -> f32 {
+    (10.0f32).powf(db * 0.05)
+}
+
 #[inline]
 fn db_to_lin(db: f32) -> f32 {
     if db <= -90.0 {
@@ -636,11 +1091,284 @@ fn db_to_lin(db: f32) -> f32 {
     }
 }
 
-#[inline]
-fn pan_mono(sample: f32, pan: f32) -> (f32, f32) {
-    let p = (pan.clamp(-1.0, 1.0) + 1.0) * 0.5;
-    let angle = core::f32::consts::FRAC_PI_2 * p;
-    let l = angle.cos();
-    let r = angle.sin();
-    (sample * l, sample * r)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mixer_with(commands: impl IntoIterator<Item = Command>) -> Mixer {
+        let cfg = MixerConfig {
+            max_tracks: 2,
+            max_block: 256,
+            ..MixerConfig::default()
+        };
+        let (mut mixer, mut tx, _auto_tx) = Mixer::new(cfg, 16, 4);
+        for cmd in commands {
+            tx.push(cmd).unwrap();
+        }
+        mixer.begin_block();
+        mixer
+    }
+
+    fn run_block(mixer: &mut Mixer, input: &[f32]) {
+        let (mut l, mut r) = (vec![0.0f32; input.len()], vec![0.0f32; input.len()]);
+        mixer.begin_block();
+        mixer.process(&[Some(input), None], &mut l, &mut r, input.len());
+        mixer.end_block();
+    }
+
+    #[test]
+    fn afl_and_pfl_sum_soloed_tracks_into_the_monitor_bus() {
+        let ones = [1.0f32; 256];
+        let centre = core::f32::consts::FRAC_1_SQRT_2;
+        let half = db_to_lin(-6.0);
+        let setup = |mode| {
+            let cfg = MixerConfig {
+                max_tracks: 3,
+                max_block: 256,
+                ..MixerConfig::default()
+            };
+            let (mut mixer, mut tx, _auto_tx) = Mixer::new(cfg, 16, 4);
+            for track in 0..3 {
+                tx.push(Command::EnableTrack {
+                    track,
+                    enable: true,
+                })
+                .unwrap();
+                tx.push(Command::SetGain {
+                    track,
+                    gain_db: -6.0,
+                })
+                .unwrap();
+            }
+            for track in [0, 1] {
+                tx.push(Command::SetSolo { track, solo: true }).unwrap();
+            }
+            tx.push(Command::SetSoloMode { mode }).unwrap();
+            let (mut l, mut r) = ([0.0f32; 256], [0.0f32; 256]);
+            mixer.begin_block();
+            mixer.process(
+                &[Some(&ones), Some(&ones), Some(&ones)],
+                &mut l,
+                &mut r,
+                256,
+            );
+            let monitor = mixer.bus_output(MONITOR_BUS).unwrap().0[255];
+            (l[255], monitor)
+        };
+
+        // Solo in place mutes the third track in the main mix.
+        let (main, monitor) = setup(SoloMode::Sip);
+        assert!((main - 2.0 * half * centre).abs() < 1e-4, "{main}");
+        assert_eq!(main, monitor);
+
+        // AFL and PFL leave the main mix alone and sum the soloed pair.
+        let (main, monitor) = setup(SoloMode::Afl);
+        assert!((main - 3.0 * half * centre).abs() < 1e-4, "{main}");
+        assert!((monitor - 2.0 * half * centre).abs() < 1e-4, "{monitor}");
+
+        let (main, monitor) = setup(SoloMode::Pfl);
+        assert!((main - 3.0 * half * centre).abs() < 1e-4, "{main}");
+        assert!((monitor - 2.0 * centre).abs() < 1e-4, "{monitor}");
+    }
+
+    #[test]
+    fn solo_listen_reaches_the_device_output_once_per_block() {
+        let mut mixer = mixer_with([
+            Command::EnableTrack {
+                track: 0,
+                enable: true,
+            },
+            Command::EnableTrack {
+                track: 1,
+                enable: true,
+            },
+            Command::SetSolo {
+                track: 1,
+                solo: true,
+            },
+            Command::SetSoloMode {
+                mode: SoloMode::Afl,
+            },
+        ]);
+        let ones = [1.0f32; 256];
+        let silence = [0.0f32; 256];
+        let (mut l, mut r) = ([0.0f32; 256], [0.0f32; 256]);
+        mixer.process(&[Some(&ones), Some(&silence)], &mut l, &mut r, 256);
+        assert!(l[255] > 0.5);
+
+        mixer.write_monitor(&mut l, &mut r);
+        assert_eq!(l[255], 0.0, "only the soloed silent track is heard");
+
+        // Without another `process` the main output passes through.
+        let (mut l, mut r) = ([0.5f32; 256], [0.5f32; 256]);
+        mixer.write_monitor(&mut l, &mut r);
+        assert_eq!((l[255], r[255]), (0.5, 0.5));
+    }
+
+    #[test]
+    fn dim_and_mono_only_touch_the_monitor_bus() {
+        let mut mixer = mixer_with([
+            Command::EnableTrack {
+                track: 0,
+                enable: true,
+            },
+            Command::SetPan {
+                track: 0,
+                pan: -1.0,
+            },
+            Command::SetMonitorDim {
+                enabled: true,
+                dim_db: 20.0,
+            },
+            Command::SetMonitorMono { enabled: true },
+        ]);
+        let ones = [1.0f32; 256];
+        let (mut l, mut r) = ([0.0f32; 256], [0.0f32; 256]);
+        mixer.process(&[Some(&ones), None], &mut l, &mut r, 256);
+        assert!((l[255] - 1.0).abs() < 1e-4 && r[255].abs() < 1e-4);
+
+        let (mon_l, mon_r) = mixer.bus_output(MONITOR_BUS).unwrap();
+        let expected = 0.1 * core::f32::consts::FRAC_1_SQRT_2;
+        assert!((mon_l[255] - expected).abs() < 1e-4, "{}", mon_l[255]);
+        assert_eq!(mon_l, mon_r);
+
+        let mut undimmed = mixer_with([
+            Command::EnableTrack {
+                track: 0,
+                enable: true,
+            },
+            Command::SetMonitorDim {
+                enabled: false,
+                dim_db: 20.0,
+            },
+        ]);
+        undimmed.process(&[Some(&ones), None], &mut l, &mut r, 256);
+        assert_eq!(undimmed.bus_output(MONITOR_BUS).unwrap().0, &l[..]);
+    }
+
+    #[test]
+    fn device_output_is_dimmed_and_folded() {
+        let mut mixer = mixer_with([
+            Command::SetMonitorDim {
+                enabled: true,
+                dim_db: 20.0,
+            },
+            Command::SetMonitorMono { enabled: true },
+        ]);
+        let (mut l, mut r) = ([1.0f32; 256], [0.0f32; 256]);
+        mixer.write_monitor(&mut l, &mut r);
+        let expected = 0.1 * core::f32::consts::FRAC_1_SQRT_2;
+        assert!((l[255] - expected).abs() < 1e-4, "{}", l[255]);
+        assert_eq!(l, r);
+    }
+
+    #[test]
+    fn peak_hold_holds_then_decays() {
+        let mut levels = MeterLevels::default();
+        levels.update(1.0, 0.1, 0.5, 20.0);
+        for _ in 0..5 {
+            levels.update(0.0, 0.1, 0.5, 20.0);
+        }
+        assert_eq!(levels.hold, 1.0);
+        // Half a second past the hold window at 20 dB/s is -10 dB.
+        for _ in 0..5 {
+            levels.update(0.0, 0.1, 0.5, 20.0);
+        }
+        assert!(
+            (levels.hold - db_to_gain(-10.0)).abs() < 1e-4,
+            "{}",
+            levels.hold
+        );
+        levels.update(0.5, 0.1, 0.5, 20.0);
+        assert_eq!(levels.hold, 0.5);
+        assert_eq!(levels.level, 0.5);
+    }
+
+    #[test]
+    fn true_peak_sees_inter_sample_peaks() {
+        // A quarter-rate sine sampled 45 degrees off its crests never
+        // reaches its true amplitude on a sample.
+        let input: Vec<f32> = (0..256)
+            .map(|i| (core::f32::consts::FRAC_PI_2 * i as f32 + core::f32::consts::FRAC_PI_4).sin())
+            .collect();
+        let enable = Command::EnableTrack {
+            track: 0,
+            enable: true,
+        };
+
+        let mut sample = mixer_with([enable.clone()]);
+        let mut true_peak = mixer_with([
+            enable,
+            Command::SetMeterMode {
+                mode: MeterMode::TruePeak,
+            },
+        ]);
+        for _ in 0..4 {
+            run_block(&mut sample, &input);
+            run_block(&mut true_peak, &input);
+        }
+
+        let centre = core::f32::consts::FRAC_1_SQRT_2;
+        let sample_peak = sample.meter_levels(0).level;
+        let true_level = true_peak.meter_levels(0).level;
+        assert!((sample_peak - 0.5).abs() < 1e-3, "{sample_peak}");
+        assert!((true_level - centre).abs() < 0.02, "{true_level}");
+        assert!(true_peak.meter_levels(0).hold >= true_level);
+        assert_eq!(true_peak.meter_levels(7), MeterLevels::default());
+    }
+
+    #[test]
+    fn nested_vca_gains_multiply_into_member_tracks() {
+        let cfg = MixerConfig {
+            max_tracks: 2,
+            max_block: 256,
+            ..MixerConfig::default()
+        };
+        let (mut mixer, mut tx, _auto_tx) = Mixer::new(cfg, 16, 4);
+        for cmd in [
+            Command::EnableTrack {
+                track: 0,
+                enable: true,
+            },
+            Command::EnableTrack {
+                track: 1,
+                enable: true,
+            },
+            Command::AssignVca {
+                track: 0,
+                vca: Some(0),
+            },
+            Command::SetVcaParent {
+                vca: 0,
+                parent: Some(1),
+            },
+            Command::SetVcaGain {
+                vca: 0,
+                gain_db: -6.0,
+            },
+            Command::SetVcaGain {
+                vca: 1,
+                gain_db: -3.0,
+            },
+        ] {
+            tx.push(cmd).unwrap();
+        }
+
+        let ones = [1.0f32; 256];
+        let silent = [0.0f32; 256];
+        let (mut l, mut r) = ([0.0f32; 256], [0.0f32; 256]);
+        mixer.begin_block();
+        mixer.process(&[Some(&ones), Some(&silent)], &mut l, &mut r, 256);
+        let centre = core::f32::consts::FRAC_1_SQRT_2;
+        assert!(
+            (l[255] - db_to_lin(-9.0) * centre).abs() < 1e-4,
+            "{}",
+            l[255]
+        );
+
+        // The unassigned track is untouched by either VCA.
+        mixer.begin_block();
+        mixer.process(&[Some(&silent), Some(&ones)], &mut l, &mut r, 256);
+        assert!((r[255] - centre).abs() < 1e-4, "{}", r[255]);
+    }
 }
@@ -14,7 +14,9 @@ pub struct ClapNode {
 
 impl ClapNode {
     pub fn new(descriptor: ClapPluginDescriptor) -> Self {
-        let ClapPluginDescriptor { id, name, vendor } = descriptor;
+        let ClapPluginDescriptor {
+            id, name, vendor, ..
+        } = descriptor;
         let vendor_name = if vendor.is_empty() {
             "Unknown".to_string()
         } else {
@@ -3,9 +3,11 @@
 pub mod clap_node;
 pub mod gain;
 pub mod noise;
+pub mod saw;
 pub mod sine;
 
 pub use clap_node::ClapNode;
 pub use gain::GainNode;
 pub use noise::{NodeNoise, NoiseNode};
+pub use saw::NodeSaw;
 pub use sine::{NodeOsc, SineNode};
@@ -0,0 +1,101 @@
+use harmoniq_dsp::osc::SyncSaw;
+
+use crate::{AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, PluginDescriptor};
+
+/// Band-limited sawtooth oscillator with anti-aliased hard sync.
+///
+/// Parameter `0` sets the sync ratio (synced frequency over master frequency).
+/// A ratio of `1.0` produces a plain minBLEP sawtooth.
+pub struct NodeSaw {
+    frequency: f32,
+    amplitude: f32,
+    sample_rate: f32,
+    osc: SyncSaw,
+}
+
+impl NodeSaw {
+    /// Creates a new oscillator running at the provided frequency in hertz.
+    pub fn new(frequency: f32) -> Self {
+        Self {
+            frequency: frequency.max(0.0),
+            amplitude: 1.0,
+            sample_rate: 44_100.0,
+            osc: SyncSaw::new(),
+        }
+    }
+
+    /// Sets the oscillator amplitude.
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Sets the hard-sync ratio. Values below `1.0` are clamped.
+    pub fn with_sync_ratio(mut self, ratio: f32) -> Self {
+        self.osc.set_sync_ratio(ratio);
+        self
+    }
+
+    /// Adjusts the oscillator frequency.
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency.max(0.0);
+    }
+
+    /// Adjusts the hard-sync ratio.
+    pub fn set_sync_ratio(&mut self, ratio: f32) {
+        self.osc.set_sync_ratio(ratio);
+    }
+
+    pub fn sync_ratio(&self) -> f32 {
+        self.osc.sync_ratio()
+    }
+}
+
+impl AudioProcessor for NodeSaw {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("harmoniq.saw", "Sync Saw", "Harmoniq Labs")
+            .with_description("Band-limited sawtooth oscillator with hard sync")
+    }
+
+    fn prepare(&mut self, config: &BufferConfig) -> anyhow::Result<()> {
+        self.sample_rate = config.sample_rate.max(f32::EPSILON);
+        self.osc.reset();
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        let frames = buffer.len();
+        if frames == 0 {
+            return Ok(());
+        }
+
+        let channel_count = buffer.channel_count();
+        let data = buffer.as_mut_slice();
+        for frame in 0..frames {
+            let value = self.osc.next(self.frequency, self.sample_rate) * self.amplitude;
+            for channel in 0..channel_count {
+                let index = channel * frames + frame;
+                if index < data.len() {
+                    data[index] = value;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn supports_layout(&self, layout: ChannelLayout) -> bool {
+        matches!(layout, ChannelLayout::Mono | ChannelLayout::Stereo)
+    }
+
+    fn handle_automation_event(
+        &mut self,
+        parameter: usize,
+        value: f32,
+        _sample_offset: usize,
+    ) -> anyhow::Result<()> {
+        if parameter == 0 {
+            self.osc.set_sync_ratio(value);
+        }
+        Ok(())
+    }
+}
@@ -6,7 +6,8 @@ use thiserror::Error;
 use std::sync::OnceLock;
 use std::time::{Duration, Instant};
 
-use crate::{AudioBuffer, BufferConfig, ChannelLayout};
+use crate::expression::NoteController;
+use crate::{AudioBuffer, BufferConfig, ChannelLayout, LoopRegion, Tempo, TimeSignature};
 
 /// Unique identifier for a plugin instance within the engine.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -125,6 +126,15 @@ pub enum MidiEvent {
         sample_offset: u32,
         timestamp: Option<MidiTimestamp>,
     },
+    /// Per-note controller addressed to the voice playing `note`.
+    NoteExpression {
+        channel: u8,
+        note: u8,
+        controller: NoteController,
+        value: f32,
+        sample_offset: u32,
+        timestamp: Option<MidiTimestamp>,
+    },
 }
 
 impl MidiEvent {
@@ -134,7 +144,8 @@ impl MidiEvent {
             MidiEvent::NoteOn { timestamp, .. }
             | MidiEvent::NoteOff { timestamp, .. }
             | MidiEvent::ControlChange { timestamp, .. }
-            | MidiEvent::PitchBend { timestamp, .. } => *timestamp,
+            | MidiEvent::PitchBend { timestamp, .. }
+            | MidiEvent::NoteExpression { timestamp, .. } => *timestamp,
         }
     }
 
@@ -151,6 +162,24 @@ impl MidiEvent {
         Self::from_bytes(sample_offset, data, Some(timestamp))
     }
 
+    /// Construct a per-note controller event.
+    pub fn note_expression(
+        sample_offset: u32,
+        channel: u8,
+        note: u8,
+        controller: NoteController,
+        value: f32,
+    ) -> Self {
+        MidiEvent::NoteExpression {
+            channel: channel & 0x0F,
+            note: note & 0x7F,
+            controller,
+            value,
+            sample_offset,
+            timestamp: None,
+        }
+    }
+
     fn from_bytes(sample_offset: u32, data: [u8; 3], timestamp: Option<MidiTimestamp>) -> Self {
         let status = data[0] & 0xF0;
         let channel = data[0] & 0x0F;
@@ -192,13 +221,35 @@ impl MidiEvent {
         }
     }
 
+    /// The MIDI channel the event is addressed to.
+    pub fn channel(&self) -> u8 {
+        match self {
+            MidiEvent::NoteOn { channel, .. }
+            | MidiEvent::NoteOff { channel, .. }
+            | MidiEvent::ControlChange { channel, .. }
+            | MidiEvent::PitchBend { channel, .. }
+            | MidiEvent::NoteExpression { channel, .. } => *channel,
+        }
+    }
+
     /// Returns the sample offset associated with this event.
     pub fn sample_offset(&self) -> u32 {
         match self {
             MidiEvent::NoteOn { sample_offset, .. }
             | MidiEvent::NoteOff { sample_offset, .. }
             | MidiEvent::ControlChange { sample_offset, .. }
-            | MidiEvent::PitchBend { sample_offset, .. } => *sample_offset,
+            | MidiEvent::PitchBend { sample_offset, .. }
+            | MidiEvent::NoteExpression { sample_offset, .. } => *sample_offset,
+        }
+    }
+
+    pub fn set_sample_offset(&mut self, offset: u32) {
+        match self {
+            MidiEvent::NoteOn { sample_offset, .. }
+            | MidiEvent::NoteOff { sample_offset, .. }
+            | MidiEvent::ControlChange { sample_offset, .. }
+            | MidiEvent::PitchBend { sample_offset, .. }
+            | MidiEvent::NoteExpression { sample_offset, .. } => *sample_offset = offset,
         }
     }
 }
@@ -214,6 +265,35 @@ pub enum PluginError {
     NotPrepared,
 }
 
+/// Musical position of the block a processor is about to render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessTransport {
+    pub tempo: Tempo,
+    pub time_signature: TimeSignature,
+    /// Playhead at the first frame of the block.
+    pub sample_position: u64,
+    /// Quarter notes elapsed at `sample_position`, across tempo changes.
+    pub beat_position: f64,
+    pub is_playing: bool,
+    pub is_recording: bool,
+    /// The active loop, when the host is looping.
+    pub loop_region: Option<LoopRegion>,
+}
+
+impl Default for ProcessTransport {
+    fn default() -> Self {
+        Self {
+            tempo: Tempo::default(),
+            time_signature: TimeSignature::default(),
+            sample_position: 0,
+            beat_position: 0.0,
+            is_playing: false,
+            is_recording: false,
+            loop_region: None,
+        }
+    }
+}
+
 /// Primary audio processor trait implemented by native plugins.
 pub trait AudioProcessor: Send + Sync {
     fn descriptor(&self) -> PluginDescriptor;
@@ -233,6 +313,46 @@ pub trait AudioProcessor: Send + Sync {
         0
     }
 
+    /// Number of auxiliary input busses, such as a sidechain key, that the
+    /// processor can read in [`process_with_aux`](Self::process_with_aux).
+    /// The engine feeds the first from the node's
+    /// [`SIDECHAIN_PIN`](crate::SIDECHAIN_PIN).
+    fn aux_input_count(&self) -> usize {
+        0
+    }
+
+    /// Processes `buffer` with one entry of `aux` per auxiliary input that
+    /// the host has routed, knowing where the transport stands. Unrouted
+    /// inputs may be missing from the end of `aux`. The default ignores
+    /// auxiliary audio entirely and calls
+    /// [`process_with_context`](Self::process_with_context).
+    fn process_with_aux(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        aux: &[&AudioBuffer],
+        ctx: &ProcessTransport,
+    ) -> anyhow::Result<()> {
+        let _ = aux;
+        self.process_with_context(buffer, ctx)
+    }
+
+    /// Processes `buffer` knowing where the transport stands. The engine
+    /// calls this every block that no sidechain is routed to; the default ignores the transport and calls
+    /// [`process`](Self::process).
+    fn process_with_context(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        ctx: &ProcessTransport,
+    ) -> anyhow::Result<()> {
+        let _ = ctx;
+        self.process(buffer)
+    }
+
+    /// Receives the project tempo after `prepare` and whenever it changes,
+    /// before the next block is processed. Tempo-synced processors derive
+    /// their timing from it; the default ignores it.
+    fn set_tempo(&mut self, _tempo: Tempo) {}
+
     /// Allows processors to consume queued MIDI events. The default
     /// implementation ignores incoming data which keeps existing
     /// processors backwards compatible without any additional changes.
@@ -240,6 +360,26 @@ pub trait AudioProcessor: Send + Sync {
         Ok(())
     }
 
+    /// Consumes the block's MIDI like [`process_midi`](Self::process_midi)
+    /// and lets the processor emit events of its own into `output`, with
+    /// offsets relative to the start of the block. The engine sends them
+    /// along the node's MIDI output, delayed by
+    /// [`latency_samples`](Self::latency_samples); offsets past the end of
+    /// the block arrive in later blocks. Called every block, before
+    /// `process`. The default forwards non-empty input to `process_midi`
+    /// and emits nothing.
+    fn process_midi_with_output(
+        &mut self,
+        events: &[MidiEvent],
+        output: &mut Vec<MidiEvent>,
+    ) -> anyhow::Result<()> {
+        let _ = output;
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.process_midi(events)
+    }
+
     /// Receives automation changes with sample accurate timing information.
     /// The engine guarantees that offsets never exceed the current audio block
     /// length.
@@ -251,6 +391,18 @@ pub trait AudioProcessor: Send + Sync {
     ) -> anyhow::Result<()> {
         Ok(())
     }
+
+    /// Opaque state saved with the project. Processors that keep none
+    /// return an empty blob, which is the default.
+    fn save_state(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    /// Restores a blob returned by [`save_state`](Self::save_state). The
+    /// default ignores it.
+    fn load_state(&mut self, _data: &[u8]) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
 /// Trait for plugins capable of consuming MIDI events.
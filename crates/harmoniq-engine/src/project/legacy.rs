@@ -0,0 +1,146 @@
+//! Layouts of [`ProjectState`] saved by earlier project versions.
+//!
+//! Bincode payloads are positional, so a field added to any serialized type
+//! changes the layout even when it has a serde default. Every such change
+//! bumps [`CURRENT_VERSION`](super::schema::CURRENT_VERSION) and freezes the
+//! previous layout here, so older binary payloads decode with the structs
+//! they were written with and are converted afterwards.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::state::{ArrangementState, AutomationState, ProjectState};
+use crate::mixer::{
+    MixerAuxSendState, MixerAuxState, MixerBusState, MixerInsertState, MixerMasterState,
+    MixerState, MixerTargetState, MixerTrackState, MixerTrackType,
+};
+
+/// [`ProjectState`] as saved by versions 3 and 4, before mixer inserts kept
+/// plug-in parameter ids and state.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectStateV4 {
+    pub arrangement: ArrangementState,
+    pub mixer: MixerStateV4,
+    pub automation: AutomationState,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MixerStateV4 {
+    pub tracks: Vec<MixerTrackStateV4>,
+    pub buses: Vec<MixerBusStateV4>,
+    pub auxes: Vec<MixerAuxState>,
+    pub master: MixerMasterState,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MixerTrackStateV4 {
+    pub name: String,
+    pub fader_db: f32,
+    pub width: f32,
+    pub phase_invert: bool,
+    pub pan: f32,
+    pub mute: bool,
+    pub solo: bool,
+    pub record_arm: bool,
+    pub monitor: bool,
+    pub track_type: MixerTrackType,
+    pub input_bus: Option<String>,
+    pub output_bus: Option<String>,
+    pub target: MixerTargetState,
+    pub aux_sends: Vec<MixerAuxSendState>,
+    pub pre_inserts: Vec<MixerInsertStateV4>,
+    pub post_inserts: Vec<MixerInsertStateV4>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MixerBusStateV4 {
+    pub name: String,
+    pub fader_db: f32,
+    pub width: f32,
+    pub phase_invert: bool,
+    pub pan: f32,
+    pub mute: bool,
+    pub solo: bool,
+    pub aux_sends: Vec<MixerAuxSendState>,
+    pub post_inserts: Vec<MixerInsertStateV4>,
+    pub target: MixerTargetState,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MixerInsertStateV4 {
+    pub id: Option<String>,
+    pub bypassed: bool,
+}
+
+fn inserts(inserts: Vec<MixerInsertStateV4>) -> Vec<MixerInsertState> {
+    inserts
+        .into_iter()
+        .map(|insert| MixerInsertState {
+            id: insert.id,
+            bypassed: insert.bypassed,
+            ..MixerInsertState::default()
+        })
+        .collect()
+}
+
+impl From<MixerTrackStateV4> for MixerTrackState {
+    fn from(track: MixerTrackStateV4) -> Self {
+        Self {
+            name: track.name,
+            fader_db: track.fader_db,
+            width: track.width,
+            phase_invert: track.phase_invert,
+            pan: track.pan,
+            mute: track.mute,
+            solo: track.solo,
+            record_arm: track.record_arm,
+            monitor: track.monitor,
+            track_type: track.track_type,
+            input_bus: track.input_bus,
+            output_bus: track.output_bus,
+            target: track.target,
+            aux_sends: track.aux_sends,
+            pre_inserts: inserts(track.pre_inserts),
+            post_inserts: inserts(track.post_inserts),
+        }
+    }
+}
+
+impl From<MixerBusStateV4> for MixerBusState {
+    fn from(bus: MixerBusStateV4) -> Self {
+        Self {
+            name: bus.name,
+            fader_db: bus.fader_db,
+            width: bus.width,
+            phase_invert: bus.phase_invert,
+            pan: bus.pan,
+            mute: bus.mute,
+            solo: bus.solo,
+            aux_sends: bus.aux_sends,
+            post_inserts: inserts(bus.post_inserts),
+            target: bus.target,
+        }
+    }
+}
+
+/// Inserts open without parameter ids or state, as if the plug-ins were
+/// freshly added.
+impl From<ProjectStateV4> for ProjectState {
+    fn from(state: ProjectStateV4) -> Self {
+        let MixerStateV4 {
+            tracks,
+            buses,
+            auxes,
+            master,
+        } = state.mixer;
+        Self {
+            arrangement: state.arrangement,
+            mixer: MixerState {
+                tracks: tracks.into_iter().map(Into::into).collect(),
+                buses: buses.into_iter().map(Into::into).collect(),
+                auxes,
+                master,
+            },
+            automation: state.automation,
+        }
+    }
+}
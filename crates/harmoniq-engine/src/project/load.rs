@@ -3,20 +3,27 @@ use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use serde::de::DeserializeOwned;
 use thiserror::Error;
 
 use super::migrate;
-use super::save::autosave_path;
+use super::save::{autosave_path, recovery_snapshots};
 use super::schema::{
-    MediaAsset, MediaChecksum, ProjectDocument, ProjectMediaEntryV2, ProjectV1, ProjectV2,
-    ProjectV3, PROJECT_MAGIC,
+    MediaAsset, MediaChecksum, ProjectDocument, ProjectMediaEntryV2, ProjectV1, ProjectV3,
+    ProjectV4, ProjectV5, BINCODE_MAGIC, CURRENT_VERSION, PROJECT_MAGIC, ZSTD_BINCODE_MAGIC,
 };
 
 pub type RelinkerCallback<'a> = dyn for<'r> FnMut(RelinkRequest<'r>) -> Option<PathBuf> + 'a;
 
+/// Built from [`LoadOptions::default`] and the `with_*` methods, so new
+/// options can be added without breaking callers.
+#[non_exhaustive]
 pub struct LoadOptions<'a> {
     pub prefer_autosave: bool,
     pub relinker: Option<Box<RelinkerCallback<'a>>>,
+    /// Rate the project is converted to when it was saved at another one,
+    /// usually the device rate. `None` keeps the saved rate.
+    pub sample_rate: Option<f32>,
 }
 
 impl<'a> Default for LoadOptions<'a> {
@@ -24,10 +31,31 @@ impl<'a> Default for LoadOptions<'a> {
         Self {
             prefer_autosave: true,
             relinker: None,
+            sample_rate: None,
         }
     }
 }
 
+impl<'a> LoadOptions<'a> {
+    pub fn with_prefer_autosave(mut self, prefer_autosave: bool) -> Self {
+        self.prefer_autosave = prefer_autosave;
+        self
+    }
+
+    pub fn with_relinker<F>(mut self, relinker: F) -> Self
+    where
+        F: for<'r> FnMut(RelinkRequest<'r>) -> Option<PathBuf> + 'a,
+    {
+        self.relinker = Some(Box::new(relinker));
+        self
+    }
+
+    pub fn with_sample_rate(mut self, sample_rate: f32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+}
+
 #[derive(Debug)]
 pub struct RelinkRequest<'a> {
     pub id: &'a str,
@@ -49,6 +77,8 @@ pub enum LoadError {
     Io(#[from] io::Error),
     #[error("failed to parse project file: {0}")]
     Parse(#[from] serde_json::Error),
+    #[error("failed to decode binary project payload: {0}")]
+    Decode(String),
     #[error("unsupported project version {0}")]
     UnsupportedVersion(u32),
     #[error("corrupt project file: {0}")]
@@ -67,8 +97,7 @@ pub fn load_project(path: &Path, mut options: LoadOptions<'_>) -> Result<LoadRep
 
     if options.prefer_autosave && should_use_autosave(path, &autosave) {
         if autosave.exists() {
-            let document =
-                load_from_file(&autosave, path, base_dir, options.relinker.as_deref_mut())?;
+            let document = load_from_file(&autosave, path, base_dir, &mut options)?;
             return Ok(LoadReport {
                 document,
                 recovered_from_autosave: true,
@@ -77,7 +106,7 @@ pub fn load_project(path: &Path, mut options: LoadOptions<'_>) -> Result<LoadRep
         }
     }
 
-    let document = load_from_file(path, path, base_dir, options.relinker.as_deref_mut())?;
+    let document = load_from_file(path, path, base_dir, &mut options)?;
     Ok(LoadReport {
         document,
         recovered_from_autosave: false,
@@ -85,6 +114,31 @@ pub fn load_project(path: &Path, mut options: LoadOptions<'_>) -> Result<LoadRep
     })
 }
 
+/// Loads the newest snapshot in `dir` written by
+/// [`save_recovery_snapshot`](super::save::save_recovery_snapshot), skipping
+/// any that cannot be read, and returns `None` if none can. Missing media is
+/// not skipped: the newest snapshot is still the right one to recover, so
+/// the error is returned for the caller to relink.
+pub fn latest_recovery_snapshot(
+    dir: &Path,
+    mut options: LoadOptions<'_>,
+) -> Result<Option<LoadReport>, LoadError> {
+    for path in recovery_snapshots(dir)?.into_iter().rev() {
+        match load_from_file(&path, &path, dir, &mut options) {
+            Ok(document) => {
+                return Ok(Some(LoadReport {
+                    document,
+                    recovered_from_autosave: true,
+                    source_path: path,
+                }))
+            }
+            Err(err @ LoadError::MissingMedia { .. }) => return Err(err),
+            Err(err) => log::warn!("skipping unreadable recovery snapshot {path:?}: {err}"),
+        }
+    }
+    Ok(None)
+}
+
 fn should_use_autosave(primary: &Path, autosave: &Path) -> bool {
     if !autosave.exists() {
         return false;
@@ -106,12 +160,21 @@ fn load_from_file(
     path: &Path,
     source_path: &Path,
     base_dir: &Path,
-    relinker: Option<&mut RelinkerCallback<'_>>,
+    options: &mut LoadOptions<'_>,
 ) -> Result<ProjectDocument, LoadError> {
     let mut buffer = Vec::new();
     let mut file = File::open(path)?;
     file.read_to_end(&mut buffer)?;
-    parse_buffer(&buffer, source_path, base_dir, relinker)
+    let mut document = parse_buffer(
+        &buffer,
+        source_path,
+        base_dir,
+        options.relinker.as_deref_mut(),
+    )?;
+    if let Some(sample_rate) = options.sample_rate {
+        document.resample_to(sample_rate);
+    }
+    Ok(document)
 }
 
 fn parse_buffer(
@@ -146,14 +209,18 @@ fn parse_archive(
     }
 
     let version = u32::from_le_bytes(buffer[4..8].try_into().unwrap());
-    let json_len = u32::from_le_bytes(buffer[8..12].try_into().unwrap()) as usize;
+    let payload_len = u32::from_le_bytes(buffer[8..12].try_into().unwrap()) as usize;
     let media_len = u64::from_le_bytes(buffer[12..20].try_into().unwrap()) as usize;
-    if buffer.len() < 20 + json_len + media_len {
+    let end = 20usize
+        .checked_add(payload_len)
+        .and_then(|end| end.checked_add(media_len))
+        .ok_or(LoadError::Corrupt("payload truncated"))?;
+    if buffer.len() < end {
         return Err(LoadError::Corrupt("payload truncated"));
     }
 
-    let json_slice = &buffer[20..20 + json_len];
-    let media_slice = &buffer[20 + json_len..20 + json_len + media_len];
+    let payload = &buffer[20..20 + payload_len];
+    let media_slice = &buffer[20 + payload_len..end];
     let mut load_media = |entries: Vec<ProjectMediaEntryV2>| {
         let mut media_assets = Vec::with_capacity(entries.len());
         for entry in entries {
@@ -225,17 +292,46 @@ fn parse_archive(
         Ok(media_assets)
     };
 
-    match version {
-        2 => {
-            let project: ProjectV2 = serde_json::from_slice(json_slice)?;
-            let media_assets = load_media(project.media)?;
-            Ok(ProjectDocument::new(project.metadata, media_assets))
-        }
-        3 => {
-            let project: ProjectV3 = serde_json::from_slice(json_slice)?;
-            let media_assets = load_media(project.media)?;
-            Ok(ProjectDocument::new(project.metadata, media_assets).with_state(project.state))
-        }
-        other => Err(LoadError::UnsupportedVersion(other)),
+    if !(2..=CURRENT_VERSION).contains(&version) {
+        return Err(LoadError::UnsupportedVersion(version));
     }
+    let project = decode_payload(payload, version)?;
+    let media_assets = load_media(project.media)?;
+    let mut document = ProjectDocument::new(project.metadata, media_assets)
+        .with_state(project.state)
+        .with_samples(project.samples)
+        .with_tempo_map(project.tempo_map);
+    // Missing samples do not fail the load; see `ProjectDocument::missing_samples`.
+    document.relink_samples(base_dir, &[]);
+    Ok(document)
+}
+
+/// Decodes a payload written by any [`ProjectFormat`](super::save::ProjectFormat),
+/// picked by its prefix. Unprefixed payloads are JSON, which covers every
+/// project saved before binary formats existed; those are parsed to a raw
+/// value first and brought up to the current schema by
+/// [`migrate::upgrade`]. Binary payloads are positional and cannot be
+/// migrated as raw values, so each binary version is decoded with its own
+/// layout and converted.
+fn decode_payload(payload: &[u8], version: u32) -> Result<ProjectV5, LoadError> {
+    let binary = |encoded: &[u8]| match version {
+        3 => decode_bincode::<ProjectV3>(encoded).map(|v3| ProjectV4::from(v3).into()),
+        4 => decode_bincode::<ProjectV4>(encoded).map(ProjectV5::from),
+        CURRENT_VERSION => decode_bincode(encoded),
+        _ => Err(LoadError::UnsupportedVersion(version)),
+    };
+    if let Some(encoded) = payload.strip_prefix(&BINCODE_MAGIC) {
+        binary(encoded)
+    } else if let Some(compressed) = payload.strip_prefix(&ZSTD_BINCODE_MAGIC) {
+        binary(&zstd::stream::decode_all(compressed)?)
+    } else {
+        let project = serde_json::from_slice(payload)?;
+        let project = migrate::upgrade(project, version)
+            .map_err(|err| LoadError::Migration(err.to_string()))?;
+        Ok(serde_json::from_value(project)?)
+    }
+}
+
+fn decode_bincode<T: DeserializeOwned>(encoded: &[u8]) -> Result<T, LoadError> {
+    bincode::deserialize(encoded).map_err(|err| LoadError::Decode(err.to_string()))
 }
@@ -1,9 +1,12 @@
 use std::fs;
 use std::path::Path;
 
+use serde_json::Value;
 use thiserror::Error;
 
-use super::schema::{MediaAsset, ProjectDocument, ProjectMetadata, ProjectV1};
+use super::schema::{MediaAsset, ProjectDocument, ProjectMetadata, ProjectV1, CURRENT_VERSION};
+use crate::core::state::ProjectState;
+use crate::time::TempoMap;
 
 #[derive(Debug, Error)]
 pub enum MigrationError {
@@ -11,6 +14,71 @@ pub enum MigrationError {
     Io(#[from] std::io::Error),
     #[error("invalid project schema: {0}")]
     Invalid(&'static str),
+    #[error("no migration from project version {0}")]
+    MissingStep(u32),
+}
+
+/// Rewrites a raw project payload saved at one version into the layout of
+/// the next.
+pub type Migration = fn(&mut Value) -> Result<(), MigrationError>;
+
+/// Registered migrations, keyed by the version they upgrade from. Each step
+/// produces version `from + 1`; add a step here whenever `CURRENT_VERSION`
+/// is bumped.
+const MIGRATIONS: &[(u32, Migration)] = &[(2, v2_to_v3), (3, v3_to_v4), (4, v4_to_v5)];
+
+/// Runs every migration from `version` up to [`CURRENT_VERSION`] in order
+/// and returns the payload ready to deserialize as the current schema.
+pub fn upgrade(mut project: Value, version: u32) -> Result<Value, MigrationError> {
+    for from in version..CURRENT_VERSION {
+        let (_, migration) = MIGRATIONS
+            .iter()
+            .find(|(step, _)| *step == from)
+            .ok_or(MigrationError::MissingStep(from))?;
+        migration(&mut project)?;
+        set_version(&mut project, from + 1)?;
+    }
+    Ok(project)
+}
+
+fn set_version(project: &mut Value, version: u32) -> Result<(), MigrationError> {
+    let object = project
+        .as_object_mut()
+        .ok_or(MigrationError::Invalid("project payload is not an object"))?;
+    object.insert("version".into(), version.into());
+    Ok(())
+}
+
+/// Version 3 added the arrangement, mixer and automation state. Older
+/// projects only stored media, so they open with the default session.
+fn v2_to_v3(project: &mut Value) -> Result<(), MigrationError> {
+    let state = serde_json::to_value(ProjectState::default())
+        .map_err(|_| MigrationError::Invalid("default project state is not serializable"))?;
+    let object = project
+        .as_object_mut()
+        .ok_or(MigrationError::Invalid("project payload is not an object"))?;
+    object.entry("state").or_insert(state);
+    Ok(())
+}
+
+/// Version 4 added external sample references and the tempo map.
+fn v3_to_v4(project: &mut Value) -> Result<(), MigrationError> {
+    let tempo_map = serde_json::to_value(TempoMap::default())
+        .map_err(|_| MigrationError::Invalid("default tempo map is not serializable"))?;
+    let object = project
+        .as_object_mut()
+        .ok_or(MigrationError::Invalid("project payload is not an object"))?;
+    object
+        .entry("samples")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    object.entry("tempo_map").or_insert(tempo_map);
+    Ok(())
+}
+
+/// Version 5 added plug-in parameter ids and state to mixer inserts. Both
+/// default to empty, which serde fills in for text payloads.
+fn v4_to_v5(_project: &mut Value) -> Result<(), MigrationError> {
+    Ok(())
 }
 
 pub fn from_v1(
@@ -1,3 +1,4 @@
+pub mod legacy;
 pub mod load;
 pub mod migrate;
 pub mod save;
@@ -5,10 +6,17 @@ pub mod schema;
 
 #[cfg(any(test, feature = "fuzzing"))]
 pub use load::fuzz_parse_project;
-pub use load::{load_project, LoadError, LoadOptions, LoadReport, RelinkRequest};
-pub use migrate::MigrationError;
-pub use save::{autosave_path, save_autosave, save_project, SaveError, SaveOptions, SaveReport};
+pub use load::{
+    latest_recovery_snapshot, load_project, LoadError, LoadOptions, LoadReport, RelinkRequest,
+};
+pub use migrate::{Migration, MigrationError};
+pub use save::{
+    autosave_path, recovery_snapshots, save_autosave, save_project, save_project_with,
+    save_recovery_snapshot, AutosaveConfig, ProjectFormat, SaveError, SaveOptions, SaveReport,
+    RECOVERY_EXTENSION, RECOVERY_PREFIX,
+};
 pub use schema::{
     MediaAsset, MediaChecksum, MediaChunkDescriptor, ProjectDocument, ProjectMediaEntryV1,
-    ProjectMetadata, ProjectV1, ProjectV2, CURRENT_VERSION, MEDIA_CHUNK_SIZE, PROJECT_MAGIC,
+    ProjectMetadata, ProjectV1, ProjectV2, ProjectV3, ProjectV4, ProjectV5, SampleRef,
+    BINCODE_MAGIC, CURRENT_VERSION, MEDIA_CHUNK_SIZE, PROJECT_MAGIC, ZSTD_BINCODE_MAGIC,
 };
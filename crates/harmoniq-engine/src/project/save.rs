@@ -1,18 +1,39 @@
 use std::fs::{self, File};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use thiserror::Error;
 
 use super::schema::{
-    MediaChunkDescriptor, ProjectDocument, ProjectMediaEntryV2, ProjectV3, CURRENT_VERSION,
-    MEDIA_CHUNK_SIZE, PROJECT_MAGIC,
+    MediaChunkDescriptor, ProjectDocument, ProjectMediaEntryV2, ProjectV5, BINCODE_MAGIC,
+    CURRENT_VERSION, MEDIA_CHUNK_SIZE, PROJECT_MAGIC, ZSTD_BINCODE_MAGIC,
 };
 
+/// Zstd level used for [`ProjectFormat::ZstdBincode`].
+const ZSTD_LEVEL: i32 = 3;
+
+/// Encoding of the project payload inside the archive. Loading detects the
+/// encoding from the payload prefix, so any format can be opened without
+/// knowing how it was saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProjectFormat {
+    /// Indented JSON, readable and diffable.
+    #[default]
+    JsonPretty,
+    /// JSON without whitespace.
+    Json,
+    /// Compact binary encoding, much faster to parse for large projects.
+    Bincode,
+    /// Bincode compressed with zstd, the smallest on disk.
+    ZstdBincode,
+}
+
 #[derive(Debug, Clone)]
 pub struct SaveOptions {
     pub remove_autosave: bool,
     pub chunk_size: usize,
+    pub format: ProjectFormat,
 }
 
 impl Default for SaveOptions {
@@ -20,6 +41,7 @@ impl Default for SaveOptions {
         Self {
             remove_autosave: true,
             chunk_size: MEDIA_CHUNK_SIZE,
+            format: ProjectFormat::default(),
         }
     }
 }
@@ -38,6 +60,8 @@ pub enum SaveError {
     Io(#[from] io::Error),
     #[error("project payload too large")]
     ProjectTooLarge,
+    #[error("failed to encode project: {0}")]
+    Encode(String),
 }
 
 pub fn save_project(
@@ -48,6 +72,19 @@ pub fn save_project(
     write_archive(path, document, options, false)
 }
 
+/// Saves with the default options but an explicit payload encoding.
+pub fn save_project_with(
+    path: &Path,
+    document: &ProjectDocument,
+    format: ProjectFormat,
+) -> Result<SaveReport, SaveError> {
+    let options = SaveOptions {
+        format,
+        ..SaveOptions::default()
+    };
+    save_project(path, document, options)
+}
+
 pub fn save_autosave(path: &Path, document: &ProjectDocument) -> Result<SaveReport, SaveError> {
     let autosave_path = autosave_path(path);
     let mut options = SaveOptions::default();
@@ -55,6 +92,97 @@ pub fn save_autosave(path: &Path, document: &ProjectDocument) -> Result<SaveRepo
     write_archive(&autosave_path, document, options, true)
 }
 
+/// File name prefix of the snapshots written by [`save_recovery_snapshot`].
+pub const RECOVERY_PREFIX: &str = "recovery-";
+/// File extension of recovery snapshots.
+pub const RECOVERY_EXTENSION: &str = "hsq";
+
+/// How often the app should write recovery snapshots and how many to keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutosaveConfig {
+    pub interval: Duration,
+    pub keep: usize,
+}
+
+impl Default for AutosaveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(120),
+            keep: 5,
+        }
+    }
+}
+
+impl AutosaveConfig {
+    /// Whether a snapshot is due `elapsed` after the previous one.
+    pub fn is_due(&self, elapsed: Duration) -> bool {
+        elapsed >= self.interval
+    }
+}
+
+/// Writes a timestamped recovery snapshot into `dir` and deletes all but the
+/// `config.keep` newest. The snapshot is written to a temporary file and
+/// renamed into place, so a crash mid-save leaves the previous snapshots
+/// intact.
+pub fn save_recovery_snapshot(
+    dir: &Path,
+    document: &ProjectDocument,
+    config: &AutosaveConfig,
+) -> Result<SaveReport, SaveError> {
+    fs::create_dir_all(dir)?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis());
+    // Zero padding keeps lexical order chronological. Bump the stamp if two
+    // snapshots land in the same millisecond.
+    let mut stamp = millis;
+    let path = loop {
+        let candidate = dir.join(format!("{RECOVERY_PREFIX}{stamp:020}.{RECOVERY_EXTENSION}"));
+        if !candidate.exists() {
+            break candidate;
+        }
+        stamp += 1;
+    };
+
+    let options = SaveOptions {
+        remove_autosave: false,
+        ..SaveOptions::default()
+    };
+    let report = write_archive(&path, document, options, true)?;
+
+    let snapshots = recovery_snapshots(dir)?;
+    let excess = snapshots.len().saturating_sub(config.keep.max(1));
+    for old in &snapshots[..excess] {
+        let _ = fs::remove_file(old);
+    }
+    Ok(report)
+}
+
+/// Recovery snapshots in `dir`, oldest first.
+pub fn recovery_snapshots(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut snapshots = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(snapshots),
+        Err(err) => return Err(err),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        let is_snapshot = path
+            .extension()
+            .is_some_and(|ext| ext == RECOVERY_EXTENSION)
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(RECOVERY_PREFIX));
+        if is_snapshot {
+            snapshots.push(path);
+        }
+    }
+    snapshots.sort();
+    Ok(snapshots)
+}
+
 pub fn autosave_path(path: &Path) -> PathBuf {
     if path.extension().is_some() {
         let mut new = path.as_os_str().to_owned();
@@ -111,19 +239,21 @@ fn write_archive(
         });
     }
 
-    let project = ProjectV3::new(document.metadata.clone(), entries, document.state.clone());
-    let json = serde_json::to_vec_pretty(&project).map_err(|_| SaveError::ProjectTooLarge)?;
+    let project = ProjectV5::new(document.metadata.clone(), entries, document.state.clone())
+        .with_samples(document.samples.clone())
+        .with_tempo_map(document.tempo_map.clone());
+    let payload = encode_payload(&project, options.format)?;
 
-    let json_len = u32::try_from(json.len()).map_err(|_| SaveError::ProjectTooLarge)?;
+    let payload_len = u32::try_from(payload.len()).map_err(|_| SaveError::ProjectTooLarge)?;
     let media_len = u64::try_from(chunk_data.len()).map_err(|_| SaveError::ProjectTooLarge)?;
 
     let tmp_path = path.with_extension("tmp");
     let mut file = File::create(&tmp_path)?;
     file.write_all(&PROJECT_MAGIC)?;
     file.write_all(&CURRENT_VERSION.to_le_bytes())?;
-    file.write_all(&json_len.to_le_bytes())?;
+    file.write_all(&payload_len.to_le_bytes())?;
     file.write_all(&media_len.to_le_bytes())?;
-    file.write_all(&json)?;
+    file.write_all(&payload)?;
     file.write_all(&chunk_data)?;
     file.flush()?;
     drop(file);
@@ -139,8 +269,32 @@ fn write_archive(
 
     Ok(SaveReport {
         path: path.to_path_buf(),
-        bytes_written: (json.len() + chunk_data.len() + 20) as u64,
+        bytes_written: (payload.len() + chunk_data.len() + 20) as u64,
         media_bytes: media_len,
         autosave,
     })
 }
+
+fn encode_payload(project: &ProjectV5, format: ProjectFormat) -> Result<Vec<u8>, SaveError> {
+    match format {
+        ProjectFormat::JsonPretty => {
+            serde_json::to_vec_pretty(project).map_err(|err| SaveError::Encode(err.to_string()))
+        }
+        ProjectFormat::Json => {
+            serde_json::to_vec(project).map_err(|err| SaveError::Encode(err.to_string()))
+        }
+        ProjectFormat::Bincode => {
+            let mut payload = BINCODE_MAGIC.to_vec();
+            bincode::serialize_into(&mut payload, project)
+                .map_err(|err| SaveError::Encode(err.to_string()))?;
+            Ok(payload)
+        }
+        ProjectFormat::ZstdBincode => {
+            let encoded =
+                bincode::serialize(project).map_err(|err| SaveError::Encode(err.to_string()))?;
+            let mut payload = ZSTD_BINCODE_MAGIC.to_vec();
+            payload.extend(zstd::bulk::compress(&encoded, ZSTD_LEVEL)?);
+            Ok(payload)
+        }
+    }
+}
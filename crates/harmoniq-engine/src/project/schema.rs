@@ -1,12 +1,24 @@
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use crate::core::state::ProjectState;
+use super::legacy::ProjectStateV4;
+use crate::core::state::{ArrangementClip, ProjectState};
+use crate::media::loader::{DecodedAudio, MediaLoader, MediaLoaderError};
+use crate::time::TempoMap;
 
 pub const PROJECT_MAGIC: [u8; 4] = *b"HSQ2";
-pub const CURRENT_VERSION: u32 = 3;
+/// Prefix of a bincode-encoded project payload. Payloads without a known
+/// prefix are JSON.
+pub const BINCODE_MAGIC: [u8; 4] = *b"HQBC";
+/// Prefix of a zstd-compressed bincode project payload.
+pub const ZSTD_BINCODE_MAGIC: [u8; 4] = *b"HQZB";
+/// Bumped whenever the layout of a serialized type changes, including added
+/// fields with serde defaults, since binary payloads are positional. See
+/// [`legacy`](super::legacy).
+pub const CURRENT_VERSION: u32 = 5;
 pub const MEDIA_CHUNK_SIZE: usize = 64 * 1024;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -62,6 +74,31 @@ impl MediaAsset {
         self
     }
 
+    /// Converts the embedded audio to `target_rate`, re-encoded as 32-bit
+    /// float WAV, and refreshes the checksum. Returns whether the data
+    /// changed. The file at `resolved_path` is not rewritten.
+    pub fn resample_to(&mut self, target_rate: f32) -> Result<bool, MediaLoaderError> {
+        let decoded = MediaLoader::new()
+            .load_from_reader(Cursor::new(self.data.clone()), Some(&self.relative_path))?;
+        if target_rate <= 0.0 || decoded.channels.is_empty() {
+            return Ok(false);
+        }
+        let target = target_rate.round() as u32;
+        if decoded.sample_rate == target {
+            return Ok(false);
+        }
+        let clip = decoded.to_clip().resample_to(target as f32);
+        let audio = DecodedAudio {
+            sample_rate: target,
+            channels: clip.cloned_channels(),
+        };
+        let mut encoded = Cursor::new(Vec::new());
+        MediaLoader::new().encode_wav(&audio, &mut encoded)?;
+        self.data = encoded.into_inner();
+        self.checksum = MediaChecksum::from_data(&self.data);
+        Ok(true)
+    }
+
     pub fn update_relative_path(&mut self, base: &Path, new_path: PathBuf) {
         if new_path.is_absolute() {
             self.resolved_path = Some(new_path.clone());
@@ -78,6 +115,100 @@ impl MediaAsset {
     }
 }
 
+/// An external sample file referenced by clips but not embedded in the
+/// project archive. Both paths are stored so the file can be found again
+/// after the project moves: the project-relative path when the project and
+/// its samples move together, the absolute path when only the project moves.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SampleRef {
+    pub id: String,
+    pub absolute_path: PathBuf,
+    pub relative_path: PathBuf,
+    /// Where the file was found by the last relink; `None` while missing.
+    #[serde(skip)]
+    pub resolved_path: Option<PathBuf>,
+}
+
+impl SampleRef {
+    /// References `path`, stored relative to `base` when it lies inside it.
+    pub fn new(id: impl Into<String>, path: impl Into<PathBuf>, base: &Path) -> Self {
+        let path = path.into();
+        let absolute_path = if path.is_absolute() {
+            path
+        } else {
+            base.join(path)
+        };
+        let relative_path = absolute_path
+            .strip_prefix(base)
+            .map_or_else(|_| absolute_path.clone(), Path::to_path_buf);
+        let resolved_path = absolute_path.exists().then(|| absolute_path.clone());
+        Self {
+            id: id.into(),
+            absolute_path,
+            relative_path,
+            resolved_path,
+        }
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.resolved_path.is_some()
+    }
+
+    /// Looks for the file relative to `base` first, then at the stored
+    /// absolute path, then by file name anywhere below `search_dirs`. Both
+    /// stored paths are updated when the file is found somewhere new.
+    pub fn relink(&mut self, base: &Path, search_dirs: &[PathBuf]) -> bool {
+        let relative = base.join(&self.relative_path);
+        let found = if relative.is_file() {
+            Some(relative)
+        } else if self.absolute_path.is_file() {
+            Some(self.absolute_path.clone())
+        } else {
+            self.absolute_path
+                .file_name()
+                .or_else(|| self.relative_path.file_name())
+                .and_then(|name| {
+                    search_dirs
+                        .iter()
+                        .find_map(|dir| find_file(dir, name, SAMPLE_SEARCH_DEPTH))
+                })
+        };
+        match found {
+            Some(path) => {
+                *self = Self::new(std::mem::take(&mut self.id), path, base);
+                true
+            }
+            None => {
+                self.resolved_path = None;
+                false
+            }
+        }
+    }
+}
+
+/// How many directory levels [`SampleRef::relink`] descends into each
+/// search directory.
+const SAMPLE_SEARCH_DEPTH: usize = 8;
+
+fn find_file(dir: &Path, name: &std::ffi::OsStr, depth: usize) -> Option<PathBuf> {
+    let candidate = dir.join(name);
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+    if depth == 0 {
+        return None;
+    }
+    let mut subdirs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    subdirs.sort();
+    subdirs
+        .iter()
+        .find_map(|subdir| find_file(subdir, name, depth - 1))
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MediaChunkDescriptor {
     pub offset: u64,
@@ -119,6 +250,8 @@ pub struct ProjectDocument {
     pub version: u32,
     pub metadata: ProjectMetadata,
     pub media: Vec<MediaAsset>,
+    pub samples: Vec<SampleRef>,
+    pub tempo_map: TempoMap,
     pub state: ProjectState,
 }
 
@@ -128,6 +261,8 @@ impl ProjectDocument {
             version: CURRENT_VERSION,
             metadata,
             media,
+            samples: Vec::new(),
+            tempo_map: TempoMap::default(),
             state: ProjectState::default(),
         }
     }
@@ -137,22 +272,77 @@ impl ProjectDocument {
         self
     }
 
+    /// Converts the document to `target_rate` and returns the conversion
+    /// ratio. Tempo events move to the same time at the new rate; clips and
+    /// automation are stored in beats, so they follow the tempo map.
+    /// Embedded audio media is resampled with [`MediaAsset::resample_to`];
+    /// media that does not decode as audio is kept as saved. Sample-based
+    /// runtime data built from the document, such as a
+    /// [`Timeline`](crate::Timeline), is converted separately.
+    pub fn resample_to(&mut self, target_rate: f32) -> f64 {
+        let source_rate = self.metadata.sample_rate;
+        if target_rate <= 0.0 || source_rate <= 0.0 || target_rate == source_rate {
+            return 1.0;
+        }
+        self.tempo_map = self.tempo_map.resampled(source_rate, target_rate);
+        for asset in &mut self.media {
+            if let Err(err) = asset.resample_to(target_rate) {
+                log::debug!("keeping media {} at its saved rate: {err}", asset.id);
+            }
+        }
+        self.metadata.sample_rate = target_rate;
+        target_rate as f64 / source_rate as f64
+    }
+
+    pub fn with_tempo_map(mut self, tempo_map: TempoMap) -> Self {
+        self.tempo_map = tempo_map;
+        self
+    }
+
     pub fn with_state(mut self, state: ProjectState) -> Self {
         self.state = state;
         self.version = CURRENT_VERSION;
         self
     }
+
+    pub fn with_samples(mut self, samples: Vec<SampleRef>) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Resolves every sample reference against the project directory `base`,
+    /// falling back to a search of `search_dirs` by file name. Returns how
+    /// many references are still missing.
+    pub fn relink_samples(&mut self, base: &Path, search_dirs: &[PathBuf]) -> usize {
+        for sample in &mut self.samples {
+            sample.relink(base, search_dirs);
+        }
+        self.missing_samples().count()
+    }
+
+    /// Sample references the last relink could not find.
+    pub fn missing_samples(&self) -> impl Iterator<Item = &SampleRef> {
+        self.samples.iter().filter(|sample| !sample.is_resolved())
+    }
+
+    /// The external sample a clip plays, if its media id names one.
+    pub fn sample_for_clip(&self, clip: &ArrangementClip) -> Option<&SampleRef> {
+        let id = clip.media.as_deref()?;
+        self.samples.iter().find(|sample| sample.id == id)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct ProjectV3 {
+pub struct ProjectV5 {
     pub version: u32,
     pub metadata: ProjectMetadata,
     pub media: Vec<ProjectMediaEntryV2>,
     pub state: ProjectState,
+    pub samples: Vec<SampleRef>,
+    pub tempo_map: TempoMap,
 }
 
-impl ProjectV3 {
+impl ProjectV5 {
     pub fn new(
         metadata: ProjectMetadata,
         media: Vec<ProjectMediaEntryV2>,
@@ -163,8 +353,67 @@ impl ProjectV3 {
             metadata,
             media,
             state,
+            samples: Vec::new(),
+            tempo_map: TempoMap::default(),
         }
     }
+
+    pub fn with_samples(mut self, samples: Vec<SampleRef>) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_tempo_map(mut self, tempo_map: TempoMap) -> Self {
+        self.tempo_map = tempo_map;
+        self
+    }
+}
+
+/// Version 5 added plug-in parameter ids and state to mixer inserts.
+impl From<ProjectV4> for ProjectV5 {
+    fn from(project: ProjectV4) -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            metadata: project.metadata,
+            media: project.media,
+            state: project.state.into(),
+            samples: project.samples,
+            tempo_map: project.tempo_map,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectV4 {
+    pub version: u32,
+    pub metadata: ProjectMetadata,
+    pub media: Vec<ProjectMediaEntryV2>,
+    pub state: ProjectStateV4,
+    pub samples: Vec<SampleRef>,
+    pub tempo_map: TempoMap,
+}
+
+/// Version 4 added external sample references and the tempo map. Older
+/// projects have no samples and play at the default tempo.
+impl From<ProjectV3> for ProjectV4 {
+    fn from(project: ProjectV3) -> Self {
+        Self {
+            version: 4,
+            metadata: project.metadata,
+            media: project.media,
+            state: project.state,
+            samples: Vec::new(),
+            tempo_map: TempoMap::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectV3 {
+    pub version: u32,
+    pub metadata: ProjectMetadata,
+    pub media: Vec<ProjectMediaEntryV2>,
+    pub state: ProjectStateV4,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -1,8 +1,9 @@
 use std::cmp;
 use std::convert::TryFrom;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
@@ -14,6 +15,200 @@ use crate::{cpu_pinning, AudioBuffer, BufferConfig, EngineCommandQueue, Harmoniq
 
 const DEFAULT_QUEUE_DEPTH: usize = 3;
 
+/// Audio interface reported by the default host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub name: String,
+    pub input_channels: u16,
+    pub output_channels: u16,
+    pub is_default_input: bool,
+    pub is_default_output: bool,
+}
+
+/// Lists the devices of the default host with their channel capabilities.
+pub fn list_devices() -> anyhow::Result<Vec<DeviceInfo>> {
+    let host = cpal::default_host();
+    let default_input = host.default_input_device().and_then(|d| d.name().ok());
+    let default_output = host.default_output_device().and_then(|d| d.name().ok());
+    let mut devices = Vec::new();
+    for device in host
+        .devices()
+        .context("failed to enumerate audio devices")?
+    {
+        let Ok(name) = device.name() else {
+            continue;
+        };
+        let input_channels = device
+            .supported_input_configs()
+            .map(|configs| configs.map(|range| range.channels()).max().unwrap_or(0))
+            .unwrap_or(0);
+        let output_channels = device
+            .supported_output_configs()
+            .map(|configs| configs.map(|range| range.channels()).max().unwrap_or(0))
+            .unwrap_or(0);
+        devices.push(DeviceInfo {
+            is_default_input: default_input.as_deref() == Some(name.as_str()),
+            is_default_output: default_output.as_deref() == Some(name.as_str()),
+            name,
+            input_channels,
+            output_channels,
+        });
+    }
+    Ok(devices)
+}
+
+/// Change reported by [`watch_devices`]. Devices are identified by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceEvent {
+    Added(DeviceInfo),
+    Removed(String),
+    /// The host's default output device changed to the named device.
+    DefaultChanged(String),
+}
+
+/// Background device poller returned by [`watch_devices`]. Dropping it stops
+/// the polling thread.
+pub struct DeviceWatcher {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for DeviceWatcher {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            if let Err(err) = thread.join() {
+                tracing::error!(?err, "failed to join device watcher thread");
+            }
+        }
+    }
+}
+
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Polls the default host's device list on a background thread and reports
+/// devices that appear or disappear and changes of the default output, so a
+/// caller can reopen its stream when an interface is unplugged.
+pub fn watch_devices(callback: Box<dyn Fn(DeviceEvent) + Send>) -> anyhow::Result<DeviceWatcher> {
+    let mut known = list_devices()?;
+    let running = Arc::new(AtomicBool::new(true));
+    let thread = {
+        let running = Arc::clone(&running);
+        thread::Builder::new()
+            .name("harmoniq-device-watcher".into())
+            .spawn(move || {
+                while running.load(Ordering::Relaxed) {
+                    thread::park_timeout(DEVICE_POLL_INTERVAL);
+                    if !running.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    match list_devices() {
+                        Ok(current) => {
+                            for event in diff_devices(&known, &current) {
+                                callback(event);
+                            }
+                            known = current;
+                        }
+                        Err(err) => tracing::warn!(?err, "failed to poll audio devices"),
+                    }
+                }
+            })
+            .context("failed to spawn device watcher thread")?
+    };
+    Ok(DeviceWatcher {
+        running,
+        thread: Some(thread),
+    })
+}
+
+fn diff_devices(previous: &[DeviceInfo], current: &[DeviceInfo]) -> Vec<DeviceEvent> {
+    let mut events = Vec::new();
+    for device in previous {
+        if !current.iter().any(|next| next.name == device.name) {
+            events.push(DeviceEvent::Removed(device.name.clone()));
+        }
+    }
+    for device in current {
+        if !previous.iter().any(|prev| prev.name == device.name) {
+            events.push(DeviceEvent::Added(device.clone()));
+        }
+    }
+    let default_output = |devices: &[DeviceInfo]| {
+        devices
+            .iter()
+            .find(|device| device.is_default_output)
+            .map(|device| device.name.clone())
+    };
+    if let Some(name) = default_output(current) {
+        if default_output(previous).as_ref() != Some(&name) {
+            events.push(DeviceEvent::DefaultChanged(name));
+        }
+    }
+    events
+}
+
+/// Stream statistics returned by [`EngineHandle::stream_metrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamMetrics {
+    /// Output callbacks run since the stream started.
+    pub callbacks: u64,
+    /// Blocks the render thread took longer to process than the audio they
+    /// hold, which must eventually starve the output.
+    pub overruns: u64,
+    /// Callbacks that ran out of rendered audio and played silence.
+    pub underruns: u64,
+    /// Longest callback so far, in microseconds.
+    pub max_callback_us: u32,
+}
+
+impl StreamMetrics {
+    /// Overruns and underruns together, as reported by `RtEvent::Xrun`.
+    pub fn xruns(&self) -> u64 {
+        self.overruns + self.underruns
+    }
+}
+
+/// Lock-free counters behind [`StreamMetrics`], written by the output
+/// callback and, for overruns, the render thread.
+#[derive(Default)]
+struct CallbackStats {
+    callbacks: AtomicU64,
+    overruns: AtomicU64,
+    underruns: AtomicU64,
+    max_callback_us: AtomicU32,
+}
+
+/// Frame counters advanced by the output and input callbacks. Both streams
+/// run from the same sample clock, so the difference tells consumers how
+/// far captured audio is ahead of playback.
+///
+/// The callbacks also publish what the devices actually negotiated: the
+/// frames delivered per callback and the latency between the callback and
+/// the moment audio reaches or left the hardware.
+#[derive(Default)]
+struct StreamClock {
+    output_frames: AtomicU64,
+    input_frames: AtomicU64,
+    output_callback_frames: AtomicU32,
+    output_latency_frames: AtomicU32,
+    input_latency_frames: AtomicU32,
+    stats: CallbackStats,
+}
+
+/// Captured input shared between the input callback and the handle.
+struct InputCapture {
+    queue: ArrayQueue<f32>,
+    channels: usize,
+}
+
+/// Output and input streams opened by [`start_realtime_duplex`]. Dropping
+/// them stops both callbacks.
+pub struct DuplexStreams {
+    pub output: Stream,
+    pub input: Stream,
+}
+
 /// Handle returned by [`start_realtime`] for controlling the running engine.
 pub struct EngineHandle {
     engine: Arc<Mutex<HarmoniqEngine>>,
@@ -21,6 +216,9 @@ pub struct EngineHandle {
     queue: Arc<ArrayQueue<f32>>,
     render_thread: Option<JoinHandle<()>>,
     config: BufferConfig,
+    clock: Arc<StreamClock>,
+    input: Option<Arc<InputCapture>>,
+    stream_config: StreamConfig,
 }
 
 impl EngineHandle {
@@ -53,6 +251,74 @@ impl EngineHandle {
         self.queue.len()
     }
 
+    /// Output stream configuration as negotiated with the device. Once the
+    /// first callback has run, `buffer_size` holds the frames cpal actually
+    /// delivers per callback, which may differ from the engine block size.
+    pub fn stream_config(&self) -> StreamConfig {
+        let mut config = self.stream_config.clone();
+        let frames = self.clock.output_callback_frames.load(Ordering::Relaxed);
+        if frames > 0 {
+            config.buffer_size = BufferSize::Fixed(frames);
+        }
+        config
+    }
+
+    /// Round-trip latency in frames: the device-reported input and output
+    /// latency plus the block the render thread keeps queued ahead of the
+    /// output callback. Output-only streams report no input latency.
+    pub fn latency_samples(&self) -> u32 {
+        let queued = u32::try_from(self.config.block_size).unwrap_or(u32::MAX);
+        self.clock
+            .output_latency_frames
+            .load(Ordering::Relaxed)
+            .saturating_add(self.clock.input_latency_frames.load(Ordering::Relaxed))
+            .saturating_add(queued)
+    }
+
+    /// Timing statistics of the output callback.
+    pub fn stream_metrics(&self) -> StreamMetrics {
+        let stats = &self.clock.stats;
+        StreamMetrics {
+            callbacks: stats.callbacks.load(Ordering::Relaxed),
+            overruns: stats.overruns.load(Ordering::Relaxed),
+            underruns: stats.underruns.load(Ordering::Relaxed),
+            max_callback_us: stats.max_callback_us.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Number of interleaved channels captured by the input stream, or
+    /// `0` when the handle belongs to an output-only stream.
+    pub fn input_channels(&self) -> usize {
+        self.input.as_ref().map_or(0, |input| input.channels)
+    }
+
+    /// Moves captured interleaved samples into `target` and returns how many
+    /// were written. The capture buffer keeps only the most recent blocks,
+    /// so input that is not read in time is dropped rather than delayed.
+    pub fn read_input(&self, target: &mut [f32]) -> usize {
+        let Some(input) = self.input.as_ref() else {
+            return 0;
+        };
+        let mut written = 0;
+        for slot in target.iter_mut() {
+            match input.queue.pop() {
+                Some(sample) => {
+                    *slot = sample;
+                    written += 1;
+                }
+                None => break,
+            }
+        }
+        written
+    }
+
+    /// Frames captured minus frames played since the streams started.
+    pub fn input_offset_frames(&self) -> i64 {
+        let input = self.clock.input_frames.load(Ordering::Relaxed) as i64;
+        let output = self.clock.output_frames.load(Ordering::Relaxed) as i64;
+        input - output
+    }
+
     /// Indicates whether the render thread is still active.
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::Relaxed)
@@ -86,14 +352,76 @@ impl Drop for EngineHandle {
 /// A dedicated render thread performs the heavy lifting while the CPAL callback
 /// stays allocation and lock free.
 pub fn start_realtime(engine: HarmoniqEngine) -> anyhow::Result<(Stream, EngineHandle)> {
-    let config = engine.config().clone();
-    let engine = Arc::new(Mutex::new(engine));
-
     let host = cpal::default_host();
     let device = host
         .default_output_device()
         .context("failed to acquire default output device")?;
-    let supported = choose_stream_config(&device, &config)?;
+    start_output(engine, &device, None)
+}
+
+/// Starts an output stream as [`start_realtime`] does together with an input
+/// stream capturing from `input_device`, or the default input device when
+/// `None`. Captured audio is read through [`EngineHandle::read_input`].
+pub fn start_realtime_duplex(
+    engine: HarmoniqEngine,
+    input_device: Option<&str>,
+) -> anyhow::Result<(DuplexStreams, EngineHandle)> {
+    let host = cpal::default_host();
+    let output_device = host
+        .default_output_device()
+        .context("failed to acquire default output device")?;
+    let input_device = match input_device {
+        Some(name) => host
+            .input_devices()
+            .context("failed to enumerate input devices")?
+            .find(|device| device.name().map(|n| n == name).unwrap_or(false))
+            .with_context(|| format!("input device '{name}' not found"))?,
+        None => host
+            .default_input_device()
+            .context("failed to acquire default input device")?,
+    };
+
+    let engine_config = engine.config().clone();
+    let supported = choose_input_config(&input_device, &engine_config)?;
+    let mut input_config: StreamConfig = supported.config();
+    let frames_per_block = cmp::max(1, engine_config.block_size);
+    input_config.buffer_size =
+        BufferSize::Fixed(u32::try_from(frames_per_block).unwrap_or(u32::MAX));
+    let channels = input_config.channels as usize;
+    let capture = Arc::new(InputCapture {
+        queue: ArrayQueue::new(
+            frames_per_block
+                .saturating_mul(channels.max(1))
+                .saturating_mul(DEFAULT_QUEUE_DEPTH.max(2)),
+        ),
+        channels,
+    });
+
+    let (output, handle) = start_output(engine, &output_device, Some(Arc::clone(&capture)))?;
+    let input = build_input(
+        &input_device,
+        &input_config,
+        supported.sample_format(),
+        capture,
+        Arc::clone(&handle.clock),
+        Arc::clone(&handle.running),
+    )?;
+    input.play()?;
+    // Both streams are running now; count from a common origin.
+    handle.clock.input_frames.store(0, Ordering::Relaxed);
+    handle.clock.output_frames.store(0, Ordering::Relaxed);
+
+    Ok((DuplexStreams { output, input }, handle))
+}
+
+fn start_output(
+    engine: HarmoniqEngine,
+    device: &cpal::Device,
+    input: Option<Arc<InputCapture>>,
+) -> anyhow::Result<(Stream, EngineHandle)> {
+    let config = engine.config().clone();
+    let engine = Arc::new(Mutex::new(engine));
+    let supported = choose_stream_config(device, &config)?;
     let mut stream_config: StreamConfig = supported.config();
 
     let desired_channels = config.layout.channels() as usize;
@@ -121,21 +449,24 @@ pub fn start_realtime(engine: HarmoniqEngine) -> anyhow::Result<(Stream, EngineH
     );
 
     let running = Arc::new(AtomicBool::new(true));
+    let clock = Arc::new(StreamClock::default());
 
     let render_thread = spawn_render_thread(
         Arc::clone(&engine),
         Arc::clone(&queue),
         Arc::clone(&running),
+        Arc::clone(&clock),
         config.clone(),
         output_channels,
     )?;
 
     let stream = build_stream(
-        &device,
+        device,
         &stream_config,
         supported.sample_format(),
         Arc::clone(&queue),
         Arc::clone(&running),
+        Arc::clone(&clock),
     )?;
     stream.play()?;
 
@@ -145,6 +476,9 @@ pub fn start_realtime(engine: HarmoniqEngine) -> anyhow::Result<(Stream, EngineH
         queue,
         render_thread: Some(render_thread),
         config,
+        clock,
+        input,
+        stream_config,
     };
 
     Ok((stream, handle))
@@ -154,6 +488,7 @@ fn spawn_render_thread(
     engine: Arc<Mutex<HarmoniqEngine>>,
     queue: Arc<ArrayQueue<f32>>,
     running: Arc<AtomicBool>,
+    clock: Arc<StreamClock>,
     config: BufferConfig,
     output_channels: usize,
 ) -> anyhow::Result<JoinHandle<()>> {
@@ -166,11 +501,18 @@ fn spawn_render_thread(
             let stride = output_channels.max(1);
             let mut interleaved = vec![0.0f32; stride.saturating_mul(cmp::max(1, buffer.len()))];
 
+            let sample_rate = config.sample_rate.max(1.0) as f64;
+
             while running.load(Ordering::Relaxed) {
+                let started = Instant::now();
                 let process_result = {
                     let mut guard = engine.lock();
                     guard.process_block(&mut buffer)
                 };
+                let budget = Duration::from_secs_f64(buffer.len() as f64 / sample_rate);
+                if started.elapsed() > budget {
+                    clock.stats.overruns.fetch_add(1, Ordering::Relaxed);
+                }
 
                 if let Err(err) = process_result {
                     tracing::error!(?err, "engine processing failed in realtime thread");
@@ -206,28 +548,92 @@ fn build_stream(
     format: SampleFormat,
     queue: Arc<ArrayQueue<f32>>,
     running: Arc<AtomicBool>,
+    clock: Arc<StreamClock>,
 ) -> anyhow::Result<Stream> {
     match format {
-        SampleFormat::F32 => build_output_stream::<f32>(device, config, queue, running),
-        SampleFormat::I16 => build_output_stream::<i16>(device, config, queue, running),
-        SampleFormat::U16 => build_output_stream::<u16>(device, config, queue, running),
+        SampleFormat::F32 => build_output_stream::<f32>(device, config, queue, running, clock),
+        SampleFormat::I16 => build_output_stream::<i16>(device, config, queue, running, clock),
+        SampleFormat::U16 => build_output_stream::<u16>(device, config, queue, running, clock),
         other => Err(anyhow!("unsupported sample format: {other:?}")),
     }
 }
 
+fn build_input(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    format: SampleFormat,
+    capture: Arc<InputCapture>,
+    clock: Arc<StreamClock>,
+    running: Arc<AtomicBool>,
+) -> anyhow::Result<Stream> {
+    match format {
+        SampleFormat::F32 => build_input_stream::<f32>(device, config, capture, clock, running),
+        SampleFormat::I16 => build_input_stream::<i16>(device, config, capture, clock, running),
+        SampleFormat::U16 => build_input_stream::<u16>(device, config, capture, clock, running),
+        other => Err(anyhow!("unsupported sample format: {other:?}")),
+    }
+}
+
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    capture: Arc<InputCapture>,
+    clock: Arc<StreamClock>,
+    running: Arc<AtomicBool>,
+) -> anyhow::Result<Stream>
+where
+    T: Sample + cpal::SizedSample + Send + 'static,
+    f32: FromSample<T>,
+{
+    let channels = capture.channels.max(1) as u64;
+    let sample_rate = config.sample_rate.0;
+    let stream = device.build_input_stream(
+        config,
+        move |input: &[T], info| {
+            ensure_denormals_disabled();
+            if !running.load(Ordering::Relaxed) {
+                return;
+            }
+            // Overwrite the oldest samples when the reader falls behind so
+            // the capture stays aligned with what is playing.
+            for sample in input.iter().copied() {
+                capture.queue.force_push(f32::from_sample(sample));
+            }
+            clock
+                .input_frames
+                .fetch_add(input.len() as u64 / channels, Ordering::Relaxed);
+            let timestamp = info.timestamp();
+            if let Some(latency) = timestamp.callback.duration_since(&timestamp.capture) {
+                clock
+                    .input_latency_frames
+                    .store(duration_to_frames(latency, sample_rate), Ordering::Relaxed);
+            }
+        },
+        move |err| {
+            tracing::error!(?err, "cpal input stream error");
+        },
+        None,
+    )?;
+    Ok(stream)
+}
+
 fn build_output_stream<T>(
     device: &cpal::Device,
     config: &StreamConfig,
     queue: Arc<ArrayQueue<f32>>,
     running: Arc<AtomicBool>,
+    clock: Arc<StreamClock>,
 ) -> anyhow::Result<Stream>
 where
     T: Sample + cpal::SizedSample + FromSample<f32> + Send + 'static,
 {
     let silence = T::from_sample(0.0f32);
+    let channels = config.channels.max(1) as u64;
+    let sample_rate = config.sample_rate.0;
     let stream = device.build_output_stream(
         config,
-        move |output: &mut [T], _info| {
+        move |output: &mut [T], info| {
+            let started = Instant::now();
             ensure_denormals_disabled();
             if !running.load(Ordering::Relaxed) {
                 for sample in output.iter_mut() {
@@ -236,13 +642,33 @@ where
                 return;
             }
 
+            let mut starved = false;
             for sample in output.iter_mut() {
                 if let Some(value) = queue.pop() {
                     *sample = T::from_sample(value);
                 } else {
                     *sample = silence;
+                    starved = true;
                 }
             }
+            let frames = output.len() as u64 / channels;
+            let stats = &clock.stats;
+            stats.callbacks.fetch_add(1, Ordering::Relaxed);
+            if starved {
+                stats.underruns.fetch_add(1, Ordering::Relaxed);
+            }
+            let micros = u32::try_from(started.elapsed().as_micros()).unwrap_or(u32::MAX);
+            stats.max_callback_us.fetch_max(micros, Ordering::Relaxed);
+            clock.output_frames.fetch_add(frames, Ordering::Relaxed);
+            clock
+                .output_callback_frames
+                .store(frames as u32, Ordering::Relaxed);
+            let timestamp = info.timestamp();
+            if let Some(latency) = timestamp.playback.duration_since(&timestamp.callback) {
+                clock
+                    .output_latency_frames
+                    .store(duration_to_frames(latency, sample_rate), Ordering::Relaxed);
+            }
         },
         move |err| {
             tracing::error!(?err, "cpal output stream error");
@@ -294,6 +720,44 @@ fn choose_stream_config(
         .context("failed to fetch default output config")
 }
 
+fn choose_input_config(
+    device: &cpal::Device,
+    config: &BufferConfig,
+) -> anyhow::Result<cpal::SupportedStreamConfig> {
+    let rate_hz = config.sample_rate.max(1.0).round();
+    let desired_rate = SampleRate(rate_hz.clamp(1.0, u32::MAX as f32) as u32);
+
+    // The input has to run at the engine rate to share the output clock;
+    // any channel count the device offers is accepted.
+    if let Ok(configs) = device.supported_input_configs() {
+        let mut matching: Vec<_> = configs
+            .filter(|range| {
+                range.min_sample_rate() <= desired_rate && desired_rate <= range.max_sample_rate()
+            })
+            .collect();
+        matching.sort_by_key(|range| range.sample_format() != SampleFormat::F32);
+        if let Some(range) = matching.into_iter().next() {
+            return Ok(range.with_sample_rate(desired_rate));
+        }
+    }
+
+    let default = device
+        .default_input_config()
+        .context("failed to fetch default input config")?;
+    if default.sample_rate() != desired_rate {
+        return Err(anyhow!(
+            "input device does not support {} Hz",
+            desired_rate.0
+        ));
+    }
+    Ok(default)
+}
+
+fn duration_to_frames(duration: Duration, sample_rate: u32) -> u32 {
+    let frames = (duration.as_secs_f64() * sample_rate as f64).round();
+    frames.clamp(0.0, u32::MAX as f64) as u32
+}
+
 fn interleave_buffer(buffer: &AudioBuffer, output_channels: usize, target: &mut [f32]) -> usize {
     let frames = buffer.len();
     let channel_count = buffer.channel_count();
@@ -375,3 +839,33 @@ fn ensure_denormals_disabled() {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, default_output: bool) -> DeviceInfo {
+        DeviceInfo {
+            name: name.to_string(),
+            input_channels: 2,
+            output_channels: 2,
+            is_default_input: false,
+            is_default_output: default_output,
+        }
+    }
+
+    #[test]
+    fn diff_reports_hotplug_and_default_changes() {
+        let before = [device("Built-in", false), device("USB Interface", true)];
+        let after = [device("Built-in", true), device("Headset", false)];
+        assert_eq!(
+            diff_devices(&before, &after),
+            vec![
+                DeviceEvent::Removed("USB Interface".into()),
+                DeviceEvent::Added(device("Headset", false)),
+                DeviceEvent::DefaultChanged("Built-in".into()),
+            ]
+        );
+        assert!(diff_devices(&after, &after).is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+//! Offline loudness analysis following ITU-R BS.1770 and EBU Tech 3342.
+
+use anyhow::Result;
+use harmoniq_dsp::loudness::{lufs as loudness, KWeighting};
+use harmoniq_dsp::true_peak::TruePeakDetector;
+
+use super::{OfflineRenderer, RenderDuration, RenderProject, RenderRequest, RenderResult};
+use crate::{engine::HarmoniqEngine, plugin::PluginId, AudioClip};
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const INTEGRATED_RELATIVE_GATE_LU: f64 = -10.0;
+const RANGE_RELATIVE_GATE_LU: f64 = -20.0;
+
+/// Loudness and peak statistics for a rendered project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnessReport {
+    /// Gated integrated loudness of the mixdown.
+    pub integrated_lufs: f32,
+    /// Highest inter-sample peak of the mixdown, estimated at 4x oversampling.
+    pub true_peak_dbtp: f32,
+    /// Loudness range (LRA) of the mixdown in LU.
+    pub loudness_range_lu: f32,
+    /// Sample peak of every rendered track.
+    pub track_peaks: Vec<TrackPeak>,
+}
+
+/// Sample peak of a single track's stem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackPeak {
+    pub plugin_id: PluginId,
+    pub name: String,
+    pub peak_dbfs: f32,
+}
+
+impl LoudnessReport {
+    pub fn from_render(result: &RenderResult) -> Self {
+        Self {
+            integrated_lufs: integrated_loudness(&result.mixdown),
+            true_peak_dbtp: true_peak(&result.mixdown),
+            loudness_range_lu: loudness_range(&result.mixdown),
+            track_peaks: result
+                .stems
+                .iter()
+                .map(|stem| TrackPeak {
+                    plugin_id: stem.plugin_id,
+                    name: stem.descriptor.name.clone(),
+                    peak_dbfs: sample_peak(&stem.clip),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl HarmoniqEngine {
+    /// Renders `duration` of `project` offline and measures the result.
+    pub fn analyze_project(
+        project: &dyn RenderProject,
+        duration: RenderDuration,
+    ) -> Result<LoudnessReport> {
+        let mut renderer = OfflineRenderer::new(project.create_engine()?)?;
+        let result = renderer.render(&RenderRequest {
+            duration,
+            ..RenderRequest::default()
+        })?;
+        Ok(LoudnessReport::from_render(&result))
+    }
+}
+
+/// Gated integrated loudness in LUFS, or negative infinity when the clip is
+/// shorter than one 400 ms block or entirely below the absolute gate.
+pub fn integrated_loudness(clip: &AudioClip) -> f32 {
+    integrated_loudness_of(&channels(clip), clip.sample_rate())
+}
+
+/// [`integrated_loudness`] of planar channels at `sample_rate`.
+pub(super) fn integrated_loudness_of(channels: &[&[f32]], sample_rate: f32) -> f32 {
+    let energy = WeightedEnergy::measure(channels, sample_rate);
+    let blocks = energy.windows(0.4, 0.1);
+    let gated: Vec<f64> = blocks
+        .into_iter()
+        .filter(|power| loudness(*power) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if gated.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let threshold = loudness(mean(&gated)) + INTEGRATED_RELATIVE_GATE_LU;
+    let relative: Vec<f64> = gated
+        .into_iter()
+        .filter(|power| loudness(*power) > threshold)
+        .collect();
+    if relative.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    loudness(mean(&relative)) as f32
+}
+
+/// Loudness range in LU: the spread between the 10th and 95th percentile of
+/// gated 3 s short-term loudness values.
+pub fn loudness_range(clip: &AudioClip) -> f32 {
+    let energy = WeightedEnergy::measure(&channels(clip), clip.sample_rate());
+    let windows: Vec<f64> = energy
+        .windows(3.0, 0.1)
+        .into_iter()
+        .filter(|power| loudness(*power) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if windows.is_empty() {
+        return 0.0;
+    }
+    let threshold = loudness(mean(&windows)) + RANGE_RELATIVE_GATE_LU;
+    let mut levels: Vec<f64> = windows
+        .into_iter()
+        .map(loudness)
+        .filter(|level| *level > threshold)
+        .collect();
+    if levels.is_empty() {
+        return 0.0;
+    }
+    levels.sort_by(f64::total_cmp);
+    let percentile = |fraction: f64| {
+        let index = ((levels.len() - 1) as f64 * fraction).round() as usize;
+        levels[index]
+    };
+    (percentile(0.95) - percentile(0.10)) as f32
+}
+
+/// Highest absolute sample value in dBFS.
+pub fn sample_peak(clip: &AudioClip) -> f32 {
+    let peak = (0..clip.channels())
+        .filter_map(|index| clip.channel(index))
+        .flatten()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    to_db(peak)
+}
+
+/// Highest inter-sample peak in dBTP, reconstructed with a windowed-sinc
+/// interpolator at 4x the clip rate.
+pub fn true_peak(clip: &AudioClip) -> f32 {
+    let peak = true_peak_envelope(&channels(clip))
+        .into_iter()
+        .fold(0.0f32, f32::max);
+    to_db(peak)
+}
+
+/// Linear true peak at every frame: the largest magnitude, across channels,
+/// of the sample and the interpolated points that follow it.
+pub(super) fn true_peak_envelope(channels: &[&[f32]]) -> Vec<f32> {
+    let frames = channels
+        .iter()
+        .map(|samples| samples.len())
+        .max()
+        .unwrap_or(0);
+    let mut envelope = vec![0.0f32; frames];
+    let latency = TruePeakDetector::LATENCY;
+    for samples in channels {
+        let mut detector = TruePeakDetector::new();
+        let flushed = samples
+            .iter()
+            .copied()
+            .chain(std::iter::repeat(0.0).take(latency));
+        for (index, sample) in flushed.enumerate() {
+            let peak = detector.process(sample);
+            if let Some(position) = index.checked_sub(latency) {
+                envelope[position] = envelope[position].max(peak);
+            }
+        }
+    }
+    envelope
+}
+
+fn channels(clip: &AudioClip) -> Vec<&[f32]> {
+    (0..clip.channels())
+        .filter_map(|index| clip.channel(index))
+        .collect()
+}
+
+/// Prefix sum of K-weighted energy summed across channels.
+struct WeightedEnergy {
+    sample_rate: f64,
+    cumulative: Vec<f64>,
+}
+
+impl WeightedEnergy {
+    fn measure(channels: &[&[f32]], sample_rate: f32) -> Self {
+        let frames = channels
+            .iter()
+            .map(|samples| samples.len())
+            .max()
+            .unwrap_or(0);
+        let mut energy = vec![0.0f64; frames];
+        for samples in channels {
+            let mut filter = KWeighting::new(sample_rate);
+            for (slot, sample) in energy.iter_mut().zip(samples.iter()) {
+                let weighted = filter.process(*sample as f64);
+                *slot += weighted * weighted;
+            }
+        }
+        let mut cumulative = Vec::with_capacity(frames + 1);
+        cumulative.push(0.0);
+        let mut total = 0.0;
+        for value in energy {
+            total += value;
+            cumulative.push(total);
+        }
+        Self {
+            sample_rate: sample_rate as f64,
+            cumulative,
+        }
+    }
+
+    /// Mean power of every complete `length`-second window, stepping by `hop`
+    /// seconds.
+    fn windows(&self, length: f64, hop: f64) -> Vec<f64> {
+        let frames = self.cumulative.len() - 1;
+        let length = (length * self.sample_rate).round() as usize;
+        let hop = ((hop * self.sample_rate).round() as usize).max(1);
+        if length == 0 || frames < length {
+            return Vec::new();
+        }
+        (0..=(frames - length) / hop)
+            .map(|index| {
+                let start = index * hop;
+                (self.cumulative[start + length] - self.cumulative[start]) / length as f64
+            })
+            .collect()
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn to_db(linear: f32) -> f32 {
+    if linear > 0.0 {
+        20.0 * linear.log10()
+    } else {
+        f32::NEG_INFINITY
+    }
+}
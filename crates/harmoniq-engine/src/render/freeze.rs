@@ -0,0 +1,197 @@
+//! Freezing a track in place.
+//!
+//! A frozen track keeps its graph nodes, but every processor on it is
+//! swapped for one that plays back the audio the original produced at that
+//! node. Mixer gains, sends and delay compensation downstream see exactly
+//! the same signal while the original plugins stop running.
+
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use petgraph::stable_graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use super::{append_buffer, FreezeSettings};
+use crate::engine::{HarmoniqEngine, TransportState};
+use crate::graph::{GraphHandle, NodeHandle, SignalKind};
+use crate::plugin::{AudioProcessor, PluginDescriptor, PluginId};
+use crate::transport::Transport;
+use crate::{AudioBuffer, AudioClip, BufferConfig};
+
+impl HarmoniqEngine {
+    /// Renders `node` and the insert chain only it feeds from the project
+    /// start through `settings.duration` plus `settings.tail_seconds`, then
+    /// replaces the chain's processors with playback of that render.
+    ///
+    /// Returns the chain's contribution to the master, scaled by its faders.
+    /// Frozen processors are silent while the transport is stopped. The
+    /// transport state and position are restored once the render is done.
+    pub fn freeze_track(
+        &mut self,
+        node: NodeHandle,
+        settings: &FreezeSettings,
+    ) -> Result<AudioClip> {
+        if self.frozen_tracks.contains_key(&node) {
+            bail!("track is already frozen");
+        }
+        let graph = self
+            .graph()
+            .ok_or_else(|| anyhow!("project has no active processing graph"))?;
+        let chain = insert_chain(&graph, node.0)
+            .ok_or_else(|| anyhow!("only plugin nodes can be frozen"))?;
+
+        let sample_rate = self.config().sample_rate;
+        let block_size = self.config().block_size;
+        let tail = (settings.tail_seconds.max(0.0) * sample_rate).round() as usize;
+        let frames = settings.duration.frames(sample_rate) + tail;
+        let plugin_ids = graph.plugin_ids();
+        let mut captured: Vec<Vec<Vec<f32>>> = vec![Vec::new(); chain.len()];
+
+        let previous_state = self.transport();
+        let previous_position = self.transport_metrics().sample_pos.load(Ordering::Relaxed);
+        self.reset_render_state()?;
+        self.set_transport(TransportState::Playing);
+        let mut remaining = frames;
+        let rendered = (|| -> Result<()> {
+            while remaining > 0 {
+                let frames_this = remaining.min(block_size);
+                self.render_block_with(|_, scratch| {
+                    for (slot, index) in chain.iter().enumerate() {
+                        if let Some(buffer) = scratch.get(*index) {
+                            append_buffer(buffer, &mut captured[slot], frames_this);
+                        }
+                    }
+                })?;
+                remaining -= frames_this;
+            }
+            Ok(())
+        })();
+        self.set_transport(TransportState::Stopped);
+        self.reset_render_state()?;
+        self.restore_transport(previous_state, previous_position);
+        rendered?;
+
+        let channels = self.config().layout.channels() as usize;
+        let mut mix = vec![vec![0.0f32; frames]; channels];
+        let mut originals = Vec::with_capacity(chain.len());
+        for (index, audio) in chain.iter().zip(captured) {
+            let gain = graph.gain_for(graph.plugin_nodes[*index]);
+            for (target, source) in mix.iter_mut().zip(&audio) {
+                for (sample, value) in target.iter_mut().zip(source) {
+                    *sample += value * gain;
+                }
+            }
+
+            let id = plugin_ids[*index];
+            let descriptor = self
+                .plugin_descriptor(id)
+                .unwrap_or_else(|| PluginDescriptor::new("unknown", "Unknown", "Harmoniq"));
+            let player = FrozenProcessor {
+                descriptor,
+                clip: AudioClip::with_sample_rate(sample_rate, audio),
+                transport: self.transport_metrics(),
+                latency: self.processor_latency(id),
+            };
+            if let Some(original) = self.swap_processor(id, Box::new(player)) {
+                originals.push((id, original));
+            }
+        }
+        self.frozen_tracks.insert(node, originals);
+        Ok(AudioClip::with_sample_rate(sample_rate, mix))
+    }
+
+    /// Restores live processing on a track frozen by
+    /// [`freeze_track`](Self::freeze_track). Returns `false` if it was not
+    /// frozen.
+    pub fn unfreeze_track(&mut self, node: NodeHandle) -> Result<bool> {
+        let Some(originals) = self.frozen_tracks.remove(&node) else {
+            return Ok(false);
+        };
+        let config = self.config().clone();
+        for (id, mut processor) in originals {
+            processor.prepare(&config)?;
+            self.swap_processor(id, processor);
+        }
+        Ok(true)
+    }
+
+    pub fn is_track_frozen(&self, node: NodeHandle) -> bool {
+        self.frozen_tracks.contains_key(&node)
+    }
+}
+
+/// `node` and the plugins downstream of it whose every audio input comes
+/// from within the chain, as indices into [`GraphHandle::plugin_ids`].
+fn insert_chain(graph: &GraphHandle, node: NodeIndex) -> Option<Vec<usize>> {
+    let first = *graph.node_lookup.get(&node)?;
+    let mut members: HashSet<NodeIndex> = HashSet::from([node]);
+    let mut chain = vec![first];
+    let mut pending = vec![node];
+    while let Some(current) = pending.pop() {
+        for edge in graph.graph.edges_directed(current, Direction::Outgoing) {
+            let target = edge.target();
+            if edge.weight().signal != SignalKind::Audio || members.contains(&target) {
+                continue;
+            }
+            let Some(index) = graph.node_lookup.get(&target) else {
+                continue;
+            };
+            let exclusive = graph
+                .graph
+                .edges_directed(target, Direction::Incoming)
+                .filter(|edge| edge.weight().signal == SignalKind::Audio)
+                .all(|edge| members.contains(&edge.source()));
+            if exclusive {
+                members.insert(target);
+                chain.push(*index);
+                pending.push(target);
+            }
+        }
+    }
+    Some(chain)
+}
+
+/// Plays back the audio a frozen processor rendered, following the
+/// transport position.
+struct FrozenProcessor {
+    descriptor: PluginDescriptor,
+    clip: AudioClip,
+    transport: Arc<Transport>,
+    latency: usize,
+}
+
+impl AudioProcessor for FrozenProcessor {
+    fn descriptor(&self) -> PluginDescriptor {
+        self.descriptor.clone()
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> Result<()> {
+        let playing = self.transport.playing.load(Ordering::Relaxed);
+        let start = self.transport.sample_pos.load(Ordering::Relaxed) as usize;
+        let channels = self.clip.channels();
+        for (index, channel) in buffer.channels_mut().enumerate() {
+            let source = match self.clip.channel(index.min(channels.saturating_sub(1))) {
+                Some(source) if playing => source,
+                _ => {
+                    channel.fill(0.0);
+                    continue;
+                }
+            };
+            for (offset, sample) in channel.iter_mut().enumerate() {
+                *sample = source.get(start + offset).copied().unwrap_or(0.0);
+            }
+        }
+        Ok(())
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.latency
+    }
+}
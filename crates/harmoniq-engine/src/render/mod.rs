@@ -1,39 +1,89 @@
 use std::collections::HashSet;
 use std::fs;
+use std::ops::Range;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Result};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 
+mod analysis;
+mod freeze;
+mod normalize;
+pub(crate) mod oversample;
+mod stems;
+
+pub use analysis::{
+    integrated_loudness, loudness_range, sample_peak, true_peak, LoudnessReport, TrackPeak,
+};
+pub use normalize::{Normalization, NormalizationReport};
+pub use stems::{StemGroup, StemSource, StemSplit, DEFAULT_STEM_TEMPLATE};
+
+use oversample::MasterOversample;
+
 use crate::{
     engine::{HarmoniqEngine, TransportState},
-    plugin::{PluginDescriptor, PluginId},
+    plugin::{AudioProcessor, PluginDescriptor, PluginId},
     AudioBuffer, AudioClip, BufferConfig, EngineCommand,
 };
 
 /// Audio file formats supported by the offline renderer.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RenderFormat {
+    /// 24-bit PCM.
     Wav,
-    Flac,
+    /// 24-bit lossless FLAC. `compression` follows the reference encoder's
+    /// levels, from 0 (fastest) to 8 (smallest).
+    Flac { compression: u8 },
+    /// Lossy Ogg Vorbis at a VBR `quality` from -0.1 to 1.0. The encoder
+    /// takes float samples, so no dither is applied.
+    OggVorbis { quality: f32 },
 }
 
 impl RenderFormat {
     fn extension(self) -> &'static str {
         match self {
             RenderFormat::Wav => "wav",
-            RenderFormat::Flac => "flac",
+            RenderFormat::Flac { .. } => "flac",
+            RenderFormat::OggVorbis { .. } => "ogg",
         }
     }
 }
 
-/// Rendering speed.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Tags written into rendered files whose format supports them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderMetadata {
+    pub title: String,
+    /// Project tempo in BPM.
+    pub tempo: f32,
+}
+
+impl RenderMetadata {
+    /// Vorbis comment fields, shared by FLAC and Ogg Vorbis.
+    fn comments(&self) -> Vec<(&'static str, String)> {
+        let mut comments = Vec::with_capacity(2);
+        if !self.title.is_empty() {
+            comments.push(("TITLE", self.title.clone()));
+        }
+        if self.tempo > 0.0 {
+            comments.push(("BPM", format!("{}", self.tempo)));
+        }
+        comments
+    }
+}
+
+/// How fast blocks are produced. Every speed renders the same samples;
+/// pacing only delays the next block, it never resamples.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RenderSpeed {
+    /// As fast as the CPU allows.
     Offline,
+    /// One block per block duration of wall-clock time.
     Realtime,
+    /// This many times faster than real time. Factors that are not
+    /// positive and finite render offline.
+    Multiplier(f32),
 }
 
 impl Default for RenderSpeed {
@@ -42,6 +92,75 @@ impl Default for RenderSpeed {
     }
 }
 
+/// How a processor produces its audio, for picking a [`RenderSpeed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessorKind {
+    /// Audio computed from the input and parameters alone, like the
+    /// built-in nodes. Safe at any speed.
+    Native,
+    /// A hosted plugin that may stream from disk or hand work to
+    /// background threads, which can fall behind an unpaced render.
+    Hosted,
+    /// Hardware or an external synth reached through the audio and MIDI
+    /// devices. It runs on its own clock, so only real time captures it.
+    External,
+}
+
+impl RenderSpeed {
+    /// Fastest speed that renders every kind in `kinds` correctly.
+    pub fn recommended_for(kinds: &[ProcessorKind]) -> Self {
+        if kinds.contains(&ProcessorKind::External) {
+            RenderSpeed::Realtime
+        } else if kinds.contains(&ProcessorKind::Hosted) {
+            RenderSpeed::Multiplier(4.0)
+        } else {
+            RenderSpeed::Offline
+        }
+    }
+
+    /// Wall-clock seconds allowed per rendered second, or `None` when
+    /// unpaced.
+    fn pace(self) -> Option<f64> {
+        match self {
+            RenderSpeed::Offline => None,
+            RenderSpeed::Realtime => Some(1.0),
+            RenderSpeed::Multiplier(factor) if factor.is_finite() && factor > 0.0 => {
+                Some(1.0 / factor as f64)
+            }
+            RenderSpeed::Multiplier(_) => None,
+        }
+    }
+}
+
+/// Render progress passed to the callback of
+/// [`OfflineRenderer::render_with_progress`] once per block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderProgress {
+    pub frames_rendered: usize,
+    pub total_frames: usize,
+    /// Lead plugin of the stem being rendered, or of the freeze being
+    /// written. `None` while the mixdown is rendered.
+    pub stem: Option<PluginId>,
+}
+
+impl RenderProgress {
+    /// Completed share of the render in `0..=1`.
+    pub fn fraction(&self) -> f32 {
+        if self.total_frames == 0 {
+            1.0
+        } else {
+            self.frames_rendered as f32 / self.total_frames as f32
+        }
+    }
+}
+
+/// Returned by progress callbacks to keep going or abort the render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderControl {
+    Continue,
+    Cancel,
+}
+
 /// Duration of a render request.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RenderDuration {
@@ -64,6 +183,35 @@ impl RenderDuration {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DitherKind {
     Tpdf,
+    /// TPDF dither with an error feedback filter that moves the noise out
+    /// of the 1–5 kHz region the ear is most sensitive to and up towards the
+    /// top of the spectrum.
+    NoiseShaped {
+        profile: ShapingProfile,
+    },
+}
+
+/// Error feedback curve used by [`DitherKind::NoiseShaped`]. The curves are
+/// Wannamaker's psychoacoustically weighted filters, designed for 44.1 and
+/// 48 kHz.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShapingProfile {
+    /// Third-order curve with a gentler rise towards Nyquist.
+    ThirdOrder,
+    /// Ninth-order E-weighted curve that follows the ear's sensitivity
+    /// more closely.
+    #[default]
+    NinthOrder,
+}
+
+impl ShapingProfile {
+    /// Error feedback coefficients, ordered from the most recent error.
+    fn coefficients(self) -> &'static [f32] {
+        match self {
+            ShapingProfile::ThirdOrder => &THIRD_ORDER_SHAPING,
+            ShapingProfile::NinthOrder => &NINTH_ORDER_SHAPING,
+        }
+    }
 }
 
 /// Target audio file output.
@@ -93,10 +241,37 @@ pub struct StemSettings {
     pub directory: PathBuf,
     pub format: RenderFormat,
     pub dither: Option<DitherKind>,
+    /// Only stems containing one of these plugins are exported.
     pub plugins: Option<Vec<PluginId>>,
+    pub split: StemSplit,
+    /// File name without extension. `{project}`, `{track}` and `{index}`
+    /// are replaced with the project label, the stem's track, bus or group
+    /// name, and its 1-based position.
+    pub name_template: String,
+    /// Extra time rendered past the end of the request so reverb and delay
+    /// tails ring out.
+    pub tail_seconds: f32,
+    /// Keep shared sends audible, fed by the stem alone. Dry stems mute
+    /// every plugin the stem does not own.
+    pub wet: bool,
 }
 
 impl StemSettings {
+    /// Dry per-track stems named after [`DEFAULT_STEM_TEMPLATE`] without a
+    /// tail.
+    pub fn new(directory: impl Into<PathBuf>, format: RenderFormat) -> Self {
+        Self {
+            directory: directory.into(),
+            format,
+            dither: None,
+            plugins: None,
+            split: StemSplit::PerTrack,
+            name_template: DEFAULT_STEM_TEMPLATE.to_owned(),
+            tail_seconds: 0.0,
+            wet: false,
+        }
+    }
+
     fn ensure_dir(&self) -> Result<()> {
         fs::create_dir_all(&self.directory).with_context(|| {
             format!(
@@ -114,6 +289,11 @@ pub struct FreezeSettings {
     pub format: RenderFormat,
     pub dither: Option<DitherKind>,
     pub plugins: Option<Vec<PluginId>>,
+    /// Length [`HarmoniqEngine::freeze_track`] renders before the tail.
+    pub duration: RenderDuration,
+    /// Extra audio rendered after `duration` so reverb and delay tails are
+    /// kept in the frozen clip.
+    pub tail_seconds: f32,
 }
 
 impl FreezeSettings {
@@ -135,6 +315,12 @@ pub struct RenderRequest {
     pub stems: Option<StemSettings>,
     pub freeze: Option<FreezeSettings>,
     pub speed: RenderSpeed,
+    /// Oversampling factor for the master chain during this render: 1, 2 or
+    /// 4. Nonlinear master processors alias less at higher factors.
+    pub master_oversample: u8,
+    /// Level target applied to the mixdown once it has been rendered.
+    /// Stems keep their rendered level.
+    pub normalization: Normalization,
 }
 
 impl Default for RenderRequest {
@@ -145,10 +331,29 @@ impl Default for RenderRequest {
             stems: None,
             freeze: None,
             speed: RenderSpeed::Offline,
+            master_oversample: 1,
+            normalization: Normalization::None,
         }
     }
 }
 
+/// What a written file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RenderSource {
+    Mixdown,
+    Stem(StemSource),
+    Freeze(PluginId),
+}
+
+/// A file written by a render job.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedFile {
+    pub path: PathBuf,
+    pub source: RenderSource,
+    /// Sample peak of the audio before dither, in dBFS.
+    pub peak_dbfs: f32,
+}
+
 /// Summary information for a completed render job.
 #[derive(Debug, Clone)]
 pub struct RenderReport {
@@ -156,7 +361,14 @@ pub struct RenderReport {
     pub mixdown: Option<PathBuf>,
     pub stems: Vec<PathBuf>,
     pub freezes: Vec<PathBuf>,
+    /// Every file written, in the order it was written.
+    pub files: Vec<RenderedFile>,
     pub duration_frames: usize,
+    /// The job was cancelled. Files it had started writing were removed
+    /// and no paths are reported.
+    pub cancelled: bool,
+    /// Mixdown loudness before and after normalisation, if requested.
+    pub normalization: Option<NormalizationReport>,
 }
 
 /// Offline render result containing audio clips before export.
@@ -165,6 +377,26 @@ pub struct RenderResult {
     pub duration_frames: usize,
     pub mixdown: AudioClip,
     pub stems: Vec<StemRender>,
+    /// Project tempo in BPM, written into file tags.
+    pub tempo: f32,
+    /// Rendering stopped early at the caller's request. The clips hold the
+    /// audio rendered up to that point.
+    pub cancelled: bool,
+    /// Mixdown loudness before and after normalisation, if requested.
+    pub normalization: Option<NormalizationReport>,
+}
+
+/// Insert comparison produced by [`OfflineRenderer::render_ab`].
+#[derive(Debug, Clone)]
+pub struct AbRenderResult {
+    /// Mixdown processed by the insert, shifted back by its latency.
+    pub enabled: AudioClip,
+    /// Mixdown with the insert bypassed.
+    pub bypassed: AudioClip,
+    /// `enabled - bypassed`, sample for sample.
+    pub difference: AudioClip,
+    /// Latency reported by the insert and removed from `enabled`.
+    pub latency_frames: usize,
 }
 
 /// Captured stem render information.
@@ -204,14 +436,35 @@ impl RenderQueue {
         self.jobs.push(RenderJob { project, request });
     }
 
-    pub fn process_all(mut self) -> Result<Vec<RenderReport>> {
+    pub fn process_all(self) -> Result<Vec<RenderReport>> {
+        self.process_all_with_progress(|_, _| RenderControl::Continue)
+    }
+
+    /// Processes every job, reporting progress together with the index of
+    /// the job in the queue. Cancelling stops only the current job; the
+    /// callback is asked again for the next one.
+    pub fn process_all_with_progress(
+        mut self,
+        mut progress: impl FnMut(usize, RenderProgress) -> RenderControl,
+    ) -> Result<Vec<RenderReport>> {
         let mut reports = Vec::new();
-        for job in self.jobs.drain(..) {
+        for (index, job) in self.jobs.drain(..).enumerate() {
             let label = job.project.label().to_owned();
             let engine = job.project.create_engine()?;
             let mut renderer = OfflineRenderer::new(engine)?;
-            let result = renderer.render(&job.request)?;
-            let report = write_outputs(&label, &result, &job.request)?;
+            let mut job_progress = |update: RenderProgress| progress(index, update);
+            let result = renderer.render_with_progress(&job.request, &mut job_progress)?;
+            let report = if result.cancelled {
+                cancelled_report(&label, &result)
+            } else {
+                write_outputs(
+                    &label,
+                    &mut renderer,
+                    &result,
+                    &job.request,
+                    &mut job_progress,
+                )?
+            };
             reports.push(report);
         }
         Ok(reports)
@@ -219,6 +472,9 @@ impl RenderQueue {
 }
 
 /// Offline renderer driving the Harmoniq engine faster than real-time.
+///
+/// Only the main output is rendered. Listening aids on the mixer's monitor
+/// bus, such as solo listen, dim and mono, never reach it.
 pub struct OfflineRenderer {
     engine: HarmoniqEngine,
     config: BufferConfig,
@@ -232,6 +488,17 @@ impl OfflineRenderer {
     }
 
     pub fn render(&mut self, request: &RenderRequest) -> Result<RenderResult> {
+        self.render_with_progress(request, |_| RenderControl::Continue)
+    }
+
+    /// Renders like [`OfflineRenderer::render`], calling `progress` after
+    /// every block. Returning [`RenderControl::Cancel`] stops the render and
+    /// yields a result marked as cancelled.
+    pub fn render_with_progress(
+        &mut self,
+        request: &RenderRequest,
+        mut progress: impl FnMut(RenderProgress) -> RenderControl,
+    ) -> Result<RenderResult> {
         let frames_to_render = request.duration.frames(self.config.sample_rate);
         if frames_to_render == 0 {
             return Ok(RenderResult {
@@ -241,6 +508,9 @@ impl OfflineRenderer {
                     self.config.layout.channels() as usize,
                 ),
                 stems: Vec::new(),
+                tempo: self.engine.tempo(),
+                cancelled: false,
+                normalization: None,
             });
         }
 
@@ -261,23 +531,31 @@ impl OfflineRenderer {
             descriptors.push(descriptor);
         }
 
+        let factor = match request.master_oversample {
+            0 | 1 => 1,
+            2 => 2,
+            4 => 4,
+            other => return Err(anyhow!("unsupported master oversampling factor {other}")),
+        };
+        // The resampling filters delay the master; render past the end and
+        // drop the lead-in so the mixdown stays aligned with the stems.
+        let mut engine = MasterOversample::enable(&mut self.engine, factor)?;
+        let master_latency = engine.latency();
+
         let mut mixdown_channels = vec![Vec::new(); self.config.layout.channels() as usize];
-        let mut remaining = frames_to_render;
+        let total_frames = frames_to_render + master_latency;
+        let mut remaining = total_frames;
+        let mut cancelled = false;
 
-        self.engine
-            .execute_command(EngineCommand::SetTransport(TransportState::Playing))?;
+        let pace = request.speed.pace();
+        let started = Instant::now();
+
+        engine.execute_command(EngineCommand::SetTransport(TransportState::Playing))?;
 
         while remaining > 0 {
             let frames_this = remaining.min(self.config.block_size);
-            let sleep = if matches!(request.speed, RenderSpeed::Realtime) {
-                Some(Duration::from_secs_f32(
-                    self.config.block_size as f32 / self.config.sample_rate,
-                ))
-            } else {
-                None
-            };
 
-            self.engine.render_block_with(|master, scratch| {
+            engine.render_block_with(|master, scratch| {
                 append_buffer(master, &mut mixdown_channels, frames_this);
                 for (index, buffer) in scratch.iter().enumerate() {
                     if index >= stem_buffers.len() {
@@ -292,14 +570,50 @@ impl OfflineRenderer {
 
             remaining = remaining.saturating_sub(frames_this);
 
-            if let Some(duration) = sleep {
-                std::thread::sleep(duration);
+            // Progress excludes the oversampling lead-in that gets dropped.
+            let control = progress(RenderProgress {
+                frames_rendered: (total_frames - remaining).saturating_sub(master_latency),
+                total_frames: frames_to_render,
+                stem: None,
+            });
+            if control == RenderControl::Cancel {
+                cancelled = true;
+                break;
+            }
+
+            // Pace against the start of the render rather than per block, so
+            // time spent processing does not add up to drift.
+            if let Some(pace) = pace {
+                let rendered = (total_frames - remaining) as f64 / self.config.sample_rate as f64;
+                let due = started + Duration::from_secs_f64(rendered * pace);
+                if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                    std::thread::sleep(wait);
+                }
             }
         }
 
-        self.engine
-            .execute_command(EngineCommand::SetTransport(TransportState::Stopped))?;
+        engine.execute_command(EngineCommand::SetTransport(TransportState::Stopped))?;
 
+        engine.finish()?;
+        if master_latency > 0 {
+            for channel in &mut mixdown_channels {
+                channel.drain(..master_latency.min(channel.len()));
+            }
+            for channel in stem_buffers.iter_mut().flatten() {
+                let rendered = channel.len().saturating_sub(master_latency);
+                channel.truncate(frames_to_render.min(rendered));
+            }
+        }
+
+        let normalization = if cancelled {
+            None
+        } else {
+            normalize::apply(
+                &mut mixdown_channels,
+                self.config.sample_rate,
+                request.normalization,
+            )
+        };
         let mixdown = AudioClip::with_sample_rate(self.config.sample_rate, mixdown_channels);
         let mut stems = Vec::with_capacity(plugin_ids.len());
         for ((plugin_id, descriptor), channels) in plugin_ids
@@ -321,6 +635,107 @@ impl OfflineRenderer {
             duration_frames: mixdown.frames(),
             mixdown,
             stems,
+            tempo: self.engine.tempo(),
+            cancelled,
+            normalization,
+        })
+    }
+
+    /// Freezes a single node: renders the project from the start through
+    /// `range.end` and returns the node's output over `range` (in frames).
+    ///
+    /// The node's automation lanes are evaluated on the project timeline
+    /// while rendering, so every parameter move inside the range is baked
+    /// into the frozen audio.
+    pub fn freeze_node(&mut self, plugin: PluginId, range: Range<usize>) -> Result<AudioClip> {
+        if range.start > range.end {
+            return Err(anyhow!(
+                "invalid freeze range {}..{}",
+                range.start,
+                range.end
+            ));
+        }
+        self.engine.reset_render_state()?;
+        let result = self.render(&RenderRequest {
+            duration: RenderDuration::Frames(range.end),
+            ..RenderRequest::default()
+        })?;
+        let stem = result
+            .stems
+            .into_iter()
+            .find(|stem| stem.plugin_id == plugin)
+            .ok_or_else(|| anyhow!("plugin {} is not part of the render graph", plugin.0))?;
+        let channels = (0..stem.clip.channels())
+            .map(|index| {
+                stem.clip
+                    .channel(index)
+                    .and_then(|samples| samples.get(range.clone()))
+                    .unwrap_or(&[])
+                    .to_vec()
+            })
+            .collect();
+        Ok(AudioClip::with_sample_rate(
+            self.config.sample_rate,
+            channels,
+        ))
+    }
+
+    /// Renders the request once and compares the mixdown with and without
+    /// `insert` applied on the master.
+    ///
+    /// The bypassed take is the untouched mixdown, so both takes share the
+    /// exact same source material. The insert output is flushed past its
+    /// reported latency and shifted back so the takes line up sample for
+    /// sample.
+    pub fn render_ab(
+        &mut self,
+        request: &RenderRequest,
+        insert: &mut dyn AudioProcessor,
+    ) -> Result<AbRenderResult> {
+        let bypassed = self.render(request)?.mixdown;
+        insert.prepare(&self.config)?;
+        let latency = insert.latency_samples();
+
+        let channels = bypassed.channels();
+        let frames = bypassed.frames();
+        let block_size = self.config.block_size.max(1);
+        let mut wet = vec![Vec::with_capacity(frames + latency); channels];
+        let mut block = AudioBuffer::new(channels, block_size);
+        let mut position = 0;
+        while position < frames + latency {
+            for (channel_index, channel) in block.channels_mut().enumerate() {
+                let source = bypassed.channel(channel_index).unwrap_or(&[]);
+                for (offset, sample) in channel.iter_mut().enumerate() {
+                    *sample = source.get(position + offset).copied().unwrap_or(0.0);
+                }
+            }
+            insert.process(&mut block)?;
+            append_buffer(&block, &mut wet, block_size);
+            position += block_size;
+        }
+        for channel in &mut wet {
+            channel.drain(..latency.min(channel.len()));
+            channel.truncate(frames);
+        }
+
+        let difference = wet
+            .iter()
+            .enumerate()
+            .map(|(channel_index, channel)| {
+                let dry = bypassed.channel(channel_index).unwrap_or(&[]);
+                channel
+                    .iter()
+                    .zip(dry.iter())
+                    .map(|(wet, dry)| wet - dry)
+                    .collect()
+            })
+            .collect();
+
+        Ok(AbRenderResult {
+            enabled: AudioClip::with_sample_rate(self.config.sample_rate, wet),
+            difference: AudioClip::with_sample_rate(self.config.sample_rate, difference),
+            bypassed,
+            latency_frames: latency,
         })
     }
 }
@@ -340,23 +755,81 @@ fn append_buffer(source: &AudioBuffer, destination: &mut Vec<Vec<f32>>, frames:
     }
 }
 
-struct TpdfDither {
+/// E-weighted error feedback coefficients (Wannamaker), ordered from the
+/// most recent error.
+const NINTH_ORDER_SHAPING: [f32; 9] = [
+    2.412, -3.370, 3.937, -4.174, 3.353, -2.205, 1.281, -0.569, 0.0847,
+];
+
+/// Wannamaker's third-order weighted coefficients.
+const THIRD_ORDER_SHAPING: [f32; 3] = [1.623, -0.982, 0.109];
+
+/// Longest shaping filter, which sizes the per-channel error history.
+const MAX_SHAPING_ORDER: usize = NINTH_ORDER_SHAPING.len();
+
+/// Largest error, in LSBs, fed back into the shaping filter. Keeps the loop
+/// stable when the signal clips.
+const MAX_SHAPED_ERROR: f32 = 4.0;
+
+/// Converts float samples to signed integers at a fixed bit depth, applying
+/// dither and noise shaping. Shaping state is kept per channel, so one
+/// quantizer should process a whole render.
+pub struct Quantizer {
+    full_scale: f32,
+    dither: Option<DitherKind>,
     rng: StdRng,
-    scale: f32,
+    errors: Vec<[f32; MAX_SHAPING_ORDER]>,
 }
 
-impl TpdfDither {
-    fn new(seed: u64, scale: f32) -> Self {
+impl Quantizer {
+    pub fn new(bits: u32, dither: Option<DitherKind>, channels: usize, seed: u64) -> Self {
+        let bits = bits.clamp(2, 32);
         Self {
+            full_scale: ((1u64 << (bits - 1)) - 1) as f32,
+            dither,
             rng: StdRng::seed_from_u64(seed),
-            scale,
+            errors: vec![[0.0; MAX_SHAPING_ORDER]; channels],
         }
     }
 
-    fn sample(&mut self) -> f32 {
+    /// Integer value of a full-scale sample.
+    pub fn full_scale(&self) -> f32 {
+        self.full_scale
+    }
+
+    pub fn quantize(&mut self, channel: usize, sample: f32) -> i32 {
+        let limit = self.full_scale;
+        let mut value = sample * limit;
+        match self.dither {
+            None => {}
+            Some(DitherKind::Tpdf) => value += self.tpdf(),
+            Some(DitherKind::NoiseShaped { profile }) => {
+                if channel >= self.errors.len() {
+                    self.errors.resize(channel + 1, [0.0; MAX_SHAPING_ORDER]);
+                }
+                let history = &self.errors[channel];
+                let feedback: f32 = profile
+                    .coefficients()
+                    .iter()
+                    .zip(history.iter())
+                    .map(|(coeff, error)| coeff * error)
+                    .sum();
+                let target = value - feedback;
+                let quantised = (target + self.tpdf()).round().clamp(-limit, limit);
+                let history = &mut self.errors[channel];
+                history.copy_within(..MAX_SHAPING_ORDER - 1, 1);
+                history[0] = (quantised - target).clamp(-MAX_SHAPED_ERROR, MAX_SHAPED_ERROR);
+                return quantised as i32;
+            }
+        }
+        value.round().clamp(-limit, limit).trunc() as i32
+    }
+
+    /// Triangular dither spanning ±1 LSB.
+    fn tpdf(&mut self) -> f32 {
         let a: f32 = self.rng.gen();
         let b: f32 = self.rng.gen();
-        (a - b) / self.scale
+        a - b
     }
 }
 
@@ -374,48 +847,86 @@ fn slugify(name: &str) -> String {
     slug.trim_matches('_').to_owned()
 }
 
+fn cancelled_report(project: &str, result: &RenderResult) -> RenderReport {
+    RenderReport {
+        project: project.to_owned(),
+        mixdown: None,
+        stems: Vec::new(),
+        freezes: Vec::new(),
+        files: Vec::new(),
+        duration_frames: result.duration_frames,
+        cancelled: true,
+        normalization: None,
+    }
+}
+
+/// Removes the files a cancelled job already wrote.
+fn remove_outputs<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) {
+    for path in paths {
+        if let Err(err) = fs::remove_file(path) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(
+                    "failed to remove cancelled render {}: {err}",
+                    path.display()
+                );
+            }
+        }
+    }
+}
+
+/// Writes the job's files. Stems are rendered here, one pass each, so the
+/// mixdown in `result` is untouched by their solo routing.
 fn write_outputs(
     project: &str,
+    renderer: &mut OfflineRenderer,
     result: &RenderResult,
     request: &RenderRequest,
+    progress: &mut dyn FnMut(RenderProgress) -> RenderControl,
 ) -> Result<RenderReport> {
     let mut mixdown_path = None;
     let mut stem_paths = Vec::new();
     let mut freeze_paths = Vec::new();
+    let mut files = Vec::new();
+    let metadata = RenderMetadata {
+        title: project.to_owned(),
+        tempo: result.tempo,
+    };
 
     if let Some(target) = &request.mixdown {
         target.ensure_parent()?;
         let path = target.path.clone();
-        write_clip(&result.mixdown, target, 0)?;
+        write_clip(&result.mixdown, target, &metadata, 0)?;
+        files.push(RenderedFile {
+            path: path.clone(),
+            source: RenderSource::Mixdown,
+            peak_dbfs: sample_peak(&result.mixdown),
+        });
         mixdown_path = Some(path);
     }
 
     if let Some(settings) = &request.stems {
         settings.ensure_dir()?;
-        let allowed: Option<HashSet<PluginId>> = settings
-            .plugins
-            .as_ref()
-            .map(|ids| ids.iter().cloned().collect());
-        for stem in &result.stems {
-            if let Some(allowed) = &allowed {
-                if !allowed.contains(&stem.plugin_id) {
-                    continue;
-                }
-            }
-            let mut file_name = slugify(&stem.descriptor.name);
-            if file_name.is_empty() {
-                file_name = format!("stem_{}", stem.plugin_id.0);
-            }
-            let path =
-                settings
-                    .directory
-                    .join(format!("{}.{}", file_name, settings.format.extension()));
+        for (index, plan) in renderer.plan_stems(settings)?.iter().enumerate() {
+            let Some(clip) = renderer.render_stem(request, settings, plan, progress)? else {
+                remove_outputs(mixdown_path.iter().chain(&stem_paths));
+                return Ok(cancelled_report(project, result));
+            };
+            let path = settings.directory.join(format!(
+                "{}.{}",
+                settings.file_name(project, &plan.name, index),
+                settings.format.extension()
+            ));
             let target = RenderFile {
                 path: path.clone(),
                 format: settings.format,
                 dither: settings.dither,
             };
-            write_clip(&stem.clip, &target, stem.plugin_id.0)?;
+            write_clip(&clip, &target, &metadata, plan.lead.0)?;
+            files.push(RenderedFile {
+                path: path.clone(),
+                source: RenderSource::Stem(plan.source),
+                peak_dbfs: sample_peak(&clip),
+            });
             stem_paths.push(path);
         }
     }
@@ -432,6 +943,15 @@ fn write_outputs(
                     continue;
                 }
             }
+            let control = progress(RenderProgress {
+                frames_rendered: result.duration_frames,
+                total_frames: result.duration_frames,
+                stem: Some(stem.plugin_id),
+            });
+            if control == RenderControl::Cancel {
+                remove_outputs(mixdown_path.iter().chain(&stem_paths).chain(&freeze_paths));
+                return Ok(cancelled_report(project, result));
+            }
             let mut file_name = slugify(&stem.descriptor.name);
             if file_name.is_empty() {
                 file_name = format!("freeze_{}", stem.plugin_id.0);
@@ -447,7 +967,17 @@ fn write_outputs(
                 format: settings.format,
                 dither: settings.dither,
             };
-            write_clip(&stem.clip, &target, stem.plugin_id.0 ^ 0xDEADBEEF)?;
+            write_clip(
+                &stem.clip,
+                &target,
+                &metadata,
+                stem.plugin_id.0 ^ 0xDEADBEEF,
+            )?;
+            files.push(RenderedFile {
+                path: path.clone(),
+                source: RenderSource::Freeze(stem.plugin_id),
+                peak_dbfs: sample_peak(&stem.clip),
+            });
             freeze_paths.push(path);
         }
     }
@@ -457,15 +987,24 @@ fn write_outputs(
         mixdown: mixdown_path,
         stems: stem_paths,
         freezes: freeze_paths,
+        files,
         duration_frames: result.duration_frames,
+        cancelled: false,
+        normalization: result.normalization,
     })
 }
 
-fn write_clip(clip: &AudioClip, target: &RenderFile, seed: u64) -> Result<()> {
+fn write_clip(
+    clip: &AudioClip,
+    target: &RenderFile,
+    metadata: &RenderMetadata,
+    seed: u64,
+) -> Result<()> {
     target.ensure_parent()?;
     match target.format {
         RenderFormat::Wav => write_wav(clip, target, seed),
-        RenderFormat::Flac => write_flac(clip, target, seed),
+        RenderFormat::Flac { compression } => write_flac(clip, target, compression, metadata, seed),
+        RenderFormat::OggVorbis { quality } => write_ogg_vorbis(clip, target, quality, metadata),
     }
 }
 
@@ -483,9 +1022,7 @@ fn write_wav(clip: &AudioClip, target: &RenderFile, seed: u64) -> Result<()> {
         .with_context(|| format!("failed to create {}", target.path.display()))?;
     let mut writer = writer;
 
-    let mut dither = target.dither.map(|kind| match kind {
-        DitherKind::Tpdf => TpdfDither::new(seed, I24_MAX as f32),
-    });
+    let mut quantizer = Quantizer::new(24, target.dither, clip.channels(), seed);
 
     let frames = clip.frames();
     for frame in 0..frames {
@@ -495,7 +1032,7 @@ fn write_wav(clip: &AudioClip, target: &RenderFile, seed: u64) -> Result<()> {
                 .and_then(|channel| channel.get(frame))
                 .copied()
                 .unwrap_or(0.0);
-            let quantised = quantise_sample(sample, dither.as_mut());
+            let quantised = quantizer.quantize(channel, sample);
             writer.write_sample(quantised)?;
         }
     }
@@ -504,10 +1041,33 @@ fn write_wav(clip: &AudioClip, target: &RenderFile, seed: u64) -> Result<()> {
     Ok(())
 }
 
-fn write_flac(clip: &AudioClip, target: &RenderFile, seed: u64) -> Result<()> {
+/// Encoder settings approximating the reference encoder's `-0` to `-8`.
+fn flac_config(compression: u8) -> flacenc::config::Encoder {
+    let level = compression.min(8);
+    let mut config = flacenc::config::Encoder::default();
+    config.block_size = if level < 3 { 1152 } else { 4096 };
+    let decorrelate = level > 0;
+    config.stereo_coding.use_leftside = decorrelate;
+    config.stereo_coding.use_rightside = decorrelate;
+    config.stereo_coding.use_midside = decorrelate;
+    config.subframe_coding.use_lpc = level >= 3;
+    config.subframe_coding.qlpc.lpc_order = match level {
+        0..=3 => 6,
+        4 | 5 => 8,
+        _ => 12,
+    };
+    config
+}
+
+fn write_flac(
+    clip: &AudioClip,
+    target: &RenderFile,
+    compression: u8,
+    metadata: &RenderMetadata,
+    seed: u64,
+) -> Result<()> {
     use flacenc::bitsink::ByteSink;
     use flacenc::component::BitRepr;
-    use flacenc::config::Encoder as FlacEncoder;
     use flacenc::error::Verify;
     use flacenc::source::MemSource;
 
@@ -515,9 +1075,7 @@ fn write_flac(clip: &AudioClip, target: &RenderFile, seed: u64) -> Result<()> {
     let sample_rate = clip.sample_rate() as usize;
     let frames = clip.frames();
 
-    let mut dither = target.dither.map(|kind| match kind {
-        DitherKind::Tpdf => TpdfDither::new(seed, I24_MAX as f32),
-    });
+    let mut quantizer = Quantizer::new(24, target.dither, channels, seed);
 
     let mut buffer: Vec<i32> = Vec::with_capacity(frames * channels);
     for frame in 0..frames {
@@ -527,11 +1085,11 @@ fn write_flac(clip: &AudioClip, target: &RenderFile, seed: u64) -> Result<()> {
                 .and_then(|channel| channel.get(frame))
                 .copied()
                 .unwrap_or(0.0);
-            buffer.push(quantise_sample(sample, dither.as_mut()));
+            buffer.push(quantizer.quantize(channel, sample));
         }
     }
 
-    let config = FlacEncoder::default()
+    let config = flac_config(compression)
         .into_verified()
         .map_err(|(_, err)| anyhow!("invalid FLAC encoder configuration: {err}"))?;
     let source = MemSource::from_samples(&buffer, channels, 24, sample_rate);
@@ -542,18 +1100,105 @@ fn write_flac(clip: &AudioClip, target: &RenderFile, seed: u64) -> Result<()> {
     stream
         .write(&mut sink)
         .map_err(|err| anyhow!("failed to serialise FLAC stream: {err:?}"))?;
-    fs::write(&target.path, sink.as_slice())
+    let bytes = insert_vorbis_comment(sink.as_slice(), metadata)?;
+    fs::write(&target.path, bytes)
         .with_context(|| format!("failed to write {}", target.path.display()))?;
     Ok(())
 }
 
-const I24_MAX: i32 = 0x7F_FFFF;
+/// Vendor string recorded in Vorbis comment blocks.
+const TAG_VENDOR: &str = "Harmoniq Studio";
+
+/// Adds a `VORBIS_COMMENT` metadata block after the existing metadata of an
+/// encoded FLAC stream.
+fn insert_vorbis_comment(stream: &[u8], metadata: &RenderMetadata) -> Result<Vec<u8>> {
+    const LAST_BLOCK: u8 = 0x80;
+    const VORBIS_COMMENT: u8 = 4;
+
+    let comments = metadata.comments();
+    if comments.is_empty() {
+        return Ok(stream.to_vec());
+    }
+    if !stream.starts_with(b"fLaC") {
+        return Err(anyhow!("encoded FLAC stream is missing its signature"));
+    }
+
+    let mut body = Vec::new();
+    body.extend_from_slice(&(TAG_VENDOR.len() as u32).to_le_bytes());
+    body.extend_from_slice(TAG_VENDOR.as_bytes());
+    body.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for (key, value) in &comments {
+        let field = format!("{key}={value}");
+        body.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        body.extend_from_slice(field.as_bytes());
+    }
+
+    let mut position = 4;
+    loop {
+        let header = stream
+            .get(position..position + 4)
+            .ok_or_else(|| anyhow!("truncated FLAC metadata"))?;
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let end = position + 4 + length;
+        if header[0] & LAST_BLOCK == 0 {
+            position = end;
+            continue;
+        }
 
-fn quantise_sample(sample: f32, dither: Option<&mut TpdfDither>) -> i32 {
-    let mut value = sample;
-    if let Some(dither) = dither {
-        value += dither.sample();
+        let mut output = Vec::with_capacity(stream.len() + body.len() + 4);
+        output.extend_from_slice(&stream[..end]);
+        output[position] &= !LAST_BLOCK;
+        output.push(LAST_BLOCK | VORBIS_COMMENT);
+        output.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        output.extend_from_slice(&body);
+        output.extend_from_slice(&stream[end..]);
+        return Ok(output);
     }
-    let scaled = (value * I24_MAX as f32).round();
-    scaled.clamp(-(I24_MAX as f32), I24_MAX as f32).trunc() as i32
+}
+
+fn write_ogg_vorbis(
+    clip: &AudioClip,
+    target: &RenderFile,
+    quality: f32,
+    metadata: &RenderMetadata,
+) -> Result<()> {
+    use std::num::{NonZeroU32, NonZeroU8};
+    use vorbis_rs::{VorbisBitrateManagementStrategy, VorbisEncoderBuilder};
+
+    const BLOCK_FRAMES: usize = 4096;
+
+    let sample_rate = NonZeroU32::new(clip.sample_rate() as u32)
+        .ok_or_else(|| anyhow!("cannot encode Ogg Vorbis at a sample rate of zero"))?;
+    let channels = u8::try_from(clip.channels())
+        .ok()
+        .and_then(NonZeroU8::new)
+        .ok_or_else(|| anyhow!("Ogg Vorbis needs 1 to 255 channels"))?;
+
+    let file = fs::File::create(&target.path)
+        .with_context(|| format!("failed to create {}", target.path.display()))?;
+    let mut builder = VorbisEncoderBuilder::new(sample_rate, channels, file)?;
+    builder.bitrate_management_strategy(VorbisBitrateManagementStrategy::QualityVbr {
+        target_quality: quality.clamp(-0.1, 1.0),
+    });
+    for (key, value) in metadata.comments() {
+        builder.add_comment_tag(key, value)?;
+    }
+    let mut encoder = builder.build()?;
+
+    let frames = clip.frames();
+    let mut start = 0;
+    while start < frames {
+        let end = (start + BLOCK_FRAMES).min(frames);
+        let block: Vec<&[f32]> = (0..clip.channels())
+            .map(|channel| {
+                clip.channel(channel)
+                    .and_then(|samples| samples.get(start..end))
+                    .unwrap_or(&[])
+            })
+            .collect();
+        encoder.encode_audio_block(&block)?;
+        start = end;
+    }
+    encoder.finish()?;
+    Ok(())
 }
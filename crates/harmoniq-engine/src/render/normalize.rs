@@ -0,0 +1,136 @@
+//! Level normalisation applied to the rendered mixdown.
+//!
+//! Loudness normalisation takes two passes over the render: the first
+//! measures the integrated loudness, the second applies the make-up gain
+//! through a linked true-peak limiter so the gain never pushes the
+//! reconstructed signal past the requested ceiling.
+
+use super::analysis::{integrated_loudness_of, true_peak_envelope};
+
+/// Time the limiter takes to reach full gain reduction ahead of a peak.
+const LIMITER_ATTACK_S: f32 = 0.0015;
+/// Time constant of the limiter's recovery after a peak.
+const LIMITER_RELEASE_S: f32 = 0.05;
+
+/// Level target for the rendered mixdown.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Normalization {
+    #[default]
+    None,
+    /// Scales the mixdown so its sample peak sits at this level in dBFS.
+    Peak(f32),
+    /// Scales the mixdown to an integrated loudness of `target` LUFS and
+    /// limits inter-sample peaks to `true_peak_ceiling` dBTP.
+    Lufs { target: f32, true_peak_ceiling: f32 },
+}
+
+/// Loudness of the mixdown around normalisation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalizationReport {
+    /// Integrated loudness before normalisation.
+    pub pre_lufs: f32,
+    /// Integrated loudness of the delivered mixdown.
+    pub post_lufs: f32,
+    /// Static gain applied ahead of the limiter.
+    pub gain_db: f32,
+}
+
+/// Normalises planar `channels` in place. Returns `None` when nothing was
+/// requested or the render is silent.
+pub(super) fn apply(
+    channels: &mut [Vec<f32>],
+    sample_rate: f32,
+    normalization: Normalization,
+) -> Option<NormalizationReport> {
+    let (pre_lufs, gain_db) = match normalization {
+        Normalization::None => return None,
+        Normalization::Peak(ceiling) => {
+            let peak = channels
+                .iter()
+                .flatten()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            if peak <= 0.0 {
+                return None;
+            }
+            (
+                loudness(channels, sample_rate),
+                ceiling - 20.0 * peak.log10(),
+            )
+        }
+        Normalization::Lufs { target, .. } => {
+            let pre_lufs = loudness(channels, sample_rate);
+            if !pre_lufs.is_finite() {
+                return None;
+            }
+            (pre_lufs, target - pre_lufs)
+        }
+    };
+
+    let gain = 10f32.powf(gain_db / 20.0);
+    for sample in channels.iter_mut().flatten() {
+        *sample *= gain;
+    }
+    if let Normalization::Lufs {
+        true_peak_ceiling, ..
+    } = normalization
+    {
+        limit_true_peak(channels, sample_rate, 10f32.powf(true_peak_ceiling / 20.0));
+    }
+
+    Some(NormalizationReport {
+        pre_lufs,
+        post_lufs: loudness(channels, sample_rate),
+        gain_db,
+    })
+}
+
+fn loudness(channels: &[Vec<f32>], sample_rate: f32) -> f32 {
+    let slices: Vec<&[f32]> = channels.iter().map(Vec::as_slice).collect();
+    integrated_loudness_of(&slices, sample_rate)
+}
+
+/// Look-ahead limiter linked across channels. The gain curve is built
+/// offline: it never exceeds what each frame's true peak allows, ramps into
+/// a reduction over the attack time before the peak arrives and recovers
+/// exponentially afterwards.
+fn limit_true_peak(channels: &mut [Vec<f32>], sample_rate: f32, ceiling: f32) {
+    let envelope = peaks(channels);
+    if envelope.iter().all(|peak| *peak <= ceiling) {
+        return;
+    }
+
+    let mut gain: Vec<f32> = envelope
+        .iter()
+        .map(|peak| if *peak > ceiling { ceiling / peak } else { 1.0 })
+        .collect();
+    let release = (-1.0 / (LIMITER_RELEASE_S * sample_rate).max(1.0)).exp();
+    for index in 1..gain.len() {
+        let recovered = 1.0 - (1.0 - gain[index - 1]) * release;
+        gain[index] = gain[index].min(recovered);
+    }
+    let attack_step = 1.0 / (LIMITER_ATTACK_S * sample_rate).max(1.0);
+    for index in (0..gain.len().saturating_sub(1)).rev() {
+        gain[index] = gain[index].min(gain[index + 1] + attack_step);
+    }
+
+    for samples in channels.iter_mut() {
+        for (sample, gain) in samples.iter_mut().zip(&gain) {
+            *sample *= gain;
+        }
+    }
+
+    // The interpolated peaks move with the gain around them; trim whatever
+    // the curve left above the ceiling.
+    let peak = peaks(channels).into_iter().fold(0.0f32, f32::max);
+    if peak > ceiling {
+        let trim = ceiling / peak;
+        for sample in channels.iter_mut().flatten() {
+            *sample *= trim;
+        }
+    }
+}
+
+fn peaks(channels: &[Vec<f32>]) -> Vec<f32> {
+    let slices: Vec<&[f32]> = channels.iter().map(Vec::as_slice).collect();
+    true_peak_envelope(&slices)
+}
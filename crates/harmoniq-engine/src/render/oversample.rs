@@ -0,0 +1,147 @@
+//! Oversampling used to run the master chain at a higher rate during
+//! offline renders, built on the harmoniq-dsp polyphase
+//! [`Oversampler`](harmoniq_dsp::oversample::Oversampler).
+
+use std::ops::{Deref, DerefMut};
+
+use harmoniq_dsp::oversample::Oversampler as Filters;
+
+use crate::{engine::HarmoniqEngine, AudioBuffer};
+
+enum Factor {
+    X2(Filters<2>),
+    X4(Filters<4>),
+}
+
+/// Upsamples a block, hands it to a closure at the oversampled rate and
+/// decimates the result back, with linear-phase windowed-sinc filters.
+pub(crate) struct Oversampler {
+    filters: Factor,
+    upsampled: AudioBuffer,
+}
+
+impl Oversampler {
+    /// Oversamples by 2 for a `factor` of 2 and by 4 for anything higher.
+    pub(crate) fn new(factor: usize, channels: usize, block_size: usize) -> Self {
+        let (filters, factor) = match factor {
+            0..=2 => (Factor::X2(Filters::with_channels(channels)), 2),
+            _ => (Factor::X4(Filters::with_channels(channels)), 4),
+        };
+        Self {
+            filters,
+            upsampled: AudioBuffer::new(channels, block_size * factor),
+        }
+    }
+
+    pub(crate) fn factor(&self) -> usize {
+        match &self.filters {
+            Factor::X2(filters) => filters.factor(),
+            Factor::X4(filters) => filters.factor(),
+        }
+    }
+
+    /// Delay in base rate frames added by the filters.
+    pub(crate) fn latency(&self) -> usize {
+        match &self.filters {
+            Factor::X2(filters) => filters.latency(),
+            Factor::X4(filters) => filters.latency(),
+        }
+    }
+
+    pub(crate) fn process<F>(&mut self, buffer: &mut AudioBuffer, mut process: F)
+    where
+        F: FnMut(&mut AudioBuffer),
+    {
+        let factor = self.factor();
+        let frames = buffer.len();
+        let channels = buffer.channel_count().min(self.upsampled.channel_count());
+        if self.upsampled.len() != frames * factor {
+            self.upsampled
+                .resize(self.upsampled.channel_count(), frames * factor);
+        }
+
+        for channel in 0..channels {
+            let input = buffer.channel(channel);
+            let upsampled = self.upsampled.channel_mut(channel);
+            match &mut self.filters {
+                Factor::X2(filters) => filters.upsample(channel, input, upsampled),
+                Factor::X4(filters) => filters.upsample(channel, input, upsampled),
+            }
+        }
+
+        process(&mut self.upsampled);
+
+        for channel in 0..channels {
+            let upsampled = self.upsampled.channel(channel);
+            let output = buffer.channel_mut(channel);
+            match &mut self.filters {
+                Factor::X2(filters) => filters.downsample(channel, upsampled, output),
+                Factor::X4(filters) => filters.downsample(channel, upsampled, output),
+            }
+        }
+    }
+}
+
+/// Runs an engine's master chain oversampled until it is finished or
+/// dropped, so a render that fails part way still leaves the chain at the
+/// base rate.
+pub(crate) struct MasterOversample<'a> {
+    engine: &'a mut HarmoniqEngine,
+    latency: usize,
+}
+
+impl<'a> MasterOversample<'a> {
+    /// Oversamples the master chain by `factor` when it has inserts to run;
+    /// otherwise the engine is left as it is.
+    pub(crate) fn enable(engine: &'a mut HarmoniqEngine, factor: usize) -> anyhow::Result<Self> {
+        let mut latency = 0;
+        if factor > 1 && engine.master_insert_count() > 0 {
+            latency = match engine.set_master_oversample(factor) {
+                Ok(latency) => latency,
+                Err(err) => {
+                    let _ = engine.set_master_oversample(1);
+                    return Err(err);
+                }
+            };
+        }
+        Ok(Self { engine, latency })
+    }
+
+    /// Delay in base rate frames the filters add to the master output.
+    pub(crate) fn latency(&self) -> usize {
+        self.latency
+    }
+
+    /// Puts the master chain back at the base rate, reporting a failure to
+    /// re-prepare its processors.
+    pub(crate) fn finish(mut self) -> anyhow::Result<()> {
+        if std::mem::take(&mut self.latency) > 0 {
+            self.engine.set_master_oversample(1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Deref for MasterOversample<'_> {
+    type Target = HarmoniqEngine;
+
+    fn deref(&self) -> &HarmoniqEngine {
+        self.engine
+    }
+}
+
+impl DerefMut for MasterOversample<'_> {
+    fn deref_mut(&mut self) -> &mut HarmoniqEngine {
+        self.engine
+    }
+}
+
+impl Drop for MasterOversample<'_> {
+    fn drop(&mut self) {
+        if self.latency > 0 {
+            if let Err(err) = self.engine.set_master_oversample(1) {
+                tracing::warn!("failed to restore the master chain rate: {err:#}");
+            }
+        }
+    }
+}
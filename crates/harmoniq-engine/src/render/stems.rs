@@ -0,0 +1,346 @@
+//! Stem export.
+//!
+//! Every stem is rendered in a pass of its own over a copy of the graph in
+//! which only the stem's plugins reach the master. A track is a plugin that
+//! no other plugin feeds, together with the chain downstream of it that no
+//! other track reaches. Plugins reached by several tracks, such as send
+//! effects, are shared: a wet stem keeps them audible, fed only by the
+//! soloed tracks, while a dry stem mutes them.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use petgraph::stable_graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use super::{
+    slugify, OfflineRenderer, RenderControl, RenderDuration, RenderProgress, RenderRequest,
+    StemSettings,
+};
+use crate::graph::{Connection, GraphHandle, NodeHandle, NodeKind, SignalKind};
+use crate::plugin::PluginId;
+use crate::AudioClip;
+
+/// File name template used when [`StemSettings::name_template`] is not
+/// overridden.
+pub const DEFAULT_STEM_TEMPLATE: &str = "{project}_{track}_{index}";
+
+/// How a project is divided into stems.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum StemSplit {
+    /// One stem per track.
+    #[default]
+    PerTrack,
+    /// One stem per mixer bus, holding every track routed into it.
+    PerBus,
+    /// One stem per named group of tracks, such as the members of a VCA.
+    Groups(Vec<StemGroup>),
+}
+
+/// Tracks exported together as one stem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StemGroup {
+    pub name: String,
+    /// Any plugin on a track pulls in the whole track.
+    pub plugins: Vec<PluginId>,
+}
+
+/// What a stem was rendered from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StemSource {
+    /// The track whose first plugin is this one.
+    Track(PluginId),
+    /// A mixer bus node.
+    Bus(NodeHandle),
+    /// An entry of [`StemSplit::Groups`], by index.
+    Group(usize),
+}
+
+/// A stem to render: its source, the name used for `{track}` and the plugin
+/// nodes left audible.
+pub(super) struct StemPlan {
+    pub source: StemSource,
+    pub name: String,
+    /// Plugin reported in [`RenderProgress::stem`] while the stem renders.
+    pub lead: PluginId,
+    members: HashSet<NodeIndex>,
+}
+
+impl StemSettings {
+    /// Expands the name template for the stem at `index` (counted from 0,
+    /// written from 01) of `project`.
+    pub(super) fn file_name(&self, project: &str, track: &str, index: usize) -> String {
+        let mut track = slugify(track);
+        if track.is_empty() {
+            track = "track".to_owned();
+        }
+        let name = self
+            .name_template
+            .replace("{project}", &slugify(project))
+            .replace("{track}", &track)
+            .replace("{index}", &format!("{:02}", index + 1));
+        if name.trim().is_empty() {
+            format!("stem_{:02}", index + 1)
+        } else {
+            name
+        }
+    }
+}
+
+impl OfflineRenderer {
+    /// Lists the stems `settings` asks for in the current graph.
+    pub(super) fn plan_stems(&self, settings: &StemSettings) -> Result<Vec<StemPlan>> {
+        let graph = self
+            .engine
+            .graph()
+            .ok_or_else(|| anyhow!("project has no active processing graph"))?;
+        let name_of = |id: PluginId| {
+            self.engine
+                .plugin_descriptor(id)
+                .map(|descriptor| descriptor.name)
+                .unwrap_or_default()
+        };
+        let tracks = tracks(&graph);
+
+        let mut plans = Vec::new();
+        match &settings.split {
+            StemSplit::PerTrack => {
+                for (root, members) in tracks {
+                    let lead = plugin_id(&graph, root);
+                    plans.push(StemPlan {
+                        source: StemSource::Track(lead),
+                        name: name_of(lead),
+                        lead,
+                        members,
+                    });
+                }
+            }
+            StemSplit::PerBus => {
+                for bus in graph.graph.node_indices() {
+                    let NodeKind::MixerBus { name } = &graph.graph[bus] else {
+                        continue;
+                    };
+                    let routed: Vec<&(NodeIndex, HashSet<NodeIndex>)> = tracks
+                        .iter()
+                        .filter(|(_, members)| {
+                            members.iter().any(|node| {
+                                graph
+                                    .graph
+                                    .edges_directed(*node, Direction::Outgoing)
+                                    .any(|edge| {
+                                        edge.target() == bus
+                                            && edge.weight().signal == SignalKind::Audio
+                                    })
+                            })
+                        })
+                        .collect();
+                    let Some((root, _)) = routed.first() else {
+                        continue;
+                    };
+                    plans.push(StemPlan {
+                        source: StemSource::Bus(NodeHandle(bus)),
+                        name: name.clone(),
+                        lead: plugin_id(&graph, *root),
+                        members: routed
+                            .iter()
+                            .flat_map(|(_, members)| members.iter().copied())
+                            .collect(),
+                    });
+                }
+            }
+            StemSplit::Groups(groups) => {
+                let present = graph.plugin_ids();
+                for (index, group) in groups.iter().enumerate() {
+                    let wanted: HashSet<PluginId> = group.plugins.iter().copied().collect();
+                    let mut members: HashSet<NodeIndex> = tracks
+                        .iter()
+                        .filter(|(_, members)| {
+                            members
+                                .iter()
+                                .any(|node| wanted.contains(&plugin_id(&graph, *node)))
+                        })
+                        .flat_map(|(_, members)| members.iter().copied())
+                        .collect();
+                    // Shared plugins named directly join the group on their own.
+                    members.extend(
+                        graph
+                            .plugin_nodes
+                            .iter()
+                            .copied()
+                            .filter(|node| wanted.contains(&plugin_id(&graph, *node))),
+                    );
+                    let Some(lead) = group
+                        .plugins
+                        .iter()
+                        .copied()
+                        .find(|id| present.contains(id))
+                    else {
+                        continue;
+                    };
+                    plans.push(StemPlan {
+                        source: StemSource::Group(index),
+                        name: group.name.clone(),
+                        lead,
+                        members,
+                    });
+                }
+            }
+        }
+
+        if let Some(allowed) = &settings.plugins {
+            plans.retain(|plan| {
+                plan.members
+                    .iter()
+                    .any(|node| allowed.contains(&plugin_id(&graph, *node)))
+            });
+        }
+        Ok(plans)
+    }
+
+    /// Renders one stem over the request's duration plus the configured
+    /// tail, then restores the project graph. Returns `None` if `progress`
+    /// cancelled the pass.
+    pub(super) fn render_stem(
+        &mut self,
+        request: &RenderRequest,
+        settings: &StemSettings,
+        plan: &StemPlan,
+        progress: &mut dyn FnMut(RenderProgress) -> RenderControl,
+    ) -> Result<Option<AudioClip>> {
+        let original = self
+            .engine
+            .graph()
+            .ok_or_else(|| anyhow!("project has no active processing graph"))?;
+        let sample_rate = self.config.sample_rate;
+        let tail = (settings.tail_seconds.max(0.0) * sample_rate).round() as usize;
+        let pass = RenderRequest {
+            duration: RenderDuration::Frames(request.duration.frames(sample_rate) + tail),
+            speed: request.speed,
+            master_oversample: request.master_oversample,
+            ..RenderRequest::default()
+        };
+
+        self.engine
+            .replace_graph(solo_graph(&original, &plan.members, settings.wet))?;
+        self.engine.reset_render_state()?;
+        let rendered = self.render_with_progress(&pass, |update| {
+            progress(RenderProgress {
+                stem: Some(plan.lead),
+                ..update
+            })
+        });
+        self.engine.replace_graph(original)?;
+        let result = rendered?;
+        Ok((!result.cancelled).then_some(result.mixdown))
+    }
+}
+
+fn plugin_id(graph: &GraphHandle, node: NodeIndex) -> PluginId {
+    match &graph.graph[node] {
+        NodeKind::Plugin { id } => *id,
+        _ => unreachable!("stem members are plugin nodes"),
+    }
+}
+
+/// Plugins fed with audio by `node`.
+fn plugin_targets(graph: &GraphHandle, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+    graph
+        .graph
+        .edges_directed(node, Direction::Outgoing)
+        .filter(|edge| edge.weight().signal == SignalKind::Audio)
+        .map(|edge| edge.target())
+        .filter(|target| graph.node_lookup.contains_key(target))
+}
+
+/// `start` and every plugin downstream of it.
+fn downstream(
+    graph: &GraphHandle,
+    start: impl IntoIterator<Item = NodeIndex>,
+) -> HashSet<NodeIndex> {
+    let mut reached = HashSet::new();
+    let mut pending: Vec<NodeIndex> = start.into_iter().collect();
+    while let Some(node) = pending.pop() {
+        if reached.insert(node) {
+            pending.extend(plugin_targets(graph, node));
+        }
+    }
+    reached
+}
+
+/// Each track's first plugin with the plugins only that track reaches, in
+/// graph order.
+fn tracks(graph: &GraphHandle) -> Vec<(NodeIndex, HashSet<NodeIndex>)> {
+    let fed: HashSet<NodeIndex> = graph
+        .plugin_nodes
+        .iter()
+        .flat_map(|node| plugin_targets(graph, *node))
+        .collect();
+    let reach: Vec<(NodeIndex, HashSet<NodeIndex>)> = graph
+        .plugin_nodes
+        .iter()
+        .copied()
+        .filter(|node| !fed.contains(node))
+        .map(|root| (root, downstream(graph, [root])))
+        .collect();
+    let mut owners: HashMap<NodeIndex, usize> = HashMap::new();
+    for node in reach.iter().flat_map(|(_, nodes)| nodes) {
+        *owners.entry(*node).or_default() += 1;
+    }
+    reach
+        .into_iter()
+        .map(|(root, nodes)| {
+            let exclusive = nodes.into_iter().filter(|node| owners[node] == 1).collect();
+            (root, exclusive)
+        })
+        .collect()
+}
+
+/// Copy of `graph` in which only `members`, and with `wet` the shared
+/// plugins they feed, reach the master. Muted plugins are also cut off from
+/// the shared plugins so they cannot leak in through a send.
+fn solo_graph(graph: &GraphHandle, members: &HashSet<NodeIndex>, wet: bool) -> GraphHandle {
+    let audible = if wet {
+        downstream(graph, members.iter().copied())
+    } else {
+        members.clone()
+    };
+    let mut solo = graph.clone();
+    let master = solo.master;
+    for node in graph
+        .plugin_nodes
+        .iter()
+        .copied()
+        .filter(|node| !audible.contains(node))
+    {
+        let leaks: Vec<_> = solo
+            .graph
+            .edges_directed(node, Direction::Outgoing)
+            .filter(|edge| {
+                edge.weight().signal == SignalKind::Audio && audible.contains(&edge.target())
+            })
+            .map(|edge| edge.id())
+            .collect();
+        for edge in leaks {
+            solo.graph.remove_edge(edge);
+        }
+        // Plugins without a master edge are mixed at unity, so mute them
+        // with an explicit silent one.
+        match solo.graph.find_edge(node, master) {
+            Some(edge) => solo.graph[edge].gain = 0.0,
+            None => {
+                solo.graph.add_edge(
+                    node,
+                    master,
+                    Connection {
+                        gain: 0.0,
+                        from_pin: 0,
+                        to_pin: 0,
+                        signal: SignalKind::Audio,
+                    },
+                );
+            }
+        }
+    }
+    solo
+}
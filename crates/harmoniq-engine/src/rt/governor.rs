@@ -0,0 +1,203 @@
+//! CPU-overload protection that sheds voices when the audio thread falls
+//! behind its deadline.
+//!
+//! The governor is fed one [`AudioMetrics`] snapshot per block. When block
+//! time approaches the callback period or new xruns appear it lowers a global
+//! voice cap and steals the quietest voices across every instrument. Once the
+//! load has stayed low for a while the cap is raised again step by step until
+//! polyphony is unrestricted.
+
+use super::AudioMetrics;
+
+/// A voice currently sounding on an instrument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoiceLevel {
+    /// Instrument-defined identifier handed back to [`VoiceShedding::steal_voice`].
+    pub voice: u32,
+    /// Current output level, used to pick the quietest voices first.
+    pub level: f32,
+}
+
+/// Instruments whose voices can be stolen by the [`LoadGovernor`].
+pub trait VoiceShedding {
+    /// Appends every active voice to `out`.
+    fn active_voices(&self, out: &mut Vec<VoiceLevel>);
+
+    /// Releases a voice quickly; implementations should fade rather than cut.
+    fn steal_voice(&mut self, voice: u32);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadGovernorConfig {
+    /// Callback period the block time is compared against.
+    pub period_ns: u64,
+    /// Load (block time / period) at which voices start being shed.
+    pub overload_ratio: f32,
+    /// Load under which the governor counts towards recovery.
+    pub recover_ratio: f32,
+    /// Fraction of the voice cap removed per overloaded block, and restored
+    /// per recovery step.
+    pub step_ratio: f32,
+    /// The cap never drops below this many voices.
+    pub min_voices: usize,
+    /// Consecutive calm blocks required before the cap is raised.
+    pub recovery_blocks: u32,
+}
+
+impl LoadGovernorConfig {
+    pub fn new(period_ns: u64) -> Self {
+        Self {
+            period_ns,
+            overload_ratio: 0.9,
+            recover_ratio: 0.6,
+            step_ratio: 0.25,
+            min_voices: 4,
+            recovery_blocks: 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GovernorState {
+    /// Polyphony is unrestricted.
+    Normal,
+    /// A voice cap is active and was tightened or held this block.
+    Shedding { limit: usize },
+    /// A voice cap is active and being relaxed while load stays low.
+    Recovering { limit: usize },
+}
+
+pub struct LoadGovernor {
+    config: LoadGovernorConfig,
+    last_xruns: Option<u64>,
+    limit: Option<usize>,
+    peak_voices: usize,
+    calm_blocks: u32,
+    scratch: Vec<(usize, VoiceLevel)>,
+    voices: Vec<VoiceLevel>,
+}
+
+impl LoadGovernor {
+    pub fn new(config: LoadGovernorConfig) -> Self {
+        Self {
+            config,
+            last_xruns: None,
+            limit: None,
+            peak_voices: 0,
+            calm_blocks: 0,
+            scratch: Vec::with_capacity(256),
+            voices: Vec::with_capacity(64),
+        }
+    }
+
+    pub fn config(&self) -> &LoadGovernorConfig {
+        &self.config
+    }
+
+    /// Current global voice cap, `None` while polyphony is unrestricted.
+    pub fn voice_limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Block time relative to the callback period.
+    pub fn load(&self, metrics: &AudioMetrics) -> f32 {
+        if self.config.period_ns == 0 {
+            return 0.0;
+        }
+        metrics.last_block_ns as f32 / self.config.period_ns as f32
+    }
+
+    pub fn reset(&mut self) {
+        self.last_xruns = None;
+        self.limit = None;
+        self.peak_voices = 0;
+        self.calm_blocks = 0;
+    }
+
+    /// Updates the voice cap from the latest metrics and steals voices across
+    /// `instruments` until the cap is respected.
+    pub fn update(
+        &mut self,
+        metrics: AudioMetrics,
+        instruments: &mut [&mut dyn VoiceShedding],
+    ) -> GovernorState {
+        self.collect_voices(instruments);
+        let total = self.scratch.len();
+
+        let new_xruns = self
+            .last_xruns
+            .is_some_and(|previous| metrics.xruns > previous);
+        self.last_xruns = Some(metrics.xruns);
+        let load = self.load(&metrics);
+
+        let state = if new_xruns || load >= self.config.overload_ratio {
+            self.calm_blocks = 0;
+            let current = match self.limit {
+                Some(limit) => limit.min(total.max(self.config.min_voices)),
+                None => {
+                    self.peak_voices = total;
+                    total
+                }
+            };
+            let limit = current
+                .saturating_sub(self.step(current))
+                .max(self.config.min_voices);
+            self.limit = Some(limit);
+            GovernorState::Shedding { limit }
+        } else if let Some(limit) = self.limit {
+            if load <= self.config.recover_ratio {
+                self.calm_blocks += 1;
+            } else {
+                self.calm_blocks = 0;
+            }
+            if self.calm_blocks >= self.config.recovery_blocks {
+                self.calm_blocks = 0;
+                let raised = limit + self.step(self.peak_voices.max(limit));
+                if raised >= self.peak_voices {
+                    self.limit = None;
+                } else {
+                    self.limit = Some(raised);
+                }
+            }
+            match self.limit {
+                Some(limit) => GovernorState::Recovering { limit },
+                None => GovernorState::Normal,
+            }
+        } else {
+            GovernorState::Normal
+        };
+
+        if let Some(limit) = self.limit {
+            self.shed_to(limit, instruments);
+        }
+        state
+    }
+
+    fn step(&self, voices: usize) -> usize {
+        ((voices as f32 * self.config.step_ratio).ceil() as usize).max(1)
+    }
+
+    fn collect_voices(&mut self, instruments: &mut [&mut dyn VoiceShedding]) {
+        self.scratch.clear();
+        for (index, instrument) in instruments.iter().enumerate() {
+            self.voices.clear();
+            instrument.active_voices(&mut self.voices);
+            self.scratch
+                .extend(self.voices.iter().map(|voice| (index, *voice)));
+        }
+    }
+
+    fn shed_to(&mut self, limit: usize, instruments: &mut [&mut dyn VoiceShedding]) {
+        if self.scratch.len() <= limit {
+            return;
+        }
+        let excess = self.scratch.len() - limit;
+        self.scratch
+            .sort_unstable_by(|a, b| a.1.level.total_cmp(&b.1.level));
+        for (index, voice) in self.scratch.iter().take(excess) {
+            if let Some(instrument) = instruments.get_mut(*index) {
+                instrument.steal_voice(voice.voice);
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod backend;
 pub mod cpu;
+pub mod governor;
 pub mod metrics;
 pub mod thread;
 
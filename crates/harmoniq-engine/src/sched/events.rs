@@ -2,10 +2,23 @@ use std::cell::UnsafeCell;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::vec::Vec;
 
+use crate::expression::NoteController;
+
 #[derive(Clone, Debug)]
 pub enum Ev {
     Midi([u8; 3], u32),
-    Param { id: u32, norm: f32, sample: u32 },
+    Param {
+        id: u32,
+        norm: f32,
+        sample: u32,
+    },
+    NoteExpression {
+        channel: u8,
+        note: u8,
+        controller: NoteController,
+        value: f32,
+        sample: u32,
+    },
 }
 
 impl Default for Ev {
@@ -19,6 +32,7 @@ impl Ev {
         match *self {
             Ev::Midi(_, sample) => sample as u64,
             Ev::Param { sample, .. } => sample as u64,
+            Ev::NoteExpression { sample, .. } => sample as u64,
         }
     }
 
@@ -26,6 +40,7 @@ impl Ev {
         match self {
             Ev::Midi(_, s) => *s = sample,
             Ev::Param { sample: s, .. } => *s = sample,
+            Ev::NoteExpression { sample: s, .. } => *s = sample,
         }
     }
 }
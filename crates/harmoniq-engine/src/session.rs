@@ -0,0 +1,301 @@
+//! Session-view clip launcher: a grid of clip slots per track that start and
+//! stop on quantised musical boundaries.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::time::{SharedTempoMap, TempoMap};
+
+/// Musical grid used to align launches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LaunchQuantize {
+    Immediate,
+    Beat,
+    #[default]
+    Bar,
+}
+
+/// Clip placed in a session slot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionClip {
+    pub name: String,
+    pub length_beats: f64,
+    pub looping: bool,
+}
+
+impl SessionClip {
+    pub fn new(name: impl Into<String>, length_beats: f64) -> Self {
+        Self {
+            name: name.into(),
+            length_beats: length_beats.max(0.0),
+            looping: true,
+        }
+    }
+}
+
+/// Launch that is waiting for its quantise boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledLaunch {
+    pub track: usize,
+    pub slot: usize,
+    pub start_sample: u64,
+    /// Slot that stops on the same boundary, if one was playing.
+    pub stops: Option<usize>,
+}
+
+/// Start/stop transitions emitted while advancing the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    ClipStarted {
+        track: usize,
+        slot: usize,
+        sample: u64,
+    },
+    ClipStopped {
+        track: usize,
+        slot: usize,
+        sample: u64,
+    },
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SessionError {
+    #[error("track {0} does not exist")]
+    TrackOutOfRange(usize),
+    #[error("slot {0} does not exist")]
+    SlotOutOfRange(usize),
+    #[error("slot {0} is empty")]
+    EmptySlot(usize),
+}
+
+#[derive(Debug, Clone, Default)]
+struct SessionTrack {
+    slots: Vec<Option<SessionClip>>,
+    playing: Option<usize>,
+    pending: Option<PendingChange>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PendingChange {
+    slot: Option<usize>,
+    at: u64,
+}
+
+/// Grid of tracks by scenes with quantised clip launching.
+#[derive(Debug, Clone)]
+pub struct SessionView {
+    tracks: Vec<SessionTrack>,
+    scenes: usize,
+    quantize: LaunchQuantize,
+    sample_rate: f32,
+    tempo_map: SharedTempoMap,
+    position: u64,
+}
+
+impl SessionView {
+    pub fn new(tracks: usize, scenes: usize, sample_rate: f32, tempo_map: SharedTempoMap) -> Self {
+        let track = SessionTrack {
+            slots: vec![None; scenes],
+            ..SessionTrack::default()
+        };
+        Self {
+            tracks: vec![track; tracks],
+            scenes,
+            quantize: LaunchQuantize::default(),
+            sample_rate: sample_rate.max(1.0),
+            tempo_map,
+            position: 0,
+        }
+    }
+
+    pub fn track_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn scene_count(&self) -> usize {
+        self.scenes
+    }
+
+    pub fn quantize(&self) -> LaunchQuantize {
+        self.quantize
+    }
+
+    pub fn set_quantize(&mut self, quantize: LaunchQuantize) {
+        self.quantize = quantize;
+    }
+
+    pub fn set_tempo_map(&mut self, tempo_map: SharedTempoMap) {
+        self.tempo_map = tempo_map;
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn set_clip(
+        &mut self,
+        track: usize,
+        slot: usize,
+        clip: Option<SessionClip>,
+    ) -> Result<(), SessionError> {
+        *self.slot_mut(track, slot)? = clip;
+        Ok(())
+    }
+
+    pub fn clip(&self, track: usize, slot: usize) -> Option<&SessionClip> {
+        self.tracks.get(track)?.slots.get(slot)?.as_ref()
+    }
+
+    pub fn playing_slot(&self, track: usize) -> Option<usize> {
+        self.tracks.get(track)?.playing
+    }
+
+    pub fn pending_launch(&self, track: usize) -> Option<ScheduledLaunch> {
+        let state = self.tracks.get(track)?;
+        let pending = state.pending?;
+        Some(ScheduledLaunch {
+            track,
+            slot: pending.slot?,
+            start_sample: pending.at,
+            stops: state.playing,
+        })
+    }
+
+    /// Schedules `slot` to start on the next quantise boundary, stopping the
+    /// clip currently playing on the same track at that boundary.
+    pub fn launch_clip(
+        &mut self,
+        track: usize,
+        slot: usize,
+    ) -> Result<ScheduledLaunch, SessionError> {
+        if self.slot_mut(track, slot)?.is_none() {
+            return Err(SessionError::EmptySlot(slot));
+        }
+        let at = self.next_boundary(self.position);
+        let state = &mut self.tracks[track];
+        state.pending = Some(PendingChange {
+            slot: Some(slot),
+            at,
+        });
+        Ok(ScheduledLaunch {
+            track,
+            slot,
+            start_sample: at,
+            stops: state.playing,
+        })
+    }
+
+    /// Launches every non-empty slot of a scene.
+    pub fn launch_scene(&mut self, scene: usize) -> Result<Vec<ScheduledLaunch>, SessionError> {
+        if scene >= self.scenes {
+            return Err(SessionError::SlotOutOfRange(scene));
+        }
+        let mut launches = Vec::new();
+        for track in 0..self.tracks.len() {
+            if self.clip(track, scene).is_some() {
+                launches.push(self.launch_clip(track, scene)?);
+            }
+        }
+        Ok(launches)
+    }
+
+    /// Stops the track's clip on the next quantise boundary.
+    pub fn stop_track(&mut self, track: usize) -> Result<Option<u64>, SessionError> {
+        let at = self.next_boundary(self.position);
+        let state = self
+            .tracks
+            .get_mut(track)
+            .ok_or(SessionError::TrackOutOfRange(track))?;
+        if state.playing.is_none() {
+            state.pending = None;
+            return Ok(None);
+        }
+        state.pending = Some(PendingChange { slot: None, at });
+        Ok(Some(at))
+    }
+
+    /// Advances the session playhead by `frames`, emitting the transitions
+    /// whose boundary falls inside the advanced range.
+    pub fn advance(&mut self, frames: u64, events: &mut Vec<SessionEvent>) {
+        let end = self.position.saturating_add(frames);
+        for (index, state) in self.tracks.iter_mut().enumerate() {
+            let Some(pending) = state.pending else {
+                continue;
+            };
+            if pending.at >= end {
+                continue;
+            }
+            // Relaunching the playing slot restarts it, so it also stops first.
+            if let Some(playing) = state.playing.take() {
+                events.push(SessionEvent::ClipStopped {
+                    track: index,
+                    slot: playing,
+                    sample: pending.at,
+                });
+            }
+            if let Some(slot) = pending.slot {
+                events.push(SessionEvent::ClipStarted {
+                    track: index,
+                    slot,
+                    sample: pending.at,
+                });
+                state.playing = Some(slot);
+            }
+            state.pending = None;
+        }
+        self.position = end;
+    }
+
+    /// Moves the playhead without emitting events, e.g. after a transport seek.
+    pub fn seek(&mut self, sample: u64) {
+        self.position = sample;
+    }
+
+    fn slot_mut(
+        &mut self,
+        track: usize,
+        slot: usize,
+    ) -> Result<&mut Option<SessionClip>, SessionError> {
+        let state = self
+            .tracks
+            .get_mut(track)
+            .ok_or(SessionError::TrackOutOfRange(track))?;
+        state
+            .slots
+            .get_mut(slot)
+            .ok_or(SessionError::SlotOutOfRange(slot))
+    }
+
+    fn next_boundary(&self, sample: u64) -> u64 {
+        next_quantize_boundary(&self.tempo_map, self.sample_rate, sample, self.quantize)
+    }
+}
+
+/// First sample at or after `sample` that lies on the requested grid.
+pub fn next_quantize_boundary(
+    tempo_map: &TempoMap,
+    sample_rate: f32,
+    sample: u64,
+    quantize: LaunchQuantize,
+) -> u64 {
+    if quantize == LaunchQuantize::Immediate {
+        return sample;
+    }
+    let Some(mut beat) = tempo_map.first_beat_at_or_after(sample_rate, sample) else {
+        return sample;
+    };
+    if quantize == LaunchQuantize::Beat {
+        return beat.sample;
+    }
+    // A bar holds at most 255 beats, so this always terminates.
+    for _ in 0..=u8::MAX as usize {
+        if beat.is_downbeat() {
+            return beat.sample;
+        }
+        match tempo_map.beat_after(sample_rate, &beat) {
+            Some(next) => beat = next,
+            None => break,
+        }
+    }
+    beat.sample
+}
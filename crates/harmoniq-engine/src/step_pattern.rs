@@ -0,0 +1,138 @@
+//! Step-sequencer patterns rendered into playlist pattern notes.
+//!
+//! Each step occupies a fixed window of ticks. Ratcheted steps split that
+//! window into evenly spaced retriggers, optionally ramping the velocity from
+//! the step velocity towards a target across the repeats.
+
+use harmoniq_playlist::state::PatternNote;
+use serde::{Deserialize, Serialize};
+
+/// A single step of a [`StepPattern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    pub on: bool,
+    pub pitch: u8,
+    pub velocity: u8,
+    /// Number of retriggers inside the step window. `0` behaves like `1`.
+    #[serde(default = "default_ratchet")]
+    pub ratchet: u8,
+    /// Velocity reached by the last retrigger, ramping linearly from
+    /// `velocity`. `None` keeps every retrigger at `velocity`.
+    #[serde(default)]
+    pub ratchet_velocity: Option<u8>,
+}
+
+fn default_ratchet() -> u8 {
+    1
+}
+
+impl Default for Step {
+    fn default() -> Self {
+        Self {
+            on: false,
+            pitch: 60,
+            velocity: 100,
+            ratchet: 1,
+            ratchet_velocity: None,
+        }
+    }
+}
+
+impl Step {
+    pub fn on(pitch: u8, velocity: u8) -> Self {
+        Self {
+            on: true,
+            pitch,
+            velocity,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_ratchet(mut self, ratchet: u8) -> Self {
+        self.ratchet = ratchet.max(1);
+        self
+    }
+
+    pub fn with_ratchet_velocity(mut self, velocity: u8) -> Self {
+        self.ratchet_velocity = Some(velocity);
+        self
+    }
+
+    /// Velocity of retrigger `index` out of [`Step::ratchet`] repeats.
+    pub fn retrigger_velocity(&self, index: u8) -> u8 {
+        let count = self.ratchet.max(1);
+        let velocity = match self.ratchet_velocity {
+            Some(target) if count > 1 => {
+                let t = index.min(count - 1) as f32 / (count - 1) as f32;
+                let start = self.velocity as f32;
+                (start + (target as f32 - start) * t).round() as u8
+            }
+            _ => self.velocity,
+        };
+        velocity.clamp(1, 127)
+    }
+}
+
+/// Fixed-grid step sequence with per-step ratchets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepPattern {
+    pub steps: Vec<Step>,
+    /// Length of one step in playlist ticks.
+    pub step_ticks: i64,
+    /// MIDI channel used for the rendered notes.
+    #[serde(default)]
+    pub channel: u8,
+}
+
+impl StepPattern {
+    pub fn new(steps: usize, step_ticks: i64) -> Self {
+        Self {
+            steps: vec![Step::default(); steps],
+            step_ticks: step_ticks.max(1),
+            channel: 0,
+        }
+    }
+
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = channel.min(15);
+        self
+    }
+
+    pub fn set_step(&mut self, index: usize, step: Step) {
+        if let Some(slot) = self.steps.get_mut(index) {
+            *slot = step;
+        }
+    }
+
+    pub fn length_ticks(&self) -> i64 {
+        self.steps.len() as i64 * self.step_ticks
+    }
+
+    /// Renders the active steps into pattern notes, expanding ratchets.
+    ///
+    /// Retrigger `k` of a step with `n` ratchets starts `k * step_ticks / n`
+    /// into the step window and lasts until the next retrigger.
+    pub fn to_pattern_notes(&self) -> Vec<PatternNote> {
+        let mut notes = Vec::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            if !step.on {
+                continue;
+            }
+            let window_start = index as i64 * self.step_ticks;
+            let count = step.ratchet.max(1) as i64;
+            for repeat in 0..count {
+                let start = window_start + repeat * self.step_ticks / count;
+                let end = window_start + (repeat + 1) * self.step_ticks / count;
+                notes.push(PatternNote {
+                    id: ((index as u64) << 8) | repeat as u64,
+                    start_ticks: start,
+                    duration_ticks: (end - start).max(1),
+                    pitch: step.pitch.min(127),
+                    velocity: step.retrigger_velocity(repeat as u8),
+                    channel: self.channel,
+                });
+            }
+        }
+        notes
+    }
+}
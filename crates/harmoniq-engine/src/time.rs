@@ -65,6 +65,14 @@ pub struct Transport {
     pub time_signature: TimeSignature,
     pub sample_position: u64,
     pub is_playing: bool,
+    /// Number of times the playhead has wrapped from the loop end.
+    pub loop_iteration: u64,
+    /// Count-in samples left before the loop pass starts; `0` when not
+    /// counting in.
+    pub count_in_remaining: u64,
+    /// Position recording starts from while recording. Audio before it,
+    /// such as pre-roll, is played but not recorded.
+    pub punch_in: Option<u64>,
     pub map_version: u64,
     pub tempo_map: SharedTempoMap,
 }
@@ -82,6 +90,9 @@ impl Default for Transport {
             time_signature: TimeSignature::default(),
             sample_position: 0,
             is_playing: false,
+            loop_iteration: 0,
+            count_in_remaining: 0,
+            punch_in: None,
             map_version: 0,
             tempo_map: Arc::new(TempoMap::default()),
         }
@@ -95,6 +106,10 @@ pub struct LoopRegion {
 }
 
 impl LoopRegion {
+    pub fn new(start: u64, end: u64) -> Self {
+        Self { start, end }
+    }
+
     pub fn length(&self) -> u64 {
         self.end.saturating_sub(self.start)
     }
@@ -163,6 +178,39 @@ impl TempoMap {
         &self.segments
     }
 
+    /// The same map with every segment at `tempo`, keeping its time
+    /// signatures and change positions.
+    pub fn with_tempo(&self, tempo: Tempo) -> Self {
+        Self {
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| TempoSegment {
+                    tempo,
+                    ..segment.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the map with every tempo event moved from `from_rate` to
+    /// `to_rate` sample positions.
+    pub fn resampled(&self, from_rate: f32, to_rate: f32) -> Self {
+        if from_rate <= 0.0 || to_rate <= 0.0 {
+            return self.clone();
+        }
+        let ratio = to_rate as f64 / from_rate as f64;
+        Self::new(
+            self.segments
+                .iter()
+                .map(|segment| TempoSegment {
+                    start_sample: (segment.start_sample as f64 * ratio).round() as u64,
+                    ..segment.clone()
+                })
+                .collect(),
+        )
+    }
+
     pub fn segment_index_at(&self, sample: u64) -> usize {
         match self
             .segments
@@ -227,6 +275,7 @@ impl TempoMap {
                 sample: beat_sample.round() as u64,
                 beat_index,
                 time_signature: segment.time_signature,
+                tempo: segment.tempo,
             });
         }
     }
@@ -236,6 +285,43 @@ impl TempoMap {
         self.first_beat_at_or_after(sample_rate, sample)
     }
 
+    /// Beats elapsed from the start of the map to `sample`, integrated
+    /// across every tempo change before it.
+    pub fn beat_at_sample(&self, sample_rate: f32, sample: f64) -> f64 {
+        let mut beats = 0.0;
+        for (index, segment) in self.segments.iter().enumerate() {
+            let start = segment.start_sample as f64;
+            let end = self
+                .segments
+                .get(index + 1)
+                .map_or(f64::INFINITY, |next| next.start_sample as f64);
+            let spb = segment.tempo.samples_per_beat(sample_rate);
+            if sample < end {
+                return beats + (sample - start).max(0.0) / spb;
+            }
+            beats += (end - start) / spb;
+        }
+        beats
+    }
+
+    /// Inverse of [`beat_at_sample`](Self::beat_at_sample): the fractional
+    /// sample at which `beat` falls.
+    pub fn sample_at_beat(&self, sample_rate: f32, beat: f64) -> f64 {
+        let mut beats = 0.0;
+        for (index, segment) in self.segments.iter().enumerate() {
+            let start = segment.start_sample as f64;
+            let spb = segment.tempo.samples_per_beat(sample_rate);
+            let length = self.segments.get(index + 1).map_or(f64::INFINITY, |next| {
+                (next.start_sample as f64 - start) / spb
+            });
+            if beat < beats + length {
+                return start + (beat - beats).max(0.0) * spb;
+            }
+            beats += length;
+        }
+        0.0
+    }
+
     fn segment_beat_offset(&self, sample_rate: f32, segment_index: usize) -> u64 {
         let mut beats = 0.0;
         for window in self.segments.windows(2).take(segment_index) {
@@ -264,6 +350,10 @@ pub struct BeatInfo {
     pub sample: u64,
     pub beat_index: u64,
     pub time_signature: TimeSignature,
+    /// Tempo in effect at the beat, which follows an external clock when the
+    /// transport is slaved to one.
+    #[serde(default)]
+    pub tempo: Tempo,
 }
 
 impl BeatInfo {
@@ -3,6 +3,7 @@ use std::cmp::Ordering;
 use thiserror::Error;
 
 use crate::clips::{AudioClip, ClipError, FadeCurve, FadeSpec};
+use crate::dsp::events::{Playhead, TransportClock};
 
 #[derive(Debug, Clone)]
 pub struct ClipEvent {
@@ -11,6 +12,10 @@ pub struct ClipEvent {
     pub gain: f32,
     pub fade_in: Option<FadeSpec>,
     pub fade_out: Option<FadeSpec>,
+    /// Total playback length when the clip loops; `None` plays it once.
+    pub loop_frames: Option<usize>,
+    /// Crossfade from the clip end into its start at every loop seam.
+    pub loop_crossfade: Option<FadeSpec>,
 }
 
 impl ClipEvent {
@@ -21,6 +26,8 @@ impl ClipEvent {
             gain: 1.0,
             fade_in: None,
             fade_out: None,
+            loop_frames: None,
+            loop_crossfade: None,
         }
     }
 
@@ -38,6 +45,21 @@ impl ClipEvent {
         self.fade_out = Some(fade);
         self
     }
+
+    pub fn with_loop(mut self, frames: usize) -> Self {
+        self.loop_frames = Some(frames);
+        self
+    }
+
+    pub fn with_loop_crossfade(mut self, fade: FadeSpec) -> Self {
+        self.loop_crossfade = Some(fade);
+        self
+    }
+
+    /// Frames the event occupies on the timeline, including loop repeats.
+    pub fn frames(&self) -> usize {
+        self.loop_frames.unwrap_or_else(|| self.clip.frames())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -72,6 +94,47 @@ impl Timeline {
         self.clips.clear();
     }
 
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn clips(&self) -> &[ClipEvent] {
+        &self.clips
+    }
+
+    /// Converts the timeline to `target_rate`: clip starts and fades are
+    /// rescaled by the rate ratio and every clip is resampled so its length
+    /// in seconds is preserved.
+    pub fn resample_to(&mut self, target_rate: f32) {
+        if target_rate <= 0.0 || self.sample_rate <= 0.0 || target_rate == self.sample_rate {
+            return;
+        }
+        let ratio = target_rate as f64 / self.sample_rate as f64;
+        for event in &mut self.clips {
+            event.start_frame = (event.start_frame as f64 * ratio).round() as usize;
+            event.clip = event.clip.resample_to(target_rate);
+            event.loop_frames = event
+                .loop_frames
+                .map(|frames| (frames as f64 * ratio).round() as usize);
+            let frames = event.frames();
+            let scale = |fade: FadeSpec| {
+                let length = (fade.length() as f64 * ratio).round() as usize;
+                FadeSpec::new(length.min(frames), fade.curve())
+            };
+            event.fade_in = event.fade_in.map(scale);
+            event.fade_out = event.fade_out.map(scale);
+            event.loop_crossfade = event.loop_crossfade.map(scale);
+        }
+        self.sample_rate = target_rate;
+    }
+
+    /// Renders the timeline for playback from a [`TransportClock`].
+    pub fn player(&self) -> Result<TimelinePlayer, TimelineError> {
+        Ok(TimelinePlayer {
+            mix: self.render()?,
+        })
+    }
+
     pub fn render(&self) -> Result<AudioClip, TimelineError> {
         if self.channels == 0 {
             return Ok(AudioClip::empty(self.sample_rate, 0));
@@ -79,13 +142,13 @@ impl Timeline {
 
         let mut events = self.clips.clone();
         events.sort_by(|a, b| match a.start_frame.cmp(&b.start_frame) {
-            Ordering::Equal => a.clip.frames().cmp(&b.clip.frames()),
+            Ordering::Equal => a.frames().cmp(&b.frames()),
             other => other,
         });
 
         let total_frames = events
             .iter()
-            .map(|event| event.start_frame + event.clip.frames())
+            .map(|event| event.start_frame + event.frames())
             .max()
             .unwrap_or(0);
 
@@ -103,7 +166,14 @@ impl Timeline {
         event: &ClipEvent,
         buffer: &mut [Vec<f32>],
     ) -> Result<(), TimelineError> {
-        let clip = &event.clip;
+        let looped;
+        let clip = match event.loop_frames {
+            Some(frames) => {
+                looped = event.clip.looped(frames, event.loop_crossfade);
+                &looped
+            }
+            None => &event.clip,
+        };
         if clip.frames() == 0 {
             return Ok(());
         }
@@ -139,6 +209,54 @@ impl Timeline {
     }
 }
 
+/// A rendered timeline played back at the position of a [`TransportClock`].
+#[derive(Debug, Clone)]
+pub struct TimelinePlayer {
+    mix: AudioClip,
+}
+
+impl TimelinePlayer {
+    pub fn mix(&self) -> &AudioClip {
+        &self.mix
+    }
+
+    /// What `channel` plays at `playhead`. After a loop wrap, the audio past
+    /// the loop end fades out under an equal-power fade-in of the loop start.
+    pub fn sample(&self, channel: usize, playhead: Playhead) -> f32 {
+        let source = self
+            .mix
+            .channel(channel.min(self.mix.channels().saturating_sub(1)))
+            .unwrap_or(&[]);
+        let at = |position: u64| source.get(position as usize).copied().unwrap_or(0.0);
+        match playhead {
+            Playhead::Idle => 0.0,
+            Playhead::Playing(position) => at(position),
+            Playhead::Crossfading {
+                head,
+                tail,
+                elapsed,
+                length,
+            } => FadeSpec::new(length as usize, FadeCurve::EqualPower).mix_at(
+                elapsed as usize,
+                at(tail),
+                at(head),
+            ),
+        }
+    }
+
+    /// Fills `output` with the next block and advances `clock` past it.
+    pub fn process(&self, clock: &TransportClock, output: &mut [Vec<f32>]) {
+        let frames = output.iter().map(Vec::len).max().unwrap_or(0);
+        clock.advance_with(frames as u32, |frame, playhead| {
+            for (index, channel) in output.iter_mut().enumerate() {
+                if let Some(slot) = channel.get_mut(frame) {
+                    *slot = self.sample(index, playhead);
+                }
+            }
+        });
+    }
+}
+
 fn ensure_capacity(buffer: &mut [Vec<f32>], frames: usize) {
     for channel in buffer {
         if channel.len() < frames {
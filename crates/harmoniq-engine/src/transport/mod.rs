@@ -1,5 +1,9 @@
 use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 
+mod sync;
+
+pub use sync::{MidiSink, MidiSyncGenerator, MidiSyncSettings, MtcRate, MIDI_CLOCK_PPQN};
+
 #[derive(Debug)]
 pub struct Transport {
     pub playing: AtomicBool,
@@ -0,0 +1,259 @@
+//! MIDI clock and MIDI time code generated from the timeline transport.
+//!
+//! Clock ticks fall on the tempo map's own beat grid, so each one lands on
+//! the sample where its 1/24 of a beat begins even across tempo changes.
+//! Quarter-frame MTC follows wall-clock time and ignores tempo.
+
+use ringbuf::HeapProducer;
+
+use crate::dsp::events::MidiEvent;
+use crate::time::{TempoMap, Transport};
+
+/// MIDI clock resolution in ticks per quarter note.
+pub const MIDI_CLOCK_PPQN: u32 = 24;
+
+const CLOCK: u8 = 0xF8;
+const START: u8 = 0xFA;
+const CONTINUE: u8 = 0xFB;
+const STOP: u8 = 0xFC;
+const SONG_POSITION: u8 = 0xF2;
+const QUARTER_FRAME: u8 = 0xF1;
+
+/// Destination for generated MIDI messages.
+pub trait MidiSink {
+    fn send(&mut self, event: MidiEvent);
+}
+
+impl MidiSink for Vec<MidiEvent> {
+    fn send(&mut self, event: MidiEvent) {
+        self.push(event);
+    }
+}
+
+/// Drops messages while the ring is full.
+impl MidiSink for HeapProducer<MidiEvent> {
+    fn send(&mut self, event: MidiEvent) {
+        let _ = self.push(event);
+    }
+}
+
+/// Frame rate of the MIDI time code stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtcRate {
+    Fps24,
+    Fps25,
+    /// 29.97 fps drop-frame.
+    Fps2997Drop,
+    Fps30,
+}
+
+impl MtcRate {
+    pub fn frames_per_second(self) -> f64 {
+        match self {
+            MtcRate::Fps24 => 24.0,
+            MtcRate::Fps25 => 25.0,
+            MtcRate::Fps2997Drop => 30_000.0 / 1_001.0,
+            MtcRate::Fps30 => 30.0,
+        }
+    }
+
+    /// Rate code carried in the last quarter-frame piece.
+    fn code(self) -> u8 {
+        match self {
+            MtcRate::Fps24 => 0,
+            MtcRate::Fps25 => 1,
+            MtcRate::Fps2997Drop => 2,
+            MtcRate::Fps30 => 3,
+        }
+    }
+
+    /// Hours, minutes, seconds and frames of the `frame`th frame.
+    fn timecode(self, frame: u64) -> [u8; 4] {
+        let (nominal, frame) = match self {
+            MtcRate::Fps24 => (24, frame),
+            MtcRate::Fps25 => (25, frame),
+            MtcRate::Fps30 => (30, frame),
+            MtcRate::Fps2997Drop => {
+                // Frame numbers 0 and 1 are skipped at the start of every
+                // minute except each tenth.
+                let per_ten_minutes = 17_982;
+                let per_minute = 1_798;
+                let tens = frame / per_ten_minutes;
+                let rest = frame % per_ten_minutes;
+                let dropped = if rest < 2 {
+                    0
+                } else {
+                    2 * ((rest - 2) / per_minute)
+                };
+                (30, frame + 18 * tens + dropped)
+            }
+        };
+        let seconds = frame / nominal;
+        [
+            ((seconds / 3_600) % 24) as u8,
+            ((seconds / 60) % 60) as u8,
+            (seconds % 60) as u8,
+            (frame % nominal) as u8,
+        ]
+    }
+}
+
+/// What [`MidiSyncGenerator`] transmits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiSyncSettings {
+    /// Clock ticks plus start, stop and continue.
+    pub clock: bool,
+    /// Quarter-frame time code at this rate.
+    pub mtc: Option<MtcRate>,
+}
+
+impl Default for MidiSyncSettings {
+    fn default() -> Self {
+        Self {
+            clock: true,
+            mtc: None,
+        }
+    }
+}
+
+/// Turns transport snapshots into timestamped MIDI clock and MTC messages.
+///
+/// Call [`process`](Self::process) once per block with the transport as it
+/// stands at the start of the block. A playhead that does not continue from
+/// where the previous block ended is treated as a locate.
+#[derive(Debug, Clone)]
+pub struct MidiSyncGenerator {
+    sample_rate: f32,
+    settings: MidiSyncSettings,
+    was_playing: bool,
+    /// Where the playhead will be at the next block if it keeps playing.
+    expected_position: Option<u64>,
+    next_tick: u64,
+    next_quarter_frame: u64,
+    pending: Vec<(u32, u8, MidiEvent)>,
+}
+
+impl MidiSyncGenerator {
+    pub fn new(sample_rate: f32, settings: MidiSyncSettings) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1.0),
+            settings,
+            was_playing: false,
+            expected_position: None,
+            next_tick: 0,
+            next_quarter_frame: 0,
+            pending: Vec::with_capacity(256),
+        }
+    }
+
+    pub fn settings(&self) -> MidiSyncSettings {
+        self.settings
+    }
+
+    /// Emits the messages that fall inside the `frames`-sample block
+    /// starting at `transport.sample_position`, in timestamp order.
+    pub fn process(&mut self, transport: &Transport, frames: u32, output: &mut impl MidiSink) {
+        let position = transport.sample_position;
+        let map = &transport.tempo_map;
+        let frames = frames as u64;
+        self.pending.clear();
+
+        let located = self.expected_position != Some(position);
+        if transport.is_playing {
+            if !self.was_playing || located {
+                self.locate(map, position);
+            }
+            if !self.was_playing && self.settings.clock {
+                if position == 0 {
+                    self.queue(0, 1, [START, 0, 0], 1);
+                } else {
+                    // Song position counts sixteenth notes.
+                    let beat = map.beat_at_sample(self.sample_rate, position as f64);
+                    let sixteenths = ((beat * 4.0).round() as u32).min(0x3FFF);
+                    let pointer = [
+                        SONG_POSITION,
+                        (sixteenths & 0x7F) as u8,
+                        (sixteenths >> 7) as u8,
+                    ];
+                    self.queue(0, 0, pointer, 3);
+                    self.queue(0, 1, [CONTINUE, 0, 0], 1);
+                }
+            }
+            if self.settings.clock {
+                self.queue_ticks(map, position, frames);
+            }
+            if let Some(rate) = self.settings.mtc {
+                self.queue_quarter_frames(rate, position, frames);
+            }
+            self.expected_position = Some(position + frames);
+        } else {
+            if self.was_playing && self.settings.clock {
+                self.queue(0, 0, [STOP, 0, 0], 1);
+            }
+            self.expected_position = None;
+        }
+        self.was_playing = transport.is_playing;
+
+        self.pending
+            .sort_unstable_by_key(|(offset, rank, _)| (*offset, *rank));
+        for (_, _, event) in self.pending.drain(..) {
+            output.send(event);
+        }
+    }
+
+    fn locate(&mut self, map: &TempoMap, position: u64) {
+        let beat = map.beat_at_sample(self.sample_rate, position as f64);
+        // A tick that starts exactly on the playhead still sounds.
+        self.next_tick = (beat * MIDI_CLOCK_PPQN as f64 - 1e-6).ceil().max(0.0) as u64;
+        if let Some(rate) = self.settings.mtc {
+            let quarter = 4.0 * rate.frames_per_second() / self.sample_rate as f64;
+            self.next_quarter_frame = (position as f64 * quarter - 1e-6).ceil().max(0.0) as u64;
+        }
+    }
+
+    fn queue_ticks(&mut self, map: &TempoMap, position: u64, frames: u64) {
+        loop {
+            let beat = self.next_tick as f64 / MIDI_CLOCK_PPQN as f64;
+            let sample = map.sample_at_beat(self.sample_rate, beat).round() as u64;
+            if sample >= position + frames {
+                break;
+            }
+            let offset = sample.saturating_sub(position) as u32;
+            self.queue(offset, 2, [CLOCK, 0, 0], 1);
+            self.next_tick += 1;
+        }
+    }
+
+    fn queue_quarter_frames(&mut self, rate: MtcRate, position: u64, frames: u64) {
+        let samples_per_quarter = self.sample_rate as f64 / (4.0 * rate.frames_per_second());
+        loop {
+            let sample = (self.next_quarter_frame as f64 * samples_per_quarter).round() as u64;
+            if sample >= position + frames {
+                break;
+            }
+            let piece = (self.next_quarter_frame % 8) as u8;
+            // All eight pieces describe the frame on which piece 0 was sent.
+            let frame = (self.next_quarter_frame - piece as u64) / 4;
+            let [hours, minutes, seconds, frames_field] = rate.timecode(frame);
+            let nibble = match piece {
+                0 => frames_field & 0x0F,
+                1 => frames_field >> 4,
+                2 => seconds & 0x0F,
+                3 => seconds >> 4,
+                4 => minutes & 0x0F,
+                5 => minutes >> 4,
+                6 => hours & 0x0F,
+                _ => (rate.code() << 1) | (hours >> 4),
+            };
+            let offset = sample.saturating_sub(position) as u32;
+            self.queue(offset, 3, [QUARTER_FRAME, (piece << 4) | nibble, 0], 2);
+            self.next_quarter_frame += 1;
+        }
+    }
+
+    fn queue(&mut self, offset: u32, rank: u8, data: [u8; 3], length: u8) {
+        let mut event = MidiEvent::new(offset, data);
+        event.length = length;
+        self.pending.push((offset, rank, event));
+    }
+}
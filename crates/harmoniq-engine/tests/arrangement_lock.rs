@@ -0,0 +1,107 @@
+use harmoniq_engine::{
+    AddClipCommand, ArrangementClip, ArrangementTrack, CommandBus, CommandError, LockTarget,
+    MoveClipCommand, SetLockCommand,
+};
+
+fn clip(id: u64) -> ArrangementClip {
+    ArrangementClip {
+        id,
+        name: format!("Clip {id}"),
+        start: 0.0,
+        length: 4.0,
+        media: None,
+        color: None,
+        tags: Vec::new(),
+        locked: false,
+    }
+}
+
+fn bus_with_clips() -> (CommandBus, u32) {
+    let mut bus = CommandBus::default();
+    let track_id = bus.state().arrangement.tracks[0].id;
+    for id in [1, 2] {
+        bus.execute(AddClipCommand {
+            track_id,
+            clip: clip(id),
+        })
+        .unwrap();
+    }
+    (bus, track_id)
+}
+
+fn move_to(clip_id: u64, track_id: u32, new_start: f32) -> MoveClipCommand {
+    MoveClipCommand {
+        clip_id,
+        target_track: track_id,
+        new_start,
+    }
+}
+
+#[test]
+fn locked_clip_rejects_edits_while_unlocked_clip_moves() {
+    let (mut bus, track_id) = bus_with_clips();
+    bus.execute(SetLockCommand {
+        target: LockTarget::Clip(1),
+        locked: true,
+    })
+    .unwrap();
+    assert!(bus.state().is_clip_locked(1));
+    assert!(!bus.state().is_clip_locked(2));
+
+    let err = bus.execute(move_to(1, track_id, 8.0)).unwrap_err();
+    assert_eq!(err, CommandError::Locked("clip"));
+    bus.execute(move_to(2, track_id, 8.0)).unwrap();
+
+    let clips = &bus.state().arrangement.tracks[0].clips;
+    let start = |id| clips.iter().find(|clip| clip.id == id).unwrap().start;
+    assert_eq!(start(1), 0.0);
+    assert_eq!(start(2), 8.0);
+
+    bus.execute(SetLockCommand {
+        target: LockTarget::Clip(1),
+        locked: false,
+    })
+    .unwrap();
+    bus.execute(move_to(1, track_id, 4.0)).unwrap();
+}
+
+#[test]
+fn locked_track_protects_its_clips() {
+    let (mut bus, track_id) = bus_with_clips();
+    bus.execute(SetLockCommand {
+        target: LockTarget::Track(track_id),
+        locked: true,
+    })
+    .unwrap();
+    assert!(bus.state().is_track_locked(track_id));
+    assert!(bus.state().is_clip_locked(2));
+
+    assert_eq!(
+        bus.execute(AddClipCommand {
+            track_id,
+            clip: clip(3),
+        })
+        .unwrap_err(),
+        CommandError::Locked("track")
+    );
+    assert!(bus.execute(move_to(2, track_id, 8.0)).is_err());
+
+    bus.undo().unwrap();
+    assert!(!bus.state().is_track_locked(track_id));
+    bus.execute(move_to(2, track_id, 8.0)).unwrap();
+}
+
+#[test]
+fn lock_flags_survive_serialization() {
+    let mut track = ArrangementTrack::new("Keys");
+    track.locked = true;
+    let mut locked = clip(5);
+    locked.locked = true;
+    track.insert_clip(locked);
+
+    let json = serde_json::to_string(&track).unwrap();
+    let restored: ArrangementTrack = serde_json::from_str(&json).unwrap();
+    assert!(restored.locked);
+    assert!(restored.clips[0].locked);
+    assert_eq!(restored, track);
+}
@@ -0,0 +1,56 @@
+use harmoniq_engine::{ArrangementClip, ArrangementState, ArrangementTrack};
+
+fn clip(id: u64, name: &str, tags: &[&str]) -> ArrangementClip {
+    ArrangementClip {
+        id,
+        name: name.into(),
+        start: id as f32 * 4.0,
+        length: 4.0,
+        media: None,
+        color: Some([0.9, 0.3, 0.2, 1.0]),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        locked: false,
+    }
+}
+
+#[test]
+fn tags_and_colors_round_trip_and_filter_clips() {
+    let mut state = ArrangementState::default();
+    let mut vocals = ArrangementTrack::new("Vocals");
+    vocals.color = Some([0.2, 0.6, 1.0, 1.0]);
+    vocals.tags = vec!["vox".into()];
+    vocals.insert_clip(clip(1, "Verse", &["vox", "take-2"]));
+    vocals.insert_clip(clip(2, "Chorus", &["vox"]));
+    state.push_track(vocals);
+    state.tracks[0].insert_clip(clip(3, "Fill", &["take-2"]));
+
+    let json = serde_json::to_string(&state).unwrap();
+    let restored: ArrangementState = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, state);
+
+    let takes: Vec<&str> = restored
+        .clips_with_tag("take-2")
+        .map(|clip| clip.name.as_str())
+        .collect();
+    assert_eq!(takes, vec!["Fill", "Verse"]);
+    let tracks: Vec<&str> = restored
+        .tracks_with_tag("vox")
+        .map(|track| track.name.as_str())
+        .collect();
+    assert_eq!(tracks, vec!["Vocals"]);
+    assert_eq!(restored.clips_with_tag("missing").count(), 0);
+}
+
+#[test]
+fn documents_without_tags_still_load() {
+    let json = r#"{
+        "id": 4,
+        "name": "Legacy",
+        "clips": [{ "id": 9, "name": "Old", "start": 0.0, "length": 2.0, "media": null }]
+    }"#;
+    let track: ArrangementTrack = serde_json::from_str(json).unwrap();
+    assert_eq!(track.color, None);
+    assert!(track.tags.is_empty());
+    assert_eq!(track.clips[0].color, None);
+    assert!(track.clips[0].tags.is_empty());
+}
@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use harmoniq_engine::automation::{MIXER_GAIN_PARAM, MIXER_PAN_PARAM};
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, AutomationPoint, AutomationSlot, AutomationTarget, BufferConfig,
+    ChannelLayout, CommandBus, EngineCommand, GraphBuilder, HarmoniqEngine, LaneTarget,
+    ParameterSpec, PluginDescriptor, ProjectState, ResolvedTarget, TransportState,
+};
+use harmoniq_playlist::state::{
+    AutomationLane, AutomationPoint as PlaylistPoint, Clip, ClipId, ClipKind, Playlist, Track,
+    TrackId as PlaylistTrackId, TrackLane,
+};
+use parking_lot::Mutex;
+
+struct Silence;
+
+impl AudioProcessor for Silence {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.silence", "Silence", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.clear();
+        Ok(())
+    }
+}
+
+#[test]
+fn canonical_form_round_trips() {
+    let targets = [
+        AutomationTarget::mixer(0, MIXER_GAIN_PARAM),
+        AutomationTarget::instrument(3, 12),
+        AutomationTarget::insert(7, 2, 41),
+    ];
+    let encoded: Vec<String> = targets.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        encoded,
+        [
+            "track:0/mixer/param:0",
+            "track:3/instrument/param:12",
+            "track:7/insert:2/param:41",
+        ]
+    );
+    for (target, text) in targets.iter().zip(&encoded) {
+        assert_eq!(&text.parse::<AutomationTarget>().unwrap(), target);
+        let json = serde_json::to_string(target).unwrap();
+        assert_eq!(json, format!("\"{text}\""));
+        assert_eq!(
+            &serde_json::from_str::<AutomationTarget>(&json).unwrap(),
+            target
+        );
+    }
+
+    for invalid in [
+        "",
+        "volume",
+        "track:x/mixer/param:0",
+        "track:1/send/param:0",
+        "track:1/insert:a/param:0",
+        "track:1/instrument",
+        "track:1/instrument/param:2/extra",
+    ] {
+        assert!(invalid.parse::<AutomationTarget>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn legacy_strings_convert_where_possible() {
+    assert_eq!(
+        AutomationTarget::from_legacy(4, "volume"),
+        Some(AutomationTarget::mixer(4, MIXER_GAIN_PARAM))
+    );
+    assert_eq!(
+        AutomationTarget::from_legacy(4, "Pan"),
+        Some(AutomationTarget::mixer(4, MIXER_PAN_PARAM))
+    );
+    assert_eq!(
+        AutomationTarget::from_legacy(4, "param:9"),
+        Some(AutomationTarget::instrument(4, 9))
+    );
+    assert_eq!(
+        AutomationTarget::from_legacy(4, "track:1/insert:0/param:3"),
+        Some(AutomationTarget::insert(1, 0, 3))
+    );
+    assert_eq!(AutomationTarget::from_legacy(4, "Cutoff"), None);
+}
+
+#[test]
+fn project_state_migrates_string_targets() {
+    let bus = CommandBus::default();
+    let track = bus.state().arrangement.tracks[0].id;
+    let mut json = serde_json::to_value(bus.state()).unwrap();
+    let lanes = json["automation"]["lanes"].as_array_mut().unwrap();
+    let mut unknown = lanes[0].clone();
+    lanes[0].as_object_mut().unwrap().remove("target");
+    lanes[0]["parameter"] = "volume".into();
+    unknown.as_object_mut().unwrap().remove("target");
+    unknown["id"] = 99.into();
+    unknown["parameter"] = "Cutoff".into();
+    lanes.push(unknown);
+
+    let state: ProjectState = serde_json::from_value(json).unwrap();
+    assert_eq!(state.automation.lanes.len(), 2);
+    assert_eq!(
+        state.automation.lanes[0].target,
+        LaneTarget::Resolved(AutomationTarget::mixer(track, MIXER_GAIN_PARAM))
+    );
+    // Kept under its old name until the plugin that defines it is loaded.
+    assert_eq!(
+        state.automation.lanes[1].target,
+        LaneTarget::Unresolved("Cutoff".into())
+    );
+
+    let saved = serde_json::to_string(&state).unwrap();
+    let reloaded: ProjectState = serde_json::from_str(&saved).unwrap();
+    assert_eq!(reloaded, state);
+}
+
+#[test]
+fn target_resolves_to_live_parameter() {
+    let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config).expect("engine");
+    let other = engine.register_processor(Box::new(Silence)).expect("other");
+    let instrument = engine
+        .register_processor(Box::new(Silence))
+        .expect("instrument");
+    let insert = engine
+        .register_processor(Box::new(Silence))
+        .expect("insert");
+    let mut builder = GraphBuilder::new();
+    for id in [other, instrument, insert] {
+        let node = builder.add_node(id);
+        builder.connect_to_mixer(node, 1.0).expect("connect");
+    }
+    engine.replace_graph(builder.build()).expect("graph");
+    engine
+        .register_automation_parameter(
+            instrument,
+            ParameterSpec::new(3, "Cutoff", 20.0, 20_000.0, 1_000.0),
+        )
+        .expect("parameter");
+    engine
+        .register_automation_parameter(insert, ParameterSpec::new(4, "Mix", 0.0, 1.0, 1.0))
+        .expect("parameter");
+    // Track ids are the project's, not positions in the graph.
+    engine.set_track_plugins(7, vec![instrument, insert]);
+
+    let target = AutomationTarget::instrument(7, 3);
+    let Some(ResolvedTarget::Parameter { plugin, spec }) =
+        engine.resolve_automation_target(&target)
+    else {
+        panic!("instrument parameter unresolved");
+    };
+    assert_eq!((plugin, spec.name.as_str()), (instrument, "Cutoff"));
+    let Some(ResolvedTarget::Parameter { plugin, spec }) =
+        engine.resolve_automation_target(&AutomationTarget::insert(7, 0, 4))
+    else {
+        panic!("insert parameter unresolved");
+    };
+    assert_eq!((plugin, spec.name.as_str()), (insert, "Mix"));
+    // The strip is the one the insert feeds.
+    assert!(matches!(
+        engine.resolve_automation_target(&AutomationTarget::mixer(7, MIXER_PAN_PARAM)),
+        Some(ResolvedTarget::Mixer {
+            track: 2,
+            parameter: MIXER_PAN_PARAM
+        })
+    ));
+
+    for unresolved in [
+        AutomationTarget::instrument(7, 4),
+        AutomationTarget::instrument(1, 3),
+        AutomationTarget::new(7, AutomationSlot::Insert(1), 4),
+        AutomationTarget::mixer(7, 9),
+    ] {
+        assert!(
+            engine.resolve_automation_target(&unresolved).is_none(),
+            "{unresolved}"
+        );
+    }
+
+    // Names the plain conversion cannot handle are looked up on the track.
+    assert_eq!(engine.automation_target_for(7, "Cutoff"), Some(target));
+    assert_eq!(engine.automation_target_for(1, "Cutoff"), None);
+    engine.set_track_plugins(7, Vec::new());
+    assert!(engine
+        .resolve_automation_target(&AutomationTarget::instrument(7, 3))
+        .is_none());
+}
+
+type EventLog = Arc<Mutex<Vec<(usize, f32)>>>;
+
+/// Records the automation it receives as (parameter, value).
+struct Recorder {
+    log: EventLog,
+}
+
+impl AudioProcessor for Recorder {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.recorder", "Recorder", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, _buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn handle_automation_event(
+        &mut self,
+        parameter: usize,
+        value: f32,
+        _sample_offset: usize,
+    ) -> anyhow::Result<()> {
+        self.log.lock().push((parameter, value));
+        Ok(())
+    }
+}
+
+/// A 1 kHz sine at half scale.
+struct Tone {
+    phase: f32,
+}
+
+impl AudioProcessor for Tone {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.tone", "Tone", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        let start = self.phase;
+        for channel in buffer.channels_mut() {
+            self.phase = start;
+            for sample in channel.iter_mut() {
+                *sample = 0.5 * (std::f32::consts::TAU * self.phase).sin();
+                self.phase = (self.phase + 1_000.0 / 48_000.0).fract();
+            }
+        }
+        Ok(())
+    }
+}
+
+fn peak(buffer: &AudioBuffer) -> f32 {
+    buffer
+        .channels()
+        .flat_map(|channel| channel.iter())
+        .fold(0.0, |peak, sample| peak.max(sample.abs()))
+}
+
+#[test]
+fn playlist_clip_automates_an_insert_by_address() {
+    let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let instrument_log = EventLog::default();
+    let insert_log = EventLog::default();
+    let instrument = engine
+        .register_processor(Box::new(Recorder {
+            log: Arc::clone(&instrument_log),
+        }))
+        .expect("instrument");
+    let insert = engine
+        .register_processor(Box::new(Recorder {
+            log: Arc::clone(&insert_log),
+        }))
+        .expect("insert");
+    let mut builder = GraphBuilder::new();
+    for id in [instrument, insert] {
+        let node = builder.add_node(id);
+        builder.connect_to_mixer(node, 1.0).expect("connect");
+    }
+    engine.replace_graph(builder.build()).expect("graph");
+    for id in [instrument, insert] {
+        engine
+            .register_automation_parameter(id, ParameterSpec::new(4, "Mix", 0.0, 1.0, 1.0))
+            .expect("parameter");
+    }
+    engine.set_track_plugins(3, vec![instrument, insert]);
+
+    let mut track = Track::new(PlaylistTrackId(0), "Lead");
+    track.add_lane(TrackLane::new(0, "Lane 1"));
+    track.add_clip(Clip::new(
+        ClipId(1),
+        "Mix",
+        0,
+        960,
+        [1.0; 4],
+        ClipKind::Automation {
+            lane: AutomationLane {
+                parameter: AutomationTarget::insert(3, 0, 4).to_string(),
+                points: vec![PlaylistPoint {
+                    tick: 0,
+                    value: 0.25,
+                }],
+            },
+        },
+    ));
+    let playlist = Playlist {
+        ppq: 96,
+        tracks: vec![track],
+        selection: None,
+        dropped_files: Vec::new(),
+        patterns: HashMap::new(),
+    };
+    engine
+        .execute_command(EngineCommand::SetPatternMode(false))
+        .expect("pattern mode");
+    engine
+        .execute_command(EngineCommand::SetPlaylist(playlist))
+        .expect("playlist");
+    engine.set_transport(TransportState::Playing);
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    for _ in 0..4 {
+        engine.process_block(&mut buffer).expect("block");
+    }
+    assert!(insert_log.lock().contains(&(4, 0.25)));
+    assert!(instrument_log.lock().iter().all(|(_, value)| *value == 1.0));
+}
+
+#[test]
+fn track_volume_lane_drives_the_mixer_strip() {
+    let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let tone = engine
+        .register_processor(Box::new(Tone { phase: 0.0 }))
+        .expect("tone");
+    let mut builder = GraphBuilder::new();
+    let node = builder.add_node(tone);
+    builder.connect_to_mixer(node, 1.0).expect("connect");
+    engine.replace_graph(builder.build()).expect("graph");
+
+    // The volume lane every new track starts with.
+    let mut state = CommandBus::default().state().clone();
+    let track = state.arrangement.tracks[0].id;
+    let lane = &mut state.automation.lanes[0];
+    assert_eq!(
+        lane.target,
+        LaneTarget::Resolved(AutomationTarget::mixer(track, MIXER_GAIN_PARAM))
+    );
+    // Full level for the first beat, silent from the second, 24 000
+    // samples in at 120 BPM.
+    lane.points = vec![
+        AutomationPoint {
+            beat: 0.0,
+            value: 1.0,
+        },
+        AutomationPoint {
+            beat: 1.0,
+            value: 0.0,
+        },
+    ];
+    engine.set_track_plugins(track, vec![tone]);
+    engine
+        .execute_command(EngineCommand::SetAutomationLanes(state.automation.lanes))
+        .expect("lanes");
+    engine.set_transport(TransportState::Playing);
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    let peaks: Vec<f32> = (0..60)
+        .map(|_| {
+            engine.process_block(&mut buffer).expect("block");
+            peak(&buffer)
+        })
+        .collect();
+    assert!(peaks[20] > 0.1, "{}", peaks[20]);
+    assert!(peaks[59] < 1e-3, "{}", peaks[59]);
+}
+
+#[test]
+fn unresolved_lane_plays_once_its_parameter_is_registered() {
+    let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let log = EventLog::default();
+    let instrument = engine
+        .register_processor(Box::new(Recorder {
+            log: Arc::clone(&log),
+        }))
+        .expect("instrument");
+    let mut builder = GraphBuilder::new();
+    let node = builder.add_node(instrument);
+    builder.connect_to_mixer(node, 1.0).expect("connect");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine.set_track_plugins(5, vec![instrument]);
+
+    let mut state = CommandBus::default().state().clone();
+    let lane = &mut state.automation.lanes[0];
+    lane.owner = harmoniq_engine::AutomationOwner::Track(5);
+    lane.target = LaneTarget::Unresolved("Cutoff".into());
+    // One point per block at 120 BPM with 512-sample blocks.
+    lane.points = (0..8)
+        .map(|block| AutomationPoint {
+            beat: (block * 512) as f32 / 24_000.0,
+            value: block as f32 / 8.0,
+        })
+        .collect();
+    engine
+        .execute_command(EngineCommand::SetAutomationLanes(state.automation.lanes))
+        .expect("lanes");
+    engine.set_transport(TransportState::Playing);
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    for _ in 0..2 {
+        engine.process_block(&mut buffer).expect("block");
+    }
+    assert!(log.lock().is_empty());
+
+    engine
+        .register_automation_parameter(instrument, ParameterSpec::new(3, "Cutoff", 0.0, 1.0, 0.0))
+        .expect("parameter");
+    for _ in 2..8 {
+        engine.process_block(&mut buffer).expect("block");
+    }
+    let values: Vec<f32> = log
+        .lock()
+        .iter()
+        .filter(|(parameter, _)| *parameter == 3)
+        .map(|(_, value)| *value)
+        .collect();
+    assert!(!values.is_empty());
+    assert!(
+        values.windows(2).all(|pair| pair[0] <= pair[1]),
+        "{values:?}"
+    );
+}
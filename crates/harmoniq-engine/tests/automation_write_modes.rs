@@ -0,0 +1,157 @@
+use harmoniq_engine::automation::AutomationLane;
+use harmoniq_engine::{
+    AutomationCommand, AutomationWriteMode, CurveShape, ParameterSpec, PluginId,
+};
+
+const PARAM: usize = 0;
+
+/// A lane holding a linear ramp from 0.0 at sample 0 to 1.0 at sample 1000,
+/// put through one pass: playback from 100 to 700, the control touched at
+/// 200 (0.8), moved at 300 (0.6) and released at 400.
+fn record(mode: AutomationWriteMode) -> AutomationLane {
+    let mut lane = AutomationLane::new(PluginId(1), 64);
+    let commands = [
+        AutomationCommand::RegisterParameter(ParameterSpec::new(PARAM, "Volume", 0.0, 1.0, 0.5)),
+        AutomationCommand::DrawCurve {
+            parameter: PARAM,
+            sample: 0,
+            value: 0.0,
+            shape: CurveShape::Linear,
+        },
+        AutomationCommand::DrawCurve {
+            parameter: PARAM,
+            sample: 1_000,
+            value: 1.0,
+            shape: CurveShape::Linear,
+        },
+        AutomationCommand::SetWriteMode {
+            parameter: PARAM,
+            mode,
+        },
+        AutomationCommand::StartPass { sample: 100 },
+        AutomationCommand::Touch {
+            parameter: PARAM,
+            sample: 200,
+            value: 0.8,
+            shape: CurveShape::Step,
+        },
+        AutomationCommand::Touch {
+            parameter: PARAM,
+            sample: 300,
+            value: 0.6,
+            shape: CurveShape::Step,
+        },
+        AutomationCommand::Release {
+            parameter: PARAM,
+            sample: 400,
+            value: None,
+        },
+        AutomationCommand::StopPass { sample: 700 },
+    ];
+    for command in commands {
+        lane.apply_command(command);
+    }
+    lane
+}
+
+/// Curve values at each of `samples`.
+fn values(lane: &AutomationLane, samples: &[u64]) -> Vec<f32> {
+    let curve = lane.curve(PARAM).expect("curve");
+    samples
+        .iter()
+        .map(|sample| curve.value_at(*sample).expect("value"))
+        .collect()
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    for (index, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "sample {index}: {actual} vs {expected}"
+        );
+    }
+}
+
+const PROBES: [u64; 9] = [50, 150, 250, 350, 400, 401, 500, 700, 800];
+
+#[test]
+fn read_mode_leaves_the_curve_alone() {
+    let lane = record(AutomationWriteMode::Read);
+    assert_close(
+        &values(&lane, &PROBES),
+        &[0.05, 0.15, 0.25, 0.35, 0.4, 0.401, 0.5, 0.7, 0.8],
+    );
+}
+
+#[test]
+fn touch_writes_while_held_and_reverts_on_release() {
+    let lane = record(AutomationWriteMode::Touch);
+    assert_close(
+        &values(&lane, &PROBES),
+        &[0.05, 0.15, 0.8, 0.6, 0.6, 0.401, 0.5, 0.7, 0.8],
+    );
+}
+
+#[test]
+fn latch_holds_the_last_value_until_stop() {
+    let lane = record(AutomationWriteMode::Latch);
+    assert_close(
+        &values(&lane, &PROBES),
+        &[0.05, 0.15, 0.8, 0.6, 0.6, 0.6, 0.6, 0.6, 0.8],
+    );
+    assert_close(&values(&lane, &[701]), &[0.701]);
+}
+
+#[test]
+fn write_overwrites_from_play_start() {
+    let lane = record(AutomationWriteMode::Write);
+    // Until the first touch the value at play start is written.
+    assert_close(
+        &values(&lane, &PROBES),
+        &[0.05, 0.1, 0.8, 0.6, 0.6, 0.6, 0.6, 0.6, 0.8],
+    );
+    assert_close(&values(&lane, &[99, 701]), &[0.099, 0.701]);
+}
+
+#[test]
+fn latched_playback_reads_the_held_value_before_stop() {
+    let mut lane = AutomationLane::new(PluginId(1), 64);
+    for command in [
+        AutomationCommand::RegisterParameter(ParameterSpec::new(PARAM, "Volume", 0.0, 1.0, 0.5)),
+        AutomationCommand::DrawCurve {
+            parameter: PARAM,
+            sample: 0,
+            value: 0.0,
+            shape: CurveShape::Linear,
+        },
+        AutomationCommand::DrawCurve {
+            parameter: PARAM,
+            sample: 1_000,
+            value: 1.0,
+            shape: CurveShape::Linear,
+        },
+        AutomationCommand::SetWriteMode {
+            parameter: PARAM,
+            mode: AutomationWriteMode::Latch,
+        },
+        AutomationCommand::StartPass { sample: 0 },
+        AutomationCommand::Touch {
+            parameter: PARAM,
+            sample: 10,
+            value: 0.9,
+            shape: CurveShape::Step,
+        },
+        AutomationCommand::Release {
+            parameter: PARAM,
+            sample: 20,
+            value: None,
+        },
+    ] {
+        lane.apply_command(command);
+    }
+
+    let mut events = Vec::new();
+    lane.render(500, 4, &mut events);
+    assert_eq!(events.len(), 1);
+    assert!((events[0].value - 0.9).abs() < 1e-6);
+}
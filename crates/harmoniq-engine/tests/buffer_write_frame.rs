@@ -0,0 +1,34 @@
+use harmoniq_engine::buffer::AudioBuffer;
+
+#[test]
+fn mono_target_downmixes_stereo_frame() {
+    let mut buffer = AudioBuffer::new(1, 4);
+    buffer.write_stereo_frame(1, 0.75, 0.25);
+    assert_eq!(buffer.channel(0), &[0.0, 0.5, 0.0, 0.0]);
+}
+
+#[test]
+fn stereo_target_keeps_both_sides() {
+    let mut buffer = AudioBuffer::new(2, 3);
+    buffer.write_stereo_frame(0, 1.0, -1.0);
+    buffer.write_stereo_frame(2, 0.25, 0.75);
+    assert_eq!(buffer.channel(0), &[1.0, 0.0, 0.25]);
+    assert_eq!(buffer.channel(1), &[-1.0, 0.0, 0.75]);
+}
+
+#[test]
+fn four_channel_target_repeats_stereo_pair() {
+    let mut buffer = AudioBuffer::new(4, 2);
+    buffer.write_stereo_frame(1, 0.3, 0.6);
+    assert_eq!(buffer.channel(0), &[0.0, 0.3]);
+    assert_eq!(buffer.channel(1), &[0.0, 0.6]);
+    assert_eq!(buffer.channel(2), &[0.0, 0.3]);
+    assert_eq!(buffer.channel(3), &[0.0, 0.6]);
+}
+
+#[test]
+fn out_of_range_frame_is_ignored() {
+    let mut buffer = AudioBuffer::new(2, 2);
+    buffer.write_stereo_frame(2, 1.0, 1.0);
+    assert!(buffer.iter().all(|sample| *sample == 0.0));
+}
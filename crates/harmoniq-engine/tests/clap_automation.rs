@@ -0,0 +1,171 @@
+#![cfg(feature = "clap_host")]
+
+use core::ffi::c_char;
+
+use clap_host::ffi::clap_plugin_factory_t;
+use clap_testgain::GAIN_PARAM_ID;
+use harmoniq_engine::project::{load_project, save_project, LoadOptions, SaveOptions};
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, AutomationCommand, BufferConfig, ChannelLayout, ClapSlot,
+    CommandBus, CurveShape, GraphBuilder, HarmoniqEngine, MixerInsertState, PluginDescriptor,
+    ProjectDocument, ProjectMetadata, ProjectState,
+};
+use tempfile::TempDir;
+
+const BLOCK: usize = 64;
+const TEST_GAIN_ID: &str = "studio.harmoniq.testgain";
+
+/// Emits a constant 1.0 on every channel.
+struct Dc;
+
+impl AudioProcessor for Dc {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.dc", "DC", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        for sample in buffer.as_mut_slice() {
+            *sample = 1.0;
+        }
+        Ok(())
+    }
+}
+
+/// The test gain plug-in, linked into the test binary rather than loaded
+/// from disk.
+fn test_gain() -> ClapSlot {
+    let entry = &clap_testgain::clap_entry;
+    let get_factory = entry.get_factory.expect("get_factory");
+    unsafe {
+        let factory = get_factory(b"clap.plugin-factory\0".as_ptr() as *const c_char)
+            as *const clap_plugin_factory_t;
+        let factory = factory.as_ref().expect("plug-in factory");
+        ClapSlot::from_factory(factory, TEST_GAIN_ID, 48_000.0, BLOCK as u32).expect("test gain")
+    }
+}
+
+#[test]
+fn hosted_plugin_lists_its_parameters() {
+    let slot = test_gain();
+    let parameters: Vec<_> = slot.parameters().collect();
+    assert_eq!(parameters.len(), 1);
+    let (index, gain) = parameters[0];
+    assert_eq!(index, 0);
+    assert_eq!(gain.id, GAIN_PARAM_ID);
+    assert_eq!(gain.name, "Gain");
+    assert_eq!((gain.min, gain.max, gain.default), (0.0, 2.0, 0.5));
+}
+
+#[test]
+fn automation_drives_the_hosted_gain() {
+    let config = BufferConfig::new(48_000.0, BLOCK, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let source = engine.register_processor(Box::new(Dc)).expect("source");
+    let gain = engine.register_clap_slot(test_gain()).expect("hosted gain");
+
+    let mut builder = GraphBuilder::new();
+    let source_node = builder.add_node(source);
+    let gain_node = builder.add_node(gain);
+    builder
+        .connect(source_node, gain_node, 1.0)
+        .expect("insert route");
+    builder
+        .connect_to_mixer(source_node, 0.0)
+        .expect("dry route");
+    builder.connect_to_mixer(gain_node, 1.0).expect("wet route");
+    engine.replace_graph(builder.build()).expect("graph");
+
+    let spec = engine
+        .automation_parameter_spec(gain, 0)
+        .expect("gain spec");
+    assert_eq!(spec.name, "Gain");
+
+    let sender = engine.automation_sender(gain).expect("automation sender");
+    for (sample, value) in [(0, 0.25), (32, 1.5)] {
+        sender
+            .send(AutomationCommand::DrawCurve {
+                parameter: 0,
+                sample,
+                value,
+                shape: CurveShape::Step,
+            })
+            .expect("send automation");
+    }
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    engine.process_block(&mut buffer).expect("process");
+
+    let left = buffer.channel(0);
+    assert!(left[..32].iter().all(|sample| (sample - 0.25).abs() < 1e-6));
+    assert!(left[32..].iter().all(|sample| (sample - 1.5).abs() < 1e-6));
+}
+
+/// Hosts `slot` as the first insert of the project's second track.
+fn host_insert(slot: ClapSlot) -> (HarmoniqEngine, ProjectState) {
+    let config = BufferConfig::new(48_000.0, BLOCK, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config).expect("engine");
+    let source = engine.register_processor(Box::new(Dc)).expect("source");
+    let insert = engine.register_clap_slot(slot).expect("hosted gain");
+    let state = CommandBus::default().state().clone();
+    engine.set_track_plugins(state.arrangement.tracks[1].id, vec![source, insert]);
+    (engine, state)
+}
+
+#[test]
+fn inserts_survive_a_project_round_trip() {
+    // Loaded from a build of the plug-in that had a parameter before "Gain".
+    let mut older = test_gain().with_parameter_ids(vec![0xDEAD_BEEF, GAIN_PARAM_ID]);
+    older.set_bypass(true);
+    older
+        .load_state(&1.25f32.to_le_bytes())
+        .expect("gain state");
+    let (engine, mut state) = host_insert(older);
+    state.mixer.tracks[1].pre_inserts = vec![MixerInsertState {
+        bypassed: true,
+        ..MixerInsertState::default()
+    }];
+    engine
+        .store_insert_states(&mut state)
+        .expect("store inserts");
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("session.hsq");
+    let document = ProjectDocument::new(
+        ProjectMetadata::new("Demo", 48_000.0, BLOCK, 2, 4.0),
+        Vec::new(),
+    )
+    .with_state(state);
+    save_project(&path, &document, SaveOptions::default()).expect("save");
+    let loaded = load_project(&path, LoadOptions::default())
+        .expect("load")
+        .document
+        .state;
+
+    let insert = &loaded.mixer.tracks[1].pre_inserts[0];
+    assert_eq!(insert.id.as_deref(), Some(TEST_GAIN_ID));
+    assert_eq!(insert.parameter_ids, vec![0xDEAD_BEEF, GAIN_PARAM_ID]);
+    assert_eq!(insert.state, 1.25f32.to_le_bytes());
+    assert!(loaded.mixer.tracks[0].pre_inserts.is_empty());
+
+    let slot = test_gain().with_insert_state(insert);
+    assert!(slot.bypass);
+    assert_eq!(slot.parameter_index(GAIN_PARAM_ID), Some(1));
+    let specs = slot.parameter_specs();
+    assert_eq!(specs.len(), 1);
+    assert_eq!((specs[0].index, specs[0].name.as_str()), (1, "Gain"));
+
+    let (engine, mut restored) = host_insert(slot);
+    restored.mixer = loaded.mixer.clone();
+    engine
+        .restore_insert_states(&restored)
+        .expect("restore inserts");
+    restored.mixer.tracks[1].pre_inserts[0].state.clear();
+    engine
+        .store_insert_states(&mut restored)
+        .expect("store inserts");
+    assert_eq!(restored.mixer, loaded.mixer);
+}
@@ -0,0 +1,56 @@
+use harmoniq_engine::clips::{AudioClip, FadeCurve, FadeSpec};
+use harmoniq_engine::timeline::{ClipEvent, Timeline};
+
+const CLIP_FRAMES: usize = 4_800;
+const LOOP_FRAMES: usize = 3 * CLIP_FRAMES;
+
+/// Rising ramp, so the end of the clip sits far above its start.
+fn ramp_clip() -> AudioClip {
+    let samples = (0..CLIP_FRAMES)
+        .map(|index| -0.5 + index as f32 / CLIP_FRAMES as f32)
+        .collect();
+    AudioClip::with_sample_rate(48_000.0, vec![samples])
+}
+
+fn render(event: ClipEvent) -> Vec<f32> {
+    let mut timeline = Timeline::new(48_000.0, 1);
+    timeline.add_clip(event);
+    let rendered = timeline.render().expect("render");
+    rendered.channel(0).unwrap().to_vec()
+}
+
+fn max_step(samples: &[f32]) -> f32 {
+    samples
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn loop_crossfade_removes_seam_discontinuity() {
+    let hard = render(ClipEvent::new(ramp_clip(), 0).with_loop(LOOP_FRAMES));
+    let smooth = render(
+        ClipEvent::new(ramp_clip(), 0)
+            .with_loop(LOOP_FRAMES)
+            .with_loop_crossfade(FadeSpec::new(480, FadeCurve::Linear)),
+    );
+
+    assert_eq!(hard.len(), LOOP_FRAMES);
+    assert_eq!(smooth.len(), LOOP_FRAMES);
+    assert!(max_step(&hard) > 0.9);
+    assert!(max_step(&smooth) < 0.005, "step {}", max_step(&smooth));
+}
+
+#[test]
+fn looped_clip_repeats_with_shortened_period() {
+    let clip = ramp_clip();
+    let looped = clip.looped(LOOP_FRAMES, Some(FadeSpec::new(480, FadeCurve::EqualPower)));
+    let samples = looped.channel(0).unwrap();
+    let source = clip.channel(0).unwrap();
+    let period = CLIP_FRAMES - 480;
+
+    assert_eq!(&samples[..period], &source[..period]);
+    // Past the crossfade the second pass plays the source again.
+    assert_eq!(samples[period + 1_000], source[1_000]);
+    assert_eq!(samples[2 * period + 2_000], source[2_000]);
+}
@@ -15,6 +15,9 @@ fn undo_redo_restores_clip_position() {
         start: 0.0,
         length: 4.0,
         media: None,
+        color: None,
+        tags: Vec::new(),
+        locked: false,
     };
     bus.execute(AddClipCommand { track_id, clip }).unwrap();
 
@@ -47,6 +50,9 @@ fn command_merging_collapses_history() {
         start: 0.0,
         length: 8.0,
         media: None,
+        color: None,
+        tags: Vec::new(),
+        locked: false,
     };
     bus.execute(AddClipCommand { track_id, clip }).unwrap();
 
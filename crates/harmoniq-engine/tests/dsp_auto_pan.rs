@@ -0,0 +1,86 @@
+use harmoniq_dsp::{AudioBlock, AudioBlockMut};
+use harmoniq_engine::dsp::nodes::{AutoPanNode, AutoPanShape};
+use harmoniq_engine::dsp::{DspGraph, GraphProcess, Transport};
+use harmoniq_engine::Tempo;
+
+const SR: f32 = 48_000.0;
+const BLOCK: u32 = 256;
+/// One beat at 120 BPM.
+const CYCLE: usize = 24_000;
+
+/// Auto-pans a constant mono signal for one synced cycle and returns the
+/// pan position recovered from the output gains of every frame.
+fn render_pan(shape: AutoPanShape, width: f32) -> Vec<f32> {
+    let mut graph = DspGraph::new();
+    let (id, _) = graph.add_node(Box::new(AutoPanNode::new(shape, 1.0, width)), 4);
+    graph.set_topology(&[id]);
+    graph.prepare(SR, BLOCK, 1, 2);
+
+    let input = vec![1.0f32; BLOCK as usize];
+    let mut pan = Vec::with_capacity(CYCLE);
+    let mut position = 0;
+    while position < CYCLE {
+        let frames = BLOCK.min((CYCLE - position) as u32);
+        let mut block = vec![0.0f32; 2 * frames as usize];
+        let transport = Transport {
+            tempo: Tempo(120.0),
+            sample_position: position as u64,
+            is_playing: true,
+            ..Transport::default()
+        };
+        unsafe {
+            graph.process(GraphProcess {
+                inputs: AudioBlock::from_interleaved(input.as_ptr(), 1, frames),
+                outputs: AudioBlockMut::from_interleaved(block.as_mut_ptr(), 2, frames),
+                frames,
+                transport,
+                midi: &[],
+            });
+        }
+        pan.extend(block.chunks(2).map(|frame| {
+            let angle = frame[1].atan2(frame[0]);
+            angle / std::f32::consts::FRAC_PI_2 * 2.0 - 1.0
+        }));
+        position += frames as usize;
+    }
+    pan
+}
+
+#[test]
+fn pan_traces_shape_over_one_cycle() {
+    for shape in [
+        AutoPanShape::Sine,
+        AutoPanShape::Triangle,
+        AutoPanShape::Square,
+    ] {
+        let pan = render_pan(shape, 1.0);
+        for (frame, value) in pan.iter().enumerate() {
+            let expected = shape.value_at(frame as f64 / CYCLE as f64);
+            assert!(
+                (value - expected).abs() < 1e-3,
+                "{shape:?} frame {frame}: {value} vs {expected}"
+            );
+        }
+    }
+
+    let sine = render_pan(AutoPanShape::Sine, 1.0);
+    assert!(sine[0].abs() < 1e-3);
+    assert!((sine[CYCLE / 4] - 1.0).abs() < 1e-3);
+    assert!((sine[3 * CYCLE / 4] + 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn width_scales_excursion() {
+    for shape in [AutoPanShape::Sine, AutoPanShape::Triangle] {
+        let full = render_pan(shape, 1.0);
+        let half = render_pan(shape, 0.5);
+        for (full, half) in full.iter().zip(&half) {
+            assert!((half - full * 0.5).abs() < 1e-3);
+        }
+        let peak = half.iter().fold(0.0f32, |max, pan| max.max(pan.abs()));
+        assert!((peak - 0.5).abs() < 1e-3, "{shape:?} peak {peak}");
+    }
+
+    let none = render_pan(AutoPanShape::Square, 0.0);
+    assert!(none.iter().all(|pan| pan.abs() < 1e-3));
+}
@@ -0,0 +1,100 @@
+use harmoniq_dsp::{AudioBlock, AudioBlockMut};
+use harmoniq_engine::dsp::{nodes::BitCrusherNode, DspGraph, GraphProcess, Transport};
+
+const SR: f32 = 48_000.0;
+const BLOCK: u32 = 256;
+
+/// Runs a mono signal through the node and returns the output.
+fn render(node: BitCrusherNode, input: &[f32]) -> Vec<f32> {
+    render_at(SR, node, input)
+}
+
+fn render_at(sr: f32, node: BitCrusherNode, input: &[f32]) -> Vec<f32> {
+    let mut graph = DspGraph::new();
+    let (id, _) = graph.add_node(Box::new(node), 4);
+    graph.set_topology(&[id]);
+    graph.prepare(sr, BLOCK, 1, 1);
+
+    let mut output = Vec::with_capacity(input.len());
+    for chunk in input.chunks(BLOCK as usize) {
+        let mut block = vec![0.0f32; chunk.len()];
+        unsafe {
+            graph.process(GraphProcess {
+                inputs: AudioBlock::from_interleaved(chunk.as_ptr(), 1, chunk.len() as u32),
+                outputs: AudioBlockMut::from_interleaved(block.as_mut_ptr(), 1, chunk.len() as u32),
+                frames: chunk.len() as u32,
+                transport: Transport::default(),
+                midi: &[],
+            });
+        }
+        output.extend_from_slice(&block);
+    }
+    output
+}
+
+/// Runs a mono ramp from -1 to 1 through the node.
+fn render_ramp(node: BitCrusherNode, frames: usize) -> (Vec<f32>, Vec<f32>) {
+    let ramp: Vec<f32> = (0..frames)
+        .map(|i| -1.0 + 2.0 * i as f32 / (frames - 1) as f32)
+        .collect();
+    let output = render(node, &ramp);
+    (ramp, output)
+}
+
+#[test]
+fn four_bits_quantize_to_sixteen_levels() {
+    let (_, output) = render_ramp(BitCrusherNode::new(4.0, SR), 4_096);
+    let mut levels: Vec<i32> = output
+        .iter()
+        .map(|sample| (sample * 1_000_000.0).round() as i32)
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+    assert_eq!(levels.len(), 16);
+    for sample in &output {
+        let step = sample * 8.0;
+        assert!(
+            (step - step.round()).abs() < 1e-4,
+            "{sample} is off the grid"
+        );
+    }
+}
+
+#[test]
+fn silence_stays_silent() {
+    for bits in [1.0, 4.0, 8.0, 16.0] {
+        let output = render(BitCrusherNode::new(bits, SR / 3.0), &[0.0; 1_000]);
+        assert!(
+            output.iter().all(|sample| *sample == 0.0),
+            "{bits} bits added an offset"
+        );
+    }
+}
+
+#[test]
+fn rate_reduction_holds_each_value() {
+    // 12 kHz at 48 kHz: every captured value is held for four samples.
+    let (ramp, output) = render_ramp(BitCrusherNode::new(24.0, 12_000.0), 1_024);
+    for (index, sample) in output.iter().enumerate() {
+        let source = ramp[index - index % 4];
+        assert_eq!(*sample, source, "frame {index}");
+    }
+}
+
+#[test]
+fn maximum_settings_are_transparent() {
+    let (ramp, output) = render_ramp(BitCrusherNode::default(), 1_000);
+    assert_eq!(ramp, output);
+}
+
+#[test]
+fn target_rate_follows_the_prepared_rate() {
+    // 12 kHz at 96 kHz: every captured value is held for eight samples.
+    let ramp: Vec<f32> = (0..1_024).map(|i| i as f32 / 1_024.0).collect();
+    let output = render_at(96_000.0, BitCrusherNode::new(24.0, 12_000.0), &ramp);
+    for (index, sample) in output.iter().enumerate() {
+        assert_eq!(*sample, ramp[index - index % 8], "frame {index}");
+    }
+    // The default leaves any rate untouched.
+    assert_eq!(render_at(96_000.0, BitCrusherNode::default(), &ramp), ramp);
+}
@@ -0,0 +1,88 @@
+use harmoniq_dsp::{AudioBlock, AudioBlockMut};
+use harmoniq_engine::dsp::{nodes::DuckerNode, DspGraph, GraphProcess, Transport};
+
+const SR: f32 = 48_000.0;
+const BLOCK: u32 = 256;
+const PAD: f32 = 0.5;
+const KICK_FRAMES: usize = 1_920;
+const KICKS: [usize; 2] = [4_800, 28_800];
+const TOTAL: usize = 48_000;
+
+/// 40 ms bursts of a 60 Hz sine standing in for kick drums.
+fn kick(frame: usize) -> f32 {
+    KICKS
+        .iter()
+        .find(|start| frame >= **start && frame < **start + KICK_FRAMES)
+        .map(|start| {
+            let t = (frame - start) as f32 / SR;
+            0.9 * (std::f32::consts::TAU * 60.0 * t).sin()
+        })
+        .unwrap_or(0.0)
+}
+
+/// Ducks a constant pad with the kick on the key input and returns the
+/// per-frame gain applied to the pad.
+fn render_gain() -> Vec<f32> {
+    let mut graph = DspGraph::new();
+    let (id, _) = graph.add_node(Box::new(DuckerNode::new(-30.0, 10.0, 1.0, 100.0)), 4);
+    graph.set_topology(&[id]);
+    graph.prepare(SR, BLOCK, 2, 1);
+
+    let input: Vec<f32> = (0..TOTAL).flat_map(|frame| [PAD, kick(frame)]).collect();
+    let mut output = Vec::with_capacity(TOTAL);
+    for chunk in input.chunks(2 * BLOCK as usize) {
+        let frames = (chunk.len() / 2) as u32;
+        let mut block = vec![0.0f32; frames as usize];
+        unsafe {
+            graph.process(GraphProcess {
+                inputs: AudioBlock::from_interleaved(chunk.as_ptr(), 2, frames),
+                outputs: AudioBlockMut::from_interleaved(block.as_mut_ptr(), 1, frames),
+                frames,
+                transport: Transport::default(),
+                midi: &[],
+            });
+        }
+        output.extend(block.iter().map(|sample| sample / PAD));
+    }
+    output
+}
+
+#[test]
+fn kick_ducks_pad_in_time() {
+    let gain = render_gain();
+    assert!(gain[..KICKS[0]].iter().all(|g| (*g - 1.0).abs() < 1e-6));
+
+    for start in KICKS {
+        // Within a few milliseconds of each hit the pad is well ducked.
+        let onset = start + 144;
+        assert!(
+            gain[onset] < 0.25,
+            "gain {} after kick at {start}",
+            gain[onset]
+        );
+        let deepest = gain[start..start + KICK_FRAMES]
+            .iter()
+            .fold(1.0f32, |min, g| min.min(*g));
+        assert!(deepest < 0.1, "deepest {deepest}");
+    }
+}
+
+#[test]
+fn pad_recovers_per_release() {
+    let gain = render_gain();
+    let kick_end = KICKS[0] + KICK_FRAMES;
+    let recovered = kick_end + 19_200;
+
+    let release = &gain[kick_end..recovered];
+    assert!(release.windows(2).all(|pair| pair[1] >= pair[0]));
+
+    // 100 ms release, 10:1 above -30 dB: after 50 ms the pad is still held
+    // down, after 150 ms it is partially back, and after 400 ms it is fully
+    // restored before the next kick.
+    let early = gain[kick_end + 2_400];
+    let later = gain[kick_end + 7_200];
+    assert!(early < 0.2, "early {early}");
+    assert!(later > early && later < 0.5, "later {later}");
+    assert!(gain[recovered] > 0.999, "recovered {}", gain[recovered]);
+    assert!(gain[recovered..KICKS[1]].iter().all(|g| *g > 0.999));
+}
@@ -0,0 +1,210 @@
+use std::sync::{Arc, Mutex};
+
+use harmoniq_dsp::{AudioBlock, AudioBlockMut};
+use harmoniq_engine::dsp::{
+    nodes::{EnvelopeNode, EnvelopeSegment, ModulationRoute},
+    DspGraph, DspNode, GraphProcess, MidiEvent, ParamRamp, ParamUpdate, ProcessContext, Transport,
+};
+
+const SR: f32 = 48_000.0;
+const BLOCK: u32 = 48;
+
+struct ParamRecorder {
+    current: f32,
+    history: Arc<Mutex<Vec<f32>>>,
+}
+
+impl DspNode for ParamRecorder {
+    fn prepare(&mut self, _sr: f32, _max_block: u32, _in: u32, _out: u32) {}
+
+    fn param(&mut self, update: ParamUpdate) {
+        if update.id == 3 {
+            self.current = update.value;
+        }
+    }
+
+    fn process(&mut self, _ctx: &mut ProcessContext<'_>) {
+        self.history.lock().unwrap().push(self.current);
+    }
+}
+
+/// Follows the routed parameter sample by sample.
+struct RampRecorder {
+    ramp: ParamRamp,
+    samples: Arc<Mutex<Vec<f32>>>,
+}
+
+impl DspNode for RampRecorder {
+    fn prepare(&mut self, _sr: f32, _max_block: u32, _in: u32, _out: u32) {}
+
+    fn param(&mut self, update: ParamUpdate) {
+        if update.id == 3 {
+            self.ramp.apply(update);
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext<'_>) {
+        let mut samples = self.samples.lock().unwrap();
+        for _ in 0..ctx.frames {
+            samples.push(self.ramp.next());
+        }
+    }
+}
+
+fn run_block(graph: &mut DspGraph, midi: &[MidiEvent]) {
+    let input = vec![0.0f32; 2 * BLOCK as usize];
+    let mut output = vec![0.0f32; 2 * BLOCK as usize];
+    unsafe {
+        graph.process(GraphProcess {
+            inputs: AudioBlock::from_interleaved(input.as_ptr(), 2, BLOCK),
+            outputs: AudioBlockMut::from_interleaved(output.as_mut_ptr(), 2, BLOCK),
+            frames: BLOCK,
+            transport: Transport::default(),
+            midi,
+        });
+    }
+}
+
+#[test]
+fn routed_parameter_follows_envelope_shape() {
+    let history = Arc::new(Mutex::new(Vec::new()));
+    let mut graph = DspGraph::new();
+    let (target_id, target_port) = graph.add_node(
+        Box::new(ParamRecorder {
+            current: 0.0,
+            history: Arc::clone(&history),
+        }),
+        64,
+    );
+    let route = ModulationRoute::new(target_port.expect("param port"), 3, 0.0, 1.0);
+    // 1 ms blocks: 10 ms attack, 10 ms decay to 0.5, 10 ms release.
+    let envelope = EnvelopeNode::adsr(0.010, 0.010, 0.5, 0.010).with_route(route);
+    let (env_id, _) = graph.add_node(Box::new(envelope), 0);
+    graph.set_topology(&[env_id, target_id]);
+    graph.prepare(SR, BLOCK, 2, 2);
+
+    run_block(&mut graph, &[MidiEvent::new(0, [0x90, 60, 100])]);
+    for _ in 1..40 {
+        run_block(&mut graph, &[]);
+    }
+    run_block(&mut graph, &[MidiEvent::new(0, [0x80, 60, 0])]);
+    for _ in 0..20 {
+        run_block(&mut graph, &[]);
+    }
+
+    let values = history.lock().unwrap().clone();
+    assert_eq!(values.len(), 61);
+
+    // Attack ramps linearly up to full scale.
+    for window in values[..10].windows(2) {
+        assert!(window[1] > window[0], "attack must rise: {values:?}");
+    }
+    assert!((values[0] - 0.1).abs() < 1e-3);
+    assert!((values[9] - 1.0).abs() < 1e-3);
+
+    // Decay falls towards the sustain level and then holds.
+    for window in values[9..20].windows(2) {
+        assert!(window[1] < window[0], "decay must fall: {values:?}");
+    }
+    for value in &values[20..40] {
+        assert!((value - 0.5).abs() < 1e-3);
+    }
+
+    // Release returns to silence after the note-off.
+    for window in values[40..50].windows(2) {
+        assert!(window[1] < window[0], "release must fall: {values:?}");
+    }
+    for value in &values[50..] {
+        assert!(value.abs() < 1e-3);
+    }
+}
+
+#[test]
+fn looping_envelope_repeats_while_gate_is_held() {
+    let history = Arc::new(Mutex::new(Vec::new()));
+    let mut graph = DspGraph::new();
+    let (target_id, target_port) = graph.add_node(
+        Box::new(ParamRecorder {
+            current: 0.0,
+            history: Arc::clone(&history),
+        }),
+        64,
+    );
+    let route = ModulationRoute::new(target_port.expect("param port"), 3, 0.0, 1.0);
+    let envelope = EnvelopeNode::new(vec![
+        EnvelopeSegment::new(1.0, 0.002),
+        EnvelopeSegment::new(0.0, 0.002),
+    ])
+    .with_loop(0, 1)
+    .with_route(route);
+    let (env_id, _) = graph.add_node(Box::new(envelope), 0);
+    graph.set_topology(&[env_id, target_id]);
+    graph.prepare(SR, BLOCK, 2, 2);
+
+    run_block(&mut graph, &[MidiEvent::new(0, [0x90, 64, 90])]);
+    for _ in 1..12 {
+        run_block(&mut graph, &[]);
+    }
+
+    let values = history.lock().unwrap().clone();
+    for cycle in 0..3 {
+        let base = cycle * 4;
+        assert!((values[base] - 0.5).abs() < 1e-3);
+        assert!((values[base + 1] - 1.0).abs() < 1e-3);
+        assert!((values[base + 2] - 0.5).abs() < 1e-3);
+        assert!(values[base + 3].abs() < 1e-3);
+    }
+}
+
+#[test]
+fn routed_parameter_ramps_within_each_block() {
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let mut graph = DspGraph::new();
+    let (target_id, target_port) = graph.add_node(
+        Box::new(RampRecorder {
+            ramp: ParamRamp::new(0.0),
+            samples: Arc::clone(&samples),
+        }),
+        64,
+    );
+    let route = ModulationRoute::new(target_port.expect("param port"), 3, 0.0, 1.0);
+    let envelope = EnvelopeNode::adsr(0.010, 0.010, 0.5, 0.010).with_route(route);
+    let (env_id, _) = graph.add_node(Box::new(envelope), 0);
+    graph.set_topology(&[env_id, target_id]);
+    graph.prepare(SR, BLOCK, 2, 2);
+
+    run_block(&mut graph, &[MidiEvent::new(0, [0x90, 60, 100])]);
+    run_block(&mut graph, &[]);
+
+    let samples = samples.lock().unwrap().clone();
+    assert_eq!(samples.len(), 2 * BLOCK as usize);
+    for window in samples.windows(2) {
+        assert!(window[1] > window[0], "attack must rise per sample");
+    }
+    assert!((samples[BLOCK as usize - 1] - 0.1).abs() < 1e-3);
+    assert!((samples[2 * BLOCK as usize - 1] - 0.2).abs() < 1e-3);
+}
+
+#[test]
+fn every_param_port_reaches_the_node() {
+    let history = Arc::new(Mutex::new(Vec::new()));
+    let mut graph = DspGraph::new();
+    let (target_id, first) = graph.add_node(
+        Box::new(ParamRecorder {
+            current: 0.0,
+            history: Arc::clone(&history),
+        }),
+        4,
+    );
+    let mut first = first.expect("param port");
+    let mut second = graph.param_port(target_id).expect("second port");
+    graph.set_topology(&[target_id]);
+    graph.prepare(SR, BLOCK, 2, 2);
+
+    first.send(ParamUpdate::new(3, 0.25));
+    run_block(&mut graph, &[]);
+    second.send(ParamUpdate::new(3, 0.75));
+    run_block(&mut graph, &[]);
+
+    assert_eq!(history.lock().unwrap().as_slice(), &[0.25, 0.75]);
+}
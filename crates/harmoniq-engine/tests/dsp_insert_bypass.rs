@@ -0,0 +1,138 @@
+use harmoniq_dsp::{AudioBlock, AudioBlockMut};
+use harmoniq_engine::dsp::{
+    nodes::{BypassMode, InsertSlot},
+    DspGraph, DspNode, GraphProcess, NodeLatency, ParamUpdate, ProcessContext, Transport,
+};
+
+const CHANNELS: u32 = 2;
+const BLOCK: u32 = 64;
+const LATENCY: usize = 100;
+
+/// Pure delay standing in for a look-ahead or linear-phase insert.
+struct LatencyNode {
+    lines: Vec<Vec<f32>>,
+    pos: usize,
+}
+
+impl LatencyNode {
+    fn new() -> Self {
+        Self {
+            lines: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl DspNode for LatencyNode {
+    fn prepare(&mut self, _sr: f32, _max_block: u32, _in: u32, out_ch: u32) {
+        self.lines = vec![vec![0.0; LATENCY]; out_ch as usize];
+        self.pos = 0;
+    }
+
+    fn latency(&self) -> NodeLatency {
+        NodeLatency {
+            samples: LATENCY as u32,
+        }
+    }
+
+    fn process(&mut self, ctx: &mut ProcessContext<'_>) {
+        for frame in 0..ctx.frames as usize {
+            for (ch, line) in self.lines.iter_mut().enumerate() {
+                let input = unsafe { ctx.inputs.read_sample(ch, frame) };
+                let delayed = line[self.pos];
+                line[self.pos] = input;
+                unsafe { ctx.outputs.write_sample(ch, frame, delayed) };
+            }
+            self.pos = (self.pos + 1) % LATENCY;
+        }
+    }
+}
+
+/// Renders an impulse through the slot and returns the left channel.
+fn render_impulse(slot: InsertSlot, blocks: usize) -> (Vec<f32>, u32) {
+    let mut graph = DspGraph::new();
+    let (id, _) = graph.add_node(Box::new(slot), 4);
+    graph.set_topology(&[id]);
+    graph.prepare(48_000.0, BLOCK, CHANNELS, CHANNELS);
+
+    let mut left = Vec::new();
+    for block in 0..blocks {
+        let mut input = vec![0.0f32; (CHANNELS * BLOCK) as usize];
+        if block == 0 {
+            input[0] = 1.0;
+            input[1] = 1.0;
+        }
+        let mut output = vec![0.0f32; (CHANNELS * BLOCK) as usize];
+        unsafe {
+            graph.process(GraphProcess {
+                inputs: AudioBlock::from_interleaved(input.as_ptr(), CHANNELS, BLOCK),
+                outputs: AudioBlockMut::from_interleaved(output.as_mut_ptr(), CHANNELS, BLOCK),
+                frames: BLOCK,
+                transport: Transport::default(),
+                midi: &[],
+            });
+        }
+        left.extend(output.iter().step_by(CHANNELS as usize));
+    }
+    (left, graph.total_latency().samples)
+}
+
+fn impulse_position(samples: &[f32]) -> Option<usize> {
+    samples
+        .iter()
+        .position(|sample| (sample - 1.0).abs() < 1e-6)
+}
+
+#[test]
+fn latency_preserving_bypass_delays_dry_signal() {
+    let slot = InsertSlot::new(Box::new(LatencyNode::new()))
+        .with_mode(BypassMode::LatencyPreserving)
+        .with_bypass(true);
+    let (output, latency) = render_impulse(slot, 4);
+    assert_eq!(latency, LATENCY as u32);
+    assert_eq!(impulse_position(&output), Some(LATENCY));
+}
+
+#[test]
+fn immediate_bypass_passes_dry_signal_through() {
+    let slot = InsertSlot::new(Box::new(LatencyNode::new()))
+        .with_mode(BypassMode::Immediate)
+        .with_bypass(true);
+    let (output, latency) = render_impulse(slot, 4);
+    assert_eq!(latency, 0);
+    assert_eq!(impulse_position(&output), Some(0));
+}
+
+#[test]
+fn bypass_param_matches_active_insert_timing() {
+    let mut graph = DspGraph::new();
+    let slot = InsertSlot::new(Box::new(LatencyNode::new()));
+    let (id, port) = graph.add_node(Box::new(slot), 4);
+    graph.set_topology(&[id]);
+    graph.prepare(48_000.0, BLOCK, CHANNELS, CHANNELS);
+
+    // Active for the first block, bypassed from then on: the impulse must not move.
+    let port = port.expect("param port");
+    let mut left = Vec::new();
+    for block in 0..4 {
+        if block == 1 {
+            port.send(ParamUpdate::new(InsertSlot::BYPASS_PARAM, 1.0));
+        }
+        let mut input = vec![0.0f32; (CHANNELS * BLOCK) as usize];
+        if block == 0 {
+            input[0] = 1.0;
+        }
+        let mut output = vec![0.0f32; (CHANNELS * BLOCK) as usize];
+        unsafe {
+            graph.process(GraphProcess {
+                inputs: AudioBlock::from_interleaved(input.as_ptr(), CHANNELS, BLOCK),
+                outputs: AudioBlockMut::from_interleaved(output.as_mut_ptr(), CHANNELS, BLOCK),
+                frames: BLOCK,
+                transport: Transport::default(),
+                midi: &[],
+            });
+        }
+        left.extend(output.iter().step_by(CHANNELS as usize));
+    }
+    assert_eq!(impulse_position(&left), Some(LATENCY));
+}
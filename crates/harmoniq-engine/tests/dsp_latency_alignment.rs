@@ -38,10 +38,10 @@ fn dsp_graph_latency_alignment_matches_golden() {
 
     graph.prepare(48_000.0, frames, channels, channels);
 
-    if let Some(port) = gain_port {
+    if let Some(mut port) = gain_port {
         let _ = port.try_send(ParamUpdate::new(0, -3.0));
     }
-    if let Some(port) = delay_port {
+    if let Some(mut port) = delay_port {
         let _ = port.try_send(ParamUpdate::new(0, 0.001));
         let _ = port.try_send(ParamUpdate::new(1, 0.25));
         let _ = port.try_send(ParamUpdate::new(2, 0.5));
@@ -0,0 +1,72 @@
+use harmoniq_engine::dsp::nodes::{SpectrumHandle, SpectrumTap, SPECTRUM_FLOOR_DB};
+use harmoniq_engine::AudioBuffer;
+
+const SR: f32 = 48_000.0;
+const SIZE: usize = 1_024;
+const BLOCK: usize = 256;
+
+/// A stereo sine at the centre of `bin`, `frames` long, in blocks.
+fn tone_blocks(bin: usize, amplitude: f32, frames: usize) -> Vec<AudioBuffer> {
+    let frequency = bin as f32 * SR / SIZE as f32;
+    (0..frames / BLOCK)
+        .map(|block| {
+            let mut buffer = AudioBuffer::new(2, BLOCK);
+            for channel in buffer.channels_mut() {
+                for (offset, sample) in channel.iter_mut().enumerate() {
+                    let t = (block * BLOCK + offset) as f32 / SR;
+                    *sample = amplitude * (std::f32::consts::TAU * frequency * t).sin();
+                }
+            }
+            buffer
+        })
+        .collect()
+}
+
+#[test]
+fn sine_peaks_in_its_bin_at_its_level() {
+    let handle = SpectrumHandle::new(SIZE);
+    let mut tap = SpectrumTap::new(SR, handle.clone());
+    assert!(handle.spectrum().iter().all(|db| *db == SPECTRUM_FLOOR_DB));
+
+    let blocks = tone_blocks(64, 0.5, 8 * SIZE);
+    for block in &blocks {
+        let before = block.clone();
+        tap.process_buffer(block);
+        assert_eq!(block.channel(0), before.channel(0));
+    }
+
+    let spectrum = handle.spectrum();
+    assert_eq!(spectrum.len(), SIZE / 2 + 1);
+    assert_eq!(tap.bin_frequency(64), 3_000.0);
+    let (peak, level) = spectrum
+        .iter()
+        .enumerate()
+        .fold(
+            (0, f32::MIN),
+            |best, (bin, db)| if *db > best.1 { (bin, *db) } else { best },
+        );
+    assert_eq!(peak, 64);
+    // 0.5 is -6.02 dBFS.
+    assert!((level + 6.02).abs() < 0.1, "{level}");
+    // Hann sidelobes are well down a few bins away.
+    assert!(spectrum[70] < level - 60.0, "{}", spectrum[70]);
+}
+
+#[test]
+fn analysis_rate_is_decimated_for_the_ui() {
+    let handle = SpectrumHandle::new(SIZE);
+    // Overlap 4 would analyse every 256 samples, 187 times a second.
+    let mut tap = SpectrumTap::new(SR, handle.clone()).with_overlap(4);
+    for block in tone_blocks(10, 0.5, 48_128) {
+        tap.process_buffer(&block);
+    }
+    assert_eq!(handle.generation(), 60);
+
+    // Large transforms at low overlap already run slower than that.
+    let handle = SpectrumHandle::new(8_192);
+    let mut tap = SpectrumTap::new(SR, handle.clone()).with_overlap(2);
+    for block in tone_blocks(10, 0.5, 48_128) {
+        tap.process_buffer(&block);
+    }
+    assert_eq!(handle.generation(), 48_128 / 4_096);
+}
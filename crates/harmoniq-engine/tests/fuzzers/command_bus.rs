@@ -66,6 +66,9 @@ proptest! {
                         start: 0.0,
                         length: (length as f32).max(1.0),
                         media: None,
+                        color: None,
+                        tags: Vec::new(),
+                        locked: false,
                     };
                     next_clip_id += 1;
                     if bus.execute(AddClipCommand { track_id, clip }).is_ok() {
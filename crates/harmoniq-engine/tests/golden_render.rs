@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
-use harmoniq_engine::render::{RenderDuration, RenderProject, RenderRequest, RenderSpeed};
+use harmoniq_engine::render::{
+    Normalization, RenderDuration, RenderProject, RenderRequest, RenderSpeed,
+};
 use harmoniq_engine::{
     nodes::NodeOsc, AudioClip, BufferConfig, ChannelLayout, GraphBuilder, HarmoniqEngine,
 };
@@ -42,6 +44,8 @@ fn render_golden_clip() -> AudioClip {
         stems: None,
         freeze: None,
         speed: RenderSpeed::Offline,
+        master_oversample: 1,
+        normalization: Normalization::None,
     };
 
     let result = renderer.render(&request).expect("render result");
@@ -0,0 +1,188 @@
+use harmoniq_engine::{
+    pin_signal, port_count, Edge, GraphBuilder, GraphError, NodeKind, PinDirection, PluginId,
+    SignalKind, DEFAULT_MAX_LATENCY_CAP,
+};
+
+#[test]
+fn plugin_pins_are_validated_against_port_count() {
+    let kind = NodeKind::Plugin { id: PluginId(1) };
+    assert_eq!(port_count(&kind), (3, 2));
+
+    let mut builder = GraphBuilder::new();
+    let input = builder.add_input();
+    let plugin = builder.add_node(PluginId(1));
+
+    builder
+        .connect(input.pin(0), plugin.pin(0), 1.0)
+        .expect("pin 0 exists");
+
+    let err = builder
+        .connect(input.pin(0), plugin.pin(3), 1.0)
+        .expect_err("plugins have audio, MIDI and sidechain inputs");
+    assert!(matches!(
+        err,
+        GraphError::InvalidPin {
+            direction: PinDirection::Input,
+            index: 3,
+            available: 3,
+        }
+    ));
+}
+
+#[test]
+fn input_nodes_expose_no_input_pins() {
+    let mut builder = GraphBuilder::new();
+    let first = builder.add_input();
+    let second = builder.add_input();
+    let err = builder.connect(first, second, 1.0).unwrap_err();
+    assert!(matches!(
+        err,
+        GraphError::InvalidPin {
+            direction: PinDirection::Input,
+            index: 0,
+            available: 0,
+        }
+    ));
+}
+
+#[test]
+fn feedback_loops_are_rejected() {
+    let mut builder = GraphBuilder::new();
+    let a = builder.add_node(PluginId(1));
+    let b = builder.add_node(PluginId(2));
+    let c = builder.add_node(PluginId(3));
+    builder.connect(a, b, 1.0).expect("a -> b");
+    builder.connect(b, c, 1.0).expect("b -> c");
+
+    let err = builder.connect(c, a, 1.0).unwrap_err();
+    assert!(matches!(
+        err,
+        GraphError::WouldCreateCycle { from, to } if from == c && to == a
+    ));
+    assert!(matches!(
+        builder.connect(b, b, 1.0),
+        Err(GraphError::WouldCreateCycle { .. })
+    ));
+
+    // Parallel paths are not cycles.
+    builder.connect(a, c, 1.0).expect("a -> c");
+}
+
+#[test]
+fn disconnect_removes_a_single_edge() {
+    let mut builder = GraphBuilder::new();
+    let input = builder.add_input();
+    let send = builder.add_node(PluginId(1));
+    let bus = builder.add_mixer_bus("Reverb");
+    builder.connect(input, send, 1.0).expect("input -> send");
+    builder.connect(send, bus, 0.5).expect("send -> bus");
+
+    assert_eq!(
+        builder.edges_from(send).collect::<Vec<_>>(),
+        [Edge {
+            from: send.pin(0),
+            to: bus.pin(0),
+            gain: 0.5,
+            signal: SignalKind::Audio,
+        }]
+    );
+    assert_eq!(builder.edges_into(send).count(), 1);
+
+    assert!(!builder.disconnect(input, bus));
+    assert!(!builder.disconnect(send.pin(0), bus.pin(1)));
+    assert!(builder.disconnect(send, bus));
+    assert!(!builder.disconnect(send, bus));
+    assert_eq!(builder.edges_from(send).count(), 0);
+    assert_eq!(builder.edges_into(send).count(), 1);
+
+    let graph = builder.build();
+    assert_eq!(graph.edges_into(send).count(), 1);
+    assert_eq!(graph.edges_into(bus).count(), 0);
+}
+
+#[test]
+fn audio_and_midi_pins_do_not_mix() {
+    let kind = NodeKind::Plugin { id: PluginId(1) };
+    assert_eq!(
+        pin_signal(&kind, PinDirection::Input, 0),
+        Some(SignalKind::Audio)
+    );
+    assert_eq!(
+        pin_signal(&kind, PinDirection::Input, 1),
+        Some(SignalKind::Midi)
+    );
+    assert_eq!(
+        pin_signal(&kind, PinDirection::Output, 0),
+        Some(SignalKind::Audio)
+    );
+    assert_eq!(
+        pin_signal(&kind, PinDirection::Output, 1),
+        Some(SignalKind::Midi)
+    );
+
+    let mut builder = GraphBuilder::new();
+    let audio = builder.add_input();
+    let keys = builder.add_midi_input();
+    let synth = builder.add_node(PluginId(1));
+    let recorder = builder.add_midi_output();
+
+    builder.connect(audio, synth.pin(0), 1.0).expect("audio in");
+    builder.connect(keys, synth.pin(1), 1.0).expect("midi in");
+
+    assert!(matches!(
+        builder.connect(audio, synth.pin(1), 1.0),
+        Err(GraphError::SignalMismatch {
+            from: SignalKind::Audio,
+            to: SignalKind::Midi,
+        })
+    ));
+    assert!(matches!(
+        builder.connect(keys, synth.pin(0), 1.0),
+        Err(GraphError::SignalMismatch {
+            from: SignalKind::Midi,
+            to: SignalKind::Audio,
+        })
+    ));
+    assert!(matches!(
+        builder.connect(synth, recorder, 1.0),
+        Err(GraphError::SignalMismatch { .. })
+    ));
+    builder.connect(keys, recorder, 1.0).expect("midi thru");
+
+    let signals: Vec<SignalKind> = builder.edges_into(synth).map(|edge| edge.signal).collect();
+    assert_eq!(signals.len(), 2);
+    assert!(signals.contains(&SignalKind::Audio));
+    assert!(signals.contains(&SignalKind::Midi));
+}
+
+#[test]
+fn pdc_follows_audio_edges_and_caps_latency() {
+    let mut builder = GraphBuilder::new();
+    builder.set_max_latency_cap(1_000);
+    let input = builder.add_input();
+    let keys = builder.add_midi_input();
+    let first = builder.add_node(PluginId(1));
+    let second = builder.add_node(PluginId(2));
+    let liar = builder.add_node(PluginId(3));
+    let bus = builder.add_mixer_bus("Bus");
+    builder.connect(input, first, 1.0).unwrap();
+    builder.connect(first, second, 1.0).unwrap();
+    builder.connect(keys, second.pin(1), 1.0).unwrap();
+    builder.connect(input, liar, 1.0).unwrap();
+    builder.connect(second, bus, 1.0).unwrap();
+    builder.connect(liar, bus, 1.0).unwrap();
+
+    let mut graph = builder.build();
+    assert_eq!(graph.max_latency_cap(), 1_000);
+    graph
+        .recompute_pdc_checked(&[64, 32, usize::MAX])
+        .expect("acyclic graph");
+    assert!(graph.unresolved_nodes().is_empty());
+    assert_eq!(graph.input_latency(first), Some(0));
+    assert_eq!(graph.input_latency(second), Some(64));
+    assert_eq!(graph.input_latency(bus), Some(1_000));
+
+    let mut default = GraphBuilder::new().build();
+    assert_eq!(default.max_latency_cap(), DEFAULT_MAX_LATENCY_CAP);
+    default.recompute_pdc_checked(&[]).expect("empty graph");
+}
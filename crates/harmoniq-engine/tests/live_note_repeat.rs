@@ -0,0 +1,107 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder,
+    HarmoniqEngine, MidiEvent, PluginDescriptor, TransportState,
+};
+use harmoniq_midi::RepeatRate;
+
+const BLOCK: usize = 128;
+/// A sixteenth at 120 BPM and 48 kHz.
+const SIXTEENTH: u64 = 6_000;
+
+/// Records each event with its absolute sample position.
+struct MidiRecorder {
+    block_start: Arc<AtomicU64>,
+    events: Arc<Mutex<Vec<(u64, MidiEvent)>>>,
+}
+
+impl AudioProcessor for MidiRecorder {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.recorder", "MIDI Recorder", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.clear();
+        Ok(())
+    }
+
+    fn process_midi(&mut self, events: &[MidiEvent]) -> anyhow::Result<()> {
+        let start = self.block_start.load(Ordering::Relaxed);
+        self.events.lock().unwrap().extend(
+            events
+                .iter()
+                .map(|event| (start + event.sample_offset() as u64, event.clone())),
+        );
+        Ok(())
+    }
+}
+
+#[test]
+fn held_live_note_repeats_at_sixteenths_until_released() {
+    let config = BufferConfig::new(48_000.0, BLOCK, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let block_start = Arc::new(AtomicU64::new(0));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorder = engine
+        .register_processor(Box::new(MidiRecorder {
+            block_start: Arc::clone(&block_start),
+            events: Arc::clone(&events),
+        }))
+        .expect("recorder");
+    let mut builder = GraphBuilder::new();
+    let node = builder.add_node(recorder);
+    builder.connect_to_mixer(node, 1.0).expect("connect");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine
+        .execute_command(EngineCommand::SetTransport(TransportState::Playing))
+        .expect("transport");
+    let repeat = engine.note_repeat_mut();
+    repeat.set_rate(RepeatRate::Sixteenth);
+    repeat.set_armed(true);
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    let mut position = 0u64;
+    let mut run_until = |engine: &mut HarmoniqEngine, end: u64| -> u64 {
+        while position < end {
+            block_start.store(position, Ordering::Relaxed);
+            engine.process_block(&mut buffer).expect("process");
+            position += BLOCK as u64;
+        }
+        position
+    };
+
+    engine
+        .execute_command(EngineCommand::SubmitMidi(vec![MidiEvent::new(
+            0,
+            [0x90, 38, 100],
+        )]))
+        .expect("note on");
+    let released = run_until(&mut engine, 3 * SIXTEENTH + SIXTEENTH / 3);
+    engine
+        .execute_command(EngineCommand::SubmitMidi(vec![MidiEvent::new(
+            0,
+            [0x80, 38, 0],
+        )]))
+        .expect("note off");
+    run_until(&mut engine, 6 * SIXTEENTH);
+
+    let events = events.lock().unwrap();
+    let note_ons: Vec<u64> = events
+        .iter()
+        .filter(|(_, event)| matches!(event, MidiEvent::NoteOn { note: 38, .. }))
+        .map(|(at, _)| *at)
+        .collect();
+    assert_eq!(note_ons, vec![0, SIXTEENTH, 2 * SIXTEENTH, 3 * SIXTEENTH]);
+    let last_off = events
+        .iter()
+        .filter(|(_, event)| matches!(event, MidiEvent::NoteOff { note: 38, .. }))
+        .map(|(at, _)| *at)
+        .last();
+    assert_eq!(last_off, Some(released));
+}
@@ -0,0 +1,109 @@
+use harmoniq_engine::{
+    AudioMetrics, GovernorState, LoadGovernor, LoadGovernorConfig, VoiceLevel, VoiceShedding,
+};
+
+const PERIOD_NS: u64 = 1_000_000;
+
+struct MockInstrument {
+    voices: Vec<VoiceLevel>,
+    polyphony: u32,
+}
+
+impl MockInstrument {
+    fn new(polyphony: u32, base_level: f32) -> Self {
+        let mut instrument = Self {
+            voices: Vec::new(),
+            polyphony,
+        };
+        instrument.play_all(base_level);
+        instrument
+    }
+
+    /// Simulates held notes retriggering every voice the instrument can play.
+    fn play_all(&mut self, base_level: f32) {
+        self.voices = (0..self.polyphony)
+            .map(|voice| VoiceLevel {
+                voice,
+                level: base_level + voice as f32 * 0.01,
+            })
+            .collect();
+    }
+}
+
+impl VoiceShedding for MockInstrument {
+    fn active_voices(&self, out: &mut Vec<VoiceLevel>) {
+        out.extend_from_slice(&self.voices);
+    }
+
+    fn steal_voice(&mut self, voice: u32) {
+        self.voices.retain(|active| active.voice != voice);
+    }
+}
+
+fn metrics(load: f32, xruns: u64) -> AudioMetrics {
+    AudioMetrics {
+        xruns,
+        last_block_ns: (PERIOD_NS as f32 * load) as u64,
+        max_block_ns: PERIOD_NS * 2,
+    }
+}
+
+fn run(
+    governor: &mut LoadGovernor,
+    instruments: &mut [MockInstrument; 2],
+    metrics: AudioMetrics,
+) -> GovernorState {
+    let [pad, lead] = instruments;
+    let mut targets: [&mut dyn VoiceShedding; 2] = [pad, lead];
+    governor.update(metrics, &mut targets)
+}
+
+fn active(instruments: &[MockInstrument; 2]) -> usize {
+    instruments.iter().map(|i| i.voices.len()).sum()
+}
+
+#[test]
+fn governor_sheds_quiet_voices_under_load_and_recovers() {
+    let mut config = LoadGovernorConfig::new(PERIOD_NS);
+    config.recovery_blocks = 4;
+    let mut governor = LoadGovernor::new(config);
+    let mut instruments = [MockInstrument::new(8, 0.1), MockInstrument::new(8, 0.5)];
+
+    assert_eq!(
+        run(&mut governor, &mut instruments, metrics(0.4, 0)),
+        GovernorState::Normal
+    );
+    assert_eq!(active(&instruments), 16);
+
+    // Block time over budget with xruns climbing.
+    let mut xruns = 0;
+    for _ in 0..3 {
+        xruns += 1;
+        let state = run(&mut governor, &mut instruments, metrics(1.2, xruns));
+        assert!(matches!(state, GovernorState::Shedding { .. }));
+    }
+    let limit = governor.voice_limit().expect("voice cap while overloaded");
+    assert!(limit < 16);
+    assert_eq!(active(&instruments), limit);
+    // The quieter pad loses voices before the louder lead.
+    assert!(instruments[0].voices.len() < instruments[1].voices.len());
+
+    // New notes cannot exceed the cap while it is in force.
+    instruments[0].play_all(0.1);
+    run(&mut governor, &mut instruments, metrics(0.7, xruns));
+    assert_eq!(active(&instruments), limit);
+
+    // Load drops: the cap is relaxed until polyphony is unrestricted again.
+    let mut state = GovernorState::Shedding { limit };
+    for _ in 0..64 {
+        instruments[0].play_all(0.1);
+        instruments[1].play_all(0.5);
+        state = run(&mut governor, &mut instruments, metrics(0.3, xruns));
+        if state == GovernorState::Normal {
+            break;
+        }
+    }
+    assert_eq!(state, GovernorState::Normal);
+    assert_eq!(governor.voice_limit(), None);
+    assert_eq!(active(&instruments), 16);
+}
@@ -0,0 +1,55 @@
+use harmoniq_engine::render::{RenderDuration, RenderProject};
+use harmoniq_engine::{nodes::NodeOsc, BufferConfig, ChannelLayout, GraphBuilder, HarmoniqEngine};
+
+/// A 997 Hz tone that reaches the master at 0.5 (-6.02 dBFS) on each channel
+/// once the centre pan law has been applied.
+struct ToneProject;
+
+const TONE_AMPLITUDE: f32 = 0.5 * std::f32::consts::SQRT_2;
+
+impl RenderProject for ToneProject {
+    fn label(&self) -> &str {
+        "tone"
+    }
+
+    fn create_engine(&self) -> anyhow::Result<HarmoniqEngine> {
+        let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+        let mut engine = HarmoniqEngine::new(config)?;
+        let osc = engine
+            .register_processor(Box::new(NodeOsc::new(997.0).with_amplitude(TONE_AMPLITUDE)))?;
+        let mut builder = GraphBuilder::new();
+        let node = builder.add_node(osc);
+        builder.connect_to_mixer(node, 1.0)?;
+        engine.replace_graph(builder.build())?;
+        Ok(engine)
+    }
+}
+
+#[test]
+fn analyze_project_reports_tone_loudness_and_peak() {
+    let report = HarmoniqEngine::analyze_project(&ToneProject, RenderDuration::Seconds(5.0))
+        .expect("analysis");
+
+    // BS.1770 reads a 997 Hz sine at -6.02 dBFS on both channels as -6.02 LUFS.
+    let expected = 20.0 * 0.5f32.log10();
+    assert!(
+        (report.integrated_lufs - expected).abs() < 0.2,
+        "integrated {}",
+        report.integrated_lufs
+    );
+    assert!(
+        (report.true_peak_dbtp - expected).abs() < 0.1,
+        "true peak {}",
+        report.true_peak_dbtp
+    );
+    assert!(report.loudness_range_lu < 0.5);
+
+    assert_eq!(report.track_peaks.len(), 1);
+    let track = &report.track_peaks[0];
+    let track_expected = 20.0 * TONE_AMPLITUDE.log10();
+    assert!(
+        (track.peak_dbfs - track_expected).abs() < 0.1,
+        "track peak {}",
+        track.peak_dbfs
+    );
+}
@@ -0,0 +1,107 @@
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder,
+    HarmoniqEngine, PluginDescriptor, TransportState,
+};
+
+const TRACKS: usize = 64;
+const BLOCKS: usize = 8;
+
+/// Slowly drifting, strongly correlated signal; every track is a slightly
+/// detuned copy of the same positive waveform.
+fn track_sample(track: usize, frame: usize) -> f32 {
+    let phase = frame as f32 * 0.01 + track as f32 * 0.003;
+    0.013 * (phase.sin() + 1.1) * (1.0 + track as f32 * 1.0e-3)
+}
+
+struct CorrelatedSource {
+    track: usize,
+    frame: usize,
+}
+
+impl AudioProcessor for CorrelatedSource {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.correlated", "Correlated Source", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        self.frame = 0;
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        let frames = buffer.len();
+        for channel in buffer.channels_mut() {
+            for (offset, sample) in channel.iter_mut().enumerate() {
+                *sample = track_sample(self.track, self.frame + offset);
+            }
+        }
+        self.frame += frames;
+        Ok(())
+    }
+}
+
+fn render(config: BufferConfig) -> Vec<f32> {
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let mut builder = GraphBuilder::new();
+    for track in 0..TRACKS {
+        let id = engine
+            .register_processor(Box::new(CorrelatedSource { track, frame: 0 }))
+            .expect("source");
+        let node = builder.add_node(id);
+        builder.connect_to_mixer(node, 1.0).expect("connect");
+    }
+    engine.replace_graph(builder.build()).expect("graph");
+    engine
+        .execute_command(EngineCommand::SetTransport(TransportState::Playing))
+        .expect("transport");
+
+    let mut left = Vec::with_capacity(BLOCKS * config.block_size);
+    let mut buffer = AudioBuffer::from_config(&config);
+    for _ in 0..BLOCKS {
+        engine.process_block(&mut buffer).expect("process");
+        left.extend_from_slice(buffer.channel(0));
+    }
+    left
+}
+
+/// Exact sum of the centre-panned track contributions.
+fn expected(frame: usize) -> f64 {
+    let pan = std::f32::consts::FRAC_PI_4.cos();
+    (0..TRACKS)
+        .map(|track| (track_sample(track, frame) * pan) as f64)
+        .sum()
+}
+
+fn total_error(output: &[f32]) -> f64 {
+    output
+        .iter()
+        .enumerate()
+        .map(|(frame, sample)| (*sample as f64 - expected(frame)).abs())
+        .sum()
+}
+
+#[test]
+fn f64_master_sum_is_closer_to_expected_than_f32() {
+    let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+    let single = render(config.clone());
+    let double = render(config.with_high_precision_master(true));
+
+    assert!(single.iter().any(|sample| *sample > 0.5));
+    let single_error = total_error(&single);
+    let double_error = total_error(&double);
+    assert!(
+        double_error < single_error,
+        "f64 error {double_error} should be below f32 error {single_error}"
+    );
+}
+
+#[test]
+fn high_precision_flag_defaults_to_off() {
+    let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+    assert!(!config.high_precision_master);
+    assert!(
+        config
+            .with_high_precision_master(true)
+            .high_precision_master
+    );
+}
@@ -0,0 +1,174 @@
+use std::sync::{Arc, Mutex};
+
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder,
+    HarmoniqEngine, MidiEvent, PluginDescriptor, TransportState,
+};
+
+const BLOCK: usize = 256;
+/// Longer than a block, so every echo lands in a later block.
+const ECHO: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Note {
+    On(u8, u8),
+    Off(u8),
+}
+
+/// Passes notes through and repeats each one `ECHO` samples later at half
+/// velocity.
+struct NoteRepeater {
+    latency: usize,
+}
+
+impl AudioProcessor for NoteRepeater {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.note_repeater", "Note Repeater", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.clear();
+        Ok(())
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.latency
+    }
+
+    fn process_midi_with_output(
+        &mut self,
+        events: &[MidiEvent],
+        output: &mut Vec<MidiEvent>,
+    ) -> anyhow::Result<()> {
+        for event in events {
+            let offset = event.sample_offset() + ECHO;
+            let echo = match *event {
+                MidiEvent::NoteOn {
+                    channel,
+                    note,
+                    velocity,
+                    ..
+                } => MidiEvent::new(offset, [0x90 | channel, note, velocity / 2]),
+                MidiEvent::NoteOff { channel, note, .. } => {
+                    MidiEvent::new(offset, [0x80 | channel, note, 0])
+                }
+                _ => continue,
+            };
+            output.push(event.clone());
+            output.push(echo);
+        }
+        Ok(())
+    }
+}
+
+/// Records the notes it receives with their absolute sample time.
+struct Recorder {
+    blocks: u64,
+    notes: Arc<Mutex<Vec<(u64, Note)>>>,
+}
+
+impl AudioProcessor for Recorder {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.recorder", "Recorder", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.clear();
+        self.blocks += 1;
+        Ok(())
+    }
+
+    fn process_midi(&mut self, events: &[MidiEvent]) -> anyhow::Result<()> {
+        let block_start = self.blocks * BLOCK as u64;
+        let mut notes = self.notes.lock().unwrap();
+        for event in events {
+            assert!((event.sample_offset() as usize) < BLOCK);
+            let time = block_start + event.sample_offset() as u64;
+            match *event {
+                MidiEvent::NoteOn { note, velocity, .. } => {
+                    notes.push((time, Note::On(note, velocity)))
+                }
+                MidiEvent::NoteOff { note, .. } => notes.push((time, Note::Off(note))),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plays a note into a repeater whose MIDI output feeds a recorder. The
+/// recorder is added to the graph first, so it only sees the note through
+/// the repeater.
+fn run(latency: usize) -> Vec<(u64, Note)> {
+    let config = BufferConfig::new(48_000.0, BLOCK, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let notes = Arc::new(Mutex::new(Vec::new()));
+    let recorder = engine
+        .register_processor(Box::new(Recorder {
+            blocks: 0,
+            notes: Arc::clone(&notes),
+        }))
+        .expect("recorder");
+    let repeater = engine
+        .register_processor(Box::new(NoteRepeater { latency }))
+        .expect("repeater");
+
+    let mut builder = GraphBuilder::new();
+    let recorder = builder.add_node(recorder);
+    let repeater = builder.add_node(repeater);
+    builder
+        .connect(repeater.pin(1), recorder.pin(1), 1.0)
+        .expect("midi route");
+    builder.connect_to_mixer(recorder, 1.0).expect("recorder");
+    builder.connect_to_mixer(repeater, 1.0).expect("repeater");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine.set_transport(TransportState::Playing);
+
+    // Channel 1 addresses the second track, the repeater.
+    engine
+        .execute_command(EngineCommand::SubmitMidi(vec![
+            MidiEvent::new(10, [0x91, 60, 100]),
+            MidiEvent::new(200, [0x81, 60, 0]),
+        ]))
+        .expect("notes");
+    let mut buffer = AudioBuffer::from_config(&config);
+    for _ in 0..4 {
+        engine.process_block(&mut buffer).expect("block");
+    }
+    let notes = notes.lock().unwrap().clone();
+    notes
+}
+
+#[test]
+fn repeater_output_reaches_the_downstream_node_on_time() {
+    assert_eq!(
+        run(0),
+        vec![
+            (10, Note::On(60, 100)),
+            (200, Note::Off(60)),
+            (310, Note::On(60, 50)),
+            (500, Note::Off(60)),
+        ]
+    );
+}
+
+#[test]
+fn emitted_midi_is_delayed_by_the_producer_latency() {
+    assert_eq!(
+        run(64),
+        vec![
+            (74, Note::On(60, 100)),
+            (264, Note::Off(60)),
+            (374, Note::On(60, 50)),
+            (564, Note::Off(60)),
+        ]
+    );
+}
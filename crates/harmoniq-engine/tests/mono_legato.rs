@@ -0,0 +1,177 @@
+use std::sync::{Arc, Mutex};
+
+use harmoniq_engine::legato::{PORTAMENTO_CONTROL_CC, PORTAMENTO_TIME_CC};
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder,
+    HarmoniqEngine, MidiEvent, MonoLegato, MonoLegatoSettings, NotePriority, PluginDescriptor,
+    TransportState,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Note {
+    On(u8),
+    Off(u8),
+    Control(u8, u8),
+}
+
+fn summarise(events: &[MidiEvent]) -> Vec<Note> {
+    events
+        .iter()
+        .filter_map(|event| match *event {
+            MidiEvent::NoteOn { note, .. } => Some(Note::On(note)),
+            MidiEvent::NoteOff { note, .. } => Some(Note::Off(note)),
+            MidiEvent::ControlChange { control, value, .. } => Some(Note::Control(control, value)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn on(note: u8) -> MidiEvent {
+    MidiEvent::new(0, [0x90, note, 100])
+}
+
+fn off(note: u8) -> MidiEvent {
+    MidiEvent::new(0, [0x80, note, 0])
+}
+
+fn run(priority: NotePriority, events: &[MidiEvent]) -> Vec<Note> {
+    let mut legato = MonoLegato::new(MonoLegatoSettings {
+        priority,
+        glide_ms: 0.0,
+    });
+    let mut output = Vec::new();
+    legato.process(events, &mut output);
+    summarise(&output)
+}
+
+#[test]
+fn last_priority_moves_to_each_new_note_without_retriggering() {
+    let events = [on(60), on(64), on(67), off(67), off(64), off(60)];
+    assert_eq!(
+        run(NotePriority::Last, &events),
+        vec![
+            Note::On(60),
+            Note::On(64),
+            Note::Off(60),
+            Note::On(67),
+            Note::Off(64),
+            Note::On(64),
+            Note::Off(67),
+            Note::On(60),
+            Note::Off(64),
+            Note::Off(60),
+        ]
+    );
+}
+
+#[test]
+fn low_priority_ignores_higher_notes() {
+    let events = [on(64), on(67), on(60), off(60), off(67), off(64)];
+    assert_eq!(
+        run(NotePriority::Low, &events),
+        vec![
+            Note::On(64),
+            Note::On(60),
+            Note::Off(64),
+            Note::On(64),
+            Note::Off(60),
+            Note::Off(64),
+        ]
+    );
+}
+
+#[test]
+fn high_priority_ignores_lower_notes() {
+    let events = [on(60), on(55), on(67), off(55), off(67), off(60)];
+    assert_eq!(
+        run(NotePriority::High, &events),
+        vec![
+            Note::On(60),
+            Note::On(67),
+            Note::Off(60),
+            Note::On(60),
+            Note::Off(67),
+            Note::Off(60),
+        ]
+    );
+}
+
+#[test]
+fn glide_announces_the_source_note() {
+    let mut legato = MonoLegato::new(MonoLegatoSettings {
+        priority: NotePriority::Last,
+        glide_ms: 120.0,
+    });
+    let mut output = Vec::new();
+    legato.process(&[on(60), on(62)], &mut output);
+    assert_eq!(
+        summarise(&output),
+        vec![
+            Note::On(60),
+            Note::Control(PORTAMENTO_TIME_CC, 12),
+            Note::Control(PORTAMENTO_CONTROL_CC, 60),
+            Note::On(62),
+            Note::Off(60),
+        ]
+    );
+    assert_eq!(legato.sounding_note(), Some(62));
+}
+
+struct MidiRecorder {
+    events: Arc<Mutex<Vec<MidiEvent>>>,
+}
+
+impl AudioProcessor for MidiRecorder {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.recorder", "MIDI Recorder", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.clear();
+        Ok(())
+    }
+
+    fn process_midi(&mut self, events: &[MidiEvent]) -> anyhow::Result<()> {
+        self.events.lock().unwrap().extend_from_slice(events);
+        Ok(())
+    }
+}
+
+#[test]
+fn engine_applies_mono_legato_to_the_track() {
+    let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorder = engine
+        .register_processor(Box::new(MidiRecorder {
+            events: Arc::clone(&events),
+        }))
+        .expect("recorder");
+    let mut builder = GraphBuilder::new();
+    let node = builder.add_node(recorder);
+    builder.connect_to_mixer(node, 1.0).expect("connect");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine
+        .execute_command(EngineCommand::SetTransport(TransportState::Playing))
+        .expect("transport");
+    engine.set_track_mono_legato(0, Some(MonoLegatoSettings::default()));
+    assert_eq!(
+        engine.track_mono_legato(0),
+        Some(MonoLegatoSettings::default())
+    );
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    engine
+        .execute_command(EngineCommand::SubmitMidi(vec![on(60), on(64), off(60)]))
+        .expect("notes");
+    engine.process_block(&mut buffer).expect("process");
+
+    assert_eq!(
+        summarise(&events.lock().unwrap()),
+        vec![Note::On(60), Note::On(64), Note::Off(60)]
+    );
+}
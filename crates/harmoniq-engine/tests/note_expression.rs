@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder,
+    HarmoniqEngine, MidiEvent, NoteController, PluginDescriptor, TransportState, VoiceExpression,
+};
+
+const BASE_CUTOFF: f32 = 1_000.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct VoiceState {
+    note: Option<u8>,
+    expression: VoiceExpression,
+}
+
+impl VoiceState {
+    /// Timbre opens the filter by up to four octaves.
+    fn cutoff(&self) -> f32 {
+        BASE_CUTOFF * 2.0f32.powf(self.expression.timbre * 4.0)
+    }
+}
+
+/// Two-voice instrument that maps per-note timbre onto each voice's cutoff.
+struct ExpressiveSynth {
+    voices: Arc<Mutex<[VoiceState; 2]>>,
+}
+
+impl AudioProcessor for ExpressiveSynth {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.expressive", "Expressive Synth", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.clear();
+        Ok(())
+    }
+
+    fn process_midi(&mut self, events: &[MidiEvent]) -> anyhow::Result<()> {
+        let mut voices = self.voices.lock().unwrap();
+        for event in events {
+            match *event {
+                MidiEvent::NoteOn { note, .. } => {
+                    if let Some(voice) = voices.iter_mut().find(|voice| voice.note.is_none()) {
+                        voice.note = Some(note);
+                        voice.expression.reset();
+                    }
+                }
+                MidiEvent::NoteExpression {
+                    note,
+                    controller,
+                    value,
+                    ..
+                } => {
+                    for voice in voices.iter_mut().filter(|voice| voice.note == Some(note)) {
+                        voice.expression.apply(controller, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn timbre_expression_reaches_only_the_targeted_voice() {
+    let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let voices = Arc::new(Mutex::new([VoiceState::default(); 2]));
+    let synth = engine
+        .register_processor(Box::new(ExpressiveSynth {
+            voices: Arc::clone(&voices),
+        }))
+        .expect("synth");
+    let mut builder = GraphBuilder::new();
+    let node = builder.add_node(synth);
+    builder.connect_to_mixer(node, 1.0).expect("connect");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine
+        .execute_command(EngineCommand::SetTransport(TransportState::Playing))
+        .expect("transport");
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    engine
+        .execute_command(EngineCommand::SubmitMidi(vec![
+            MidiEvent::new(0, [0x90, 60, 100]),
+            MidiEvent::new(0, [0x90, 67, 100]),
+        ]))
+        .expect("notes");
+    engine.process_block(&mut buffer).expect("process");
+    let before = *voices.lock().unwrap();
+    assert_eq!(before[0].note, Some(60));
+    assert_eq!(before[1].note, Some(67));
+
+    engine
+        .execute_command(EngineCommand::SubmitMidi(vec![MidiEvent::note_expression(
+            16,
+            0,
+            67,
+            NoteController::Timbre,
+            0.5,
+        )]))
+        .expect("expression");
+    engine.process_block(&mut buffer).expect("process");
+    let after = *voices.lock().unwrap();
+
+    assert_eq!(after[0].cutoff(), before[0].cutoff());
+    assert_eq!(after[0].expression, VoiceExpression::default());
+    assert!((after[1].cutoff() - BASE_CUTOFF * 4.0).abs() < 1e-2);
+    assert_eq!(after[1].expression.get(NoteController::Timbre), Some(0.5));
+}
+
+#[test]
+fn controllers_round_trip_by_name() {
+    for controller in [
+        NoteController::Pitch,
+        NoteController::Timbre,
+        NoteController::Pressure,
+        NoteController::Pan,
+    ] {
+        assert_eq!(
+            NoteController::from_name(controller.name()),
+            Some(controller)
+        );
+    }
+    assert_eq!(NoteController::from_name("custom"), None);
+}
@@ -0,0 +1,96 @@
+use harmoniq_engine::{AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, NodeSaw};
+
+const SAMPLE_RATE: f32 = 48_000.0;
+const MASTER_HZ: f32 = 1_234.5;
+const SYNC_RATIO: f32 = 2.7;
+const FRAMES: usize = 4096;
+const WARMUP: usize = 256;
+
+fn naive_hard_sync() -> Vec<f32> {
+    let inc = MASTER_HZ * SYNC_RATIO / SAMPLE_RATE;
+    let master_inc = MASTER_HZ / SAMPLE_RATE;
+    let (mut phase, mut master) = (0.0f32, 0.0f32);
+    (0..FRAMES + WARMUP)
+        .map(|_| {
+            master += master_inc;
+            phase += inc;
+            if master >= 1.0 {
+                master -= 1.0;
+                phase = 0.0;
+            }
+            if phase >= 1.0 {
+                phase -= 1.0;
+            }
+            2.0 * phase - 1.0
+        })
+        .skip(WARMUP)
+        .collect()
+}
+
+fn blep_hard_sync() -> Vec<f32> {
+    let config = BufferConfig::new(SAMPLE_RATE, FRAMES + WARMUP, ChannelLayout::Mono);
+    let mut osc = NodeSaw::new(MASTER_HZ * SYNC_RATIO).with_sync_ratio(SYNC_RATIO);
+    osc.prepare(&config).unwrap();
+    let mut buffer = AudioBuffer::from_config(&config);
+    osc.process(&mut buffer).unwrap();
+    buffer.channel(0)[WARMUP..].to_vec()
+}
+
+/// Energy outside the harmonics of the master frequency, i.e. aliasing.
+fn inharmonic_energy(signal: &[f32]) -> f64 {
+    let n = signal.len();
+    let bin_hz = SAMPLE_RATE as f64 / n as f64;
+    let twiddles: Vec<(f64, f64)> = (0..n)
+        .map(|k| (-2.0 * std::f64::consts::PI * k as f64 / n as f64).sin_cos())
+        .collect();
+    let windowed: Vec<f64> = signal
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            let w = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / n as f64).cos();
+            *sample as f64 * w
+        })
+        .collect();
+
+    (1..n / 2)
+        .filter(|k| {
+            let freq = *k as f64 * bin_hz;
+            let harmonic = (freq / MASTER_HZ as f64).round() * MASTER_HZ as f64;
+            (freq - harmonic).abs() > 4.0 * bin_hz
+        })
+        .map(|k| {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (t, x) in windowed.iter().enumerate() {
+                let (sin, cos) = twiddles[(k * t) % n];
+                re += x * cos;
+                im += x * sin;
+            }
+            re * re + im * im
+        })
+        .sum()
+}
+
+#[test]
+fn minblep_hard_sync_aliases_less_than_naive_reset() {
+    let blep = blep_hard_sync();
+    let naive = naive_hard_sync();
+    assert!(blep.iter().all(|sample| sample.is_finite()));
+
+    let blep_energy = inharmonic_energy(&blep);
+    let naive_energy = inharmonic_energy(&naive);
+    assert!(
+        blep_energy * 10.0 < naive_energy,
+        "anti-aliased sync {blep_energy} should alias well below naive {naive_energy}"
+    );
+}
+
+#[test]
+fn unity_sync_ratio_is_plain_saw() {
+    let config = BufferConfig::new(SAMPLE_RATE, 512, ChannelLayout::Mono);
+    let mut osc = NodeSaw::new(440.0).with_sync_ratio(0.5);
+    assert_eq!(osc.sync_ratio(), 1.0);
+    osc.prepare(&config).unwrap();
+    let mut buffer = AudioBuffer::from_config(&config);
+    osc.process(&mut buffer).unwrap();
+    assert!(buffer.channel(0).iter().all(|sample| sample.abs() < 1.2));
+}
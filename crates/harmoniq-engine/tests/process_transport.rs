@@ -0,0 +1,210 @@
+use std::sync::{Arc, Mutex};
+
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder,
+    HarmoniqEngine, LoopRegion, PluginDescriptor, ProcessTransport, Tempo, TempoMap, TempoSegment,
+    TimeSignature, TransportState,
+};
+
+const SAMPLE_RATE: f32 = 48_000.0;
+const BLOCK: usize = 256;
+
+/// Records the transport it is handed each block.
+struct TransportProbe {
+    seen: Arc<Mutex<Vec<ProcessTransport>>>,
+    tempos: Arc<Mutex<Vec<Tempo>>>,
+}
+
+impl AudioProcessor for TransportProbe {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.transport_probe", "Transport Probe", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, _buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        panic!("the engine should call process_with_context");
+    }
+
+    fn set_tempo(&mut self, tempo: Tempo) {
+        self.tempos.lock().unwrap().push(tempo);
+    }
+
+    fn process_with_context(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        ctx: &ProcessTransport,
+    ) -> anyhow::Result<()> {
+        self.seen.lock().unwrap().push(*ctx);
+        buffer.clear();
+        Ok(())
+    }
+}
+
+/// Silent processor that only implements `process`.
+struct Plain;
+
+impl AudioProcessor for Plain {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.plain", "Plain", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.clear();
+        Ok(())
+    }
+}
+
+fn engine(seen: &Arc<Mutex<Vec<ProcessTransport>>>) -> HarmoniqEngine {
+    engine_with_tempos(seen, &Arc::new(Mutex::new(Vec::new())))
+}
+
+fn engine_with_tempos(
+    seen: &Arc<Mutex<Vec<ProcessTransport>>>,
+    tempos: &Arc<Mutex<Vec<Tempo>>>,
+) -> HarmoniqEngine {
+    let config = BufferConfig::new(SAMPLE_RATE, BLOCK, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config).expect("engine");
+    let probe = engine
+        .register_processor(Box::new(TransportProbe {
+            seen: Arc::clone(seen),
+            tempos: Arc::clone(tempos),
+        }))
+        .expect("probe");
+    let plain = engine.register_processor(Box::new(Plain)).expect("plain");
+    let mut builder = GraphBuilder::new();
+    let probe = builder.add_node(probe);
+    let plain = builder.add_node(plain);
+    builder.connect_to_mixer(probe, 1.0).expect("probe route");
+    builder.connect_to_mixer(plain, 1.0).expect("plain route");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine
+}
+
+fn run(engine: &mut HarmoniqEngine, blocks: usize) {
+    let mut buffer = AudioBuffer::from_config(engine.config());
+    for _ in 0..blocks {
+        engine.process_block(&mut buffer).expect("block");
+    }
+}
+
+#[test]
+fn processors_see_tempo_meter_and_playhead_each_block() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = engine(&seen);
+    let three_four = TimeSignature {
+        numerator: 3,
+        denominator: 4,
+    };
+    let six_eight = TimeSignature {
+        numerator: 6,
+        denominator: 8,
+    };
+    engine
+        .execute_command(EngineCommand::SetTempoMap(TempoMap::new(vec![
+            TempoSegment {
+                start_sample: 0,
+                tempo: Tempo(140.0),
+                time_signature: three_four,
+            },
+            TempoSegment {
+                start_sample: 2 * BLOCK as u64,
+                tempo: Tempo(70.0),
+                time_signature: six_eight,
+            },
+        ])))
+        .expect("tempo map");
+    engine.set_transport(TransportState::Playing);
+    run(&mut engine, 3);
+
+    let seen = seen.lock().unwrap().clone();
+    assert_eq!(seen.len(), 3);
+    for (index, ctx) in seen.iter().enumerate() {
+        assert_eq!(ctx.sample_position, (index * BLOCK) as u64);
+        assert!(ctx.is_playing);
+        assert!(!ctx.is_recording);
+        assert_eq!(ctx.loop_region, None);
+    }
+    assert_eq!(seen[0].tempo, Tempo(140.0));
+    assert_eq!(seen[0].time_signature, three_four);
+    assert_eq!(seen[0].beat_position, 0.0);
+    let beats_per_block = BLOCK as f64 / Tempo(140.0).samples_per_beat(SAMPLE_RATE);
+    assert!((seen[1].beat_position - beats_per_block).abs() < 1e-9);
+    assert_eq!(seen[2].tempo, Tempo(70.0));
+    assert_eq!(seen[2].time_signature, six_eight);
+    assert!((seen[2].beat_position - 2.0 * beats_per_block).abs() < 1e-9);
+}
+
+#[test]
+fn stopped_transport_holds_its_position() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = engine(&seen);
+    engine
+        .execute_command(EngineCommand::SetTempo(96.0))
+        .expect("tempo");
+    run(&mut engine, 2);
+
+    let seen = seen.lock().unwrap().clone();
+    assert_eq!(seen.len(), 2);
+    for ctx in &seen {
+        assert!(!ctx.is_playing);
+        assert_eq!(ctx.sample_position, 0);
+        assert_eq!(ctx.tempo, Tempo(96.0));
+        assert_eq!(ctx.time_signature, TimeSignature::four_four());
+    }
+}
+
+#[test]
+fn tempo_is_resent_when_playback_enters_a_new_segment() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let tempos = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = engine_with_tempos(&seen, &tempos);
+    engine
+        .execute_command(EngineCommand::SetTempoMap(TempoMap::new(vec![
+            TempoSegment {
+                start_sample: 0,
+                tempo: Tempo(140.0),
+                time_signature: TimeSignature::four_four(),
+            },
+            TempoSegment {
+                start_sample: 2 * BLOCK as u64,
+                tempo: Tempo(70.0),
+                time_signature: TimeSignature::four_four(),
+            },
+        ])))
+        .expect("tempo map");
+    tempos.lock().unwrap().clear();
+    engine.set_transport(TransportState::Playing);
+    run(&mut engine, 4);
+
+    assert_eq!(*tempos.lock().unwrap(), vec![Tempo(70.0)]);
+}
+
+#[test]
+fn loop_region_is_reported_and_wraps_the_playhead() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = engine(&seen);
+    let region = LoopRegion::new(BLOCK as u64, 3 * BLOCK as u64);
+    engine
+        .execute_command(EngineCommand::SetLoop(Some(region)))
+        .expect("loop");
+    engine.set_transport(TransportState::Playing);
+    run(&mut engine, 5);
+
+    let seen = seen.lock().unwrap().clone();
+    let positions: Vec<u64> = seen.iter().map(|ctx| ctx.sample_position).collect();
+    let block = BLOCK as u64;
+    assert_eq!(positions, vec![0, block, 2 * block, block, 2 * block]);
+    assert!(seen.iter().all(|ctx| ctx.loop_region == Some(region)));
+
+    engine
+        .execute_command(EngineCommand::SetLoop(None))
+        .expect("clear loop");
+    assert_eq!(engine.loop_region(), None);
+}
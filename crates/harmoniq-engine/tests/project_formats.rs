@@ -0,0 +1,166 @@
+use std::fs;
+
+use harmoniq_engine::project::legacy::ProjectStateV4;
+use harmoniq_engine::project::{
+    load_project, save_project, save_project_with, LoadOptions, MediaAsset, ProjectDocument,
+    ProjectFormat, ProjectMetadata, ProjectV3, ProjectV4, SaveOptions, BINCODE_MAGIC,
+    CURRENT_VERSION, PROJECT_MAGIC, ZSTD_BINCODE_MAGIC,
+};
+use harmoniq_engine::{AutomationPoint, CommandBus, MixerInsertState, ProjectState};
+use serde::Serialize;
+use tempfile::TempDir;
+
+fn sample_document(dir: &TempDir) -> ProjectDocument {
+    let data = (0..=255u8).cycle().take(4096).collect::<Vec<_>>();
+    fs::write(dir.path().join("kick.wav"), &data).unwrap();
+    let asset = MediaAsset::new("kick", "kick.wav", data);
+
+    let mut state = CommandBus::default().state().clone();
+    let lane = &mut state.automation.lanes[0];
+    lane.points = (0..2_000)
+        .map(|index| AutomationPoint {
+            beat: index as f32 * 0.25,
+            value: (index % 17) as f32 / 16.0,
+        })
+        .collect();
+
+    ProjectDocument::new(
+        ProjectMetadata::new("Formats", 48_000.0, 256, 2, 30.0),
+        vec![asset],
+    )
+    .with_state(state)
+}
+
+fn payload_prefix(bytes: &[u8]) -> &[u8] {
+    assert_eq!(&bytes[..4], &PROJECT_MAGIC);
+    &bytes[20..24]
+}
+
+#[test]
+fn every_format_round_trips() {
+    let dir = TempDir::new().unwrap();
+    let document = sample_document(&dir);
+
+    let mut sizes = Vec::new();
+    for format in [
+        ProjectFormat::JsonPretty,
+        ProjectFormat::Json,
+        ProjectFormat::Bincode,
+        ProjectFormat::ZstdBincode,
+    ] {
+        let path = dir.path().join(format!("{format:?}.hsq"));
+        let report = save_project_with(&path, &document, format).unwrap();
+        sizes.push(report.bytes_written - report.media_bytes);
+
+        let bytes = fs::read(&path).unwrap();
+        match format {
+            ProjectFormat::Bincode => assert_eq!(payload_prefix(&bytes), BINCODE_MAGIC),
+            ProjectFormat::ZstdBincode => assert_eq!(payload_prefix(&bytes), ZSTD_BINCODE_MAGIC),
+            _ => assert_eq!(payload_prefix(&bytes)[0], b'{'),
+        }
+
+        let loaded = load_project(&path, LoadOptions::default())
+            .unwrap()
+            .document;
+        assert_eq!(loaded.version, document.version, "{format:?}");
+        assert_eq!(loaded.metadata, document.metadata, "{format:?}");
+        assert_eq!(loaded.state, document.state, "{format:?}");
+        assert_eq!(loaded.media[0].data, document.media[0].data, "{format:?}");
+    }
+
+    let [pretty, json, bincode, zstd] = sizes[..] else {
+        unreachable!()
+    };
+    assert!(json < pretty);
+    assert!(bincode < json);
+    assert!(zstd < bincode);
+}
+
+#[test]
+fn default_save_stays_pretty_json() {
+    let dir = TempDir::new().unwrap();
+    let document = sample_document(&dir);
+    let path = dir.path().join("session.hsq");
+    save_project(&path, &document, SaveOptions::default()).unwrap();
+
+    let bytes = fs::read(&path).unwrap();
+    assert_eq!(&payload_prefix(&bytes)[..2], b"{\n");
+}
+
+/// `state` in the layout versions 3 and 4 saved; the fields added since
+/// are dropped on the way through JSON.
+fn state_v4(state: &ProjectState) -> ProjectStateV4 {
+    serde_json::from_value(serde_json::to_value(state).unwrap()).unwrap()
+}
+
+fn write_bincode_archive(
+    dir: &TempDir,
+    version: u32,
+    project: &impl Serialize,
+) -> std::path::PathBuf {
+    let mut payload = BINCODE_MAGIC.to_vec();
+    payload.extend(bincode::serialize(project).unwrap());
+    let mut archive = PROJECT_MAGIC.to_vec();
+    archive.extend_from_slice(&version.to_le_bytes());
+    archive.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&0u64.to_le_bytes());
+    archive.extend_from_slice(&payload);
+    let path = dir.path().join(format!("v{version}.hsq"));
+    fs::write(&path, archive).unwrap();
+    path
+}
+
+#[test]
+fn version_3_bincode_projects_still_load() {
+    let dir = TempDir::new().unwrap();
+    let document = sample_document(&dir);
+    // A version 3 bincode payload, written before sample references.
+    let project = ProjectV3 {
+        version: 3,
+        metadata: document.metadata.clone(),
+        media: Vec::new(),
+        state: state_v4(&document.state),
+    };
+    let path = write_bincode_archive(&dir, 3, &project);
+
+    let loaded = load_project(&path, LoadOptions::default())
+        .unwrap()
+        .document;
+    assert_eq!(loaded.version, CURRENT_VERSION);
+    assert_eq!(loaded.metadata, document.metadata);
+    assert_eq!(loaded.state, document.state);
+    assert!(loaded.samples.is_empty());
+}
+
+#[test]
+fn version_4_bincode_inserts_load_without_plugin_state() {
+    let dir = TempDir::new().unwrap();
+    let mut document = sample_document(&dir);
+    document.state.mixer.tracks[0]
+        .post_inserts
+        .push(MixerInsertState {
+            id: Some("clap:gain".into()),
+            bypassed: true,
+            ..MixerInsertState::default()
+        });
+    // A version 4 payload, written before inserts kept parameter ids and
+    // plug-in state.
+    let project = ProjectV4 {
+        version: 4,
+        metadata: document.metadata.clone(),
+        media: Vec::new(),
+        state: state_v4(&document.state),
+        samples: Vec::new(),
+        tempo_map: document.tempo_map.clone(),
+    };
+    let path = write_bincode_archive(&dir, 4, &project);
+
+    let loaded = load_project(&path, LoadOptions::default())
+        .unwrap()
+        .document;
+    assert_eq!(loaded.version, CURRENT_VERSION);
+    assert_eq!(loaded.state, document.state);
+    let insert = &loaded.state.mixer.tracks[0].post_inserts[0];
+    assert_eq!(insert.id.as_deref(), Some("clap:gain"));
+    assert!(insert.parameter_ids.is_empty() && insert.state.is_empty());
+}
@@ -4,10 +4,12 @@ use std::path::PathBuf;
 use std::rc::Rc;
 
 use harmoniq_engine::project::{
-    load_project, save_autosave, save_project, LoadOptions, MediaAsset, MediaChecksum,
-    ProjectDocument, ProjectMediaEntryV1, ProjectMetadata, ProjectV1, SaveOptions,
+    latest_recovery_snapshot, load_project, migrate, recovery_snapshots, save_autosave,
+    save_project, save_recovery_snapshot, AutosaveConfig, LoadOptions, MediaAsset, MediaChecksum,
+    ProjectDocument, ProjectMediaEntryV1, ProjectMetadata, ProjectV1, SaveOptions, PROJECT_MAGIC,
+    RECOVERY_PREFIX,
 };
-use harmoniq_engine::ProjectLoadError;
+use harmoniq_engine::{ProjectLoadError, ProjectMigrationError, ProjectState};
 use tempfile::TempDir;
 
 fn sample_metadata() -> ProjectMetadata {
@@ -67,19 +69,16 @@ fn missing_media_can_be_relinked_with_embedded_data() {
     let recovered_flag = Rc::new(Cell::new(false));
     let flag = recovered_flag.clone();
     let base = dir.path().to_path_buf();
-    let options = LoadOptions {
-        prefer_autosave: true,
-        relinker: Some(Box::new(move |request| {
-            flag.set(true);
-            assert_eq!(request.data, data.as_slice());
-            let new_path = base.join("audio/relinked.wav");
-            if let Some(parent) = new_path.parent() {
-                fs::create_dir_all(parent).unwrap();
-            }
-            fs::write(&new_path, request.data).unwrap();
-            Some(new_path)
-        })),
-    };
+    let options = LoadOptions::default().with_relinker(move |request| {
+        flag.set(true);
+        assert_eq!(request.data, data.as_slice());
+        let new_path = base.join("audio/relinked.wav");
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&new_path, request.data).unwrap();
+        Some(new_path)
+    });
     let load = load_project(&project_path, options).unwrap();
 
     assert!(recovered_flag.get());
@@ -108,10 +107,7 @@ fn autosave_is_preferred_when_newer() {
 
     let no_autosave = load_project(
         &project_path,
-        LoadOptions {
-            prefer_autosave: false,
-            relinker: None,
-        },
+        LoadOptions::default().with_prefer_autosave(false),
     )
     .unwrap();
     assert!(!no_autosave.recovered_from_autosave);
@@ -158,3 +154,111 @@ fn migration_from_v1_creates_v2_document() {
     save_project(&migrated_path, &report.document, SaveOptions::default()).unwrap();
     assert!(migrated_path.exists());
 }
+
+#[test]
+fn v2_archive_is_migrated_to_current_schema() {
+    let dir = TempDir::new().unwrap();
+    let data = vec![4u8; 32];
+    fs::write(dir.path().join("loop.wav"), &data).unwrap();
+    let checksum = MediaChecksum::from_data(&data);
+
+    // A version 2 payload as written before project state was stored.
+    let payload = serde_json::json!({
+        "version": 2,
+        "metadata": {
+            "name": "Old session",
+            "sample_rate": 44100.0,
+            "block_size": 512,
+            "channels": 2,
+            "duration_seconds": 12.0
+        },
+        "media": [{
+            "id": "loop",
+            "relative_path": "loop.wav",
+            "checksum": { "algorithm": checksum.algorithm, "value": checksum.value },
+            "chunks": [{ "offset": 0, "length": 32 }]
+        }]
+    });
+    let payload = serde_json::to_vec(&payload).unwrap();
+    let mut archive = Vec::new();
+    archive.extend_from_slice(&PROJECT_MAGIC);
+    archive.extend_from_slice(&2u32.to_le_bytes());
+    archive.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    archive.extend_from_slice(&payload);
+    archive.extend_from_slice(&data);
+    let path = dir.path().join("old.hsq");
+    fs::write(&path, archive).unwrap();
+
+    let document = load_project(&path, LoadOptions::default())
+        .unwrap()
+        .document;
+    assert_eq!(document.version, harmoniq_engine::PROJECT_VERSION);
+    assert_eq!(document.metadata.name, "Old session");
+    assert_eq!(document.media[0].data, data);
+    assert_eq!(document.state, ProjectState::default());
+}
+
+#[test]
+fn migrations_run_in_sequence() {
+    let payload = serde_json::json!({ "version": 2, "metadata": {}, "media": [] });
+    let upgraded = migrate::upgrade(payload.clone(), 2).unwrap();
+    assert_eq!(upgraded["version"], harmoniq_engine::PROJECT_VERSION);
+    assert!(upgraded["state"]["mixer"].is_object());
+    assert_eq!(upgraded["samples"], serde_json::json!([]));
+    assert!(upgraded["tempo_map"]["segments"].is_array());
+
+    let current = migrate::upgrade(upgraded.clone(), harmoniq_engine::PROJECT_VERSION).unwrap();
+    assert_eq!(current, upgraded);
+
+    assert!(matches!(
+        migrate::upgrade(payload, 1),
+        Err(ProjectMigrationError::MissingStep(1))
+    ));
+}
+
+#[test]
+fn recovery_snapshots_rotate_and_latest_wins() {
+    let dir = TempDir::new().unwrap();
+    let snapshots = dir.path().join("recovery");
+    // Media sits next to the snapshots so it resolves on load.
+    let data = vec![7u8; 16];
+    fs::create_dir_all(&snapshots).unwrap();
+    fs::write(snapshots.join("hit.wav"), &data).unwrap();
+    let config = AutosaveConfig {
+        keep: 3,
+        ..AutosaveConfig::default()
+    };
+    assert!(config.is_due(config.interval));
+    assert!(latest_recovery_snapshot(&snapshots, LoadOptions::default())
+        .unwrap()
+        .is_none());
+
+    for take in 0..5 {
+        let mut metadata = sample_metadata();
+        metadata.name = format!("Take {take}");
+        let document = ProjectDocument::new(
+            metadata,
+            vec![MediaAsset::new("hit", "hit.wav", data.clone())],
+        );
+        let report = save_recovery_snapshot(&snapshots, &document, &config).unwrap();
+        assert!(report.autosave);
+    }
+
+    let kept = recovery_snapshots(&snapshots).unwrap();
+    assert_eq!(kept.len(), 3);
+    let latest = latest_recovery_snapshot(&snapshots, LoadOptions::default())
+        .unwrap()
+        .expect("snapshot");
+    assert!(latest.recovered_from_autosave);
+    assert_eq!(latest.document.metadata.name, "Take 4");
+    assert_eq!(&latest.source_path, kept.last().unwrap());
+
+    // A torn newest file is skipped in favour of the previous snapshot.
+    let torn = snapshots.join(format!("{RECOVERY_PREFIX}{:020}.hsq", u128::MAX >> 64));
+    fs::write(&torn, b"HSQ2\x03").unwrap();
+    let latest = latest_recovery_snapshot(&snapshots, LoadOptions::default())
+        .unwrap()
+        .expect("snapshot");
+    assert_eq!(latest.document.metadata.name, "Take 4");
+}
@@ -0,0 +1,160 @@
+use std::io::Cursor;
+
+use harmoniq_engine::automation::CurvePoint;
+use harmoniq_engine::media::loader::{DecodedAudio, MediaLoader};
+use harmoniq_engine::project::{load_project, save_project, LoadOptions, SaveOptions};
+use harmoniq_engine::{
+    AudioClip, AutomationCurve, AutomationPoint, ClipEvent, CommandBus, CurveShape, FadeCurve,
+    FadeSpec, MediaAsset, ProjectDocument, ProjectMetadata, Tempo, TempoMap, TempoSegment,
+    TimeSignature, Timeline,
+};
+use tempfile::TempDir;
+
+const SOURCE_RATE: f32 = 44_100.0;
+const TARGET_RATE: f32 = 48_000.0;
+
+fn scaled(position: u64) -> u64 {
+    (position as f64 * TARGET_RATE as f64 / SOURCE_RATE as f64).round() as u64
+}
+
+/// 120 BPM, slowing to 100 BPM two seconds in.
+fn tempo_map() -> TempoMap {
+    TempoMap::new(vec![
+        TempoSegment {
+            start_sample: 0,
+            tempo: Tempo(120.0),
+            time_signature: TimeSignature::four_four(),
+        },
+        TempoSegment {
+            start_sample: 88_200,
+            tempo: Tempo(100.0),
+            time_signature: TimeSignature::four_four(),
+        },
+    ])
+}
+
+fn tempo_starts(tempo_map: &TempoMap) -> Vec<u64> {
+    tempo_map
+        .segments()
+        .iter()
+        .map(|segment| segment.start_sample)
+        .collect()
+}
+
+#[test]
+fn resampling_project_scales_sample_positions_by_rate_ratio() {
+    let mut document = ProjectDocument::new(
+        ProjectMetadata::new("Demo", SOURCE_RATE, 512, 2, 4.0),
+        Vec::new(),
+    )
+    .with_tempo_map(tempo_map());
+    let ratio = document.resample_to(TARGET_RATE);
+    assert!((ratio - TARGET_RATE as f64 / SOURCE_RATE as f64).abs() < 1e-12);
+    assert_eq!(document.metadata.sample_rate, TARGET_RATE);
+    assert_eq!(tempo_starts(&document.tempo_map), vec![0, 96_000]);
+    assert_eq!(document.resample_to(TARGET_RATE), 1.0);
+
+    // One second of audio starting half a second in, with a 10 ms fade-in.
+    let tone: Vec<f32> = (0..44_100).map(|i| (i as f32 * 0.01).sin()).collect();
+    let clip = AudioClip::with_sample_rate(SOURCE_RATE, vec![tone.clone(), tone]);
+    let mut timeline = Timeline::new(SOURCE_RATE, 2);
+    timeline
+        .add_clip(ClipEvent::new(clip, 22_050).with_fade_in(FadeSpec::new(441, FadeCurve::Linear)));
+    timeline.add_clip(ClipEvent::new(
+        AudioClip::with_sample_rate(SOURCE_RATE, vec![vec![0.5; 4_410]]),
+        100_000,
+    ));
+    timeline.resample_to(TARGET_RATE);
+
+    assert_eq!(timeline.sample_rate(), TARGET_RATE);
+    let clips = timeline.clips();
+    assert_eq!(clips[0].start_frame, 24_000);
+    assert_eq!(clips[0].clip.frames(), 48_000);
+    assert_eq!(clips[0].clip.sample_rate(), TARGET_RATE);
+    assert_eq!(clips[0].fade_in.map(|fade| fade.length()), Some(480));
+    assert_eq!(clips[1].start_frame as u64, scaled(100_000));
+    assert_eq!(clips[1].clip.frames(), 4_800);
+    let rendered = timeline.render().unwrap();
+    assert_eq!(rendered.sample_rate(), TARGET_RATE);
+    assert_eq!(rendered.frames() as u64, scaled(100_000) + 4_800);
+
+    let mut curve = AutomationCurve::new();
+    curve.add_point(CurvePoint::new(4_410, 0.0, CurveShape::Linear));
+    curve.add_point(CurvePoint::new(12_345, 1.0, CurveShape::Linear));
+    curve.rescale(ratio);
+    let positions: Vec<u64> = curve.points().iter().map(|point| point.sample).collect();
+    assert_eq!(positions, vec![4_800, scaled(12_345)]);
+}
+
+#[test]
+fn loading_converts_the_project_to_the_requested_rate() {
+    let dir = TempDir::new().unwrap();
+    let mut state = CommandBus::default().state().clone();
+    // One point before the tempo change and two after it.
+    state.automation.lanes[0].points = [0.5, 6.0, 9.5]
+        .into_iter()
+        .map(|beat| AutomationPoint { beat, value: 0.5 })
+        .collect();
+    let document = ProjectDocument::new(
+        ProjectMetadata::new("Demo", SOURCE_RATE, 512, 2, 4.0),
+        Vec::new(),
+    )
+    .with_tempo_map(tempo_map())
+    .with_state(state.clone());
+    let path = dir.path().join("session.hsq");
+    save_project(&path, &document, SaveOptions::default()).unwrap();
+
+    let loaded = load_project(&path, LoadOptions::default().with_sample_rate(TARGET_RATE))
+        .unwrap()
+        .document;
+    assert_eq!(loaded.metadata.sample_rate, TARGET_RATE);
+    assert_eq!(tempo_starts(&loaded.tempo_map), vec![0, 96_000]);
+
+    // Automation stays in beats and lands at the same time at the new rate.
+    assert_eq!(loaded.state.automation, state.automation);
+    for point in &loaded.state.automation.lanes[0].points {
+        let beat = point.beat as f64;
+        let before = document.tempo_map.sample_at_beat(SOURCE_RATE, beat);
+        let after = loaded.tempo_map.sample_at_beat(TARGET_RATE, beat);
+        assert!(
+            (after - scaled(before.round() as u64) as f64).abs() <= 1.0,
+            "beat {beat}: {after} vs {before}"
+        );
+    }
+
+    let unchanged = load_project(&path, LoadOptions::default())
+        .unwrap()
+        .document;
+    assert_eq!(unchanged.metadata.sample_rate, SOURCE_RATE);
+    assert_eq!(unchanged.tempo_map, document.tempo_map);
+}
+
+#[test]
+fn resampling_project_converts_embedded_audio() {
+    let loader = MediaLoader::new();
+    let audio = DecodedAudio {
+        sample_rate: SOURCE_RATE as u32,
+        channels: vec![vec![0.25; 44_100]; 2],
+    };
+    let mut wav = Cursor::new(Vec::new());
+    loader.encode_wav(&audio, &mut wav).unwrap();
+    let audio_asset = MediaAsset::new("tone", "audio/tone.wav", wav.into_inner());
+    let notes = MediaAsset::new("notes", "notes.txt", b"not audio".to_vec());
+
+    let mut document = ProjectDocument::new(
+        ProjectMetadata::new("Demo", SOURCE_RATE, 512, 2, 1.0),
+        vec![audio_asset.clone(), notes.clone()],
+    );
+    document.resample_to(TARGET_RATE);
+
+    let converted = &document.media[0];
+    assert_ne!(converted.checksum, audio_asset.checksum);
+    assert!(converted.checksum.validate(&converted.data));
+    let decoded = loader
+        .load_from_reader(Cursor::new(converted.data.clone()), None)
+        .unwrap();
+    assert_eq!(decoded.sample_rate, TARGET_RATE as u32);
+    assert_eq!(decoded.channels.len(), 2);
+    assert_eq!(decoded.channels[0].len(), 48_000);
+    assert_eq!(document.media[1], notes);
+}
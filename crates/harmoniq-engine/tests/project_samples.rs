@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::PathBuf;
+
+use harmoniq_engine::project::{
+    load_project, save_project, LoadOptions, ProjectDocument, ProjectMetadata, SampleRef,
+    SaveOptions,
+};
+use harmoniq_engine::ArrangementClip;
+use tempfile::TempDir;
+
+fn document(samples: Vec<SampleRef>) -> ProjectDocument {
+    ProjectDocument::new(
+        ProjectMetadata::new("Samples", 48_000.0, 256, 2, 10.0),
+        Vec::new(),
+    )
+    .with_samples(samples)
+}
+
+#[test]
+fn sample_refs_store_relative_and_absolute_paths() {
+    let dir = TempDir::new().unwrap();
+    let inside = dir.path().join("audio/snare.wav");
+    fs::create_dir_all(inside.parent().unwrap()).unwrap();
+    fs::write(&inside, b"snare").unwrap();
+
+    let sample = SampleRef::new("snare", "audio/snare.wav", dir.path());
+    assert_eq!(sample.absolute_path, inside);
+    assert_eq!(sample.relative_path, PathBuf::from("audio/snare.wav"));
+    assert!(sample.is_resolved());
+
+    let outside = TempDir::new().unwrap();
+    let elsewhere = outside.path().join("pad.wav");
+    let sample = SampleRef::new("pad", &elsewhere, dir.path());
+    assert_eq!(sample.relative_path, elsewhere);
+    assert!(!sample.is_resolved());
+}
+
+#[test]
+fn moved_project_relinks_relative_then_by_search() {
+    let original = TempDir::new().unwrap();
+    fs::create_dir_all(original.path().join("audio")).unwrap();
+    fs::write(original.path().join("audio/kick.wav"), b"kick").unwrap();
+    let library = TempDir::new().unwrap();
+    let library_hat = library.path().join("drums/hats/hat.wav");
+    fs::create_dir_all(library_hat.parent().unwrap()).unwrap();
+    fs::write(&library_hat, b"hat").unwrap();
+
+    let doc = document(vec![
+        SampleRef::new("kick", "audio/kick.wav", original.path()),
+        SampleRef::new("hat", original.path().join("hat.wav"), original.path()),
+        SampleRef::new("gone", "audio/gone.wav", original.path()),
+    ]);
+    let project_path = original.path().join("session.hsq");
+    save_project(&project_path, &doc, SaveOptions::default()).unwrap();
+
+    // Move the project and its audio folder to another machine.
+    let moved = TempDir::new().unwrap();
+    fs::create_dir_all(moved.path().join("audio")).unwrap();
+    fs::copy(project_path, moved.path().join("session.hsq")).unwrap();
+    fs::copy(
+        original.path().join("audio/kick.wav"),
+        moved.path().join("audio/kick.wav"),
+    )
+    .unwrap();
+    drop(original);
+
+    let mut loaded = load_project(&moved.path().join("session.hsq"), LoadOptions::default())
+        .unwrap()
+        .document;
+    let kick = &loaded.samples[0];
+    assert_eq!(
+        kick.resolved_path,
+        Some(moved.path().join("audio/kick.wav"))
+    );
+    assert_eq!(kick.absolute_path, moved.path().join("audio/kick.wav"));
+    let missing: Vec<&str> = loaded
+        .missing_samples()
+        .map(|sample| sample.id.as_str())
+        .collect();
+    assert_eq!(missing, ["hat", "gone"]);
+
+    let still_missing = loaded.relink_samples(moved.path(), &[library.path().to_path_buf()]);
+    assert_eq!(still_missing, 1);
+    let hat = &loaded.samples[1];
+    assert_eq!(hat.resolved_path.as_ref(), Some(&library_hat));
+    assert_eq!(hat.absolute_path, library_hat);
+    assert_eq!(loaded.missing_samples().next().unwrap().id, "gone");
+
+    let clip = ArrangementClip {
+        id: 1,
+        name: "Hat".into(),
+        start: 0.0,
+        length: 1.0,
+        media: Some("hat".into()),
+        color: None,
+        tags: Vec::new(),
+        locked: false,
+    };
+    assert_eq!(loaded.sample_for_clip(&clip).unwrap().id, "hat");
+}
@@ -0,0 +1,118 @@
+use harmoniq_engine::render::{RenderDuration, RenderRequest};
+use harmoniq_engine::{
+    nodes::NodeOsc, AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, GainNode,
+    GraphBuilder, HarmoniqEngine, OfflineRenderer, PluginDescriptor,
+};
+
+const FRAMES: usize = 4_800;
+
+fn renderer() -> OfflineRenderer {
+    let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config).expect("engine");
+    let mut builder = GraphBuilder::new();
+    let osc = engine
+        .register_processor(Box::new(NodeOsc::new(330.0).with_amplitude(0.25)))
+        .expect("osc");
+    let node = builder.add_node(osc);
+    builder.connect_to_mixer(node, 1.0).expect("connect");
+    engine.replace_graph(builder.build()).expect("graph");
+    OfflineRenderer::new(engine).expect("renderer")
+}
+
+fn request() -> RenderRequest {
+    RenderRequest {
+        duration: RenderDuration::Frames(FRAMES),
+        ..RenderRequest::default()
+    }
+}
+
+/// Gain stage that also delays its output, reporting the delay as latency.
+struct DelayedGain {
+    gain: f32,
+    delay: usize,
+    lines: Vec<Vec<f32>>,
+    position: usize,
+}
+
+impl AudioProcessor for DelayedGain {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.delayed_gain", "Delayed Gain", "Tests")
+    }
+
+    fn prepare(&mut self, config: &BufferConfig) -> anyhow::Result<()> {
+        self.lines = vec![vec![0.0; self.delay]; config.layout.channels() as usize];
+        self.position = 0;
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        let frames = buffer.len();
+        for (channel, line) in buffer.channels_mut().zip(self.lines.iter_mut()) {
+            let mut position = self.position;
+            for sample in channel.iter_mut() {
+                let delayed = line[position];
+                line[position] = *sample * self.gain;
+                *sample = delayed;
+                position = (position + 1) % self.delay;
+            }
+        }
+        self.position = (self.position + frames) % self.delay;
+        Ok(())
+    }
+
+    fn latency_samples(&self) -> usize {
+        self.delay
+    }
+}
+
+#[test]
+fn difference_matches_gain_effect() {
+    let mut renderer = renderer();
+    let mut insert = GainNode::new(0.5);
+    let result = renderer.render_ab(&request(), &mut insert).expect("render");
+
+    assert_eq!(result.latency_frames, 0);
+    assert_eq!(result.bypassed.frames(), FRAMES);
+    assert_eq!(result.enabled.frames(), FRAMES);
+    for channel in 0..result.bypassed.channels() {
+        let dry = result.bypassed.channel(channel).unwrap();
+        let diff = result.difference.channel(channel).unwrap();
+        assert!(dry.iter().any(|sample| sample.abs() > 0.1));
+        for (dry, diff) in dry.iter().zip(diff) {
+            assert!((diff - (-0.5 * dry)).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn unity_insert_leaves_no_difference() {
+    let mut renderer = renderer();
+    let mut insert = GainNode::new(1.0);
+    let result = renderer.render_ab(&request(), &mut insert).expect("render");
+    for channel in 0..result.difference.channels() {
+        let diff = result.difference.channel(channel).unwrap();
+        assert!(diff.iter().all(|sample| *sample == 0.0));
+    }
+}
+
+#[test]
+fn latent_insert_is_time_aligned() {
+    let mut renderer = renderer();
+    let mut insert = DelayedGain {
+        gain: 2.0,
+        delay: 200,
+        lines: Vec::new(),
+        position: 0,
+    };
+    let result = renderer.render_ab(&request(), &mut insert).expect("render");
+
+    assert_eq!(result.latency_frames, 200);
+    assert_eq!(result.enabled.frames(), FRAMES);
+    for channel in 0..result.bypassed.channels() {
+        let dry = result.bypassed.channel(channel).unwrap();
+        let diff = result.difference.channel(channel).unwrap();
+        for (dry, diff) in dry.iter().zip(diff) {
+            assert!((diff - dry).abs() < 1e-6);
+        }
+    }
+}
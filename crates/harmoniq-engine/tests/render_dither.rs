@@ -0,0 +1,76 @@
+use std::f64::consts::PI;
+
+use harmoniq_engine::render::Quantizer;
+use harmoniq_engine::{DitherKind, ShapingProfile};
+
+const SAMPLE_RATE: f64 = 48_000.0;
+const FRAMES: usize = 8_192;
+const BITS: u32 = 16;
+
+/// Quantisation error, in LSBs, left after quantising a quiet sine.
+fn quantisation_error(dither: DitherKind) -> Vec<f64> {
+    let mut quantizer = Quantizer::new(BITS, Some(dither), 1, 7);
+    let full_scale = quantizer.full_scale() as f64;
+    (0..FRAMES)
+        .map(|frame| {
+            let sample = 0.01 * (2.0 * PI * 440.0 * frame as f64 / SAMPLE_RATE).sin();
+            let quantised = quantizer.quantize(0, sample as f32);
+            quantised as f64 - sample as f32 as f64 * full_scale
+        })
+        .collect()
+}
+
+/// Hann-windowed DFT energy of `signal` between `low` and `high` Hz.
+fn band_energy(signal: &[f64], low: f64, high: f64) -> f64 {
+    let len = signal.len();
+    let windowed: Vec<f64> = signal
+        .iter()
+        .enumerate()
+        .map(|(index, sample)| sample * (0.5 - 0.5 * (2.0 * PI * index as f64 / len as f64).cos()))
+        .collect();
+    let first = (low * len as f64 / SAMPLE_RATE).ceil() as usize;
+    let last = (high * len as f64 / SAMPLE_RATE).floor() as usize;
+    (first..=last)
+        .map(|bin| {
+            let (mut re, mut im) = (0.0, 0.0);
+            for (index, sample) in windowed.iter().enumerate() {
+                let phase = 2.0 * PI * (bin * index) as f64 / len as f64;
+                re += sample * phase.cos();
+                im -= sample * phase.sin();
+            }
+            re * re + im * im
+        })
+        .sum()
+}
+
+const NINTH: DitherKind = DitherKind::NoiseShaped {
+    profile: ShapingProfile::NinthOrder,
+};
+const THIRD: DitherKind = DitherKind::NoiseShaped {
+    profile: ShapingProfile::ThirdOrder,
+};
+
+#[test]
+fn noise_shaped_dither_is_quieter_than_tpdf_in_the_sensitive_band() {
+    let tpdf = band_energy(&quantisation_error(DitherKind::Tpdf), 1_000.0, 4_000.0);
+    let ninth = band_energy(&quantisation_error(NINTH), 1_000.0, 4_000.0);
+    let third = band_energy(&quantisation_error(THIRD), 1_000.0, 4_000.0);
+    assert!(
+        ninth < tpdf * 0.25,
+        "ninth-order in-band energy {ninth} should be well below tpdf {tpdf}"
+    );
+    assert!(
+        third < tpdf,
+        "third-order in-band energy {third} should be below tpdf {tpdf}"
+    );
+}
+
+#[test]
+fn noise_shaped_dither_keeps_the_signal() {
+    for dither in [NINTH, THIRD] {
+        let error = quantisation_error(dither);
+        assert!(error.iter().all(|error| error.abs() < 64.0));
+        let mean = error.iter().sum::<f64>() / error.len() as f64;
+        assert!(mean.abs() < 0.5, "{dither:?}: mean error {mean}");
+    }
+}
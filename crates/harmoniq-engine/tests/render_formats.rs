@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use harmoniq_engine::media::loader::MediaLoader;
+use harmoniq_engine::render::{
+    RenderDuration, RenderFile, RenderFormat, RenderProject, RenderQueue, RenderRequest,
+};
+use harmoniq_engine::{
+    nodes::NodeOsc, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder, HarmoniqEngine,
+    OfflineRenderer,
+};
+use tempfile::TempDir;
+
+const FRAMES: usize = 24_000;
+
+struct SineProject;
+
+impl RenderProject for SineProject {
+    fn label(&self) -> &str {
+        "Format Check"
+    }
+
+    fn create_engine(&self) -> anyhow::Result<HarmoniqEngine> {
+        let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+        let mut engine = HarmoniqEngine::new(config)?;
+        engine.execute_command(EngineCommand::SetTempo(96.5))?;
+        let mut builder = GraphBuilder::new();
+        let osc = engine.register_processor(Box::new(NodeOsc::new(440.0).with_amplitude(0.5)))?;
+        let node = builder.add_node(osc);
+        builder.connect_to_mixer(node, 1.0)?;
+        engine.replace_graph(builder.build())?;
+        engine.reset_render_state()?;
+        Ok(engine)
+    }
+}
+
+/// Renders through the queue into `format` and returns the rendered
+/// reference, the decoded file and its raw bytes.
+fn render_and_decode(format: RenderFormat) -> (Vec<Vec<f32>>, Vec<Vec<f32>>, Vec<u8>) {
+    let dir = TempDir::new().expect("tempdir");
+    let path = dir.path().join("mixdown");
+    let request = RenderRequest {
+        duration: RenderDuration::Frames(FRAMES),
+        mixdown: Some(RenderFile {
+            path: path.clone(),
+            format,
+            dither: None,
+        }),
+        ..RenderRequest::default()
+    };
+    let mut queue = RenderQueue::new();
+    queue.enqueue_project(Arc::new(SineProject), request.clone());
+    queue.process_all().expect("render");
+
+    let mut renderer = OfflineRenderer::new(SineProject.create_engine().unwrap()).unwrap();
+    let reference = renderer
+        .render(&RenderRequest {
+            mixdown: None,
+            ..request
+        })
+        .expect("reference")
+        .mixdown;
+    let reference = (0..reference.channels())
+        .map(|channel| reference.channel(channel).unwrap().to_vec())
+        .collect();
+
+    let decoded = MediaLoader::new().load_from_path(&path).expect("decode");
+    assert_eq!(decoded.sample_rate, 48_000);
+    (reference, decoded.channels, std::fs::read(&path).unwrap())
+}
+
+fn contains(bytes: &[u8], needle: &str) -> bool {
+    bytes
+        .windows(needle.len())
+        .any(|window| window == needle.as_bytes())
+}
+
+#[test]
+fn flac_round_trips_within_quantisation_error() {
+    for compression in [0, 5, 8] {
+        let (reference, decoded, bytes) = render_and_decode(RenderFormat::Flac { compression });
+        assert_eq!(decoded.len(), reference.len());
+        for (expected, actual) in reference.iter().zip(&decoded) {
+            assert_eq!(actual.len(), expected.len());
+            for (lhs, rhs) in expected.iter().zip(actual) {
+                assert!(
+                    (lhs - rhs).abs() < 1e-6,
+                    "level {compression}: {lhs} vs {rhs}"
+                );
+            }
+        }
+        assert!(contains(&bytes, "TITLE=Format Check"));
+        assert!(contains(&bytes, "BPM=96.5"));
+    }
+}
+
+#[test]
+fn ogg_vorbis_round_trips_within_lossy_tolerance() {
+    let (reference, decoded, bytes) = render_and_decode(RenderFormat::OggVorbis { quality: 0.6 });
+    assert_eq!(decoded.len(), reference.len());
+    assert!(contains(&bytes, "TITLE=Format Check"));
+    assert!(contains(&bytes, "BPM=96.5"));
+
+    // Compare the middle of the render, allowing for any encoder delay the
+    // decoder leaves in place.
+    let window = 4_096..FRAMES - 4_096;
+    for (expected, actual) in reference.iter().zip(&decoded) {
+        assert!(actual.len() + 2_048 >= expected.len());
+        let signal: f32 = expected[window.clone()].iter().map(|s| s * s).sum();
+        let error = (0..=2_048)
+            .map(|lag| {
+                window
+                    .clone()
+                    .map(|frame| {
+                        let rhs = actual.get(frame + lag).copied().unwrap_or(0.0);
+                        (expected[frame] - rhs).powi(2)
+                    })
+                    .sum::<f32>()
+            })
+            .fold(f32::INFINITY, f32::min);
+        assert!(
+            error < signal * 0.01,
+            "error energy {error} vs signal {signal}"
+        );
+    }
+}
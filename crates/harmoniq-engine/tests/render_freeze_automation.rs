@@ -0,0 +1,105 @@
+use harmoniq_engine::render::OfflineRenderer;
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, AutomationCommand, BufferConfig, ChannelLayout, CurveShape,
+    GraphBuilder, HarmoniqEngine, ParameterSpec, PluginDescriptor, PluginId,
+};
+
+const RAMP_FRAMES: usize = 48_000;
+
+/// Outputs a constant level driven by its volume parameter.
+struct LevelSource {
+    level: f32,
+}
+
+impl AudioProcessor for LevelSource {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.level", "Level Source", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        for sample in buffer.iter_mut() {
+            *sample = self.level;
+        }
+        Ok(())
+    }
+
+    fn handle_automation_event(
+        &mut self,
+        parameter: usize,
+        value: f32,
+        _sample_offset: usize,
+    ) -> anyhow::Result<()> {
+        if parameter == 0 {
+            self.level = value;
+        }
+        Ok(())
+    }
+}
+
+fn automated_engine() -> (HarmoniqEngine, PluginId) {
+    let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config).expect("engine");
+    let source = engine
+        .register_processor(Box::new(LevelSource { level: 1.0 }))
+        .expect("source");
+    let mut builder = GraphBuilder::new();
+    let node = builder.add_node(source);
+    builder.connect_to_mixer(node, 1.0).expect("connect");
+    engine.replace_graph(builder.build()).expect("graph");
+
+    engine
+        .register_automation_parameter(source, ParameterSpec::new(0, "Volume", 0.0, 1.0, 1.0))
+        .expect("parameter");
+    let sender = engine.automation_sender(source).expect("sender");
+    for (sample, value) in [(0, 0.0), (RAMP_FRAMES as u64, 1.0)] {
+        sender
+            .send(AutomationCommand::DrawCurve {
+                parameter: 0,
+                sample,
+                value,
+                shape: CurveShape::Linear,
+            })
+            .expect("draw");
+    }
+    (engine, source)
+}
+
+#[test]
+fn frozen_clip_contains_the_volume_ramp() {
+    let (engine, source) = automated_engine();
+    let mut renderer = OfflineRenderer::new(engine).expect("renderer");
+    let frozen = renderer
+        .freeze_node(source, 0..RAMP_FRAMES)
+        .expect("freeze");
+    let samples = frozen.channel(0).expect("channel");
+    assert_eq!(samples.len(), RAMP_FRAMES);
+
+    assert!(
+        samples[0] < 0.01,
+        "ramp should start silent, got {}",
+        samples[0]
+    );
+    assert!((samples[RAMP_FRAMES / 2] - 0.5).abs() < 0.01);
+    assert!(samples[RAMP_FRAMES - 1] > 0.99);
+    assert!(samples.windows(2).all(|pair| pair[1] >= pair[0]));
+}
+
+#[test]
+fn freezing_a_later_range_evaluates_automation_at_that_position() {
+    let (engine, source) = automated_engine();
+    let mut renderer = OfflineRenderer::new(engine).expect("renderer");
+    let full = renderer
+        .freeze_node(source, 0..RAMP_FRAMES)
+        .expect("full freeze");
+    let tail = renderer
+        .freeze_node(source, RAMP_FRAMES / 2..RAMP_FRAMES)
+        .expect("tail freeze");
+
+    let full = full.channel(0).expect("channel");
+    let tail = tail.channel(0).expect("channel");
+    assert_eq!(tail, &full[RAMP_FRAMES / 2..]);
+}
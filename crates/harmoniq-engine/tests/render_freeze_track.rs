@@ -0,0 +1,208 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use harmoniq_engine::engine::TransportState;
+use harmoniq_engine::render::{FreezeSettings, RenderDuration, RenderFormat};
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, GraphBuilder, HarmoniqEngine,
+    NodeHandle, PluginDescriptor,
+};
+use tempfile::TempDir;
+
+const BLOCK: usize = 128;
+const FRAMES: usize = 4_800;
+const TAIL_SECONDS: f32 = 0.01;
+const TAIL_FRAMES: usize = 480;
+
+/// Emits a slow ramp so the output depends on where playback is.
+struct Ramp {
+    position: usize,
+    calls: Arc<AtomicUsize>,
+}
+
+impl AudioProcessor for Ramp {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.ramp", "Ramp", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        self.position = 0;
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        let frames = buffer.len();
+        for channel in buffer.channels_mut() {
+            for (offset, sample) in channel.iter_mut().enumerate() {
+                *sample = ((self.position + offset) % 1_000) as f32 / 2_000.0;
+            }
+        }
+        self.position += frames;
+        Ok(())
+    }
+}
+
+/// Insert that halves its input.
+struct Halve {
+    calls: Arc<AtomicUsize>,
+}
+
+impl AudioProcessor for Halve {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.halve", "Halve", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        for sample in buffer.as_mut_slice() {
+            *sample *= 0.5;
+        }
+        Ok(())
+    }
+}
+
+fn engine(calls: &Arc<AtomicUsize>) -> (HarmoniqEngine, NodeHandle) {
+    let config = BufferConfig::new(48_000.0, BLOCK, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config).expect("engine");
+    let source = engine
+        .register_processor(Box::new(Ramp {
+            position: 0,
+            calls: Arc::clone(calls),
+        }))
+        .expect("source");
+    let insert = engine
+        .register_processor(Box::new(Halve {
+            calls: Arc::clone(calls),
+        }))
+        .expect("insert");
+
+    let mut builder = GraphBuilder::new();
+    let source = builder.add_node(source);
+    let insert = builder.add_node(insert);
+    builder.connect(source, insert, 1.0).expect("insert route");
+    builder.connect_to_mixer(source, 0.0).expect("dry route");
+    builder.connect_to_mixer(insert, 1.0).expect("wet route");
+    engine.replace_graph(builder.build()).expect("graph");
+    (engine, source)
+}
+
+fn settings(dir: &TempDir) -> FreezeSettings {
+    FreezeSettings {
+        directory: dir.path().to_path_buf(),
+        format: RenderFormat::Wav,
+        dither: None,
+        plugins: None,
+        duration: RenderDuration::Frames(FRAMES),
+        tail_seconds: TAIL_SECONDS,
+    }
+}
+
+fn play(engine: &mut HarmoniqEngine, frames: usize) -> Vec<f32> {
+    engine.reset_render_state().expect("reset");
+    engine.set_transport(TransportState::Playing);
+    let mut output = Vec::with_capacity(frames);
+    let mut buffer = AudioBuffer::from_config(engine.config());
+    while output.len() < frames {
+        engine.process_block(&mut buffer).expect("block");
+        output.extend_from_slice(buffer.channel(0));
+    }
+    engine.set_transport(TransportState::Stopped);
+    output.truncate(frames);
+    output
+}
+
+#[test]
+fn frozen_clip_covers_the_duration_and_tail_of_the_insert_chain() {
+    let dir = TempDir::new().expect("tempdir");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (mut engine, source) = engine(&calls);
+
+    let clip = engine
+        .freeze_track(source, &settings(&dir))
+        .expect("freeze");
+    assert!(engine.is_track_frozen(source));
+    assert_eq!(clip.frames(), FRAMES + TAIL_FRAMES);
+    let frozen = clip.channel(0).expect("channel");
+    // Only the halved insert output reaches the master; the dry route is
+    // silent.
+    for (frame, sample) in frozen.iter().enumerate() {
+        let expected = (frame % 1_000) as f32 / 2_000.0 * 0.5;
+        assert!((sample - expected).abs() < 1e-6, "frame {frame}");
+    }
+}
+
+#[test]
+fn frozen_playback_matches_live_without_running_the_plugins() {
+    let dir = TempDir::new().expect("tempdir");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (mut engine, source) = engine(&calls);
+    let live = play(&mut engine, FRAMES);
+
+    engine
+        .freeze_track(source, &settings(&dir))
+        .expect("freeze");
+    calls.store(0, Ordering::Relaxed);
+    let frozen = play(&mut engine, FRAMES);
+    assert_eq!(calls.load(Ordering::Relaxed), 0);
+    for (frame, (frozen, live)) in frozen.iter().zip(&live).enumerate() {
+        assert!((frozen - live).abs() < 1e-6, "frame {frame}");
+    }
+
+    assert!(engine.unfreeze_track(source).expect("unfreeze"));
+    assert!(!engine.unfreeze_track(source).expect("second unfreeze"));
+    let restored = play(&mut engine, FRAMES);
+    assert!(calls.load(Ordering::Relaxed) > 0);
+    assert_eq!(restored, live);
+}
+
+#[test]
+fn freezing_restores_the_transport() {
+    let dir = TempDir::new().expect("tempdir");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (mut engine, source) = engine(&calls);
+    engine.set_transport(TransportState::Playing);
+    let mut buffer = AudioBuffer::from_config(engine.config());
+    for _ in 0..3 {
+        engine.process_block(&mut buffer).expect("block");
+    }
+    let position = engine
+        .transport_metrics()
+        .sample_pos
+        .load(Ordering::Relaxed);
+    assert_eq!(position, 3 * BLOCK as u64);
+
+    engine
+        .freeze_track(source, &settings(&dir))
+        .expect("freeze");
+    assert_eq!(engine.transport(), TransportState::Playing);
+    assert_eq!(
+        engine
+            .transport_metrics()
+            .sample_pos
+            .load(Ordering::Relaxed),
+        position
+    );
+
+    engine.set_transport(TransportState::Stopped);
+    engine.unfreeze_track(source).expect("unfreeze");
+    engine
+        .freeze_track(source, &settings(&dir))
+        .expect("freeze");
+    assert_eq!(engine.transport(), TransportState::Stopped);
+}
+
+#[test]
+fn freezing_twice_is_rejected() {
+    let dir = TempDir::new().expect("tempdir");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (mut engine, source) = engine(&calls);
+    engine
+        .freeze_track(source, &settings(&dir))
+        .expect("freeze");
+    assert!(engine.freeze_track(source, &settings(&dir)).is_err());
+}
@@ -0,0 +1,183 @@
+use std::sync::{Arc, Mutex};
+
+use harmoniq_engine::render::{OfflineRenderer, RenderDuration, RenderProject, RenderRequest};
+use harmoniq_engine::{
+    nodes::NodeOsc, AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, GraphBuilder,
+    HarmoniqEngine, PluginDescriptor,
+};
+
+const SAMPLE_RATE: f32 = 48_000.0;
+const ANALYSIS_FRAMES: usize = 8_192;
+const SETTLE_FRAMES: usize = 2_048;
+/// Tone placed exactly on an analysis bin so its harmonics do too.
+const TONE_BIN: usize = 853;
+
+/// Heavily driven tanh saturator, rich in harmonics above Nyquist.
+struct Saturator;
+
+impl AudioProcessor for Saturator {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.saturator", "Saturator", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        for sample in buffer.iter_mut() {
+            *sample = (8.0 * *sample).tanh();
+        }
+        Ok(())
+    }
+}
+
+struct SaturatedProject;
+
+impl RenderProject for SaturatedProject {
+    fn label(&self) -> &str {
+        "saturated-master"
+    }
+
+    fn create_engine(&self) -> anyhow::Result<HarmoniqEngine> {
+        let config = BufferConfig::new(SAMPLE_RATE, 128, ChannelLayout::Stereo);
+        let mut engine = HarmoniqEngine::new(config)?;
+        let frequency = TONE_BIN as f32 * SAMPLE_RATE / ANALYSIS_FRAMES as f32;
+        let osc =
+            engine.register_processor(Box::new(NodeOsc::new(frequency).with_amplitude(0.5)))?;
+        let mut builder = GraphBuilder::new();
+        let node = builder.add_node(osc);
+        builder.connect_to_mixer(node, 1.0)?;
+        engine.replace_graph(builder.build())?;
+        engine.add_master_insert(Box::new(Saturator))?;
+        Ok(engine)
+    }
+}
+
+fn render(master_oversample: u8) -> Vec<f32> {
+    let mut renderer =
+        OfflineRenderer::new(SaturatedProject.create_engine().expect("engine")).expect("renderer");
+    let frames = SETTLE_FRAMES + ANALYSIS_FRAMES;
+    let result = renderer
+        .render(&RenderRequest {
+            duration: RenderDuration::Frames(frames),
+            master_oversample,
+            ..RenderRequest::default()
+        })
+        .expect("render");
+    assert_eq!(result.mixdown.frames(), frames);
+    result.mixdown.channel(0).expect("left").to_vec()
+}
+
+/// Fraction of the spectrum below 0.4 fs that is neither DC nor a harmonic
+/// of the tone, i.e. energy folded back by aliasing.
+fn alias_ratio(samples: &[f32]) -> f64 {
+    let segment = &samples[SETTLE_FRAMES..SETTLE_FRAMES + ANALYSIS_FRAMES];
+    let len = segment.len();
+    let windowed: Vec<f64> = segment
+        .iter()
+        .enumerate()
+        .map(|(index, sample)| {
+            let window = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * index as f64 / len as f64).cos();
+            *sample as f64 * window
+        })
+        .collect();
+
+    let mut total = 0.0;
+    let mut aliased = 0.0;
+    for bin in 0..(len * 2 / 5) {
+        // Rotate a phasor rather than calling sin/cos per sample.
+        let step = -2.0 * std::f64::consts::PI * bin as f64 / len as f64;
+        let (step_im, step_re) = step.sin_cos();
+        let (mut phasor_re, mut phasor_im) = (1.0f64, 0.0f64);
+        let (mut re, mut im) = (0.0, 0.0);
+        for sample in &windowed {
+            re += sample * phasor_re;
+            im += sample * phasor_im;
+            let next_re = phasor_re * step_re - phasor_im * step_im;
+            phasor_im = phasor_re * step_im + phasor_im * step_re;
+            phasor_re = next_re;
+        }
+        let energy = re * re + im * im;
+        total += energy;
+        let harmonic = bin % TONE_BIN;
+        let near_harmonic = harmonic <= 4 || harmonic >= TONE_BIN - 4;
+        if !near_harmonic {
+            aliased += energy;
+        }
+    }
+    aliased / total
+}
+
+#[test]
+fn oversampled_master_chain_reduces_aliasing() {
+    let base = alias_ratio(&render(1));
+    let oversampled = alias_ratio(&render(4));
+    assert!(
+        oversampled < base * 0.1,
+        "aliasing at 4x ({oversampled:e}) should be at least 10 dB below 1x ({base:e})"
+    );
+}
+
+#[test]
+fn unsupported_oversampling_factor_is_rejected() {
+    let mut renderer =
+        OfflineRenderer::new(SaturatedProject.create_engine().expect("engine")).expect("renderer");
+    let result = renderer.render(&RenderRequest {
+        duration: RenderDuration::Frames(128),
+        master_oversample: 3,
+        ..RenderRequest::default()
+    });
+    assert!(result.is_err());
+}
+
+/// Fails any block it is handed at an oversampled rate, recording the rate
+/// of every `prepare`.
+struct RateProbe {
+    rates: Arc<Mutex<Vec<f32>>>,
+}
+
+impl AudioProcessor for RateProbe {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.rate-probe", "Rate probe", "Tests")
+    }
+
+    fn prepare(&mut self, config: &BufferConfig) -> anyhow::Result<()> {
+        self.rates.lock().unwrap().push(config.sample_rate);
+        Ok(())
+    }
+
+    fn process(&mut self, _buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        match self.rates.lock().unwrap().last() {
+            Some(rate) if *rate > SAMPLE_RATE => anyhow::bail!("oversampled block"),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn failed_render_restores_the_base_rate() {
+    let rates = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = SaturatedProject.create_engine().expect("engine");
+    engine
+        .add_master_insert(Box::new(RateProbe {
+            rates: Arc::clone(&rates),
+        }))
+        .expect("insert");
+    let mut renderer = OfflineRenderer::new(engine).expect("renderer");
+    let request = RenderRequest {
+        duration: RenderDuration::Frames(512),
+        master_oversample: 4,
+        ..RenderRequest::default()
+    };
+    assert!(renderer.render(&request).is_err());
+    assert_eq!(rates.lock().unwrap().last(), Some(&SAMPLE_RATE));
+
+    let result = renderer
+        .render(&RenderRequest {
+            master_oversample: 1,
+            ..request
+        })
+        .expect("render at the base rate");
+    assert_eq!(result.mixdown.frames(), 512);
+}
@@ -0,0 +1,86 @@
+use harmoniq_engine::render::{
+    integrated_loudness, sample_peak, true_peak, Normalization, RenderDuration, RenderProject,
+    RenderRequest,
+};
+use harmoniq_engine::{
+    nodes::NodeOsc, BufferConfig, ChannelLayout, GraphBuilder, HarmoniqEngine, OfflineRenderer,
+};
+
+/// A 997 Hz tone reaching the master at 0.5 per channel, which BS.1770
+/// reads as -6.02 LUFS.
+struct ToneProject;
+
+impl RenderProject for ToneProject {
+    fn label(&self) -> &str {
+        "tone"
+    }
+
+    fn create_engine(&self) -> anyhow::Result<HarmoniqEngine> {
+        let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+        let mut engine = HarmoniqEngine::new(config)?;
+        let amplitude = 0.5 * std::f32::consts::SQRT_2;
+        let osc =
+            engine.register_processor(Box::new(NodeOsc::new(997.0).with_amplitude(amplitude)))?;
+        let mut builder = GraphBuilder::new();
+        let node = builder.add_node(osc);
+        builder.connect_to_mixer(node, 1.0)?;
+        engine.replace_graph(builder.build())?;
+        Ok(engine)
+    }
+}
+
+fn render(normalization: Normalization) -> harmoniq_engine::RenderResult {
+    let mut renderer = OfflineRenderer::new(ToneProject.create_engine().unwrap()).unwrap();
+    renderer
+        .render(&RenderRequest {
+            duration: RenderDuration::Seconds(5.0),
+            normalization,
+            ..RenderRequest::default()
+        })
+        .expect("render")
+}
+
+#[test]
+fn lufs_normalization_hits_target() {
+    let result = render(Normalization::Lufs {
+        target: -14.0,
+        true_peak_ceiling: -1.0,
+    });
+    let measured = integrated_loudness(&result.mixdown);
+    assert!((measured + 14.0).abs() < 0.5, "integrated {measured}");
+
+    let report = result.normalization.expect("report");
+    assert!(
+        (report.pre_lufs + 6.02).abs() < 0.2,
+        "pre {}",
+        report.pre_lufs
+    );
+    assert!((report.post_lufs - measured).abs() < 1e-3);
+    assert!(
+        (report.gain_db + 7.98).abs() < 0.2,
+        "gain {}",
+        report.gain_db
+    );
+}
+
+#[test]
+fn true_peak_ceiling_limits_loud_targets() {
+    // Reaching +2 LUFS would put the tone's peaks above 0 dBTP.
+    let result = render(Normalization::Lufs {
+        target: 2.0,
+        true_peak_ceiling: -1.0,
+    });
+    let peak = true_peak(&result.mixdown);
+    assert!(peak <= -1.0 + 1e-3, "true peak {peak}");
+    let report = result.normalization.expect("report");
+    assert!(report.post_lufs < 2.0 && report.post_lufs > -2.0);
+}
+
+#[test]
+fn peak_normalization_and_none() {
+    let result = render(Normalization::Peak(-0.5));
+    let peak = sample_peak(&result.mixdown);
+    assert!((peak + 0.5).abs() < 0.01, "peak {peak}");
+
+    assert!(render(Normalization::None).normalization.is_none());
+}
@@ -1,4 +1,6 @@
-use harmoniq_engine::render::{RenderDuration, RenderProject, RenderRequest, RenderSpeed};
+use harmoniq_engine::render::{
+    Normalization, RenderDuration, RenderProject, RenderRequest, RenderSpeed,
+};
 use harmoniq_engine::{
     nodes::NodeOsc, AudioBuffer, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder,
     HarmoniqEngine, TransportState,
@@ -36,6 +38,8 @@ fn offline_render_matches_realtime_engine() {
         stems: None,
         freeze: None,
         speed: RenderSpeed::Offline,
+        master_oversample: 1,
+        normalization: Normalization::None,
     };
 
     let result = renderer.render(&request).expect("render");
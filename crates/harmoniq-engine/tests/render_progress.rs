@@ -0,0 +1,132 @@
+use std::fs::read_dir;
+use std::path::Path;
+use std::sync::Arc;
+
+use harmoniq_engine::render::{
+    RenderControl, RenderDuration, RenderFile, RenderFormat, RenderProject, RenderQueue,
+    RenderRequest, StemSettings,
+};
+use harmoniq_engine::{
+    nodes::NodeOsc, BufferConfig, ChannelLayout, GraphBuilder, HarmoniqEngine, OfflineRenderer,
+};
+use tempfile::TempDir;
+
+const BLOCK: usize = 128;
+
+struct TwoOscProject;
+
+impl RenderProject for TwoOscProject {
+    fn label(&self) -> &str {
+        "progress"
+    }
+
+    fn create_engine(&self) -> anyhow::Result<HarmoniqEngine> {
+        let config = BufferConfig::new(48_000.0, BLOCK, ChannelLayout::Stereo);
+        let mut engine = HarmoniqEngine::new(config)?;
+        let mut builder = GraphBuilder::new();
+        for frequency in [220.0, 330.0] {
+            let osc = engine
+                .register_processor(Box::new(NodeOsc::new(frequency).with_amplitude(0.25)))?;
+            let node = builder.add_node(osc);
+            builder.connect_to_mixer(node, 1.0)?;
+        }
+        engine.replace_graph(builder.build())?;
+        engine.reset_render_state()?;
+        Ok(engine)
+    }
+}
+
+fn request(frames: usize) -> RenderRequest {
+    RenderRequest {
+        duration: RenderDuration::Frames(frames),
+        ..RenderRequest::default()
+    }
+}
+
+#[test]
+fn progress_is_reported_once_per_block() {
+    let mut renderer = OfflineRenderer::new(TwoOscProject.create_engine().unwrap()).unwrap();
+    let frames = 10 * BLOCK + 40;
+    let mut updates = Vec::new();
+    let result = renderer
+        .render_with_progress(&request(frames), |progress| {
+            updates.push(progress);
+            RenderControl::Continue
+        })
+        .expect("render");
+
+    assert!(!result.cancelled);
+    assert_eq!(updates.len(), 11);
+    assert!(updates.iter().all(|update| update.total_frames == frames));
+    assert!(updates.iter().all(|update| update.stem.is_none()));
+    assert_eq!(updates[0].frames_rendered, BLOCK);
+    assert_eq!(updates[10].frames_rendered, frames);
+    assert_eq!(updates[10].fraction(), 1.0);
+}
+
+#[test]
+fn cancelling_stops_at_the_block_boundary() {
+    let mut renderer = OfflineRenderer::new(TwoOscProject.create_engine().unwrap()).unwrap();
+    let result = renderer
+        .render_with_progress(&request(48_000), |progress| {
+            if progress.frames_rendered >= 3 * BLOCK {
+                RenderControl::Cancel
+            } else {
+                RenderControl::Continue
+            }
+        })
+        .expect("render");
+
+    assert!(result.cancelled);
+    assert_eq!(result.mixdown.frames(), 3 * BLOCK);
+    assert!(result
+        .stems
+        .iter()
+        .all(|stem| stem.clip.frames() == 3 * BLOCK));
+}
+
+fn job_writing_to(dir: &Path) -> RenderRequest {
+    let mut job = request(4 * BLOCK);
+    job.mixdown = Some(RenderFile {
+        path: dir.join("mix.wav"),
+        format: RenderFormat::Wav,
+        dither: None,
+    });
+    job.stems = Some(StemSettings::new(dir.join("stems"), RenderFormat::Wav));
+    job
+}
+
+#[test]
+fn cancelled_queue_job_removes_written_files() {
+    let first = TempDir::new().expect("tempdir");
+    let second = TempDir::new().expect("tempdir");
+    let mut queue = RenderQueue::new();
+    queue.enqueue_project(Arc::new(TwoOscProject), job_writing_to(first.path()));
+    queue.enqueue_project(Arc::new(TwoOscProject), job_writing_to(second.path()));
+
+    // Cancel the first job while it renders its first stem.
+    let mut stems_seen = 0;
+    let mut jobs_seen = Vec::new();
+    let reports = queue
+        .process_all_with_progress(|index, progress| {
+            jobs_seen.push(index);
+            if index == 0 && progress.stem.is_some() {
+                stems_seen += 1;
+                if stems_seen == 2 {
+                    return RenderControl::Cancel;
+                }
+            }
+            RenderControl::Continue
+        })
+        .expect("queue");
+
+    assert!(reports[0].cancelled);
+    assert!(reports[0].mixdown.is_none() && reports[0].stems.is_empty());
+    assert!(!first.path().join("mix.wav").exists());
+    assert_eq!(read_dir(first.path().join("stems")).unwrap().count(), 0);
+
+    assert!(!reports[1].cancelled);
+    assert_eq!(reports[1].stems.len(), 2);
+    assert!(second.path().join("mix.wav").exists());
+    assert!(jobs_seen.contains(&1));
+}
@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+
+use harmoniq_engine::render::{ProcessorKind, RenderDuration, RenderRequest, RenderSpeed};
+use harmoniq_engine::{
+    nodes::NodeNoise, BufferConfig, ChannelLayout, GraphBuilder, HarmoniqEngine, OfflineRenderer,
+};
+
+const SAMPLE_RATE: f32 = 48_000.0;
+
+fn renderer() -> OfflineRenderer {
+    let config = BufferConfig::new(SAMPLE_RATE, 256, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config).expect("engine");
+    let noise = engine
+        .register_processor(Box::new(NodeNoise::new(0.5)))
+        .expect("noise");
+    let mut builder = GraphBuilder::new();
+    let node = builder.add_node(noise);
+    builder.connect_to_mixer(node, 0.5).expect("connect");
+    engine.replace_graph(builder.build()).expect("graph");
+    OfflineRenderer::new(engine).expect("renderer")
+}
+
+/// Renders `seconds` at `speed`, returning the samples and the wall-clock time taken.
+fn render(speed: RenderSpeed, seconds: f32) -> (Vec<Vec<f32>>, Duration) {
+    let mut renderer = renderer();
+    let started = Instant::now();
+    let result = renderer
+        .render(&RenderRequest {
+            duration: RenderDuration::Seconds(seconds),
+            speed,
+            ..RenderRequest::default()
+        })
+        .expect("render");
+    let elapsed = started.elapsed();
+    let channels = (0..result.mixdown.channels())
+        .map(|channel| result.mixdown.channel(channel).unwrap().to_vec())
+        .collect();
+    (channels, elapsed)
+}
+
+#[test]
+fn paced_renders_take_wall_clock_time_and_match_offline() {
+    let (offline, _) = render(RenderSpeed::Offline, 0.2);
+    let (realtime, realtime_elapsed) = render(RenderSpeed::Realtime, 0.2);
+    let (fast, fast_elapsed) = render(RenderSpeed::Multiplier(4.0), 0.2);
+
+    assert!(
+        realtime_elapsed >= Duration::from_millis(195),
+        "{realtime_elapsed:?}"
+    );
+    assert!(
+        fast_elapsed >= Duration::from_millis(48),
+        "{fast_elapsed:?}"
+    );
+    assert_eq!(realtime, offline);
+    assert_eq!(fast, offline);
+}
+
+#[test]
+fn invalid_multipliers_render_unpaced() {
+    let (offline, _) = render(RenderSpeed::Offline, 0.05);
+    for factor in [0.0, -2.0, f32::NAN] {
+        let (samples, _) = render(RenderSpeed::Multiplier(factor), 0.05);
+        assert_eq!(samples, offline);
+    }
+}
+
+#[test]
+fn recommendation_follows_the_most_demanding_processor() {
+    use ProcessorKind::*;
+    assert_eq!(RenderSpeed::recommended_for(&[]), RenderSpeed::Offline);
+    assert_eq!(
+        RenderSpeed::recommended_for(&[Native]),
+        RenderSpeed::Offline
+    );
+    assert_eq!(
+        RenderSpeed::recommended_for(&[Native, Hosted]),
+        RenderSpeed::Multiplier(4.0)
+    );
+    assert_eq!(
+        RenderSpeed::recommended_for(&[Hosted, External, Native]),
+        RenderSpeed::Realtime
+    );
+}
@@ -0,0 +1,242 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use harmoniq_engine::media::loader::MediaLoader;
+use harmoniq_engine::render::{
+    RenderDuration, RenderFile, RenderFormat, RenderProject, RenderQueue, RenderReport,
+    RenderRequest, RenderSource, StemGroup, StemSettings, StemSource, StemSplit,
+};
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, GraphBuilder, HarmoniqEngine,
+    PluginDescriptor, PluginId,
+};
+use tempfile::TempDir;
+
+const FRAMES: usize = 4_800;
+const TAIL_SECONDS: f32 = 0.05;
+const TAIL_FRAMES: usize = 2_400;
+
+/// Writes a constant level into every channel.
+struct DcSource {
+    name: &'static str,
+    level: f32,
+}
+
+impl AudioProcessor for DcSource {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.dc", self.name, "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.as_mut_slice().fill(self.level);
+        Ok(())
+    }
+}
+
+/// Shared send effect that returns its summed input unchanged.
+struct Return;
+
+impl AudioProcessor for Return {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.return", "Hall", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, _buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Keys (0.2) and bass (0.4) both feed a shared return; the bass is also
+/// routed into the "Low End" bus.
+struct SendProject;
+
+impl SendProject {
+    fn ids() -> (PluginId, PluginId) {
+        let engine = SendProject.create_engine().unwrap();
+        let ids = engine.graph().unwrap().plugin_ids();
+        (ids[0], ids[1])
+    }
+}
+
+impl RenderProject for SendProject {
+    fn label(&self) -> &str {
+        "Stem Check"
+    }
+
+    fn create_engine(&self) -> anyhow::Result<HarmoniqEngine> {
+        let config = BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo);
+        let mut engine = HarmoniqEngine::new(config)?;
+        let keys = engine.register_processor(Box::new(DcSource {
+            name: "Keys",
+            level: 0.2,
+        }))?;
+        let bass = engine.register_processor(Box::new(DcSource {
+            name: "Bass",
+            level: 0.4,
+        }))?;
+        let hall = engine.register_processor(Box::new(Return))?;
+
+        let mut builder = GraphBuilder::new();
+        let keys = builder.add_node(keys);
+        let bass = builder.add_node(bass);
+        let hall = builder.add_node(hall);
+        let low = builder.add_mixer_bus("Low End");
+        builder.connect_to_mixer(keys, 1.0)?;
+        builder.connect_to_mixer(bass, 1.0)?;
+        builder.connect_to_mixer(hall, 1.0)?;
+        builder.connect(keys, hall, 1.0)?;
+        builder.connect(bass, hall, 1.0)?;
+        builder.connect(bass, low, 1.0)?;
+        engine.replace_graph(builder.build())?;
+        engine.reset_render_state()?;
+        Ok(engine)
+    }
+}
+
+fn render(dir: &Path, settings: StemSettings) -> RenderReport {
+    let request = RenderRequest {
+        duration: RenderDuration::Frames(FRAMES),
+        mixdown: Some(RenderFile {
+            path: dir.join("mix.wav"),
+            format: RenderFormat::Wav,
+            dither: None,
+        }),
+        stems: Some(settings),
+        ..RenderRequest::default()
+    };
+    let mut queue = RenderQueue::new();
+    queue.enqueue_project(Arc::new(SendProject), request);
+    queue.process_all().expect("render").remove(0)
+}
+
+fn stems(report: &RenderReport) -> Vec<(StemSource, f32)> {
+    report
+        .files
+        .iter()
+        .filter_map(|file| match file.source {
+            RenderSource::Stem(source) => Some((source, file.peak_dbfs)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn load(path: &Path) -> Vec<Vec<f32>> {
+    MediaLoader::new()
+        .load_from_path(path)
+        .expect("decode")
+        .channels
+}
+
+#[test]
+fn per_track_stems_are_named_from_the_template_and_include_the_tail() {
+    let dir = TempDir::new().expect("tempdir");
+    let mut settings = StemSettings::new(dir.path().join("stems"), RenderFormat::Wav);
+    settings.tail_seconds = TAIL_SECONDS;
+    let report = render(dir.path(), settings);
+    let (keys, bass) = SendProject::ids();
+
+    assert_eq!(report.files.len(), 3);
+    assert_eq!(report.files[0].source, RenderSource::Mixdown);
+    assert_eq!(
+        report.stems,
+        [
+            dir.path().join("stems/stem_check_keys_01.wav"),
+            dir.path().join("stems/stem_check_bass_02.wav"),
+        ]
+    );
+    let stems = stems(&report);
+    assert_eq!(stems[0].0, StemSource::Track(keys));
+    assert_eq!(stems[1].0, StemSource::Track(bass));
+    // Dry stems leave out the shared return, so bass is simply twice keys.
+    assert!((stems[1].1 - stems[0].1 - 6.02).abs() < 0.01);
+
+    for path in &report.stems {
+        let channels = load(path);
+        assert!(channels
+            .iter()
+            .all(|channel| channel.len() == FRAMES + TAIL_FRAMES));
+    }
+    assert_eq!(load(&dir.path().join("mix.wav"))[0].len(), FRAMES);
+}
+
+#[test]
+fn wet_stems_carry_their_own_sends_and_sum_to_the_mixdown() {
+    let dir = TempDir::new().expect("tempdir");
+    let mut settings = StemSettings::new(dir.path().join("stems"), RenderFormat::Wav);
+    settings.wet = true;
+    settings.name_template = "{index}-{track}".to_owned();
+    let report = render(dir.path(), settings);
+
+    assert_eq!(
+        report.stems,
+        [
+            dir.path().join("stems/01-keys.wav"),
+            dir.path().join("stems/02-bass.wav"),
+        ]
+    );
+    let mix = load(&dir.path().join("mix.wav"));
+    let keys = load(&report.stems[0]);
+    let bass = load(&report.stems[1]);
+    for channel in 0..mix.len() {
+        for frame in 0..FRAMES {
+            let sum = keys[channel][frame] + bass[channel][frame];
+            assert!(
+                (sum - mix[channel][frame]).abs() < 1e-5,
+                "frame {frame}: {sum} vs {}",
+                mix[channel][frame]
+            );
+        }
+    }
+
+    // Each wet stem hears the return fed by itself alone: double its level.
+    let dry = StemSettings::new(dir.path().join("dry"), RenderFormat::Wav);
+    let dry = stems(&render(dir.path(), dry));
+    let wet = stems(&report);
+    for ((_, dry), (_, wet)) in dry.iter().zip(&wet) {
+        assert!((wet - dry - 6.02).abs() < 0.01, "{dry} vs {wet}");
+    }
+}
+
+#[test]
+fn bus_and_group_stems_collect_their_tracks() {
+    let dir = TempDir::new().expect("tempdir");
+    let (keys, bass) = SendProject::ids();
+
+    let mut settings = StemSettings::new(dir.path().join("bus"), RenderFormat::Wav);
+    settings.split = StemSplit::PerBus;
+    let report = render(dir.path(), settings);
+    assert_eq!(
+        report.stems,
+        [dir.path().join("bus/stem_check_low_end_01.wav")]
+    );
+    let bus = stems(&report);
+    assert!(matches!(bus[0].0, StemSource::Bus(_)));
+
+    let mut settings = StemSettings::new(dir.path().join("groups"), RenderFormat::Wav);
+    settings.split = StemSplit::Groups(vec![
+        StemGroup {
+            name: "Bass VCA".to_owned(),
+            plugins: vec![bass],
+        },
+        StemGroup {
+            name: "Everything".to_owned(),
+            plugins: vec![keys, bass],
+        },
+    ]);
+    let report = render(dir.path(), settings);
+    let groups = stems(&report);
+    assert_eq!(groups[0].0, StemSource::Group(0));
+    assert_eq!(groups[1].0, StemSource::Group(1));
+    assert!((groups[0].1 - bus[0].1).abs() < 0.01);
+    // Keys and bass together: 0.6 against 0.4.
+    let expected = 20.0 * (0.6f32 / 0.4).log10();
+    assert!((groups[1].1 - groups[0].1 - expected).abs() < 0.01);
+}
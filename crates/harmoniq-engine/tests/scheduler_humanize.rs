@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder,
+    HarmoniqEngine, HumanizeSettings, MidiEvent, PluginDescriptor, TransportState,
+};
+use harmoniq_playlist::state::{
+    Clip, ClipId, ClipKind, PatternNote, Playlist, Track, TrackId, TrackLane,
+};
+use parking_lot::Mutex;
+
+const BLOCK: usize = 512;
+const PPQ: u32 = 96;
+const STEP_TICKS: i64 = 24;
+
+type NoteLog = Arc<Mutex<Vec<(u64, u8, u8)>>>;
+
+/// Records note-ons as (absolute sample, pitch, velocity).
+struct NoteRecorder {
+    block: u64,
+    log: NoteLog,
+}
+
+impl AudioProcessor for NoteRecorder {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.recorder", "Recorder", "Harmoniq Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, _buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        self.block += 1;
+        Ok(())
+    }
+
+    fn supports_layout(&self, _layout: ChannelLayout) -> bool {
+        true
+    }
+
+    fn process_midi(&mut self, events: &[MidiEvent]) -> anyhow::Result<()> {
+        let mut log = self.log.lock();
+        for event in events {
+            if let MidiEvent::NoteOn { note, velocity, .. } = event {
+                let at = self.block * BLOCK as u64 + event.sample_offset() as u64;
+                log.push((at, *note, *velocity));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn playlist() -> Playlist {
+    let notes = (0..8)
+        .map(|index| PatternNote {
+            id: index as u64 + 1,
+            start_ticks: STEP_TICKS * (index + 1),
+            duration_ticks: STEP_TICKS / 2,
+            pitch: 60 + index as u8,
+            velocity: 100,
+            channel: 0,
+        })
+        .collect();
+
+    let mut track = Track::new(TrackId(0), "Lead");
+    track.add_lane(TrackLane::new(0, "Lane 1"));
+    track.add_clip(Clip::new(
+        ClipId(7),
+        "Pattern",
+        0,
+        STEP_TICKS as u64 * 10,
+        [1.0; 4],
+        ClipKind::Pattern { pattern_id: 1 },
+    ));
+
+    let mut playlist = Playlist {
+        ppq: PPQ,
+        tracks: vec![track],
+        selection: None,
+        dropped_files: Vec::new(),
+        patterns: HashMap::new(),
+    };
+    playlist.set_pattern_notes(1, notes);
+    playlist
+}
+
+fn render(humanize: Option<HumanizeSettings>) -> Vec<(u64, u8, u8)> {
+    let config = BufferConfig::new(48_000.0, BLOCK, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).unwrap();
+    let log = NoteLog::default();
+    let recorder = engine
+        .register_processor(Box::new(NoteRecorder {
+            block: 0,
+            log: Arc::clone(&log),
+        }))
+        .unwrap();
+    let mut graph = GraphBuilder::new();
+    let node = graph.add_node(recorder);
+    graph.connect_to_mixer(node, 1.0).unwrap();
+    engine.replace_graph(graph.build()).unwrap();
+
+    engine
+        .execute_command(EngineCommand::SetPatternMode(false))
+        .unwrap();
+    engine
+        .execute_command(EngineCommand::SetPlaylist(playlist()))
+        .unwrap();
+    engine
+        .execute_command(EngineCommand::SetHumanize(humanize))
+        .unwrap();
+    engine.set_transport(TransportState::Playing);
+
+    // 250 samples per tick at 120 BPM; cover the whole clip.
+    let mut buffer = AudioBuffer::from_config(&config);
+    for _ in 0..(STEP_TICKS as usize * 10 * 250 / BLOCK + 2) {
+        engine.process_block(&mut buffer).unwrap();
+    }
+
+    let events = log.lock().clone();
+    drop(engine);
+    events
+}
+
+#[test]
+fn same_seed_renders_identical_timing() {
+    let settings = HumanizeSettings::new(42, 6, 12);
+    let first = render(Some(settings));
+    let second = render(Some(settings));
+    assert_eq!(first.len(), 8);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn different_seeds_render_different_timing() {
+    let first = render(Some(HumanizeSettings::new(1, 6, 12)));
+    let second = render(Some(HumanizeSettings::new(2, 6, 12)));
+    assert_eq!(first.len(), 8);
+    assert_eq!(second.len(), 8);
+    assert_ne!(first, second);
+}
+
+#[test]
+fn humanize_deviates_from_grid_within_bounds() {
+    let straight = render(None);
+    let humanized = render(Some(HumanizeSettings::new(9, 6, 12)));
+    assert_eq!(straight.len(), humanized.len());
+    assert_ne!(straight, humanized);
+    for ((grid, _, grid_velocity), (shifted, _, velocity)) in straight.iter().zip(&humanized) {
+        // Six ticks of deviation plus one block of tick rounding slack.
+        assert!(grid.abs_diff(*shifted) <= 6 * 250 + BLOCK as u64);
+        assert!(grid_velocity.abs_diff(*velocity) <= 12);
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use harmoniq_engine::{
+    LaunchQuantize, SessionClip, SessionEvent, SessionView, Tempo, TempoMap, TempoSegment,
+    TimeSignature,
+};
+
+const SR: f32 = 48_000.0;
+
+fn session(tempo_map: TempoMap) -> SessionView {
+    let mut view = SessionView::new(2, 4, SR, Arc::new(tempo_map));
+    view.set_clip(0, 0, Some(SessionClip::new("drums", 16.0)))
+        .unwrap();
+    view.set_clip(0, 1, Some(SessionClip::new("drums b", 16.0)))
+        .unwrap();
+    view.set_clip(1, 0, Some(SessionClip::new("bass", 8.0)))
+        .unwrap();
+    view
+}
+
+#[test]
+fn launch_mid_bar_waits_for_next_bar() {
+    // 120 bpm in 4/4: one beat is 24 000 samples, one bar 96 000.
+    let mut view = session(TempoMap::single(Tempo(120.0), TimeSignature::four_four()));
+    let mut events = Vec::new();
+    view.advance(30_000, &mut events);
+    assert!(events.is_empty());
+
+    let launch = view.launch_clip(0, 0).unwrap();
+    assert_eq!(launch.start_sample, 96_000);
+    assert_eq!(launch.stops, None);
+
+    view.advance(60_000, &mut events);
+    assert!(events.is_empty());
+    assert_eq!(view.playing_slot(0), None);
+
+    view.advance(10_000, &mut events);
+    assert_eq!(
+        events,
+        vec![SessionEvent::ClipStarted {
+            track: 0,
+            slot: 0,
+            sample: 96_000,
+        }]
+    );
+    assert_eq!(view.playing_slot(0), Some(0));
+}
+
+#[test]
+fn launch_follows_tempo_changes_and_stops_playing_clip() {
+    // Four beats at 120 bpm, then 90 bpm (32 000 samples per beat) from bar 2.
+    let tempo_map = TempoMap::new(vec![
+        TempoSegment {
+            start_sample: 0,
+            tempo: Tempo(120.0),
+            time_signature: TimeSignature::four_four(),
+        },
+        TempoSegment {
+            start_sample: 96_000,
+            tempo: Tempo(90.0),
+            time_signature: TimeSignature::four_four(),
+        },
+    ]);
+    let mut view = session(tempo_map);
+    view.set_quantize(LaunchQuantize::Immediate);
+    view.launch_clip(0, 0).unwrap();
+    let mut events = Vec::new();
+    view.advance(100_000, &mut events);
+    assert_eq!(view.playing_slot(0), Some(0));
+    events.clear();
+
+    view.set_quantize(LaunchQuantize::Bar);
+    let launch = view.launch_clip(0, 1).unwrap();
+    assert_eq!(launch.start_sample, 96_000 + 4 * 32_000);
+    assert_eq!(launch.stops, Some(0));
+
+    view.advance(200_000, &mut events);
+    assert_eq!(
+        events,
+        vec![
+            SessionEvent::ClipStopped {
+                track: 0,
+                slot: 0,
+                sample: 224_000,
+            },
+            SessionEvent::ClipStarted {
+                track: 0,
+                slot: 1,
+                sample: 224_000,
+            },
+        ]
+    );
+    assert_eq!(view.playing_slot(0), Some(1));
+    assert_eq!(view.playing_slot(1), None);
+}
+
+#[test]
+fn launching_empty_slot_is_rejected() {
+    let mut view = session(TempoMap::default());
+    assert!(view.launch_clip(1, 3).is_err());
+    assert!(view.launch_clip(5, 0).is_err());
+}
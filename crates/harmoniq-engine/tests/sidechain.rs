@@ -0,0 +1,274 @@
+use std::sync::{Arc, Mutex};
+
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, GraphBuilder, HarmoniqEngine,
+    PluginDescriptor, ProcessTransport, TransportState, SIDECHAIN_PIN,
+};
+
+const KEY_LEVEL: f32 = 0.5;
+
+/// Writes a constant level into every channel.
+struct DcSource;
+
+impl AudioProcessor for DcSource {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.dc", "DC Source", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.as_mut_slice().fill(KEY_LEVEL);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Levels {
+    input: f32,
+    key: Option<f32>,
+    playing: bool,
+}
+
+/// Pass-through effect with one sidechain input that records the peak of
+/// its main input and of its key.
+struct KeyMeter {
+    levels: Arc<Mutex<Levels>>,
+}
+
+fn peak(buffer: &AudioBuffer) -> f32 {
+    buffer
+        .as_slice()
+        .iter()
+        .fold(0.0, |peak, sample| peak.max(sample.abs()))
+}
+
+impl AudioProcessor for KeyMeter {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.key-meter", "Key Meter", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        let mut levels = self.levels.lock().unwrap();
+        levels.input = levels.input.max(peak(buffer));
+        Ok(())
+    }
+
+    fn aux_input_count(&self) -> usize {
+        1
+    }
+
+    fn process_with_aux(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        aux: &[&AudioBuffer],
+        ctx: &ProcessTransport,
+    ) -> anyhow::Result<()> {
+        let key = aux.first().map_or(0.0, |key| peak(key));
+        {
+            let mut levels = self.levels.lock().unwrap();
+            levels.key = Some(levels.key.unwrap_or(0.0).max(key));
+            levels.playing = ctx.is_playing;
+        }
+        self.process(buffer)
+    }
+}
+
+/// Renders a few blocks of a DC source and a key meter, optionally keying
+/// the meter from the source, and returns the meter's levels and the
+/// output peak.
+fn run(keyed: bool) -> (Levels, f32) {
+    let config = BufferConfig::new(48_000.0, 256, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let levels = Arc::new(Mutex::new(Levels::default()));
+    let source = engine
+        .register_processor(Box::new(DcSource))
+        .expect("source");
+    let meter = engine
+        .register_processor(Box::new(KeyMeter {
+            levels: Arc::clone(&levels),
+        }))
+        .expect("meter");
+
+    let mut builder = GraphBuilder::new();
+    let source = builder.add_node(source);
+    let meter = builder.add_node(meter);
+    if keyed {
+        builder
+            .connect(source, meter.pin(SIDECHAIN_PIN), 1.0)
+            .expect("sidechain");
+    }
+    builder.connect_to_mixer(meter, 1.0).expect("meter");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine.set_transport(TransportState::Playing);
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    let mut output = 0.0f32;
+    for _ in 0..4 {
+        engine.process_block(&mut buffer).expect("block");
+        output = output.max(peak(&buffer));
+    }
+    let levels = std::mem::take(&mut *levels.lock().unwrap());
+    (levels, output)
+}
+
+#[test]
+fn sidechain_edge_keys_the_processor() {
+    let (levels, output) = run(true);
+    assert_eq!(levels.key, Some(KEY_LEVEL));
+    assert!(levels.playing, "the keyed processor sees the transport");
+    // The key is only listened to: the meter's own input stays empty, and
+    // the source is still heard on its own route.
+    assert_eq!(levels.input, 0.0);
+    assert!(output > 0.1, "{output}");
+}
+
+#[test]
+fn unkeyed_processor_is_processed_without_aux() {
+    let (levels, _) = run(false);
+    assert_eq!(levels.key, None);
+}
+
+const LATENCY: usize = 64;
+
+/// Passes its input through `LATENCY` samples late and reports it.
+struct LatentPass {
+    history: Vec<Vec<f32>>,
+}
+
+impl AudioProcessor for LatentPass {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.latent", "Latent Pass", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        let channels = buffer.channel_count();
+        self.history.resize(channels, vec![0.0; LATENCY]);
+        for (channel, history) in self.history.iter_mut().enumerate() {
+            for sample in buffer.channel_mut(channel) {
+                history.push(*sample);
+                *sample = history.remove(0);
+            }
+        }
+        Ok(())
+    }
+
+    fn latency_samples(&self) -> usize {
+        LATENCY
+    }
+}
+
+/// Records the first sample at which its main input and its key become
+/// non-silent.
+#[derive(Default)]
+struct Onsets {
+    elapsed: usize,
+    input: Option<usize>,
+    key: Option<usize>,
+}
+
+struct OnsetMeter {
+    onsets: Arc<Mutex<Onsets>>,
+}
+
+fn onset(buffer: &AudioBuffer) -> Option<usize> {
+    (0..buffer.len()).find(|&frame| {
+        (0..buffer.channel_count()).any(|channel| buffer.channel(channel)[frame] != 0.0)
+    })
+}
+
+impl AudioProcessor for OnsetMeter {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.onset-meter", "Onset Meter", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        let mut onsets = self.onsets.lock().unwrap();
+        let elapsed = onsets.elapsed;
+        if onsets.input.is_none() {
+            onsets.input = onset(buffer).map(|frame| elapsed + frame);
+        }
+        onsets.elapsed += buffer.len();
+        Ok(())
+    }
+
+    fn aux_input_count(&self) -> usize {
+        1
+    }
+
+    fn process_with_aux(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        aux: &[&AudioBuffer],
+        _ctx: &ProcessTransport,
+    ) -> anyhow::Result<()> {
+        {
+            let mut onsets = self.onsets.lock().unwrap();
+            let elapsed = onsets.elapsed;
+            if onsets.key.is_none() {
+                onsets.key = aux
+                    .first()
+                    .and_then(|key| onset(key))
+                    .map(|frame| elapsed + frame);
+            }
+        }
+        self.process(buffer)
+    }
+}
+
+#[test]
+fn key_is_delayed_to_the_latency_of_the_main_input() {
+    let config = BufferConfig::new(48_000.0, 256, ChannelLayout::Stereo);
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let onsets = Arc::new(Mutex::new(Onsets::default()));
+    let source = engine
+        .register_processor(Box::new(DcSource))
+        .expect("source");
+    let latent = engine
+        .register_processor(Box::new(LatentPass {
+            history: Vec::new(),
+        }))
+        .expect("latent");
+    let key = engine.register_processor(Box::new(DcSource)).expect("key");
+    let meter = engine
+        .register_processor(Box::new(OnsetMeter {
+            onsets: Arc::clone(&onsets),
+        }))
+        .expect("meter");
+
+    let mut builder = GraphBuilder::new();
+    let source = builder.add_node(source);
+    let latent = builder.add_node(latent);
+    let key = builder.add_node(key);
+    let meter = builder.add_node(meter);
+    builder.connect(source, latent, 1.0).expect("source");
+    builder.connect(latent, meter, 1.0).expect("input");
+    builder
+        .connect(key, meter.pin(SIDECHAIN_PIN), 1.0)
+        .expect("sidechain");
+    builder.connect_to_mixer(meter, 1.0).expect("meter");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine.set_transport(TransportState::Playing);
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    for _ in 0..2 {
+        engine.process_block(&mut buffer).expect("block");
+    }
+    let onsets = onsets.lock().unwrap();
+    assert_eq!(onsets.input, Some(LATENCY));
+    assert_eq!(onsets.key, onsets.input);
+}
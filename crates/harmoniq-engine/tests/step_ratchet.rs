@@ -0,0 +1,38 @@
+use harmoniq_engine::{Step, StepPattern};
+
+#[test]
+fn ratchet_splits_step_into_even_retriggers() {
+    let mut pattern = StepPattern::new(4, 120);
+    pattern.set_step(0, Step::on(36, 100));
+    pattern.set_step(2, Step::on(38, 90).with_ratchet(3));
+
+    let notes = pattern.to_pattern_notes();
+    assert_eq!(notes.len(), 4);
+    assert_eq!(notes[0].start_ticks, 0);
+
+    let ratchet: Vec<_> = notes.iter().filter(|note| note.pitch == 38).collect();
+    assert_eq!(ratchet.len(), 3);
+    let starts: Vec<i64> = ratchet.iter().map(|note| note.start_ticks).collect();
+    assert_eq!(starts, vec![240, 280, 320]);
+    for note in &ratchet {
+        assert_eq!(note.duration_ticks, 40);
+        assert!(note.end_ticks() <= 360, "retrigger leaves the step window");
+        assert_eq!(note.velocity, 90);
+    }
+}
+
+#[test]
+fn ratchet_velocity_ramps_across_retriggers() {
+    let mut pattern = StepPattern::new(1, 96);
+    pattern.set_step(
+        0,
+        Step::on(42, 40).with_ratchet(4).with_ratchet_velocity(100),
+    );
+
+    let velocities: Vec<u8> = pattern
+        .to_pattern_notes()
+        .iter()
+        .map(|note| note.velocity)
+        .collect();
+    assert_eq!(velocities, vec![40, 60, 80, 100]);
+}
@@ -0,0 +1,97 @@
+use harmoniq_engine::clips::AudioClip;
+use harmoniq_engine::dsp::events::TransportClock;
+use harmoniq_engine::timeline::{ClipEvent, Timeline, TimelinePlayer};
+use harmoniq_engine::LoopRegion;
+
+const SAMPLE_RATE: f32 = 48_000.0;
+const FREQUENCY: f32 = 440.0;
+const BLOCK: usize = 256;
+// 92.125 periods of the sine, so a hard wrap jumps by an eighth of a cycle.
+const LOOP_START: u64 = 2_000;
+const LOOP_END: u64 = LOOP_START + 10_050;
+const CROSSFADE: u64 = 480;
+
+fn player() -> TimelinePlayer {
+    let sine = (0..SAMPLE_RATE as usize)
+        .map(|frame| (std::f32::consts::TAU * FREQUENCY * frame as f32 / SAMPLE_RATE).sin())
+        .collect();
+    let mut timeline = Timeline::new(SAMPLE_RATE, 1);
+    timeline.add_clip(ClipEvent::new(
+        AudioClip::with_sample_rate(SAMPLE_RATE, vec![sine]),
+        0,
+    ));
+    timeline.player().expect("player")
+}
+
+fn play(
+    player: &TimelinePlayer,
+    region: Option<LoopRegion>,
+    crossfade: u64,
+    frames: usize,
+) -> Vec<f32> {
+    let clock = TransportClock::new();
+    clock.set_loop_region(region);
+    clock.set_loop_crossfade(crossfade);
+    clock.start_immediately();
+    let mut rendered = Vec::with_capacity(frames);
+    let mut block = vec![vec![0.0f32; BLOCK]];
+    while rendered.len() < frames {
+        player.process(&clock, &mut block);
+        rendered.extend_from_slice(&block[0]);
+    }
+    rendered.truncate(frames);
+    rendered
+}
+
+fn largest_step(samples: &[f32]) -> f32 {
+    samples
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn looping_sine_wraps_without_a_discontinuity() {
+    let player = player();
+    let frames = 4 * LOOP_END as usize;
+    // The sine itself moves by at most this much per sample.
+    let sine_step = std::f32::consts::TAU * FREQUENCY / SAMPLE_RATE;
+
+    let region = LoopRegion::new(LOOP_START, LOOP_END);
+    let hard = play(&player, Some(region), 0, frames);
+    assert!(largest_step(&hard) > 5.0 * sine_step);
+
+    let faded = play(&player, Some(region), CROSSFADE, frames);
+    // Equal-power mixing of two out-of-phase sines peaks slightly above
+    // unity, plus the change in the fade gains themselves.
+    let tolerance = 1.5 * sine_step + std::f32::consts::FRAC_PI_2 / CROSSFADE as f32;
+    assert!(
+        largest_step(&faded) < tolerance,
+        "{} >= {tolerance}",
+        largest_step(&faded)
+    );
+
+    // Outside the crossfade after each wrap, playback is the loop itself.
+    let length = (LOOP_END - LOOP_START) as usize;
+    let first_pass = LOOP_END as usize;
+    for frame in first_pass + CROSSFADE as usize..first_pass + length {
+        assert_eq!(faded[frame], hard[frame], "frame {frame}");
+    }
+}
+
+#[test]
+fn crossfade_is_inactive_without_a_wrap() {
+    let player = player();
+    let mix = player.mix().channel(0).expect("channel");
+    let frames = 8 * BLOCK;
+
+    let unlooped = play(&player, None, CROSSFADE, frames);
+    assert_eq!(unlooped, &mix[..frames]);
+
+    // The region lies past the rendered span, so the playhead never wraps.
+    let region = LoopRegion::new(20_000, 30_000);
+    assert_eq!(
+        play(&player, Some(region), CROSSFADE, frames),
+        &mix[..frames]
+    );
+}
@@ -0,0 +1,162 @@
+use std::sync::{Arc, Mutex};
+
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, EngineCommand, GraphBuilder,
+    HarmoniqEngine, PluginDescriptor, TransportState,
+};
+
+const SOURCE_LEVEL: f32 = 0.5;
+
+/// Writes a constant level into every channel.
+struct DcSource;
+
+impl AudioProcessor for DcSource {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.dc", "DC Source", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        buffer.as_mut_slice().fill(SOURCE_LEVEL);
+        Ok(())
+    }
+}
+
+/// Pass-through insert that records the peak level it receives.
+struct PeakInsert {
+    peak: Arc<Mutex<f32>>,
+}
+
+impl AudioProcessor for PeakInsert {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new("test.peak", "Peak Insert", "Tests")
+    }
+
+    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        let peak = buffer
+            .as_slice()
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        *self.peak.lock().unwrap() = peak;
+        Ok(())
+    }
+}
+
+fn config() -> BufferConfig {
+    BufferConfig::new(48_000.0, 128, ChannelLayout::Stereo)
+}
+
+/// Plays a few blocks and returns the last master sample.
+fn master_level(engine: &mut HarmoniqEngine) -> f32 {
+    engine
+        .execute_command(EngineCommand::SetTransport(TransportState::Playing))
+        .expect("transport");
+    let mut buffer = AudioBuffer::from_config(&config());
+    for _ in 0..64 {
+        engine.process_block(&mut buffer).expect("process");
+    }
+    *buffer.channel(0).last().expect("sample")
+}
+
+/// Renders a source feeding a single insert and returns the level the insert saw.
+fn insert_input_level(trim_db: f32, fader: f32) -> f32 {
+    let config = config();
+    let mut engine = HarmoniqEngine::new(config.clone()).expect("engine");
+    let peak = Arc::new(Mutex::new(0.0));
+    let source = engine
+        .register_processor(Box::new(DcSource))
+        .expect("source");
+    let insert = engine
+        .register_processor(Box::new(PeakInsert {
+            peak: Arc::clone(&peak),
+        }))
+        .expect("insert");
+
+    let mut builder = GraphBuilder::new();
+    let source_node = builder.add_node(source);
+    let insert_node = builder.add_node(insert);
+    builder
+        .connect(source_node, insert_node, 1.0)
+        .expect("connect");
+    builder.connect_to_mixer(insert_node, fader).expect("fader");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine.set_track_input_trim(1, trim_db);
+    engine
+        .execute_command(EngineCommand::SetTransport(TransportState::Playing))
+        .expect("transport");
+
+    let mut buffer = AudioBuffer::from_config(&config);
+    engine.process_block(&mut buffer).expect("process");
+    let level = *peak.lock().unwrap();
+    level
+}
+
+#[test]
+fn input_trim_scales_what_the_insert_receives() {
+    assert!((insert_input_level(0.0, 1.0) - SOURCE_LEVEL).abs() < 1e-6);
+
+    let trimmed = insert_input_level(-6.0, 1.0);
+    let expected = SOURCE_LEVEL * 10.0f32.powf(-6.0 / 20.0);
+    assert!((trimmed - expected).abs() < 1e-5, "trimmed {trimmed}");
+}
+
+#[test]
+fn fader_does_not_change_insert_input() {
+    let unity = insert_input_level(0.0, 1.0);
+    let quiet = insert_input_level(0.0, 0.25);
+    assert_eq!(unity, quiet);
+}
+
+/// A lone source track and the master level it produces.
+fn single_track_level(trim_db: f32) -> f32 {
+    let mut engine = HarmoniqEngine::new(config()).expect("engine");
+    let source = engine
+        .register_processor(Box::new(DcSource))
+        .expect("source");
+    let mut builder = GraphBuilder::new();
+    let node = builder.add_node(source);
+    builder.connect_to_mixer(node, 1.0).expect("fader");
+    engine.replace_graph(builder.build()).expect("graph");
+    engine.set_track_input_trim(0, trim_db);
+    master_level(&mut engine)
+}
+
+#[test]
+fn input_trim_applies_to_a_track_without_inserts() {
+    let unity = single_track_level(0.0);
+    assert!(unity > 0.0);
+    let trimmed = single_track_level(-6.0);
+    let expected = unity * 10.0f32.powf(-6.0 / 20.0);
+    assert!((trimmed - expected).abs() < 1e-5, "trimmed {trimmed}");
+}
+
+#[test]
+fn insert_chain_reaches_the_master_once() {
+    let mut engine = HarmoniqEngine::new(config()).expect("engine");
+    let source = engine
+        .register_processor(Box::new(DcSource))
+        .expect("source");
+    let insert = engine
+        .register_processor(Box::new(PeakInsert {
+            peak: Arc::new(Mutex::new(0.0)),
+        }))
+        .expect("insert");
+    let mut builder = GraphBuilder::new();
+    let source_node = builder.add_node(source);
+    let insert_node = builder.add_node(insert);
+    builder
+        .connect(source_node, insert_node, 1.0)
+        .expect("connect");
+    builder.connect_to_mixer(insert_node, 1.0).expect("fader");
+    engine.replace_graph(builder.build()).expect("graph");
+
+    let chained = master_level(&mut engine);
+    assert!((chained - single_track_level(0.0)).abs() < 1e-6);
+}
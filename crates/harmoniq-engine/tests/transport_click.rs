@@ -142,7 +142,7 @@ fn transport_clock_sample_accuracy() {
 
     clock.seek(0);
     clock.start_immediately();
-    clock.set_loop_region(Some(LoopRegion { start: 64, end: 96 }));
+    clock.set_loop_region(Some(LoopRegion::new(64, 96)));
     clock.advance_samples(160);
     let snapshot = clock.load();
     assert!(snapshot.sample_position >= 64 && snapshot.sample_position < 96);
@@ -0,0 +1,132 @@
+use harmoniq_dsp::{AudioBlock, AudioBlockMut};
+use harmoniq_engine::dsp::events::{Playhead, TransportClock, TransportCommand};
+use harmoniq_engine::dsp::{nodes::MetronomeClickNode, DspGraph, GraphProcess};
+use harmoniq_engine::{Tempo, TempoMap, TimeSignature};
+
+const SAMPLE_RATE: f32 = 48_000.0;
+// 120 BPM: one beat is 24 000 samples, one 4/4 bar 96 000.
+const BEAT: u64 = 24_000;
+const BAR: u64 = 4 * BEAT;
+const BLOCK: u32 = 480;
+
+fn clock() -> TransportClock {
+    let clock =
+        TransportClock::with_map(TempoMap::single(Tempo(120.0), TimeSignature::four_four()));
+    clock.set_sample_rate(SAMPLE_RATE);
+    clock
+}
+
+/// Frames advanced until the playhead first plays `sample`.
+fn frames_until_playing(clock: &TransportClock, sample: u64, limit: u64) -> Option<u64> {
+    let mut elapsed = 0;
+    while elapsed < limit {
+        let mut found = None;
+        clock.advance_with(BLOCK, |frame, playhead| {
+            if found.is_none() && playhead == Playhead::Playing(sample) {
+                found = Some(frame as u64);
+            }
+        });
+        if let Some(frame) = found {
+            return Some(elapsed + frame);
+        }
+        elapsed += BLOCK as u64;
+    }
+    None
+}
+
+#[test]
+fn record_point_is_reached_exactly_after_the_count_in() {
+    let clock = clock();
+    clock.set_count_in(2);
+    let punch_in = 3 * BAR;
+    clock.execute(TransportCommand::Record { punch_in });
+
+    let snapshot = clock.load();
+    assert!(!snapshot.is_playing);
+    assert_eq!(snapshot.sample_position, punch_in);
+    assert_eq!(snapshot.count_in_remaining, 2 * BAR);
+    assert_eq!(snapshot.punch_in, Some(punch_in));
+
+    assert_eq!(
+        frames_until_playing(&clock, punch_in, 4 * BAR),
+        Some(2 * BAR)
+    );
+}
+
+#[test]
+fn pre_roll_plays_before_the_punch_in() {
+    let clock = clock();
+    clock.set_count_in(1);
+    clock.set_pre_roll(0.25);
+    let pre_roll = 12_000;
+    let punch_in = 2 * BAR;
+    clock.execute(TransportCommand::Record { punch_in });
+
+    let snapshot = clock.load();
+    assert_eq!(snapshot.sample_position, punch_in - pre_roll);
+    assert_eq!(snapshot.count_in_remaining, BAR);
+
+    assert_eq!(
+        frames_until_playing(&clock, punch_in - pre_roll, 2 * BAR),
+        Some(BAR)
+    );
+    let snapshot = clock.load();
+    assert!(snapshot.is_playing);
+    assert!(snapshot.sample_position < punch_in);
+    assert_eq!(snapshot.punch_in, Some(punch_in));
+
+    clock.execute(TransportCommand::Stop);
+    assert_eq!(clock.load().punch_in, None);
+}
+
+#[test]
+fn start_counts_in_only_from_stop() {
+    let clock = clock();
+    clock.set_count_in(1);
+    clock.execute(TransportCommand::Start);
+    assert_eq!(clock.load().count_in_remaining, BAR);
+    assert_eq!(frames_until_playing(&clock, 0, 2 * BAR), Some(BAR));
+
+    clock.execute(TransportCommand::Start);
+    assert_eq!(clock.load().count_in_remaining, 0);
+}
+
+#[test]
+fn metronome_clicks_every_beat_of_the_count_in() {
+    let clock = clock();
+    clock.set_count_in(2);
+    clock.execute(TransportCommand::Record { punch_in: BAR });
+
+    let mut graph = DspGraph::new();
+    let (click_id, _) = graph.add_node(Box::new(MetronomeClickNode::default()), 0);
+    graph.set_topology(&[click_id]);
+    graph.prepare(SAMPLE_RATE, BLOCK, 0, 1);
+
+    let mut rendered = Vec::new();
+    for _ in 0..(2 * BAR) / BLOCK as u64 {
+        let mut block = vec![0.0f32; BLOCK as usize];
+        let transport = clock.load();
+        unsafe {
+            graph.process(GraphProcess {
+                inputs: AudioBlock::empty(),
+                outputs: AudioBlockMut::from_interleaved(block.as_mut_ptr(), 1, BLOCK),
+                frames: BLOCK,
+                transport,
+                midi: &[],
+            });
+        }
+        rendered.extend_from_slice(&block);
+        clock.advance_samples(BLOCK);
+    }
+
+    let clicks: Vec<(u64, f32)> = rendered
+        .iter()
+        .enumerate()
+        .filter(|(_, sample)| sample.abs() > 1e-5)
+        .map(|(index, sample)| (index as u64, *sample))
+        .collect();
+    let expected: Vec<(u64, f32)> = (0..8)
+        .map(|beat| (beat * BEAT, if beat % 4 == 0 { 1.0 } else { 0.4 }))
+        .collect();
+    assert_eq!(clicks, expected);
+}
@@ -0,0 +1,91 @@
+use harmoniq_engine::dsp::events::{ClockSource, TransportClock, TransportCommand};
+use harmoniq_engine::{Tempo, TempoMap, TimeSignature};
+
+const PPQN: u32 = 24;
+
+/// A 3/4 map at 90 BPM whose tempo the external clock should replace.
+fn clock() -> TransportClock {
+    let clock = TransportClock::with_map(TempoMap::single(
+        Tempo(90.0),
+        TimeSignature {
+            numerator: 3,
+            denominator: 4,
+        },
+    ));
+    clock.set_sample_rate(48_000.0);
+    clock.execute(TransportCommand::SetClockSource(ClockSource::External {
+        ppqn: PPQN,
+    }));
+    clock
+}
+
+/// Tick times `period` samples apart with up to ±30 samples of
+/// deterministic jitter.
+fn jittered_ticks(start: u64, period: u64, count: u64) -> Vec<u64> {
+    let mut state = 12_345u64;
+    (0..count)
+        .map(|index| {
+            state = (state * 1_103_515_245 + 12_345) % (1 << 31);
+            let jitter = (state % 61) as i64 - 30;
+            (start as i64 + (index * period) as i64 + jitter) as u64
+        })
+        .collect()
+}
+
+#[test]
+fn external_clock_overrides_the_tempo_but_keeps_the_meter() {
+    let clock = clock();
+    assert_eq!(clock.load().tempo, Tempo(90.0));
+    assert_eq!(clock.locked_tempo(), None);
+
+    // 120 BPM at 24 ppqn and 48 kHz: a tick every 1 000 samples.
+    let ticks = jittered_ticks(1_000, 1_000, 400);
+    for (index, tick) in ticks.iter().enumerate() {
+        clock.on_external_clock(*tick);
+        if index >= 200 {
+            let bpm = clock.locked_tempo().expect("locked").beats_per_minute();
+            assert!((bpm - 120.0).abs() < 0.5, "tick {index}: {bpm}");
+        }
+    }
+
+    let snapshot = clock.load();
+    assert!((snapshot.tempo.beats_per_minute() - 120.0).abs() < 0.5);
+    assert_eq!(snapshot.time_signature.numerator, 3);
+    let beat = snapshot
+        .tempo_map
+        .first_beat_at_or_after(48_000.0, 1)
+        .expect("beat");
+    assert!((beat.tempo.beats_per_minute() - 120.0).abs() < 0.5);
+    assert_eq!(beat.sample, 24_000);
+}
+
+#[test]
+fn large_discontinuities_relock_instead_of_smoothing() {
+    let clock = clock();
+    for tick in jittered_ticks(1_000, 1_000, 200) {
+        clock.on_external_clock(tick);
+    }
+
+    // The remote side restarts at 60 BPM far from the predicted tick.
+    clock.on_external_start(500_000);
+    for index in 1..=2 {
+        clock.on_external_clock(500_000 + index * 2_000);
+    }
+    let bpm = clock.locked_tempo().expect("locked").beats_per_minute();
+    assert!((bpm - 60.0).abs() < 0.01, "{bpm}");
+}
+
+#[test]
+fn internal_clock_ignores_external_ticks() {
+    let clock = clock();
+    for tick in jittered_ticks(1_000, 1_000, 50) {
+        clock.on_external_clock(tick);
+    }
+    clock.set_clock_source(ClockSource::Internal);
+    assert_eq!(clock.load().tempo, Tempo(90.0));
+
+    clock.on_external_clock(1_000_000);
+    clock.on_external_clock(1_001_000);
+    assert_eq!(clock.locked_tempo(), None);
+    assert_eq!(clock.load().tempo, Tempo(90.0));
+}
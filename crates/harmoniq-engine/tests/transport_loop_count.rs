@@ -0,0 +1,126 @@
+use harmoniq_dsp::{AudioBlock, AudioBlockMut};
+use harmoniq_engine::dsp::events::{TransportClock, TransportCommand};
+use harmoniq_engine::dsp::{nodes::MetronomeClickNode, DspGraph, GraphProcess};
+use harmoniq_engine::{LoopRegion, Tempo, TempoMap, TimeSignature};
+
+const SAMPLE_RATE: f32 = 48_000.0;
+// 120 BPM: one beat is 24 000 samples, so 480-sample blocks divide it evenly.
+const BEAT: u64 = 24_000;
+const BLOCK: u32 = 480;
+
+fn clock() -> TransportClock {
+    let clock =
+        TransportClock::with_map(TempoMap::single(Tempo(120.0), TimeSignature::four_four()));
+    clock.set_sample_rate(SAMPLE_RATE);
+    clock
+}
+
+fn advance(clock: &TransportClock, samples: u64) {
+    for _ in 0..samples / BLOCK as u64 {
+        clock.advance_samples(BLOCK);
+    }
+}
+
+#[test]
+fn loop_plays_after_count_in_and_counts_iterations() {
+    let clock = clock();
+    let region = LoopRegion::new(2 * BEAT, 6 * BEAT);
+    clock.execute(TransportCommand::LoopWithCount {
+        region,
+        count_in_beats: 2,
+    });
+
+    let snapshot = clock.load();
+    assert!(!snapshot.is_playing);
+    assert_eq!(snapshot.sample_position, region.start);
+    assert_eq!(snapshot.count_in_remaining, 2 * BEAT);
+    assert_eq!(snapshot.loop_iteration, 0);
+
+    advance(&clock, BEAT);
+    let snapshot = clock.load();
+    assert!(!snapshot.is_playing);
+    assert_eq!(snapshot.sample_position, region.start);
+    assert_eq!(snapshot.count_in_remaining, BEAT);
+
+    advance(&clock, BEAT);
+    let snapshot = clock.load();
+    assert!(snapshot.is_playing);
+    assert_eq!(snapshot.sample_position, region.start);
+    assert_eq!(snapshot.count_in_remaining, 0);
+
+    advance(&clock, 3 * BEAT);
+    let snapshot = clock.load();
+    assert!(snapshot.is_playing);
+    assert_eq!(snapshot.sample_position, region.start + 3 * BEAT);
+
+    for iteration in 1..=3 {
+        advance(&clock, BEAT);
+        let snapshot = clock.load();
+        assert_eq!(snapshot.loop_iteration, iteration);
+        assert_eq!(snapshot.sample_position, region.start);
+        assert!(!snapshot.is_playing, "counting in before pass {iteration}");
+
+        advance(&clock, 2 * BEAT + 3 * BEAT);
+        let snapshot = clock.load();
+        assert!(snapshot.is_playing);
+        assert_eq!(snapshot.loop_iteration, iteration);
+        assert_eq!(snapshot.sample_position, region.start + 3 * BEAT);
+    }
+}
+
+#[test]
+fn stop_cancels_count_in() {
+    let clock = clock();
+    clock.execute(TransportCommand::LoopWithCount {
+        region: LoopRegion::new(0, 4 * BEAT),
+        count_in_beats: 4,
+    });
+    advance(&clock, BEAT);
+    clock.execute(TransportCommand::Stop);
+    let snapshot = clock.load();
+    assert!(!snapshot.is_playing);
+    assert_eq!(snapshot.count_in_remaining, 0);
+}
+
+#[test]
+fn metronome_clicks_during_count_in() {
+    let clock = clock();
+    clock.execute(TransportCommand::LoopWithCount {
+        region: LoopRegion::new(0, 8 * BEAT),
+        count_in_beats: 4,
+    });
+
+    let mut graph = DspGraph::new();
+    let (click_id, _) = graph.add_node(Box::new(MetronomeClickNode::default()), 0);
+    graph.set_topology(&[click_id]);
+    graph.prepare(SAMPLE_RATE, BLOCK, 0, 1);
+
+    let mut rendered = Vec::new();
+    for _ in 0..(4 * BEAT) / BLOCK as u64 {
+        let mut block = vec![0.0f32; BLOCK as usize];
+        let transport = clock.load();
+        unsafe {
+            graph.process(GraphProcess {
+                inputs: AudioBlock::empty(),
+                outputs: AudioBlockMut::from_interleaved(block.as_mut_ptr(), 1, BLOCK),
+                frames: BLOCK,
+                transport,
+                midi: &[],
+            });
+        }
+        rendered.extend_from_slice(&block);
+        clock.advance_samples(BLOCK);
+    }
+
+    let clicks: Vec<(usize, f32)> = rendered
+        .iter()
+        .enumerate()
+        .filter(|(_, sample)| sample.abs() > 1e-5)
+        .map(|(index, sample)| (index, *sample))
+        .collect();
+    let beat = BEAT as usize;
+    assert_eq!(
+        clicks,
+        vec![(0, 1.0), (beat, 0.4), (2 * beat, 0.4), (3 * beat, 0.4)]
+    );
+}
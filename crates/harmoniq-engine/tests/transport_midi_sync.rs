@@ -0,0 +1,136 @@
+use harmoniq_engine::dsp::events::{MidiEvent, TransportClock};
+use harmoniq_engine::transport::{MidiSyncGenerator, MidiSyncSettings, MtcRate};
+use harmoniq_engine::{Tempo, TempoMap, TempoSegment, TimeSignature};
+
+const SAMPLE_RATE: f32 = 48_000.0;
+const BLOCK: u32 = 512;
+
+/// Runs the clock for `blocks` blocks and returns every message with its
+/// absolute sample time.
+fn run(
+    clock: &TransportClock,
+    generator: &mut MidiSyncGenerator,
+    blocks: usize,
+) -> Vec<(u64, MidiEvent)> {
+    let mut messages = Vec::new();
+    for _ in 0..blocks {
+        let transport = clock.load();
+        let mut block = Vec::new();
+        generator.process(&transport, BLOCK, &mut block);
+        messages.extend(block.into_iter().map(|event| {
+            (
+                transport.sample_position + event.sample_offset as u64,
+                event,
+            )
+        }));
+        clock.advance_samples(BLOCK);
+    }
+    messages
+}
+
+fn ticks(messages: &[(u64, MidiEvent)]) -> Vec<u64> {
+    messages
+        .iter()
+        .filter(|(_, event)| event.data[0] == 0xF8)
+        .map(|(time, _)| *time)
+        .collect()
+}
+
+#[test]
+fn clock_runs_at_24_ticks_per_quarter_note() {
+    // 120 BPM: a quarter note is 24 000 samples, a tick 1 000.
+    let clock =
+        TransportClock::with_map(TempoMap::single(Tempo(120.0), TimeSignature::four_four()));
+    let mut generator = MidiSyncGenerator::new(SAMPLE_RATE, MidiSyncSettings::default());
+    clock.start_immediately();
+    let messages = run(&clock, &mut generator, 4 * 24_000 / BLOCK as usize + 1);
+
+    assert_eq!(messages[0].1.data[0], 0xFA);
+    assert_eq!(messages[0].1.length, 1);
+    let ticks = ticks(&messages);
+    let quarter: Vec<&u64> = ticks.iter().filter(|time| **time < 24_000).collect();
+    assert_eq!(quarter.len(), 24);
+    assert_eq!(ticks.iter().filter(|time| **time < 4 * 24_000).count(), 96);
+    for (index, time) in ticks.iter().enumerate() {
+        assert_eq!(*time, index as u64 * 1_000);
+    }
+
+    clock.stop_immediately();
+    let stopped = run(&clock, &mut generator, 2);
+    assert_eq!(stopped.len(), 1);
+    assert_eq!(stopped[0].1.data[0], 0xFC);
+}
+
+#[test]
+fn ticks_follow_tempo_changes() {
+    // Two beats at 120 BPM, then 60 BPM from sample 48 000.
+    let map = TempoMap::new(vec![
+        TempoSegment {
+            start_sample: 0,
+            tempo: Tempo(120.0),
+            time_signature: TimeSignature::four_four(),
+        },
+        TempoSegment {
+            start_sample: 48_000,
+            tempo: Tempo(60.0),
+            time_signature: TimeSignature::four_four(),
+        },
+    ]);
+    let clock = TransportClock::with_map(map);
+    let mut generator = MidiSyncGenerator::new(SAMPLE_RATE, MidiSyncSettings::default());
+    clock.start_immediately();
+    let ticks = ticks(&run(&clock, &mut generator, 200));
+
+    assert_eq!(ticks[47], 47_000);
+    assert_eq!(ticks[48], 48_000);
+    assert_eq!(ticks[49], 50_000);
+    assert_eq!(ticks[60], 72_000);
+}
+
+#[test]
+fn relocated_playback_sends_song_position_and_continue() {
+    let clock =
+        TransportClock::with_map(TempoMap::single(Tempo(120.0), TimeSignature::four_four()));
+    let mut generator = MidiSyncGenerator::new(SAMPLE_RATE, MidiSyncSettings::default());
+    // Beat 4 is sixteenth 16.
+    clock.seek(4 * 24_000);
+    clock.start_immediately();
+    let messages = run(&clock, &mut generator, 1);
+
+    assert_eq!(messages[0].1.data, [0xF2, 16, 0]);
+    assert_eq!(messages[0].1.length, 3);
+    assert_eq!(messages[1].1.data[0], 0xFB);
+    assert_eq!(messages[2].0, 4 * 24_000);
+    assert_eq!(messages[2].1.data[0], 0xF8);
+}
+
+#[test]
+fn quarter_frames_encode_the_timecode() {
+    let clock = TransportClock::new();
+    let settings = MidiSyncSettings {
+        clock: false,
+        mtc: Some(MtcRate::Fps25),
+    };
+    let mut generator = MidiSyncGenerator::new(SAMPLE_RATE, settings);
+    // One second and one frame in.
+    let start = 48_000 + 1_920;
+    clock.seek(start);
+    clock.start_immediately();
+    let messages = run(&clock, &mut generator, 94);
+
+    // 25 fps sends 100 quarter frames a second, one every 480 samples.
+    let second = messages
+        .iter()
+        .filter(|(time, _)| *time < start + 48_000)
+        .count();
+    assert_eq!(second, 100);
+    assert!(messages
+        .iter()
+        .all(|(time, event)| event.data[0] == 0xF1 && event.length == 2 && time % 480 == 0));
+    let pieces: Vec<u8> = messages[..8]
+        .iter()
+        .map(|(_, event)| event.data[1])
+        .collect();
+    // Frame 1, second 1, minute 0, hour 0 at the 25 fps rate code.
+    assert_eq!(pieces, [0x01, 0x10, 0x21, 0x30, 0x40, 0x50, 0x60, 0x72]);
+}
@@ -123,18 +123,44 @@ impl PluginBroker {
             .context("failed to request state dump")
     }
 
+    pub fn load_state(&self, data: &[u8]) -> Result<()> {
+        self.client
+            .send(&BrokerCommand::LoadState {
+                data: data.to_vec(),
+            })
+            .context("failed to send plugin state")
+    }
+
     pub fn request_preset_dump(&self) -> Result<()> {
         self.client
             .send(&BrokerCommand::RequestPresetDump)
             .context("failed to request preset dump")
     }
 
+    pub fn describe_plugin(&self, path: impl AsRef<Path>, timeout: Duration) -> Result<()> {
+        self.client
+            .send(&BrokerCommand::DescribePlugin {
+                path: path.as_ref().to_path_buf(),
+                timeout,
+            })
+            .context("failed to request plugin descriptor")
+    }
+
     pub fn kill_plugin(&self) -> Result<()> {
         self.client
             .send(&BrokerCommand::KillPlugin)
             .context("failed to send kill command")
     }
 
+    /// Kills the broker process itself, for when it stops responding.
+    pub fn kill(&mut self) {
+        let _ = self.child.kill();
+    }
+
+    pub fn has_exited(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+
     pub fn shutdown(&mut self) -> Result<()> {
         let _ = self.client.send(&BrokerCommand::Shutdown);
         match self.child.wait() {
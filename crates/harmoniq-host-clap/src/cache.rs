@@ -1,13 +1,50 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::io::{self, Read};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
+use crate::broker::{BrokerConfig, PluginBroker};
+use crate::ipc::BrokerEvent;
+
+/// Timed-out or crashed scans in a row after which a plugin is blacklisted.
+pub const BLACKLIST_AFTER_FAILURES: u32 = 2;
+
+/// Extra time the broker gets to report back after the plugin's own timeout.
+const BROKER_GRACE: Duration = Duration::from_secs(2);
+
+/// Result of querying a plugin's descriptor.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ScanOutcome {
+    /// The descriptor was read; the entry holds its name and version.
+    #[default]
+    Ok,
+    TimedOut,
+    Crashed {
+        code: Option<i32>,
+    },
+    Incompatible {
+        reason: String,
+    },
+}
+
+impl ScanOutcome {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Self::Ok)
+    }
+
+    /// Whether the plugin misbehaved while being probed. An incompatible
+    /// file is skipped on its own merits and never counts toward the
+    /// blacklist.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Self::TimedOut | Self::Crashed { .. })
+    }
+}
+
 /// Metadata describing a single CLAP plugin discovered during a scan.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PluginCacheEntry {
@@ -15,6 +52,15 @@ pub struct PluginCacheEntry {
     pub name: String,
     pub version: Option<String>,
     pub last_modified: SystemTime,
+    #[serde(default)]
+    pub outcome: ScanOutcome,
+    /// Timed-out or crashed scans in a row.
+    #[serde(default)]
+    pub failures: u32,
+    /// File size in bytes; with `last_modified`, tells rescans whether the
+    /// plugin changed.
+    #[serde(default)]
+    pub size: u64,
 }
 
 impl PluginCacheEntry {
@@ -29,15 +75,58 @@ impl PluginCacheEntry {
             name,
             version,
             last_modified,
+            outcome: ScanOutcome::Ok,
+            failures: 0,
+            size: 0,
         }
     }
 }
 
+/// What a rescan changed in the cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RescanSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    /// Plugins reused from the cache without probing.
+    pub unchanged: usize,
+}
+
+impl RescanSummary {
+    /// Plugins whose descriptor was queried.
+    pub fn probed(&self) -> usize {
+        self.added + self.updated
+    }
+}
+
+/// On-disk layout of the scan cache.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    #[serde(default)]
+    plugins: Vec<PluginCacheEntry>,
+    /// Plugins skipped by scans until [`PluginScanner::retry`] clears them.
+    #[serde(default)]
+    blacklist: BTreeSet<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct ScanCache {
+    plugins: HashMap<PathBuf, PluginCacheEntry>,
+    blacklist: BTreeSet<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+struct ScanIsolation {
+    broker: BrokerConfig,
+    timeout: Duration,
+}
+
 /// Handles discovery of CLAP plugins with a persistent cache to avoid redundant filesystem work.
 #[derive(Debug, Clone)]
 pub struct PluginScanner {
     cache_path: PathBuf,
     extension: String,
+    isolation: Option<ScanIsolation>,
 }
 
 impl PluginScanner {
@@ -45,14 +134,59 @@ impl PluginScanner {
         Self {
             cache_path: cache_path.into(),
             extension: ".clap".to_owned(),
+            isolation: None,
         }
     }
 
+    /// Queries each plugin's descriptor through a broker process of its own,
+    /// so a plugin that hangs or crashes only costs `timeout` and its own
+    /// entry.
+    pub fn with_broker(mut self, broker: BrokerConfig, timeout: Duration) -> Self {
+        self.isolation = Some(ScanIsolation { broker, timeout });
+        self
+    }
+
     /// Perform a scan of the provided directories, returning the cached entries if they are
     /// still valid, otherwise rescanning the filesystem and updating the cache.
+    ///
+    /// Plugins that timed out or crashed are probed again on every scan until
+    /// they fail [`BLACKLIST_AFTER_FAILURES`] times in a row; blacklisted
+    /// plugins keep their last entry and are not probed again. Incompatible
+    /// files are probed again but never blacklisted.
     pub fn scan_directories(&self, roots: &[PathBuf]) -> Result<Vec<PluginCacheEntry>> {
+        Ok(self.scan(roots, false)?.0)
+    }
+
+    /// Rescans `roots`, probing only plugins that are new or whose
+    /// modification time or size changed, and dropping entries for files that
+    /// are gone.
+    pub fn rescan_incremental(&self, roots: &[PathBuf]) -> Result<RescanSummary> {
+        self.rescan(roots, false)
+    }
+
+    /// Like [`rescan_incremental`](Self::rescan_incremental), but with
+    /// `force` every plugin is probed again. Blacklisted plugins stay
+    /// skipped either way.
+    pub fn rescan(&self, roots: &[PathBuf], force: bool) -> Result<RescanSummary> {
+        Ok(self.scan(roots, force)?.1)
+    }
+
+    /// Entries recorded by previous scans.
+    pub fn cached_entries(&self) -> Result<Vec<PluginCacheEntry>> {
+        let mut entries: Vec<_> = self.load_cache()?.plugins.into_values().collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
+    fn scan(
+        &self,
+        roots: &[PathBuf],
+        force: bool,
+    ) -> Result<(Vec<PluginCacheEntry>, RescanSummary)> {
         let mut cache = self.load_cache().unwrap_or_default();
         let mut result = Vec::new();
+        let mut summary = RescanSummary::default();
+        let mut seen = HashSet::new();
 
         for root in roots {
             for entry in WalkDir::new(root) {
@@ -69,24 +203,114 @@ impl PluginScanner {
                 {
                     let metadata = fs::metadata(entry.path())?;
                     let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    let size = metadata.len();
                     let path = entry.path().to_path_buf();
+                    seen.insert(path.clone());
 
-                    if let Some(existing) = cache.get(entry.path()) {
-                        if existing.last_modified >= modified {
+                    let previous = cache.plugins.get(entry.path());
+                    if let Some(existing) = previous {
+                        let unchanged = existing.outcome.is_ok()
+                            && existing.last_modified == modified
+                            && existing.size == size;
+                        if cache.blacklist.contains(entry.path()) || (unchanged && !force) {
+                            summary.unchanged += 1;
                             result.push(existing.clone());
                             continue;
                         }
+                        summary.updated += 1;
+                    } else {
+                        summary.added += 1;
                     }
 
-                    let descriptor = self.parse_descriptor(entry.path(), modified)?;
-                    cache.insert(path.clone(), descriptor.clone());
+                    let mut descriptor = self.probe(entry.path(), modified)?;
+                    descriptor.size = size;
+                    if descriptor.outcome.is_failure() {
+                        descriptor.failures = previous.map_or(0, |entry| entry.failures) + 1;
+                        if descriptor.failures >= BLACKLIST_AFTER_FAILURES {
+                            tracing::warn!(
+                                "blacklisting plugin {:?} after {} failed scans",
+                                path,
+                                descriptor.failures
+                            );
+                            cache.blacklist.insert(path.clone());
+                        }
+                    }
+                    cache.plugins.insert(path.clone(), descriptor.clone());
                     result.push(descriptor);
                 }
             }
         }
 
+        let removed: Vec<_> = cache
+            .plugins
+            .keys()
+            .filter(|path| !seen.contains(*path) && roots.iter().any(|root| path.starts_with(root)))
+            .cloned()
+            .collect();
+        for path in removed {
+            cache.plugins.remove(&path);
+            cache.blacklist.remove(&path);
+            summary.removed += 1;
+        }
+
+        self.save_cache(&cache)?;
+        Ok((result, summary))
+    }
+
+    /// Plugins currently skipped by scans.
+    pub fn blacklist(&self) -> Result<Vec<PathBuf>> {
+        Ok(self.load_cache()?.blacklist.into_iter().collect())
+    }
+
+    /// Takes `path` off the blacklist so the next scan probes it again.
+    /// Returns whether it was blacklisted.
+    pub fn retry(&self, path: impl AsRef<Path>) -> Result<bool> {
+        let path = path.as_ref();
+        let mut cache = self.load_cache()?;
+        if !cache.blacklist.remove(path) {
+            return Ok(false);
+        }
+        if let Some(entry) = cache.plugins.get_mut(path) {
+            entry.failures = 0;
+        }
         self.save_cache(&cache)?;
-        Ok(result)
+        Ok(true)
+    }
+
+    fn probe(&self, path: &Path, modified: SystemTime) -> Result<PluginCacheEntry> {
+        let Some(isolation) = &self.isolation else {
+            return self.parse_descriptor(path, modified);
+        };
+        let mut entry = self.parse_descriptor(path, modified)?;
+        let mut broker = PluginBroker::spawn(isolation.broker.clone())
+            .context("failed to spawn broker for plugin scan")?;
+        broker.describe_plugin(path, isolation.timeout)?;
+        let outcome = loop {
+            match broker.recv_event(isolation.timeout + BROKER_GRACE) {
+                Some(BrokerEvent::PluginDescribed { name, version }) => {
+                    entry.name = name;
+                    entry.version = version;
+                    break ScanOutcome::Ok;
+                }
+                Some(BrokerEvent::DescribeTimedOut) => break ScanOutcome::TimedOut,
+                Some(BrokerEvent::PluginCrashed { code }) => break ScanOutcome::Crashed { code },
+                Some(BrokerEvent::PluginIncompatible { reason }) => {
+                    break ScanOutcome::Incompatible { reason }
+                }
+                Some(_) => continue,
+                // The broker itself went down or stopped answering.
+                None if broker.has_exited() => break ScanOutcome::Crashed { code: None },
+                None => {
+                    broker.kill();
+                    break ScanOutcome::TimedOut;
+                }
+            }
+        };
+        if !outcome.is_ok() {
+            tracing::warn!("plugin scan of {:?} failed: {:?}", path, outcome);
+        }
+        entry.outcome = outcome;
+        Ok(entry)
     }
 
     fn parse_descriptor(&self, path: &Path, modified: SystemTime) -> Result<PluginCacheEntry> {
@@ -103,32 +327,39 @@ impl PluginScanner {
         ))
     }
 
-    fn load_cache(&self) -> Result<HashMap<PathBuf, PluginCacheEntry>> {
+    fn load_cache(&self) -> Result<ScanCache> {
         if !self.cache_path.exists() {
-            return Ok(HashMap::new());
+            return Ok(ScanCache::default());
         }
         let mut file = fs::File::open(&self.cache_path)
             .with_context(|| format!("Failed to open cache at {:?}", self.cache_path))?;
         let mut buf = Vec::new();
         file.read_to_end(&mut buf)?;
         if buf.is_empty() {
-            return Ok(HashMap::new());
+            return Ok(ScanCache::default());
         }
-        let raw: Vec<PluginCacheEntry> = serde_json::from_slice(&buf)
+        let raw: CacheFile = serde_json::from_slice(&buf)
             .with_context(|| format!("Failed to deserialize cache {:?}", self.cache_path))?;
-        Ok(raw
-            .into_iter()
-            .map(|entry| (entry.path.clone(), entry))
-            .collect())
+        Ok(ScanCache {
+            plugins: raw
+                .plugins
+                .into_iter()
+                .map(|entry| (entry.path.clone(), entry))
+                .collect(),
+            blacklist: raw.blacklist,
+        })
     }
 
-    fn save_cache(&self, cache: &HashMap<PathBuf, PluginCacheEntry>) -> Result<()> {
+    fn save_cache(&self, cache: &ScanCache) -> Result<()> {
         if let Some(parent) = self.cache_path.parent() {
             fs::create_dir_all(parent)?;
         }
-        let mut entries: Vec<_> = cache.values().cloned().collect();
-        entries.sort_by(|a, b| a.path.cmp(&b.path));
-        let data = serde_json::to_vec_pretty(&entries)?;
+        let mut plugins: Vec<_> = cache.plugins.values().cloned().collect();
+        plugins.sort_by(|a, b| a.path.cmp(&b.path));
+        let data = serde_json::to_vec_pretty(&CacheFile {
+            plugins,
+            blacklist: cache.blacklist.clone(),
+        })?;
         fs::write(&self.cache_path, data)?;
         Ok(())
     }
@@ -14,6 +14,8 @@ pub struct HostOptions {
     pub cache_path: PathBuf,
     pub broker: BrokerConfig,
     pub event_timeout: Duration,
+    /// How long a plugin may take to describe itself during a scan.
+    pub scan_timeout: Duration,
 }
 
 impl Default for HostOptions {
@@ -23,6 +25,7 @@ impl Default for HostOptions {
             cache_path: std::env::temp_dir().join("harmoniq-clap-cache.json"),
             broker: BrokerConfig::default(),
             event_timeout: Duration::from_millis(250),
+            scan_timeout: Duration::from_secs(5),
         }
     }
 }
@@ -38,7 +41,8 @@ pub struct ClapHost {
 
 impl ClapHost {
     pub fn new(options: HostOptions) -> Result<Self> {
-        let scanner = PluginScanner::new(&options.cache_path);
+        let scanner = PluginScanner::new(&options.cache_path)
+            .with_broker(options.broker.clone(), options.scan_timeout);
         let broker = PluginBroker::spawn(options.broker.clone())?;
         Ok(Self {
             scanner,
@@ -85,10 +89,24 @@ impl ClapHost {
     pub fn request_state(&mut self) -> Result<Vec<u8>> {
         self.broker.request_state_dump()?;
         self.wait_for_event(|event| match event {
-            BrokerEvent::StateDump { data } => Some(data.clone()),
+            BrokerEvent::StateDump { data } => Some(Ok(data.clone())),
+            BrokerEvent::StateFailed { reason } => Some(Err(reason.clone())),
             _ => None,
         })?
-        .ok_or_else(|| anyhow!("broker did not provide a state dump"))
+        .ok_or_else(|| anyhow!("broker did not provide a state dump"))?
+        .map_err(|reason| anyhow!("plugin failed to save its state: {reason}"))
+    }
+
+    /// Restores a blob returned by [`request_state`](Self::request_state).
+    pub fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        self.broker.load_state(data)?;
+        self.wait_for_event(|event| match event {
+            BrokerEvent::StateLoaded => Some(Ok(())),
+            BrokerEvent::StateFailed { reason } => Some(Err(reason.clone())),
+            _ => None,
+        })?
+        .ok_or_else(|| anyhow!("broker did not confirm the state load"))?
+        .map_err(|reason| anyhow!("plugin rejected its state: {reason}"))
     }
 
     pub fn request_preset(&mut self) -> Result<Vec<u8>> {
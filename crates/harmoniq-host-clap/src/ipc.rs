@@ -1,7 +1,7 @@
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
 use crossbeam_channel::{unbounded, Receiver, Sender};
@@ -21,21 +21,55 @@ pub enum BrokerCommand {
         frames: u32,
     },
     RequestState,
+    /// Hands a blob from a [`BrokerEvent::StateDump`] back to the plugin.
+    LoadState {
+        data: Vec<u8>,
+    },
     RequestPresetDump,
     RegisterRtChannel,
     Shutdown,
     KillPlugin,
+    /// Runs the plugin with `HARMONIQ_PLUGIN_DESCRIBE=1` set. It should
+    /// print a JSON `{ "name": .., "version": .. }` descriptor to stdout and
+    /// exit within `timeout`.
+    DescribePlugin {
+        path: PathBuf,
+        timeout: Duration,
+    },
 }
 
 /// Events delivered from the broker back to the host.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum BrokerEvent {
     Acknowledge,
-    PluginLoaded { name: String },
-    PluginCrashed { code: Option<i32> },
-    AudioProcessed { frames: u32 },
-    StateDump { data: Vec<u8> },
-    PresetDump { data: Vec<u8> },
+    PluginLoaded {
+        name: String,
+    },
+    PluginCrashed {
+        code: Option<i32>,
+    },
+    AudioProcessed {
+        frames: u32,
+    },
+    StateDump {
+        data: Vec<u8>,
+    },
+    StateLoaded,
+    /// The plugin could not save its state or rejected a blob.
+    StateFailed {
+        reason: String,
+    },
+    PresetDump {
+        data: Vec<u8>,
+    },
+    PluginDescribed {
+        name: String,
+        version: Option<String>,
+    },
+    DescribeTimedOut,
+    PluginIncompatible {
+        reason: String,
+    },
 }
 
 /// Real-time safe message categories exchanged over the RT channel.
@@ -10,10 +10,13 @@ pub mod host;
 pub mod ipc;
 pub mod pdc;
 pub mod ring;
+pub mod runtime;
 pub mod window;
 
 pub use broker::{BrokerConfig, PluginBroker};
-pub use cache::{PluginCacheEntry, PluginScanner};
+pub use cache::{
+    PluginCacheEntry, PluginScanner, RescanSummary, ScanOutcome, BLACKLIST_AFTER_FAILURES,
+};
 pub use host::{ClapHost, HostOptions};
 pub use ipc::{BrokerCommand, BrokerEvent, RtMessage, RtMessageKind};
 pub use ring::{SharedAudioRing, SharedAudioRingDescriptor};
@@ -0,0 +1,342 @@
+//! The broker side of the IPC protocol, served by
+//! `harmoniq-host-clap-broker`.
+//!
+//! A CLAP library is instantiated inside the broker process, so a plug-in
+//! that crashes takes the broker down rather than the host. Anything else
+//! is run as a standalone plug-in process that shares the audio ring.
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use clap_host::{ClapInstance, ClapLibrary, PluginDiscovery, HARMONIQ_HOST};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::ipc::{BrokerCommand, BrokerEvent, IpcTransport};
+use crate::ring::SharedAudioRingDescriptor;
+
+/// Answers broker commands read from `reader` on `writer` until the host
+/// asks the broker to shut down.
+pub fn serve<R, W>(reader: R, writer: W) -> Result<()>
+where
+    R: Read + 'static,
+    W: Write + 'static,
+{
+    let mut transport = IpcTransport::new(reader, writer);
+    let mut runtime = BrokerRuntime::default();
+
+    loop {
+        let cmd: BrokerCommand = transport.recv()?;
+        let continue_running = runtime.handle_command(&mut transport, cmd)?;
+        runtime.poll_plugin(&mut transport)?;
+        if !continue_running {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// The first plug-in of a CLAP library loaded into the broker.
+struct HostedClap {
+    // Declared before the library so the instance is destroyed first.
+    instance: ClapInstance,
+    _library: ClapLibrary,
+    name: String,
+}
+
+impl HostedClap {
+    fn load(path: &Path) -> Result<Self> {
+        let library = unsafe { ClapLibrary::load(path) }?;
+        let factory = library.factory()?;
+        let descriptor = PluginDiscovery::new(factory)
+            .list()
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("{} contains no plug-ins", path.display()))?;
+        let instance =
+            unsafe { ClapInstance::create(factory, &descriptor, HARMONIQ_HOST.as_ptr()) }?;
+        Ok(Self {
+            instance,
+            _library: library,
+            name: descriptor.name,
+        })
+    }
+
+    fn save_state(&self) -> BrokerEvent {
+        match unsafe { self.instance.save_state() } {
+            Ok(data) => BrokerEvent::StateDump { data },
+            Err(err) => BrokerEvent::StateFailed {
+                reason: err.to_string(),
+            },
+        }
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> BrokerEvent {
+        match unsafe { self.instance.load_state(data) } {
+            Ok(()) => BrokerEvent::StateLoaded,
+            Err(err) => BrokerEvent::StateFailed {
+                reason: err.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct BrokerRuntime {
+    plugin: Option<Child>,
+    clap: Option<HostedClap>,
+    ring: Option<SharedAudioRingDescriptor>,
+    last_state: Option<Vec<u8>>,
+    last_preset: Option<Vec<u8>>,
+}
+
+impl BrokerRuntime {
+    fn handle_command<R, W>(
+        &mut self,
+        transport: &mut IpcTransport<R, W>,
+        cmd: BrokerCommand,
+    ) -> Result<bool>
+    where
+        R: Read + 'static,
+        W: Write + 'static,
+    {
+        match cmd {
+            BrokerCommand::Hello => {
+                transport.send(&BrokerEvent::Acknowledge)?;
+            }
+            BrokerCommand::LoadPlugin { path, audio_ring } => {
+                self.load_plugin(&path, audio_ring, transport)?;
+            }
+            BrokerCommand::ProcessBlock { frames } => {
+                transport.send(&BrokerEvent::AudioProcessed { frames })?;
+            }
+            BrokerCommand::RequestState => {
+                let event = match &self.clap {
+                    Some(clap) => clap.save_state(),
+                    None => BrokerEvent::StateDump {
+                        data: self
+                            .snapshot_ring()
+                            .or_else(|| self.last_state.clone())
+                            .unwrap_or_default(),
+                    },
+                };
+                transport.send(&event)?;
+            }
+            BrokerCommand::LoadState { data } => {
+                let event = match &mut self.clap {
+                    Some(clap) => clap.load_state(&data),
+                    None => {
+                        self.last_state = Some(data);
+                        BrokerEvent::StateLoaded
+                    }
+                };
+                transport.send(&event)?;
+            }
+            BrokerCommand::RequestPresetDump => {
+                let preset = self.last_preset.clone().unwrap_or_default();
+                transport.send(&BrokerEvent::PresetDump { data: preset })?;
+            }
+            BrokerCommand::KillPlugin => {
+                if let Some(mut child) = self.plugin.take() {
+                    let _ = child.kill();
+                    transport.send(&BrokerEvent::PluginCrashed { code: None })?;
+                }
+                if self.clap.take().is_some() {
+                    transport.send(&BrokerEvent::PluginCrashed { code: None })?;
+                }
+            }
+            BrokerCommand::Shutdown => {
+                if let Some(mut child) = self.plugin.take() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                self.clap = None;
+                return Ok(false);
+            }
+            BrokerCommand::RegisterRtChannel => {
+                transport.send(&BrokerEvent::Acknowledge)?;
+            }
+            BrokerCommand::DescribePlugin { path, timeout } => {
+                transport.send(&describe_plugin(&path, timeout))?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn poll_plugin<R, W>(&mut self, transport: &mut IpcTransport<R, W>) -> Result<()>
+    where
+        R: Read + 'static,
+        W: Write + 'static,
+    {
+        if let Some(child) = self.plugin.as_mut() {
+            if let Some(status) = child.try_wait()? {
+                let code = status.code();
+                transport.send(&BrokerEvent::PluginCrashed { code })?;
+                self.plugin = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn load_plugin<R, W>(
+        &mut self,
+        path: &Path,
+        audio_ring: SharedAudioRingDescriptor,
+        transport: &mut IpcTransport<R, W>,
+    ) -> Result<()>
+    where
+        R: Read + 'static,
+        W: Write + 'static,
+    {
+        self.clap = None;
+        self.ring = Some(audio_ring.clone());
+        match HostedClap::load(path) {
+            Ok(clap) => {
+                let name = clap.name.clone();
+                self.clap = Some(clap);
+                transport.send(&BrokerEvent::PluginLoaded { name })?;
+                return Ok(());
+            }
+            Err(err) => debug!("running {:?} as a plugin process: {err:#}", path),
+        }
+
+        let mut command = Command::new(path);
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::inherit())
+            .env("HARMONIQ_PLUGIN_RING_PATH", &audio_ring.path)
+            .env("HARMONIQ_PLUGIN_RING_FRAMES", audio_ring.frames.to_string())
+            .env(
+                "HARMONIQ_PLUGIN_RING_CHANNELS",
+                audio_ring.channels.to_string(),
+            );
+
+        let child = command
+            .spawn()
+            .with_context(|| format!("failed to spawn plugin at {:?}", path))?;
+        self.plugin = Some(child);
+        transport.send(&BrokerEvent::PluginLoaded {
+            name: path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("Plugin")
+                .to_string(),
+        })?;
+        Ok(())
+    }
+
+    fn snapshot_ring(&mut self) -> Option<Vec<u8>> {
+        let descriptor = self.ring.clone()?;
+        match descriptor.read_latest_block() {
+            Ok((data, _generation)) => {
+                let mut bytes = Vec::with_capacity(data.len() * std::mem::size_of::<f32>());
+                for sample in data {
+                    bytes.extend_from_slice(&sample.to_ne_bytes());
+                }
+                self.last_state = Some(bytes.clone());
+                Some(bytes)
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PluginDescription {
+    name: String,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+/// Reads the descriptor of the first plug-in in a CLAP library, or asks a
+/// standalone plug-in process for its own when `path` is not a library.
+fn describe_plugin(path: &Path, timeout: Duration) -> BrokerEvent {
+    match describe_library(path) {
+        Ok(event) => return event,
+        Err(err) => debug!("describing {:?} as a plugin process: {err:#}", path),
+    }
+    describe_process(path, timeout)
+}
+
+fn describe_library(path: &Path) -> Result<BrokerEvent> {
+    let library = unsafe { ClapLibrary::load(path) }?;
+    let factory = library.factory()?;
+    let event = match PluginDiscovery::new(factory).list().into_iter().next() {
+        Some(descriptor) => BrokerEvent::PluginDescribed {
+            name: descriptor.name,
+            version: Some(descriptor.version).filter(|version| !version.is_empty()),
+        },
+        None => BrokerEvent::PluginIncompatible {
+            reason: format!("{} contains no plug-ins", path.display()),
+        },
+    };
+    Ok(event)
+}
+
+fn describe_process(path: &Path, timeout: Duration) -> BrokerEvent {
+    let mut child = match Command::new(path)
+        .env("HARMONIQ_PLUGIN_DESCRIBE", "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            return BrokerEvent::PluginIncompatible {
+                reason: format!("failed to spawn plugin: {err}"),
+            }
+        }
+    };
+
+    // Drain stdout on the side so a chatty plugin cannot fill the pipe and
+    // stall before exiting.
+    let mut stdout = child.stdout.take();
+    let reader = thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(stdout) = stdout.as_mut() {
+            let _ = stdout.read_to_string(&mut output);
+        }
+        output
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return BrokerEvent::DescribeTimedOut;
+            }
+            Err(err) => {
+                return BrokerEvent::PluginIncompatible {
+                    reason: format!("failed to wait for plugin: {err}"),
+                }
+            }
+        }
+    };
+    if !status.success() {
+        return BrokerEvent::PluginCrashed {
+            code: status.code(),
+        };
+    }
+
+    let output = reader.join().unwrap_or_default();
+    match serde_json::from_str::<PluginDescription>(output.trim()) {
+        Ok(description) => BrokerEvent::PluginDescribed {
+            name: description.name,
+            version: description.version,
+        },
+        Err(err) => BrokerEvent::PluginIncompatible {
+            reason: format!("invalid plugin descriptor: {err}"),
+        },
+    }
+}
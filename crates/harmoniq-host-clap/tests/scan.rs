@@ -0,0 +1,176 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use harmoniq_host_clap::{
+    BrokerConfig, PluginScanner, RescanSummary, ScanOutcome, BLACKLIST_AFTER_FAILURES,
+};
+use tempfile::TempDir;
+
+fn broker_config() -> BrokerConfig {
+    BrokerConfig {
+        executable: PathBuf::from(env!("CARGO_BIN_EXE_harmoniq-host-clap-broker")),
+        ..BrokerConfig::default()
+    }
+}
+
+fn scanner(dir: &TempDir) -> PluginScanner {
+    PluginScanner::new(dir.path().join("cache.json"))
+        .with_broker(broker_config(), Duration::from_millis(200))
+}
+
+/// Installs the fake plugin, which never answers a descriptor query and
+/// sleeps forever.
+fn install_sleeper(root: &Path) -> PathBuf {
+    let path = root.join("sleeper.clap");
+    fs::copy(env!("CARGO_BIN_EXE_harmoniq-host-clap-fake-plugin"), &path).expect("copy plugin");
+    path
+}
+
+#[cfg(unix)]
+fn install_script(root: &Path, name: &str, body: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = root.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{body}\n")).expect("write script");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("chmod");
+    path
+}
+
+#[test]
+fn hanging_plugin_is_marked_timed_out() {
+    let dir = TempDir::new().expect("temp dir");
+    let root = dir.path().join("plugins");
+    fs::create_dir_all(&root).expect("plugin root");
+    let sleeper = install_sleeper(&root);
+
+    let entries = scanner(&dir).scan_directories(&[root]).expect("scan");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].path, sleeper);
+    assert_eq!(entries[0].outcome, ScanOutcome::TimedOut);
+}
+
+#[test]
+fn repeat_offenders_are_blacklisted_until_retried() {
+    let dir = TempDir::new().expect("temp dir");
+    let root = dir.path().join("plugins");
+    fs::create_dir_all(&root).expect("plugin root");
+    let sleeper = install_sleeper(&root);
+    let scanner = scanner(&dir);
+    let roots = [root];
+
+    for _ in 0..BLACKLIST_AFTER_FAILURES {
+        scanner.scan_directories(&roots).expect("scan");
+    }
+    assert_eq!(
+        scanner.blacklist().expect("blacklist"),
+        vec![sleeper.clone()]
+    );
+    let json = fs::read_to_string(dir.path().join("cache.json")).expect("cache");
+    let cache: serde_json::Value = serde_json::from_str(&json).expect("cache json");
+    assert_eq!(cache["blacklist"].as_array().map(Vec::len), Some(1));
+
+    // Skipped: the failure count stays where blacklisting left it.
+    let entries = scanner.scan_directories(&roots).expect("scan");
+    assert_eq!(entries[0].failures, BLACKLIST_AFTER_FAILURES);
+
+    assert!(scanner.retry(&sleeper).expect("retry"));
+    assert!(scanner.blacklist().expect("blacklist").is_empty());
+    let entries = scanner.scan_directories(&roots).expect("scan");
+    assert_eq!(entries[0].outcome, ScanOutcome::TimedOut);
+    assert_eq!(entries[0].failures, 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn scan_continues_past_failing_plugins() {
+    let dir = TempDir::new().expect("temp dir");
+    let root = dir.path().join("plugins");
+    fs::create_dir_all(&root).expect("plugin root");
+    let good = install_script(
+        &root,
+        "good.clap",
+        r#"echo '{"name": "Good Synth", "version": "1.2.0"}'"#,
+    );
+    let crashing = install_script(&root, "crashing.clap", "exit 3");
+    let library = root.join("library.clap");
+    fs::write(&library, b"not an executable").expect("write library");
+
+    let entries = scanner(&dir).scan_directories(&[root]).expect("scan");
+    let outcome = |path: &Path| {
+        entries
+            .iter()
+            .find(|entry| entry.path == path)
+            .expect("entry")
+            .clone()
+    };
+    let good = outcome(&good);
+    assert_eq!(good.outcome, ScanOutcome::Ok);
+    assert_eq!(
+        (good.name.as_str(), good.version.as_deref()),
+        ("Good Synth", Some("1.2.0"))
+    );
+    assert_eq!(
+        outcome(&crashing).outcome,
+        ScanOutcome::Crashed { code: Some(3) }
+    );
+    assert!(matches!(
+        outcome(&library).outcome,
+        ScanOutcome::Incompatible { .. }
+    ));
+}
+
+#[test]
+fn incremental_rescan_only_probes_changed_plugins() {
+    let dir = TempDir::new().expect("temp dir");
+    let root = dir.path().join("plugins");
+    fs::create_dir_all(&root).expect("plugin root");
+    fs::write(root.join("first.clap"), b"first").expect("write plugin");
+    fs::write(root.join("second.clap"), b"second").expect("write plugin");
+    let scanner = PluginScanner::new(dir.path().join("cache.json"));
+    let roots = [root.clone()];
+
+    let first = scanner.rescan_incremental(&roots).expect("scan");
+    assert_eq!(
+        first,
+        RescanSummary {
+            added: 2,
+            ..RescanSummary::default()
+        }
+    );
+
+    let second = scanner.rescan_incremental(&roots).expect("rescan");
+    assert_eq!(second.probed(), 0);
+    assert_eq!(second.unchanged, 2);
+
+    fs::write(root.join("first.clap"), b"first, rebuilt").expect("rewrite plugin");
+    fs::remove_file(root.join("second.clap")).expect("remove plugin");
+    let third = scanner.rescan_incremental(&roots).expect("rescan");
+    assert_eq!((third.updated, third.removed, third.probed()), (1, 1, 1));
+    let entries = scanner.cached_entries().expect("entries");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].size, b"first, rebuilt".len() as u64);
+
+    let forced = scanner.rescan(&roots, true).expect("forced rescan");
+    assert_eq!(forced.probed(), 1);
+}
+
+#[test]
+fn incompatible_plugins_are_never_blacklisted() {
+    let dir = TempDir::new().expect("temp dir");
+    let root = dir.path().join("plugins");
+    fs::create_dir_all(&root).expect("plugin root");
+    fs::write(root.join("library.clap"), b"not a plugin").expect("write library");
+    let scanner = scanner(&dir);
+    let roots = [root];
+
+    for _ in 0..=BLACKLIST_AFTER_FAILURES {
+        let entries = scanner.scan_directories(&roots).expect("scan");
+        assert!(matches!(
+            entries[0].outcome,
+            ScanOutcome::Incompatible { .. }
+        ));
+        assert_eq!(entries[0].failures, 0);
+    }
+    assert!(scanner.blacklist().expect("blacklist").is_empty());
+}
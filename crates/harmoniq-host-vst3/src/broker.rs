@@ -124,6 +124,14 @@ impl PluginBroker {
             .context("failed to request state dump")
     }
 
+    pub fn load_state(&self, data: &[u8]) -> Result<()> {
+        self.client
+            .send(&BrokerCommand::LoadState {
+                data: data.to_vec(),
+            })
+            .context("failed to send plugin state")
+    }
+
     pub fn request_preset_dump(&self) -> Result<()> {
         self.client
             .send(&BrokerCommand::RequestPresetDump)
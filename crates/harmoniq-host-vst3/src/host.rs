@@ -1,12 +1,12 @@
 use std::path::Path;
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 
 use crate::adapter::{AdapterDescriptor, SandboxRequest};
 use crate::broker::{BrokerConfig, PluginBroker};
-use crate::ipc::{BrokerEvent, RtChannel, RtMessage};
+use crate::ipc::{BlockEvent, BrokerEvent, RtChannel, RtMessage};
 use crate::pdc::{PdcEvent, PluginDataCache};
 use crate::ring::SharedAudioRing;
 use crate::window::WindowEmbedder;
@@ -17,7 +17,14 @@ pub trait SandboxBroker {
     fn audio_ring_mut(&mut self) -> &mut SharedAudioRing;
     fn load_plugin(&mut self, request: SandboxRequest) -> Result<()>;
     fn process_block(&mut self, frames: u32) -> Result<()>;
+    /// Publishes the block's events in the shared ring, then requests processing.
+    /// Events that overflow the ring's event region are dropped and counted there.
+    fn process_block_with_events(&mut self, frames: u32, events: &[BlockEvent]) -> Result<()> {
+        self.audio_ring_mut().write_events(events)?;
+        self.process_block(frames)
+    }
     fn request_state_dump(&mut self) -> Result<()>;
+    fn load_state(&mut self, data: &[u8]) -> Result<()>;
     fn request_preset_dump(&mut self) -> Result<()>;
     fn register_rt_channel(&mut self) -> Result<()>;
     fn kill_plugin(&mut self) -> Result<()>;
@@ -46,6 +53,10 @@ impl SandboxBroker for PluginBroker {
         PluginBroker::request_state_dump(self)
     }
 
+    fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        PluginBroker::load_state(self, data)
+    }
+
     fn request_preset_dump(&mut self) -> Result<()> {
         PluginBroker::request_preset_dump(self)
     }
@@ -174,12 +185,44 @@ impl<B: SandboxBroker> Vst3Host<B> {
             .context("failed to request audio processing")
     }
 
+    pub fn process_block_with_events(&mut self, frames: u32, events: &[BlockEvent]) -> Result<()> {
+        self.broker
+            .process_block_with_events(frames, events)
+            .context("failed to request audio processing with events")
+    }
+
     pub fn request_state_dump(&mut self) -> Result<()> {
         self.broker
             .request_state_dump()
             .context("failed to request state dump")
     }
 
+    /// Requests the plugin's state and waits for the broker to dump it.
+    pub fn save_state(&mut self) -> Result<Vec<u8>> {
+        self.request_state_dump()?;
+        self.wait_for_reply(|event| match event {
+            BrokerEvent::StateDump { data } => Some(Ok(data.clone())),
+            BrokerEvent::StateFailed { reason } => Some(Err(reason.clone())),
+            _ => None,
+        })
+        .context("broker did not provide a state dump")?
+        .map_err(|reason| anyhow!("plugin failed to save its state: {reason}"))
+    }
+
+    /// Restores a blob returned by [`save_state`](Self::save_state).
+    pub fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        self.broker
+            .load_state(data)
+            .context("failed to send plugin state")?;
+        self.wait_for_reply(|event| match event {
+            BrokerEvent::StateLoaded => Some(Ok(())),
+            BrokerEvent::StateFailed { reason } => Some(Err(reason.clone())),
+            _ => None,
+        })
+        .context("broker did not confirm the state load")?
+        .map_err(|reason| anyhow!("plugin rejected its state: {reason}"))
+    }
+
     pub fn request_preset_dump(&mut self) -> Result<()> {
         self.broker
             .request_preset_dump()
@@ -259,6 +302,21 @@ impl<B: SandboxBroker> Vst3Host<B> {
         Ok(())
     }
 
+    /// Handles events until `reply` picks one out, or the poll timeout
+    /// passes without it.
+    fn wait_for_reply<T>(&mut self, mut reply: impl FnMut(&BrokerEvent) -> Option<T>) -> Option<T> {
+        let deadline = Instant::now() + self.event_poll_timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let event = self.broker.recv_event(timeout)?;
+            let value = reply(&event);
+            self.handle_event(event);
+            if value.is_some() {
+                return value;
+            }
+        }
+    }
+
     fn handle_event(&mut self, event: BrokerEvent) {
         match event {
             BrokerEvent::PluginLoaded { name } => {
@@ -287,7 +345,9 @@ impl<B: SandboxBroker> Vst3Host<B> {
             BrokerEvent::EditorWindowCreated { window_id } => {
                 self.pending_editor_window = Some(window_id);
             }
-            BrokerEvent::Acknowledge => {}
+            BrokerEvent::Acknowledge
+            | BrokerEvent::StateLoaded
+            | BrokerEvent::StateFailed { .. } => {}
         }
     }
 }
@@ -21,23 +21,141 @@ pub enum BrokerCommand {
         frames: u32,
     },
     RequestState,
+    /// Hands a blob from a [`BrokerEvent::StateDump`] back to the plugin.
+    LoadState {
+        data: Vec<u8>,
+    },
     RequestPresetDump,
     RegisterRtChannel,
     Shutdown,
     KillPlugin,
 }
 
+/// Sample-accurate event delivered to the sandboxed plugin through the shared
+/// event region of the audio ring instead of the command pipe.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum BlockEvent {
+    NoteOn {
+        channel: u8,
+        key: u8,
+        velocity: f32,
+        sample_offset: u32,
+    },
+    NoteOff {
+        channel: u8,
+        key: u8,
+        velocity: f32,
+        sample_offset: u32,
+    },
+    ParamChange {
+        id: u32,
+        value: f32,
+        sample_offset: u32,
+    },
+}
+
+impl BlockEvent {
+    /// Size of an encoded event record in the shared region.
+    pub const ENCODED_LEN: usize = 16;
+
+    pub fn sample_offset(&self) -> u32 {
+        match self {
+            BlockEvent::NoteOn { sample_offset, .. }
+            | BlockEvent::NoteOff { sample_offset, .. }
+            | BlockEvent::ParamChange { sample_offset, .. } => *sample_offset,
+        }
+    }
+
+    /// Encodes the event into a fixed-size little-endian record.
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let (tag, channel, key, id, value) = match *self {
+            BlockEvent::NoteOn {
+                channel,
+                key,
+                velocity,
+                ..
+            } => (1u8, channel, key, 0u32, velocity),
+            BlockEvent::NoteOff {
+                channel,
+                key,
+                velocity,
+                ..
+            } => (2, channel, key, 0, velocity),
+            BlockEvent::ParamChange { id, value, .. } => (3, 0, 0, id, value),
+        };
+        let mut record = [0u8; Self::ENCODED_LEN];
+        record[0] = tag;
+        record[1] = channel;
+        record[2] = key;
+        record[4..8].copy_from_slice(&self.sample_offset().to_le_bytes());
+        record[8..12].copy_from_slice(&id.to_le_bytes());
+        record[12..16].copy_from_slice(&value.to_le_bytes());
+        record
+    }
+
+    /// Decodes a record produced by [`BlockEvent::encode`].
+    pub fn decode(record: &[u8; Self::ENCODED_LEN]) -> Option<Self> {
+        let word = |range: std::ops::Range<usize>| {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&record[range]);
+            bytes
+        };
+        let sample_offset = u32::from_le_bytes(word(4..8));
+        let id = u32::from_le_bytes(word(8..12));
+        let value = f32::from_le_bytes(word(12..16));
+        match record[0] {
+            1 => Some(BlockEvent::NoteOn {
+                channel: record[1],
+                key: record[2],
+                velocity: value,
+                sample_offset,
+            }),
+            2 => Some(BlockEvent::NoteOff {
+                channel: record[1],
+                key: record[2],
+                velocity: value,
+                sample_offset,
+            }),
+            3 => Some(BlockEvent::ParamChange {
+                id,
+                value,
+                sample_offset,
+            }),
+            _ => None,
+        }
+    }
+}
+
 /// Events delivered from the broker back to the host.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum BrokerEvent {
     Acknowledge,
-    PluginLoaded { name: String },
-    PluginCrashed { code: Option<i32> },
-    AudioProcessed { frames: u32 },
-    StateDump { data: Vec<u8> },
-    PresetDump { data: Vec<u8> },
-    LatencyReported { samples: u32 },
-    EditorWindowCreated { window_id: u64 },
+    PluginLoaded {
+        name: String,
+    },
+    PluginCrashed {
+        code: Option<i32>,
+    },
+    AudioProcessed {
+        frames: u32,
+    },
+    StateDump {
+        data: Vec<u8>,
+    },
+    StateLoaded,
+    /// The plugin could not save its state or rejected a blob.
+    StateFailed {
+        reason: String,
+    },
+    PresetDump {
+        data: Vec<u8>,
+    },
+    LatencyReported {
+        samples: u32,
+    },
+    EditorWindowCreated {
+        window_id: u64,
+    },
 }
 
 /// Real-time safe message categories exchanged over the RT channel.
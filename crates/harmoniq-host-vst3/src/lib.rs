@@ -21,7 +21,7 @@ pub use broker::{BrokerConfig, PluginBroker};
 pub use host::{HostOptions, Vst3Host, Vst3HostBuilder};
 #[cfg(any(test, feature = "fuzzing"))]
 pub use ipc::fuzz_roundtrip_ipc;
-pub use ipc::{BrokerCommand, BrokerEvent, RtChannel, RtMessage, RtMessageKind};
+pub use ipc::{BlockEvent, BrokerCommand, BrokerEvent, RtChannel, RtMessage, RtMessageKind};
 pub use pdc::{PdcEvent, PluginDataCache};
-pub use ring::{SharedAudioRing, SharedAudioRingDescriptor};
+pub use ring::{SharedAudioRing, SharedAudioRingDescriptor, SharedAudioRingView};
 pub use window::{WaylandEmbedder, WindowEmbedder, X11Embedder};
@@ -1,12 +1,17 @@
 use std::fs::File;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{fence, AtomicU32, Ordering};
 
 use anyhow::{Context, Result};
 use memmap2::{MmapMut, MmapOptions};
 use serde::{Deserialize, Serialize};
 use tempfile::NamedTempFile;
 
+use crate::ipc::BlockEvent;
+
+/// Number of per-block events the shared region holds unless configured otherwise.
+pub const DEFAULT_EVENT_CAPACITY: u32 = 256;
+
 #[repr(C)]
 struct RingHeader {
     write_index: AtomicU32,
@@ -15,6 +20,105 @@ struct RingHeader {
     channels: u32,
 }
 
+/// Header of the event region that follows the audio samples. `sequence` acts as
+/// a seqlock: it is odd while the host is rewriting the records. `dropped` counts
+/// the events of the current block that did not fit in the region.
+#[repr(C)]
+struct EventHeader {
+    sequence: AtomicU32,
+    count: AtomicU32,
+    capacity: u32,
+    dropped: AtomicU32,
+}
+
+fn audio_len(frames: u32, channels: u32) -> usize {
+    frames as usize * channels as usize * std::mem::size_of::<f32>()
+}
+
+fn event_header_offset(frames: u32, channels: u32) -> usize {
+    std::mem::size_of::<RingHeader>() + audio_len(frames, channels)
+}
+
+fn write_event_region(
+    mmap: &mut [u8],
+    frames: u32,
+    channels: u32,
+    events: &[BlockEvent],
+) -> Result<u32> {
+    let offset = event_header_offset(frames, channels);
+    let records_offset = offset + std::mem::size_of::<EventHeader>();
+    anyhow::ensure!(
+        mmap.len() >= records_offset,
+        "shared ring has no event region"
+    );
+    let header = unsafe { &*(mmap.as_ptr().add(offset) as *const EventHeader) };
+    // Events past the capacity are dropped rather than failing the whole block.
+    let written = events.len().min(header.capacity as usize);
+    let dropped = (events.len() - written) as u32;
+
+    let sequence = header.sequence.load(Ordering::Relaxed);
+    header
+        .sequence
+        .store(sequence.wrapping_add(1), Ordering::Relaxed);
+    fence(Ordering::Release);
+
+    let records = &mut mmap[records_offset..];
+    for (event, slot) in events[..written]
+        .iter()
+        .zip(records.chunks_exact_mut(BlockEvent::ENCODED_LEN))
+    {
+        slot.copy_from_slice(&event.encode());
+    }
+    header.count.store(written as u32, Ordering::Relaxed);
+    header.dropped.store(dropped, Ordering::Relaxed);
+    header
+        .sequence
+        .store(sequence.wrapping_add(2), Ordering::Release);
+    Ok(dropped)
+}
+
+fn read_event_region(
+    mmap: &[u8],
+    frames: u32,
+    channels: u32,
+    events: &mut Vec<BlockEvent>,
+) -> Result<u32> {
+    let offset = event_header_offset(frames, channels);
+    let records_offset = offset + std::mem::size_of::<EventHeader>();
+    anyhow::ensure!(
+        mmap.len() >= records_offset,
+        "shared ring has no event region"
+    );
+    let header = unsafe { &*(mmap.as_ptr().add(offset) as *const EventHeader) };
+    let records = &mmap[records_offset..];
+
+    for _ in 0..64 {
+        let before = header.sequence.load(Ordering::Acquire);
+        if before % 2 == 1 {
+            std::hint::spin_loop();
+            continue;
+        }
+        let count = (header.count.load(Ordering::Relaxed) as usize).min(header.capacity as usize);
+        events.clear();
+        events.extend(
+            records
+                .chunks_exact(BlockEvent::ENCODED_LEN)
+                .take(count)
+                .filter_map(|chunk| {
+                    let mut record = [0u8; BlockEvent::ENCODED_LEN];
+                    record.copy_from_slice(chunk);
+                    BlockEvent::decode(&record)
+                }),
+        );
+        fence(Ordering::Acquire);
+        if header.sequence.load(Ordering::Relaxed) == before {
+            return Ok(before / 2);
+        }
+    }
+    events.clear();
+    anyhow::bail!("event region kept changing while reading")
+}
+
 /// Describes a shared audio ring buffer that can be opened from another process.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SharedAudioRingDescriptor {
@@ -24,25 +128,34 @@ pub struct SharedAudioRingDescriptor {
 }
 
 /// Shared-memory audio ring with zero-copy semantics. The ring exposes a single block of
-/// interleaved audio samples that can be swapped between producer and consumer without copying.
+/// interleaved audio samples that can be swapped between producer and consumer without copying,
+/// followed by a lock-free region carrying the MIDI and parameter events for that block.
 #[derive(Debug)]
 pub struct SharedAudioRing {
     descriptor: SharedAudioRingDescriptor,
     _file: NamedTempFile,
     mmap: MmapMut,
+    dropped_events: u64,
 }
 
 impl SharedAudioRing {
     pub fn create(frames: u32, channels: u32) -> Result<Self> {
+        Self::create_with_event_capacity(frames, channels, DEFAULT_EVENT_CAPACITY)
+    }
+
+    pub fn create_with_event_capacity(
+        frames: u32,
+        channels: u32,
+        event_capacity: u32,
+    ) -> Result<Self> {
         let mut file = tempfile::Builder::new()
             .prefix("harmoniq-audio-ring")
             .tempfile()
             .context("failed to allocate shared audio ring backing file")?;
 
-        let total_samples = frames as usize * channels as usize;
-        let data_len = total_samples * std::mem::size_of::<f32>();
-        let header_len = std::mem::size_of::<RingHeader>();
-        let total_len = header_len + data_len;
+        let events_len =
+            std::mem::size_of::<EventHeader>() + event_capacity as usize * BlockEvent::ENCODED_LEN;
+        let total_len = event_header_offset(frames, channels) + events_len;
 
         file.as_file_mut()
             .set_len(total_len as u64)
@@ -60,6 +173,17 @@ impl SharedAudioRing {
                     channels,
                 },
             );
+            let events_ptr =
+                mmap.as_mut_ptr().add(event_header_offset(frames, channels)) as *mut EventHeader;
+            std::ptr::write(
+                events_ptr,
+                EventHeader {
+                    sequence: AtomicU32::new(0),
+                    count: AtomicU32::new(0),
+                    capacity: event_capacity,
+                    dropped: AtomicU32::new(0),
+                },
+            );
         }
 
         let descriptor = SharedAudioRingDescriptor {
@@ -72,6 +196,7 @@ impl SharedAudioRing {
             descriptor,
             mmap,
             _file: file,
+            dropped_events: 0,
         })
     }
 
@@ -125,6 +250,33 @@ impl SharedAudioRing {
         (data, generation)
     }
 
+    /// Publish the events for the next block. The helper consumes them at block start.
+    ///
+    /// Events beyond the region's capacity are dropped; the number dropped is returned
+    /// and added to [`dropped_events`](Self::dropped_events).
+    pub fn write_events(&mut self, events: &[BlockEvent]) -> Result<u32> {
+        let (frames, channels) = (self.descriptor.frames, self.descriptor.channels);
+        let dropped = write_event_region(&mut self.mmap, frames, channels, events)?;
+        self.dropped_events += u64::from(dropped);
+        Ok(dropped)
+    }
+
+    /// Total number of events dropped because a block overflowed the event region.
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events
+    }
+
+    /// Fill `events` with the most recently published block events and return their
+    /// generation.
+    pub fn read_events(&self, events: &mut Vec<BlockEvent>) -> Result<u32> {
+        read_event_region(
+            &self.mmap,
+            self.descriptor.frames,
+            self.descriptor.channels,
+            events,
+        )
+    }
+
     /// Mark that the reader has consumed the latest audio block.
     pub fn acknowledge(&self, generation: u32) {
         self.header()
@@ -152,6 +304,26 @@ impl SharedAudioRingDescriptor {
         Ok((slice.to_vec(), generation))
     }
 
+    /// Map the ring once for a helper that reads it every block.
+    pub fn map(&self) -> Result<SharedAudioRingView> {
+        let (file, mmap) = self.open()?;
+        let header = unsafe { &*(mmap.as_ptr() as *const RingHeader) };
+        let (frames, channels) = (header.frames, header.channels);
+        Ok(SharedAudioRingView {
+            _file: file,
+            mmap,
+            frames,
+            channels,
+            last_generation: None,
+        })
+    }
+
+    /// Fill `events` with the events published for the current block from another
+    /// process.
+    pub fn read_events(&self, events: &mut Vec<BlockEvent>) -> Result<u32> {
+        self.map()?.read_events(events)
+    }
+
     pub fn write_block(&self, samples: &[f32]) -> Result<()> {
         let (_file, mut mmap) = self.open()?;
         let header = unsafe { &*(mmap.as_ptr() as *const RingHeader) };
@@ -165,3 +337,42 @@ impl SharedAudioRingDescriptor {
         Ok(())
     }
 }
+
+/// The helper's side of a [`SharedAudioRing`]: keeps the mapping open so each block
+/// reads the host's events without reopening the backing file.
+#[derive(Debug)]
+pub struct SharedAudioRingView {
+    _file: File,
+    mmap: MmapMut,
+    frames: u32,
+    channels: u32,
+    last_generation: Option<u32>,
+}
+
+impl SharedAudioRingView {
+    /// Fill `events` with the most recently published block events and return their
+    /// generation.
+    pub fn read_events(&self, events: &mut Vec<BlockEvent>) -> Result<u32> {
+        read_event_region(&self.mmap, self.frames, self.channels, events)
+    }
+
+    /// Consume the events for the block about to be processed. Returns `false` and
+    /// leaves `events` empty when the host has not published a block since the last
+    /// call, so the same events are never applied twice.
+    pub fn take_block_events(&mut self, events: &mut Vec<BlockEvent>) -> Result<bool> {
+        let generation = self.read_events(events)?;
+        if self.last_generation == Some(generation) {
+            events.clear();
+            return Ok(false);
+        }
+        self.last_generation = Some(generation);
+        Ok(true)
+    }
+
+    /// Number of events the host dropped from the current block.
+    pub fn dropped_events(&self) -> u32 {
+        let offset = event_header_offset(self.frames, self.channels);
+        let header = unsafe { &*(self.mmap.as_ptr().add(offset) as *const EventHeader) };
+        header.dropped.load(Ordering::Relaxed)
+    }
+}
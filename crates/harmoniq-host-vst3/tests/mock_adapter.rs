@@ -7,7 +7,7 @@ use parking_lot::Mutex;
 
 use harmoniq_host_vst3::adapter::{AdapterDescriptor, AdapterKind, SandboxRequest};
 use harmoniq_host_vst3::host::{SandboxBroker, Vst3HostBuilder};
-use harmoniq_host_vst3::ipc::{BrokerCommand, BrokerEvent, RtMessageKind};
+use harmoniq_host_vst3::ipc::{BlockEvent, BrokerCommand, BrokerEvent, RtMessageKind};
 use harmoniq_host_vst3::pdc::PdcEvent;
 use harmoniq_host_vst3::ring::SharedAudioRing;
 use harmoniq_host_vst3::window::WindowEmbedder;
@@ -65,6 +65,13 @@ impl SandboxBroker for MockBroker {
         Ok(())
     }
 
+    fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        self.commands.lock().push(BrokerCommand::LoadState {
+            data: data.to_vec(),
+        });
+        Ok(())
+    }
+
     fn request_preset_dump(&mut self) -> Result<()> {
         self.commands.lock().push(BrokerCommand::RequestPresetDump);
         Ok(())
@@ -184,6 +191,36 @@ fn preset_and_state_events_populate_cache() {
     );
 }
 
+#[test]
+fn state_save_and_load_wait_for_the_broker() {
+    let broker = MockBroker::new();
+    let log = broker.command_log();
+    let events = broker.event_queue();
+    let mut host = Vst3HostBuilder::new().build_with_broker(broker);
+
+    events
+        .lock()
+        .push_back(BrokerEvent::LatencyReported { samples: 64 });
+    events
+        .lock()
+        .push_back(BrokerEvent::StateDump { data: vec![7, 8] });
+    assert_eq!(host.save_state().unwrap(), vec![7, 8]);
+    // Events ahead of the reply are still handled.
+    assert_eq!(host.latency_samples(), 64);
+
+    events.lock().push_back(BrokerEvent::StateLoaded);
+    host.load_state(&[7, 8]).unwrap();
+    assert!(log
+        .lock()
+        .contains(&BrokerCommand::LoadState { data: vec![7, 8] }));
+
+    events.lock().push_back(BrokerEvent::StateFailed {
+        reason: "corrupt".into(),
+    });
+    assert!(host.load_state(&[0]).is_err());
+    assert!(host.save_state().is_err(), "no reply");
+}
+
 #[test]
 fn latency_events_update_rt_channel() {
     let broker = MockBroker::new();
@@ -222,3 +259,81 @@ fn editor_host_attaches_embedder() {
     let attach_log = log.lock();
     assert_eq!(attach_log.as_slice(), &[4242]);
 }
+
+#[test]
+fn block_events_reach_helper_through_shared_region() {
+    let broker = MockBroker::new();
+    let log = broker.command_log();
+    let mut host = Vst3HostBuilder::new().build_with_broker(broker);
+
+    let events = [
+        BlockEvent::NoteOn {
+            channel: 0,
+            key: 60,
+            velocity: 0.8,
+            sample_offset: 0,
+        },
+        BlockEvent::ParamChange {
+            id: 7,
+            value: 0.25,
+            sample_offset: 12,
+        },
+        BlockEvent::NoteOff {
+            channel: 0,
+            key: 60,
+            velocity: 0.0,
+            sample_offset: 31,
+        },
+    ];
+    host.process_block_with_events(32, &events).unwrap();
+    assert_eq!(
+        log.lock().as_slice(),
+        &[BrokerCommand::ProcessBlock { frames: 32 }]
+    );
+
+    // The helper maps the ring through its descriptor, as a separate process would.
+    let mut view = host.audio_ring().descriptor().map().unwrap();
+    let mut received = Vec::with_capacity(8);
+    assert!(view.take_block_events(&mut received).unwrap());
+    assert_eq!(received, events);
+    let offsets: Vec<u32> = received.iter().map(BlockEvent::sample_offset).collect();
+    assert_eq!(offsets, vec![0, 12, 31]);
+
+    // Without a new block the helper gets nothing, rather than replaying the events.
+    assert!(!view.take_block_events(&mut received).unwrap());
+    assert!(received.is_empty());
+
+    let generation = view.read_events(&mut received).unwrap();
+    host.process_block_with_events(32, &[]).unwrap();
+    assert!(view.take_block_events(&mut received).unwrap());
+    assert!(received.is_empty());
+    assert!(view.read_events(&mut received).unwrap() > generation);
+}
+
+#[test]
+fn block_events_past_capacity_are_dropped_and_counted() {
+    let mut ring = SharedAudioRing::create_with_event_capacity(16, 2, 1).unwrap();
+    let first = BlockEvent::ParamChange {
+        id: 1,
+        value: 1.0,
+        sample_offset: 0,
+    };
+    let second = BlockEvent::ParamChange {
+        id: 2,
+        value: 0.5,
+        sample_offset: 4,
+    };
+    assert_eq!(ring.write_events(&[first]).unwrap(), 0);
+    assert_eq!(ring.write_events(&[first, second]).unwrap(), 1);
+    assert_eq!(ring.dropped_events(), 1);
+
+    let mut received = Vec::new();
+    ring.read_events(&mut received).unwrap();
+    assert_eq!(received, vec![first]);
+
+    let view = ring.descriptor().map().unwrap();
+    assert_eq!(view.dropped_events(), 1);
+    ring.write_events(&[second]).unwrap();
+    assert_eq!(view.dropped_events(), 0);
+    assert_eq!(ring.dropped_events(), 1);
+}
@@ -16,9 +16,12 @@ pub mod hotplug;
 pub mod learn;
 /// MIDI output helpers.
 pub mod output;
+/// Note-repeat processing for live input.
+pub mod repeat;
 
 pub use device::{MidiDeviceId, MidiDeviceManager, MidiEvent, MidiMessage, MidiSource};
 pub use output::{MidiOutputHandle, MidiOutputManager};
+pub use repeat::{NoteRepeat, RepeatRate, TimedMessage};
 
 /// Timestamp captured from the monotonic clock when a MIDI event was received.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -0,0 +1,354 @@
+use serde::{Deserialize, Serialize};
+
+/// Musical spacing between note-repeat retriggers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RepeatRate {
+    /// One retrigger per quarter note.
+    Quarter,
+    /// One retrigger per eighth note.
+    Eighth,
+    /// Eighth-note triplets.
+    EighthTriplet,
+    /// One retrigger per sixteenth note.
+    #[default]
+    Sixteenth,
+    /// Sixteenth-note triplets.
+    SixteenthTriplet,
+    /// One retrigger per thirty-second note.
+    ThirtySecond,
+}
+
+impl RepeatRate {
+    /// Length of one repeat in quarter-note beats.
+    pub fn beats(self) -> f64 {
+        match self {
+            RepeatRate::Quarter => 1.0,
+            RepeatRate::Eighth => 0.5,
+            RepeatRate::EighthTriplet => 1.0 / 3.0,
+            RepeatRate::Sixteenth => 0.25,
+            RepeatRate::SixteenthTriplet => 1.0 / 6.0,
+            RepeatRate::ThirtySecond => 0.125,
+        }
+    }
+}
+
+/// Raw three-byte MIDI message positioned inside an audio block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedMessage {
+    /// Sample offset from the start of the block.
+    pub offset: u32,
+    /// Channel voice message bytes.
+    pub msg: [u8; 3],
+}
+
+impl TimedMessage {
+    /// Create a message at the given block offset.
+    pub fn new(offset: u32, msg: [u8; 3]) -> Self {
+        Self { offset, msg }
+    }
+
+    /// Returns `(channel, note, velocity)` for a note-on with non-zero velocity.
+    pub fn note_on(&self) -> Option<(u8, u8, u8)> {
+        let [status, note, velocity] = self.msg;
+        (status & 0xF0 == 0x90 && velocity > 0).then_some((status & 0x0F, note, velocity))
+    }
+
+    /// Returns `(channel, note)` for a note-off or zero-velocity note-on.
+    pub fn note_off(&self) -> Option<(u8, u8)> {
+        let [status, note, velocity] = self.msg;
+        match status & 0xF0 {
+            0x80 => Some((status & 0x0F, note)),
+            0x90 if velocity == 0 => Some((status & 0x0F, note)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct HeldNote {
+    channel: u8,
+    note: u8,
+    velocity: f32,
+    next_on: f64,
+    off_at: Option<f64>,
+}
+
+/// Retriggers held notes at a tempo-synced rate while armed.
+///
+/// Incoming note-ons pass straight through and start a repeat cycle; each
+/// following cycle re-sends the note with its velocity scaled by the decay
+/// factor. The gate sets how much of each cycle the note sounds before its
+/// note-off. Releasing the key ends the cycle.
+#[derive(Debug, Clone)]
+pub struct NoteRepeat {
+    sample_rate: f32,
+    tempo_bpm: f32,
+    rate: RepeatRate,
+    gate: f32,
+    velocity_decay: f32,
+    armed: bool,
+    position: u64,
+    held: Vec<HeldNote>,
+}
+
+impl NoteRepeat {
+    /// Create a disarmed note-repeat at 120 BPM, repeating sixteenths.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1.0),
+            tempo_bpm: 120.0,
+            rate: RepeatRate::default(),
+            gate: 0.5,
+            velocity_decay: 1.0,
+            armed: false,
+            position: 0,
+            held: Vec::with_capacity(16),
+        }
+    }
+
+    /// Change the sample rate the repeat interval is measured in.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate.max(1.0);
+    }
+
+    /// Whether any note is still held or waiting for its note-off.
+    pub fn is_holding(&self) -> bool {
+        !self.held.is_empty()
+    }
+
+    /// Whether held notes are currently being repeated.
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    /// Arm or disarm the repeat. Disarming stops pending retriggers but leaves
+    /// held notes sounding until their note-off arrives.
+    pub fn set_armed(&mut self, armed: bool) {
+        self.armed = armed;
+    }
+
+    /// Update the tempo driving the repeat interval.
+    pub fn set_tempo(&mut self, tempo_bpm: f32) {
+        self.tempo_bpm = tempo_bpm.max(1.0);
+    }
+
+    /// Current repeat rate.
+    pub fn rate(&self) -> RepeatRate {
+        self.rate
+    }
+
+    /// Change the repeat rate; takes effect from the next retrigger.
+    pub fn set_rate(&mut self, rate: RepeatRate) {
+        self.rate = rate;
+    }
+
+    /// Fraction of each repeat the note sounds for, clamped to `0.01..=1.0`.
+    pub fn set_gate(&mut self, gate: f32) {
+        self.gate = gate.clamp(0.01, 1.0);
+    }
+
+    /// Velocity multiplier applied on every retrigger, clamped to `0.0..=1.0`.
+    pub fn set_velocity_decay(&mut self, decay: f32) {
+        self.velocity_decay = decay.clamp(0.0, 1.0);
+    }
+
+    /// Length of one repeat in samples at the current tempo and rate.
+    pub fn interval_samples(&self) -> f64 {
+        60.0 / self.tempo_bpm as f64 * self.rate.beats() * self.sample_rate as f64
+    }
+
+    /// Release all held notes and restart the timeline at zero.
+    pub fn reset(&mut self) {
+        self.held.clear();
+        self.position = 0;
+    }
+
+    /// Process one block of `frames` samples. `input` must be sorted by
+    /// offset; the result is appended to `output` in time order.
+    pub fn process(&mut self, input: &[TimedMessage], frames: u32, output: &mut Vec<TimedMessage>) {
+        let block_start = self.position;
+        for event in input {
+            let at = block_start + event.offset.min(frames.saturating_sub(1)) as u64;
+            self.flush_until(at as f64, block_start, output);
+
+            if let Some((channel, note, velocity)) = event.note_on() {
+                output.push(*event);
+                if self.armed {
+                    self.hold(channel, note, velocity, at as f64);
+                }
+            } else if let Some((channel, note)) = event.note_off() {
+                let held = self
+                    .held
+                    .iter()
+                    .position(|held| held.channel == channel && held.note == note);
+                match held {
+                    Some(index) => {
+                        let held = self.held.remove(index);
+                        if held.off_at.is_some() {
+                            output.push(*event);
+                        }
+                    }
+                    None => output.push(*event),
+                }
+            } else {
+                output.push(*event);
+            }
+        }
+        let block_end = block_start + frames as u64;
+        self.flush_until(block_end as f64, block_start, output);
+        self.position = block_end;
+    }
+
+    fn hold(&mut self, channel: u8, note: u8, velocity: u8, at: f64) {
+        self.held
+            .retain(|held| held.channel != channel || held.note != note);
+        let interval = self.interval_samples();
+        self.held.push(HeldNote {
+            channel,
+            note,
+            velocity: velocity as f32,
+            next_on: at + interval,
+            off_at: Some(at + interval * self.gate as f64),
+        });
+    }
+
+    /// Emits every scheduled note-off and retrigger before `end`.
+    fn flush_until(&mut self, end: f64, block_start: u64, output: &mut Vec<TimedMessage>) {
+        loop {
+            let mut earliest: Option<(usize, f64, bool)> = None;
+            for (index, held) in self.held.iter().enumerate() {
+                if let Some(off) = held.off_at {
+                    if off < end && earliest.is_none_or(|(_, time, _)| off <= time) {
+                        earliest = Some((index, off, false));
+                    }
+                }
+                if self.armed
+                    && held.next_on < end
+                    && earliest.is_none_or(|(_, time, _)| held.next_on < time)
+                {
+                    earliest = Some((index, held.next_on, true));
+                }
+            }
+            let Some((index, time, retrigger)) = earliest else {
+                break;
+            };
+
+            let interval = self.interval_samples();
+            let gate = self.gate as f64;
+            let decay = self.velocity_decay;
+            let held = &mut self.held[index];
+            let offset = (time.floor() as u64).saturating_sub(block_start) as u32;
+            if retrigger {
+                if held.off_at.take().is_some() {
+                    output.push(TimedMessage::new(
+                        offset,
+                        [0x80 | held.channel, held.note, 0],
+                    ));
+                }
+                held.velocity *= decay;
+                let velocity = held.velocity.round().clamp(1.0, 127.0) as u8;
+                output.push(TimedMessage::new(
+                    offset,
+                    [0x90 | held.channel, held.note, velocity],
+                ));
+                held.next_on = time + interval;
+                held.off_at = Some(time + interval * gate);
+            } else {
+                held.off_at = None;
+                output.push(TimedMessage::new(
+                    offset,
+                    [0x80 | held.channel, held.note, 0],
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: u32 = 512;
+
+    fn run(repeat: &mut NoteRepeat, events: &[(u64, [u8; 3])], total: u64) -> Vec<(u64, [u8; 3])> {
+        let mut output = Vec::new();
+        let mut collected = Vec::new();
+        let mut start = 0u64;
+        while start < total {
+            let input: Vec<TimedMessage> = events
+                .iter()
+                .filter(|(at, _)| *at >= start && *at < start + BLOCK as u64)
+                .map(|(at, msg)| TimedMessage::new((*at - start) as u32, *msg))
+                .collect();
+            output.clear();
+            repeat.process(&input, BLOCK, &mut output);
+            collected.extend(
+                output
+                    .iter()
+                    .map(|event| (start + event.offset as u64, event.msg)),
+            );
+            start += BLOCK as u64;
+        }
+        collected
+    }
+
+    #[test]
+    fn retriggers_sixteenths_until_note_off() {
+        // 120 BPM at 48 kHz: one sixteenth is 6 000 samples.
+        let mut repeat = NoteRepeat::new(48_000.0);
+        repeat.set_armed(true);
+        repeat.set_rate(RepeatRate::Sixteenth);
+        repeat.set_velocity_decay(0.5);
+        let events = [(100, [0x90, 36, 120]), (27_000, [0x80, 36, 0])];
+        let output = run(&mut repeat, &events, 48_000);
+
+        let ons: Vec<(u64, u8)> = output
+            .iter()
+            .filter(|(_, msg)| msg[0] == 0x90 && msg[2] > 0)
+            .map(|(at, msg)| (*at, msg[2]))
+            .collect();
+        assert_eq!(
+            ons,
+            vec![
+                (100, 120),
+                (6_100, 60),
+                (12_100, 30),
+                (18_100, 15),
+                (24_100, 8)
+            ]
+        );
+
+        let offs: Vec<u64> = output
+            .iter()
+            .filter(|(_, msg)| msg[0] == 0x80)
+            .map(|(at, _)| *at)
+            .collect();
+        assert_eq!(offs, vec![3_100, 9_100, 15_100, 21_100, 27_000]);
+    }
+
+    #[test]
+    fn full_gate_sends_off_before_each_retrigger() {
+        let mut repeat = NoteRepeat::new(48_000.0);
+        repeat.set_armed(true);
+        repeat.set_gate(1.0);
+        let events = [(0, [0x90, 40, 100]), (12_500, [0x80, 40, 0])];
+        let output = run(&mut repeat, &events, 24_000);
+        let expected = vec![
+            (0, [0x90, 40, 100]),
+            (6_000, [0x80, 40, 0]),
+            (6_000, [0x90, 40, 100]),
+            (12_000, [0x80, 40, 0]),
+            (12_000, [0x90, 40, 100]),
+            (12_500, [0x80, 40, 0]),
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn disarmed_repeat_passes_input_through() {
+        let mut repeat = NoteRepeat::new(48_000.0);
+        let events = [(10, [0x90, 36, 100]), (20_000, [0x80, 36, 0])];
+        let output = run(&mut repeat, &events, 24_000);
+        assert_eq!(output, events.to_vec());
+    }
+}
@@ -1,9 +1,11 @@
 pub mod state;
 
-mod rt;
-pub use rt::{
+// The real-time mixer is the one the engine runs, so VCA, solo and monitor
+// commands reach the audio path unchanged.
+pub use harmoniq_engine::mixer_rt::{
     AutoRx, AutoTx, AutomationEvent, AuxBusId, Command, CommandRx, CommandTx, Group, GroupId,
-    Mixer, MixerConfig, RoutingBuilder, RoutingTable, TrackId,
+    MeterLevels, MeterMode, Mixer, MixerConfig, RoutingBuilder, RoutingTable, SoloMode, TrackId,
+    VcaId, MAX_VCAS, MONITOR_BUS,
 };
 
 #[cfg(feature = "egui")]
@@ -16,6 +18,10 @@ use state::MixerState;
 use state::{ChannelId, SendId};
 
 /// Callbacks provided by the host app (non-RT).
+///
+/// Start from [`MixerCallbacks::noop`] and replace the callbacks the host
+/// handles, so adding a callback does not break existing hosts.
+#[non_exhaustive]
 pub struct MixerCallbacks {
     /// Open plugin browser to fill an insert slot (or append if slot is None)
     pub open_insert_browser: Box<dyn FnMut(ChannelId, Option<usize>) + Send>,
@@ -31,13 +37,24 @@ pub struct MixerCallbacks {
     pub apply_routing: Box<dyn FnMut(RoutingDelta) + Send>,
     /// Create/route a send target (A/B/C…) — host decides exact routing object
     pub configure_send: Box<dyn FnMut(ChannelId, SendId, f32, bool) + Send>,
-    /// Set channel gain (dB) and pan (-1..1) in engine
+    /// Set channel fader gain (dB, without VCA gain) and pan (-1..1) in engine
     pub set_gain_pan: Box<dyn FnMut(ChannelId, f32, f32) + Send>,
+    /// Set a VCA fader (dB); the engine scales its member channels
+    pub set_vca_gain: Box<dyn FnMut(VcaId, f32) + Send>,
+    /// Place a channel under a VCA, or release it with `None`
+    pub assign_vca: Box<dyn FnMut(ChannelId, Option<VcaId>) + Send>,
+    /// Nest a VCA under a parent VCA, or detach it with `None`
+    pub set_vca_parent: Box<dyn FnMut(VcaId, Option<VcaId>) + Send>,
     /// Adjust stereo separation/width for the channel
     pub set_stereo_separation: Box<dyn FnMut(ChannelId, f32) + Send>,
     /// Mute/Solo changes
     pub set_mute: Box<dyn FnMut(ChannelId, bool) + Send>,
     pub set_solo: Box<dyn FnMut(ChannelId, bool) + Send>,
+    /// Switch between solo in place, AFL and PFL
+    pub set_solo_mode: Box<dyn FnMut(SoloMode) + Send>,
+    /// Monitor-only dim (enabled, attenuation dB) and mono fold
+    pub set_monitor_dim: Box<dyn FnMut(bool, f32) + Send>,
+    pub set_monitor_mono: Box<dyn FnMut(bool) + Send>,
     /// Callback to add a new channel (host/app should handle creating the channel).
     pub add_channel: Box<dyn FnMut() + Send>,
 }
@@ -53,9 +70,15 @@ impl MixerCallbacks {
             apply_routing: Box::new(|_| {}),
             configure_send: Box::new(|_, _, _, _| {}),
             set_gain_pan: Box::new(|_, _, _| {}),
+            set_vca_gain: Box::new(|_, _| {}),
+            assign_vca: Box::new(|_, _| {}),
+            set_vca_parent: Box::new(|_, _| {}),
             set_stereo_separation: Box::new(|_, _| {}),
             set_mute: Box::new(|_, _| {}),
             set_solo: Box::new(|_, _| {}),
+            set_solo_mode: Box::new(|_| {}),
+            set_monitor_dim: Box::new(|_, _| {}),
+            set_monitor_mono: Box::new(|_| {}),
             add_channel: Box::new(|| {}),
         }
     }
@@ -85,3 +108,68 @@ pub fn pan_mono(sample: f32, pan: f32) -> (f32, f32) {
     let r = angle.sin();
     (sample * l, sample * r)
 }
+
+/// Equal-power crossfade from `a` (`t = 0`) to `b` (`t = 1`).
+///
+/// The gains follow a quarter sine/cosine, so the summed power stays
+/// constant and both signals sit at -3 dB at the midpoint.
+#[inline]
+pub fn crossfade(a: f32, b: f32, t: f32) -> f32 {
+    let angle = core::f32::consts::FRAC_PI_2 * t.clamp(0.0, 1.0);
+    a * angle.cos() + b * angle.sin()
+}
+
+/// Block form of [`crossfade`] with `t` ramping linearly from `t_start` at
+/// the first frame towards `t_end`, which the frame after the block would
+/// reach. Consecutive blocks whose positions chain therefore fade without
+/// steps.
+#[inline]
+pub fn crossfade_block(a: &[f32], b: &[f32], out: &mut [f32], t_start: f32, t_end: f32) {
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), out.len());
+    let step = if out.is_empty() {
+        0.0
+    } else {
+        (t_end - t_start) / out.len() as f32
+    };
+    for (i, ((dst, a), b)) in out.iter_mut().zip(a).zip(b).enumerate() {
+        *dst = crossfade(*a, *b, t_start + step * i as f32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossfade_centre_is_minus_3_db() {
+        let half = crossfade(1.0, 0.0, 0.5);
+        assert!((half - core::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert!((crossfade(0.0, 1.0, 0.5) - half).abs() < 1e-6);
+        let db = 20.0 * half.log10();
+        assert!((db + 3.0103).abs() < 1e-3, "{db}");
+
+        assert_eq!(crossfade(0.25, 0.75, 0.0), 0.25);
+        assert!((crossfade(0.25, 0.75, 1.0) - 0.75).abs() < 1e-6);
+        assert_eq!(crossfade(0.25, 0.75, -2.0), 0.25);
+        assert!((crossfade(0.25, 0.75, 3.0) - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn crossfade_block_ramps_and_chains() {
+        let a = [1.0f32; 8];
+        let b = [0.0f32; 8];
+        let mut first = [0.0f32; 4];
+        let mut second = [0.0f32; 4];
+        crossfade_block(&a[..4], &b[..4], &mut first, 0.0, 0.5);
+        crossfade_block(&a[4..], &b[4..], &mut second, 0.5, 1.0);
+
+        let mut whole = [0.0f32; 8];
+        crossfade_block(&a, &b, &mut whole, 0.0, 1.0);
+        for (i, sample) in first.iter().chain(&second).enumerate() {
+            assert!((sample - whole[i]).abs() < 1e-6);
+            assert!((sample - crossfade(1.0, 0.0, i as f32 / 8.0)).abs() < 1e-6);
+        }
+        assert!(whole.windows(2).all(|pair| pair[1] < pair[0]));
+    }
+}
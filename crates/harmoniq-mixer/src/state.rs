@@ -1,9 +1,11 @@
 use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::time::Instant;
 
+pub use crate::{SoloMode, VcaId, MAX_VCAS};
+
 // CURRENT ARCH SUMMARY:
 // - Mixer UI/state lives here with rich channel metadata (inserts, sends, EQ stubs, meters).
-// - Real-time mixer DSP lives in `rt.rs` as a lightweight pan/gain/mute mixer with aux/group sends.
+// - Real-time mixer DSP lives in `harmoniq_engine::mixer_rt` as a lightweight pan/gain/mute mixer with aux/group sends.
 // - Engine wiring is thin: callbacks notify the host to update routing/params but no deep project IO.
 // - Missing: explicit track types, tighter separation of RT/editor data, full insert/send routing, and
 //   shared automation/meter transport between DSP and UI.
@@ -442,6 +444,13 @@ pub struct MixerState {
     pub master: MasterProcessing,
     pub default_pan_law: PanLaw,
     pub rack_routes: HashMap<u16, usize>,
+    pub solo_mode: SoloMode,
+    /// Transient listening controls. They only shape the monitor bus and
+    /// are neither saved nor applied to renders.
+    pub monitor: MonitorControls,
+    pub vcas: Vec<Vca>,
+    /// VCA controlling each assigned channel.
+    pub vca_assignments: HashMap<ChannelId, VcaId>,
 }
 
 impl Default for MixerState {
@@ -459,10 +468,43 @@ impl Default for MixerState {
             master: MasterProcessing::default(),
             default_pan_law: PanLaw::default(),
             rack_routes: HashMap::new(),
+            solo_mode: SoloMode::default(),
+            monitor: MonitorControls::default(),
+            vcas: Vec::new(),
+            vca_assignments: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MonitorControls {
+    pub dim: bool,
+    /// Attenuation applied while `dim` is on.
+    pub dim_db: f32,
+    pub mono: bool,
+}
+
+impl Default for MonitorControls {
+    fn default() -> Self {
+        Self {
+            dim: false,
+            dim_db: 20.0,
+            mono: false,
         }
     }
 }
 
+/// Fader that scales the gain of its member channels without routing their
+/// audio through a bus. A VCA can itself be controlled by a parent VCA, in
+/// which case the gains add in dB along the chain.
+#[derive(Clone, Debug)]
+pub struct Vca {
+    pub id: VcaId,
+    pub name: String,
+    pub gain_db: f32,
+    pub parent: Option<VcaId>,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct RoutingMatrix {
     pub routes: HashMap<ChannelId, HashMap<String, f32>>,
@@ -748,6 +790,150 @@ impl MixerState {
     pub fn set_master_gain(&mut self, gain_db: f32) {
         self.master.gain_db = gain_db;
     }
+
+    pub fn set_solo_mode(&mut self, mode: SoloMode) {
+        self.solo_mode = mode;
+    }
+
+    pub fn set_monitor_dim(&mut self, dim: bool) {
+        self.monitor.dim = dim;
+    }
+
+    /// Sets the dim attenuation; the sign is ignored, so `-20.0` and `20.0`
+    /// both dim by 20 dB.
+    pub fn set_monitor_dim_db(&mut self, dim_db: f32) {
+        self.monitor.dim_db = dim_db.abs().min(90.0);
+    }
+
+    pub fn set_monitor_mono(&mut self, mono: bool) {
+        self.monitor.mono = mono;
+    }
+
+    /// Adds a VCA under the lowest free id. Returns `None` once all
+    /// [`MAX_VCAS`] faders are in use.
+    pub fn create_vca(&mut self, name: impl Into<String>) -> Option<VcaId> {
+        let id = (0..MAX_VCAS as VcaId).find(|id| self.vca(*id).is_none())?;
+        self.vcas.push(Vca {
+            id,
+            name: name.into(),
+            gain_db: 0.0,
+            parent: None,
+        });
+        Some(id)
+    }
+
+    pub fn vca(&self, id: VcaId) -> Option<&Vca> {
+        self.vcas.iter().find(|vca| vca.id == id)
+    }
+
+    /// Puts `channel` under `vca`, replacing any previous assignment.
+    /// Returns `false` if either does not exist.
+    pub fn assign_channel_to_vca(
+        &mut self,
+        channel: ChannelId,
+        vca: VcaId,
+        callbacks: &mut crate::MixerCallbacks,
+    ) -> bool {
+        if self.vca(vca).is_none() || !self.channels.iter().any(|c| c.id == channel) {
+            return false;
+        }
+        self.vca_assignments.insert(channel, vca);
+        (callbacks.assign_vca)(channel, Some(vca));
+        true
+    }
+
+    pub fn unassign_channel_from_vca(
+        &mut self,
+        channel: ChannelId,
+        callbacks: &mut crate::MixerCallbacks,
+    ) {
+        if self.vca_assignments.remove(&channel).is_some() {
+            (callbacks.assign_vca)(channel, None);
+        }
+    }
+
+    /// Nests `vca` under `parent`, or detaches it with `None`. Returns
+    /// `false` if either VCA is unknown or the link would form a loop.
+    pub fn set_vca_parent(
+        &mut self,
+        vca: VcaId,
+        parent: Option<VcaId>,
+        callbacks: &mut crate::MixerCallbacks,
+    ) -> bool {
+        if self.vca(vca).is_none() {
+            return false;
+        }
+        if let Some(parent) = parent {
+            if self.vca(parent).is_none() || self.vca_chain(parent).any(|id| id == vca) {
+                return false;
+            }
+        }
+        if let Some(entry) = self.vcas.iter_mut().find(|entry| entry.id == vca) {
+            entry.parent = parent;
+        }
+        (callbacks.set_vca_parent)(vca, parent);
+        true
+    }
+
+    /// `vca` followed by each of its ancestors.
+    fn vca_chain(&self, vca: VcaId) -> impl Iterator<Item = VcaId> + '_ {
+        let mut next = self.vca(vca).map(|vca| vca.id);
+        std::iter::from_fn(move || {
+            let id = next?;
+            next = self.vca(id).and_then(|vca| vca.parent);
+            Some(id)
+        })
+        .take(self.vcas.len())
+    }
+
+    /// Gain `vca` applies to its members, including every parent VCA.
+    pub fn vca_gain_db(&self, vca: VcaId) -> f32 {
+        self.vca_chain(vca)
+            .filter_map(|id| self.vca(id))
+            .map(|vca| vca.gain_db)
+            .sum()
+    }
+
+    /// Total VCA gain applied to `channel`, or `0.0` if it is unassigned.
+    pub fn channel_vca_gain_db(&self, channel: ChannelId) -> f32 {
+        self.vca_assignments
+            .get(&channel)
+            .map_or(0.0, |vca| self.vca_gain_db(*vca))
+    }
+
+    /// Channel fader plus VCA gain, as the real-time mixer applies it. Only
+    /// the fader is reported through `set_gain_pan`.
+    pub fn effective_gain_db(&self, channel: ChannelId) -> Option<f32> {
+        let gain_db = self.channels.iter().find(|c| c.id == channel)?.gain_db;
+        Some(gain_db + self.channel_vca_gain_db(channel))
+    }
+
+    /// Channels controlled by `vca` directly or through a nested VCA.
+    pub fn vca_members(&self, vca: VcaId) -> Vec<ChannelId> {
+        let mut members: Vec<ChannelId> = self
+            .vca_assignments
+            .iter()
+            .filter(|(_, assigned)| self.vca_chain(**assigned).any(|id| id == vca))
+            .map(|(channel, _)| *channel)
+            .collect();
+        members.sort_unstable();
+        members
+    }
+
+    /// Moves a VCA fader. The real-time mixer scales the members, so their
+    /// channel faders are left alone.
+    pub fn set_vca_gain(
+        &mut self,
+        vca: VcaId,
+        gain_db: f32,
+        callbacks: &mut crate::MixerCallbacks,
+    ) {
+        let Some(entry) = self.vcas.iter_mut().find(|entry| entry.id == vca) else {
+            return;
+        };
+        entry.gain_db = gain_db;
+        (callbacks.set_vca_gain)(vca, gain_db);
+    }
 }
 
 #[cfg(test)]
@@ -789,4 +975,88 @@ mod tests {
         assert_eq!(state.master.gain_db, -3.0);
         assert!(!state.master.dither_on_export);
     }
+
+    #[test]
+    fn nested_vcas_add_up_and_leave_channel_faders_alone() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Debug, PartialEq)]
+        enum Reported {
+            Gain(ChannelId, f32),
+            Vca(VcaId, f32),
+            Assign(ChannelId, Option<VcaId>),
+            Parent(VcaId, Option<VcaId>),
+        }
+
+        let mut state = MixerState::new_default();
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let mut callbacks = crate::MixerCallbacks::noop();
+        let sink = Arc::clone(&reported);
+        callbacks.set_gain_pan = Box::new(move |channel, gain_db, _pan| {
+            sink.lock().unwrap().push(Reported::Gain(channel, gain_db));
+        });
+        let sink = Arc::clone(&reported);
+        callbacks.set_vca_gain = Box::new(move |vca, gain_db| {
+            sink.lock().unwrap().push(Reported::Vca(vca, gain_db));
+        });
+        let sink = Arc::clone(&reported);
+        callbacks.assign_vca = Box::new(move |channel, vca| {
+            sink.lock().unwrap().push(Reported::Assign(channel, vca));
+        });
+        let sink = Arc::clone(&reported);
+        callbacks.set_vca_parent = Box::new(move |vca, parent| {
+            sink.lock().unwrap().push(Reported::Parent(vca, parent));
+        });
+
+        let drums = state.create_vca("Drums").unwrap();
+        let all = state.create_vca("All").unwrap();
+        assert!(state.assign_channel_to_vca(1, drums, &mut callbacks));
+        assert!(state.assign_channel_to_vca(2, all, &mut callbacks));
+        assert!(!state.assign_channel_to_vca(99, drums, &mut callbacks));
+        assert!(state.set_vca_parent(drums, Some(all), &mut callbacks));
+        assert!(!state.set_vca_parent(all, Some(drums), &mut callbacks));
+        assert_eq!(
+            *reported.lock().unwrap(),
+            vec![
+                Reported::Assign(1, Some(drums)),
+                Reported::Assign(2, Some(all)),
+                Reported::Parent(drums, Some(all)),
+            ]
+        );
+        reported.lock().unwrap().clear();
+
+        state.set_vca_gain(drums, -6.0, &mut callbacks);
+        state.set_vca_gain(all, -3.0, &mut callbacks);
+        assert_eq!(state.vca_members(all), vec![1, 2]);
+        assert_eq!(state.effective_gain_db(1), Some(-9.0));
+        assert_eq!(state.effective_gain_db(2), Some(-3.0));
+        assert_eq!(state.effective_gain_db(3), Some(0.0));
+        // The VCA gain is applied once, by the real-time mixer, so no
+        // channel fader is moved.
+        assert_eq!(
+            *reported.lock().unwrap(),
+            vec![Reported::Vca(drums, -6.0), Reported::Vca(all, -3.0)]
+        );
+
+        reported.lock().unwrap().clear();
+        assert!(state.set_vca_parent(drums, None, &mut callbacks));
+        state.unassign_channel_from_vca(2, &mut callbacks);
+        assert_eq!(
+            *reported.lock().unwrap(),
+            vec![Reported::Parent(drums, None), Reported::Assign(2, None)]
+        );
+    }
+
+    #[test]
+    fn vca_ids_stay_within_the_realtime_mixer() {
+        let mut state = MixerState::new_default();
+        let ids: Vec<VcaId> = (0..MAX_VCAS)
+            .map(|index| state.create_vca(format!("VCA {index}")).unwrap())
+            .collect();
+        assert_eq!(ids, (0..MAX_VCAS as VcaId).collect::<Vec<_>>());
+        assert_eq!(state.create_vca("Overflow"), None);
+        assert!(ids
+            .iter()
+            .all(|id| (*id as usize) < crate::MixerConfig::default().max_vcas));
+    }
 }
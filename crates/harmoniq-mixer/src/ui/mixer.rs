@@ -1,4 +1,4 @@
-use crate::state::{InsertSlot, MixerState};
+use crate::state::{InsertSlot, MixerState, SoloMode};
 use egui::{self, Align, ComboBox, Frame, Margin, RichText, Rounding, Slider, Stroke, Vec2};
 use harmoniq_ui::{Fader, HarmoniqPalette, LevelMeter, StateToggleButton};
 
@@ -34,6 +34,38 @@ pub fn render(ui: &mut egui::Ui, props: crate::MixerProps) {
 
                 ui.separator();
 
+                let mut solo_mode = state.solo_mode;
+                ComboBox::from_id_source("solo_mode_picker")
+                    .selected_text(format!("Solo: {}", solo_mode_label(solo_mode)))
+                    .show_ui(ui, |ui| {
+                        for mode in [SoloMode::Sip, SoloMode::Afl, SoloMode::Pfl] {
+                            ui.selectable_value(&mut solo_mode, mode, solo_mode_label(mode));
+                        }
+                    });
+                if solo_mode != state.solo_mode {
+                    state.set_solo_mode(solo_mode);
+                    (callbacks.set_solo_mode)(solo_mode);
+                }
+
+                let mut dim = state.monitor.dim;
+                if ui
+                    .add(StateToggleButton::new(&mut dim, "Dim", palette))
+                    .changed()
+                {
+                    state.set_monitor_dim(dim);
+                    (callbacks.set_monitor_dim)(dim, state.monitor.dim_db);
+                }
+                let mut mono = state.monitor.mono;
+                if ui
+                    .add(StateToggleButton::new(&mut mono, "Mono", palette))
+                    .changed()
+                {
+                    state.set_monitor_mono(mono);
+                    (callbacks.set_monitor_mono)(mono);
+                }
+
+                ui.separator();
+
                 if ui
                     .button(RichText::new("Reset clips").color(palette.text_primary))
                     .clicked()
@@ -79,6 +111,14 @@ pub fn render(ui: &mut egui::Ui, props: crate::MixerProps) {
         });
 }
 
+fn solo_mode_label(mode: SoloMode) -> &'static str {
+    match mode {
+        SoloMode::Sip => "In place",
+        SoloMode::Afl => "AFL",
+        SoloMode::Pfl => "PFL",
+    }
+}
+
 fn strip_ui(
     ui: &mut egui::Ui,
     channel_index: usize,
@@ -89,6 +129,7 @@ fn strip_ui(
     is_master: bool,
 ) {
     let mut channel = state.channels[channel_index].clone();
+    let vca_db = state.channel_vca_gain_db(channel.id);
 
     let bg = if is_master {
         palette.mixer_strip_bg.gamma_multiply(0.5)
@@ -111,7 +152,7 @@ fn strip_ui(
                 ui.add_space(6.0);
 
                 ui.horizontal(|ui| {
-                    meter_and_fader(ui, &mut channel, palette, callbacks);
+                    meter_and_fader(ui, &mut channel, vca_db, palette, callbacks);
                     ui.add_space(6.0);
                     insert_column(ui, &mut channel, palette, callbacks);
                     ui.add_space(4.0);
@@ -124,7 +165,7 @@ fn strip_ui(
 
                 ui.add_space(4.0);
 
-                pan_row(ui, &mut channel, palette, callbacks);
+                pan_row(ui, &mut channel, vca_db, palette, callbacks);
             });
         });
 
@@ -172,6 +213,7 @@ fn strip_header(
 fn meter_and_fader(
     ui: &mut egui::Ui,
     channel: &mut crate::state::Channel,
+    vca_db: f32,
     palette: &HarmoniqPalette,
     callbacks: &mut crate::MixerCallbacks,
 ) {
@@ -199,7 +241,7 @@ fn meter_and_fader(
             .changed()
         {
             channel.gain_db = gain;
-            (callbacks.set_gain_pan)(channel.id, gain, channel.pan);
+            (callbacks.set_gain_pan)(channel.id, gain + vca_db, channel.pan);
         }
     });
 }
@@ -371,6 +413,7 @@ fn control_row(
 fn pan_row(
     ui: &mut egui::Ui,
     channel: &mut crate::state::Channel,
+    vca_db: f32,
     palette: &HarmoniqPalette,
     callbacks: &mut crate::MixerCallbacks,
 ) {
@@ -382,7 +425,7 @@ fn pan_row(
             .changed()
         {
             channel.pan = pan;
-            (callbacks.set_gain_pan)(channel.id, channel.gain_db, pan);
+            (callbacks.set_gain_pan)(channel.id, channel.gain_db + vca_db, pan);
         }
 
         ui.add_space(8.0);
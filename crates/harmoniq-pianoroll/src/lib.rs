@@ -11,7 +11,7 @@ pub mod theme;
 pub mod tools;
 pub mod transport;
 
-pub use tools::NotePreview;
+pub use tools::{ArpPattern, NotePreview, OverlapMode, StrumDir};
 
 use std::ops::RangeInclusive;
 
@@ -21,7 +21,9 @@ use egui::{
 };
 use model::{Clip, Edit, EditorState, Note, QuantizePreset, SnapUnit};
 use theme::{Spacing, Theme};
-use tools::{HitNote, PointerPosition, Tool, ToolController, ToolOutput};
+use tools::{
+    HitNote, NoteClipboard, PlayheadFollow, PointerPosition, Tool, ToolController, ToolOutput,
+};
 use transport::ruler_ui;
 
 /// Stateful piano roll widget. The widget owns the `EditorState` so it can
@@ -41,6 +43,12 @@ pub struct PianoRoll {
     gesture_edits: Vec<Edit>,
     history_snapshot: Option<Clip>,
     history_dirty: bool,
+    follow: PlayheadFollow,
+    /// Grid width from the last frame, used to page-follow the playhead.
+    grid_width: f32,
+    /// Set while the user scrolls or pans the view.
+    user_scrolling: bool,
+    clipboard: NoteClipboard,
 }
 
 impl PianoRoll {
@@ -64,6 +72,10 @@ impl PianoRoll {
             gesture_edits: Vec::new(),
             history_snapshot: None,
             history_dirty: false,
+            follow: PlayheadFollow::default(),
+            grid_width: 0.0,
+            user_scrolling: false,
+            clipboard: NoteClipboard::default(),
         }
     }
 
@@ -94,6 +106,20 @@ impl PianoRoll {
         );
     }
 
+    /// Moves the playhead and, with follow enabled, scrolls the grid to keep
+    /// it in view. Following pauses while the user scrolls or pans.
+    pub fn set_playhead(&mut self, ppq: i64) {
+        self.state.playhead_ppq = ppq;
+        if !self.state.follow_playhead || self.user_scrolling {
+            self.follow.cancel();
+            return;
+        }
+        let playhead_x = ppq as f32 / self.state.ppq() as f32 * self.state.zoom_x;
+        self.state.scroll_px.x =
+            self.follow
+                .update(self.state.scroll_px.x, playhead_x, self.grid_width);
+    }
+
     /// Drains the edits accumulated during the previous call to [`PianoRoll::ui`].
     pub fn take_edits(&mut self) -> Vec<Edit> {
         self.pending_edits.drain(..).collect()
@@ -135,6 +161,7 @@ impl PianoRoll {
             Rect::from_min_size(rect.min, vec2(self.spacing.keyboard_width, rect.height()));
         let grid_rect =
             Rect::from_min_max(pos2(keyboard_rect.right(), rect.top()), rect.right_bottom());
+        self.grid_width = grid_rect.width();
 
         self.handle_input(ui, keyboard_rect, grid_rect, &response);
         self.paint_keyboard(ui.painter_at(keyboard_rect), keyboard_rect);
@@ -158,6 +185,86 @@ impl PianoRoll {
         }
     }
 
+    /// Arpeggiates the held chords of the selection, or the whole clip, as
+    /// one undo step. See [`tools::arpeggiate`].
+    pub fn arpeggiate(&mut self, pattern: ArpPattern, rate: SnapUnit, gate: f32) {
+        self.apply_batch(|clip| tools::arpeggiate(clip, pattern, rate, gate));
+    }
+
+    /// Resolves overlapping same-pitch notes as one undo step. See
+    /// [`tools::fix_overlaps`].
+    pub fn fix_overlaps(&mut self, mode: OverlapMode) {
+        self.apply_batch(|clip| tools::fix_overlaps(clip, mode));
+    }
+
+    /// Sets the velocities of the time-sorted selection, or the whole clip,
+    /// on a straight line from `start_vel` to `end_vel` as one undo step.
+    pub fn set_velocity_ramp(&mut self, start_vel: u8, end_vel: u8) {
+        self.apply_batch(|clip| tools::velocity_ramp(clip, start_vel, end_vel));
+    }
+
+    /// Jitters start times by up to `timing_ppq` and velocities by up to
+    /// `velocity` as one undo step. The same seed always gives the same
+    /// result. See [`tools::humanize`].
+    pub fn humanize(&mut self, timing_ppq: i64, velocity: u8, rng_seed: u64) {
+        self.apply_batch(|clip| tools::humanize(clip, timing_ppq, velocity, rng_seed));
+    }
+
+    /// Staggers the chords of the selection, or the whole clip, as one undo
+    /// step. See [`tools::strum`].
+    pub fn strum(&mut self, offset_ppq: i64, direction: StrumDir) {
+        self.apply_batch(|clip| tools::strum(clip, offset_ppq, direction));
+    }
+
+    /// Quantizes the clip with the editor's triplet setting as one undo
+    /// step, emitting the moved notes as updates.
+    pub fn quantize(&mut self, preset: &QuantizePreset) {
+        let triplets = self.state.triplets;
+        self.apply_batch(|clip| {
+            let ppq = clip.ppq();
+            let moved = model::quantize::apply(
+                &mut clip.notes,
+                &mut clip.quantize_origins,
+                preset,
+                preset.strength,
+                preset.swing,
+                preset.range.clone(),
+                ppq,
+                triplets,
+            );
+            clip.notes
+                .iter()
+                .filter(|note| moved.contains(&note.id))
+                .map(tools::update_edit)
+                .collect()
+        });
+    }
+
+    /// Runs a batch tool against the clip through the same history path as
+    /// pointer gestures and forwards the resulting edits.
+    fn apply_batch(&mut self, tool: impl FnOnce(&mut Clip) -> Vec<Edit>) {
+        self.begin_history_snapshot();
+        let edits = tool(&mut self.state.clip);
+        if edits.is_empty() {
+            self.history_snapshot = None;
+            return;
+        }
+        self.state.clip.sort_notes();
+        self.state.selection = self
+            .state
+            .clip
+            .notes
+            .iter()
+            .filter(|note| note.selected)
+            .map(|note| note.id)
+            .collect();
+        self.history_dirty = true;
+        self.pending_edits.extend(edits.clone());
+        self.gesture_edits.extend(edits);
+        self.commit_history_snapshot();
+        self.gesture_edits.clear();
+    }
+
     fn top_toolbar(&mut self, ui: &mut Ui) {
         ui.horizontal(|ui| {
             let tool_buttons = [
@@ -205,6 +312,8 @@ impl PianoRoll {
                 });
             ui.toggle_value(&mut self.state.triplets, "Triplet");
             ui.toggle_value(&mut self.state.follow_playhead, "Follow");
+            ui.toggle_value(&mut self.state.constrain_to_scale, "Scale Lock")
+                .on_hover_text("Snap note pitches to the scale (hold Alt to bypass)");
             ui.separator();
             let mut loop_beats = self.state.clip.loop_len_ppq as f32 / self.state.ppq() as f32;
             let len_response = ui
@@ -235,22 +344,34 @@ impl PianoRoll {
                 egui::Slider::new(&mut self.state.quantize_strength, 0.0..=1.0).text("Strength"),
             );
             ui.add(egui::Slider::new(&mut self.state.quantize_swing, -0.75..=0.75).text("Swing"));
+            ui.toggle_value(&mut self.state.quantize_iterative, "Iterative")
+                .on_hover_text("Each press moves notes Strength of the way to the grid");
             if ui.button("Apply Q").clicked() {
-                if let Some(snap) = self.state.snap.or(Some(SnapUnit::Grid(4))) {
-                    let preset = QuantizePreset {
-                        name: "Grid".to_owned(),
-                        snap,
-                        strength: self.state.quantize_strength,
-                        swing: self.state.quantize_swing,
-                        range: RangeInclusive::new(i64::MIN, i64::MAX),
-                        iterative: false,
-                    };
-                    self.pending_edits.push(Edit::Quantize {
-                        preset,
-                        strength: self.state.quantize_strength,
-                        swing: self.state.quantize_swing,
-                    });
+                let preset = QuantizePreset {
+                    name: "Grid".to_owned(),
+                    snap: self.state.snap.unwrap_or(SnapUnit::Grid(4)),
+                    strength: self.state.quantize_strength,
+                    swing: self.state.quantize_swing,
+                    range: RangeInclusive::new(i64::MIN, i64::MAX),
+                    iterative: self.state.quantize_iterative,
+                };
+                self.quantize(&preset);
+            }
+            let mut overlap_fix = None;
+            ui.menu_button("Fix Overlaps", |ui| {
+                for (label, mode) in [
+                    ("Trim to Next", OverlapMode::TrimToNext),
+                    ("Remove Duplicates", OverlapMode::RemoveDuplicates),
+                    ("Merge", OverlapMode::Merge),
+                ] {
+                    if ui.button(label).clicked() {
+                        overlap_fix = Some(mode);
+                        ui.close_menu();
+                    }
                 }
+            });
+            if let Some(mode) = overlap_fix {
+                self.fix_overlaps(mode);
             }
         });
     }
@@ -340,6 +461,7 @@ impl PianoRoll {
     }
 
     fn handle_scroll_and_zoom(&mut self, ui: &Ui, grid_rect: Rect) {
+        self.user_scrolling = self.tool_controller.is_panning();
         ui.input(|input| {
             let scroll = input.smooth_scroll_delta;
             if scroll == egui::Vec2::ZERO {
@@ -355,6 +477,7 @@ impl PianoRoll {
             } else {
                 self.state.scroll_px.x = (self.state.scroll_px.x - scroll.x).max(0.0);
                 self.state.scroll_px.y += scroll.y;
+                self.user_scrolling = true;
             }
         });
         if self.state.scroll_px.y < -grid_rect.height() {
@@ -389,13 +512,17 @@ impl PianoRoll {
 
     fn finalize_marquee(&mut self, grid_rect: Rect) {
         if let Some(rect) = self.marquee_rect.take() {
-            let mut selection = Vec::new();
+            let mut hits = Vec::new();
             for note in &self.state.clip.notes {
                 let note_rect = self.note_rect(note, grid_rect);
                 if rect.intersects(note_rect) {
-                    selection.push(note.id);
+                    hits.push(note.id);
                 }
             }
+            let selection = self
+                .tool_controller
+                .marquee_selection(&hits)
+                .unwrap_or(hits);
             self.state.clear_selection();
             for id in selection {
                 self.state.select_note(id, true);
@@ -430,6 +557,9 @@ impl PianoRoll {
         let mut delete = false;
         let mut undo = false;
         let mut redo = false;
+        let mut copy = false;
+        let mut paste = false;
+        let mut duplicate = false;
         let mut modifiers = egui::Modifiers::default();
 
         response.ctx.input(|input| {
@@ -446,8 +576,40 @@ impl PianoRoll {
                 || (input.key_pressed(egui::Key::Z)
                     && (input.modifiers.ctrl || input.modifiers.command)
                     && input.modifiers.shift);
+            // Integrations turn the platform shortcuts into clipboard events
+            // instead of key presses.
+            let command = input.modifiers.ctrl || input.modifiers.command;
+            copy = (command && input.key_pressed(egui::Key::C))
+                || input
+                    .events
+                    .iter()
+                    .any(|event| matches!(event, egui::Event::Copy));
+            paste = (command && input.key_pressed(egui::Key::V))
+                || input
+                    .events
+                    .iter()
+                    .any(|event| matches!(event, egui::Event::Paste(_)));
+            duplicate = command && input.key_pressed(egui::Key::D);
         });
 
+        if copy {
+            let clipboard = NoteClipboard::copy(&self.state.clip);
+            if !clipboard.is_empty() {
+                self.clipboard = clipboard;
+            }
+        }
+        if paste && !self.clipboard.is_empty() {
+            let at = self
+                .tool_controller
+                .snapper
+                .snap_ppq(self.state.playhead_ppq);
+            let clipboard = self.clipboard.clone();
+            self.apply_batch(|clip| clipboard.paste(clip, at));
+        }
+        if duplicate {
+            self.apply_batch(tools::duplicate);
+        }
+
         if undo {
             let before = self.state.clip.clone();
             if self.state.undo() {
@@ -624,6 +786,16 @@ impl PianoRoll {
                 }
             }
         }
+        let playhead_x = self.time_to_x(rect, self.state.playhead_ppq);
+        if playhead_x >= rect.left() && playhead_x <= rect.right() {
+            self.grid_shapes.push(Shape::line_segment(
+                [
+                    pos2(playhead_x, rect.top()),
+                    pos2(playhead_x, rect.bottom()),
+                ],
+                self.theme.playhead,
+            ));
+        }
         painter.extend(self.grid_shapes.drain(..));
     }
 
@@ -66,6 +66,23 @@ impl Scale {
         let root = (self.tonic as usize) % 12;
         pattern[(12 + semitone - root) % 12]
     }
+
+    /// Closest pitch in the scale, preferring the lower one on a tie.
+    pub fn nearest(&self, pitch: u8) -> u8 {
+        for distance in 0..12u8 {
+            if let Some(below) = pitch.checked_sub(distance) {
+                if self.contains(below) {
+                    return below;
+                }
+            }
+            if let Some(above) = pitch.checked_add(distance).filter(|p| *p <= 127) {
+                if self.contains(above) {
+                    return above;
+                }
+            }
+        }
+        pitch
+    }
 }
 
 /// Diatonic mode.
@@ -102,6 +119,18 @@ pub struct Clip {
     pub notes: Vec<Note>,
     pub loop_start_ppq: i64,
     pub loop_len_ppq: i64,
+    /// Pre-quantize positions of quantized notes, by note id.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub(crate) quantize_origins: HashMap<u64, QuantizeOrigin>,
+}
+
+/// Where a note started before it was first quantized, and where the last
+/// quantize left it. A note moved since then is measured from its new
+/// position instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuantizeOrigin {
+    pub original_ppq: i64,
+    pub quantized_ppq: i64,
 }
 
 impl Clip {
@@ -111,6 +140,7 @@ impl Clip {
             notes: Vec::new(),
             loop_start_ppq: 0,
             loop_len_ppq: (ppq as i64) * 4,
+            quantize_origins: HashMap::new(),
         }
     }
 
@@ -126,6 +156,25 @@ impl Clip {
         self.notes.sort_by_key(|n| (n.start_ppq, n.pitch));
     }
 
+    /// Quantizes note starts in `preset.range` to the preset's grid without
+    /// triplets and returns the ids of the notes that moved. See
+    /// [`QuantizePreset::iterative`].
+    pub fn quantize(&mut self, preset: &QuantizePreset) -> Vec<u64> {
+        let ppq = self.ppq();
+        let moved = quantize::apply(
+            &mut self.notes,
+            &mut self.quantize_origins,
+            preset,
+            preset.strength,
+            preset.swing,
+            preset.range.clone(),
+            ppq,
+            false,
+        );
+        self.sort_notes();
+        moved
+    }
+
     pub fn note_map(&self) -> HashMap<u64, usize> {
         let mut out = HashMap::with_capacity(self.notes.len());
         for (index, note) in self.notes.iter().enumerate() {
@@ -189,9 +238,16 @@ impl Lane {
 pub struct QuantizePreset {
     pub name: String,
     pub snap: SnapUnit,
+    /// Fraction of each note's original distance to the grid that a pass
+    /// covers.
     pub strength: f32,
+    /// Delay of every odd subdivision, as a fraction of half a step.
     pub swing: f32,
     pub range: RangeInclusive<i64>,
+    /// Iterative passes move notes a further `strength` of the way each
+    /// time, so repeating one tightens timing gradually until notes land on
+    /// the grid. Repeating a one-shot pass leaves notes where the first one
+    /// put them.
     pub iterative: bool,
 }
 
@@ -248,6 +304,8 @@ pub struct EditorState {
     pub timebase: Timebase,
     pub key_sig: (u8, u8),
     pub scale_highlight: Option<Scale>,
+    /// Snap dragged and drawn pitches to [`EditorState::constraint_scale`].
+    pub constrain_to_scale: bool,
     pub ghost_clip: Option<Clip>,
     pub selection: Vec<u64>,
     pub tool: Tool,
@@ -256,6 +314,8 @@ pub struct EditorState {
     pub scroll_px: Vec2,
     pub quantize_strength: f32,
     pub quantize_swing: f32,
+    /// "Apply Q" moves notes `quantize_strength` of the way per press.
+    pub quantize_iterative: bool,
     pub step_input: bool,
     pub follow_zoom: bool,
     history: History,
@@ -273,6 +333,7 @@ impl EditorState {
             timebase: Timebase::Musical,
             key_sig: (0, 0),
             scale_highlight: None,
+            constrain_to_scale: false,
             ghost_clip: None,
             selection: Vec::new(),
             tool: Tool::Arrow,
@@ -281,6 +342,7 @@ impl EditorState {
             scroll_px: Vec2::ZERO,
             quantize_strength: 1.0,
             quantize_swing: 0.0,
+            quantize_iterative: false,
             step_input: false,
             follow_zoom: true,
             history: History::new(200),
@@ -295,6 +357,24 @@ impl EditorState {
         self.clip.beats_per_bar()
     }
 
+    /// Scale edited pitches snap to while `constrain_to_scale` is on: the
+    /// highlighted scale, or else the key signature read as `(tonic, mode)`
+    /// with mode `1` for minor.
+    pub fn constraint_scale(&self) -> Option<Scale> {
+        if !self.constrain_to_scale {
+            return None;
+        }
+        let key = Scale {
+            tonic: self.key_sig.0 % 12,
+            mode: if self.key_sig.1 == 1 {
+                ScaleMode::Minor
+            } else {
+                ScaleMode::Major
+            },
+        };
+        Some(self.scale_highlight.clone().unwrap_or(key))
+    }
+
     pub fn selected_notes_mut(&mut self) -> Vec<&mut Note> {
         let ids: HashSet<u64> = self.selection.iter().copied().collect();
         self.clip
@@ -383,6 +463,7 @@ impl EditorState {
                     let ppq = self.ppq();
                     quantize::apply(
                         &mut self.clip.notes,
+                        &mut self.clip.quantize_origins,
                         preset,
                         *strength,
                         *swing,
@@ -524,55 +605,60 @@ pub mod grid {
         }
 
         pub fn snap_ppq(&self, value: i64) -> i64 {
-            let snap = match self.snap {
-                None => return value,
-                Some(s) => s,
-            };
-            let base_div = snap.divisions_per_beat();
-            let mut step_ppq = (self.ppq_per_beat as i64) / (base_div as i64);
-            if self.triplets {
-                step_ppq = (self.ppq_per_beat as i64) * 2 / (base_div as i64 * 3).max(1);
+            if self.snap.is_none() {
+                return value;
             }
-            step_ppq = step_ppq.max(1);
-            let remainder = value % step_ppq;
-            let mut snapped = if remainder.abs() * 2 >= step_ppq.abs() {
-                value + (step_ppq - remainder)
+            let step_ppq = self.step_ppq();
+            let swing_offset = if matches!(self.timebase, Timebase::Musical) {
+                // Swing delays every odd subdivision by up to half a step.
+                (self.swing.clamp(-1.0, 1.0) * step_ppq as f32 * 0.5) as i64
             } else {
-                value - remainder
+                0
             };
-            if matches!(self.timebase, Timebase::Musical) {
-                // Apply swing: shift every other subdivision forward by swing ratio.
-                if self.swing.abs() > f32::EPSILON {
-                    let beat_idx = ((snapped / step_ppq) % 2).abs();
-                    if beat_idx == 1 {
-                        let swing_offset =
-                            (self.swing.clamp(-1.0, 1.0) * step_ppq as f32 * 0.5) as i64;
-                        snapped += swing_offset;
-                    }
-                }
-            }
-            snapped
+            let grid_point = |index: i64| {
+                let offset = if index.rem_euclid(2) == 1 {
+                    swing_offset
+                } else {
+                    0
+                };
+                index * step_ppq + offset
+            };
+            // The swung grid is no longer evenly spaced, so the nearest point
+            // may be either neighbour of the straight one. Ties round up.
+            let base = value.div_euclid(step_ppq);
+            (base - 1..=base + 2)
+                .map(grid_point)
+                .min_by_key(|point| ((point - value).abs(), -*point))
+                .unwrap_or(value)
         }
     }
 }
 
 /// Quantize operations on notes.
 pub mod quantize {
+    use std::collections::{HashMap, HashSet};
     use std::ops::RangeInclusive;
 
-    use super::{grid::Snapper, Note, QuantizePreset, Timebase};
+    use super::{grid::Snapper, Note, QuantizeOrigin, QuantizePreset, Timebase};
 
+    /// Moves note starts in `range` toward the grid and returns the ids of
+    /// the notes that moved. Distances are measured from each note's
+    /// position in `origins`, so passes do not compound rounding or drift
+    /// towards another grid point.
+    #[allow(clippy::too_many_arguments)]
     pub fn apply(
         notes: &mut [Note],
+        origins: &mut HashMap<u64, QuantizeOrigin>,
         preset: &QuantizePreset,
         strength: f32,
         swing: f32,
         range: RangeInclusive<i64>,
         ppq_per_beat: i32,
         triplets: bool,
-    ) {
+    ) -> Vec<u64> {
+        let mut moved = Vec::new();
         if notes.is_empty() {
-            return;
+            return moved;
         }
         let snapper = Snapper::new(
             ppq_per_beat,
@@ -581,19 +667,45 @@ pub mod quantize {
             swing,
             Timebase::Musical,
         );
+        let ids: HashSet<u64> = notes.iter().map(|note| note.id).collect();
+        origins.retain(|id, _| ids.contains(id));
         let amount = strength.clamp(0.0, 1.0);
         for note in notes {
-            if !range.contains(&note.start_ppq) {
+            let original = origins
+                .get(&note.id)
+                .filter(|origin| origin.quantized_ppq == note.start_ppq)
+                .map_or(note.start_ppq, |origin| origin.original_ppq);
+            if !range.contains(&original) {
                 continue;
             }
-            let snapped = snapper.snap_ppq(note.start_ppq);
-            let delta = snapped - note.start_ppq;
-            let applied = (delta as f32 * amount).round() as i64;
-            note.start_ppq += applied;
+            let target = snapper.snap_ppq(original);
+            let step = ((target - original) as f32 * amount).round() as i64;
+            let start = if preset.iterative {
+                let next = note.start_ppq + step;
+                if target >= original {
+                    next.min(target)
+                } else {
+                    next.max(target)
+                }
+            } else {
+                original + step
+            };
+            origins.insert(
+                note.id,
+                QuantizeOrigin {
+                    original_ppq: original,
+                    quantized_ppq: start,
+                },
+            );
             if note.dur_ppq < 1 {
                 note.dur_ppq = 1;
             }
+            if start != note.start_ppq {
+                note.start_ppq = start;
+                moved.push(note.id);
+            }
         }
+        moved
     }
 }
 
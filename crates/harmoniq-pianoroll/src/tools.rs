@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use egui::{Modifiers, Pos2, Rect, Vec2};
 
-use crate::model::{grid::Snapper, Clip, Edit, EditorState, Note, SnapUnit};
+use crate::model::{grid::Snapper, Clip, Edit, EditorState, Note, Scale, SnapUnit};
 
 /// Tools available in the piano roll.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -46,6 +46,50 @@ pub struct NotePreview {
     pub channel: u8,
 }
 
+/// How a marquee combines with the selection it started from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarqueeMode {
+    /// No modifier: the marquee becomes the selection.
+    Replace,
+    /// Shift: the marquee adds to the selection.
+    Add,
+    /// Ctrl/Cmd: notes inside the marquee flip their selection state.
+    Toggle,
+}
+
+impl MarqueeMode {
+    pub fn from_modifiers(modifiers: Modifiers) -> Self {
+        if modifiers.command || modifiers.ctrl {
+            MarqueeMode::Toggle
+        } else if modifiers.shift {
+            MarqueeMode::Add
+        } else {
+            MarqueeMode::Replace
+        }
+    }
+
+    /// Selection resulting from marqueeing `hits` over `base`.
+    pub fn combine(self, base: &[u64], hits: &[u64]) -> Vec<u64> {
+        match self {
+            MarqueeMode::Replace => hits.to_vec(),
+            MarqueeMode::Add => {
+                let mut selection = base.to_vec();
+                selection.extend(hits.iter().filter(|id| !base.contains(id)));
+                selection
+            }
+            MarqueeMode::Toggle => {
+                let mut selection: Vec<u64> = base
+                    .iter()
+                    .copied()
+                    .filter(|id| !hits.contains(id))
+                    .collect();
+                selection.extend(hits.iter().filter(|id| !base.contains(id)));
+                selection
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 enum GestureState {
     DragNotes {
@@ -60,6 +104,9 @@ enum GestureState {
     Marquee {
         start_pos: Pos2,
         current: Pos2,
+        mode: MarqueeMode,
+        /// Selection when the marquee started.
+        base: Vec<u64>,
     },
     Pan {
         start_pos: Pos2,
@@ -119,7 +166,9 @@ impl ToolController {
         let mut output = ToolOutput::default();
         match self.active {
             Tool::Arrow => {
-                if modifiers.command || modifiers.ctrl {
+                // Ctrl/Cmd is taken by the toggling marquee, so empty-space
+                // panning uses Alt.
+                if hit.is_none() && modifiers.alt {
                     self.gesture = Some(GestureState::Pan {
                         start_pos: pointer.pos,
                         start_scroll: ctx.scroll_px,
@@ -173,23 +222,30 @@ impl ToolController {
                         });
                     }
                 } else {
+                    let mode = MarqueeMode::from_modifiers(modifiers);
                     self.gesture = Some(GestureState::Marquee {
                         start_pos: pointer.pos,
                         current: pointer.pos,
+                        mode,
+                        base: ctx.selection.clone(),
                     });
-                    ctx.clear_selection();
-                    output.selection = Some(Vec::new());
+                    if mode == MarqueeMode::Replace {
+                        ctx.clear_selection();
+                        output.selection = Some(Vec::new());
+                    }
                 }
             }
             Tool::Draw => {
                 let new_id = ctx.next_note_id();
                 let length = (ctx.ppq() / 4).max(1) as i64;
                 let start = self.snapper.snap_ppq(pointer.time_ppq);
+                let pitch =
+                    constrain_pitch(ctx.constraint_scale().as_ref(), pointer.pitch, modifiers);
                 let mut note = Note {
                     id: new_id,
                     start_ppq: start,
                     dur_ppq: length,
-                    pitch: pointer.pitch,
+                    pitch,
                     vel: 100,
                     chan: 0,
                     selected: false,
@@ -205,7 +261,7 @@ impl ToolController {
                 });
                 output.edits.push(Edit::Add(note));
                 output.preview = Some(NotePreview {
-                    pitch: pointer.pitch,
+                    pitch,
                     velocity: 100,
                     channel: 0,
                 });
@@ -264,17 +320,27 @@ impl ToolController {
                         self.snapper.snap_ppq(pointer.time_ppq)
                             - self.snapper.snap_ppq(start_pointer.time_ppq)
                     };
-                    let delta_pitch = if modifiers.alt {
+                    // Ctrl/Cmd keeps the pitch. A vertical move is snapped once, at
+                    // the grabbed pitch, so a chord keeps its intervals and notes
+                    // moved only in time keep theirs.
+                    let delta_pitch = if modifiers.command
+                        || modifiers.ctrl
+                        || pointer.pitch == start_pointer.pitch
+                    {
                         0
                     } else {
-                        (pointer.pitch as i32 - start_pointer.pitch as i32) as i64
+                        let target = constrain_pitch(
+                            ctx.constraint_scale().as_ref(),
+                            pointer.pitch,
+                            modifiers,
+                        );
+                        target as i64 - start_pointer.pitch as i64
                     };
                     for id in ids.iter().copied() {
                         if let Some(note) = ctx.clip.notes.iter_mut().find(|n| n.id == id) {
                             if let Some((start_ppq, pitch)) = origin.get(&id) {
                                 note.start_ppq = start_ppq + delta_time;
-                                let new_pitch = (*pitch as i64 + delta_pitch).clamp(0, 127) as u8;
-                                note.pitch = new_pitch;
+                                note.pitch = (*pitch as i64 + delta_pitch).clamp(0, 127) as u8;
                                 output.edits.push(Edit::Update {
                                     id,
                                     start_ppq: note.start_ppq,
@@ -289,6 +355,8 @@ impl ToolController {
                 }
                 GestureState::DrawNote { id, start_pointer } => {
                     let len = (pointer.time_ppq - start_pointer.time_ppq).max(1);
+                    let pitch =
+                        constrain_pitch(ctx.constraint_scale().as_ref(), pointer.pitch, modifiers);
                     if let Some(note) = ctx.clip.notes.iter_mut().find(|n| n.id == *id) {
                         note.dur_ppq = if modifiers.shift {
                             len
@@ -297,7 +365,7 @@ impl ToolController {
                             let snapped_end = self.snapper.snap_ppq(pointer.time_ppq);
                             (snapped_end - snapped_start).max(1)
                         };
-                        note.pitch = pointer.pitch;
+                        note.pitch = pitch;
                         output.edits.push(Edit::Update {
                             id: *id,
                             start_ppq: note.start_ppq,
@@ -308,7 +376,9 @@ impl ToolController {
                         });
                     }
                 }
-                GestureState::Marquee { start_pos, current } => {
+                GestureState::Marquee {
+                    start_pos, current, ..
+                } => {
                     *current = pointer.pos;
                     output.marquee = Some(Rect::from_two_pos(*start_pos, *current));
                 }
@@ -350,20 +420,510 @@ impl ToolController {
         output
     }
 
+    /// Selection for a marquee gesture in progress that covers `hits`,
+    /// combined with the selection it started from.
+    pub fn marquee_selection(&self, hits: &[u64]) -> Option<Vec<u64>> {
+        match &self.gesture {
+            Some(GestureState::Marquee { mode, base, .. }) => Some(mode.combine(base, hits)),
+            _ => None,
+        }
+    }
+
+    /// Whether the user is dragging the view around.
+    pub fn is_panning(&self) -> bool {
+        matches!(self.gesture, Some(GestureState::Pan { .. }))
+    }
+
     pub fn on_pointer_released(&mut self) {
         self.gesture = None;
     }
 }
 
+/// Snaps `pitch` into `scale` unless Alt is held.
+fn constrain_pitch(scale: Option<&Scale>, pitch: u8, modifiers: Modifiers) -> u8 {
+    match scale {
+        Some(scale) if !modifiers.alt => scale.nearest(pitch),
+        _ => pitch,
+    }
+}
+
 /// Convert a pointer position into PPQ time based on zoom and scroll.
 pub fn pointer_to_ppq(clip: &Clip, zoom_x: f32, scroll_x: f32, pointer: f32) -> i64 {
     let beats = ((pointer + scroll_x) / zoom_x).max(0.0);
     (beats * clip.ppq() as f32).round() as i64
 }
 
+/// Share of the view kept to the left of the playhead after a page scroll.
+const FOLLOW_LEAD: f32 = 0.05;
+/// Largest scroll step per update, as a share of the view width.
+const FOLLOW_MAX_STEP: f32 = 0.25;
+
+/// Page-scrolls the view to keep the playhead visible.
+///
+/// Positions are content pixels, i.e. time times `zoom_x` with no scroll
+/// applied. Once the playhead leaves the view the scroll heads for a page
+/// that starts just before it, moving at most a quarter of the view per
+/// update so the jump reads as motion rather than a cut.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayheadFollow {
+    target: Option<f32>,
+}
+
+impl PlayheadFollow {
+    /// Returns the new horizontal scroll for a playhead at `playhead_x`.
+    pub fn update(&mut self, scroll_x: f32, playhead_x: f32, view_width: f32) -> f32 {
+        if view_width <= 0.0 {
+            return scroll_x;
+        }
+        let view_start = self.target.unwrap_or(scroll_x);
+        if playhead_x < view_start || playhead_x > view_start + view_width {
+            self.target = Some((playhead_x - view_width * FOLLOW_LEAD).max(0.0));
+        }
+        let Some(target) = self.target else {
+            return scroll_x;
+        };
+        let max_step = view_width * FOLLOW_MAX_STEP;
+        let next = scroll_x + (target - scroll_x).clamp(-max_step, max_step);
+        if (target - next).abs() < 0.5 {
+            self.target = None;
+            return target;
+        }
+        next
+    }
+
+    /// Drops any page scroll in flight, e.g. when the user takes over.
+    pub fn cancel(&mut self) {
+        self.target = None;
+    }
+}
+
 /// Convert a Y coordinate to MIDI pitch.
 pub fn pointer_to_pitch(zoom_y: f32, scroll_y: f32, rect: Rect, pointer: f32) -> u8 {
     let local = rect.bottom() - pointer + scroll_y;
     let pitch = (local / zoom_y).round() as i32;
     pitch.clamp(0, 127) as u8
 }
+
+/// Note order used by [`arpeggiate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArpPattern {
+    Up,
+    Down,
+    /// Up then back down, without repeating the top or bottom note.
+    UpDown,
+    /// Pseudo-random order, seeded from the chord position so the result is
+    /// repeatable.
+    Random,
+    /// The order the chord's notes were entered in.
+    AsPlayed,
+}
+
+/// Notes that are either selected or, with nothing selected, the whole clip.
+fn target_indices(clip: &Clip) -> Vec<usize> {
+    let selected: Vec<usize> = (0..clip.notes.len())
+        .filter(|&index| clip.notes[index].selected)
+        .collect();
+    if selected.is_empty() {
+        (0..clip.notes.len()).collect()
+    } else {
+        selected
+    }
+}
+
+/// Length of one `rate` step in the clip's ticks.
+pub fn snap_step_ppq(clip: &Clip, rate: SnapUnit) -> i64 {
+    let ppq = clip.ppq() as i64;
+    match rate {
+        SnapUnit::Bar => ppq * clip.beats_per_bar() as i64,
+        SnapUnit::Beat => ppq,
+        SnapUnit::Grid(div) => (ppq / div.max(1) as i64).max(1),
+    }
+}
+
+/// Replaces the held chords of the selection (or the whole clip) with
+/// arpeggios. Notes overlapping in time form one chord; across its span a
+/// note is emitted every `rate` step, cycling through the pitches still held
+/// at that step in `pattern` order. Each note lasts `gate` of a step.
+///
+/// The clip is updated in place and the returned batch removes the source
+/// notes and adds the arpeggio, so callers snapshot the clip beforehand to
+/// make the operation undoable.
+pub fn arpeggiate(clip: &mut Clip, pattern: ArpPattern, rate: SnapUnit, gate: f32) -> Vec<Edit> {
+    let mut sources: Vec<Note> = target_indices(clip)
+        .into_iter()
+        .map(|index| clip.notes[index].clone())
+        .collect();
+    if sources.is_empty() {
+        return Vec::new();
+    }
+    sources.sort_by_key(|note| (note.start_ppq, note.id));
+    let step = snap_step_ppq(clip, rate);
+    let length = ((step as f32 * gate.clamp(0.0, 1.0)).round() as i64).max(1);
+    let mut next_id = clip
+        .notes
+        .iter()
+        .map(|note| note.id)
+        .max()
+        .unwrap_or(0)
+        .wrapping_add(1);
+
+    let mut edits: Vec<Edit> = sources.iter().map(|note| Edit::Remove(note.id)).collect();
+    let mut chord_start = 0;
+    while chord_start < sources.len() {
+        let mut chord_end = sources[chord_start].end_ppq();
+        let mut count = 1;
+        while let Some(note) = sources.get(chord_start + count) {
+            if note.start_ppq >= chord_end {
+                break;
+            }
+            chord_end = chord_end.max(note.end_ppq());
+            count += 1;
+        }
+        let chord = &sources[chord_start..chord_start + count];
+        let mut time = chord[0].start_ppq;
+        let mut position = 0usize;
+        while time < chord_end {
+            let mut held: Vec<&Note> = chord
+                .iter()
+                .filter(|note| note.start_ppq <= time && time < note.end_ppq())
+                .collect();
+            if !held.is_empty() {
+                if pattern != ArpPattern::AsPlayed {
+                    held.sort_by_key(|note| note.pitch);
+                }
+                let index = arp_index(pattern, position, held.len(), time);
+                let source = held[index];
+                edits.push(Edit::Add(Note {
+                    id: next_id,
+                    start_ppq: time,
+                    dur_ppq: length,
+                    ..source.clone()
+                }));
+                next_id = next_id.wrapping_add(1);
+                position += 1;
+            }
+            time += step;
+        }
+        chord_start += count;
+    }
+
+    let removed: Vec<u64> = sources.iter().map(|note| note.id).collect();
+    clip.notes.retain(|note| !removed.contains(&note.id));
+    for edit in &edits {
+        if let Edit::Add(note) = edit {
+            clip.notes.push(note.clone());
+        }
+    }
+    clip.sort_notes();
+    edits
+}
+
+/// Index into `len` pitch-sorted (or as-played) notes for step `position`.
+fn arp_index(pattern: ArpPattern, position: usize, len: usize, time: i64) -> usize {
+    match pattern {
+        ArpPattern::Up | ArpPattern::AsPlayed => position % len,
+        ArpPattern::Down => len - 1 - position % len,
+        ArpPattern::UpDown => {
+            if len == 1 {
+                return 0;
+            }
+            let cycle = 2 * len - 2;
+            let phase = position % cycle;
+            if phase < len {
+                phase
+            } else {
+                cycle - phase
+            }
+        }
+        ArpPattern::Random => {
+            // xorshift over the step time keeps the pattern stable per edit
+            let mut x = (time as u64) ^ 0x9e37_79b9_7f4a_7c15;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            (x % len as u64) as usize
+        }
+    }
+}
+
+/// How [`fix_overlaps`] resolves overlapping notes of the same pitch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlapMode {
+    /// Shorten each note so it ends where the next one starts.
+    TrimToNext,
+    /// Delete notes lying completely inside another one.
+    RemoveDuplicates,
+    /// Join overlapping or touching notes into a single note.
+    Merge,
+}
+
+/// Cleans up overlapping notes sharing pitch and channel in the selection,
+/// or the whole clip when nothing is selected. Notes starting together are
+/// ordered longest first, so trimming keeps the longest of them and removes
+/// the rest rather than leaving zero-length notes.
+///
+/// The clip is updated in place and the changes are returned as edits.
+pub fn fix_overlaps(clip: &mut Clip, mode: OverlapMode) -> Vec<Edit> {
+    let mut groups: HashMap<(u8, u8), Vec<usize>> = HashMap::new();
+    for index in target_indices(clip) {
+        let note = &clip.notes[index];
+        groups
+            .entry((note.chan, note.pitch))
+            .or_default()
+            .push(index);
+    }
+
+    let mut updated = Vec::new();
+    let mut removed = Vec::new();
+    for indices in groups.values_mut() {
+        indices.sort_by_key(|&index| {
+            let note = &clip.notes[index];
+            (note.start_ppq, -note.dur_ppq.max(1), note.id)
+        });
+        let mut kept = indices[0];
+        for &index in &indices[1..] {
+            let (start, end) = (clip.notes[index].start_ppq, clip.notes[index].end_ppq());
+            let kept_end = clip.notes[kept].end_ppq();
+            let same_start = start == clip.notes[kept].start_ppq;
+            match mode {
+                OverlapMode::TrimToNext if same_start => removed.push(index),
+                OverlapMode::TrimToNext => {
+                    if kept_end > start {
+                        clip.notes[kept].dur_ppq = start - clip.notes[kept].start_ppq;
+                        updated.push(kept);
+                    }
+                    kept = index;
+                }
+                OverlapMode::RemoveDuplicates => {
+                    if end <= kept_end {
+                        removed.push(index);
+                    } else {
+                        kept = index;
+                    }
+                }
+                OverlapMode::Merge => {
+                    if start <= kept_end {
+                        if end > kept_end {
+                            clip.notes[kept].dur_ppq = end - clip.notes[kept].start_ppq;
+                            updated.push(kept);
+                        }
+                        removed.push(index);
+                    } else {
+                        kept = index;
+                    }
+                }
+            }
+        }
+    }
+
+    updated.sort_unstable();
+    updated.dedup();
+    let mut edits: Vec<Edit> = updated
+        .iter()
+        .filter(|index| !removed.contains(index))
+        .map(|&index| update_edit(&clip.notes[index]))
+        .collect();
+    let removed_ids: Vec<u64> = removed.iter().map(|&index| clip.notes[index].id).collect();
+    edits.extend(removed_ids.iter().map(|&id| Edit::Remove(id)));
+    clip.notes.retain(|note| !removed_ids.contains(&note.id));
+    edits
+}
+
+/// [`Edit::Update`] carrying the note's current fields.
+pub fn update_edit(note: &Note) -> Edit {
+    Edit::Update {
+        id: note.id,
+        start_ppq: note.start_ppq,
+        dur_ppq: note.dur_ppq,
+        pitch: note.pitch,
+        vel: note.vel,
+        chan: note.chan,
+    }
+}
+
+/// Interpolates velocity linearly from `start_vel` on the earliest target
+/// note to `end_vel` on the latest. Notes starting together are ordered by
+/// pitch.
+pub fn velocity_ramp(clip: &mut Clip, start_vel: u8, end_vel: u8) -> Vec<Edit> {
+    let mut indices = target_indices(clip);
+    indices.sort_by_key(|&index| (clip.notes[index].start_ppq, clip.notes[index].pitch));
+    let last = indices.len().saturating_sub(1).max(1) as f32;
+    let mut edits = Vec::new();
+    for (position, &index) in indices.iter().enumerate() {
+        let t = position as f32 / last;
+        let vel = start_vel as f32 + (end_vel as f32 - start_vel as f32) * t;
+        let vel = vel.round().clamp(1.0, 127.0) as u8;
+        let note = &mut clip.notes[index];
+        if note.vel != vel {
+            note.vel = vel;
+            edits.push(update_edit(note));
+        }
+    }
+    edits
+}
+
+/// Seeded xorshift generator so batch tools are repeatable.
+struct Jitter(u64);
+
+impl Jitter {
+    fn new(seed: u64) -> Self {
+        // xorshift never leaves zero, so mix the seed first.
+        Self((seed ^ 0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    /// Uniform value in `-bound..=bound`.
+    fn offset(&mut self, bound: i64) -> i64 {
+        if bound <= 0 {
+            return 0;
+        }
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % (2 * bound as u64 + 1)) as i64 - bound
+    }
+}
+
+/// Moves each target note's start by up to `timing_ppq` either way and its
+/// velocity by up to `velocity`. Starts never go below zero and velocities
+/// stay within `1..=127`.
+pub fn humanize(clip: &mut Clip, timing_ppq: i64, velocity: u8, rng_seed: u64) -> Vec<Edit> {
+    let mut indices = target_indices(clip);
+    indices.sort_by_key(|&index| clip.notes[index].id);
+    let mut jitter = Jitter::new(rng_seed);
+    let mut edits = Vec::new();
+    for index in indices {
+        let time = jitter.offset(timing_ppq.abs());
+        let vel = jitter.offset(velocity as i64);
+        let note = &mut clip.notes[index];
+        let start_ppq = (note.start_ppq + time).max(0);
+        let vel = (note.vel as i64 + vel).clamp(1, 127) as u8;
+        if start_ppq != note.start_ppq || vel != note.vel {
+            note.start_ppq = start_ppq;
+            note.vel = vel;
+            edits.push(update_edit(note));
+        }
+    }
+    clip.sort_notes();
+    edits
+}
+
+/// Pitch order [`strum`] staggers a chord in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrumDir {
+    /// Lowest note first, like a down-stroke on a guitar.
+    LowToHigh,
+    HighToLow,
+}
+
+/// Staggers each group of target notes sharing a start time by
+/// `offset_ppq` per note in `direction` order. A negative offset pulls the
+/// later notes of the order earlier instead. Note ends stay put so the
+/// chord still releases together, and no note starts before zero.
+pub fn strum(clip: &mut Clip, offset_ppq: i64, direction: StrumDir) -> Vec<Edit> {
+    let mut chords: HashMap<i64, Vec<usize>> = HashMap::new();
+    for index in target_indices(clip) {
+        chords
+            .entry(clip.notes[index].start_ppq)
+            .or_default()
+            .push(index);
+    }
+
+    let mut edits = Vec::new();
+    for (start, mut chord) in chords {
+        chord.sort_by_key(|&index| (clip.notes[index].pitch, clip.notes[index].id));
+        if direction == StrumDir::HighToLow {
+            chord.reverse();
+        }
+        for (position, index) in chord.into_iter().enumerate() {
+            let note = &mut clip.notes[index];
+            let end = note.end_ppq();
+            let new_start = (start + offset_ppq * position as i64).max(0);
+            if new_start == note.start_ppq {
+                continue;
+            }
+            note.start_ppq = new_start;
+            note.dur_ppq = (end - new_start).max(1);
+            edits.push(update_edit(note));
+        }
+    }
+    clip.sort_notes();
+    edits
+}
+
+/// Notes copied out of the piano roll, with starts stored relative to the
+/// earliest copied note.
+#[derive(Clone, Debug, Default)]
+pub struct NoteClipboard {
+    notes: Vec<Note>,
+}
+
+impl NoteClipboard {
+    /// Copies the selected notes of `clip`.
+    pub fn copy(clip: &Clip) -> Self {
+        let selected = clip.notes.iter().filter(|note| note.selected);
+        let Some(origin) = selected.clone().map(|note| note.start_ppq).min() else {
+            return Self::default();
+        };
+        let notes = selected
+            .map(|note| Note {
+                start_ppq: note.start_ppq - origin,
+                selected: false,
+                ..note.clone()
+            })
+            .collect();
+        Self { notes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Inserts the copied notes with the earliest one at `at_ppq`, under new
+    /// ids. The pasted notes replace the selection. Notes are pasted whole
+    /// even where they run past the loop end.
+    pub fn paste(&self, clip: &mut Clip, at_ppq: i64) -> Vec<Edit> {
+        if self.notes.is_empty() {
+            return Vec::new();
+        }
+        for note in &mut clip.notes {
+            note.selected = false;
+        }
+        let mut next_id = clip
+            .notes
+            .iter()
+            .map(|note| note.id)
+            .max()
+            .unwrap_or(0)
+            .wrapping_add(1);
+        let at_ppq = at_ppq.max(0);
+        let mut edits = Vec::with_capacity(self.notes.len());
+        for note in &self.notes {
+            let pasted = Note {
+                id: next_id,
+                start_ppq: at_ppq + note.start_ppq,
+                selected: true,
+                ..note.clone()
+            };
+            next_id = next_id.wrapping_add(1);
+            edits.push(Edit::Add(pasted.clone()));
+            clip.notes.push(pasted);
+        }
+        clip.sort_notes();
+        edits
+    }
+}
+
+/// Pastes a copy of the selected notes right after the end of the
+/// selection's time span and selects the copy.
+pub fn duplicate(clip: &mut Clip) -> Vec<Edit> {
+    let Some(end) = clip
+        .notes
+        .iter()
+        .filter(|note| note.selected)
+        .map(|note| note.end_ppq())
+        .max()
+    else {
+        return Vec::new();
+    };
+    NoteClipboard::copy(clip).paste(clip, end)
+}
@@ -0,0 +1,118 @@
+use harmoniq_pianoroll::model::grid::Snapper;
+use harmoniq_pianoroll::model::{Clip, Note, QuantizePreset, SnapUnit, Timebase};
+
+const PPQ: i32 = 96;
+
+fn clip_with_starts(starts: &[i64]) -> Clip {
+    let mut clip = Clip::new(PPQ);
+    clip.notes = starts
+        .iter()
+        .enumerate()
+        .map(|(index, &start_ppq)| Note {
+            id: index as u64 + 1,
+            start_ppq,
+            dur_ppq: 12,
+            pitch: 60 + index as u8,
+            vel: 100,
+            chan: 0,
+            selected: false,
+        })
+        .collect();
+    clip
+}
+
+fn starts(clip: &Clip) -> Vec<i64> {
+    clip.notes.iter().map(|note| note.start_ppq).collect()
+}
+
+fn sixteenths(strength: f32, iterative: bool) -> QuantizePreset {
+    QuantizePreset {
+        strength,
+        iterative,
+        ..QuantizePreset::straight("1/16", SnapUnit::Grid(4))
+    }
+}
+
+#[test]
+fn half_strength_lands_halfway_to_grid() {
+    let mut clip = clip_with_starts(&[10, 40, 72]);
+    let preset = sixteenths(0.5, true);
+    let moved = clip.quantize(&preset);
+    assert_eq!(starts(&clip), [5, 44, 72]);
+    assert_eq!(moved, [1, 2]);
+
+    // Each pass covers half of the original distance, so the second one
+    // lands on the grid and a third leaves the notes there.
+    clip.quantize(&preset);
+    assert_eq!(starts(&clip), [0, 48, 72]);
+    assert!(clip.quantize(&preset).is_empty());
+}
+
+#[test]
+fn one_shot_quantize_applies_strength_once() {
+    let mut clip = clip_with_starts(&[10, 40, 72]);
+    let moved = clip.quantize(&sixteenths(0.5, false));
+    assert_eq!(starts(&clip), [5, 44, 72]);
+    assert_eq!(moved, [1, 2]);
+    assert!(clip.quantize(&sixteenths(0.5, false)).is_empty());
+    assert_eq!(starts(&clip), [5, 44, 72]);
+
+    clip.quantize(&sixteenths(1.0, false));
+    assert_eq!(starts(&clip), [0, 48, 72]);
+}
+
+#[test]
+fn moved_notes_are_measured_from_their_new_position() {
+    let mut clip = clip_with_starts(&[10]);
+    let preset = sixteenths(0.5, false);
+    clip.quantize(&preset);
+    assert_eq!(starts(&clip), [5]);
+
+    clip.notes[0].start_ppq = 30;
+    clip.quantize(&preset);
+    assert_eq!(starts(&clip), [27]);
+}
+
+#[test]
+fn swing_delays_odd_subdivisions() {
+    // Half swing moves odd sixteenths 6 ppq later.
+    let preset = QuantizePreset {
+        swing: 0.5,
+        ..sixteenths(1.0, false)
+    };
+    let mut clip = clip_with_starts(&[23, 37, 50, 70]);
+    clip.quantize(&preset);
+    assert_eq!(starts(&clip), [30, 30, 48, 78]);
+}
+
+#[test]
+fn range_limits_quantized_notes() {
+    let mut clip = clip_with_starts(&[10, 110]);
+    let preset = QuantizePreset {
+        range: 96..=i64::MAX,
+        ..sixteenths(1.0, false)
+    };
+    assert_eq!(clip.quantize(&preset), [2]);
+    assert_eq!(starts(&clip), [10, 120]);
+}
+
+#[test]
+fn swung_snapping_picks_the_nearest_swung_point() {
+    // Sixteenths with half swing: 0, 30, 48, 78, ... Snapping to the
+    // straight grid before swinging would send 37 to 48 rather than 30.
+    let snapper = Snapper::new(PPQ, Some(SnapUnit::Grid(4)), false, 0.5, Timebase::Musical);
+    assert_eq!(snapper.snap_ppq(37), 30);
+    assert_eq!(snapper.snap_ppq(40), 48);
+    assert_eq!(snapper.snap_ppq(66), 78);
+    assert_eq!(snapper.snap_ppq(-5), 0);
+}
+
+#[test]
+fn negative_positions_snap_to_the_nearest_step() {
+    let snapper = Snapper::new(PPQ, Some(SnapUnit::Grid(4)), false, 0.0, Timebase::Musical);
+    assert_eq!(snapper.snap_ppq(-10), 0);
+    assert_eq!(snapper.snap_ppq(-14), -24);
+    assert_eq!(snapper.snap_ppq(-36), -24);
+    assert_eq!(snapper.snap_ppq(35), 24);
+    assert_eq!(snapper.snap_ppq(36), 48);
+}
@@ -0,0 +1,517 @@
+use egui::{pos2, Modifiers, Rect};
+use harmoniq_pianoroll::model::{Clip, Edit, EditorState, Note, Scale, ScaleMode, SnapUnit};
+use harmoniq_pianoroll::tools::{
+    arpeggiate, duplicate, fix_overlaps, humanize, strum, velocity_ramp, HitNote, MarqueeMode,
+    NoteClipboard, OverlapMode, PlayheadFollow, PointerPosition, StrumDir, Tool, ToolController,
+};
+use harmoniq_pianoroll::ArpPattern;
+
+const PPQ: i32 = 96;
+
+fn note(id: u64, start_ppq: i64, dur_ppq: i64, pitch: u8) -> Note {
+    Note {
+        id,
+        start_ppq,
+        dur_ppq,
+        pitch,
+        vel: 100,
+        chan: 0,
+        selected: false,
+    }
+}
+
+/// A C major triad held for one beat.
+fn triad() -> Clip {
+    let mut clip = Clip::new(PPQ);
+    clip.notes = vec![note(1, 0, 96, 60), note(2, 0, 96, 64), note(3, 0, 96, 67)];
+    clip
+}
+
+fn pitches(clip: &Clip) -> Vec<(i64, u8)> {
+    clip.notes.iter().map(|n| (n.start_ppq, n.pitch)).collect()
+}
+
+#[test]
+fn arpeggiate_expands_chord_in_pattern_order() {
+    let mut clip = triad();
+    let edits = arpeggiate(&mut clip, ArpPattern::Up, SnapUnit::Grid(4), 0.5);
+    assert_eq!(pitches(&clip), [(0, 60), (24, 64), (48, 67), (72, 60)]);
+    assert!(clip.notes.iter().all(|n| n.dur_ppq == 12));
+    let removed = edits
+        .iter()
+        .filter(|e| matches!(e, Edit::Remove(_)))
+        .count();
+    let added = edits.iter().filter(|e| matches!(e, Edit::Add(_))).count();
+    assert_eq!((removed, added), (3, 4));
+
+    let mut clip = triad();
+    arpeggiate(&mut clip, ArpPattern::Down, SnapUnit::Grid(4), 1.0);
+    let order: Vec<u8> = clip.notes.iter().map(|n| n.pitch).collect();
+    assert_eq!(order, [67, 64, 60, 67]);
+
+    let mut clip = triad();
+    arpeggiate(&mut clip, ArpPattern::UpDown, SnapUnit::Grid(8), 1.0);
+    let order: Vec<u8> = clip.notes.iter().map(|n| n.pitch).collect();
+    assert_eq!(order, [60, 64, 67, 64, 60, 64, 67, 64]);
+
+    let mut clip = triad();
+    clip.notes.reverse();
+    arpeggiate(&mut clip, ArpPattern::AsPlayed, SnapUnit::Grid(4), 1.0);
+    let order: Vec<u8> = clip.notes.iter().map(|n| n.pitch).collect();
+    assert_eq!(order, [60, 64, 67, 60]);
+}
+
+#[test]
+fn arpeggiate_only_touches_selection_and_undoes() {
+    let mut clip = triad();
+    clip.notes.push(note(4, 192, 96, 72));
+    clip.notes[3].selected = true;
+    let mut state = EditorState::new(clip);
+
+    let snapshot = state.clip.clone();
+    arpeggiate(&mut state.clip, ArpPattern::Up, SnapUnit::Beat, 1.0);
+    state.register_history_snapshot(snapshot);
+    // The lone selected note yields a single step; the triad is untouched.
+    assert_eq!(pitches(&state.clip), [(0, 60), (0, 64), (0, 67), (192, 72)]);
+    assert_ne!(state.clip.notes[3].id, 4);
+    assert!(state.clip.notes[3].selected);
+
+    assert!(state.undo());
+    assert_eq!(state.clip.notes[3].id, 4);
+}
+
+fn pointer(time_ppq: i64, pitch: u8) -> PointerPosition {
+    PointerPosition {
+        pos: pos2(time_ppq as f32, -(pitch as f32)),
+        time_ppq,
+        pitch,
+    }
+}
+
+fn c_major_state(mut clip: Clip) -> EditorState {
+    clip.notes.iter_mut().for_each(|n| n.selected = false);
+    let mut state = EditorState::new(clip);
+    state.scale_highlight = Some(Scale {
+        tonic: 0,
+        mode: ScaleMode::Major,
+    });
+    state.constrain_to_scale = true;
+    state
+}
+
+#[test]
+fn scale_lock_rejects_out_of_scale_drag_target() {
+    let mut clip = Clip::new(PPQ);
+    clip.notes = vec![note(1, 0, 96, 60), note(2, 192, 96, 61)];
+    let mut state = c_major_state(clip);
+    let mut tools = ToolController::new(PPQ, Some(SnapUnit::Grid(4)), false);
+    let hit = HitNote {
+        id: 1,
+        rect: Rect::NOTHING,
+    };
+
+    let none = Modifiers::default();
+    tools.on_pointer_pressed(&mut state, pointer(0, 60), Some(hit.clone()), none);
+    tools.on_pointer_dragged(&mut state, pointer(0, 61), none);
+    assert_eq!(state.clip.notes[0].pitch, 60, "C# is not in C major");
+    tools.on_pointer_dragged(&mut state, pointer(0, 62), none);
+    assert_eq!(state.clip.notes[0].pitch, 62);
+
+    let alt = Modifiers {
+        alt: true,
+        ..Modifiers::default()
+    };
+    tools.on_pointer_dragged(&mut state, pointer(0, 61), alt);
+    assert_eq!(state.clip.notes[0].pitch, 61);
+    tools.on_pointer_released();
+
+    // An out-of-scale note moved only in time keeps its pitch.
+    let hit = HitNote {
+        id: 2,
+        rect: Rect::NOTHING,
+    };
+    tools.on_pointer_pressed(&mut state, pointer(192, 61), Some(hit), none);
+    tools.on_pointer_dragged(&mut state, pointer(288, 61), none);
+    let moved = state.clip.notes.iter().find(|n| n.id == 2).unwrap();
+    assert_eq!((moved.start_ppq, moved.pitch), (288, 61));
+}
+
+#[test]
+fn scale_lock_moves_a_selection_by_one_snapped_interval() {
+    let mut state = c_major_state(triad());
+    state.selection = vec![1, 2, 3];
+    let mut tools = ToolController::new(PPQ, Some(SnapUnit::Grid(4)), false);
+    let hit = HitNote {
+        id: 1,
+        rect: Rect::NOTHING,
+    };
+
+    let none = Modifiers::default();
+    tools.on_pointer_pressed(&mut state, pointer(0, 60), Some(hit), none);
+    // C# snaps down to C, so nothing moves.
+    tools.on_pointer_dragged(&mut state, pointer(0, 61), none);
+    assert_eq!(pitches(&state.clip), [(0, 60), (0, 64), (0, 67)]);
+    // Up a tone to D: the whole chord follows, E and G to F# and A.
+    tools.on_pointer_dragged(&mut state, pointer(0, 62), none);
+    assert_eq!(pitches(&state.clip), [(0, 62), (0, 66), (0, 69)]);
+
+    // Ctrl keeps the pitch and moves only in time.
+    let ctrl = Modifiers {
+        ctrl: true,
+        ..Modifiers::default()
+    };
+    tools.on_pointer_dragged(&mut state, pointer(96, 65), ctrl);
+    assert_eq!(pitches(&state.clip), [(96, 60), (96, 64), (96, 67)]);
+}
+
+#[test]
+fn scale_lock_applies_to_drawn_notes() {
+    let mut state = c_major_state(Clip::new(PPQ));
+    let mut tools = ToolController::new(PPQ, Some(SnapUnit::Grid(4)), false);
+    tools.set_tool(Tool::Draw);
+    let output = tools.on_pointer_pressed(&mut state, pointer(0, 66), None, Modifiers::default());
+    assert_eq!(state.clip.notes[0].pitch, 65);
+    assert_eq!(output.preview.map(|p| p.pitch), Some(65));
+
+    state.constrain_to_scale = false;
+    tools.on_pointer_released();
+    tools.on_pointer_pressed(&mut state, pointer(96, 66), None, Modifiers::default());
+    assert!(state.clip.notes.iter().any(|n| n.pitch == 66));
+}
+
+fn spans(clip: &Clip) -> Vec<(u64, i64, i64)> {
+    let mut spans: Vec<_> = clip
+        .notes
+        .iter()
+        .map(|n| (n.id, n.start_ppq, n.end_ppq()))
+        .collect();
+    spans.sort();
+    spans
+}
+
+/// Overlapping, covered and identical-start notes on one pitch plus an
+/// overlapping note on another pitch that must be left alone.
+fn overlapping() -> Clip {
+    let mut clip = Clip::new(PPQ);
+    clip.notes = vec![
+        note(1, 0, 96, 60),
+        note(2, 48, 96, 60),
+        note(3, 60, 24, 60),
+        note(4, 288, 48, 60),
+        note(5, 288, 96, 60),
+        note(6, 0, 192, 62),
+    ];
+    clip
+}
+
+#[test]
+fn fix_overlaps_trims_to_next_start() {
+    let mut clip = overlapping();
+    let edits = fix_overlaps(&mut clip, OverlapMode::TrimToNext);
+    // Of the two notes starting at 288 the longer one survives.
+    assert_eq!(
+        spans(&clip),
+        [
+            (1, 0, 48),
+            (2, 48, 60),
+            (3, 60, 84),
+            (5, 288, 384),
+            (6, 0, 192)
+        ]
+    );
+    assert!(edits.iter().any(|e| matches!(e, Edit::Remove(4))));
+    assert!(edits.iter().any(|e| matches!(
+        e,
+        Edit::Update {
+            id: 1,
+            dur_ppq: 48,
+            ..
+        }
+    )));
+}
+
+#[test]
+fn fix_overlaps_removes_covered_duplicates() {
+    let mut clip = overlapping();
+    fix_overlaps(&mut clip, OverlapMode::RemoveDuplicates);
+    assert_eq!(
+        spans(&clip),
+        [(1, 0, 96), (2, 48, 144), (5, 288, 384), (6, 0, 192)]
+    );
+
+    // Exact duplicates collapse to the lowest id.
+    let mut clip = Clip::new(PPQ);
+    clip.notes = vec![note(7, 0, 96, 60), note(3, 0, 96, 60)];
+    fix_overlaps(&mut clip, OverlapMode::RemoveDuplicates);
+    assert_eq!(spans(&clip), [(3, 0, 96)]);
+}
+
+#[test]
+fn fix_overlaps_merges_touching_notes() {
+    let mut clip = overlapping();
+    clip.notes.push(note(8, 144, 48, 60));
+    let edits = fix_overlaps(&mut clip, OverlapMode::Merge);
+    assert_eq!(spans(&clip), [(1, 0, 192), (5, 288, 384), (6, 0, 192)]);
+    let removed = edits
+        .iter()
+        .filter(|e| matches!(e, Edit::Remove(_)))
+        .count();
+    assert_eq!(removed, 4);
+}
+
+fn row(count: u64) -> Clip {
+    let mut clip = Clip::new(PPQ);
+    clip.notes = (0..count)
+        .map(|i| note(i + 1, i as i64 * 24, 24, 60))
+        .collect();
+    clip
+}
+
+#[test]
+fn velocity_ramp_interpolates_in_time_order() {
+    let mut clip = row(5);
+    clip.notes.reverse();
+    let edits = velocity_ramp(&mut clip, 20, 100);
+    clip.sort_notes();
+    let velocities: Vec<u8> = clip.notes.iter().map(|n| n.vel).collect();
+    assert_eq!(velocities, [20, 40, 60, 80, 100]);
+    // The last note already had velocity 100.
+    assert_eq!(edits.len(), 4);
+    assert!(edits.iter().all(|e| matches!(e, Edit::Update { .. })));
+}
+
+#[test]
+fn humanize_is_seeded_and_bounded() {
+    let original = row(32);
+    let mut first = original.clone();
+    let mut second = original.clone();
+    humanize(&mut first, 6, 10, 42);
+    humanize(&mut second, 6, 10, 42);
+    assert_eq!(first.notes, second.notes);
+
+    let mut moved = false;
+    for note in &first.notes {
+        let before = original.notes.iter().find(|n| n.id == note.id).unwrap();
+        assert!(note.start_ppq >= 0);
+        assert!((note.start_ppq - before.start_ppq).abs() <= 6);
+        assert!((note.vel as i32 - before.vel as i32).abs() <= 10);
+        moved |= note.start_ppq != before.start_ppq;
+    }
+    assert!(moved);
+
+    let mut other = original.clone();
+    humanize(&mut other, 6, 10, 7);
+    assert_ne!(other.notes, first.notes);
+
+    let mut still = original.clone();
+    assert!(humanize(&mut still, 0, 0, 42).is_empty());
+}
+
+fn c_chord_at(start: i64) -> Clip {
+    let mut clip = Clip::new(PPQ);
+    clip.notes = vec![
+        note(1, start, 96, 64),
+        note(2, start, 96, 60),
+        note(3, start, 96, 67),
+    ];
+    clip
+}
+
+fn starts_by_pitch(clip: &Clip) -> Vec<(u8, i64, i64)> {
+    let mut starts: Vec<_> = clip
+        .notes
+        .iter()
+        .map(|n| (n.pitch, n.start_ppq, n.end_ppq()))
+        .collect();
+    starts.sort();
+    starts
+}
+
+#[test]
+fn strum_staggers_c_chord_by_eight_ppq() {
+    let mut clip = c_chord_at(96);
+    let edits = strum(&mut clip, 8, StrumDir::LowToHigh);
+    assert_eq!(
+        starts_by_pitch(&clip),
+        [(60, 96, 192), (64, 104, 192), (67, 112, 192)]
+    );
+    assert_eq!(edits.len(), 2);
+
+    let mut clip = c_chord_at(96);
+    strum(&mut clip, 8, StrumDir::HighToLow);
+    assert_eq!(
+        starts_by_pitch(&clip),
+        [(60, 112, 192), (64, 104, 192), (67, 96, 192)]
+    );
+
+    // Negative offsets pull the later notes earlier, clamped at zero.
+    let mut clip = c_chord_at(96);
+    strum(&mut clip, -8, StrumDir::LowToHigh);
+    assert_eq!(
+        starts_by_pitch(&clip),
+        [(60, 96, 192), (64, 88, 192), (67, 80, 192)]
+    );
+    let mut clip = c_chord_at(4);
+    strum(&mut clip, -8, StrumDir::LowToHigh);
+    assert_eq!(
+        starts_by_pitch(&clip),
+        [(60, 4, 100), (64, 0, 100), (67, 0, 100)]
+    );
+}
+
+#[test]
+fn strum_is_limited_to_selection_and_undoable() {
+    let mut clip = c_chord_at(0);
+    clip.notes.push(note(4, 0, 96, 72));
+    for n in &mut clip.notes[..3] {
+        n.selected = true;
+    }
+    let mut state = EditorState::new(clip);
+    let snapshot = state.clip.clone();
+    strum(&mut state.clip, 8, StrumDir::LowToHigh);
+    state.register_history_snapshot(snapshot);
+    assert_eq!(
+        starts_by_pitch(&state.clip),
+        [(60, 0, 96), (64, 8, 96), (67, 16, 96), (72, 0, 96)]
+    );
+
+    assert!(state.undo());
+    assert!(state.clip.notes.iter().all(|n| n.start_ppq == 0));
+}
+
+fn marquee(state: &mut EditorState, modifiers: Modifiers, hits: &[u64]) -> Vec<u64> {
+    let mut tools = ToolController::new(PPQ, Some(SnapUnit::Grid(4)), false);
+    let output = tools.on_pointer_pressed(state, pointer(0, 60), None, modifiers);
+    if let Some(selection) = output.selection {
+        state.clear_selection();
+        for id in selection {
+            state.select_note(id, true);
+        }
+    }
+    tools.on_pointer_dragged(state, pointer(96, 72), modifiers);
+    let selection = tools.marquee_selection(hits).expect("marquee gesture");
+    tools.on_pointer_released();
+    selection
+}
+
+#[test]
+fn shift_marquee_adds_to_selection() {
+    let mut clip = triad();
+    clip.notes.push(note(4, 96, 96, 72));
+    let mut state = EditorState::new(clip);
+    state.select_note(1, false);
+
+    let shift = Modifiers {
+        shift: true,
+        ..Modifiers::default()
+    };
+    // The existing selection survives the press and joins the marquee.
+    assert_eq!(marquee(&mut state, shift, &[2, 3]), [1, 2, 3]);
+    assert_eq!(state.selection, [1]);
+
+    let ctrl = Modifiers {
+        ctrl: true,
+        ..Modifiers::default()
+    };
+    state.select_note(2, true);
+    assert_eq!(marquee(&mut state, ctrl, &[2, 4]), [1, 4]);
+
+    assert_eq!(marquee(&mut state, Modifiers::default(), &[3]), [3]);
+    assert!(state.selection.is_empty());
+}
+
+#[test]
+fn marquee_modes_combine_with_base_selection() {
+    assert_eq!(MarqueeMode::Replace.combine(&[1, 2], &[3]), [3]);
+    assert_eq!(MarqueeMode::Add.combine(&[1, 2], &[2, 3]), [1, 2, 3]);
+    assert_eq!(MarqueeMode::Toggle.combine(&[1, 2], &[2, 3]), [1, 3]);
+}
+
+#[test]
+fn playhead_follow_pages_in_bounded_steps() {
+    let mut follow = PlayheadFollow::default();
+    // Inside the view nothing moves.
+    assert_eq!(follow.update(0.0, 300.0, 400.0), 0.0);
+
+    // Crossing the right edge heads for a page starting just before the
+    // playhead, at most a quarter view per update.
+    let mut scroll = 0.0;
+    let mut steps = Vec::new();
+    for _ in 0..8 {
+        let next = follow.update(scroll, 410.0, 400.0);
+        steps.push(next - scroll);
+        scroll = next;
+    }
+    assert_eq!(scroll, 390.0);
+    assert!(steps.iter().all(|step| *step <= 100.0));
+    assert_eq!(follow.update(scroll, 420.0, 400.0), 390.0);
+
+    // Jumping back before the view, e.g. on a loop wrap, pages back.
+    let mut scroll = 390.0;
+    for _ in 0..8 {
+        scroll = follow.update(scroll, 0.0, 400.0);
+    }
+    assert_eq!(scroll, 0.0);
+
+    follow.update(0.0, 1_000.0, 400.0);
+    follow.cancel();
+    assert_eq!(follow.update(50.0, 300.0, 400.0), 50.0);
+}
+
+#[test]
+fn paste_offsets_from_earliest_note_with_new_ids() {
+    let mut clip = Clip::new(PPQ);
+    clip.notes = vec![
+        note(1, 96, 48, 60),
+        note(2, 144, 96, 64),
+        note(5, 0, 96, 40),
+    ];
+    clip.notes[0].selected = true;
+    clip.notes[1].selected = true;
+    let clipboard = NoteClipboard::copy(&clip);
+
+    // The loop covers one bar, so the second note lands past its end and is
+    // pasted whole rather than wrapped or trimmed.
+    let loop_end = clip.loop_start_ppq + clip.loop_len_ppq;
+    let edits = clipboard.paste(&mut clip, loop_end - 24);
+    let added: Vec<(u64, i64, i64, u8)> = edits
+        .iter()
+        .map(|edit| match edit {
+            Edit::Add(note) => (note.id, note.start_ppq, note.dur_ppq, note.pitch),
+            other => panic!("unexpected edit {other:?}"),
+        })
+        .collect();
+    assert_eq!(added, [(6, 360, 48, 60), (7, 408, 96, 64)]);
+    assert_eq!(clip.loop_start_ppq + clip.loop_len_ppq, loop_end);
+
+    let selected: Vec<u64> = clip
+        .notes
+        .iter()
+        .filter(|note| note.selected)
+        .map(|note| note.id)
+        .collect();
+    assert_eq!(selected, [6, 7]);
+    assert_eq!(clip.notes.len(), 5);
+}
+
+#[test]
+fn duplicate_pastes_after_selection_span() {
+    let mut clip = triad();
+    clip.notes.push(note(4, 48, 96, 72));
+    for note in &mut clip.notes {
+        note.selected = true;
+    }
+    let edits = duplicate(&mut clip);
+    assert_eq!(edits.len(), 4);
+    let copies: Vec<(i64, u8)> = clip
+        .notes
+        .iter()
+        .filter(|note| note.selected)
+        .map(|note| (note.start_ppq, note.pitch))
+        .collect();
+    assert_eq!(copies, [(144, 60), (144, 64), (144, 67), (192, 72)]);
+
+    let mut empty = triad();
+    assert!(duplicate(&mut empty).is_empty());
+    assert!(NoteClipboard::copy(&empty).is_empty());
+}
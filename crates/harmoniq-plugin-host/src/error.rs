@@ -19,4 +19,6 @@ pub enum HostError {
     Io(#[from] std::io::Error),
     #[error("invalid plugin state: {0}")]
     InvalidState(String),
+    #[error("plugin broker unavailable: {0}")]
+    Broker(String),
 }
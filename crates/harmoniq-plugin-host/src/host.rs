@@ -11,6 +11,9 @@ use crate::parameters::{
     create_parameter_automation, AutomationMessage, ParameterAutomationChannels, PluginParam,
 };
 use crossbeam_channel::{Receiver, Sender};
+use harmoniq_host_clap::ClapHost;
+use harmoniq_host_vst3::{PluginBroker as Vst3Broker, Vst3Host};
+use parking_lot::Mutex;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PluginId(pub u64);
@@ -29,6 +32,11 @@ pub trait PluginHost {
     fn get_parameters(&self) -> Vec<PluginParam>;
     fn set_parameter(&mut self, index: usize, value: f32);
     fn editor(&mut self) -> Option<PluginEditorHandle>;
+    /// Opaque state of plugin `id`, for storing with the project. Plugins
+    /// that keep no state return an empty blob.
+    fn save_state(&self, id: PluginId) -> Result<Vec<u8>, HostError>;
+    /// Restores a blob returned by [`save_state`](Self::save_state).
+    fn load_state(&mut self, id: PluginId, data: &[u8]) -> Result<(), HostError>;
 }
 
 /// Unified host capable of managing VST3, LV2, CLAP, and Harmoniq plugins.
@@ -37,6 +45,8 @@ pub struct UnifiedPluginHost {
     plugins: HashMap<PluginId, LoadedPlugin>,
     discovery: Vec<DiscoveredPlugin>,
     active_plugin: Option<PluginId>,
+    clap_options: harmoniq_host_clap::HostOptions,
+    vst3_options: harmoniq_host_vst3::HostOptions,
 }
 
 struct LoadedPlugin {
@@ -47,6 +57,47 @@ struct LoadedPlugin {
     automation: Vec<ParameterAutomationChannels>,
     editor: Option<PluginEditorHandle>,
     editor_channels: Option<EditorChannelState>,
+    state: StateBackend,
+}
+
+/// Where a plugin's state lives. CLAP and VST3 binaries run in a broker
+/// process, which saves and restores their state over IPC.
+enum StateBackend {
+    /// The format keeps no third-party state.
+    Stateless,
+    Clap(Mutex<ClapHost>),
+    Vst3(Mutex<Vst3Host<Vst3Broker>>),
+    /// The broker could not be started.
+    Unavailable(String),
+}
+
+impl StateBackend {
+    fn brokered<T>(result: anyhow::Result<T>, backend: impl FnOnce(T) -> Self) -> Self {
+        match result {
+            Ok(host) => backend(host),
+            Err(err) => StateBackend::Unavailable(format!("{err:#}")),
+        }
+    }
+
+    fn save(&self) -> Result<Vec<u8>, HostError> {
+        let result = match self {
+            StateBackend::Stateless => return Ok(Vec::new()),
+            StateBackend::Clap(host) => host.lock().request_state(),
+            StateBackend::Vst3(host) => host.lock().save_state(),
+            StateBackend::Unavailable(reason) => return Err(HostError::Broker(reason.clone())),
+        };
+        result.map_err(|err| HostError::InvalidState(format!("{err:#}")))
+    }
+
+    fn load(&self, data: &[u8]) -> Result<(), HostError> {
+        let result = match self {
+            StateBackend::Stateless => return Ok(()),
+            StateBackend::Clap(host) => host.lock().load_state(data),
+            StateBackend::Vst3(host) => host.lock().load_state(data),
+            StateBackend::Unavailable(reason) => return Err(HostError::Broker(reason.clone())),
+        };
+        result.map_err(|err| HostError::InvalidState(format!("{err:#}")))
+    }
 }
 
 struct EditorChannelState {
@@ -62,9 +113,23 @@ impl UnifiedPluginHost {
             plugins: HashMap::new(),
             discovery,
             active_plugin: None,
+            clap_options: harmoniq_host_clap::HostOptions::default(),
+            vst3_options: harmoniq_host_vst3::HostOptions::default(),
         }
     }
 
+    /// Options for the broker that hosts CLAP plugins.
+    pub fn with_clap_options(mut self, options: harmoniq_host_clap::HostOptions) -> Self {
+        self.clap_options = options;
+        self
+    }
+
+    /// Options for the broker that hosts VST3 plugins.
+    pub fn with_vst3_options(mut self, options: harmoniq_host_vst3::HostOptions) -> Self {
+        self.vst3_options = options;
+        self
+    }
+
     pub fn discovered_plugins(&self) -> &[DiscoveredPlugin] {
         &self.discovery
     }
@@ -79,6 +144,30 @@ impl UnifiedPluginHost {
         let id = self.active_plugin?;
         self.plugins.get_mut(&id)
     }
+
+    /// Starts a broker for the plugin at `path`. A plugin whose broker
+    /// fails still loads; only its state is unavailable.
+    fn broker_plugin(&self, path: &Path, format: PluginFormat) -> StateBackend {
+        match format {
+            PluginFormat::Clap => {
+                let host = ClapHost::new(self.clap_options.clone()).and_then(|mut host| {
+                    host.load_plugin_path(path)?;
+                    Ok(host)
+                });
+                StateBackend::brokered(host, |host| StateBackend::Clap(Mutex::new(host)))
+            }
+            PluginFormat::Vst3 => {
+                let options = self.vst3_options.clone();
+                let host = Vst3Broker::spawn(options.broker.clone()).and_then(|broker| {
+                    let mut host = Vst3Host::with_broker(broker, options);
+                    host.load_plugin(path)?;
+                    Ok(host)
+                });
+                StateBackend::brokered(host, |host| StateBackend::Vst3(Mutex::new(host)))
+            }
+            _ => StateBackend::Stateless,
+        }
+    }
 }
 
 impl PluginHost for UnifiedPluginHost {
@@ -95,6 +184,7 @@ impl PluginHost for UnifiedPluginHost {
             ))
         })?;
 
+        let state = self.broker_plugin(&path, format);
         let id = PluginId::next(&self.next_id);
         let mut parameters = Vec::new();
         let mut automation_channels = Vec::new();
@@ -123,6 +213,7 @@ impl PluginHost for UnifiedPluginHost {
             automation: automation_channels,
             editor: None,
             editor_channels: None,
+            state,
         };
         self.plugins.insert(id, plugin);
         self.active_plugin = Some(id);
@@ -198,6 +289,20 @@ impl PluginHost for UnifiedPluginHost {
         plugin.editor = Some(handle.clone());
         Some(handle)
     }
+
+    fn save_state(&self, id: PluginId) -> Result<Vec<u8>, HostError> {
+        let plugin = self.plugins.get(&id).ok_or_else(|| unknown_plugin(id))?;
+        plugin.state.save()
+    }
+
+    fn load_state(&mut self, id: PluginId, data: &[u8]) -> Result<(), HostError> {
+        let plugin = self.plugins.get(&id).ok_or_else(|| unknown_plugin(id))?;
+        plugin.state.load(data)
+    }
+}
+
+fn unknown_plugin(id: PluginId) -> HostError {
+    HostError::InvalidState(format!("no plugin loaded as {id:?}"))
 }
 
 fn detect_format(path: &Path) -> Option<PluginFormat> {
@@ -0,0 +1,145 @@
+//! Runs without the libtest harness: started with [`BROKER_ROLE`] set, the
+//! test binary serves as the CLAP broker that the unified host spawns.
+
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fs;
+use std::io::{stdin, stdout};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use harmoniq_host_clap::{BrokerConfig, HostOptions};
+use harmoniq_plugin_host::{HostError, PluginHost, UnifiedPluginHost};
+use tempfile::TempDir;
+
+const BROKER_ROLE: &str = "HARMONIQ_PLUGIN_HOST_TEST_BROKER";
+
+fn main() {
+    if std::env::var_os(BROKER_ROLE).is_some() {
+        harmoniq_host_clap::runtime::serve(stdin().lock(), stdout().lock()).expect("broker");
+        return;
+    }
+    // Inherited by the brokers the tests spawn.
+    std::env::set_var(BROKER_ROLE, "1");
+
+    let tests: [(&str, fn()); 4] = [
+        (
+            "clap_state_round_trips_between_instances",
+            clap_state_round_trips_between_instances,
+        ),
+        ("clap_rejects_truncated_state", clap_rejects_truncated_state),
+        (
+            "plugins_without_state_save_an_empty_blob",
+            plugins_without_state_save_an_empty_blob,
+        ),
+        (
+            "plugins_load_without_a_broker",
+            plugins_load_without_a_broker,
+        ),
+    ];
+    for (name, test) in tests {
+        test();
+        println!("test {name} ... ok");
+    }
+}
+
+fn host() -> UnifiedPluginHost {
+    let exe = std::env::current_exe().expect("test executable");
+    UnifiedPluginHost::new().with_clap_options(HostOptions {
+        broker: BrokerConfig {
+            executable: exe,
+            ..BrokerConfig::default()
+        },
+        event_timeout: Duration::from_secs(2),
+        ..HostOptions::default()
+    })
+}
+
+/// Cargo builds the test gain dev-dependency as a cdylib alongside the
+/// test binary.
+fn test_gain_library() -> PathBuf {
+    let exe = std::env::current_exe().expect("test executable");
+    let deps = exe.parent().expect("deps directory");
+    let prefix = format!("{DLL_PREFIX}clap_testgain");
+    [deps, deps.parent().expect("target directory")]
+        .into_iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| {
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
+            name.starts_with(&prefix) && name.ends_with(DLL_SUFFIX)
+        })
+        .expect("built test gain plug-in")
+}
+
+fn install_test_gain(dir: &TempDir) -> PathBuf {
+    let path = dir.path().join("testgain.clap");
+    fs::copy(test_gain_library(), &path).expect("copy plug-in");
+    path
+}
+
+fn gain_state(gain: f32) -> Vec<u8> {
+    gain.to_le_bytes().to_vec()
+}
+
+fn clap_state_round_trips_between_instances() {
+    let dir = TempDir::new().expect("temp dir");
+    let path = install_test_gain(&dir);
+    let mut host = host();
+
+    let first = host.load_plugin(&path).expect("load plug-in");
+    assert_eq!(host.save_state(first).expect("save"), gain_state(0.5));
+
+    host.load_state(first, &gain_state(1.25)).expect("load");
+    let saved = host.save_state(first).expect("save");
+    assert_eq!(saved, gain_state(1.25));
+
+    let second = host.load_plugin(&path).expect("load plug-in");
+    host.load_state(second, &saved).expect("restore");
+    assert_eq!(host.save_state(second).expect("save"), saved);
+}
+
+fn clap_rejects_truncated_state() {
+    let dir = TempDir::new().expect("temp dir");
+    let mut host = host();
+    let id = host
+        .load_plugin(&install_test_gain(&dir))
+        .expect("load plug-in");
+
+    assert!(host.load_state(id, &[0x00, 0x01]).is_err());
+    assert_eq!(host.save_state(id).expect("save"), gain_state(0.5));
+}
+
+fn plugins_without_state_save_an_empty_blob() {
+    let dir = TempDir::new().expect("temp dir");
+    let path = dir.path().join("stub.harmoniq");
+    fs::write(&path, b"").expect("write stub");
+    let mut host = host();
+    let id = host.load_plugin(&path).expect("load stub");
+
+    assert!(host.save_state(id).expect("save").is_empty());
+    host.load_state(id, &[1, 2, 3]).expect("ignored");
+}
+
+fn plugins_load_without_a_broker() {
+    let dir = TempDir::new().expect("temp dir");
+    let mut host = UnifiedPluginHost::new().with_clap_options(HostOptions {
+        broker: BrokerConfig {
+            executable: dir.path().join("missing-broker"),
+            ..BrokerConfig::default()
+        },
+        ..HostOptions::default()
+    });
+    let id = host
+        .load_plugin(&install_test_gain(&dir))
+        .expect("load plug-in");
+
+    assert!(matches!(host.save_state(id), Err(HostError::Broker(_))));
+    assert!(matches!(
+        host.load_state(id, &gain_state(1.0)),
+        Err(HostError::Broker(_))
+    ));
+}
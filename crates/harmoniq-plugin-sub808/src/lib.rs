@@ -1,8 +1,9 @@
 //! Harmoniq Sub808 – a simple 808-style sub-bass synth implemented as a CLAP plugin.
 //! - Sine/sub oscillator with pitch thump envelope
-//! - Amp decay envelope (808 style)
+//! - Amp decay envelope (808 style) with exponential release and sustain pedal
 //! - Glide/portamento
-//! - Drive (tanh) + post low-pass
+//! - Drive (tanh) + second-harmonic grit + post low-pass
+//! - Stereo width above a 120 Hz crossover, fundamental kept mono
 //! - Mono/poly up to 8 voices, simple voice stealing
 //! - EGUI editor via nih-plug
 //!
@@ -11,9 +12,11 @@
 use nih_plug::prelude::*;
 use std::sync::Arc;
 
+mod stereo;
 #[cfg(feature = "editor")]
 mod ui;
 mod voice;
+use stereo::{Grit, Widener};
 use voice::{SubVoice, MAX_VOICES};
 
 #[derive(Params)]
@@ -25,6 +28,9 @@ struct Sub808Params {
     #[id = "decay"]
     decay_s: FloatParam, // seconds
 
+    #[id = "release"]
+    release_s: FloatParam, // seconds, tail after note-off
+
     #[id = "velsens"]
     vel_sens: FloatParam, // 0..1
 
@@ -46,6 +52,12 @@ struct Sub808Params {
     #[id = "tone"]
     tone_hz: FloatParam, // 1-pole lowpass cutoff
 
+    #[id = "harmonics"]
+    harmonics: FloatParam, // 0..1 second-harmonic grit
+
+    #[id = "width"]
+    width: FloatParam, // 0..1 side level above the crossover
+
     // VOICING
     #[id = "voices"]
     voices: IntParam, // 1..8
@@ -78,6 +90,17 @@ impl Default for Sub808Params {
             )
             .with_unit(" s")
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            release_s: FloatParam::new(
+                "Release",
+                0.3,
+                FloatRange::Skewed {
+                    min: 0.005,
+                    max: 3.0,
+                    factor: 0.35,
+                },
+            )
+            .with_unit(" s")
+            .with_value_to_string(formatters::v2s_f32_rounded(3)),
             vel_sens: FloatParam::new("VelSens", 0.7, FloatRange::Linear { min: 0.0, max: 1.0 }),
 
             thump_amt_st: FloatParam::new(
@@ -131,6 +154,8 @@ impl Default for Sub808Params {
             )
             .with_unit(" Hz")
             .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            harmonics: FloatParam::new("Harmonics", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 }),
+            width: FloatParam::new("Width", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 }),
 
             voices: IntParam::new(
                 "Voices",
@@ -148,6 +173,8 @@ impl Default for Sub808Params {
 struct Sub808 {
     params: Arc<Sub808Params>,
     voices: [SubVoice; MAX_VOICES],
+    grit: Grit,
+    widener: Widener,
     sample_rate: f32,
     // sustain pedal state (optional)
     sustain: bool,
@@ -158,6 +185,8 @@ impl Default for Sub808 {
         Self {
             params: Arc::new(Sub808Params::default()),
             voices: core::array::from_fn(|_| SubVoice::new()),
+            grit: Grit::default(),
+            widener: Widener::default(),
             sample_rate: 44100.0,
             sustain: false,
         }
@@ -199,6 +228,8 @@ impl Plugin for Sub808 {
         for v in &mut self.voices {
             v.reset(self.sample_rate);
         }
+        self.grit.prepare(self.sample_rate);
+        self.widener.prepare(self.sample_rate);
         true
     }
 
@@ -206,6 +237,8 @@ impl Plugin for Sub808 {
         for v in &mut self.voices {
             v.reset(self.sample_rate);
         }
+        self.grit.prepare(self.sample_rate);
+        self.widener.prepare(self.sample_rate);
     }
 
     fn process(
@@ -222,12 +255,15 @@ impl Plugin for Sub808 {
         let level_db = self.params.level.smoothed.next();
         let level = util::db_to_gain_fast(level_db);
         let decay_s = self.params.decay_s.value();
+        let release_s = self.params.release_s.value();
         let vel_sens = self.params.vel_sens.value();
         let thump_st = self.params.thump_amt_st.value();
         let thump_decay_s = self.params.thump_decay_s.value();
         let glide_ms = self.params.glide_ms.value();
         let drive = self.params.drive.value();
         let tone_hz = self.params.tone_hz.value();
+        let harmonics = self.params.harmonics.value();
+        let width = self.params.width.value();
 
         // Handle incoming MIDI
         while let Some(event) = context.next_event() {
@@ -277,17 +313,7 @@ impl Plugin for Sub808 {
                     }
                 }
                 NoteEvent::NoteOff { note, .. } => {
-                    if mono {
-                        if self.voices[0].current_note == Some(note) {
-                            self.voices[0].note_off();
-                        }
-                    } else {
-                        for v in &mut self.voices[..voices_active] {
-                            if v.current_note == Some(note) {
-                                v.note_off();
-                            }
-                        }
-                    }
+                    self.release_note(note, mono, voices_active);
                 }
                 NoteEvent::PolyPressure { .. } => {}
                 NoteEvent::Choke { note, .. } => {
@@ -305,7 +331,7 @@ impl Plugin for Sub808 {
                 }
                 NoteEvent::MidiCC { cc, value, .. } => {
                     if cc == 64 {
-                        self.sustain = value >= 0.5;
+                        self.set_sustain(value >= 0.5);
                     }
                 }
                 _ => {}
@@ -317,29 +343,35 @@ impl Plugin for Sub808 {
         let num_channels = buffer.channels();
         let out = buffer.as_slice();
 
-        // simple one-pole LP after drive, per channel
+        // simple one-pole LP after drive; the signal is mono until the widener
         let g = (1.0 - (-2.0 * std::f32::consts::PI * tone_hz / sr).exp()).clamp(0.0, 1.0);
-        let mut lp_l = 0.0f32;
-        let mut lp_r = 0.0f32;
+        let mut lp = 0.0f32;
 
         for s in 0..num_samples {
             let mut acc = 0.0f32;
             // sum active voices
             for v in &mut self.voices[..voices_active] {
-                acc += v.process_one(sr, decay_s, thump_st, thump_decay_s, glide_ms, vel_sens);
+                acc += v.process_one(
+                    sr,
+                    decay_s,
+                    thump_st,
+                    thump_decay_s,
+                    glide_ms,
+                    vel_sens,
+                    release_s,
+                );
             }
             // drive
             let pre = acc * drive;
-            let driven = fast_tanh(pre);
+            let driven = self.grit.process(fast_tanh(pre), harmonics);
             // LP
-            lp_l += g * (driven - lp_l);
-            lp_r += g * (driven - lp_r);
-            // output gain
-            let out_s = (lp_l * level).clamp(-1.0, 1.0);
+            lp += g * (driven - lp);
+            // output gain, then spread the highs (identical L/R at zero width)
+            let (wide_l, wide_r) = self.widener.process(lp * level, width);
             // write stereo
-            out[0][s] = out_s;
+            out[0][s] = wide_l.clamp(-1.0, 1.0);
             if num_channels > 1 {
-                out[1][s] = out_s;
+                out[1][s] = wide_r.clamp(-1.0, 1.0);
             }
         }
 
@@ -347,6 +379,36 @@ impl Plugin for Sub808 {
     }
 }
 
+impl Sub808 {
+    /// Note-off, deferred while the sustain pedal is down.
+    fn release_note(&mut self, note: u8, mono: bool, voices_active: usize) {
+        let voices = if mono {
+            &mut self.voices[..1]
+        } else {
+            &mut self.voices[..voices_active]
+        };
+        for v in voices {
+            if v.current_note == Some(note) {
+                if self.sustain {
+                    v.sustain();
+                } else {
+                    v.note_off();
+                }
+            }
+        }
+    }
+
+    /// CC64. Lifting the pedal releases every note held by it.
+    fn set_sustain(&mut self, down: bool) {
+        self.sustain = down;
+        if !down {
+            for v in &mut self.voices {
+                v.release_sustained();
+            }
+        }
+    }
+}
+
 // Lightweight tanh for drive
 #[inline(always)]
 fn fast_tanh(x: f32) -> f32 {
@@ -377,3 +439,65 @@ mod util {
         (db * 0.115129254f32).exp()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SR: f32 = 48_000.0;
+
+    /// Renders `samples` frames of voice 0 and returns the last one's level.
+    fn render(synth: &mut Sub808, samples: usize) -> f32 {
+        let mut peak = 0.0f32;
+        for n in 0..samples {
+            let s = synth.voices[0].process_one(SR, 3.0, 0.0, 0.06, 0.0, 0.0, 0.03);
+            if n + 480 >= samples {
+                peak = peak.max(s.abs());
+            }
+        }
+        peak
+    }
+
+    fn playing(note: u8) -> Sub808 {
+        let mut synth = Sub808::default();
+        synth.voices[0].reset(SR);
+        synth.voices[0].note_on(note, 1.0, SR, 3.0, 0.0, 0.06, 0.0);
+        render(&mut synth, 4_800);
+        synth
+    }
+
+    #[test]
+    fn note_off_under_sustain_keeps_voice_audible() {
+        let mut released = playing(48);
+        released.release_note(48, true, 1);
+        assert!(render(&mut released, 24_000) < 1e-3);
+        assert!(!released.voices[0].is_on());
+
+        let mut held = playing(48);
+        held.set_sustain(true);
+        held.release_note(48, true, 1);
+        assert!(render(&mut held, 24_000) > 0.5);
+        assert!(held.voices[0].is_on());
+
+        // Lifting the pedal lets the release run.
+        held.set_sustain(false);
+        assert!(render(&mut held, 24_000) < 1e-3);
+    }
+
+    #[test]
+    fn kill_fades_out_within_a_millisecond() {
+        let mut synth = playing(48);
+        let before = synth.voices[0].process_one(SR, 3.0, 0.0, 0.06, 0.0, 0.0, 0.03);
+        synth.voices[0].kill();
+        assert_eq!(synth.voices[0].current_note, None);
+
+        let mut previous = before;
+        for _ in 0..60 {
+            let s = synth.voices[0].process_one(SR, 3.0, 0.0, 0.06, 0.0, 0.0, 0.03);
+            assert!((s - previous).abs() < 0.1, "step {previous} -> {s}");
+            previous = s;
+        }
+        assert_eq!(previous, 0.0);
+        assert!(!synth.voices[0].is_on());
+    }
+}
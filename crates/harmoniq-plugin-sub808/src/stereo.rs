@@ -0,0 +1,98 @@
+//! Post-drive colour stages: a second-harmonic "grit" shaper and a
+//! two-band widener that spreads the upper harmonics while the fundamental
+//! stays mono. Both are plain per-sample state, so `process()` stays
+//! allocation-free.
+
+use std::f32::consts::PI;
+
+/// Below this the signal stays in the mid channel only.
+const WIDTH_CROSSOVER_HZ: f32 = 120.0;
+/// Corner of the DC tracker removing the offset squaring introduces.
+const GRIT_DC_HZ: f32 = 10.0;
+/// Break frequencies of the all-pass chain that decorrelates the side.
+const DECORRELATE_HZ: [f32; 2] = [600.0, 2400.0];
+
+/// Adds a second harmonic by mixing in the squared signal.
+#[derive(Clone, Default)]
+pub struct Grit {
+    dc: f32,
+    dc_coeff: f32,
+}
+
+impl Grit {
+    pub fn prepare(&mut self, sr: f32) {
+        self.dc = 0.0;
+        self.dc_coeff = one_pole_coeff(GRIT_DC_HZ, sr);
+    }
+
+    #[inline]
+    pub fn process(&mut self, x: f32, amount: f32) -> f32 {
+        let squared = x * x;
+        self.dc += self.dc_coeff * (squared - self.dc);
+        x + amount * (squared - self.dc)
+    }
+}
+
+/// Mid/side widener. A one-pole split keeps everything below
+/// [`WIDTH_CROSSOVER_HZ`] in the mid channel; the band above is phase
+/// shifted into the side channel, scaled by `width`. At zero width both
+/// outputs equal the input exactly.
+#[derive(Clone, Default)]
+pub struct Widener {
+    low: f32,
+    low_coeff: f32,
+    allpass: [Allpass; 2],
+}
+
+impl Widener {
+    pub fn prepare(&mut self, sr: f32) {
+        self.low = 0.0;
+        self.low_coeff = one_pole_coeff(WIDTH_CROSSOVER_HZ, sr);
+        for (stage, hz) in self.allpass.iter_mut().zip(DECORRELATE_HZ) {
+            *stage = Allpass::new(hz, sr);
+        }
+    }
+
+    #[inline]
+    pub fn process(&mut self, x: f32, width: f32) -> (f32, f32) {
+        self.low += self.low_coeff * (x - self.low);
+        let mut high = x - self.low;
+        for stage in &mut self.allpass {
+            high = stage.process(high);
+        }
+        let side = width * high;
+        (x + side, x - side)
+    }
+}
+
+/// First-order all-pass section.
+#[derive(Clone, Default)]
+struct Allpass {
+    coeff: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl Allpass {
+    fn new(hz: f32, sr: f32) -> Self {
+        let t = (PI * hz / sr).tan();
+        Self {
+            coeff: (t - 1.0) / (t + 1.0),
+            x1: 0.0,
+            y1: 0.0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.coeff * x + self.x1 - self.coeff * self.y1;
+        self.x1 = x;
+        self.y1 = y;
+        y
+    }
+}
+
+#[inline]
+fn one_pole_coeff(hz: f32, sr: f32) -> f32 {
+    (1.0 - (-2.0 * PI * hz / sr).exp()).clamp(0.0, 1.0)
+}
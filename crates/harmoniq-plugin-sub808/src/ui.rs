@@ -8,7 +8,7 @@ pub fn editor(params: Arc<Sub808Params>) -> Option<Box<dyn Editor>> {
     let params = params.clone();
 
     nih_plug_egui::create_egui_editor(
-        nih_plug_egui::EguiState::from_size(380, 280),
+        nih_plug_egui::EguiState::from_size(380, 320),
         (),
         |_ctx, _state| {},
         move |egui_ctx, setter, _state| {
@@ -23,6 +23,7 @@ pub fn editor(params: Arc<Sub808Params>) -> Option<Box<dyn Editor>> {
                     ui.label("Amp");
                     ui.add(widgets::ParamSlider::for_param(&params.level, setter));
                     ui.add(widgets::ParamSlider::for_param(&params.decay_s, setter));
+                    ui.add(widgets::ParamSlider::for_param(&params.release_s, setter));
                     ui.add(widgets::ParamSlider::for_param(&params.vel_sens, setter));
 
                     ui.separator();
@@ -35,6 +36,8 @@ pub fn editor(params: Arc<Sub808Params>) -> Option<Box<dyn Editor>> {
                     ui.add(widgets::ParamSlider::for_param(&params.glide_ms, setter));
                     ui.add(widgets::ParamSlider::for_param(&params.drive, setter));
                     ui.add(widgets::ParamSlider::for_param(&params.tone_hz, setter));
+                    ui.add(widgets::ParamSlider::for_param(&params.harmonics, setter));
+                    ui.add(widgets::ParamSlider::for_param(&params.width, setter));
 
                     ui.separator();
                     let mut mono = params.mono.value();
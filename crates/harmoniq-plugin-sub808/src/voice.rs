@@ -2,6 +2,8 @@ use std::f32::consts::TAU;
 
 pub const MAX_VOICES: usize = 8;
 const WT_SIZE: usize = 2048;
+/// Fade length used by `kill()` so chokes do not pop.
+const KILL_FADE_S: f32 = 0.001;
 
 /// A single synth voice. No heap allocations. Sine wavetable + envelopes.
 #[derive(Clone)]
@@ -18,6 +20,12 @@ pub struct SubVoice {
     env_amp: f32,
     env_amp_coeff: f32,
     env_gate: bool,
+    /// Note-off arrived while the sustain pedal was down; the gate stays
+    /// open until the pedal lifts.
+    sustained: bool,
+    // linear fade after kill(), per-sample decrement
+    kill_step: f32,
+    sample_rate: f32,
 
     // pitch thump env
     env_thump: f32,
@@ -46,6 +54,9 @@ impl SubVoice {
             env_amp: 0.0,
             env_amp_coeff: 0.0,
             env_gate: false,
+            sustained: false,
+            kill_step: 0.0,
+            sample_rate: 44100.0,
             env_thump: 0.0,
             env_thump_coeff: 0.0,
             velocity: 1.0,
@@ -54,7 +65,7 @@ impl SubVoice {
     }
 
     #[inline]
-    pub fn reset(&mut self, _sr: f32) {
+    pub fn reset(&mut self, sr: f32) {
         self.current_note = None;
         self.age = 0;
         self.phase = 0.0;
@@ -63,6 +74,9 @@ impl SubVoice {
         self.env_amp = 0.0;
         self.env_amp_coeff = 0.0;
         self.env_gate = false;
+        self.sustained = false;
+        self.kill_step = 0.0;
+        self.sample_rate = sr;
         self.env_thump = 0.0;
         self.env_thump_coeff = 0.0;
         self.velocity = 1.0;
@@ -70,7 +84,13 @@ impl SubVoice {
 
     #[inline]
     pub fn is_on(&self) -> bool {
-        self.current_note.is_some() && (self.env_amp > 1e-5 || self.env_gate)
+        self.is_fading() || self.current_note.is_some() && (self.env_amp > 1e-5 || self.env_gate)
+    }
+
+    /// Fading out after `kill()`.
+    #[inline]
+    pub fn is_fading(&self) -> bool {
+        self.kill_step > 0.0
     }
 
     pub fn note_on(
@@ -86,6 +106,9 @@ impl SubVoice {
         self.current_note = Some(note);
         self.velocity = vel;
         self.env_gate = true;
+        self.sustained = false;
+        self.kill_step = 0.0;
+        self.sample_rate = sr;
         self.age = 0;
 
         // base frequency
@@ -115,19 +138,41 @@ impl SubVoice {
 
     #[inline]
     pub fn note_off(&mut self) {
-        // 808 behavior: continue decay, gate false
+        // 808 behavior: keep decaying, but no slower than the release
         self.env_gate = false;
+        self.sustained = false;
     }
 
+    /// Defers a note-off until [`Self::release_sustained`].
+    #[inline]
+    pub fn sustain(&mut self) {
+        self.sustained = self.env_gate;
+    }
+
+    /// Releases the voice if its note-off was deferred by the pedal.
+    #[inline]
+    pub fn release_sustained(&mut self) {
+        if self.sustained {
+            self.note_off();
+        }
+    }
+
+    /// Fades the voice out over [`KILL_FADE_S`]. The note is freed at once
+    /// so further events no longer address it.
     #[inline]
     pub fn kill(&mut self) {
         self.current_note = None;
         self.env_gate = false;
-        self.env_amp = 0.0;
-        self.env_thump = 0.0;
+        self.sustained = false;
+        if self.env_amp > 1e-5 {
+            self.kill_step = self.env_amp / (KILL_FADE_S * self.sample_rate).max(1.0);
+        } else {
+            self.env_amp = 0.0;
+        }
     }
 
     /// Process one sample and return mono signal.
+    #[allow(clippy::too_many_arguments)]
     pub fn process_one(
         &mut self,
         sr: f32,
@@ -136,14 +181,29 @@ impl SubVoice {
         thump_decay_s: f32,
         glide_ms: f32,
         vel_sens: f32,
+        release_s: f32,
     ) -> f32 {
         // update decay coeffs if host automates them
         self.env_amp_coeff = time_to_coeff(decay_s.max(1e-4), sr);
+        if !self.env_gate {
+            // released: exponential tail, at least as fast as the release
+            self.env_amp_coeff = self
+                .env_amp_coeff
+                .max(time_to_coeff(release_s.max(1e-4), sr));
+        }
         self.env_thump_coeff = time_to_coeff(thump_decay_s.max(1e-4), sr);
 
         // advance envelopes
-        self.env_amp *= 1.0 - self.env_amp_coeff;
-        if !self.env_gate && self.env_amp < 1e-5 {
+        if self.is_fading() {
+            self.env_amp -= self.kill_step;
+            if self.env_amp <= 0.0 {
+                self.env_amp = 0.0;
+                self.kill_step = 0.0;
+            }
+        } else {
+            self.env_amp *= 1.0 - self.env_amp_coeff;
+        }
+        if !self.is_fading() && !self.env_gate && self.env_amp < 1e-5 {
             // finish voice
             self.current_note = None;
             return 0.0;
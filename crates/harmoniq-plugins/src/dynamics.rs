@@ -1,17 +1,25 @@
 use std::sync::Arc;
 
+use harmoniq_dsp::allpass::Allpass2;
+use harmoniq_dsp::crossover::LinkwitzRiley4;
+use harmoniq_dsp::dynamics::hard_knee;
+use harmoniq_dsp::smoothing::SmoothedParam;
 use harmoniq_engine::{AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, PluginDescriptor};
 use harmoniq_plugin_sdk::{
     NativePlugin, ParameterDefinition, ParameterId, ParameterKind, ParameterLayout, ParameterSet,
     ParameterValue, PluginFactory, PluginParameterError,
 };
 
+use crate::effects::{db_to_gain, follow_envelope, time_to_coeff};
+
 const GAIN_PARAM: &str = "gain";
+const GAIN_SMOOTHING_MS: f32 = 20.0;
 
 /// Simple gain plugin for balancing levels in the mixer.
 #[derive(Debug, Clone)]
 pub struct GainPlugin {
-    gain: f32,
+    gain: SmoothedParam,
+    ramp: Vec<f32>,
     parameters: ParameterSet,
 }
 
@@ -19,13 +27,15 @@ impl GainPlugin {
     pub fn new(gain: f32) -> Self {
         let mut plugin = Self::default();
         let _ = plugin.set_gain(gain);
+        plugin.gain.reset(gain);
         plugin
     }
 
+    /// Sets the gain the plugin glides to over the next few milliseconds.
     pub fn set_gain(&mut self, gain: f32) -> Result<(), PluginParameterError> {
         self.parameters
             .set(&ParameterId::from(GAIN_PARAM), ParameterValue::from(gain))?;
-        self.gain = gain;
+        self.gain.set_target(gain);
         Ok(())
     }
 }
@@ -37,7 +47,11 @@ impl Default for GainPlugin {
             .get(&ParameterId::from(GAIN_PARAM))
             .and_then(ParameterValue::as_continuous)
             .unwrap_or(1.0);
-        Self { gain, parameters }
+        Self {
+            gain: SmoothedParam::linear(48_000.0, GAIN_SMOOTHING_MS, gain),
+            ramp: Vec::new(),
+            parameters,
+        }
     }
 }
 
@@ -46,13 +60,33 @@ impl AudioProcessor for GainPlugin {
         PluginDescriptor::new("harmoniq.gain", "Gain", "Harmoniq Labs")
     }
 
-    fn prepare(&mut self, _config: &BufferConfig) -> anyhow::Result<()> {
+    fn prepare(&mut self, config: &BufferConfig) -> anyhow::Result<()> {
+        let target = self.gain.target();
+        self.gain.set_time_ms(config.sample_rate, GAIN_SMOOTHING_MS);
+        self.gain.reset(target);
+        self.ramp = vec![target; config.block_size];
         Ok(())
     }
 
     fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
-        for sample in buffer.iter_mut() {
-            *sample *= self.gain;
+        if !self.gain.is_smoothing() {
+            let gain = self.gain.current();
+            for sample in buffer.iter_mut() {
+                *sample *= gain;
+            }
+            return Ok(());
+        }
+
+        let frames = buffer.len();
+        if self.ramp.len() < frames {
+            self.ramp.resize(frames, 0.0);
+        }
+        let ramp = &mut self.ramp[..frames];
+        self.gain.process_into(ramp);
+        for channel in buffer.channels_mut() {
+            for (sample, gain) in channel.iter_mut().zip(ramp.iter()) {
+                *sample *= *gain;
+            }
         }
         Ok(())
     }
@@ -72,7 +106,7 @@ impl AudioProcessor for GainPlugin {
             let _ = self
                 .parameters
                 .set(&ParameterId::from(GAIN_PARAM), ParameterValue::from(gain));
-            self.gain = gain;
+            self.gain.set_target(gain);
         }
         Ok(())
     }
@@ -94,7 +128,7 @@ impl NativePlugin for GainPlugin {
     ) -> Result<(), PluginParameterError> {
         if id.as_str() == GAIN_PARAM {
             if let Some(gain) = value.as_continuous() {
-                self.gain = gain;
+                self.gain.set_target(gain);
             }
         }
         Ok(())
@@ -125,3 +159,334 @@ impl PluginFactory for GainPluginFactory {
         Box::new(GainPlugin::default())
     }
 }
+
+const PARAM_MB_LOW_CROSSOVER: &str = "low_crossover";
+const PARAM_MB_HIGH_CROSSOVER: &str = "high_crossover";
+
+/// Prefixes of the per-band parameter ids, lowest band first.
+const MB_BANDS: [&str; 3] = ["low", "mid", "high"];
+const MB_BAND_NAMES: [&str; 3] = ["Low", "Mid", "High"];
+
+/// Ids of one band's threshold, ratio, attack and release parameters.
+#[derive(Debug, Clone)]
+struct BandParameterIds {
+    threshold: ParameterId,
+    ratio: ParameterId,
+    attack: ParameterId,
+    release: ParameterId,
+}
+
+impl BandParameterIds {
+    fn new(prefix: &str) -> Self {
+        Self {
+            threshold: ParameterId::new(format!("{prefix}_threshold")),
+            ratio: ParameterId::new(format!("{prefix}_ratio")),
+            attack: ParameterId::new(format!("{prefix}_attack")),
+            release: ParameterId::new(format!("{prefix}_release")),
+        }
+    }
+}
+
+/// Settings and detector state of one band.
+#[derive(Debug, Clone)]
+struct CompressorBand {
+    ids: BandParameterIds,
+    threshold: f32,
+    ratio: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: Vec<f32>,
+}
+
+impl CompressorBand {
+    #[inline]
+    fn process(&mut self, channel: usize, input: f32) -> f32 {
+        let env_db = follow_envelope(
+            &mut self.envelope[channel],
+            input,
+            self.attack_coeff,
+            self.release_coeff,
+        );
+        input * db_to_gain(hard_knee(env_db, self.threshold, self.ratio))
+    }
+}
+
+/// Per-channel three-way split: `(low, rest)` at the low crossover, `rest`
+/// into `(mid, high)` at the high crossover, and the low band through the
+/// high crossover's allpass so all three stay in phase.
+#[derive(Debug, Clone, Copy)]
+struct ThreeBandSplit {
+    low: LinkwitzRiley4,
+    high: LinkwitzRiley4,
+    low_allpass: Allpass2,
+}
+
+impl ThreeBandSplit {
+    fn new(sample_rate: f32, low_hz: f32, high_hz: f32) -> Self {
+        let high = LinkwitzRiley4::new(sample_rate, high_hz);
+        Self {
+            low: LinkwitzRiley4::new(sample_rate, low_hz),
+            low_allpass: high.matching_allpass(),
+            high,
+        }
+    }
+
+    #[inline]
+    fn split(&mut self, x: f32) -> [f32; 3] {
+        let (low, rest) = self.low.split(x);
+        let (mid, high) = self.high.split(rest);
+        [self.low_allpass.process_sample(low), mid, high]
+    }
+}
+
+/// Three-band compressor built on Linkwitz-Riley crossovers. With no gain
+/// reduction the bands sum back to the input, delayed only by the
+/// crossovers' allpass response.
+#[derive(Debug, Clone)]
+pub struct MultibandCompressor {
+    sample_rate: f32,
+    low_crossover: f32,
+    high_crossover: f32,
+    splits: Vec<ThreeBandSplit>,
+    bands: [CompressorBand; 3],
+    parameters: ParameterSet,
+}
+
+impl Default for MultibandCompressor {
+    fn default() -> Self {
+        let band = |prefix| CompressorBand {
+            ids: BandParameterIds::new(prefix),
+            threshold: -18.0,
+            ratio: 1.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            envelope: Vec::new(),
+        };
+        let mut plugin = Self {
+            sample_rate: 48_000.0,
+            low_crossover: 200.0,
+            high_crossover: 2_500.0,
+            splits: Vec::new(),
+            bands: MB_BANDS.map(band),
+            parameters: ParameterSet::new(multiband_compressor_layout()),
+        };
+        plugin.refresh_from_parameters();
+        plugin
+    }
+}
+
+impl MultibandCompressor {
+    fn continuous(&self, id: &ParameterId, default: f32) -> f32 {
+        self.parameters
+            .get(id)
+            .and_then(ParameterValue::as_continuous)
+            .unwrap_or(default)
+    }
+
+    fn refresh_from_parameters(&mut self) {
+        self.low_crossover = self.continuous(&ParameterId::from(PARAM_MB_LOW_CROSSOVER), 200.0);
+        self.high_crossover = self
+            .continuous(&ParameterId::from(PARAM_MB_HIGH_CROSSOVER), 2_500.0)
+            .max(self.low_crossover);
+        for split in &mut self.splits {
+            split.low.set_crossover_hz(self.low_crossover);
+            split.high.set_crossover_hz(self.high_crossover);
+            split.low_allpass.set_frequency(self.high_crossover);
+        }
+
+        for index in 0..self.bands.len() {
+            let ids = &self.bands[index].ids;
+            let threshold = self.continuous(&ids.threshold, -18.0);
+            let ratio = self.continuous(&ids.ratio, 1.0);
+            let attack = self.continuous(&ids.attack, 10.0);
+            let release = self.continuous(&ids.release, 100.0);
+            let band = &mut self.bands[index];
+            band.threshold = threshold;
+            band.ratio = ratio;
+            band.attack_coeff = time_to_coeff(attack, self.sample_rate);
+            band.release_coeff = time_to_coeff(release, self.sample_rate);
+        }
+    }
+}
+
+impl AudioProcessor for MultibandCompressor {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new(
+            "harmoniq.dynamics.multiband_compressor",
+            "Multiband Compressor",
+            "Harmoniq Labs",
+        )
+    }
+
+    fn prepare(&mut self, config: &BufferConfig) -> anyhow::Result<()> {
+        self.sample_rate = config.sample_rate;
+        let channels = config.layout.channels() as usize;
+        self.splits =
+            vec![
+                ThreeBandSplit::new(config.sample_rate, self.low_crossover, self.high_crossover);
+                channels
+            ];
+        for band in &mut self.bands {
+            band.envelope = vec![0.0; channels];
+        }
+        self.refresh_from_parameters();
+        Ok(())
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        for (index, (channel, split)) in buffer
+            .channels_mut()
+            .zip(self.splits.iter_mut())
+            .enumerate()
+        {
+            for sample in channel.iter_mut() {
+                let bands = split.split(*sample);
+                *sample = bands
+                    .iter()
+                    .zip(self.bands.iter_mut())
+                    .map(|(input, band)| band.process(index, *input))
+                    .sum();
+            }
+        }
+        Ok(())
+    }
+
+    fn supports_layout(&self, layout: ChannelLayout) -> bool {
+        matches!(layout, ChannelLayout::Mono | ChannelLayout::Stereo)
+    }
+}
+
+impl NativePlugin for MultibandCompressor {
+    fn parameters(&self) -> &ParameterSet {
+        &self.parameters
+    }
+
+    fn parameters_mut(&mut self) -> &mut ParameterSet {
+        &mut self.parameters
+    }
+
+    fn on_parameter_changed(
+        &mut self,
+        id: &ParameterId,
+        value: &ParameterValue,
+    ) -> Result<(), PluginParameterError> {
+        // Called before the set stores `value`, so store it first.
+        self.parameters.set(id, value.clone())?;
+        self.refresh_from_parameters();
+        Ok(())
+    }
+}
+
+fn multiband_compressor_layout() -> ParameterLayout {
+    let mut definitions = vec![
+        ParameterDefinition::new(
+            PARAM_MB_LOW_CROSSOVER,
+            "Low Crossover",
+            ParameterKind::continuous(40.0..=1_000.0, 200.0),
+        )
+        .with_unit("Hz")
+        .with_description("Split point between the low and mid bands"),
+        ParameterDefinition::new(
+            PARAM_MB_HIGH_CROSSOVER,
+            "High Crossover",
+            ParameterKind::continuous(1_000.0..=12_000.0, 2_500.0),
+        )
+        .with_unit("Hz")
+        .with_description("Split point between the mid and high bands"),
+    ];
+    for (prefix, name) in MB_BANDS.iter().zip(MB_BAND_NAMES) {
+        definitions.extend([
+            ParameterDefinition::new(
+                ParameterId::new(format!("{prefix}_threshold")),
+                format!("{name} Threshold"),
+                ParameterKind::continuous(-60.0..=0.0, -18.0),
+            )
+            .with_unit("dB")
+            .with_description("Level above which the band is compressed"),
+            ParameterDefinition::new(
+                ParameterId::new(format!("{prefix}_ratio")),
+                format!("{name} Ratio"),
+                ParameterKind::continuous(1.0..=20.0, 1.0),
+            )
+            .with_description("Amount of gain reduction applied to the band"),
+            ParameterDefinition::new(
+                ParameterId::new(format!("{prefix}_attack")),
+                format!("{name} Attack"),
+                ParameterKind::continuous(0.1..=200.0, 10.0),
+            )
+            .with_unit("ms")
+            .with_description("Band envelope attack time"),
+            ParameterDefinition::new(
+                ParameterId::new(format!("{prefix}_release")),
+                format!("{name} Release"),
+                ParameterKind::continuous(5.0..=500.0, 100.0),
+            )
+            .with_unit("ms")
+            .with_description("Band envelope release time"),
+        ]);
+    }
+    ParameterLayout::new(definitions)
+}
+
+pub struct MultibandCompressorFactory;
+
+impl PluginFactory for MultibandCompressorFactory {
+    fn descriptor(&self) -> PluginDescriptor {
+        PluginDescriptor::new(
+            "harmoniq.dynamics.multiband_compressor",
+            "Multiband Compressor",
+            "Harmoniq Labs",
+        )
+    }
+
+    fn parameter_layout(&self) -> Arc<ParameterLayout> {
+        Arc::new(multiband_compressor_layout())
+    }
+
+    fn create(&self) -> Box<dyn NativePlugin> {
+        Box::new(MultibandCompressor::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uncompressed_bands_recombine_to_the_input() {
+        let mut plugin = MultibandCompressor::default();
+        let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Mono);
+        plugin.prepare(&config).expect("prepare");
+
+        // With every ratio at 1:1 the output is the input through both
+        // crossovers' allpasses.
+        let mut low = LinkwitzRiley4::new(48_000.0, 200.0).matching_allpass();
+        let mut high = LinkwitzRiley4::new(48_000.0, 2_500.0).matching_allpass();
+        let mut state = 0x2468_ace1u32;
+        let mut buffer = AudioBuffer::new(1, 512);
+        for _ in 0..64 {
+            let input: Vec<f32> = (0..512)
+                .map(|_| {
+                    state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    (state >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+                })
+                .collect();
+            buffer.channel_mut(0).copy_from_slice(&input);
+            plugin.process(&mut buffer).expect("process");
+            for (x, y) in input.iter().zip(buffer.channel(0)) {
+                let expected = high.process_sample(low.process_sample(*x));
+                assert!((y - expected).abs() < 5e-4, "{y} vs {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn reports_no_latency() {
+        // The crossovers are minimum phase: their allpass response is not a
+        // delay that compensation could remove.
+        let mut plugin = MultibandCompressor::default();
+        let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Stereo);
+        plugin.prepare(&config).expect("prepare");
+        assert_eq!(plugin.latency_samples(), 0);
+    }
+}
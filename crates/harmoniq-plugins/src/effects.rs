@@ -1,7 +1,14 @@
+use std::collections::VecDeque;
 use std::f32::consts::PI;
 use std::sync::Arc;
 
-use harmoniq_engine::{AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, PluginDescriptor};
+use harmoniq_dsp::convolution::LinearPhaseConvolver;
+use harmoniq_dsp::dynamics::hard_knee;
+use harmoniq_dsp::smoothing::SmoothedParam;
+use harmoniq_engine::{
+    AudioBuffer, AudioProcessor, BufferConfig, ChannelLayout, PluginDescriptor, ProcessTransport,
+    Tempo,
+};
 use harmoniq_plugin_sdk::{
     ContinuousParameterOptions, NativePlugin, ParameterDefinition, ParameterId, ParameterKind,
     ParameterLayout, ParameterSet, ParameterValue, PluginFactory, PluginParameterError,
@@ -10,7 +17,7 @@ use harmoniq_plugin_sdk::{
 const TWO_PI: f32 = PI * 2.0;
 
 #[inline]
-fn db_to_gain(db: f32) -> f32 {
+pub(crate) fn db_to_gain(db: f32) -> f32 {
     10.0_f32.powf(db * 0.05)
 }
 
@@ -23,6 +30,20 @@ struct BiquadCoeffs {
     a2: f32,
 }
 
+impl BiquadCoeffs {
+    /// Magnitude response at `frequency`, a fraction of the sample rate.
+    fn magnitude(&self, frequency: f32) -> f32 {
+        let omega = TWO_PI * frequency;
+        let (cos1, sin1) = (omega.cos(), omega.sin());
+        let (cos2, sin2) = ((2.0 * omega).cos(), (2.0 * omega).sin());
+        let num_re = self.b0 + self.b1 * cos1 + self.b2 * cos2;
+        let num_im = -(self.b1 * sin1 + self.b2 * sin2);
+        let den_re = 1.0 + self.a1 * cos1 + self.a2 * cos2;
+        let den_im = -(self.a1 * sin1 + self.a2 * sin2);
+        (num_re.hypot(num_im) / den_re.hypot(den_im).max(1e-9)).min(1e3)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct BiquadState {
     z1: f32,
@@ -48,6 +69,15 @@ impl BiquadState {
 }
 
 const PARAM_EQ_OUTPUT_GAIN: &str = "output_gain";
+const PARAM_EQ_PHASE: &str = "phase";
+
+/// Choice index of [`PARAM_EQ_PHASE`] that runs the EQ as a linear-phase
+/// FIR.
+const EQ_PHASE_LINEAR: usize = 1;
+
+/// Length of the linear-phase FIR, about 85 ms at 48 kHz. Long enough to
+/// follow the low shelf down to its lowest setting.
+const EQ_LINEAR_PHASE_TAPS: usize = 4_097;
 
 const EQ_BAND_COUNT: usize = 4;
 
@@ -309,6 +339,11 @@ pub struct ParametricEqPlugin {
     bands: Vec<EqBandRuntime>,
     output_gain: f32,
     output_gain_id: ParameterId,
+    linear_phase: bool,
+    phase_id: ParameterId,
+    /// Allocated in `prepare` whatever the phase mode, so switching modes
+    /// while running does not allocate.
+    convolver: Option<LinearPhaseConvolver>,
     parameters: ParameterSet,
 }
 
@@ -321,6 +356,9 @@ impl Default for ParametricEqPlugin {
             bands: (0..EQ_BAND_COUNT).map(EqBandRuntime::new).collect(),
             output_gain: 1.0,
             output_gain_id: ParameterId::from(PARAM_EQ_OUTPUT_GAIN),
+            linear_phase: false,
+            phase_id: ParameterId::from(PARAM_EQ_PHASE),
+            convolver: None,
             parameters,
         };
         plugin.refresh_output_gain();
@@ -353,6 +391,22 @@ impl ParametricEqPlugin {
         for index in 0..self.bands.len() {
             self.update_band(index);
         }
+        self.update_linear_phase_kernel();
+    }
+
+    /// Redesigns the linear-phase FIR from the bands' combined magnitude
+    /// response.
+    fn update_linear_phase_kernel(&mut self) {
+        let Some(convolver) = self.convolver.as_mut() else {
+            return;
+        };
+        let bands = &self.bands;
+        convolver.set_magnitude_response(|frequency| {
+            bands
+                .iter()
+                .map(|band| band.coeffs.magnitude(frequency))
+                .product()
+        });
     }
 
     fn update_band(&mut self, index: usize) {
@@ -425,6 +479,7 @@ impl ParametricEqPlugin {
             }
             self.update_band(index);
         }
+        self.update_linear_phase_kernel();
     }
 }
 
@@ -443,11 +498,22 @@ impl AudioProcessor for ParametricEqPlugin {
         for band in &mut self.bands {
             band.resize_states(channels);
         }
+        self.convolver = Some(LinearPhaseConvolver::new(EQ_LINEAR_PHASE_TAPS, channels));
         self.update_all_bands();
         Ok(())
     }
 
     fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        if let Some(convolver) = self.convolver.as_mut().filter(|_| self.linear_phase) {
+            for (channel_index, channel) in buffer.channels_mut().enumerate() {
+                convolver.process(channel_index, channel);
+                for sample in channel.iter_mut() {
+                    *sample *= self.output_gain;
+                }
+            }
+            return Ok(());
+        }
+
         for (channel_index, channel) in buffer.channels_mut().enumerate() {
             for sample in channel.iter_mut() {
                 let mut value = *sample;
@@ -467,6 +533,16 @@ impl AudioProcessor for ParametricEqPlugin {
     fn supports_layout(&self, layout: ChannelLayout) -> bool {
         matches!(layout, ChannelLayout::Mono | ChannelLayout::Stereo)
     }
+
+    /// Linear-phase mode delays by the FIR's half length. Minimum-phase
+    /// mode adds no delay.
+    fn latency_samples(&self) -> usize {
+        if self.linear_phase {
+            LinearPhaseConvolver::latency_for(EQ_LINEAR_PHASE_TAPS)
+        } else {
+            0
+        }
+    }
 }
 
 impl NativePlugin for ParametricEqPlugin {
@@ -481,10 +557,18 @@ impl NativePlugin for ParametricEqPlugin {
     fn on_parameter_changed(
         &mut self,
         id: &ParameterId,
-        _value: &ParameterValue,
+        value: &ParameterValue,
     ) -> Result<(), PluginParameterError> {
         if id == &self.output_gain_id {
             self.refresh_output_gain();
+        } else if id == &self.phase_id {
+            let linear_phase = value.as_choice() == Some(EQ_PHASE_LINEAR);
+            if linear_phase != self.linear_phase {
+                if let Some(convolver) = self.convolver.as_mut() {
+                    convolver.reset();
+                }
+            }
+            self.linear_phase = linear_phase;
         } else {
             for index in 0..self.bands.len() {
                 let band = &self.bands[index];
@@ -494,6 +578,7 @@ impl NativePlugin for ParametricEqPlugin {
                     || id == &band.q_id
                 {
                     self.update_band(index);
+                    self.update_linear_phase_kernel();
                     break;
                 }
             }
@@ -514,6 +599,17 @@ fn parametric_eq_layout() -> ParameterLayout {
         .with_unit("dB")
         .with_description("Post-EQ output trim"),
     );
+    parameters.push(
+        ParameterDefinition::new(
+            PARAM_EQ_PHASE,
+            "Phase",
+            ParameterKind::Choice {
+                options: vec!["Minimum".into(), "Linear".into()],
+                default: 0,
+            },
+        )
+        .with_description("Minimum-phase biquads, or a latent linear-phase FIR"),
+    );
 
     for config in EQ_BAND_CONFIGS.iter() {
         parameters.push(
@@ -686,21 +782,52 @@ const PARAM_COMP_RATIO: &str = "ratio";
 const PARAM_COMP_ATTACK: &str = "attack";
 const PARAM_COMP_RELEASE: &str = "release";
 const PARAM_COMP_MAKEUP: &str = "makeup";
+const PARAM_COMP_KEY: &str = "key";
 
-fn time_to_coeff(ms: f32, sample_rate: f32) -> f32 {
+/// Choice index of [`PARAM_COMP_KEY`] that keys the detector off the
+/// sidechain input.
+const COMP_KEY_EXTERNAL: usize = 1;
+
+/// Time constant for threshold and makeup changes, so moving either control
+/// does not step the gain.
+const COMP_SMOOTHING_MS: f32 = 10.0;
+
+pub(crate) fn time_to_coeff(ms: f32, sample_rate: f32) -> f32 {
     (-1.0 / ((ms.max(0.1) / 1_000.0) * sample_rate.max(1.0))).exp()
 }
 
+/// Advances a peak detector by one sample of `input` and returns its level in
+/// dB. Shared by the compressor and each band of the multiband compressor.
+#[inline]
+pub(crate) fn follow_envelope(
+    envelope: &mut f32,
+    input: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+) -> f32 {
+    let level = input.abs().max(1e-6);
+    let coeff = if level > *envelope {
+        attack_coeff
+    } else {
+        release_coeff
+    };
+    *envelope = coeff * *envelope + (1.0 - coeff) * level;
+    20.0 * envelope.log10()
+}
+
 #[derive(Debug, Clone)]
 pub struct CompressorPlugin {
     sample_rate: f32,
-    threshold: f32,
+    threshold: SmoothedParam,
     ratio: f32,
     attack_coeff: f32,
     release_coeff: f32,
-    makeup_gain: f32,
+    makeup_gain: SmoothedParam,
+    external_key: bool,
     envelope: Vec<f32>,
     gain: Vec<f32>,
+    threshold_ramp: Vec<f32>,
+    makeup_ramp: Vec<f32>,
     parameters: ParameterSet,
 }
 
@@ -710,27 +837,67 @@ impl Default for CompressorPlugin {
         let parameters = ParameterSet::new(layout);
         let mut plugin = Self {
             sample_rate: 48_000.0,
-            threshold: -18.0,
+            threshold: SmoothedParam::exponential(48_000.0, COMP_SMOOTHING_MS, -18.0),
             ratio: 4.0,
             attack_coeff: 0.0,
             release_coeff: 0.0,
-            makeup_gain: 0.0,
+            makeup_gain: SmoothedParam::exponential(48_000.0, COMP_SMOOTHING_MS, 0.0),
+            external_key: false,
             envelope: Vec::new(),
             gain: Vec::new(),
+            threshold_ramp: Vec::new(),
+            makeup_ramp: Vec::new(),
             parameters,
         };
         plugin.refresh_from_parameters();
+        plugin.settle();
         plugin
     }
 }
 
 impl CompressorPlugin {
+    /// Runs the detector on `key`, channel by channel, and applies the gain
+    /// to `buffer`. A mono key drives every channel. Without a key each
+    /// channel detects its own level.
+    fn compress(&mut self, buffer: &mut AudioBuffer, key: Option<&AudioBuffer>) {
+        let key = key.filter(|key| key.channel_count() > 0);
+        let frames = buffer.len();
+        if self.threshold_ramp.len() < frames {
+            self.threshold_ramp.resize(frames, 0.0);
+            self.makeup_ramp.resize(frames, 0.0);
+        }
+        let thresholds = &mut self.threshold_ramp[..frames];
+        let makeups = &mut self.makeup_ramp[..frames];
+        self.threshold.process_into(thresholds);
+        self.makeup_gain.process_into(makeups);
+
+        for (index, (channel, (env, gain))) in buffer
+            .channels_mut()
+            .zip(self.envelope.iter_mut().zip(self.gain.iter_mut()))
+            .enumerate()
+        {
+            let key_channel = key.map(|key| key.channel(index % key.channel_count()));
+            for (frame, sample) in channel.iter_mut().enumerate() {
+                let input = *sample;
+                let detected = match key_channel {
+                    Some(key) => key.get(frame).copied().unwrap_or(0.0),
+                    None => input,
+                };
+                let env_db = follow_envelope(env, detected, self.attack_coeff, self.release_coeff);
+                let gain_db = hard_knee(env_db, thresholds[frame], self.ratio) + makeups[frame];
+                *gain = db_to_gain(gain_db);
+                *sample *= *gain;
+            }
+        }
+    }
+
     fn refresh_from_parameters(&mut self) {
-        self.threshold = self
-            .parameters
-            .get(&ParameterId::from(PARAM_COMP_THRESHOLD))
-            .and_then(ParameterValue::as_continuous)
-            .unwrap_or(-18.0);
+        self.threshold.set_target(
+            self.parameters
+                .get(&ParameterId::from(PARAM_COMP_THRESHOLD))
+                .and_then(ParameterValue::as_continuous)
+                .unwrap_or(-18.0),
+        );
         self.ratio = self
             .parameters
             .get(&ParameterId::from(PARAM_COMP_RATIO))
@@ -746,14 +913,26 @@ impl CompressorPlugin {
             .get(&ParameterId::from(PARAM_COMP_RELEASE))
             .and_then(ParameterValue::as_continuous)
             .unwrap_or(100.0);
-        self.makeup_gain = self
+        self.makeup_gain.set_target(
+            self.parameters
+                .get(&ParameterId::from(PARAM_COMP_MAKEUP))
+                .and_then(ParameterValue::as_continuous)
+                .unwrap_or(0.0),
+        );
+        self.external_key = self
             .parameters
-            .get(&ParameterId::from(PARAM_COMP_MAKEUP))
-            .and_then(ParameterValue::as_continuous)
-            .unwrap_or(0.0);
+            .get(&ParameterId::from(PARAM_COMP_KEY))
+            .and_then(ParameterValue::as_choice)
+            == Some(COMP_KEY_EXTERNAL);
         self.attack_coeff = time_to_coeff(attack, self.sample_rate);
         self.release_coeff = time_to_coeff(release, self.sample_rate);
     }
+
+    /// Jumps the smoothed controls to their targets.
+    fn settle(&mut self) {
+        self.threshold.reset(self.threshold.target());
+        self.makeup_gain.reset(self.makeup_gain.target());
+    }
 }
 
 impl AudioProcessor for CompressorPlugin {
@@ -765,43 +944,42 @@ impl AudioProcessor for CompressorPlugin {
         self.sample_rate = config.sample_rate;
         self.envelope = vec![0.0; config.layout.channels() as usize];
         self.gain = vec![1.0; config.layout.channels() as usize];
+        self.threshold_ramp = vec![0.0; config.block_size];
+        self.makeup_ramp = vec![0.0; config.block_size];
+        self.threshold
+            .set_time_ms(config.sample_rate, COMP_SMOOTHING_MS);
+        self.makeup_gain
+            .set_time_ms(config.sample_rate, COMP_SMOOTHING_MS);
         self.refresh_from_parameters();
+        self.settle();
         Ok(())
     }
 
     fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
-        for (channel, (env, gain)) in buffer
-            .channels_mut()
-            .zip(self.envelope.iter_mut().zip(self.gain.iter_mut()))
-        {
-            for sample in channel.iter_mut() {
-                let input = *sample;
-                let level = input.abs().max(1e-6);
-                let coeff = if level > *env {
-                    self.attack_coeff
-                } else {
-                    self.release_coeff
-                };
-                *env = coeff * *env + (1.0 - coeff) * level;
-
-                let env_db = 20.0 * env.log10();
-                let mut gain_db = 0.0;
-                if env_db > self.threshold {
-                    let delta = env_db - self.threshold;
-                    let compressed = delta / self.ratio;
-                    gain_db = (self.threshold + compressed) - env_db;
-                }
-                gain_db += self.makeup_gain;
-                *gain = db_to_gain(gain_db);
-                *sample *= *gain;
-            }
-        }
+        self.compress(buffer, None);
         Ok(())
     }
 
     fn supports_layout(&self, layout: ChannelLayout) -> bool {
         matches!(layout, ChannelLayout::Mono | ChannelLayout::Stereo)
     }
+
+    fn aux_input_count(&self) -> usize {
+        1
+    }
+
+    /// With the key set to external, the detector follows the sidechain in
+    /// `aux[0]`. An unrouted sidechain falls back to the internal key.
+    fn process_with_aux(
+        &mut self,
+        buffer: &mut AudioBuffer,
+        aux: &[&AudioBuffer],
+        _ctx: &ProcessTransport,
+    ) -> anyhow::Result<()> {
+        let key = aux.first().copied().filter(|_| self.external_key);
+        self.compress(buffer, key);
+        Ok(())
+    }
 }
 
 impl NativePlugin for CompressorPlugin {
@@ -816,9 +994,11 @@ impl NativePlugin for CompressorPlugin {
     fn on_parameter_changed(
         &mut self,
         id: &ParameterId,
-        _value: &ParameterValue,
+        value: &ParameterValue,
     ) -> Result<(), PluginParameterError> {
-        if matches!(
+        if id.as_str() == PARAM_COMP_KEY {
+            self.external_key = value.as_choice() == Some(COMP_KEY_EXTERNAL);
+        } else if matches!(
             id.as_str(),
             PARAM_COMP_THRESHOLD
                 | PARAM_COMP_RATIO
@@ -868,6 +1048,15 @@ fn compressor_layout() -> ParameterLayout {
         )
         .with_unit("dB")
         .with_description("Output gain applied after compression"),
+        ParameterDefinition::new(
+            PARAM_COMP_KEY,
+            "Key",
+            ParameterKind::Choice {
+                options: vec!["Internal".into(), "External".into()],
+                default: 0,
+            },
+        )
+        .with_description("Signal driving the detector: the input or the sidechain"),
     ])
 }
 
@@ -889,13 +1078,100 @@ impl PluginFactory for CompressorFactory {
 
 const PARAM_LIMITER_CEILING: &str = "ceiling";
 const PARAM_LIMITER_RELEASE: &str = "release";
+const PARAM_LIMITER_LOOKAHEAD: &str = "lookahead_ms";
+
+fn lookahead_samples(ms: f32, sample_rate: f32) -> usize {
+    (ms.max(0.0) / 1_000.0 * sample_rate.max(1.0)).round() as usize
+}
+
+/// Per-channel lookahead state. Every ring holds `lookahead + 1` entries so
+/// a lookahead of zero degenerates to an instantaneous clamp.
+#[derive(Debug, Clone)]
+struct LimiterChannel {
+    delay: Vec<f32>,
+    /// Windowed minima of the required gain, averaged by the box filter.
+    held: Vec<f32>,
+    held_sum: f64,
+    /// Monotonic queue of `(frame, required gain)` for the windowed minimum.
+    minima: VecDeque<(u64, f32)>,
+    position: usize,
+    frame: u64,
+    gain: f32,
+}
+
+impl LimiterChannel {
+    fn new(lookahead: usize) -> Self {
+        let len = lookahead + 1;
+        Self {
+            delay: vec![0.0; len],
+            held: vec![1.0; len],
+            held_sum: len as f64,
+            minima: VecDeque::with_capacity(len + 1),
+            position: 0,
+            frame: 0,
+            gain: 1.0,
+        }
+    }
+
+    /// Delays `input` by the lookahead and returns it with the gain needed
+    /// for the loudest sample still ahead of it.
+    ///
+    /// The required gain is held at its minimum over the window and then
+    /// averaged over the same window, so the reduction ramps in across the
+    /// lookahead and is complete when the peak leaves the delay line.
+    #[inline]
+    fn process(&mut self, input: f32, ceiling: f32, release_coeff: f32) -> f32 {
+        let len = self.delay.len();
+        let abs = input.abs();
+        let required = if abs > ceiling && abs > 1e-6 {
+            ceiling / abs
+        } else {
+            1.0
+        };
+
+        while self
+            .minima
+            .back()
+            .is_some_and(|&(_, gain)| gain >= required)
+        {
+            self.minima.pop_back();
+        }
+        self.minima.push_back((self.frame, required));
+        while self
+            .minima
+            .front()
+            .is_some_and(|&(frame, _)| frame + len as u64 <= self.frame)
+        {
+            self.minima.pop_front();
+        }
+        let held = self.minima.front().map_or(1.0, |&(_, gain)| gain);
+
+        let position = self.position;
+        self.held_sum += f64::from(held) - f64::from(self.held[position]);
+        self.held[position] = held;
+        let target = ((self.held_sum / len as f64) as f32).min(1.0);
+        self.gain = if target < self.gain {
+            target
+        } else {
+            self.gain + (target - self.gain) * (1.0 - release_coeff)
+        };
+
+        self.delay[position] = input;
+        self.position = (position + 1) % len;
+        self.frame += 1;
+        self.delay[self.position] * self.gain
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct LimiterPlugin {
     sample_rate: f32,
     ceiling: f32,
     release_coeff: f32,
-    gain: Vec<f32>,
+    lookahead_ms: f32,
+    /// Lookahead the delay lines were sized for in the last `prepare`.
+    lookahead: usize,
+    channels: Vec<LimiterChannel>,
     parameters: ParameterSet,
 }
 
@@ -907,10 +1183,13 @@ impl Default for LimiterPlugin {
             sample_rate: 48_000.0,
             ceiling: db_to_gain(-0.3),
             release_coeff: 0.0,
-            gain: Vec::new(),
+            lookahead_ms: 0.0,
+            lookahead: 0,
+            channels: Vec::new(),
             parameters,
         };
         plugin.refresh_from_parameters();
+        plugin.lookahead = lookahead_samples(plugin.lookahead_ms, plugin.sample_rate);
         plugin
     }
 }
@@ -927,6 +1206,11 @@ impl LimiterPlugin {
             .get(&ParameterId::from(PARAM_LIMITER_RELEASE))
             .and_then(ParameterValue::as_continuous)
             .unwrap_or(80.0);
+        self.lookahead_ms = self
+            .parameters
+            .get(&ParameterId::from(PARAM_LIMITER_LOOKAHEAD))
+            .and_then(ParameterValue::as_continuous)
+            .unwrap_or(0.0);
         self.ceiling = db_to_gain(ceiling_db);
         self.release_coeff = time_to_coeff(release_ms, self.sample_rate);
     }
@@ -939,22 +1223,17 @@ impl AudioProcessor for LimiterPlugin {
 
     fn prepare(&mut self, config: &BufferConfig) -> anyhow::Result<()> {
         self.sample_rate = config.sample_rate;
-        self.gain = vec![1.0; config.layout.channels() as usize];
         self.refresh_from_parameters();
+        self.lookahead = lookahead_samples(self.lookahead_ms, self.sample_rate);
+        self.channels =
+            vec![LimiterChannel::new(self.lookahead); config.layout.channels() as usize];
         Ok(())
     }
 
     fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
-        for (channel, gain) in buffer.channels_mut().zip(self.gain.iter_mut()) {
+        for (channel, state) in buffer.channels_mut().zip(self.channels.iter_mut()) {
             for sample in channel.iter_mut() {
-                let abs = sample.abs();
-                if abs > self.ceiling && abs > 1e-6 {
-                    let target = (self.ceiling / abs).min(*gain);
-                    *gain = target;
-                } else {
-                    *gain = *gain + (1.0 - *gain) * (1.0 - self.release_coeff);
-                }
-                *sample *= *gain;
+                *sample = state.process(*sample, self.ceiling, self.release_coeff);
             }
         }
         Ok(())
@@ -963,6 +1242,12 @@ impl AudioProcessor for LimiterPlugin {
     fn supports_layout(&self, layout: ChannelLayout) -> bool {
         matches!(layout, ChannelLayout::Mono | ChannelLayout::Stereo)
     }
+
+    /// Lookahead the delay lines were sized for. Lookahead changes take
+    /// effect, and change the reported latency, on the next `prepare`.
+    fn latency_samples(&self) -> usize {
+        self.lookahead
+    }
 }
 
 impl NativePlugin for LimiterPlugin {
@@ -977,9 +1262,13 @@ impl NativePlugin for LimiterPlugin {
     fn on_parameter_changed(
         &mut self,
         id: &ParameterId,
-        _value: &ParameterValue,
+        value: &ParameterValue,
     ) -> Result<(), PluginParameterError> {
-        if matches!(id.as_str(), PARAM_LIMITER_CEILING | PARAM_LIMITER_RELEASE) {
+        if id.as_str() == PARAM_LIMITER_LOOKAHEAD {
+            if let Some(ms) = value.as_continuous() {
+                self.lookahead_ms = ms;
+            }
+        } else if matches!(id.as_str(), PARAM_LIMITER_CEILING | PARAM_LIMITER_RELEASE) {
             self.refresh_from_parameters();
         }
         Ok(())
@@ -1002,6 +1291,15 @@ fn limiter_layout() -> ParameterLayout {
         )
         .with_unit("ms")
         .with_description("Time for the limiter to recover"),
+        ParameterDefinition::new(
+            PARAM_LIMITER_LOOKAHEAD,
+            "Lookahead",
+            ParameterKind::continuous(0.0..=10.0, 0.0),
+        )
+        .with_unit("ms")
+        .with_description(
+            "Delay that lets gain reduction start before a peak; off by default so the limiter adds no latency",
+        ),
     ])
 }
 
@@ -1358,38 +1656,188 @@ impl PluginFactory for ReverbFactory {
 const PARAM_DELAY_TIME: &str = "time";
 const PARAM_DELAY_FEEDBACK: &str = "feedback";
 const PARAM_DELAY_MIX: &str = "mix";
+const PARAM_DELAY_SYNC: &str = "sync";
+const PARAM_DELAY_DIVISION: &str = "division";
+const PARAM_DELAY_PING_PONG: &str = "ping_pong";
+const PARAM_DELAY_DAMPING: &str = "damping";
+const PARAM_DELAY_WIDTH: &str = "width";
+
+/// Longest free delay time.
+const DELAY_MAX_TIME_MS: f32 = 2_000.0;
+/// Slowest tempo whose synced divisions the lines are allocated for, the
+/// bottom of the transport's tempo range.
+const DELAY_MIN_TEMPO_BPM: f64 = 20.0;
+/// Taps a line can fade out at once when it is retargeted mid-fade.
+const DELAY_FADE_TAPS: usize = 4;
+/// Sample rate at which the damping parameter is the feedback low-pass
+/// coefficient; other rates keep the same cutoff.
+const DELAY_DAMPING_RATE: f32 = 48_000.0;
+/// Time over which the read tap moves to a new delay length.
+const DELAY_CROSSFADE_MS: f32 = 50.0;
+/// Index into [`DELAY_DIVISIONS`] of the default quarter note.
+const DELAY_DEFAULT_DIVISION: usize = 4;
+
+/// Note divisions offered when the delay follows the tempo, with their
+/// length in beats.
+const DELAY_DIVISIONS: [(&str, f64); 14] = [
+    ("1/1", 4.0),
+    ("1/2", 2.0),
+    ("1/2 dotted", 3.0),
+    ("1/2 triplet", 4.0 / 3.0),
+    ("1/4", 1.0),
+    ("1/4 dotted", 1.5),
+    ("1/4 triplet", 2.0 / 3.0),
+    ("1/8", 0.5),
+    ("1/8 dotted", 0.75),
+    ("1/8 triplet", 1.0 / 3.0),
+    ("1/16", 0.25),
+    ("1/16 dotted", 0.375),
+    ("1/16 triplet", 1.0 / 6.0),
+    ("1/32", 0.125),
+];
+
+/// Longest delay the lines are allocated for: the longest free time, or the
+/// longest division at [`DELAY_MIN_TEMPO_BPM`].
+fn delay_max_ms() -> f32 {
+    let beats = DELAY_DIVISIONS
+        .iter()
+        .map(|(_, beats)| *beats)
+        .fold(0.0, f64::max);
+    ((beats * 60_000.0 / DELAY_MIN_TEMPO_BPM) as f32).max(DELAY_MAX_TIME_MS)
+}
+
+/// How [`DelayPlugin`] derives its delay time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncMode {
+    /// The time parameter in milliseconds.
+    #[default]
+    FreeMs,
+    /// The selected note division at the project tempo.
+    Tempo,
+}
 
 #[derive(Debug, Clone)]
 struct DelayLine {
     buffer: Vec<f32>,
     index: usize,
+    delay: usize,
+    /// Taps the read tap is fading away from while `fade < 1`, as delays
+    /// with weights summing to one. The first `outgoing_len` are in use.
+    outgoing: [(usize, f32); DELAY_FADE_TAPS],
+    outgoing_len: usize,
+    fade: f32,
+    /// State of the one-pole low-pass in the feedback path.
+    damp: f32,
 }
 
 impl DelayLine {
-    fn new(length: usize) -> Self {
+    fn new(capacity: usize, delay: usize) -> Self {
+        let capacity = capacity.max(1);
+        let delay = delay.clamp(1, capacity);
         Self {
-            buffer: vec![0.0; length.max(1)],
+            buffer: vec![0.0; capacity],
             index: 0,
+            delay,
+            outgoing: [(delay, 0.0); DELAY_FADE_TAPS],
+            outgoing_len: 0,
+            fade: 1.0,
+            damp: 0.0,
+        }
+    }
+
+    /// Moves the read tap to `delay` samples, crossfading from what the line
+    /// currently plays so the length change does not repitch the echoes. A
+    /// retarget during a fade restarts it from the blend heard so far.
+    fn set_delay(&mut self, delay: usize) {
+        let delay = delay.clamp(1, self.buffer.len());
+        if delay == self.delay {
+            return;
+        }
+        if self.fade >= 1.0 {
+            self.outgoing_len = 0;
+        }
+        for (_, weight) in &mut self.outgoing[..self.outgoing_len] {
+            *weight *= 1.0 - self.fade;
+        }
+        let weight = self.fade.min(1.0);
+        if weight > 0.0 {
+            self.push_outgoing(self.delay, weight);
         }
+        self.delay = delay;
+        self.fade = 0.0;
     }
 
-    fn set_length(&mut self, length: usize) {
-        if self.buffer.len() != length.max(1) {
-            self.buffer = vec![0.0; length.max(1)];
-            self.index = 0;
+    /// Adds `weight` of the tap at `delay` to the outgoing blend. When every
+    /// slot is taken, the lightest tap is dropped and the rest renormalized.
+    fn push_outgoing(&mut self, delay: usize, weight: f32) {
+        let taps = &mut self.outgoing[..self.outgoing_len];
+        if let Some(tap) = taps.iter_mut().find(|(tap, _)| *tap == delay) {
+            tap.1 += weight;
+            return;
+        }
+        if self.outgoing_len == DELAY_FADE_TAPS {
+            let lightest = (0..DELAY_FADE_TAPS)
+                .min_by(|a, b| self.outgoing[*a].1.total_cmp(&self.outgoing[*b].1))
+                .unwrap_or(0);
+            self.outgoing[lightest] = (delay, weight);
+            let total: f32 = self.outgoing.iter().map(|(_, weight)| weight).sum();
+            for (_, weight) in &mut self.outgoing {
+                *weight /= total;
+            }
+            return;
         }
+        self.outgoing[self.outgoing_len] = (delay, weight);
+        self.outgoing_len += 1;
     }
 
     #[allow(dead_code)]
     fn reset(&mut self) {
         self.buffer.fill(0.0);
         self.index = 0;
+        self.outgoing_len = 0;
+        self.fade = 1.0;
+        self.damp = 0.0;
     }
 
-    fn process(&mut self, input: f32, feedback: f32) -> f32 {
-        let delayed = self.buffer[self.index];
-        self.buffer[self.index] = input + delayed * feedback;
+    #[inline]
+    fn tap(&self, delay: usize) -> f32 {
+        let len = self.buffer.len();
+        self.buffer[(self.index + len - delay) % len]
+    }
+
+    /// Output of the read tap for the current sample. Call once per sample,
+    /// before [`write`](Self::write).
+    fn read(&mut self, fade_step: f32) -> f32 {
+        let current = self.tap(self.delay);
+        if self.fade < 1.0 {
+            let previous: f32 = self.outgoing[..self.outgoing_len]
+                .iter()
+                .map(|(delay, weight)| self.tap(*delay) * weight)
+                .sum();
+            self.fade = (self.fade + fade_step).min(1.0);
+            previous + (current - previous) * self.fade
+        } else {
+            current
+        }
+    }
+
+    fn write(&mut self, value: f32) {
+        self.buffer[self.index] = value;
         self.index = (self.index + 1) % self.buffer.len();
+    }
+
+    /// Low-passes a repeat before it is fed back, so each echo is darker
+    /// than the last. A coefficient of zero passes the signal unchanged.
+    #[inline]
+    fn damp(&mut self, value: f32, coefficient: f32) -> f32 {
+        self.damp += (value - self.damp) * (1.0 - coefficient);
+        self.damp
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, coefficient: f32, fade_step: f32) -> f32 {
+        let delayed = self.read(fade_step);
+        let repeat = self.damp(delayed, coefficient) * feedback;
+        self.write(input + repeat);
         delayed
     }
 }
@@ -1397,9 +1845,16 @@ impl DelayLine {
 #[derive(Debug, Clone)]
 pub struct DelayPlugin {
     sample_rate: f32,
+    sync: SyncMode,
+    time_ms: f32,
+    division: usize,
+    tempo: Tempo,
     delay_samples: usize,
     feedback: f32,
     mix: f32,
+    ping_pong: bool,
+    damping: f32,
+    width: f32,
     lines: Vec<DelayLine>,
     parameters: ParameterSet,
 }
@@ -1410,9 +1865,16 @@ impl Default for DelayPlugin {
         let parameters = ParameterSet::new(layout);
         let mut plugin = Self {
             sample_rate: 48_000.0,
+            sync: SyncMode::FreeMs,
+            time_ms: 400.0,
+            division: DELAY_DEFAULT_DIVISION,
+            tempo: Tempo::default(),
             delay_samples: 1,
             feedback: 0.35,
             mix: 0.35,
+            ping_pong: false,
+            damping: 0.0,
+            width: 1.0,
             lines: Vec::new(),
             parameters,
         };
@@ -1422,13 +1884,43 @@ impl Default for DelayPlugin {
 }
 
 impl DelayPlugin {
+    pub fn sync_mode(&self) -> SyncMode {
+        self.sync
+    }
+
+    /// Current delay time, either the free time or the synced division.
+    /// Divisions longer than the lines hold, below [`DELAY_MIN_TEMPO_BPM`],
+    /// play at the longest delay, and this reports that time.
+    pub fn delay_time_ms(&self) -> f32 {
+        let time_ms = match self.sync {
+            SyncMode::FreeMs => self.time_ms,
+            SyncMode::Tempo => {
+                let beats = DELAY_DIVISIONS[self.division.min(DELAY_DIVISIONS.len() - 1)].1;
+                (beats * self.tempo.seconds_per_beat() * 1_000.0) as f32
+            }
+        };
+        time_ms.min(delay_max_ms())
+    }
+
     fn refresh_from_parameters(&mut self) {
-        let time_ms = self
+        self.time_ms = self
             .parameters
             .get(&ParameterId::from(PARAM_DELAY_TIME))
             .and_then(ParameterValue::as_continuous)
             .unwrap_or(400.0);
-        self.delay_samples = ((time_ms / 1_000.0) * self.sample_rate).round().max(1.0) as usize;
+        self.sync = match self
+            .parameters
+            .get(&ParameterId::from(PARAM_DELAY_SYNC))
+            .and_then(ParameterValue::as_choice)
+        {
+            Some(1) => SyncMode::Tempo,
+            _ => SyncMode::FreeMs,
+        };
+        self.division = self
+            .parameters
+            .get(&ParameterId::from(PARAM_DELAY_DIVISION))
+            .and_then(ParameterValue::as_choice)
+            .unwrap_or(DELAY_DEFAULT_DIVISION);
         self.feedback = self
             .parameters
             .get(&ParameterId::from(PARAM_DELAY_FEEDBACK))
@@ -1441,8 +1933,71 @@ impl DelayPlugin {
             .and_then(ParameterValue::as_continuous)
             .unwrap_or(0.35)
             .clamp(0.0, 1.0);
+        self.ping_pong = self
+            .parameters
+            .get(&ParameterId::from(PARAM_DELAY_PING_PONG))
+            .and_then(ParameterValue::as_toggle)
+            .unwrap_or(false);
+        self.damping = self
+            .parameters
+            .get(&ParameterId::from(PARAM_DELAY_DAMPING))
+            .and_then(ParameterValue::as_continuous)
+            .unwrap_or(0.0)
+            .clamp(0.0, 0.99);
+        self.width = self
+            .parameters
+            .get(&ParameterId::from(PARAM_DELAY_WIDTH))
+            .and_then(ParameterValue::as_continuous)
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0);
+        self.update_delay();
+    }
+
+    /// Feedback low-pass coefficient at the current sample rate.
+    fn damping_coefficient(&self) -> f32 {
+        self.damping
+            .powf(DELAY_DAMPING_RATE / self.sample_rate.max(1.0))
+    }
+
+    /// Feeds each line from its own channel, or with ping-pong the summed
+    /// input into the left line and each line's repeats into the other, so
+    /// echoes alternate between the sides. `width` scales the side signal
+    /// of the echoes only.
+    fn process_stereo(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        damping: f32,
+        fade_step: f32,
+    ) {
+        let [left_line, right_line, ..] = self.lines.as_mut_slice() else {
+            return;
+        };
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let delayed_l = left_line.read(fade_step);
+            let delayed_r = right_line.read(fade_step);
+            let repeat_l = left_line.damp(delayed_l, damping) * self.feedback;
+            let repeat_r = right_line.damp(delayed_r, damping) * self.feedback;
+            if self.ping_pong {
+                left_line.write((*l + *r) * 0.5 + repeat_r);
+                right_line.write(repeat_l);
+            } else {
+                left_line.write(*l + repeat_l);
+                right_line.write(*r + repeat_r);
+            }
+
+            let mid = (delayed_l + delayed_r) * 0.5;
+            let side = (delayed_l - delayed_r) * 0.5 * self.width;
+            *l = *l * (1.0 - self.mix) + (mid + side) * self.mix;
+            *r = *r * (1.0 - self.mix) + (mid - side) * self.mix;
+        }
+    }
+
+    fn update_delay(&mut self) {
+        let samples = (self.delay_time_ms() / 1_000.0) * self.sample_rate;
+        self.delay_samples = samples.round().max(1.0) as usize;
         for line in &mut self.lines {
-            line.set_length(self.delay_samples);
+            line.set_delay(self.delay_samples);
         }
     }
 }
@@ -1454,17 +2009,27 @@ impl AudioProcessor for DelayPlugin {
 
     fn prepare(&mut self, config: &BufferConfig) -> anyhow::Result<()> {
         self.sample_rate = config.sample_rate;
+        self.refresh_from_parameters();
+        let capacity = (delay_max_ms() / 1_000.0 * self.sample_rate).ceil() as usize;
         self.lines = (0..config.layout.channels() as usize)
-            .map(|_| DelayLine::new(self.delay_samples))
+            .map(|_| DelayLine::new(capacity, self.delay_samples))
             .collect();
-        self.refresh_from_parameters();
         Ok(())
     }
 
     fn process(&mut self, buffer: &mut AudioBuffer) -> anyhow::Result<()> {
+        let fade_step = 1_000.0 / (DELAY_CROSSFADE_MS * self.sample_rate.max(1.0));
+        let damping = self.damping_coefficient();
+        if buffer.channel_count() >= 2 && self.lines.len() >= 2 {
+            let mut channels = buffer.channels_mut();
+            if let (Some(left), Some(right)) = (channels.next(), channels.next()) {
+                self.process_stereo(left, right, damping, fade_step);
+            }
+            return Ok(());
+        }
         for (channel, line) in buffer.channels_mut().zip(self.lines.iter_mut()) {
             for sample in channel.iter_mut() {
-                let delayed = line.process(*sample, self.feedback);
+                let delayed = line.process(*sample, self.feedback, damping, fade_step);
                 *sample = *sample * (1.0 - self.mix) + delayed * self.mix;
             }
         }
@@ -1474,6 +2039,13 @@ impl AudioProcessor for DelayPlugin {
     fn supports_layout(&self, layout: ChannelLayout) -> bool {
         matches!(layout, ChannelLayout::Mono | ChannelLayout::Stereo)
     }
+
+    fn set_tempo(&mut self, tempo: Tempo) {
+        self.tempo = tempo;
+        if self.sync == SyncMode::Tempo {
+            self.update_delay();
+        }
+    }
 }
 
 impl NativePlugin for DelayPlugin {
@@ -1488,14 +2060,53 @@ impl NativePlugin for DelayPlugin {
     fn on_parameter_changed(
         &mut self,
         id: &ParameterId,
-        _value: &ParameterValue,
+        value: &ParameterValue,
     ) -> Result<(), PluginParameterError> {
-        if matches!(
-            id.as_str(),
-            PARAM_DELAY_TIME | PARAM_DELAY_FEEDBACK | PARAM_DELAY_MIX
-        ) {
-            self.refresh_from_parameters();
+        match id.as_str() {
+            PARAM_DELAY_TIME => {
+                if let Some(ms) = value.as_continuous() {
+                    self.time_ms = ms;
+                }
+            }
+            PARAM_DELAY_SYNC => {
+                self.sync = match value.as_choice() {
+                    Some(1) => SyncMode::Tempo,
+                    _ => SyncMode::FreeMs,
+                };
+            }
+            PARAM_DELAY_DIVISION => {
+                if let Some(division) = value.as_choice() {
+                    self.division = division;
+                }
+            }
+            PARAM_DELAY_FEEDBACK => {
+                if let Some(feedback) = value.as_continuous() {
+                    self.feedback = feedback.clamp(0.0, 0.95);
+                }
+            }
+            PARAM_DELAY_MIX => {
+                if let Some(mix) = value.as_continuous() {
+                    self.mix = mix.clamp(0.0, 1.0);
+                }
+            }
+            PARAM_DELAY_PING_PONG => {
+                if let Some(enabled) = value.as_toggle() {
+                    self.ping_pong = enabled;
+                }
+            }
+            PARAM_DELAY_DAMPING => {
+                if let Some(damping) = value.as_continuous() {
+                    self.damping = damping.clamp(0.0, 0.99);
+                }
+            }
+            PARAM_DELAY_WIDTH => {
+                if let Some(width) = value.as_continuous() {
+                    self.width = width.clamp(0.0, 1.0);
+                }
+            }
+            _ => return Ok(()),
         }
+        self.update_delay();
         Ok(())
     }
 }
@@ -1505,7 +2116,7 @@ fn delay_layout() -> ParameterLayout {
         ParameterDefinition::new(
             PARAM_DELAY_TIME,
             "Time",
-            ParameterKind::continuous(1.0..=2_000.0, 400.0),
+            ParameterKind::continuous(1.0..=DELAY_MAX_TIME_MS, 400.0),
         )
         .with_unit("ms")
         .with_description("Delay time"),
@@ -1521,6 +2132,45 @@ fn delay_layout() -> ParameterLayout {
             ParameterKind::continuous(0.0..=1.0, 0.35),
         )
         .with_description("Wet/dry balance"),
+        ParameterDefinition::new(
+            PARAM_DELAY_SYNC,
+            "Sync",
+            ParameterKind::Choice {
+                options: vec!["Free".into(), "Tempo".into()],
+                default: 0,
+            },
+        )
+        .with_description("Follow the time in milliseconds or the project tempo"),
+        ParameterDefinition::new(
+            PARAM_DELAY_DIVISION,
+            "Division",
+            ParameterKind::Choice {
+                options: DELAY_DIVISIONS
+                    .iter()
+                    .map(|(label, _)| (*label).into())
+                    .collect(),
+                default: DELAY_DEFAULT_DIVISION,
+            },
+        )
+        .with_description("Note length of the delay when synced to tempo"),
+        ParameterDefinition::new(
+            PARAM_DELAY_PING_PONG,
+            "Ping-Pong",
+            ParameterKind::Toggle { default: false },
+        )
+        .with_description("Bounce repeats between the left and right channels"),
+        ParameterDefinition::new(
+            PARAM_DELAY_DAMPING,
+            "Damping",
+            ParameterKind::continuous(0.0..=0.99, 0.0),
+        )
+        .with_description("High-frequency loss applied to every repeat"),
+        ParameterDefinition::new(
+            PARAM_DELAY_WIDTH,
+            "Width",
+            ParameterKind::continuous(0.0..=1.0, 1.0),
+        )
+        .with_description("Stereo spread of the echoes"),
     ])
 }
 
@@ -3004,3 +3654,354 @@ impl PluginFactory for NoiseGateFactory {
         Box::new(NoiseGatePlugin::default())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gain in dB the EQ applies to a sine at `frequency`, measured once
+    /// the filters, and in linear-phase mode the FIR latency, have settled.
+    fn eq_gain_db(preset: &ParametricEqPreset, phase: usize, frequency: f32) -> f32 {
+        let mut plugin = ParametricEqPlugin::default();
+        plugin
+            .set_parameter(
+                &ParameterId::from(PARAM_EQ_PHASE),
+                ParameterValue::Choice(phase),
+            )
+            .expect("phase parameter");
+        let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Mono);
+        plugin.prepare(&config).expect("prepare");
+        plugin.apply_preset(preset);
+
+        let (mut input_energy, mut output_energy) = (0.0f64, 0.0f64);
+        let mut buffer = AudioBuffer::new(1, 512);
+        for block in 0..72 {
+            let start = block * 512;
+            for (offset, sample) in buffer.channel_mut(0).iter_mut().enumerate() {
+                *sample = (TWO_PI * frequency * (start + offset) as f32 / 48_000.0).sin();
+            }
+            let input = buffer.channel(0).to_vec();
+            plugin.process(&mut buffer).expect("process");
+            if block >= 48 {
+                input_energy += input.iter().map(|x| (*x as f64).powi(2)).sum::<f64>();
+                output_energy += buffer
+                    .channel(0)
+                    .iter()
+                    .map(|y| (*y as f64).powi(2))
+                    .sum::<f64>();
+            }
+        }
+        (10.0 * (output_energy / input_energy).log10()) as f32
+    }
+
+    #[test]
+    fn linear_phase_matches_minimum_phase_magnitude() {
+        let band = |frequency, gain, q| ParametricEqBandPreset {
+            enabled: true,
+            frequency,
+            gain,
+            q,
+        };
+        let steep = ParametricEqPreset {
+            name: "Steep",
+            output_gain: 0.0,
+            bands: [
+                band(100.0, -6.0, 0.7),
+                band(1_000.0, 9.0, 2.0),
+                band(3_000.0, -6.0, 1.0),
+                band(8_000.0, 6.0, 0.7),
+            ],
+        };
+        let presets = PARAMETRIC_EQ_FACTORY_PRESETS.iter().chain([&steep]);
+        for preset in presets {
+            for frequency in [50.0, 150.0, 420.0, 1_000.0, 2_800.0, 6_000.0, 12_000.0] {
+                let minimum = eq_gain_db(preset, 0, frequency);
+                let linear = eq_gain_db(preset, EQ_PHASE_LINEAR, frequency);
+                assert!(
+                    (minimum - linear).abs() < 0.1,
+                    "{} at {frequency} Hz: {minimum} dB vs {linear} dB",
+                    preset.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn linear_phase_reports_its_latency() {
+        let mut plugin = ParametricEqPlugin::default();
+        assert_eq!(plugin.latency_samples(), 0);
+        plugin
+            .set_parameter(
+                &ParameterId::from(PARAM_EQ_PHASE),
+                ParameterValue::Choice(EQ_PHASE_LINEAR),
+            )
+            .expect("phase parameter");
+        // 4 096-sample overlap-add hops plus half of the 4 097-tap FIR.
+        assert_eq!(plugin.latency_samples(), 4_096 + 2_048);
+    }
+
+    /// Feeds a steady -20 dBFS tone, below the default threshold, through the
+    /// compressor for half a second with a full-scale key on the sidechain.
+    fn compress_with_key(key_source: usize) -> f32 {
+        let mut plugin = CompressorPlugin::default();
+        plugin
+            .set_parameter(
+                &ParameterId::from(PARAM_COMP_KEY),
+                ParameterValue::Choice(key_source),
+            )
+            .expect("key parameter");
+        let config = BufferConfig::new(48_000.0, 256, ChannelLayout::Stereo);
+        plugin.prepare(&config).expect("prepare");
+
+        let mut key = AudioBuffer::new(1, 256);
+        for sample in key.channel_mut(0) {
+            *sample = 1.0;
+        }
+        let mut buffer = AudioBuffer::new(2, 256);
+        for _ in 0..96 {
+            for channel in buffer.channels_mut() {
+                channel.fill(0.1);
+            }
+            plugin
+                .process_with_aux(&mut buffer, &[&key], &ProcessTransport::default())
+                .expect("process");
+        }
+        buffer.channel(1)[255]
+    }
+
+    #[test]
+    fn limiter_adds_no_latency_unless_lookahead_is_enabled() {
+        let mut plugin = LimiterPlugin::default();
+        let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Stereo);
+        plugin.prepare(&config).expect("prepare");
+        assert_eq!(plugin.latency_samples(), 0);
+
+        let mut buffer = AudioBuffer::new(2, 64);
+        buffer.channel_mut(0)[0] = 0.5;
+        plugin.process(&mut buffer).expect("process");
+        assert_eq!(buffer.channel(0)[0], 0.5);
+    }
+
+    #[test]
+    fn lookahead_limits_transient_before_it_arrives() {
+        let mut plugin = LimiterPlugin::default();
+        plugin
+            .set_parameter(
+                &ParameterId::from(PARAM_LIMITER_LOOKAHEAD),
+                ParameterValue::Continuous(5.0),
+            )
+            .expect("lookahead parameter");
+        assert_eq!(plugin.latency_samples(), 0);
+        let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Mono);
+        plugin.prepare(&config).expect("prepare");
+        let latency = plugin.latency_samples();
+        assert_eq!(latency, 240);
+
+        let onset = 1_000;
+        let mut buffer = AudioBuffer::new(1, 2_048);
+        for (index, sample) in buffer.channel_mut(0).iter_mut().enumerate() {
+            *sample = if index < onset { 0.1 } else { 1.0 };
+        }
+        plugin.process(&mut buffer).expect("process");
+        let output = buffer.channel(0);
+
+        let ceiling = db_to_gain(-0.3);
+        assert!(output.iter().all(|sample| sample.abs() <= ceiling + 1e-4));
+        assert_eq!(output[latency - 1], 0.0);
+        assert!((output[latency] - 0.1).abs() < 1e-6);
+        // The quiet signal is already ducked just before the step emerges.
+        assert!(output[latency + onset - 1] < 0.1 * ceiling + 1e-3);
+        assert!((output[latency + onset] - ceiling).abs() < 1e-3);
+    }
+
+    #[test]
+    fn synced_quarter_note_at_120_bpm_is_500_ms() {
+        let mut plugin = DelayPlugin::default();
+        for (id, value) in [
+            (PARAM_DELAY_SYNC, ParameterValue::Choice(1)),
+            (PARAM_DELAY_DIVISION, ParameterValue::Choice(4)),
+            (PARAM_DELAY_MIX, ParameterValue::Continuous(1.0)),
+            (PARAM_DELAY_FEEDBACK, ParameterValue::Continuous(0.0)),
+        ] {
+            plugin
+                .set_parameter(&ParameterId::from(id), value)
+                .expect("delay parameter");
+        }
+        plugin.set_tempo(Tempo(120.0));
+        assert_eq!(plugin.sync_mode(), SyncMode::Tempo);
+        assert!((plugin.delay_time_ms() - 500.0).abs() < 1e-3);
+
+        let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Mono);
+        plugin.prepare(&config).expect("prepare");
+        let mut buffer = AudioBuffer::new(1, 30_000);
+        buffer.channel_mut(0)[0] = 1.0;
+        plugin.process(&mut buffer).expect("process");
+        let echo = buffer
+            .channel(0)
+            .iter()
+            .position(|sample| sample.abs() > 0.5)
+            .expect("echo");
+        assert_eq!(echo, 24_000);
+
+        plugin.set_tempo(Tempo(60.0));
+        assert!((plugin.delay_time_ms() - 1_000.0).abs() < 1e-3);
+
+        // A whole note at the slowest tempo still fits in the lines.
+        plugin
+            .set_parameter(
+                &ParameterId::from(PARAM_DELAY_DIVISION),
+                ParameterValue::Choice(0),
+            )
+            .expect("division");
+        plugin.set_tempo(Tempo(DELAY_MIN_TEMPO_BPM));
+        assert!((plugin.delay_time_ms() - 12_000.0).abs() < 1e-2);
+        assert_eq!(plugin.delay_samples, 576_000);
+        assert!(plugin.lines[0].buffer.len() >= plugin.delay_samples);
+    }
+
+    /// Mono delay with a 10 ms time and a fully wet mix, prepared at
+    /// `sample_rate`.
+    fn mono_delay(sample_rate: f32, parameters: &[(&str, ParameterValue)]) -> DelayPlugin {
+        let mut plugin = DelayPlugin::default();
+        for (id, value) in [
+            (PARAM_DELAY_TIME, ParameterValue::Continuous(10.0)),
+            (PARAM_DELAY_MIX, ParameterValue::Continuous(1.0)),
+        ]
+        .into_iter()
+        .chain(parameters.iter().cloned())
+        {
+            plugin
+                .set_parameter(&ParameterId::from(id), value)
+                .expect("delay parameter");
+        }
+        let config = BufferConfig::new(sample_rate, 512, ChannelLayout::Mono);
+        plugin.prepare(&config).expect("prepare");
+        plugin
+    }
+
+    /// Renders 100 ms of an impulse through `plugin`.
+    fn impulse_response(plugin: &mut DelayPlugin) -> Vec<f32> {
+        let mut buffer = AudioBuffer::new(1, plugin.sample_rate as usize / 10);
+        buffer.channel_mut(0)[0] = 1.0;
+        plugin.process(&mut buffer).expect("process");
+        buffer.channel(0).to_vec()
+    }
+
+    #[test]
+    fn damping_keeps_its_cutoff_across_sample_rates() {
+        let decay = |sample_rate: f32| {
+            let mut plugin = mono_delay(
+                sample_rate,
+                &[
+                    (PARAM_DELAY_FEEDBACK, ParameterValue::Continuous(0.5)),
+                    (PARAM_DELAY_DAMPING, ParameterValue::Continuous(0.9)),
+                ],
+            );
+            let output = impulse_response(&mut plugin);
+            // The second echo is the first one low-passed; compare its
+            // level 1 ms in with its onset.
+            let onset = 2 * (sample_rate as usize / 100);
+            output[onset + sample_rate as usize / 1_000] / output[onset]
+        };
+        let (base, double) = (decay(48_000.0), decay(96_000.0));
+        assert!((base - 0.9f32.powi(48)).abs() < 1e-4, "{base}");
+        assert!((double - base).abs() < 1e-4, "{double} vs {base}");
+    }
+
+    #[test]
+    fn feedback_above_unity_is_clamped() {
+        let mut plugin = mono_delay(48_000.0, &[]);
+        let feedback = ParameterId::from(PARAM_DELAY_FEEDBACK);
+        assert!(plugin
+            .set_parameter(&feedback, ParameterValue::Continuous(5.0))
+            .is_err());
+
+        // Values that bypass validation, such as automation, are clamped.
+        plugin
+            .on_parameter_changed(&feedback, &ParameterValue::Continuous(5.0))
+            .expect("feedback");
+        let output = impulse_response(&mut plugin);
+        let echoes: Vec<f32> = (1..10).map(|repeat| output[repeat * 480]).collect();
+        assert!((echoes[1] - 0.95).abs() < 1e-6, "{echoes:?}");
+        assert!(
+            echoes.windows(2).all(|pair| pair[1] < pair[0]),
+            "{echoes:?}"
+        );
+    }
+
+    #[test]
+    fn retargeting_mid_fade_does_not_jump() {
+        let mut line = DelayLine::new(1_000, 100);
+        let mut last = 0.0;
+        let mut largest_step = 0.0f32;
+        for n in 0..800 {
+            match n {
+                300 => line.set_delay(300),
+                350 => line.set_delay(50),
+                370 => line.set_delay(200),
+                _ => {}
+            }
+            let output = line.process(n as f32 / 1_000.0, 0.0, 0.0, 0.01);
+            if n > 300 {
+                largest_step = largest_step.max((output - last).abs());
+            }
+            last = output;
+        }
+        // A ramp moves 0.001 a sample; the fades add at most 0.01 of the
+        // 0.25 gap between taps.
+        assert!(largest_step < 0.004, "{largest_step}");
+    }
+
+    #[test]
+    fn ping_pong_alternates_echoes_between_channels() {
+        let mut plugin = DelayPlugin::default();
+        for (id, value) in [
+            (PARAM_DELAY_TIME, ParameterValue::Continuous(10.0)),
+            (PARAM_DELAY_PING_PONG, ParameterValue::Toggle(true)),
+            (PARAM_DELAY_MIX, ParameterValue::Continuous(1.0)),
+            (PARAM_DELAY_FEEDBACK, ParameterValue::Continuous(0.5)),
+        ] {
+            plugin
+                .set_parameter(&ParameterId::from(id), value)
+                .expect("delay parameter");
+        }
+        let config = BufferConfig::new(48_000.0, 512, ChannelLayout::Stereo);
+        plugin.prepare(&config).expect("prepare");
+
+        let delay = 480;
+        let mut buffer = AudioBuffer::new(2, 5 * delay);
+        for channel in buffer.channels_mut() {
+            channel[0] = 1.0;
+        }
+        plugin.process(&mut buffer).expect("process");
+
+        let (left, right) = (buffer.channel(0), buffer.channel(1));
+        for (repeat, level) in [1.0, 0.5, 0.25, 0.125].into_iter().enumerate() {
+            let offset = (repeat + 1) * delay;
+            let (loud, quiet) = if repeat % 2 == 0 {
+                (left, right)
+            } else {
+                (right, left)
+            };
+            assert!((loud[offset] - level).abs() < 1e-6, "repeat {repeat}");
+            assert!(quiet[offset].abs() < 1e-6, "repeat {repeat}");
+        }
+        let echoes = left
+            .iter()
+            .chain(right)
+            .filter(|sample| sample.abs() > 1e-6)
+            .count();
+        assert_eq!(echoes, 4);
+    }
+
+    #[test]
+    fn external_key_drives_gain_reduction() {
+        let internal = compress_with_key(0);
+        assert!((internal - 0.1).abs() < 1e-3, "internal {internal}");
+
+        // 18 dB over the threshold at 4:1 leaves 13.5 dB of reduction.
+        let external = compress_with_key(COMP_KEY_EXTERNAL);
+        let expected = 0.1 * db_to_gain(-13.5);
+        assert!((external - expected).abs() < 2e-3, "external {external}");
+    }
+}
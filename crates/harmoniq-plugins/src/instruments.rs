@@ -132,15 +132,9 @@ fn midi_note_to_freq(note: u8) -> f32 {
 }
 
 fn fill_buffer(buffer: &mut AudioBuffer, mut render: impl FnMut() -> f32) {
-    let samples = buffer.len();
-    let mut values = Vec::with_capacity(samples);
-    for _ in 0..samples {
-        values.push(render());
-    }
-    for channel in buffer.channels_mut() {
-        for (sample, value) in channel.iter_mut().zip(values.iter()) {
-            *sample = *value;
-        }
+    for frame in 0..buffer.len() {
+        let value = render();
+        buffer.write_stereo_frame(frame, value, value);
     }
 }
 
@@ -1269,11 +1263,7 @@ impl AudioProcessor for GranularSynth {
                 self.spawn_grain();
             }
             let sample = self.process_sample() * level;
-            for channel in buffer.channels_mut() {
-                if let Some(slot) = channel.get_mut(frame) {
-                    *slot = sample;
-                }
-            }
+            buffer.write_stereo_frame(frame, sample, sample);
         }
         Ok(())
     }
@@ -1699,8 +1689,6 @@ impl AudioProcessor for GrandPianoClap {
             clap_level: self.clap_level,
             width: self.width,
         };
-        let mut left = vec![0.0f32; frames];
-        let mut right = vec![0.0f32; frames];
         for frame in 0..frames {
             let mut l = 0.0;
             let mut r = 0.0;
@@ -1709,25 +1697,7 @@ impl AudioProcessor for GrandPianoClap {
                 l += vl;
                 r += vr;
             }
-            left[frame] = l.tanh();
-            right[frame] = r.tanh();
-        }
-
-        match buffer.channel_count() {
-            0 => {}
-            1 => {
-                let channel = buffer.channel_mut(0);
-                for (index, sample) in channel.iter_mut().enumerate() {
-                    *sample = ((left[index] + right[index]) * 0.5).clamp(-1.0, 1.0);
-                }
-            }
-            _ => {
-                buffer.channel_mut(0).copy_from_slice(&left);
-                buffer.channel_mut(1).copy_from_slice(&right);
-                for channel in 2..buffer.channel_count() {
-                    buffer.channel_mut(channel).fill(0.0);
-                }
-            }
+            buffer.write_stereo_frame(frame, l.tanh(), r.tanh());
         }
         Ok(())
     }
@@ -9,7 +9,9 @@ pub mod generators;
 pub mod instruments;
 pub mod samples;
 
-pub use dynamics::{GainPlugin, GainPluginFactory};
+pub use dynamics::{
+    GainPlugin, GainPluginFactory, MultibandCompressor, MultibandCompressorFactory,
+};
 pub use editors::{AudioClipMetrics, AudioEditorPlugin, AudioEditorPluginFactory};
 pub use effects::{
     AutoFilterFactory, AutoFilterPlugin, ChorusFactory, ChorusPlugin, CompressorFactory,
@@ -17,7 +19,7 @@ pub use effects::{
     FlangerFactory, FlangerPlugin, LimiterFactory, LimiterPlugin, NoiseGateFactory,
     NoiseGatePlugin, ParametricEqBandPreset, ParametricEqFactory, ParametricEqPlugin,
     ParametricEqPreset, PhaserFactory, PhaserPlugin, ReverbFactory, ReverbPlugin,
-    StereoEnhancerFactory, StereoEnhancerPlugin, PARAMETRIC_EQ_FACTORY_PRESETS,
+    StereoEnhancerFactory, StereoEnhancerPlugin, SyncMode, PARAMETRIC_EQ_FACTORY_PRESETS,
 };
 pub use generators::{NoisePlugin, NoisePluginFactory, SineSynth, SineSynthFactory};
 pub use instruments::{
@@ -49,6 +51,7 @@ pub fn builtin_module() -> PluginModule {
         .register_factory(Box::new(WestCoastLeadFactory))
         .register_factory(Box::new(ParametricEqFactory))
         .register_factory(Box::new(CompressorFactory))
+        .register_factory(Box::new(MultibandCompressorFactory))
         .register_factory(Box::new(LimiterFactory))
         .register_factory(Box::new(ReverbFactory))
         .register_factory(Box::new(DelayFactory))
@@ -1,8 +1,13 @@
 //! Utilities to convert step lanes to a simple MIDI clip representation
 //! (kept UI-side; engine bridge does the scheduling later).
 
+use harmoniq_engine::{Step as PatternStep, StepPattern};
+
 use crate::state::{ChannelId, ChannelKind, PatternId, RackState};
 
+const PPQ: u32 = 480;
+const ROOT_KEY: u8 = 60; // C4
+
 #[derive(Clone, Debug)]
 pub struct MidiNote {
     pub start_ticks: u32,
@@ -17,31 +22,70 @@ pub struct MidiClip {
     pub notes: Vec<MidiNote>,
 }
 
-/// Generate a 1-bar clip from steps using 16th/32nd grid at C4.
-pub fn steps_to_midi(state: &RackState, pat: PatternId, ch: ChannelId) -> Option<MidiClip> {
+/// Build a 1-bar engine step pattern from steps using 16th/32nd grid at C4,
+/// carrying each step's ratchets.
+pub fn steps_to_pattern(state: &RackState, pat: PatternId, ch: ChannelId) -> Option<StepPattern> {
     let chref = state.channels.iter().find(|c| c.id == ch)?;
     if !matches!(chref.kind, ChannelKind::Instrument | ChannelKind::Sample) {
         return None;
     }
     let steps = chref.steps.get(&pat)?;
     let div = chref.steps_per_bar.max(1);
-    let ppq = 480;
-    let bar_ticks = 4 * ppq;
-    let step_ticks = (bar_ticks as f32 / div as f32).round() as u32;
+    let bar_ticks = 4 * PPQ;
+    let step_ticks = (bar_ticks as f32 / div as f32).round() as i64;
 
-    let mut clip = MidiClip { ppq, notes: vec![] };
+    let mut pattern = StepPattern::new(steps.len(), step_ticks);
     for (i, st) in steps.iter().enumerate() {
         if !st.on {
             continue;
         }
-        let start = i as u32 * step_ticks;
-        let len = step_ticks.max(ppq / 8);
-        clip.notes.push(MidiNote {
-            start_ticks: start,
-            length_ticks: len,
-            key: 60, // C4
-            velocity: st.velocity.min(127).max(1),
-        });
+        let mut step =
+            PatternStep::on(ROOT_KEY, st.velocity.clamp(1, 127)).with_ratchet(st.ratchet);
+        if let Some(velocity) = st.ratchet_velocity {
+            step = step.with_ratchet_velocity(velocity);
+        }
+        pattern.set_step(i, step);
+    }
+    Some(pattern)
+}
+
+/// Generate a 1-bar clip from steps using 16th/32nd grid at C4, with one
+/// note per ratchet retrigger.
+pub fn steps_to_midi(state: &RackState, pat: PatternId, ch: ChannelId) -> Option<MidiClip> {
+    let pattern = steps_to_pattern(state, pat, ch)?;
+    let notes = pattern
+        .to_pattern_notes()
+        .into_iter()
+        .map(|note| MidiNote {
+            start_ticks: note.start_ticks as u32,
+            length_ticks: note.duration_ticks as u32,
+            key: note.pitch as i8,
+            velocity: note.velocity,
+        })
+        .collect();
+    Some(MidiClip { ppq: PPQ, notes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratcheted_steps_become_evenly_spaced_notes() {
+        let mut rack = RackState::new_default();
+        let channel = rack.add_channel("Hats".into(), ChannelKind::Sample, None);
+        let pattern = rack.current_pattern;
+        let steps = rack.steps_mut(pattern, channel);
+        steps[0].on = true;
+        steps[4].on = true;
+        steps[4].ratchet = 3;
+        steps[4].ratchet_velocity = Some(40);
+
+        let clip = steps_to_midi(&rack, pattern, channel).expect("clip");
+        let starts: Vec<u32> = clip.notes.iter().map(|note| note.start_ticks).collect();
+        assert_eq!(starts, vec![0, 480, 520, 560]);
+        let velocities: Vec<u8> = clip.notes.iter().map(|note| note.velocity).collect();
+        assert_eq!(velocities, vec![100, 100, 70, 40]);
+        assert!(clip.notes[1..].iter().all(|note| note.length_ticks == 40));
     }
-    Some(clip)
 }
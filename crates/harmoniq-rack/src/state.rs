@@ -81,6 +81,10 @@ pub struct Step {
     pub velocity: u8,
     pub pan: i8,
     pub shift_ticks: i16,
+    /// Number of retriggers inside the step.
+    pub ratchet: u8,
+    /// Velocity reached by the last retrigger; `None` keeps them level.
+    pub ratchet_velocity: Option<u8>,
 }
 
 impl Default for Step {
@@ -90,6 +94,8 @@ impl Default for Step {
             velocity: 100,
             pan: 0,
             shift_ticks: 0,
+            ratchet: 1,
+            ratchet_velocity: None,
         }
     }
 }
@@ -475,6 +475,14 @@ fn step_grid(ui: &mut egui::Ui, pat: PatternId, ch: &mut Channel) {
                 {
                     st.shift_ticks = shift as i16;
                 }
+                ui.add(egui::Slider::new(&mut st.ratchet, 1..=8).text("Ratchet"));
+                let mut ramp = st.ratchet_velocity.is_some();
+                if ui.checkbox(&mut ramp, "Ramp velocity").changed() {
+                    st.ratchet_velocity = ramp.then_some(st.velocity);
+                }
+                if let Some(target) = st.ratchet_velocity.as_mut() {
+                    ui.add(egui::Slider::new(target, 1..=127).text("Ramp to"));
+                }
             });
         }
     });
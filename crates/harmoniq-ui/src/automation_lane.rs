@@ -0,0 +1,453 @@
+use std::collections::BTreeSet;
+
+use egui::{self, pos2, vec2, Pos2, Rect, Response, Sense, Stroke};
+use harmoniq_engine::automation::{AutomationCurve, CurvePoint, CurveShape};
+
+use crate::theme::HarmoniqPalette;
+
+/// Distance in points within which the pointer grabs a curve point.
+pub const POINT_HIT_RADIUS: f32 = 6.0;
+
+/// Zoom, scroll and value range of an automation lane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LaneView {
+    /// Sample at the left edge of the lane.
+    pub scroll: u64,
+    pub samples_per_pixel: f64,
+    pub min_value: f32,
+    pub max_value: f32,
+    /// Grid spacing in samples that new and moved points snap to.
+    pub snap: Option<u64>,
+}
+
+impl LaneView {
+    pub fn new(samples_per_pixel: f64, min_value: f32, max_value: f32) -> Self {
+        Self {
+            scroll: 0,
+            samples_per_pixel: samples_per_pixel.max(f64::EPSILON),
+            min_value,
+            max_value,
+            snap: None,
+        }
+    }
+
+    pub fn with_scroll(mut self, scroll: u64) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    pub fn with_snap(mut self, snap: Option<u64>) -> Self {
+        self.snap = snap.filter(|grid| *grid > 0);
+        self
+    }
+
+    pub fn sample_to_x(&self, rect: Rect, sample: u64) -> f32 {
+        let offset = sample as f64 - self.scroll as f64;
+        rect.left() + (offset / self.samples_per_pixel) as f32
+    }
+
+    pub fn x_to_sample(&self, rect: Rect, x: f32) -> u64 {
+        let sample = self.scroll as f64 + (x - rect.left()) as f64 * self.samples_per_pixel;
+        sample.max(0.0).round() as u64
+    }
+
+    pub fn value_to_y(&self, rect: Rect, value: f32) -> f32 {
+        let span = (self.max_value - self.min_value).abs().max(f32::EPSILON);
+        let normalized = ((value - self.min_value) / span).clamp(0.0, 1.0);
+        rect.bottom() - normalized * rect.height()
+    }
+
+    pub fn y_to_value(&self, rect: Rect, y: f32) -> f32 {
+        let normalized = ((rect.bottom() - y) / rect.height().max(1.0)).clamp(0.0, 1.0);
+        self.min_value + normalized * (self.max_value - self.min_value)
+    }
+
+    pub fn snap_sample(&self, sample: u64) -> u64 {
+        match self.snap {
+            Some(grid) => (sample + grid / 2) / grid * grid,
+            None => sample,
+        }
+    }
+
+    pub fn point_position(&self, rect: Rect, point: &CurvePoint) -> Pos2 {
+        pos2(
+            self.sample_to_x(rect, point.sample),
+            self.value_to_y(rect, point.value),
+        )
+    }
+
+    /// Index of the point nearest to `pos`, if one lies within
+    /// [`POINT_HIT_RADIUS`].
+    pub fn hit_test(&self, rect: Rect, curve: &AutomationCurve, pos: Pos2) -> Option<usize> {
+        curve
+            .points()
+            .iter()
+            .enumerate()
+            .map(|(index, point)| (index, self.point_position(rect, point).distance(pos)))
+            .filter(|(_, distance)| *distance <= POINT_HIT_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    /// Sample and value of a point placed at `pos`, snapped to the grid.
+    pub fn point_at(&self, rect: Rect, pos: Pos2) -> (u64, f32) {
+        (
+            self.snap_sample(self.x_to_sample(rect, pos.x)),
+            self.y_to_value(rect, pos.y),
+        )
+    }
+
+    /// Index of the point that starts the segment under `x`.
+    pub fn segment_at(&self, rect: Rect, curve: &AutomationCurve, x: f32) -> Option<usize> {
+        let sample = self.x_to_sample(rect, x);
+        let index = curve
+            .points()
+            .partition_point(|point| point.sample <= sample);
+        index.checked_sub(1)
+    }
+}
+
+/// Change made to the curve by the editor. Points are identified by sample
+/// position, which stays stable while indices shift.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AutomationEdit {
+    Added {
+        sample: u64,
+        value: f32,
+        shape: CurveShape,
+    },
+    Moved {
+        from: u64,
+        sample: u64,
+        value: f32,
+    },
+    Removed {
+        samples: Vec<u64>,
+    },
+    ShapeChanged {
+        sample: u64,
+        shape: CurveShape,
+    },
+}
+
+/// Interaction state kept between frames.
+#[derive(Debug, Clone, Default)]
+pub struct AutomationLaneState {
+    selected: BTreeSet<u64>,
+    dragging: Option<u64>,
+    marquee: Option<Pos2>,
+}
+
+impl AutomationLaneState {
+    pub fn selected(&self) -> impl Iterator<Item = u64> + '_ {
+        self.selected.iter().copied()
+    }
+
+    pub fn is_selected(&self, sample: u64) -> bool {
+        self.selected.contains(&sample)
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selected.clear();
+    }
+
+    /// Selects every point inside `area`, extending the selection when
+    /// `additive` is set.
+    pub fn select_in(
+        &mut self,
+        view: &LaneView,
+        rect: Rect,
+        curve: &AutomationCurve,
+        area: Rect,
+        additive: bool,
+    ) {
+        if !additive {
+            self.selected.clear();
+        }
+        for point in curve.points() {
+            if area.contains(view.point_position(rect, point)) {
+                self.selected.insert(point.sample);
+            }
+        }
+    }
+}
+
+pub struct AutomationLaneResult {
+    pub response: Response,
+    pub edits: Vec<AutomationEdit>,
+}
+
+/// Point editor bound to an [`AutomationCurve`].
+///
+/// Double-click adds a point, dragging a point moves it, dragging empty space
+/// draws a selection box and Delete removes the selection. Right-clicking a
+/// segment picks its curve shape.
+pub struct AutomationLaneEditor<'a> {
+    curve: &'a mut AutomationCurve,
+    state: &'a mut AutomationLaneState,
+    view: LaneView,
+    palette: &'a HarmoniqPalette,
+    height: f32,
+    default_shape: CurveShape,
+}
+
+impl<'a> AutomationLaneEditor<'a> {
+    pub fn new(
+        curve: &'a mut AutomationCurve,
+        state: &'a mut AutomationLaneState,
+        view: LaneView,
+        palette: &'a HarmoniqPalette,
+    ) -> Self {
+        Self {
+            curve,
+            state,
+            view,
+            palette,
+            height: 72.0,
+            default_shape: CurveShape::Linear,
+        }
+    }
+
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = height.max(32.0);
+        self
+    }
+
+    pub fn with_default_shape(mut self, shape: CurveShape) -> Self {
+        self.default_shape = shape;
+        self
+    }
+
+    pub fn show(self, ui: &mut egui::Ui) -> AutomationLaneResult {
+        let (rect, mut response) = ui.allocate_exact_size(
+            vec2(ui.available_width(), self.height),
+            Sense::click_and_drag(),
+        );
+        let view = self.view;
+        let curve = self.curve;
+        let state = self.state;
+        let mut edits = Vec::new();
+        let shift = ui.input(|input| input.modifiers.shift);
+
+        if response.double_clicked() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                if view.hit_test(rect, curve, pos).is_none() {
+                    let (sample, value) = view.point_at(rect, pos);
+                    curve.add_point(CurvePoint::new(sample, value, self.default_shape));
+                    state.selected.clear();
+                    state.selected.insert(sample);
+                    edits.push(AutomationEdit::Added {
+                        sample,
+                        value,
+                        shape: self.default_shape,
+                    });
+                }
+            }
+        } else if response.clicked() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                match view.hit_test(rect, curve, pos) {
+                    Some(index) => {
+                        let sample = curve.points()[index].sample;
+                        if !shift {
+                            state.selected.clear();
+                        }
+                        if !state.selected.remove(&sample) {
+                            state.selected.insert(sample);
+                        }
+                    }
+                    None if !shift => state.selected.clear(),
+                    None => {}
+                }
+            }
+        }
+
+        if response.drag_started() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                match view.hit_test(rect, curve, pos) {
+                    Some(index) => {
+                        let sample = curve.points()[index].sample;
+                        if !state.selected.contains(&sample) {
+                            if !shift {
+                                state.selected.clear();
+                            }
+                            state.selected.insert(sample);
+                        }
+                        state.dragging = Some(sample);
+                    }
+                    None => state.marquee = Some(pos),
+                }
+            }
+        }
+
+        if response.dragged() {
+            if let (Some(from), Some(pos)) = (state.dragging, response.interact_pointer_pos()) {
+                let (sample, value) = view.point_at(rect, pos);
+                let occupied = sample != from && curve.index_of(sample).is_some();
+                if let Some(index) = curve.index_of(from).filter(|_| !occupied) {
+                    let moved = &curve.points()[index];
+                    if moved.sample != sample || moved.value != value {
+                        let shape = moved.shape;
+                        curve.remove_point(index);
+                        curve.add_point(CurvePoint::new(sample, value, shape));
+                        state.selected.remove(&from);
+                        state.selected.insert(sample);
+                        state.dragging = Some(sample);
+                        edits.push(AutomationEdit::Moved {
+                            from,
+                            sample,
+                            value,
+                        });
+                    }
+                }
+            }
+        }
+
+        if response.drag_stopped() {
+            if let (Some(start), Some(end)) = (state.marquee, response.interact_pointer_pos()) {
+                state.select_in(&view, rect, curve, Rect::from_two_pos(start, end), shift);
+            }
+            state.dragging = None;
+            state.marquee = None;
+        }
+
+        let delete = response.hovered()
+            && ui.input(|input| {
+                input.key_pressed(egui::Key::Delete) || input.key_pressed(egui::Key::Backspace)
+            });
+        if delete && !state.selected.is_empty() {
+            let samples: Vec<u64> = state.selected.iter().copied().collect();
+            for sample in samples.iter().rev() {
+                if let Some(index) = curve.index_of(*sample) {
+                    curve.remove_point(index);
+                }
+            }
+            state.selected.clear();
+            edits.push(AutomationEdit::Removed { samples });
+        }
+
+        let segment = response
+            .hover_pos()
+            .and_then(|pos| view.segment_at(rect, curve, pos.x));
+        response.clone().context_menu(|ui| {
+            let Some(index) = segment else {
+                ui.close_menu();
+                return;
+            };
+            for (label, shape) in [
+                ("Step", CurveShape::Step),
+                ("Linear", CurveShape::Linear),
+                ("Smooth", CurveShape::Bezier { tension: 0.5 }),
+            ] {
+                if ui.button(label).clicked() {
+                    if curve.set_shape(index, shape) {
+                        edits.push(AutomationEdit::ShapeChanged {
+                            sample: curve.points()[index].sample,
+                            shape,
+                        });
+                    }
+                    ui.close_menu();
+                }
+            }
+        });
+
+        if !edits.is_empty() {
+            response.mark_changed();
+        }
+
+        paint_lane(ui, rect, curve, state, &view, self.palette, &response);
+
+        AutomationLaneResult { response, edits }
+    }
+}
+
+fn paint_lane(
+    ui: &egui::Ui,
+    rect: Rect,
+    curve: &AutomationCurve,
+    state: &AutomationLaneState,
+    view: &LaneView,
+    palette: &HarmoniqPalette,
+    response: &Response,
+) {
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, palette.automation_lane_bg);
+
+    if let Some(grid) = view.snap {
+        let first = view.x_to_sample(rect, rect.left()) / grid * grid;
+        let step = grid as f64 / view.samples_per_pixel;
+        if step >= 4.0 {
+            let mut sample = first;
+            while view.sample_to_x(rect, sample) <= rect.right() {
+                let x = view.sample_to_x(rect, sample);
+                painter.line_segment(
+                    [pos2(x, rect.top()), pos2(x, rect.bottom())],
+                    Stroke::new(1.0, palette.timeline_grid_secondary),
+                );
+                sample += grid;
+            }
+        }
+    }
+
+    let line = Stroke::new(1.5, palette.accent);
+    let points = curve.points();
+    if let Some(first) = points.first() {
+        let start = view.point_position(rect, first);
+        painter.line_segment([pos2(rect.left(), start.y), start], line);
+    }
+    for pair in points.windows(2) {
+        let from = view.point_position(rect, &pair[0]);
+        let to = view.point_position(rect, &pair[1]);
+        match pair[0].shape {
+            CurveShape::Linear => painter.line_segment([from, to], line),
+            CurveShape::Step => {
+                let corner = pos2(to.x, from.y);
+                painter.line_segment([from, corner], line);
+                painter.line_segment([corner, to], line);
+            }
+            CurveShape::Bezier { .. } => {
+                const SEGMENTS: u64 = 16;
+                let span = pair[1].sample.saturating_sub(pair[0].sample);
+                let mut previous = from;
+                for step in 1..=SEGMENTS {
+                    let sample = pair[0].sample + span * step / SEGMENTS;
+                    let value = curve.value_at(sample).unwrap_or(pair[1].value);
+                    let next = pos2(view.sample_to_x(rect, sample), view.value_to_y(rect, value));
+                    painter.line_segment([previous, next], line);
+                    previous = next;
+                }
+            }
+        }
+    }
+    if let Some(last) = points.last() {
+        let end = view.point_position(rect, last);
+        painter.line_segment([end, pos2(rect.right(), end.y)], line);
+    }
+
+    for point in points {
+        let pos = view.point_position(rect, point);
+        if !rect.expand(POINT_HIT_RADIUS).contains(pos) {
+            continue;
+        }
+        let fill = if state.is_selected(point.sample) {
+            palette.accent_alt
+        } else {
+            palette.accent
+        };
+        painter.circle(
+            pos,
+            3.5,
+            fill,
+            Stroke::new(1.0, palette.automation_point_border),
+        );
+    }
+
+    if let (Some(start), Some(end)) = (state.marquee, response.interact_pointer_pos()) {
+        let area = Rect::from_two_pos(start, end);
+        painter.rect(
+            area,
+            0.0,
+            palette.accent_soft.gamma_multiply(0.3),
+            Stroke::new(1.0, palette.accent_soft),
+        );
+    }
+}
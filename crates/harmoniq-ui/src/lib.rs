@@ -1,3 +1,4 @@
+pub mod automation_lane;
 pub mod grand_piano_clap;
 pub mod overlay;
 pub mod parametric_eq;
@@ -5,6 +6,9 @@ pub mod theme;
 pub mod widget_framework;
 pub mod widgets;
 
+pub use automation_lane::{
+    AutomationEdit, AutomationLaneEditor, AutomationLaneResult, AutomationLaneState, LaneView,
+};
 pub use grand_piano_clap::{show_grand_piano_clap_ui, GrandPianoClapParams};
 pub use overlay::startup_banner;
 pub use parametric_eq::{
@@ -0,0 +1,74 @@
+use egui::{pos2, Rect};
+use harmoniq_engine::automation::{AutomationCurve, CurvePoint, CurveShape};
+use harmoniq_ui::{AutomationLaneState, LaneView};
+
+fn lane_rect() -> Rect {
+    Rect::from_min_max(pos2(100.0, 20.0), pos2(500.0, 120.0))
+}
+
+fn curve() -> AutomationCurve {
+    let mut curve = AutomationCurve::new();
+    curve.add_point(CurvePoint::new(1_000, 0.25, CurveShape::Linear));
+    curve.add_point(CurvePoint::new(3_000, 0.75, CurveShape::Step));
+    curve
+}
+
+#[test]
+fn hit_test_accounts_for_zoom_and_scroll() {
+    let rect = lane_rect();
+    let curve = curve();
+    // 10 samples per pixel, scrolled 500 samples in: sample 1000 sits 50 px
+    // from the left edge and 0.25 a quarter of the way up.
+    let view = LaneView::new(10.0, 0.0, 1.0).with_scroll(500);
+
+    assert_eq!(view.hit_test(rect, &curve, pos2(150.0, 95.0)), Some(0));
+    assert_eq!(view.hit_test(rect, &curve, pos2(354.0, 47.0)), Some(1));
+    assert_eq!(view.hit_test(rect, &curve, pos2(150.0, 80.0)), None);
+    assert_eq!(view.hit_test(rect, &curve, pos2(250.0, 70.0)), None);
+
+    let zoomed = LaneView::new(5.0, 0.0, 1.0).with_scroll(500);
+    assert_eq!(zoomed.hit_test(rect, &curve, pos2(150.0, 95.0)), None);
+    assert_eq!(zoomed.hit_test(rect, &curve, pos2(200.0, 95.0)), Some(0));
+}
+
+#[test]
+fn new_points_follow_view_and_snap_to_grid() {
+    let rect = lane_rect();
+    let view = LaneView::new(10.0, -1.0, 1.0).with_scroll(2_000);
+
+    let (sample, value) = view.point_at(rect, pos2(223.0, 70.0));
+    assert_eq!(sample, 3_230);
+    assert!(value.abs() < 1e-6);
+
+    let snapped = view.with_snap(Some(480));
+    let (sample, value) = snapped.point_at(rect, pos2(223.0, 20.0));
+    assert_eq!(sample, 3_360);
+    assert_eq!(value, 1.0);
+
+    let (sample, value) = snapped.point_at(rect, pos2(-200.0, 400.0));
+    assert_eq!(sample, 0);
+    assert_eq!(value, -1.0);
+}
+
+#[test]
+fn segments_and_box_selection_use_view_geometry() {
+    let rect = lane_rect();
+    let curve = curve();
+    let view = LaneView::new(10.0, 0.0, 1.0);
+
+    assert_eq!(view.segment_at(rect, &curve, 150.0), None);
+    assert_eq!(view.segment_at(rect, &curve, 250.0), Some(0));
+    assert_eq!(view.segment_at(rect, &curve, 450.0), Some(1));
+
+    let mut state = AutomationLaneState::default();
+    state.select_in(
+        &view,
+        rect,
+        &curve,
+        Rect::from_min_max(pos2(150.0, 20.0), pos2(250.0, 120.0)),
+        false,
+    );
+    assert_eq!(state.selected().collect::<Vec<_>>(), vec![1_000]);
+    state.select_in(&view, rect, &curve, rect, true);
+    assert_eq!(state.selected().collect::<Vec<_>>(), vec![1_000, 3_000]);
+}
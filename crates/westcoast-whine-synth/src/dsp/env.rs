@@ -5,6 +5,9 @@ enum EnvelopeState {
     Decay,
     Sustain,
     Release,
+    /// Forced linear fade to silence over a fixed time, ignoring the
+    /// release setting.
+    Steal,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -17,6 +20,7 @@ pub struct AdsrEnvelope {
     level: f32,
     state: EnvelopeState,
     release_start: f32,
+    steal_step: f32,
 }
 
 impl AdsrEnvelope {
@@ -30,6 +34,7 @@ impl AdsrEnvelope {
             level: 0.0,
             state: EnvelopeState::Idle,
             release_start: 0.0,
+            steal_step: 0.0,
         }
     }
 
@@ -60,6 +65,15 @@ impl AdsrEnvelope {
         }
     }
 
+    /// Fades the current level to zero over `seconds`, for a voice that is
+    /// about to be reused.
+    pub fn steal(&mut self, seconds: f32) {
+        if self.state != EnvelopeState::Idle {
+            self.steal_step = self.level / (seconds * self.sample_rate).max(1.0);
+            self.state = EnvelopeState::Steal;
+        }
+    }
+
     pub fn reset(&mut self) {
         self.state = EnvelopeState::Idle;
         self.level = 0.0;
@@ -114,6 +128,13 @@ impl AdsrEnvelope {
                     self.state = EnvelopeState::Idle;
                 }
             }
+            EnvelopeState::Steal => {
+                self.level -= self.steal_step;
+                if self.level <= 0.0 {
+                    self.level = 0.0;
+                    self.state = EnvelopeState::Idle;
+                }
+            }
         }
 
         self.level = self.level.clamp(0.0, 1.0);
@@ -26,6 +26,12 @@ impl Oscillator {
         self.phase = 0.0;
     }
 
+    /// Restarts the oscillator at `phase` radians.
+    #[inline]
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = wrap_phase(phase.rem_euclid(TAU));
+    }
+
     #[inline]
     pub fn advance_sine(&mut self, freq: f32, sample_rate: f32) -> f32 {
         let incr = (freq / sample_rate).clamp(0.0, 0.5) * TAU;
@@ -1,7 +1,16 @@
+use std::f32::consts::{FRAC_PI_4, SQRT_2, TAU};
+
 use super::env::AdsrEnvelope;
 use super::filter::LadderFilter;
 use super::osc::Oscillator;
 
+/// Largest unison stack a single voice renders.
+pub const MAX_UNISON: usize = 8;
+/// Fade applied to a voice that is stolen for a new note.
+pub const STEAL_RAMP_SECONDS: f32 = 0.003;
+/// Detune of the outermost unison oscillators at full spread.
+const UNISON_MAX_DETUNE_CENTS: f32 = 50.0;
+
 #[derive(Clone, Copy, Debug)]
 pub struct EnvelopeSettings {
     pub attack: f32,
@@ -20,12 +29,32 @@ pub struct VoiceParams {
     pub filter_env_amount: f32,
     pub velocity_to_cutoff: bool,
     pub filter_env_depth: f32,
+    /// Cutoff tracking of the note relative to middle C, where `1.0`
+    /// follows the note one-for-one in semitones.
+    pub filter_key_track: f32,
     pub lfo_value: f32,
     pub lfo_pitch_amount: f32,
     pub lfo_cutoff_amount: f32,
     pub lfo_amp_amount: f32,
     pub pitch_bend_semitones: f32,
     pub velocity_amp_scale: f32,
+    /// Oscillator pairs stacked per voice, `1..=MAX_UNISON`.
+    pub unison: usize,
+    /// Detune and stereo spread of the unison stack (0..1).
+    pub unison_spread: f32,
+}
+
+/// Note waiting for a stolen voice to fade out.
+#[derive(Clone, Copy, Debug)]
+struct PendingNote {
+    note: u8,
+    velocity: f32,
+    freq_hz: f32,
+    filter_cutoff: f32,
+    filter_resonance: f32,
+    glide_time: f32,
+    amp_env: EnvelopeSettings,
+    filter_env: EnvelopeSettings,
 }
 
 pub struct Voice {
@@ -37,13 +66,24 @@ pub struct Voice {
     target_freq: f32,
     glide_step: f32,
     glide_samples: f32,
-    sine: Oscillator,
-    saw: Oscillator,
+    sine: [Oscillator; MAX_UNISON],
+    saw: [Oscillator; MAX_UNISON],
     sub: Oscillator,
     amp_env: AdsrEnvelope,
     filter_env: AdsrEnvelope,
     filter: LadderFilter,
+    /// Filters the right channel when the unison stack is spread in stereo.
+    filter_right: LadderFilter,
+    /// Frequency ratio and left/right gain of each unison oscillator, for
+    /// the `(unison, spread)` in `unison_key`.
+    unison_ratio: [f32; MAX_UNISON],
+    unison_gain: [(f32, f32); MAX_UNISON],
+    unison_key: (usize, f32),
     released: bool,
+    expression_pitch: f32,
+    timbre: f32,
+    cutoff: f32,
+    pending: Option<PendingNote>,
 }
 
 impl Voice {
@@ -57,13 +97,21 @@ impl Voice {
             target_freq: 0.0,
             glide_step: 0.0,
             glide_samples: 0.0,
-            sine: Oscillator::new(),
-            saw: Oscillator::new(),
+            sine: [Oscillator::new(); MAX_UNISON],
+            saw: [Oscillator::new(); MAX_UNISON],
             sub: Oscillator::new(),
             amp_env: AdsrEnvelope::new(sample_rate),
             filter_env: AdsrEnvelope::new(sample_rate),
             filter: LadderFilter::new(sample_rate),
+            filter_right: LadderFilter::new(sample_rate),
+            unison_ratio: [1.0; MAX_UNISON],
+            unison_gain: [(1.0, 1.0); MAX_UNISON],
+            unison_key: (1, 0.0),
             released: false,
+            expression_pitch: 0.0,
+            timbre: 0.0,
+            cutoff: 0.0,
+            pending: None,
         }
     }
 
@@ -72,6 +120,7 @@ impl Voice {
         self.amp_env.set_sample_rate(self.sample_rate);
         self.filter_env.set_sample_rate(self.sample_rate);
         self.filter.set_sample_rate(self.sample_rate);
+        self.filter_right.set_sample_rate(self.sample_rate);
     }
 
     pub fn reset(&mut self) {
@@ -81,13 +130,38 @@ impl Voice {
         self.target_freq = 0.0;
         self.glide_step = 0.0;
         self.glide_samples = 0.0;
-        self.sine.reset();
-        self.saw.reset();
-        self.sub.reset();
+        self.reset_oscillators();
         self.amp_env.reset();
         self.filter_env.reset();
         self.filter.reset();
+        self.filter_right.reset();
         self.released = false;
+        self.expression_pitch = 0.0;
+        self.timbre = 0.0;
+        self.pending = None;
+    }
+
+    pub fn note(&self) -> u8 {
+        self.note
+    }
+
+    /// Per-note pitch offset in semitones, on top of the global bend.
+    pub fn set_expression_pitch(&mut self, semitones: f32) {
+        self.expression_pitch = semitones;
+    }
+
+    pub fn timbre(&self) -> f32 {
+        self.timbre
+    }
+
+    /// Filter cutoff in Hz after all modulation, as of the last sample.
+    pub fn cutoff(&self) -> f32 {
+        self.cutoff
+    }
+
+    /// Per-note timbre (0..1); opens the filter by up to four octaves.
+    pub fn set_timbre(&mut self, timbre: f32) {
+        self.timbre = timbre.clamp(0.0, 1.0);
     }
 
     pub fn apply_envelopes(&mut self, amp: EnvelopeSettings, filter: EnvelopeSettings) {
@@ -115,14 +189,16 @@ impl Voice {
         self.target_freq = freq_hz;
         self.glide_step = 0.0;
         self.glide_samples = 0.0;
-        self.sine.reset();
-        self.saw.reset();
-        self.sub.reset();
+        self.reset_oscillators();
         self.amp_env.trigger();
         self.filter_env.trigger();
         self.filter.set_params(filter_cutoff, filter_resonance);
+        self.filter_right
+            .set_params(filter_cutoff, filter_resonance);
         self.released = false;
         self.active = true;
+        self.expression_pitch = 0.0;
+        self.timbre = 0.0;
     }
 
     pub fn legato_note(
@@ -141,11 +217,63 @@ impl Voice {
         self.apply_envelopes(amp_env, filter_env);
         self.start_glide(freq_hz, glide_time);
         self.filter.set_params(filter_cutoff, filter_resonance);
+        self.filter_right
+            .set_params(filter_cutoff, filter_resonance);
         self.released = false;
         self.active = true;
     }
 
+    /// Reuses the voice for a new note. A sounding voice first fades out
+    /// over [`STEAL_RAMP_SECONDS`] and the new note starts once it is
+    /// silent, so the old note does not cut off with a click.
+    pub fn steal(
+        &mut self,
+        note: u8,
+        velocity: f32,
+        freq_hz: f32,
+        filter_cutoff: f32,
+        filter_resonance: f32,
+        glide_time: f32,
+        amp_env: EnvelopeSettings,
+        filter_env: EnvelopeSettings,
+    ) {
+        if !self.active || !self.amp_env.is_active() {
+            self.note_on(
+                note,
+                velocity,
+                freq_hz,
+                filter_cutoff,
+                filter_resonance,
+                glide_time,
+                amp_env,
+                filter_env,
+            );
+            return;
+        }
+        self.amp_env.steal(STEAL_RAMP_SECONDS);
+        self.released = true;
+        self.pending = Some(PendingNote {
+            note,
+            velocity,
+            freq_hz,
+            filter_cutoff,
+            filter_resonance,
+            glide_time,
+            amp_env,
+            filter_env,
+        });
+    }
+
+    /// Whether the voice is fading out before starting a queued note.
+    pub fn is_stealing(&self) -> bool {
+        self.pending.is_some()
+    }
+
     pub fn note_off(&mut self) {
+        // The queued note ended before it started; let the fade finish.
+        if self.pending.take().is_some() {
+            return;
+        }
         if self.active {
             self.amp_env.release();
             self.filter_env.release();
@@ -157,6 +285,40 @@ impl Voice {
         !self.amp_env.is_active() && self.released
     }
 
+    /// Restarts the oscillators. The first unison pair starts at phase zero
+    /// and the others at staggered phases, so a stack does not begin with
+    /// every oscillator in phase.
+    fn reset_oscillators(&mut self) {
+        for (index, (sine, saw)) in self.sine.iter_mut().zip(&mut self.saw).enumerate() {
+            let phase = (index as f32 * 0.618_034).fract() * TAU;
+            sine.set_phase(phase);
+            saw.set_phase(phase);
+        }
+        self.sub.reset();
+    }
+
+    /// Recomputes the detune bank when the unison count or spread changes.
+    /// Oscillators sit evenly across `-spread..=spread`, detuned by up to
+    /// `UNISON_MAX_DETUNE_CENTS` and panned with a constant-power law.
+    fn update_unison(&mut self, unison: usize, spread: f32) {
+        if self.unison_key == (unison, spread) {
+            return;
+        }
+        self.unison_key = (unison, spread);
+        let norm = (unison as f32).sqrt().recip();
+        let bank = self.unison_ratio.iter_mut().zip(&mut self.unison_gain);
+        for (index, (ratio, gain)) in bank.take(unison).enumerate() {
+            let position = if unison > 1 {
+                (2.0 * index as f32 / (unison - 1) as f32 - 1.0) * spread
+            } else {
+                0.0
+            };
+            *ratio = (2.0f32).powf(position * UNISON_MAX_DETUNE_CENTS / 1200.0);
+            let angle = (position + 1.0) * FRAC_PI_4;
+            *gain = (angle.cos() * SQRT_2 * norm, angle.sin() * SQRT_2 * norm);
+        }
+    }
+
     fn start_glide(&mut self, target_freq: f32, glide_time: f32) {
         self.target_freq = target_freq;
         let time = glide_time.max(0.0);
@@ -183,25 +345,46 @@ impl Voice {
         }
     }
 
-    pub fn render(&mut self, params: &VoiceParams) -> f32 {
+    /// Renders one stereo sample. With a unison of one both channels carry
+    /// the same mono signal.
+    pub fn render(&mut self, params: &VoiceParams) -> (f32, f32) {
         if !self.active {
-            return 0.0;
+            return (0.0, 0.0);
         }
 
         self.update_frequency();
 
-        let pitch_bend_ratio = (2.0f32).powf(params.pitch_bend_semitones / 12.0);
+        let bend = params.pitch_bend_semitones + self.expression_pitch;
+        let pitch_bend_ratio = (2.0f32).powf(bend / 12.0);
         let base_freq = (self.current_freq * pitch_bend_ratio).clamp(0.0, self.sample_rate * 0.45);
         let lfo_pitch = params.lfo_value * params.lfo_pitch_amount;
         let pitch_mod_ratio = (2.0f32).powf(lfo_pitch / 12.0);
         let freq = (base_freq * pitch_mod_ratio).clamp(20.0, self.sample_rate * 0.45);
         let detune_ratio = (2.0f32).powf(params.detune_cents / 1200.0);
-        let sine = self.sine.advance_sine(freq, self.sample_rate);
-        let saw = self.saw.advance_saw(freq * detune_ratio, self.sample_rate);
+        let unison = params.unison.clamp(1, MAX_UNISON);
+        let (mut left, mut right) = if unison == 1 {
+            let sine = self.sine[0].advance_sine(freq, self.sample_rate);
+            let saw = self.saw[0].advance_saw(freq * detune_ratio, self.sample_rate);
+            let sample = sine * (1.0 - params.blend) + saw * params.blend;
+            (sample, sample)
+        } else {
+            self.update_unison(unison, params.unison_spread.clamp(0.0, 1.0));
+            let (mut left, mut right) = (0.0, 0.0);
+            let bank = self.unison_ratio.iter().zip(&self.unison_gain);
+            let oscillators = self.sine.iter_mut().zip(&mut self.saw);
+            for ((ratio, (gain_l, gain_r)), (sine, saw)) in bank.zip(oscillators).take(unison) {
+                let freq = freq * ratio;
+                let sine = sine.advance_sine(freq, self.sample_rate);
+                let saw = saw.advance_saw(freq * detune_ratio, self.sample_rate);
+                let sample = sine * (1.0 - params.blend) + saw * params.blend;
+                left += sample * gain_l;
+                right += sample * gain_r;
+            }
+            (left, right)
+        };
         let sub = self.sub.advance_sine(freq * 0.5, self.sample_rate);
-
-        let mut sample = sine * (1.0 - params.blend) + saw * params.blend;
-        sample += sub * params.sub_level;
+        left += sub * params.sub_level;
+        right += sub * params.sub_level;
 
         let filter_env = self.filter_env.next_sample() * params.filter_env_depth;
         let mut cutoff = params.filter_cutoff * (1.0 + params.filter_env_amount * filter_env * 3.0);
@@ -209,18 +392,46 @@ impl Voice {
             cutoff *= 0.5 + self.velocity * 0.75;
         }
         cutoff *= 1.0 + params.lfo_cutoff_amount * params.lfo_value;
+        cutoff *= (2.0f32).powf(self.timbre * 4.0);
+        let key_offset = (self.note as f32 - 60.0) * params.filter_key_track;
+        cutoff *= (2.0f32).powf(key_offset / 12.0);
         cutoff = cutoff.clamp(20.0, 20_000.0);
+        self.cutoff = cutoff;
         self.filter
             .set_params(cutoff.min(self.sample_rate * 0.45), params.filter_resonance);
 
-        let signal = self.filter.process(sample);
+        let signal_l = self.filter.process(left);
+        let signal_r = if unison == 1 {
+            // Keep the right filter in step so spreading mid-note is seamless.
+            self.filter_right = self.filter;
+            signal_l
+        } else {
+            self.filter_right
+                .set_params(cutoff.min(self.sample_rate * 0.45), params.filter_resonance);
+            self.filter_right.process(right)
+        };
 
         let amp_env = self.amp_env.next_sample();
         let amp_mod = 1.0 + params.lfo_amp_amount * params.lfo_value;
         let mut gain = amp_env * self.velocity * params.velocity_amp_scale * amp_mod;
         gain = gain.clamp(0.0, 1.5);
-        let output = signal * gain;
+        let output = (signal_l * gain, signal_r * gain);
 
+        if !self.amp_env.is_active() {
+            if let Some(pending) = self.pending.take() {
+                self.note_on(
+                    pending.note,
+                    pending.velocity,
+                    pending.freq_hz,
+                    pending.filter_cutoff,
+                    pending.filter_resonance,
+                    pending.glide_time,
+                    pending.amp_env,
+                    pending.filter_env,
+                );
+                return output;
+            }
+        }
         if self.finished() {
             self.reset();
         }
@@ -1,4 +1,5 @@
 use egui_plot::{Line, Plot, PlotPoints};
+use harmoniq_engine::expression::NoteController;
 use nih_plug::prelude::*;
 use nih_plug::prelude::{formatters, AtomicF32};
 use nih_plug::util;
@@ -10,7 +11,7 @@ use nih_plug_egui::{
 };
 use std::fmt::{Display, Formatter};
 use std::num::NonZeroU32;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 mod dsp;
@@ -18,16 +19,28 @@ mod dsp;
 use dsp::fx_chorus::StereoChorus;
 use dsp::fx_reverb::PlateReverb;
 use dsp::osc::{Lfo, LfoWaveform};
-use dsp::voice::{EnvelopeSettings, Voice, VoiceParams};
+use dsp::voice::{EnvelopeSettings, Voice, VoiceParams, MAX_UNISON};
 
 const MAX_VOICES: usize = 8;
 const PITCH_BEND_RANGE: f32 = 12.0;
+/// Per-note bend range on MPE member channels, per the MPE default.
+const MPE_PITCH_BEND_RANGE: f32 = 48.0;
+const MIDI_CHANNELS: usize = 16;
 const OSCILLOSCOPE_SAMPLES: usize = 256;
+/// History kept behind the displayed window so the trigger has a few
+/// periods of a low note to search.
+const OSCILLOSCOPE_HISTORY: usize = OSCILLOSCOPE_SAMPLES * 8;
 
 pub struct WestCoastWhineSynth {
     params: Arc<WestCoastParams>,
     voices: Vec<Voice>,
     voice_notes: [Option<u8>; MAX_VOICES],
+    /// MIDI channel each voice's note arrived on.
+    voice_channels: [u8; MAX_VOICES],
+    /// Latest pitch bend (semitones) and pressure (0..1) per MIDI channel,
+    /// applied to voices on MPE member channels.
+    channel_bend: [f32; MIDI_CHANNELS],
+    channel_pressure: [f32; MIDI_CHANNELS],
     voice_age: [u64; MAX_VOICES],
     note_counter: u64,
     sample_rate: f32,
@@ -99,9 +112,21 @@ impl NoteStack {
     }
 }
 
+/// Ring buffer feeding the editor's scope. The audio thread pushes samples
+/// and the editor copies out a window, aligned to a rising crossing of the
+/// trigger level when triggering is on.
 struct OscilloscopeState {
     samples: Vec<AtomicF32>,
     write_index: AtomicUsize,
+    frozen: AtomicBool,
+    triggered: AtomicBool,
+    trigger_level: AtomicF32,
+    /// Minimum spacing between successive triggers, so noise around the
+    /// level cannot make the window hop between nearby crossings.
+    holdoff_ms: AtomicF32,
+    sample_rate: AtomicF32,
+    /// Absolute position of the last trigger, or `usize::MAX` for none.
+    last_trigger: AtomicUsize,
 }
 
 impl OscilloscopeState {
@@ -113,19 +138,95 @@ impl OscilloscopeState {
         Self {
             samples,
             write_index: AtomicUsize::new(0),
+            frozen: AtomicBool::new(false),
+            triggered: AtomicBool::new(true),
+            trigger_level: AtomicF32::new(0.0),
+            holdoff_ms: AtomicF32::new(10.0),
+            sample_rate: AtomicF32::new(48_000.0),
+            last_trigger: AtomicUsize::new(usize::MAX),
         }
     }
 
     fn push(&self, sample: f32) {
+        if self.frozen.load(Ordering::Relaxed) {
+            return;
+        }
         let idx = self.write_index.fetch_add(1, Ordering::Relaxed) % self.samples.len();
         self.samples[idx].store(sample, Ordering::Relaxed);
     }
 
-    fn snapshot(&self) -> Vec<f32> {
-        self.samples
-            .iter()
-            .map(|sample| sample.load(Ordering::Relaxed))
-            .collect()
+    fn set_sample_rate(&self, sample_rate: f32) {
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+
+    fn set_frozen(&self, frozen: bool) {
+        self.frozen.store(frozen, Ordering::Relaxed);
+    }
+
+    fn set_trigger(&self, enabled: bool, level: f32, holdoff_ms: f32) {
+        self.triggered.store(enabled, Ordering::Relaxed);
+        self.trigger_level.store(level, Ordering::Relaxed);
+        self.holdoff_ms
+            .store(holdoff_ms.max(0.0), Ordering::Relaxed);
+    }
+
+    fn sample_at(&self, position: usize) -> f32 {
+        self.samples[position % self.samples.len()].load(Ordering::Relaxed)
+    }
+
+    /// Copies the most recent window into `window` and returns the absolute
+    /// position it starts at when a trigger was found. Without a crossing,
+    /// or with triggering off, the newest samples are shown free-running.
+    fn snapshot(&self, window: &mut [f32]) -> Option<usize> {
+        let written = self.write_index.load(Ordering::Relaxed);
+        let len = window.len().min(self.samples.len());
+        let latest = written.saturating_sub(len);
+        let start = if self.triggered.load(Ordering::Relaxed) {
+            self.find_trigger(written, latest, len)
+        } else {
+            None
+        };
+        for (offset, slot) in window.iter_mut().enumerate() {
+            *slot = if offset < len {
+                self.sample_at(start.unwrap_or(latest) + offset)
+            } else {
+                0.0
+            };
+        }
+        start
+    }
+
+    /// Scans back from the newest window start for a rising crossing,
+    /// skipping crossings within the hold-off of the previous trigger.
+    fn find_trigger(&self, written: usize, latest: usize, len: usize) -> Option<usize> {
+        // Leave a full window of margin so the audio thread cannot overwrite
+        // the oldest samples while they are being read.
+        let oldest = (written + len + 1)
+            .saturating_sub(self.samples.len())
+            .max(1);
+        if latest < oldest {
+            return None;
+        }
+        let level = self.trigger_level.load(Ordering::Relaxed);
+        let holdoff = (self.holdoff_ms.load(Ordering::Relaxed)
+            * 0.001
+            * self.sample_rate.load(Ordering::Relaxed)) as usize;
+        let last = self.last_trigger.load(Ordering::Relaxed);
+        let mut next = self.sample_at(latest);
+        for position in (oldest..=latest).rev() {
+            let previous = self.sample_at(position - 1);
+            let rising = previous < level && next >= level;
+            next = previous;
+            if !rising {
+                continue;
+            }
+            let held = last != usize::MAX && position > last && position - last < holdoff;
+            if !held {
+                self.last_trigger.store(position, Ordering::Relaxed);
+                return Some(position);
+            }
+        }
+        None
     }
 }
 
@@ -145,6 +246,23 @@ impl Default for LfoShape {
     }
 }
 
+/// What MPE channel pressure modulates on its voice.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum PressureTarget {
+    #[id = "amp"]
+    #[name = "Amp"]
+    Amp,
+    #[id = "cutoff"]
+    #[name = "Cutoff"]
+    Cutoff,
+}
+
+impl Default for PressureTarget {
+    fn default() -> Self {
+        PressureTarget::Amp
+    }
+}
+
 impl LfoShape {
     fn to_waveform(self) -> LfoWaveform {
         match self {
@@ -197,6 +315,10 @@ pub struct OscParams {
     pub detune_cents: FloatParam,
     #[id = "glide_time"]
     pub glide_time: FloatParam,
+    #[id = "unison"]
+    pub unison: IntParam,
+    #[id = "unison_spread"]
+    pub unison_spread: FloatParam,
 }
 
 #[derive(Params)]
@@ -211,6 +333,8 @@ pub struct FilterParams {
     pub env_depth: FloatParam,
     #[id = "vel_cutoff"]
     pub velocity_to_cutoff: BoolParam,
+    #[id = "key_track"]
+    pub key_track: FloatParam,
 }
 
 #[derive(Params)]
@@ -257,6 +381,11 @@ pub struct ModParams {
     pub cutoff_amount: FloatParam,
     #[id = "lfo_amp"]
     pub amp_amount: FloatParam,
+    /// Treats MIDI channels 2-16 as per-note MPE member channels.
+    #[id = "mpe"]
+    pub mpe: BoolParam,
+    #[id = "mpe_pressure"]
+    pub pressure_target: EnumParam<PressureTarget>,
 }
 
 #[derive(Params)]
@@ -314,6 +443,21 @@ impl Default for WestCoastParams {
                     },
                 )
                 .with_unit(" s"),
+                unison: IntParam::new(
+                    "Unison",
+                    1,
+                    IntRange::Linear {
+                        min: 1,
+                        max: MAX_UNISON as i32,
+                    },
+                ),
+                unison_spread: FloatParam::new(
+                    "Unison Spread",
+                    0.3,
+                    FloatRange::Linear { min: 0.0, max: 1.0 },
+                )
+                .with_smoother(SmoothingStyle::Linear(10.0))
+                .with_value_to_string(formatters::v2s_f32_percentage(0)),
             },
             filter: FilterParams {
                 cutoff: FloatParam::new(
@@ -343,6 +487,13 @@ impl Default for WestCoastParams {
                     FloatRange::Linear { min: 0.0, max: 1.0 },
                 ),
                 velocity_to_cutoff: BoolParam::new("Velocity -> Cutoff", true),
+                key_track: FloatParam::new(
+                    "Key Track",
+                    0.0,
+                    FloatRange::Linear { min: 0.0, max: 1.0 },
+                )
+                .with_smoother(SmoothingStyle::Linear(10.0))
+                .with_value_to_string(formatters::v2s_f32_percentage(0)),
             },
             envelopes: EnvelopeParams {
                 amp: AmpEnvelopeParams {
@@ -440,6 +591,8 @@ impl Default for WestCoastParams {
                     0.0,
                     FloatRange::Linear { min: 0.0, max: 1.0 },
                 ),
+                mpe: BoolParam::new("MPE", false),
+                pressure_target: EnumParam::new("Pressure Target", PressureTarget::Amp),
             },
             fx: FxParams {
                 chorus_rate: FloatParam::new(
@@ -501,6 +654,9 @@ impl Default for WestCoastWhineSynth {
             params,
             voices,
             voice_notes: [None; MAX_VOICES],
+            voice_channels: [0; MAX_VOICES],
+            channel_bend: [0.0; MIDI_CHANNELS],
+            channel_pressure: [0.0; MIDI_CHANNELS],
             voice_age: [0; MAX_VOICES],
             note_counter: 0,
             sample_rate,
@@ -509,7 +665,7 @@ impl Default for WestCoastWhineSynth {
             reverb: PlateReverb::new(),
             lfo: Lfo::new(),
             note_stack: NoteStack::default(),
-            oscilloscope: Arc::new(OscilloscopeState::new(OSCILLOSCOPE_SAMPLES)),
+            oscilloscope: Arc::new(OscilloscopeState::new(OSCILLOSCOPE_HISTORY)),
         }
     }
 }
@@ -533,7 +689,7 @@ impl WestCoastWhineSynth {
         }
     }
 
-    fn handle_note_on(&mut self, note: u8, velocity: f32, voices_allowed: usize) {
+    fn handle_note_on(&mut self, channel: u8, note: u8, velocity: f32, voices_allowed: usize) {
         let freq = Self::note_to_hz(note);
         let cutoff = self.params.filter.cutoff.value();
         let resonance = self.params.filter.resonance.value();
@@ -554,6 +710,7 @@ impl WestCoastWhineSynth {
                 );
             }
             self.voice_notes[0] = Some(note);
+            self.voice_channels[0] = channel;
             self.voice_age[0] = self.note_counter;
             self.note_counter = self.note_counter.wrapping_add(1);
             return;
@@ -579,15 +736,25 @@ impl WestCoastWhineSynth {
             oldest_index
         });
 
-        self.voices[voice_index].note_on(
-            note, velocity, freq, cutoff, resonance, glide, amp_env, filter_env,
-        );
+        let voice = &mut self.voices[voice_index];
+        if target_voice.is_some() {
+            voice.note_on(
+                note, velocity, freq, cutoff, resonance, glide, amp_env, filter_env,
+            );
+        } else {
+            voice.steal(
+                note, velocity, freq, cutoff, resonance, glide, amp_env, filter_env,
+            );
+        }
         self.voice_notes[voice_index] = Some(note);
+        self.voice_channels[voice_index] = channel;
         self.voice_age[voice_index] = self.note_counter;
         self.note_counter = self.note_counter.wrapping_add(1);
     }
 
-    fn handle_note_off(&mut self, note: u8, voices_allowed: usize) {
+    /// In MPE mode every member channel carries its own notes, so only the
+    /// voice playing `note` on `channel` is released.
+    fn handle_note_off(&mut self, channel: u8, note: u8, voices_allowed: usize, mpe: bool) {
         if voices_allowed <= 1 {
             if let Some(result) = self.note_stack.remove(note) {
                 if result.was_top {
@@ -619,13 +786,73 @@ impl WestCoastWhineSynth {
         }
 
         for idx in 0..voices_allowed.min(self.voices.len()) {
-            if self.voice_notes[idx] == Some(note) {
+            if self.voice_notes[idx] == Some(note) && (!mpe || self.voice_channels[idx] == channel)
+            {
                 self.voices[idx].note_off();
                 self.voice_notes[idx] = None;
             }
         }
     }
 
+    /// Maps pitch (semitones) and timbre (brightness, mapped to the filter
+    /// cutoff) onto the voices playing `note`; other controllers are ignored.
+    fn handle_note_expression(&mut self, note: u8, controller: NoteController, value: f32) {
+        for (voice, voice_note) in self.voices.iter_mut().zip(self.voice_notes.iter()) {
+            if *voice_note != Some(note) {
+                continue;
+            }
+            match controller {
+                NoteController::Pitch => voice.set_expression_pitch(value),
+                NoteController::Timbre => voice.set_timbre(value),
+                _ => {}
+            }
+        }
+    }
+
+    /// Global bend on the master channel, or with MPE off on any channel;
+    /// per-note bend on MPE member channels.
+    fn handle_pitch_bend(&mut self, channel: u8, value: f32, mpe: bool) {
+        if mpe && channel != 0 {
+            if let Some(bend) = self.channel_bend.get_mut(channel as usize) {
+                *bend = (value - 0.5) * 2.0 * MPE_PITCH_BEND_RANGE;
+            }
+        } else {
+            self.pitch_bend = (value - 0.5) * 2.0 * PITCH_BEND_RANGE;
+        }
+    }
+
+    /// Channel pressure only has a voice to act on in MPE mode.
+    fn handle_channel_pressure(&mut self, channel: u8, pressure: f32, mpe: bool) {
+        if mpe && channel != 0 {
+            if let Some(slot) = self.channel_pressure.get_mut(channel as usize) {
+                *slot = pressure.clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    /// Applies the MPE bend and pressure of the voice's channel on top of
+    /// the shared parameters. Pressure boosts the voice by up to 6 dB or
+    /// opens its filter by up to two octaves.
+    fn mpe_voice_params(
+        &self,
+        voice: usize,
+        shared: &VoiceParams,
+        target: PressureTarget,
+    ) -> VoiceParams {
+        let channel = self.voice_channels[voice] as usize;
+        if channel == 0 || channel >= MIDI_CHANNELS {
+            return *shared;
+        }
+        let pressure = self.channel_pressure[channel];
+        let mut params = *shared;
+        params.pitch_bend_semitones += self.channel_bend[channel];
+        match target {
+            PressureTarget::Amp => params.velocity_amp_scale *= 1.0 + pressure,
+            PressureTarget::Cutoff => params.filter_cutoff *= (2.0f32).powf(pressure * 2.0),
+        }
+        params
+    }
+
     fn render_block(
         &mut self,
         buffer: &mut Buffer,
@@ -645,15 +872,20 @@ impl WestCoastWhineSynth {
         }
 
         let lfo_waveform = self.params.modulation.waveform.value().to_waveform();
+        let mpe = self.params.modulation.mpe.value();
+        let pressure_target = self.params.modulation.pressure_target.value();
 
         for sample_idx in start..end {
             let blend = self.params.oscillators.blend.smoothed.next();
             let sub_level = self.params.oscillators.sub_level.smoothed.next();
             let detune = self.params.oscillators.detune_cents.smoothed.next();
+            let unison = self.params.oscillators.unison.value() as usize;
+            let unison_spread = self.params.oscillators.unison_spread.smoothed.next();
             let filter_cutoff = self.params.filter.cutoff.smoothed.next();
             let resonance = self.params.filter.resonance.smoothed.next();
             let filter_env_amount = self.params.filter.env_amount.smoothed.next();
             let filter_env_depth = self.params.filter.env_depth.smoothed.next();
+            let filter_key_track = self.params.filter.key_track.smoothed.next();
             let lfo_rate = self.params.modulation.rate.smoothed.next();
             let lfo_pitch_amount = self.params.modulation.pitch_amount.smoothed.next();
             let lfo_cutoff_amount = self.params.modulation.cutoff_amount.smoothed.next();
@@ -676,21 +908,30 @@ impl WestCoastWhineSynth {
                 filter_env_amount,
                 velocity_to_cutoff,
                 filter_env_depth,
+                filter_key_track,
                 lfo_value,
                 lfo_pitch_amount,
                 lfo_cutoff_amount,
                 lfo_amp_amount,
                 pitch_bend_semitones: self.pitch_bend,
                 velocity_amp_scale: 1.0,
+                unison,
+                unison_spread,
             };
 
-            let mut sample = 0.0f32;
+            let (mut voice_left, mut voice_right) = (0.0f32, 0.0f32);
             for idx in 0..voices_allowed {
-                sample += self.voices[idx].render(&voice_params);
+                let (left, right) = if mpe {
+                    let params = self.mpe_voice_params(idx, &voice_params, pressure_target);
+                    self.voices[idx].render(&params)
+                } else {
+                    self.voices[idx].render(&voice_params)
+                };
+                voice_left += left;
+                voice_right += right;
             }
 
-            sample = sample.tanh();
-            let (mut left, mut right) = (sample, sample);
+            let (mut left, mut right) = (voice_left.tanh(), voice_right.tanh());
             let (c_left, c_right) =
                 self.chorus
                     .process(left, right, chorus_rate, chorus_depth, chorus_mix);
@@ -745,6 +986,7 @@ impl Plugin for WestCoastWhineSynth {
         context: &mut impl InitContext<Self>,
     ) -> bool {
         self.sample_rate = buffer_config.sample_rate;
+        self.oscilloscope.set_sample_rate(self.sample_rate);
         for voice in &mut self.voices {
             voice.set_sample_rate(self.sample_rate);
         }
@@ -761,6 +1003,8 @@ impl Plugin for WestCoastWhineSynth {
         self.voice_notes = [None; MAX_VOICES];
         self.note_stack.clear();
         self.pitch_bend = 0.0;
+        self.channel_bend = [0.0; MIDI_CHANNELS];
+        self.channel_pressure = [0.0; MIDI_CHANNELS];
     }
 
     fn process(
@@ -787,16 +1031,37 @@ impl Plugin for WestCoastWhineSynth {
 
             while let Some(event) = next_event.take() {
                 match event {
-                    NoteEvent::NoteOn { note, velocity, .. } => {
-                        self.handle_note_on(note, velocity, voices_allowed);
+                    NoteEvent::NoteOn {
+                        channel,
+                        note,
+                        velocity,
+                        ..
+                    } => {
+                        self.handle_note_on(channel, note, velocity, voices_allowed);
+                    }
+                    NoteEvent::NoteOff { channel, note, .. }
+                    | NoteEvent::Choke { channel, note, .. }
+                    | NoteEvent::VoiceTerminated { channel, note, .. } => {
+                        let mpe = self.params.modulation.mpe.value();
+                        self.handle_note_off(channel, note, voices_allowed, mpe);
+                    }
+                    NoteEvent::MidiPitchBend { channel, value, .. } => {
+                        let mpe = self.params.modulation.mpe.value();
+                        self.handle_pitch_bend(channel, value, mpe);
                     }
-                    NoteEvent::NoteOff { note, .. }
-                    | NoteEvent::Choke { note, .. }
-                    | NoteEvent::VoiceTerminated { note, .. } => {
-                        self.handle_note_off(note, voices_allowed);
+                    NoteEvent::MidiChannelPressure {
+                        channel, pressure, ..
+                    } => {
+                        let mpe = self.params.modulation.mpe.value();
+                        self.handle_channel_pressure(channel, pressure, mpe);
                     }
-                    NoteEvent::MidiPitchBend { value, .. } => {
-                        self.pitch_bend = (value - 0.5) * 2.0 * PITCH_BEND_RANGE;
+                    NoteEvent::PolyTuning { note, tuning, .. } => {
+                        self.handle_note_expression(note, NoteController::Pitch, tuning);
+                    }
+                    NoteEvent::PolyBrightness {
+                        note, brightness, ..
+                    } => {
+                        self.handle_note_expression(note, NoteController::Timbre, brightness);
                     }
                     _ => {}
                 }
@@ -824,7 +1089,7 @@ impl Plugin for WestCoastWhineSynth {
         let oscilloscope = self.oscilloscope.clone();
         create_egui_editor(
             self.params.editor_state.clone(),
-            GuiState { oscilloscope },
+            GuiState::new(oscilloscope),
             |_ctx, _state| {},
             move |egui_ctx, setter, state| {
                 ResizableWindow::new("westcoast-whine")
@@ -866,6 +1131,18 @@ impl Plugin for WestCoastWhineSynth {
                                             setter,
                                         ))
                                         .on_hover_text("Glide time for legato portamento");
+                                        ui.add(widgets::ParamSlider::for_param(
+                                            &params.oscillators.unison,
+                                            setter,
+                                        ))
+                                        .on_hover_text("Detuned oscillators stacked per voice");
+                                        ui.add(widgets::ParamSlider::for_param(
+                                            &params.oscillators.unison_spread,
+                                            setter,
+                                        ))
+                                        .on_hover_text(
+                                            "Detune and stereo width of the unison stack",
+                                        );
                                         ui.add(widgets::ParamSlider::for_param(
                                             &params.voices,
                                             setter,
@@ -891,6 +1168,10 @@ impl Plugin for WestCoastWhineSynth {
                                             &params.filter.env_depth,
                                             setter,
                                         ));
+                                        ui.add(widgets::ParamSlider::for_param(
+                                            &params.filter.key_track,
+                                            setter,
+                                        ));
                                         let mut vel = params.filter.velocity_to_cutoff.value();
                                         if ui
                                             .checkbox(&mut vel, "Velocity -> Cutoff")
@@ -998,6 +1279,22 @@ impl Plugin for WestCoastWhineSynth {
                                             &params.modulation.amp_amount,
                                             setter,
                                         ));
+                                        let mut mpe = params.modulation.mpe.value();
+                                        if ui
+                                            .checkbox(&mut mpe, "MPE")
+                                            .on_hover_text(
+                                                "Per-note bend and pressure on channels 2-16",
+                                            )
+                                            .changed()
+                                        {
+                                            setter.begin_set_parameter(&params.modulation.mpe);
+                                            setter.set_parameter(&params.modulation.mpe, mpe);
+                                            setter.end_set_parameter(&params.modulation.mpe);
+                                        }
+                                        ui.add(widgets::ParamSlider::for_param(
+                                            &params.modulation.pressure_target,
+                                            setter,
+                                        ));
                                     });
                                 });
                             });
@@ -1029,9 +1326,29 @@ impl Plugin for WestCoastWhineSynth {
                                         &params.master.output_gain,
                                         setter,
                                     ));
-                                    let samples = state.oscilloscope.snapshot();
+                                    ui.horizontal(|ui| {
+                                        ui.checkbox(&mut state.frozen, "Freeze");
+                                        ui.checkbox(&mut state.triggered, "Trigger");
+                                    });
+                                    ui.add(
+                                        egui::Slider::new(&mut state.trigger_level, -1.0..=1.0)
+                                            .text("Trigger Level"),
+                                    );
+                                    ui.add(
+                                        egui::Slider::new(&mut state.holdoff_ms, 0.0..=100.0)
+                                            .text("Hold-off")
+                                            .suffix(" ms"),
+                                    );
+                                    state.oscilloscope.set_frozen(state.frozen);
+                                    state.oscilloscope.set_trigger(
+                                        state.triggered,
+                                        state.trigger_level,
+                                        state.holdoff_ms,
+                                    );
+                                    state.oscilloscope.snapshot(&mut state.window);
                                     let points = PlotPoints::from_iter(
-                                        samples
+                                        state
+                                            .window
                                             .iter()
                                             .enumerate()
                                             .map(|(i, sample)| [i as f64, *sample as f64]),
@@ -1054,6 +1371,25 @@ impl Plugin for WestCoastWhineSynth {
 
 struct GuiState {
     oscilloscope: Arc<OscilloscopeState>,
+    /// Reused between frames so drawing the scope does not allocate.
+    window: Vec<f32>,
+    frozen: bool,
+    triggered: bool,
+    trigger_level: f32,
+    holdoff_ms: f32,
+}
+
+impl GuiState {
+    fn new(oscilloscope: Arc<OscilloscopeState>) -> Self {
+        Self {
+            oscilloscope,
+            window: vec![0.0; OSCILLOSCOPE_SAMPLES],
+            frozen: false,
+            triggered: true,
+            trigger_level: 0.0,
+            holdoff_ms: 10.0,
+        }
+    }
 }
 
 impl ClapPlugin for WestCoastWhineSynth {
@@ -1078,3 +1414,298 @@ impl Vst3Plugin for WestCoastWhineSynth {
 
 nih_export_clap!(WestCoastWhineSynth);
 nih_export_vst3!(WestCoastWhineSynth);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_voice(note: u8) -> Voice {
+        let envelope = EnvelopeSettings {
+            attack: 0.01,
+            decay: 0.1,
+            sustain: 1.0,
+            release: 0.1,
+        };
+        let mut voice = Voice::new(48_000.0);
+        voice.note_on(
+            note,
+            1.0,
+            WestCoastWhineSynth::note_to_hz(note),
+            1_000.0,
+            0.2,
+            0.0,
+            envelope,
+            envelope,
+        );
+        voice
+    }
+
+    fn test_voice_params() -> VoiceParams {
+        VoiceParams {
+            blend: 0.5,
+            sub_level: 0.0,
+            detune_cents: 0.0,
+            filter_cutoff: 1_000.0,
+            filter_resonance: 0.2,
+            filter_env_amount: 0.0,
+            velocity_to_cutoff: false,
+            filter_env_depth: 0.0,
+            filter_key_track: 0.0,
+            lfo_value: 0.0,
+            lfo_pitch_amount: 0.0,
+            lfo_cutoff_amount: 0.0,
+            lfo_amp_amount: 0.0,
+            pitch_bend_semitones: 0.0,
+            velocity_amp_scale: 1.0,
+            unison: 1,
+            unison_spread: 0.0,
+        }
+    }
+
+    fn key_tracked_cutoff(note: u8, key_track: f32) -> f32 {
+        let mut voice = test_voice(note);
+        voice.render(&VoiceParams {
+            filter_key_track: key_track,
+            ..test_voice_params()
+        });
+        voice.cutoff()
+    }
+
+    fn render_unison(unison: usize, spread: f32) -> Vec<(f32, f32)> {
+        let mut voice = test_voice(57);
+        let params = VoiceParams {
+            unison,
+            unison_spread: spread,
+            ..test_voice_params()
+        };
+        (0..2_048).map(|_| voice.render(&params)).collect()
+    }
+
+    #[test]
+    fn single_unison_is_mono_and_ignores_spread() {
+        let mono = render_unison(1, 0.0);
+        assert!(mono.iter().all(|(left, right)| left == right));
+        assert!(mono.iter().any(|(left, _)| left.abs() > 1e-3));
+        assert_eq!(mono, render_unison(1, 1.0));
+    }
+
+    #[test]
+    fn unison_stack_spreads_across_stereo_field() {
+        let stack = render_unison(4, 1.0);
+        assert!(stack
+            .iter()
+            .any(|(left, right)| (left - right).abs() > 1e-3));
+        assert!(stack
+            .iter()
+            .all(|(left, right)| left.is_finite() && right.is_finite()));
+        let peak = stack.iter().fold(0.0f32, |peak, (left, right)| {
+            peak.max(left.abs()).max(right.abs())
+        });
+        assert!(peak < 4.0, "{peak}");
+
+        let narrow = render_unison(4, 0.0);
+        assert!(narrow
+            .iter()
+            .all(|(left, right)| (left - right).abs() < 1e-5));
+    }
+
+    #[test]
+    fn key_track_follows_note_two_octaves_apart() {
+        let low = key_tracked_cutoff(48, 1.0);
+        let high = key_tracked_cutoff(72, 1.0);
+        assert!((low - 500.0).abs() < 0.01, "{low}");
+        assert!((high - 2_000.0).abs() < 0.01, "{high}");
+
+        let half_low = key_tracked_cutoff(48, 0.5);
+        let half_high = key_tracked_cutoff(72, 0.5);
+        assert!((half_high / half_low - 2.0).abs() < 1e-4);
+
+        assert_eq!(key_tracked_cutoff(48, 0.0), key_tracked_cutoff(72, 0.0));
+    }
+
+    #[test]
+    fn stealing_a_voice_does_not_click() {
+        let mut synth = WestCoastWhineSynth::default();
+        let params = VoiceParams {
+            blend: 0.0,
+            sub_level: 0.0,
+            filter_cutoff: 8_000.0,
+            ..test_voice_params()
+        };
+        let render = |synth: &mut WestCoastWhineSynth| -> (f32, f32) {
+            let mut oldest = 0.0;
+            let mut sum = 0.0;
+            for (index, voice) in synth.voices.iter_mut().enumerate() {
+                let (left, _) = voice.render(&params);
+                if index == 0 {
+                    oldest = left;
+                }
+                sum += left;
+            }
+            (sum, oldest)
+        };
+
+        for note in 0..MAX_VOICES as u8 {
+            synth.handle_note_on(0, 36 + note, 1.0, MAX_VOICES);
+        }
+        let mut output = Vec::new();
+        // Steal the oldest voice while it is near the top of its cycle.
+        loop {
+            let (sum, oldest) = render(&mut synth);
+            output.push(sum);
+            if output.len() > 4_800 && oldest.abs() > 0.35 {
+                break;
+            }
+        }
+        synth.handle_note_on(0, 60, 1.0, MAX_VOICES);
+        assert!(synth.voices[0].is_stealing());
+        for _ in 0..4_800 {
+            output.push(render(&mut synth).0);
+        }
+        assert!(!synth.voices[0].is_stealing());
+        assert_eq!(synth.voices[0].note(), 60);
+
+        let largest_step = output
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0f32, f32::max);
+        assert!(largest_step < 0.15, "{largest_step}");
+    }
+
+    fn push_sine(scope: &OscilloscopeState, start: usize, count: usize, period: f32) {
+        for n in start..start + count {
+            scope.push((std::f32::consts::TAU * (n as f32 + 0.3) / period).sin());
+        }
+    }
+
+    #[test]
+    fn scope_trigger_aligns_window_to_rising_crossing() {
+        let scope = OscilloscopeState::new(OSCILLOSCOPE_HISTORY);
+        scope.set_trigger(true, 0.0, 0.0);
+        push_sine(&scope, 0, 1_000, 100.0);
+
+        let mut window = vec![0.0; OSCILLOSCOPE_SAMPLES];
+        let start = scope.snapshot(&mut window).expect("trigger");
+        assert_eq!(start, 700);
+        assert!(window[0] >= 0.0 && window[0] < 0.05);
+        assert!(window[1] > window[0]);
+
+        // Freezing stops updates, so the window stays put.
+        scope.set_frozen(true);
+        push_sine(&scope, 1_000, 500, 37.0);
+        assert_eq!(scope.snapshot(&mut window), Some(700));
+
+        scope.set_trigger(false, 0.0, 0.0);
+        assert_eq!(scope.snapshot(&mut window), None);
+        assert_eq!(window[0], scope.sample_at(1_000 - OSCILLOSCOPE_SAMPLES));
+    }
+
+    #[test]
+    fn scope_holdoff_skips_close_retriggers() {
+        let scope = OscilloscopeState::new(OSCILLOSCOPE_HISTORY);
+        scope.set_sample_rate(48_000.0);
+        // 5 ms hold-off is 240 samples, longer than the 100 sample period.
+        scope.set_trigger(true, 0.0, 5.0);
+        push_sine(&scope, 0, 1_000, 100.0);
+        let mut window = vec![0.0; OSCILLOSCOPE_SAMPLES];
+        assert_eq!(scope.snapshot(&mut window), Some(700));
+
+        push_sine(&scope, 1_000, 100, 100.0);
+        assert_eq!(scope.snapshot(&mut window), Some(700));
+        push_sine(&scope, 1_100, 200, 100.0);
+        assert_eq!(scope.snapshot(&mut window), Some(1_000));
+    }
+
+    #[test]
+    fn mpe_routes_bend_and_pressure_per_channel() {
+        let mut synth = WestCoastWhineSynth::default();
+        synth.handle_note_on(1, 60, 0.8, MAX_VOICES);
+        synth.handle_note_on(2, 64, 0.8, MAX_VOICES);
+        let first = synth
+            .voice_notes
+            .iter()
+            .position(|note| *note == Some(60))
+            .unwrap();
+        let second = synth
+            .voice_notes
+            .iter()
+            .position(|note| *note == Some(64))
+            .unwrap();
+
+        synth.handle_pitch_bend(1, 0.75, true);
+        synth.handle_channel_pressure(2, 1.0, true);
+        synth.handle_pitch_bend(0, 1.0, true);
+        assert_eq!(synth.pitch_bend, PITCH_BEND_RANGE);
+
+        let shared = VoiceParams {
+            pitch_bend_semitones: synth.pitch_bend,
+            ..test_voice_params()
+        };
+        let bent = synth.mpe_voice_params(first, &shared, PressureTarget::Amp);
+        assert_eq!(bent.pitch_bend_semitones, PITCH_BEND_RANGE + 24.0);
+        assert_eq!(bent.velocity_amp_scale, 1.0);
+
+        let pressed = synth.mpe_voice_params(second, &shared, PressureTarget::Amp);
+        assert_eq!(pressed.pitch_bend_semitones, PITCH_BEND_RANGE);
+        assert_eq!(pressed.velocity_amp_scale, 2.0);
+        let opened = synth.mpe_voice_params(second, &shared, PressureTarget::Cutoff);
+        assert_eq!(opened.filter_cutoff, shared.filter_cutoff * 4.0);
+
+        // Without MPE every channel drives the global bend and pressure is ignored.
+        synth.handle_pitch_bend(3, 0.5, false);
+        synth.handle_channel_pressure(3, 1.0, false);
+        assert_eq!(synth.pitch_bend, 0.0);
+        assert_eq!(synth.channel_bend[3], 0.0);
+        assert_eq!(synth.channel_pressure[3], 0.0);
+    }
+
+    #[test]
+    fn mpe_note_off_releases_only_its_channel() {
+        let mut synth = WestCoastWhineSynth::default();
+        synth.handle_note_on(1, 60, 0.8, MAX_VOICES);
+        synth.handle_note_on(2, 60, 0.8, MAX_VOICES);
+        let held = |synth: &WestCoastWhineSynth| {
+            let mut channels: Vec<u8> = synth
+                .voice_notes
+                .iter()
+                .zip(synth.voice_channels.iter())
+                .filter(|(note, _)| **note == Some(60))
+                .map(|(_, channel)| *channel)
+                .collect();
+            channels.sort_unstable();
+            channels
+        };
+        assert_eq!(held(&synth), vec![1, 2]);
+
+        synth.handle_note_off(1, 60, MAX_VOICES, true);
+        assert_eq!(held(&synth), vec![2]);
+
+        // Without MPE the channel is ignored.
+        synth.handle_note_off(5, 60, MAX_VOICES, false);
+        assert!(held(&synth).is_empty());
+    }
+
+    #[test]
+    fn timbre_expression_targets_only_matching_voice() {
+        let mut synth = WestCoastWhineSynth::default();
+        synth.handle_note_on(0, 60, 0.8, MAX_VOICES);
+        synth.handle_note_on(0, 67, 0.8, MAX_VOICES);
+        let target = synth
+            .voice_notes
+            .iter()
+            .position(|note| *note == Some(67))
+            .unwrap();
+
+        synth.handle_note_expression(67, NoteController::Timbre, 0.75);
+        synth.handle_note_expression(67, NoteController::Pitch, 0.5);
+
+        for (index, voice) in synth.voices.iter().enumerate() {
+            if index == target {
+                assert_eq!(voice.note(), 67);
+                assert_eq!(voice.timbre(), 0.75);
+            } else {
+                assert_eq!(voice.timbre(), 0.0);
+            }
+        }
+    }
+}
@@ -1,34 +1,98 @@
-use clap_plugin_authoring::{clap_export, AudioProcessor, Plugin, PluginDescriptor, PluginFactory};
+use clap_plugin_authoring::{
+    clap_export, AudioProcessor, ParamDescriptor, ParamValueEvents, Params, Plugin,
+    PluginDescriptor, PluginFactory, State,
+};
 use clap_sys::{clap_host, clap_process, clap_process_status, CLAP_PROCESS_CONTINUE};
 
+/// CLAP id of the gain parameter, spelling "GAIN".
+pub const GAIN_PARAM_ID: u32 = 0x4741_494E;
+
+const DEFAULT_GAIN: f32 = 0.5;
+
+static PARAMS: [ParamDescriptor; 1] = [ParamDescriptor {
+    id: GAIN_PARAM_ID,
+    name: "Gain",
+    min: 0.0,
+    max: 2.0,
+    default: DEFAULT_GAIN as f64,
+}];
+
 struct TestGain {
     gain: f32,
 }
 
+impl TestGain {
+    /// Scales frames `start..end` of every output by the current gain.
+    unsafe fn apply(&self, process: &mut clap_process, start: u32, end: u32) {
+        let outputs = std::slice::from_raw_parts_mut(
+            process.audio_outputs,
+            process.audio_outputs_count as usize,
+        );
+        for buffer in outputs {
+            if buffer.data32.is_null() {
+                continue;
+            }
+            for channel in 0..buffer.channel_count as isize {
+                let channel_ptr = *buffer.data32.offset(channel);
+                for frame in start as isize..end as isize {
+                    let sample = *channel_ptr.offset(frame) * self.gain;
+                    *channel_ptr.offset(frame) = sample;
+                }
+            }
+        }
+    }
+}
+
 impl AudioProcessor for TestGain {
     fn process(&mut self, process: &mut clap_process) -> clap_process_status {
         unsafe {
-            let outputs = std::slice::from_raw_parts_mut(
-                process.audio_outputs,
-                process.audio_outputs_count as usize,
-            );
-            for buffer in outputs {
-                if buffer.data32.is_null() {
-                    continue;
-                }
-                for channel in 0..buffer.channel_count as isize {
-                    let channel_ptr = *buffer.data32.offset(channel);
-                    for frame in 0..process.frames_count as isize {
-                        let sample = *channel_ptr.offset(frame) * self.gain;
-                        *channel_ptr.offset(frame) = sample;
-                    }
+            let frames = process.frames_count;
+            let mut position = 0;
+            for event in ParamValueEvents::new(process.in_events) {
+                let time = event.time.min(frames);
+                if time > position {
+                    self.apply(process, position, time);
+                    position = time;
                 }
+                self.set_param_value(event.id, event.value);
             }
+            self.apply(process, position, frames);
         }
         CLAP_PROCESS_CONTINUE.0 as clap_process_status
     }
 }
 
+impl Params for TestGain {
+    fn param_descriptors(&self) -> &'static [ParamDescriptor] {
+        &PARAMS
+    }
+
+    fn param_value(&self, id: u32) -> Option<f64> {
+        (id == GAIN_PARAM_ID).then_some(self.gain as f64)
+    }
+
+    fn set_param_value(&mut self, id: u32, value: f64) {
+        if id == GAIN_PARAM_ID {
+            self.gain = value.clamp(PARAMS[0].min, PARAMS[0].max) as f32;
+        }
+    }
+}
+
+/// The state is the gain as a little-endian `f32`.
+impl State for TestGain {
+    fn save(&mut self, stream: &mut dyn std::io::Write) -> anyhow::Result<()> {
+        stream.write_all(&self.gain.to_le_bytes())?;
+        Ok(())
+    }
+
+    fn load(&mut self, stream: &mut dyn std::io::Read) -> anyhow::Result<()> {
+        let mut bytes = [0; 4];
+        stream.read_exact(&mut bytes)?;
+        self.set_param_value(GAIN_PARAM_ID, f32::from_le_bytes(bytes) as f64);
+        Ok(())
+    }
+}
+
 impl Plugin for TestGain {
     fn descriptor(&self) -> &'static PluginDescriptor {
         static DESCRIPTOR: PluginDescriptor = PluginDescriptor {
@@ -42,6 +106,14 @@ impl Plugin for TestGain {
         };
         &DESCRIPTOR
     }
+
+    fn params(&mut self) -> Option<&mut dyn Params> {
+        Some(self)
+    }
+
+    fn state(&mut self) -> Option<&mut dyn State> {
+        Some(self)
+    }
 }
 
 struct TestGainFactory;
@@ -63,7 +135,7 @@ impl PluginFactory for TestGainFactory {
     }
 
     fn new_plugin(_descriptor_id: &str, _host: *const clap_host) -> anyhow::Result<Self::Plugin> {
-        Ok(TestGain { gain: 0.5 })
+        Ok(TestGain { gain: DEFAULT_GAIN })
     }
 }
 